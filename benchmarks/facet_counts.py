@@ -0,0 +1,90 @@
+"""Compare faceted and unfaceted searches over a multi-segment index.
+
+Usage:
+
+    python benchmarks/facet_counts.py [--docs 1000000] [--segments 6]
+
+The faceted searches count the facets of the segments in parallel, they
+should take about as long as the unfaceted ones on a machine with as many
+cores as segments. The index writer merges the segments once there are 8
+of them, keep `--segments` below that.
+"""
+
+import argparse
+import random
+import statistics
+import time
+
+from tantivy import Document, Facet, Index, MultiCollector, Query, SchemaBuilder
+
+CATEGORIES = [
+    f"/{department}/{aisle}/{shelf}"
+    for department in ["food", "home", "garden", "toys", "books"]
+    for aisle in range(20)
+    for shelf in range(10)
+]
+
+
+def build_index(num_docs, num_segments):
+    schema = (
+        SchemaBuilder()
+        .add_text_field("title")
+        .add_facet_field("category")
+        .build()
+    )
+    index = Index(schema)
+    writer = index.writer(500_000_000, 1)
+    rng = random.Random(42)
+    per_segment = num_docs // num_segments
+    for _ in range(num_segments):
+        for _ in range(per_segment):
+            doc = Document(title=rng.choice(["red", "green", "blue"]))
+            doc.add_facet("category", Facet.from_string(rng.choice(CATEGORIES)))
+            writer.add_document(doc)
+        writer.commit()
+    writer.wait_merging_threads()
+    index.reload()
+    return index
+
+
+def timed(run, repeat):
+    timings = []
+    for _ in range(repeat):
+        start = time.perf_counter()
+        run()
+        timings.append(time.perf_counter() - start)
+    return statistics.median(timings) * 1000
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__)
+    parser.add_argument("--docs", type=int, default=1_000_000)
+    parser.add_argument("--segments", type=int, default=6)
+    parser.add_argument("--repeat", type=int, default=10)
+    args = parser.parse_args()
+
+    index = build_index(args.docs, args.segments)
+    searcher = index.searcher()
+    queries = {
+        "all": Query.all_query(),
+        "term": index.parse_query("red", ["title"]),
+    }
+    print(f"{searcher.num_docs} docs in {searcher.num_segments} segments")
+    collector = MultiCollector().add_facets("category", "category")
+    for name, query in queries.items():
+        plain = timed(lambda: searcher.search(query, 10), args.repeat)
+        faceted = timed(
+            lambda: searcher.search(query, 10, collector=collector),
+            args.repeat,
+        )
+        counts = timed(
+            lambda: searcher.facet_counts(query, "category"), args.repeat
+        )
+        print(
+            f"{name:>5}: search {plain:8.1f} ms, with a facets collector "
+            f"{faceted:8.1f} ms, facet_counts {counts:8.1f} ms"
+        )
+
+
+if __name__ == "__main__":
+    main()
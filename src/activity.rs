@@ -0,0 +1,113 @@
+//! The commits and merges of the writers of an index, for the report of
+//! `Index.stats`.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use tantivy::{
+    indexer::{MergeCandidate, MergePolicy},
+    SegmentMeta,
+};
+
+/// What the writers of an `Index` object did since it was opened.
+pub(crate) struct Activity {
+    opened_at: Instant,
+    commits: AtomicU64,
+    committed_docs: AtomicU64,
+    merges: AtomicU64,
+    /// The times of the last commit and of the start of the last merge, in
+    /// seconds since the epoch.
+    last_commit: Mutex<Option<f64>>,
+    last_merge: Mutex<Option<f64>>,
+}
+
+impl Default for Activity {
+    fn default() -> Self {
+        Activity {
+            opened_at: Instant::now(),
+            commits: AtomicU64::new(0),
+            committed_docs: AtomicU64::new(0),
+            merges: AtomicU64::new(0),
+            last_commit: Mutex::new(None),
+            last_merge: Mutex::new(None),
+        }
+    }
+}
+
+impl std::fmt::Debug for Activity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Activity")
+            .field("commits", &self.commits())
+            .field("merges", &self.merges())
+            .finish()
+    }
+}
+
+impl Activity {
+    pub(crate) fn record_commit(&self, num_docs: u64) {
+        self.commits.fetch_add(1, Ordering::SeqCst);
+        self.committed_docs.fetch_add(num_docs, Ordering::SeqCst);
+        *self.last_commit.lock().unwrap() = Some(now());
+    }
+
+    pub(crate) fn record_merge(&self) {
+        self.merges.fetch_add(1, Ordering::SeqCst);
+        *self.last_merge.lock().unwrap() = Some(now());
+    }
+
+    pub(crate) fn commits(&self) -> u64 {
+        self.commits.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn committed_docs(&self) -> u64 {
+        self.committed_docs.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn merges(&self) -> u64 {
+        self.merges.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn last_commit(&self) -> Option<f64> {
+        *self.last_commit.lock().unwrap()
+    }
+
+    pub(crate) fn last_merge(&self) -> Option<f64> {
+        *self.last_merge.lock().unwrap()
+    }
+
+    /// The seconds elapsed since the index was opened.
+    pub(crate) fn uptime_secs(&self) -> f64 {
+        self.opened_at.elapsed().as_secs_f64()
+    }
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64())
+}
+
+/// A merge policy recording the merges started by the policy of a writer.
+#[derive(Debug)]
+pub(crate) struct RecordingMergePolicy {
+    pub(crate) inner: Arc<dyn MergePolicy>,
+    pub(crate) activity: Arc<Activity>,
+}
+
+impl MergePolicy for RecordingMergePolicy {
+    fn compute_merge_candidates(
+        &self,
+        segments: &[SegmentMeta],
+    ) -> Vec<MergeCandidate> {
+        let candidates = self.inner.compute_merge_candidates(segments);
+        for _ in &candidates {
+            self.activity.record_merge();
+        }
+        candidates
+    }
+}
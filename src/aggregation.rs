@@ -0,0 +1,402 @@
+use std::collections::HashMap;
+
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{
+        PyBool, PyDate, PyDateTime, PyDict, PyFloat, PyInt, PyList, PyString,
+        PyTuple,
+    },
+    IntoPyObjectExt,
+};
+use tantivy::{
+    self as tv,
+    aggregation::{
+        agg_req::{AggregationVariants, Aggregations},
+        agg_result::{
+            AggregationResult, AggregationResults, BucketEntries, BucketResult,
+            MetricResult,
+        },
+        metric::Stats,
+        AggregationCollector, Key,
+    },
+    collector::Count,
+};
+
+use crate::{
+    pythonize,
+    query::{filtered, Query},
+    to_pyerr,
+};
+
+/// Name of the single aggregation run by the convenience methods.
+const NAME: &str = "agg";
+
+/// Parse aggregation requests by name, in the Elasticsearch syntax, from
+/// the Python objects directly.
+///
+/// The requests may hold `datetime` and `date` values, read as UTC when
+/// naive: the bounds of `range` aggregations become nanoseconds since the
+/// epoch, the unit of the values of date fields, and the other values
+/// milliseconds since the epoch, the unit of the bounds of date histograms.
+/// `Decimal` values become integers when integral and floats otherwise.
+pub(crate) fn parse_requests(
+    requests: &Bound<PyAny>,
+) -> PyResult<Aggregations> {
+    let requests =
+        native_values(requests, false)?.unwrap_or_else(|| requests.clone());
+    pythonize::depythonize(&requests).map_err(to_pyerr)
+}
+
+/// The value the documents without a value of a field are aggregated
+/// with, see the `missing` argument of `Searcher.aggregate`.
+#[derive(FromPyObject)]
+pub(crate) enum MissingValue {
+    Number(f64),
+    Text(String),
+}
+
+/// Give the missing values by field name to the aggregations that accept
+/// one, the metrics and `terms` aggregations, unless their request sets
+/// it.
+///
+/// Raises a ValueError if the missing value of a field aggregated by a
+/// metric isn't a number.
+pub(crate) fn set_missing(
+    aggs: &mut Aggregations,
+    missing: &HashMap<String, MissingValue>,
+) -> PyResult<()> {
+    let number = |field: &str| match missing.get(field) {
+        Some(MissingValue::Number(value)) => Ok(Some(*value)),
+        Some(MissingValue::Text(_)) => Err(PyValueError::new_err(format!(
+            "The missing value of `{field}` must be a number, it is \
+             aggregated by a metric."
+        ))),
+        None => Ok(None),
+    };
+    for agg in aggs.values_mut() {
+        match &mut agg.agg {
+            AggregationVariants::Terms(terms) if terms.missing.is_none() => {
+                terms.missing =
+                    missing.get(&terms.field).map(|value| match value {
+                        MissingValue::Number(value) => Key::F64(*value),
+                        MissingValue::Text(value) => Key::Str(value.clone()),
+                    });
+            }
+            AggregationVariants::Average(metric)
+                if metric.missing.is_none() =>
+            {
+                metric.missing = number(&metric.field)?;
+            }
+            AggregationVariants::Count(metric) if metric.missing.is_none() => {
+                metric.missing = number(&metric.field)?;
+            }
+            AggregationVariants::Max(metric) if metric.missing.is_none() => {
+                metric.missing = number(&metric.field)?;
+            }
+            AggregationVariants::Min(metric) if metric.missing.is_none() => {
+                metric.missing = number(&metric.field)?;
+            }
+            AggregationVariants::Stats(metric) if metric.missing.is_none() => {
+                metric.missing = number(&metric.field)?;
+            }
+            AggregationVariants::Sum(metric) if metric.missing.is_none() => {
+                metric.missing = number(&metric.field)?;
+            }
+            AggregationVariants::Percentiles(metric)
+                if metric.missing.is_none() =>
+            {
+                metric.missing = number(&metric.field)?;
+            }
+            _ => {}
+        }
+        set_missing(&mut agg.sub_aggregation, missing)?;
+    }
+    Ok(())
+}
+
+/// A datetime or date as a UTC datetime, naive ones being UTC.
+pub(crate) fn utc_datetime(
+    value: &Bound<PyAny>,
+) -> PyResult<chrono::DateTime<chrono::Utc>> {
+    if let Ok(datetime) =
+        value.extract::<chrono::DateTime<chrono::FixedOffset>>()
+    {
+        return Ok(datetime.to_utc());
+    }
+    if value.is_instance_of::<PyDateTime>() {
+        let datetime: chrono::NaiveDateTime = value.extract()?;
+        return Ok(datetime.and_utc());
+    }
+    let date: chrono::NaiveDate = value.extract()?;
+    Ok(date.and_time(chrono::NaiveTime::MIN).and_utc())
+}
+
+/// A copy of an aggregation request with its datetime, date and `Decimal`
+/// values converted, see `parse_requests`. None if it holds none of them.
+///
+/// `in_range` is set below the `range` member of a request.
+fn native_values<'py>(
+    value: &Bound<'py, PyAny>,
+    in_range: bool,
+) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let py = value.py();
+    if let Ok(dict) = value.downcast::<PyDict>() {
+        let mut converted = None;
+        for (key, item) in dict.iter() {
+            let in_range = in_range
+                || key
+                    .downcast::<PyString>()
+                    .is_ok_and(|key| key.to_str().is_ok_and(|k| k == "range"));
+            if let Some(item) = native_values(&item, in_range)? {
+                converted.get_or_insert(dict.copy()?).set_item(key, item)?;
+            }
+        }
+        return Ok(converted.map(Bound::into_any));
+    }
+    if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+        let items: Vec<Bound<PyAny>> = value.extract()?;
+        let mut changed = false;
+        let items = items
+            .into_iter()
+            .map(|item| {
+                let converted = native_values(&item, in_range)?;
+                changed |= converted.is_some();
+                Ok(converted.unwrap_or(item))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        if !changed {
+            return Ok(None);
+        }
+        return Ok(Some(PyList::new(py, items)?.into_any()));
+    }
+    if value.is_instance_of::<PyDate>() {
+        let datetime = utc_datetime(value)?;
+        let timestamp = match in_range {
+            true => datetime.timestamp_nanos_opt().ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "The datetime {datetime} is out of the range of date \
+                     fields."
+                ))
+            })?,
+            false => datetime.timestamp_millis(),
+        };
+        return Ok(Some(timestamp.into_bound_py_any(py)?));
+    }
+    if value.is_none()
+        || value.is_instance_of::<PyString>()
+        || value.is_instance_of::<PyBool>()
+        || value.is_instance_of::<PyInt>()
+        || value.is_instance_of::<PyFloat>()
+    {
+        return Ok(None);
+    }
+    let decimal = py.import("decimal")?.getattr("Decimal")?;
+    if !value.is_instance(&decimal)? {
+        return Ok(None);
+    }
+    let integral = value.call_method0("to_integral_value")?;
+    if value.eq(&integral)? {
+        return Ok(Some(integral.call_method0("__int__")?));
+    }
+    Ok(Some(value.call_method0("__float__")?))
+}
+
+/// Parse the request of a single aggregation, in the Elasticsearch syntax.
+pub(crate) fn single(request: serde_json::Value) -> PyResult<Aggregations> {
+    serde_json::from_value(serde_json::json!({ NAME: request }))
+        .map_err(to_pyerr)
+}
+
+/// Run an aggregation parsed with `single`.
+pub(crate) fn run_single(
+    searcher: &tv::Searcher,
+    query: &dyn tv::query::Query,
+    aggs: Aggregations,
+) -> tv::Result<Option<AggregationResult>> {
+    let collector = AggregationCollector::from_aggs(aggs, Default::default());
+    let mut results = searcher.search(query, &collector)?;
+    Ok(results.0.remove(NAME))
+}
+
+/// The `(key, doc_count)` pairs of a histogram result, in key order.
+pub(crate) fn histogram_buckets(
+    result: Option<AggregationResult>,
+) -> Vec<(f64, u64)> {
+    let Some(AggregationResult::BucketResult(BucketResult::Histogram {
+        buckets,
+    })) = result
+    else {
+        return Vec::new();
+    };
+    let buckets = match buckets {
+        BucketEntries::Vec(buckets) => buckets,
+        BucketEntries::HashMap(buckets) => buckets.into_values().collect(),
+    };
+    let mut buckets: Vec<(f64, u64)> = buckets
+        .into_iter()
+        .filter_map(|bucket| match bucket.key {
+            Key::F64(key) => Some((key, bucket.doc_count)),
+            Key::Str(_) => None,
+        })
+        .collect();
+    buckets.sort_by(|a, b| a.0.total_cmp(&b.0));
+    buckets
+}
+
+/// The value of a single value metric result, None if no document had a
+/// value.
+pub(crate) fn metric_value(result: Option<AggregationResult>) -> Option<f64> {
+    match result? {
+        AggregationResult::MetricResult(
+            MetricResult::Average(metric)
+            | MetricResult::Count(metric)
+            | MetricResult::Max(metric)
+            | MetricResult::Min(metric)
+            | MetricResult::Sum(metric),
+        ) => metric.value,
+        _ => None,
+    }
+}
+
+/// The request of a single value metric, e.g. `("avg", "price")`.
+pub(crate) fn metric(
+    op: &str,
+    field_name: &str,
+) -> PyResult<serde_json::Value> {
+    let op = match op {
+        "avg" | "min" | "max" | "sum" => op,
+        "count" => "value_count",
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "Invalid metric `{op}`, valid choices are: 'avg', 'min', \
+                 'max', 'sum' and 'count'"
+            )))
+        }
+    };
+    Ok(serde_json::json!({ op: { "field": field_name } }))
+}
+
+/// The results of stats aggregations, by aggregation name.
+pub(crate) fn stats_results(
+    results: AggregationResults,
+) -> HashMap<String, Stats> {
+    results
+        .0
+        .into_iter()
+        .filter_map(|(name, result)| match result {
+            AggregationResult::MetricResult(MetricResult::Stats(stats)) => {
+                Some((name, stats))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The `(key, doc_count, sub_aggregations)` of the buckets of a terms
+/// result, the most frequent first.
+pub(crate) fn term_buckets(
+    result: Option<AggregationResult>,
+) -> Vec<(Key, u64, AggregationResults)> {
+    let Some(AggregationResult::BucketResult(BucketResult::Terms {
+        buckets,
+        ..
+    })) = result
+    else {
+        return Vec::new();
+    };
+    buckets
+        .into_iter()
+        .map(|bucket| (bucket.key, bucket.doc_count, bucket.sub_aggregation))
+        .collect()
+}
+
+/// A filter or filters aggregation, whose buckets are defined by queries.
+///
+/// Tantivy has no such aggregation, each bucket is collected by its own
+/// search.
+pub(crate) struct FilterAggregation {
+    pub(crate) name: String,
+    /// The query of each bucket, by name. A `filter` aggregation has a
+    /// single unnamed bucket.
+    pub(crate) buckets: Vec<(Option<String>, Query)>,
+    pub(crate) sub_aggregations: Aggregations,
+}
+
+impl FilterAggregation {
+    /// Parse a `{"filter": Query}` or `{"filters": {"filters": {name:
+    /// Query}}}` request, with optional sub-aggregations under "aggs".
+    /// Returns None for the other aggregations.
+    pub(crate) fn parse(
+        name: &str,
+        request: &Bound<PyAny>,
+    ) -> PyResult<Option<Self>> {
+        let Ok(request) = request.downcast::<PyDict>() else {
+            return Ok(None);
+        };
+        let buckets = if let Some(filter) = request.get_item("filter")? {
+            let Ok(query) = filter.extract::<Query>() else {
+                return Ok(None);
+            };
+            vec![(None, query)]
+        } else if let Some(filters) = request.get_item("filters")? {
+            let filters = filters
+                .downcast::<PyDict>()?
+                .get_item("filters")?
+                .ok_or_else(|| {
+                    PyValueError::new_err(format!(
+                        "The filters aggregation `{name}` has no `filters`."
+                    ))
+                })?;
+            filters
+                .downcast::<PyDict>()?
+                .iter()
+                .map(|(bucket, query)| {
+                    Ok((Some(bucket.extract()?), query.extract()?))
+                })
+                .collect::<PyResult<_>>()?
+        } else {
+            return Ok(None);
+        };
+
+        let sub_aggregations = match request.get_item("aggs")? {
+            Some(aggs) => parse_requests(&aggs)?,
+            None => Aggregations::default(),
+        };
+        Ok(Some(FilterAggregation {
+            name: name.to_string(),
+            buckets,
+            sub_aggregations,
+        }))
+    }
+
+    /// Collect the buckets of the documents matching `query`, in the
+    /// Elasticsearch result format.
+    pub(crate) fn run(
+        &self,
+        searcher: &tv::Searcher,
+        query: &dyn tv::query::Query,
+    ) -> PyResult<serde_json::Value> {
+        let mut buckets = serde_json::Map::new();
+        for (bucket, filter) in &self.buckets {
+            let bucket_query =
+                filtered(query.box_clone(), filter.inner.box_clone());
+            let collector = AggregationCollector::from_aggs(
+                self.sub_aggregations.clone(),
+                Default::default(),
+            );
+            let (count, results) = searcher
+                .search(bucket_query.as_ref(), &(Count, collector))
+                .map_err(crate::errors::tantivy_err)?;
+            let mut result = serde_json::to_value(results).map_err(to_pyerr)?;
+            result["doc_count"] = count.into();
+            match bucket {
+                Some(bucket) => {
+                    buckets.insert(bucket.clone(), result);
+                }
+                None => return Ok(result),
+            }
+        }
+        Ok(serde_json::json!({ "buckets": buckets }))
+    }
+}
@@ -0,0 +1,112 @@
+//! How the text of a query is analyzed for a field, compared to the same
+//! text in a document, see `Index.analyze_query_terms`.
+
+use pyo3::prelude::*;
+use serde::Serialize;
+use tantivy::{
+    self as tv,
+    query::QueryParser,
+    schema::{FieldType, Type},
+};
+
+use crate::{
+    errors::{schema_err, tantivy_err},
+    get_field,
+};
+
+/// A token of the text analyzed as the text of a document.
+#[derive(Serialize)]
+struct Token {
+    text: String,
+    position: usize,
+    offset_from: usize,
+    offset_to: usize,
+}
+
+/// A term searched by the query.
+#[derive(Serialize)]
+struct QueryTerm {
+    text: String,
+    /// The number of documents of the index holding the term.
+    doc_freq: u64,
+    /// Whether the text analyzed as a document has the term.
+    in_document: bool,
+}
+
+/// The analysis of a text as a query and as the text of a document.
+#[derive(Serialize)]
+pub(crate) struct TermAnalysis {
+    tokenizer: String,
+    document_tokens: Vec<Token>,
+    query_terms: Vec<QueryTerm>,
+    consistent: bool,
+}
+
+/// Analyze a text for a text field, as the query parser and as the indexing
+/// of a document do.
+///
+/// Raises a SchemaError if the field isn't an indexed text field.
+pub(crate) fn analyze(
+    searcher: &tv::Searcher,
+    field_name: &str,
+    text: &str,
+) -> PyResult<TermAnalysis> {
+    let index = searcher.index();
+    let schema = index.schema();
+    let field = get_field(&schema, field_name)?;
+    let tokenizer = match schema.get_field_entry(field).field_type() {
+        FieldType::Str(options) => options
+            .get_indexing_options()
+            .map(|indexing| indexing.tokenizer().to_string()),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        schema_err(
+            format!("Field `{field_name}` must be an indexed text field."),
+            field_name,
+        )
+    })?;
+
+    let mut analyzer = index.tokenizer_for_field(field).map_err(tantivy_err)?;
+    let mut document_tokens = Vec::new();
+    analyzer.token_stream(text).process(&mut |token| {
+        document_tokens.push(Token {
+            text: token.text.clone(),
+            position: token.position,
+            offset_from: token.offset_from,
+            offset_to: token.offset_to,
+        })
+    });
+
+    let parser = QueryParser::for_index(index, vec![field]);
+    let (query, _) = parser.parse_query_lenient(text);
+    let mut terms = Vec::new();
+    query.query_terms(&mut |term, _| {
+        if term.field() == field && term.typ() == Type::Str {
+            terms.push(term.clone());
+        }
+    });
+    let mut query_terms: Vec<QueryTerm> = Vec::new();
+    for term in terms {
+        let value = term.value();
+        let Some(text) = value.as_str() else {
+            continue;
+        };
+        if query_terms.iter().any(|query_term| query_term.text == text) {
+            continue;
+        }
+        query_terms.push(QueryTerm {
+            text: text.to_string(),
+            doc_freq: searcher.doc_freq(&term).map_err(tantivy_err)?,
+            in_document: document_tokens.iter().any(|token| token.text == text),
+        });
+    }
+    let consistent = query_terms.iter().all(|term| term.in_document);
+
+    Ok(TermAnalysis {
+        tokenizer,
+        document_tokens,
+        query_terms,
+        consistent,
+    })
+}
@@ -0,0 +1,149 @@
+//! Estimating the number of documents matching a query from a sample of
+//! their blocks, see `Searcher.approx_count`.
+
+use serde::Serialize;
+use tantivy::{
+    self as tv,
+    query::{EnableScoring, Query, Weight},
+    DocId, DocSet, TERMINATED,
+};
+
+/// The number of consecutive documents counted together.
+const BLOCK_SIZE: DocId = 4096;
+
+/// The number of blocks counted before the error is estimated.
+const MIN_SAMPLED_BLOCKS: usize = 16;
+
+/// A prime visiting the blocks in a spread, but reproducible, order.
+const BLOCK_STRIDE: u64 = 2_654_435_761;
+
+/// The estimated number of documents matching a query.
+#[derive(Serialize)]
+pub(crate) struct CountEstimate {
+    count: u64,
+    /// Whether every block was counted, the count being exact.
+    exact: bool,
+    /// The estimated relative standard error of the count.
+    relative_error: f64,
+    /// The number of documents of the counted blocks, deleted ones
+    /// included.
+    sampled_docs: u64,
+}
+
+/// A range of documents of a segment.
+struct Block {
+    segment_ord: usize,
+    start: DocId,
+    end: DocId,
+}
+
+/// Count the alive documents of a block matching a query.
+fn count_block(
+    searcher: &tv::Searcher,
+    weight: &dyn Weight,
+    block: &Block,
+) -> tv::Result<u64> {
+    let segment = searcher.segment_reader(block.segment_ord as u32);
+    let alive_bitset = segment.alive_bitset();
+    let mut scorer = weight.scorer(segment, 1.0)?;
+    let mut doc = scorer.doc();
+    if doc < block.start {
+        doc = scorer.seek(block.start);
+    }
+    let mut count = 0;
+    while doc != TERMINATED && doc < block.end {
+        if alive_bitset.is_none_or(|alive| alive.is_alive(doc)) {
+            count += 1;
+        }
+        doc = scorer.advance();
+    }
+    Ok(count)
+}
+
+/// Estimate the number of documents matching a query, counting blocks of
+/// documents until the relative standard error of the estimate is at most
+/// `max_error`, or all of them were counted.
+///
+/// The count of the documents is the ratio of matches in the counted blocks
+/// times the number of documents, its error follows from the spread of the
+/// ratios of the blocks.
+pub(crate) fn estimate(
+    searcher: &tv::Searcher,
+    query: &dyn Query,
+    max_error: f64,
+) -> tv::Result<CountEstimate> {
+    let weight =
+        query.weight(EnableScoring::disabled_from_searcher(searcher))?;
+    let blocks: Vec<Block> = searcher
+        .segment_readers()
+        .iter()
+        .enumerate()
+        .flat_map(|(segment_ord, segment)| {
+            let max_doc = segment.max_doc();
+            (0..max_doc)
+                .step_by(BLOCK_SIZE as usize)
+                .map(move |start| Block {
+                    segment_ord,
+                    start,
+                    end: max_doc.min(start + BLOCK_SIZE),
+                })
+        })
+        .collect();
+    let num_blocks = blocks.len();
+    let total_docs: u64 = blocks
+        .iter()
+        .map(|block| u64::from(block.end - block.start))
+        .sum();
+
+    // The matches and documents of the counted blocks.
+    let mut samples: Vec<(f64, f64)> = Vec::new();
+    let mut matches = 0;
+    let mut sampled_docs = 0;
+    let mut relative_error = 0.0;
+    for i in 0..num_blocks {
+        let block =
+            &blocks[(i as u64 * BLOCK_STRIDE % num_blocks as u64) as usize];
+        let count = count_block(searcher, weight.as_ref(), block)?;
+        let docs = u64::from(block.end - block.start);
+        matches += count;
+        sampled_docs += docs;
+        samples.push((count as f64, docs as f64));
+
+        let n = samples.len();
+        if n == num_blocks {
+            relative_error = 0.0;
+            break;
+        }
+        if n < MIN_SAMPLED_BLOCKS || matches == 0 {
+            continue;
+        }
+        let ratio = matches as f64 / sampled_docs as f64;
+        let spread = samples
+            .iter()
+            .map(|(count, docs)| (count - ratio * docs).powi(2))
+            .sum::<f64>()
+            / (n - 1) as f64;
+        let n = n as f64;
+        let big_n = num_blocks as f64;
+        let variance = big_n * big_n / n * (1.0 - n / big_n) * spread;
+        let count = ratio * total_docs as f64;
+        relative_error = variance.sqrt() / count;
+        if max_error > 0.0 && relative_error <= max_error {
+            break;
+        }
+    }
+
+    let exact = samples.len() == num_blocks;
+    let count = if exact {
+        matches
+    } else {
+        (matches as f64 / sampled_docs as f64 * total_docs as f64).round()
+            as u64
+    };
+    Ok(CountEstimate {
+        count,
+        exact,
+        relative_error,
+        sampled_docs,
+    })
+}
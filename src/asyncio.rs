@@ -0,0 +1,30 @@
+use std::sync::Mutex;
+
+use pyo3::{
+    exceptions::PyRuntimeError, prelude::*, types::PyCFunction, IntoPyObjectExt,
+};
+
+/// Run `f` in the default executor of the running event loop and return
+/// the asyncio future of its result.
+///
+/// The executor threads are Python threads, which `asyncio.run` joins
+/// before the interpreter may shut down, so `f` never runs while Python is
+/// finalizing. `f` should release the GIL during the work.
+///
+/// Raises a RuntimeError if there is no running event loop.
+pub(crate) fn spawn<F, T>(py: Python, f: F) -> PyResult<Bound<PyAny>>
+where
+    F: FnOnce(Python) -> PyResult<T> + Send + 'static,
+    T: for<'py> IntoPyObject<'py>,
+{
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    let f = Mutex::new(Some(f));
+    let work = PyCFunction::new_closure(py, None, None, move |args, _| {
+        let f =
+            f.lock().unwrap().take().ok_or_else(|| {
+                PyRuntimeError::new_err("The work already ran.")
+            })?;
+        f(args.py())?.into_py_any(args.py())
+    })?;
+    event_loop.call_method1("run_in_executor", (py.None(), work))
+}
@@ -0,0 +1,103 @@
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// State shared between an `AutoCommit` handle and its thread.
+#[derive(Default)]
+struct Signal {
+    state: Mutex<SignalState>,
+    wakeup: Condvar,
+}
+
+#[derive(Default)]
+struct SignalState {
+    stopped: bool,
+    /// Set by `notify()` until the thread handles it, so that notifications
+    /// sent while the thread isn't waiting are not lost.
+    notified: bool,
+}
+
+/// A background thread periodically asking whether a commit is due.
+///
+/// The thread calls its `tick` closure whenever `interval` elapsed since the
+/// last commit, or when it is woken up with `notify()`. The closure returns
+/// `None` to stop the thread, or whether it committed.
+///
+/// Dropping the handle stops the thread without waiting for it.
+pub(crate) struct AutoCommit {
+    signal: Arc<Signal>,
+    max_docs: Option<u64>,
+}
+
+impl AutoCommit {
+    pub(crate) fn spawn<F>(
+        interval: Option<Duration>,
+        max_docs: Option<u64>,
+        mut tick: F,
+    ) -> Self
+    where
+        F: FnMut(bool) -> Option<bool> + Send + 'static,
+    {
+        let signal = Arc::new(Signal::default());
+        let thread_signal = signal.clone();
+        thread::spawn(move || {
+            let mut last_commit = Instant::now();
+            loop {
+                let state = thread_signal.state.lock().unwrap();
+                let idle =
+                    |state: &mut SignalState| !state.stopped && !state.notified;
+                let mut state = match interval {
+                    Some(interval) => {
+                        let timeout =
+                            interval.saturating_sub(last_commit.elapsed());
+                        thread_signal
+                            .wakeup
+                            .wait_timeout_while(state, timeout, idle)
+                            .unwrap()
+                            .0
+                    }
+                    None => {
+                        thread_signal.wakeup.wait_while(state, idle).unwrap()
+                    }
+                };
+                if state.stopped {
+                    return;
+                }
+                state.notified = false;
+                drop(state);
+
+                let interval_elapsed =
+                    interval.is_some_and(|i| last_commit.elapsed() >= i);
+                match tick(interval_elapsed) {
+                    Some(true) => last_commit = Instant::now(),
+                    Some(false) if interval_elapsed => {
+                        last_commit = Instant::now()
+                    }
+                    Some(false) => {}
+                    None => return,
+                }
+            }
+        });
+        AutoCommit { signal, max_docs }
+    }
+
+    /// The number of pending documents that triggers a commit.
+    pub(crate) fn max_docs(&self) -> Option<u64> {
+        self.max_docs
+    }
+
+    /// Wake the thread up so that it checks whether a commit is due.
+    pub(crate) fn notify(&self) {
+        self.signal.state.lock().unwrap().notified = true;
+        self.signal.wakeup.notify_one();
+    }
+}
+
+impl Drop for AutoCommit {
+    fn drop(&mut self) {
+        self.signal.state.lock().unwrap().stopped = true;
+        self.signal.wakeup.notify_one();
+    }
+}
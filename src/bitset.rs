@@ -0,0 +1,182 @@
+//! Sets of the documents matching a query, applied cheaply to later
+//! searches, see `Searcher.matching_bitset`.
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use tantivy::{
+    self as tv,
+    query::{EmptyScorer, EnableScoring, Explanation, Scorer, Weight},
+    DocId, Score, SegmentId, SegmentReader,
+};
+
+use crate::{
+    query::{self, Query},
+    scorer::{explain_by_seek, BitSetScorer, DocBitSet},
+};
+
+/// The documents matching a query, one bit per document of each segment.
+///
+/// Returned by `Searcher.matching_bitset`, e.g. to compute an audience once
+/// and restrict many searches to it with their `pre_filter` argument.
+/// Bitsets of the same searcher combine with the `&` and `|` operators.
+///
+/// A bitset is tied to the segments of its searcher: searching the segments
+/// of a later commit with it raises a ValueError, rather than missing the
+/// documents of the new segments.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+#[derive(Clone)]
+pub(crate) struct Bitset {
+    segments: HashMap<SegmentId, Arc<DocBitSet>>,
+}
+
+impl Bitset {
+    /// The alive documents of a searcher matching a query.
+    pub(crate) fn collect(
+        searcher: &tv::Searcher,
+        query: &dyn tv::query::Query,
+    ) -> tv::Result<Self> {
+        let weight =
+            query.weight(EnableScoring::disabled_from_searcher(searcher))?;
+        let segments = searcher
+            .segment_readers()
+            .iter()
+            .map(|reader| {
+                let mut docs = DocBitSet::collect(weight.as_ref(), reader)?;
+                if let Some(alive) = reader.alive_bitset() {
+                    docs.retain(|doc| alive.is_alive(doc));
+                }
+                Ok((reader.segment_id(), Arc::new(docs)))
+            })
+            .collect::<tv::Result<_>>()?;
+        Ok(Bitset { segments })
+    }
+
+    /// Restrict a query to the documents of the bitset, keeping its scores.
+    ///
+    /// Raises a ValueError if a segment of the searcher isn't in the bitset.
+    pub(crate) fn restrict(
+        &self,
+        searcher: &tv::Searcher,
+        query: Query,
+    ) -> PyResult<Query> {
+        let missing = searcher
+            .segment_readers()
+            .iter()
+            .filter(|reader| !self.segments.contains_key(&reader.segment_id()))
+            .count();
+        if missing > 0 {
+            return Err(PyValueError::new_err(format!(
+                "The pre_filter bitset doesn't cover {missing} segment(s) of \
+                 the searcher, compute it again with this searcher."
+            )));
+        }
+        let filter = BitsetQuery {
+            segments: self.segments.clone(),
+        };
+        Ok(Query {
+            inner: query::filtered(query.inner, Box::new(filter)),
+        })
+    }
+
+    fn num_docs(&self) -> u64 {
+        self.segments.values().map(|docs| docs.len() as u64).sum()
+    }
+
+    fn combine(
+        &self,
+        other: &Bitset,
+        op: fn(&DocBitSet, &DocBitSet) -> DocBitSet,
+    ) -> PyResult<Bitset> {
+        if self.segments.len() != other.segments.len()
+            || !self
+                .segments
+                .keys()
+                .all(|id| other.segments.contains_key(id))
+        {
+            return Err(PyValueError::new_err(
+                "Only the bitsets of the same segments can be combined.",
+            ));
+        }
+        let segments = self
+            .segments
+            .iter()
+            .map(|(id, docs)| (*id, Arc::new(op(docs, &other.segments[id]))))
+            .collect();
+        Ok(Bitset { segments })
+    }
+}
+
+#[pymethods]
+impl Bitset {
+    /// The number of matching documents.
+    fn __len__(&self) -> usize {
+        self.num_docs() as usize
+    }
+
+    /// The documents of both bitsets.
+    ///
+    /// Raises a ValueError if the bitsets are of different segments.
+    fn __and__(&self, other: &Bitset) -> PyResult<Bitset> {
+        self.combine(other, DocBitSet::intersection)
+    }
+
+    /// The documents of either bitset.
+    ///
+    /// Raises a ValueError if the bitsets are of different segments.
+    fn __or__(&self, other: &Bitset) -> PyResult<Bitset> {
+        self.combine(other, DocBitSet::union)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Bitset(num_docs={}, num_segments={})",
+            self.num_docs(),
+            self.segments.len()
+        )
+    }
+}
+
+/// A query matching the documents of a bitset, with a constant score.
+#[derive(Clone)]
+struct BitsetQuery {
+    segments: HashMap<SegmentId, Arc<DocBitSet>>,
+}
+
+impl fmt::Debug for BitsetQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BitsetQuery")
+            .field("num_segments", &self.segments.len())
+            .finish()
+    }
+}
+
+impl tv::query::Query for BitsetQuery {
+    fn weight(
+        &self,
+        _enable_scoring: EnableScoring<'_>,
+    ) -> tv::Result<Box<dyn Weight>> {
+        Ok(Box::new(self.clone()))
+    }
+}
+
+impl Weight for BitsetQuery {
+    fn scorer(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> tv::Result<Box<dyn Scorer>> {
+        Ok(match self.segments.get(&reader.segment_id()) {
+            Some(docs) => Box::new(BitSetScorer::new(docs.clone(), boost)),
+            None => Box::new(EmptyScorer),
+        })
+    }
+
+    fn explain(
+        &self,
+        reader: &SegmentReader,
+        doc: DocId,
+    ) -> tv::Result<Explanation> {
+        explain_by_seek(self, reader, doc, "BitsetQuery")
+    }
+}
@@ -0,0 +1,176 @@
+use pyo3::{exceptions::PyValueError, prelude::*};
+use tantivy::{
+    self as tv,
+    query::{EnableScoring, Explanation, Scorer, Weight},
+    DocId, DocSet, Score, SegmentReader, TERMINATED,
+};
+
+use crate::scorer::{explain_by_seek, VecScorer};
+
+/// How the scores of the matching children make the score of their parent.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum ScoreMode {
+    Avg,
+    Max,
+    Min,
+    Sum,
+    /// Every matching parent gets a constant score.
+    None,
+}
+
+impl std::str::FromStr for ScoreMode {
+    type Err = PyErr;
+
+    fn from_str(score_mode: &str) -> PyResult<Self> {
+        match score_mode {
+            "avg" => Ok(ScoreMode::Avg),
+            "max" => Ok(ScoreMode::Max),
+            "min" => Ok(ScoreMode::Min),
+            "sum" => Ok(ScoreMode::Sum),
+            "none" => Ok(ScoreMode::None),
+            _ => Err(PyValueError::new_err(
+                "Invalid score mode, valid choices are: 'avg', 'max', 'min', \
+                 'sum' and 'none'",
+            )),
+        }
+    }
+}
+
+/// The scores of the matching children of a parent.
+struct ChildScores {
+    count: u32,
+    sum: Score,
+    max: Score,
+    min: Score,
+}
+
+impl ChildScores {
+    fn new(score: Score) -> Self {
+        ChildScores {
+            count: 1,
+            sum: score,
+            max: score,
+            min: score,
+        }
+    }
+
+    fn add(&mut self, score: Score) {
+        self.count += 1;
+        self.sum += score;
+        self.max = self.max.max(score);
+        self.min = self.min.min(score);
+    }
+
+    fn score(&self, score_mode: ScoreMode) -> Score {
+        match score_mode {
+            ScoreMode::Avg => self.sum / self.count as Score,
+            ScoreMode::Max => self.max,
+            ScoreMode::Min => self.min,
+            ScoreMode::Sum => self.sum,
+            ScoreMode::None => 1.0,
+        }
+    }
+}
+
+/// Query matching the parents of the documents matching a child query.
+///
+/// It relies on the blocks added by `IndexWriter.add_document_block`: the
+/// parent of a child is the first document after it matching the parent
+/// filter.
+#[derive(Debug)]
+pub(crate) struct BlockJoinQuery {
+    pub(crate) parent_filter: Box<dyn tv::query::Query>,
+    pub(crate) child_query: Box<dyn tv::query::Query>,
+    pub(crate) score_mode: ScoreMode,
+}
+
+impl Clone for BlockJoinQuery {
+    fn clone(&self) -> Self {
+        BlockJoinQuery {
+            parent_filter: self.parent_filter.box_clone(),
+            child_query: self.child_query.box_clone(),
+            score_mode: self.score_mode,
+        }
+    }
+}
+
+impl tv::query::Query for BlockJoinQuery {
+    fn weight(
+        &self,
+        enable_scoring: EnableScoring<'_>,
+    ) -> tv::Result<Box<dyn Weight>> {
+        let disabled_scoring = match enable_scoring.searcher() {
+            Some(searcher) => EnableScoring::disabled_from_searcher(searcher),
+            None => {
+                EnableScoring::disabled_from_schema(enable_scoring.schema())
+            }
+        };
+        let parent_weight = self.parent_filter.weight(disabled_scoring)?;
+        let child_weight = self.child_query.weight(enable_scoring)?;
+        Ok(Box::new(BlockJoinWeight {
+            parent_weight,
+            child_weight,
+            score_mode: self.score_mode,
+        }))
+    }
+}
+
+struct BlockJoinWeight {
+    parent_weight: Box<dyn Weight>,
+    child_weight: Box<dyn Weight>,
+    score_mode: ScoreMode,
+}
+
+impl Weight for BlockJoinWeight {
+    fn scorer(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> tv::Result<Box<dyn Scorer>> {
+        // Deleted parents are kept as block boundaries, the collector skips
+        // them anyway.
+        let mut parents = Vec::new();
+        self.parent_weight.for_each_no_score(reader, &mut |docs| {
+            parents.extend_from_slice(docs)
+        })?;
+
+        let alive_bitset = reader.alive_bitset();
+        let mut joined: Vec<(DocId, ChildScores)> = Vec::new();
+        let mut child_scorer = self.child_weight.scorer(reader, 1.0)?;
+        let mut doc = child_scorer.doc();
+        while doc != TERMINATED {
+            let is_alive = alive_bitset.is_none_or(|alive| alive.is_alive(doc));
+            let pos = parents.partition_point(|&parent| parent < doc);
+            let is_parent = parents.get(pos) == Some(&doc);
+            if let (true, false, Some(&parent)) =
+                (is_alive, is_parent, parents.get(pos))
+            {
+                let score = child_scorer.score();
+                // Children come in order, so do their parents.
+                match joined.last_mut() {
+                    Some((last, scores)) if *last == parent => {
+                        scores.add(score)
+                    }
+                    _ => joined.push((parent, ChildScores::new(score))),
+                }
+            }
+            doc = child_scorer.advance();
+        }
+
+        let docs = joined
+            .into_iter()
+            .map(|(parent, scores)| {
+                (parent, scores.score(self.score_mode) * boost)
+            })
+            .collect();
+        Ok(Box::new(VecScorer::new(docs)))
+    }
+
+    fn explain(
+        &self,
+        reader: &SegmentReader,
+        doc: DocId,
+    ) -> tv::Result<Explanation> {
+        explain_by_seek(self, reader, doc, "BlockJoinQuery")
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    ffi::{c_int, c_void},
+    ptr,
+};
+
+use pyo3::{
+    basic::CompareOp, buffer::PyBuffer, exceptions::PyBufferError, ffi,
+    prelude::*, types::PyBytes, IntoPyObjectExt,
+};
+
+/// The value of a bytes field.
+///
+/// Bytes implements the buffer protocol: `memoryview(value)`, numpy's
+/// `frombuffer` or `bytes(value)` read the value without going through a
+/// Python list. It compares equal to the bytes-like objects holding the same
+/// bytes.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+#[derive(Clone)]
+pub(crate) struct Bytes {
+    data: Vec<u8>,
+}
+
+impl Bytes {
+    pub(crate) fn new(data: Vec<u8>) -> Self {
+        Bytes { data }
+    }
+}
+
+/// Extract the value of a bytes field from a bytes-like object, or a
+/// sequence of integers.
+pub(crate) fn extract_bytes(any: &Bound<PyAny>) -> PyResult<Vec<u8>> {
+    if let Ok(bytes) = any.downcast::<Bytes>() {
+        return Ok(bytes.get().data.clone());
+    }
+    if let Ok(buffer) = PyBuffer::<u8>::get(any) {
+        return buffer.to_vec(any.py());
+    }
+    any.extract()
+}
+
+#[pymethods]
+impl Bytes {
+    #[new]
+    fn py_new(data: &Bound<PyAny>) -> PyResult<Self> {
+        Ok(Bytes::new(extract_bytes(data)?))
+    }
+
+    fn __len__(&self) -> usize {
+        self.data.len()
+    }
+
+    fn __bytes__<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.data)
+    }
+
+    fn __hash__(&self, py: Python) -> PyResult<isize> {
+        // Consistent with the equality to `bytes`.
+        PyBytes::new(py, &self.data).hash()
+    }
+
+    fn __richcmp__(
+        &self,
+        other: &Bound<PyAny>,
+        op: CompareOp,
+        py: Python<'_>,
+    ) -> PyResult<PyObject> {
+        let Ok(other) = extract_bytes(other) else {
+            return Ok(py.NotImplemented());
+        };
+        match op {
+            CompareOp::Eq => (self.data == other).into_py_any(py),
+            CompareOp::Ne => (self.data != other).into_py_any(py),
+            _ => Ok(py.NotImplemented()),
+        }
+    }
+
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        let bytes = PyBytes::new(py, &self.data);
+        Ok(format!("Bytes({})", bytes.repr()?))
+    }
+
+    fn __getnewargs__<'py>(&self, py: Python<'py>) -> (Bound<'py, PyBytes>,) {
+        (PyBytes::new(py, &self.data),)
+    }
+
+    /// Expose the bytes as a read-only, one-dimensional buffer of unsigned
+    /// bytes. The buffer holds a reference to the object, whose data never
+    /// moves since it is immutable.
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("The buffer view is null."));
+        }
+        if flags & ffi::PyBUF_WRITABLE == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err("Bytes are read-only."));
+        }
+        let data = &slf.get().data;
+        (*view).buf = data.as_ptr() as *mut c_void;
+        (*view).len = data.len() as ffi::Py_ssize_t;
+        (*view).readonly = 1;
+        (*view).itemsize = 1;
+        (*view).format = if flags & ffi::PyBUF_FORMAT == ffi::PyBUF_FORMAT {
+            c"B".as_ptr() as *mut _
+        } else {
+            ptr::null_mut()
+        };
+        (*view).ndim = 1;
+        (*view).shape = if flags & ffi::PyBUF_ND == ffi::PyBUF_ND {
+            &mut (*view).len
+        } else {
+            ptr::null_mut()
+        };
+        (*view).strides = if flags & ffi::PyBUF_STRIDES == ffi::PyBUF_STRIDES {
+            &mut (*view).itemsize
+        } else {
+            ptr::null_mut()
+        };
+        (*view).suboffsets = ptr::null_mut();
+        (*view).internal = ptr::null_mut();
+        (*view).obj = slf.into_any().into_ptr();
+        Ok(())
+    }
+
+    unsafe fn __releasebuffer__(&self, _view: *mut ffi::Py_buffer) {}
+}
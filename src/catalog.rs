@@ -0,0 +1,202 @@
+//! Named aliases of index directories, swapped atomically, see
+//! `IndexCatalog`.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use pyo3::{
+    exceptions::{PyKeyError, PyValueError},
+    prelude::*,
+};
+use tantivy::{
+    self as tv,
+    directory::{error::OpenReadError, Lock, MmapDirectory},
+    Directory,
+};
+
+use crate::{errors::tantivy_err, index::Index, paths, to_pyerr};
+
+/// The file holding the aliases, as a JSON object.
+const ALIASES_PATH: &str = "aliases.json";
+/// The lock serializing the updates of the aliases, across processes.
+const LOCK_PATH: &str = "aliases.lock";
+
+/// A catalog of aliases, each naming the directory of an index.
+///
+/// The aliases are stored in an `aliases.json` file of the root directory.
+/// Applications open an index by its alias, and reindexing happens in a new
+/// directory which replaces the previous one atomically with `swap`, for
+/// blue/green deployments. Readers either see the previous or the new
+/// directory, never a partially written catalog.
+///
+/// Args:
+///     root_path (Union[str, os.PathLike]): The directory of the catalog,
+///         created if missing. The relative index directories of the aliases
+///         are relative to it.
+///
+/// Raises an OSError if the root directory can't be created.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+pub(crate) struct IndexCatalog {
+    root: PathBuf,
+    directory: MmapDirectory,
+}
+
+impl IndexCatalog {
+    fn load(&self) -> PyResult<BTreeMap<String, String>> {
+        match self.directory.atomic_read(Path::new(ALIASES_PATH)) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(to_pyerr),
+            Err(OpenReadError::FileDoesNotExist(_)) => Ok(BTreeMap::new()),
+            Err(err) => Err(tantivy_err(err)),
+        }
+    }
+
+    /// Update the aliases while holding the lock of the catalog.
+    fn update<R>(
+        &self,
+        py: Python,
+        f: impl FnOnce(&mut BTreeMap<String, String>) -> PyResult<R> + Send,
+    ) -> PyResult<R>
+    where
+        R: Send,
+    {
+        py.allow_threads(|| {
+            let _lock = self
+                .directory
+                .acquire_lock(&Lock {
+                    filepath: PathBuf::from(LOCK_PATH),
+                    is_blocking: true,
+                })
+                .map_err(tantivy_err)?;
+            let mut aliases = self.load()?;
+            let result = f(&mut aliases)?;
+            let bytes =
+                serde_json::to_vec_pretty(&aliases).map_err(to_pyerr)?;
+            self.directory
+                .atomic_write(Path::new(ALIASES_PATH), &bytes)
+                .map_err(tantivy_err)?;
+            Ok(result)
+        })
+    }
+
+    /// The directory of an index, relative to the root of the catalog.
+    fn index_path(&self, path: &str) -> PathBuf {
+        paths::normalize(self.root.join(path))
+    }
+}
+
+#[pymethods]
+impl IndexCatalog {
+    #[new]
+    fn new(root_path: PathBuf) -> PyResult<Self> {
+        let root_path = paths::normalize(root_path);
+        std::fs::create_dir_all(&root_path)?;
+        let directory = MmapDirectory::open(&root_path).map_err(tantivy_err)?;
+        Ok(IndexCatalog {
+            root: root_path,
+            directory,
+        })
+    }
+
+    /// Point an alias at an index directory, atomically.
+    ///
+    /// Indexes already opened through the alias keep reading the previous
+    /// directory, the later calls to `open` open the new one.
+    ///
+    /// Args:
+    ///     alias (str): The name of the alias, created if missing.
+    ///     index_path (Union[str, os.PathLike]): The directory of the index,
+    ///         relative to the root of the catalog or absolute.
+    ///
+    /// Returns the directory the alias pointed at before, if any.
+    ///
+    /// Raises a ValueError if the alias name is invalid or if there is no
+    /// index in the directory.
+    fn swap(
+        &self,
+        py: Python,
+        alias: &str,
+        index_path: PathBuf,
+    ) -> PyResult<Option<String>> {
+        if alias.is_empty() || alias.chars().any(char::is_control) {
+            return Err(PyValueError::new_err(format!(
+                "Invalid alias name `{alias}`."
+            )));
+        }
+        // The aliases are stored as JSON strings.
+        let index_path = index_path.to_str().ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "The directory `{}` isn't valid unicode.",
+                index_path.display()
+            ))
+        })?;
+        let directory = MmapDirectory::open(self.index_path(index_path))
+            .map_err(tantivy_err)?;
+        if !tv::Index::exists(&directory).map_err(tantivy_err)? {
+            return Err(PyValueError::new_err(format!(
+                "There is no index in `{index_path}`."
+            )));
+        }
+        self.update(py, |aliases| {
+            Ok(aliases.insert(alias.to_string(), index_path.to_string()))
+        })
+    }
+
+    /// Remove an alias. The index directory is left alone.
+    ///
+    /// Returns the directory the alias pointed at.
+    ///
+    /// Raises a KeyError if there is no such alias.
+    fn remove(&self, py: Python, alias: &str) -> PyResult<String> {
+        self.update(py, |aliases| {
+            aliases.remove(alias).ok_or_else(|| {
+                PyKeyError::new_err(format!("No alias `{alias}`."))
+            })
+        })
+    }
+
+    /// The index directory of an alias.
+    ///
+    /// Raises a KeyError if there is no such alias.
+    fn resolve(&self, alias: &str) -> PyResult<PathBuf> {
+        match self.load()?.get(alias) {
+            Some(path) => Ok(self.index_path(path)),
+            None => Err(PyKeyError::new_err(format!("No alias `{alias}`."))),
+        }
+    }
+
+    /// Open the index an alias points at.
+    ///
+    /// Args:
+    ///     alias (str): The name of the alias.
+    ///     on_corruption (str, optional): See `Index.open`. Defaults to
+    ///         'raise'.
+    ///     read_mode (str, optional): See `Index.open`. Defaults to 'mmap'.
+    ///     madvise (str, optional): See `Index.open`.
+    ///     cache_mb (float, optional): See `Index.open`.
+    ///
+    /// Raises a KeyError if there is no such alias.
+    #[pyo3(signature = (alias, on_corruption = "raise", read_mode = "mmap", madvise = None, cache_mb = None))]
+    fn open(
+        &self,
+        py: Python,
+        alias: &str,
+        on_corruption: &str,
+        read_mode: &str,
+        madvise: Option<&str>,
+        cache_mb: Option<f64>,
+    ) -> PyResult<Index> {
+        let path = self.resolve(alias)?;
+        Index::open(py, path, on_corruption, read_mode, madvise, cache_mb)
+    }
+
+    /// The aliases, with the index directory they point at.
+    fn aliases(&self) -> PyResult<BTreeMap<String, String>> {
+        self.load()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("IndexCatalog(root_path='{}')", self.root.display())
+    }
+}
@@ -0,0 +1,447 @@
+//! Running several built-in collectors in a single pass over the matching
+//! documents, see `Searcher.collect` and the `collector` of
+//! `Searcher.search`.
+
+use std::collections::BTreeMap;
+
+use pyo3::{
+    exceptions::PyValueError, prelude::*, types::PyDict, IntoPyObjectExt,
+};
+use tantivy::{
+    self as tv,
+    aggregation::{
+        agg_req::Aggregations,
+        agg_result::{AggregationResult, MetricResult},
+        AggregationCollector,
+    },
+    collector::{Count, FruitHandle, TopDocs},
+    schema, Score,
+};
+
+use crate::{
+    aggregation,
+    errors::tantivy_err,
+    facet::{self, Facet, FacetCounts, FacetTreeCollector},
+    pythonize,
+    query::Query,
+    searcher::{DocAddress, Searcher},
+    to_pyerr,
+};
+
+/// A collector added to a `MultiCollector`.
+#[derive(Clone)]
+enum Collector {
+    TopDocs {
+        limit: usize,
+        offset: usize,
+    },
+    Count,
+    Facets {
+        field_name: String,
+        facets: Vec<Facet>,
+    },
+    Histogram {
+        field_name: String,
+        interval: f64,
+    },
+    Stats {
+        field_name: String,
+    },
+}
+
+/// The handle of the fruit of a collector in a tantivy `MultiCollector`.
+/// The histograms and stats share one aggregation collector, their
+/// aggregations are named after their collectors.
+enum Handle {
+    TopDocs(FruitHandle<Vec<(Score, tv::DocAddress)>>),
+    Count(FruitHandle<usize>),
+    Facets(FruitHandle<BTreeMap<schema::Facet, u64>>),
+    Histogram,
+    Stats,
+}
+
+/// The fruit of a collector, before its conversion to Python.
+enum Output {
+    TopDocs(Vec<(Score, tv::DocAddress)>),
+    Count(usize),
+    Facets(BTreeMap<schema::Facet, u64>),
+    Histogram(Vec<(f64, u64)>),
+    Stats(Option<tv::aggregation::metric::Stats>),
+}
+
+/// Several built-in collectors run in a single pass over the documents
+/// matching a query, for the cases `Searcher.search` doesn't cover.
+///
+/// Every collector is added under a name, the key of its fruit in the dict
+/// returned by `Searcher.collect`. The `add_*` methods return the collector
+/// so that the calls can be chained. The stats and facets collectors can
+/// also run along the hits of `Searcher.search`, see its `collector`
+/// argument.
+///
+/// Example:
+///
+///     >>> collector = (
+///     ...     tantivy.MultiCollector()
+///     ...     .add_top_docs("hits", limit=5)
+///     ...     .add_count("total")
+///     ...     .add_stats("rating", "rating")
+///     ... )
+///     >>> fruits = searcher.collect(query, collector)
+///     >>> fruits["total"]
+#[pyclass(module = "tantivy.tantivy")]
+#[derive(Clone, Default)]
+pub(crate) struct MultiCollector {
+    collectors: Vec<(String, Collector)>,
+}
+
+#[pymethods]
+impl MultiCollector {
+    #[new]
+    fn new() -> Self {
+        MultiCollector::default()
+    }
+
+    /// Collect the best scoring hits.
+    ///
+    /// Args:
+    ///     name (str): The name of the fruit, a list of `(score,
+    ///         DocAddress)` tuples, the best hit first.
+    ///     limit (int, optional): The maximum number of hits. Defaults to
+    ///         10.
+    ///     offset (int, optional): The number of best hits skipped.
+    ///         Defaults to 0.
+    ///
+    /// Raises a ValueError if the name is taken or the limit is 0.
+    #[pyo3(signature = (name, limit = 10, offset = 0))]
+    fn add_top_docs(
+        slf: PyRefMut<Self>,
+        name: String,
+        limit: usize,
+        offset: usize,
+    ) -> PyResult<PyRefMut<Self>> {
+        if limit == 0 {
+            return Err(PyValueError::new_err(
+                "The limit of the top docs must be at least 1.",
+            ));
+        }
+        Self::add(slf, name, Collector::TopDocs { limit, offset })
+    }
+
+    /// Count the matching documents.
+    ///
+    /// Args:
+    ///     name (str): The name of the fruit, an int.
+    ///
+    /// Raises a ValueError if the name is taken.
+    fn add_count(
+        slf: PyRefMut<Self>,
+        name: String,
+    ) -> PyResult<PyRefMut<Self>> {
+        Self::add(slf, name, Collector::Count)
+    }
+
+    /// Count the matching documents of the facets of a field, as
+    /// `Searcher.facet_counts`.
+    ///
+    /// Args:
+    ///     name (str): The name of the fruit, a FacetCounts object.
+    ///     field_name (str): The facet field to count.
+    ///     facets (List[Facet], optional): The facets whose descendants are
+    ///         counted. Defaults to the root facet, as does an empty list.
+    ///
+    /// Raises a ValueError if the name is taken.
+    #[pyo3(signature = (name, field_name, facets = None))]
+    fn add_facets(
+        slf: PyRefMut<Self>,
+        name: String,
+        field_name: String,
+        facets: Option<Vec<Facet>>,
+    ) -> PyResult<PyRefMut<Self>> {
+        let facets = facets.unwrap_or_default();
+        Self::add(slf, name, Collector::Facets { field_name, facets })
+    }
+
+    /// Count the matching documents by bucket of the values of a fast
+    /// field, the buckets starting at the multiples of the interval.
+    ///
+    /// Args:
+    ///     name (str): The name of the fruit, a list of `(key, doc_count)`
+    ///         tuples in key order, without the empty buckets.
+    ///     field_name (str): The numeric fast field to bucket.
+    ///     interval (float): The width of the buckets.
+    ///
+    /// Raises a ValueError if the name is taken or the interval isn't
+    /// positive.
+    fn add_histogram(
+        slf: PyRefMut<Self>,
+        name: String,
+        field_name: String,
+        interval: f64,
+    ) -> PyResult<PyRefMut<Self>> {
+        if interval.is_nan() || interval <= 0.0 {
+            return Err(PyValueError::new_err(
+                "The interval of a histogram must be a positive number.",
+            ));
+        }
+        Self::add(
+            slf,
+            name,
+            Collector::Histogram {
+                field_name,
+                interval,
+            },
+        )
+    }
+
+    /// Compute the count, sum, min, max and average of the values of a
+    /// fast field.
+    ///
+    /// Args:
+    ///     name (str): The name of the fruit, a dict with the `count`,
+    ///         `sum`, `min`, `max` and `avg` keys, the last four None if no
+    ///         matching document has a value.
+    ///     field_name (str): The numeric fast field.
+    ///
+    /// Raises a ValueError if the name is taken.
+    fn add_stats(
+        slf: PyRefMut<Self>,
+        name: String,
+        field_name: String,
+    ) -> PyResult<PyRefMut<Self>> {
+        Self::add(slf, name, Collector::Stats { field_name })
+    }
+
+    /// The names of the collectors, in the order they were added.
+    #[getter]
+    fn names(&self) -> Vec<String> {
+        self.collectors
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.collectors.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("MultiCollector(names={:?})", self.names())
+    }
+}
+
+/// The collectors of a `MultiCollector` run by `Searcher.search` along
+/// its hits.
+pub(crate) struct SearchCollectors {
+    /// The aggregations of the stats collectors, named after them.
+    pub(crate) stats: Option<Aggregations>,
+    /// The facets collectors, by name.
+    pub(crate) facets: Vec<(String, FacetTreeCollector)>,
+}
+
+impl MultiCollector {
+    fn add(
+        mut slf: PyRefMut<Self>,
+        name: String,
+        collector: Collector,
+    ) -> PyResult<PyRefMut<Self>> {
+        if slf.collectors.iter().any(|(taken, _)| *taken == name) {
+            return Err(PyValueError::new_err(format!(
+                "A collector is already named `{name}`."
+            )));
+        }
+        slf.collectors.push((name, collector));
+        Ok(slf)
+    }
+
+    /// The aggregations of the histograms and stats, named after their
+    /// collectors.
+    fn aggregations(&self) -> PyResult<Option<Aggregations>> {
+        let requests: serde_json::Map<String, serde_json::Value> = self
+            .collectors
+            .iter()
+            .filter_map(|(name, collector)| {
+                let request = match collector {
+                    Collector::Histogram {
+                        field_name,
+                        interval,
+                    } => serde_json::json!({ "histogram": {
+                        "field": field_name,
+                        "interval": interval,
+                    }}),
+                    Collector::Stats { field_name } => {
+                        serde_json::json!({ "stats": { "field": field_name } })
+                    }
+                    _ => return None,
+                };
+                Some((name.clone(), request))
+            })
+            .collect();
+        if requests.is_empty() {
+            return Ok(None);
+        }
+        serde_json::from_value(requests.into())
+            .map(Some)
+            .map_err(to_pyerr)
+    }
+
+    /// The collectors run by `Searcher.search`, checked against the
+    /// searcher.
+    ///
+    /// Raises a ValueError for the collectors other than the stats and
+    /// facets ones, as the search collects its hits and count itself.
+    pub(crate) fn search_collectors(
+        &self,
+        searcher: &Searcher,
+    ) -> PyResult<SearchCollectors> {
+        let mut facets = Vec::new();
+        for (name, collector) in &self.collectors {
+            match collector {
+                Collector::Facets {
+                    field_name,
+                    facets: roots,
+                } => facets.push((
+                    name.clone(),
+                    searcher.facet_collector(field_name, roots.clone())?,
+                )),
+                Collector::Stats { .. } => {}
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "The `{name}` collector can't run along a search, \
+                         which only runs stats and facets collectors, use \
+                         Searcher.collect instead."
+                    )))
+                }
+            }
+        }
+        let stats = self.aggregations()?;
+        if let Some(aggs) = &stats {
+            searcher.check_aggregation_fields(aggs)?;
+        }
+        Ok(SearchCollectors { stats, facets })
+    }
+
+    /// Run the collectors on the documents of a searcher matching a query.
+    pub(crate) fn collect(
+        &self,
+        py: Python,
+        searcher: &Searcher,
+        query: &Query,
+    ) -> PyResult<Py<PyDict>> {
+        let mut facet_collectors = Vec::new();
+        for (_, collector) in &self.collectors {
+            match collector {
+                Collector::TopDocs { limit, offset } => {
+                    searcher.limits.check_limit("limit", *limit)?;
+                    searcher.limits.check_offset(*offset)?;
+                }
+                Collector::Facets { field_name, facets } => facet_collectors
+                    .push(
+                        searcher.facet_collector(field_name, facets.clone())?,
+                    ),
+                _ => {}
+            }
+        }
+        let aggs = self.aggregations()?;
+        if let Some(aggs) = &aggs {
+            searcher.check_aggregation_fields(aggs)?;
+        }
+        let query = searcher.restrict(py, query)?;
+        let faceted = !facet_collectors.is_empty();
+
+        let outputs = py.allow_threads(|| {
+            let mut multicollector = tv::collector::MultiCollector::new();
+            let mut facet_collectors = facet_collectors.into_iter();
+            let handles: Vec<Handle> = self
+                .collectors
+                .iter()
+                .map(|(_, collector)| match collector {
+                    Collector::TopDocs { limit, offset } => {
+                        Handle::TopDocs(multicollector.add_collector(
+                            TopDocs::with_limit(*limit).and_offset(*offset),
+                        ))
+                    }
+                    Collector::Count => {
+                        Handle::Count(multicollector.add_collector(Count))
+                    }
+                    Collector::Facets { .. } => Handle::Facets(
+                        multicollector
+                            .add_collector(facet_collectors.next().unwrap()),
+                    ),
+                    Collector::Histogram { .. } => Handle::Histogram,
+                    Collector::Stats { .. } => Handle::Stats,
+                })
+                .collect();
+            let aggs_handle = aggs.map(|aggs| {
+                multicollector.add_collector(AggregationCollector::from_aggs(
+                    aggs,
+                    Default::default(),
+                ))
+            });
+
+            let mut fruits = if faceted {
+                facet::search_in_parallel(
+                    &searcher.inner,
+                    query.get(),
+                    &multicollector,
+                )
+            } else {
+                searcher.inner.search(query.get(), &multicollector)
+            }
+            .map_err(tantivy_err)?;
+            let mut agg_results =
+                aggs_handle.map(|handle| handle.extract(&mut fruits));
+            let mut agg_result = |name: &str| {
+                agg_results
+                    .as_mut()
+                    .and_then(|results| results.0.remove(name))
+            };
+            let outputs: Vec<Output> = self
+                .collectors
+                .iter()
+                .zip(handles)
+                .map(|((name, _), handle)| match handle {
+                    Handle::TopDocs(handle) => {
+                        Output::TopDocs(handle.extract(&mut fruits))
+                    }
+                    Handle::Count(handle) => {
+                        Output::Count(handle.extract(&mut fruits))
+                    }
+                    Handle::Facets(handle) => {
+                        Output::Facets(handle.extract(&mut fruits))
+                    }
+                    Handle::Histogram => Output::Histogram(
+                        aggregation::histogram_buckets(agg_result(name)),
+                    ),
+                    Handle::Stats => Output::Stats(match agg_result(name) {
+                        Some(AggregationResult::MetricResult(
+                            MetricResult::Stats(stats),
+                        )) => Some(stats),
+                        _ => None,
+                    }),
+                })
+                .collect();
+            Ok::<_, PyErr>(outputs)
+        })?;
+
+        let dict = PyDict::new(py);
+        for ((name, _), output) in self.collectors.iter().zip(outputs) {
+            let fruit = match output {
+                Output::TopDocs(hits) => hits
+                    .iter()
+                    .map(|(score, doc)| (*score, DocAddress::from(doc)))
+                    .collect::<Vec<_>>()
+                    .into_py_any(py)?,
+                Output::Count(count) => count.into_py_any(py)?,
+                Output::Facets(counts) => {
+                    FacetCounts { counts }.into_py_any(py)?
+                }
+                Output::Histogram(buckets) => buckets.into_py_any(py)?,
+                Output::Stats(stats) => {
+                    pythonize::pythonize(py, &stats).map_err(to_pyerr)?
+                }
+            };
+            dict.set_item(name, fruit)?;
+        }
+        Ok(dict.unbind())
+    }
+}
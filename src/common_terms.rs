@@ -0,0 +1,48 @@
+use tantivy::{
+    query::{BooleanQuery, Occur, Query},
+    Term,
+};
+
+/// Remove the clauses of `query` searching a single term for which
+/// `is_common` holds, acting as query-time stop words.
+///
+/// A boolean query whose positive clauses were all removed is removed as
+/// well. Returns None if nothing is left of `query`, in which case the caller
+/// should keep the original query rather than match nothing.
+pub(crate) fn drop_common_terms(
+    query: &dyn Query,
+    is_common: &dyn Fn(&Term) -> bool,
+) -> Option<Box<dyn Query>> {
+    let Some(boolean) = query.downcast_ref::<BooleanQuery>() else {
+        return match single_term(query) {
+            Some(term) if is_common(term) => None,
+            _ => Some(query.box_clone()),
+        };
+    };
+    let clauses: Vec<(Occur, Box<dyn Query>)> = boolean
+        .clauses()
+        .iter()
+        .filter_map(|(occur, clause)| match occur {
+            // Excluding a common term changes what matches.
+            Occur::MustNot => Some((*occur, clause.box_clone())),
+            _ => Some((*occur, drop_common_terms(clause.as_ref(), is_common)?)),
+        })
+        .collect();
+    if clauses.iter().all(|(occur, _)| *occur == Occur::MustNot) {
+        return None;
+    }
+    Some(Box::new(BooleanQuery::new(clauses)))
+}
+
+/// The term searched by a term query, possibly boosted. Phrase queries
+/// report their terms as needing positions, unlike term queries.
+fn single_term(query: &dyn Query) -> Option<&Term> {
+    let mut terms = Vec::new();
+    query.query_terms(&mut |term, need_position| {
+        terms.push((term, need_position))
+    });
+    match terms[..] {
+        [(term, false)] => Some(term),
+        _ => None,
+    }
+}
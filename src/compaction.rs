@@ -0,0 +1,212 @@
+//! Merges and garbage collection run by a background thread, see
+//! `Index.start_compaction`.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use tantivy::{
+    self as tv, directory::error::LockError, indexer::NoMergePolicy,
+    SegmentMeta,
+};
+
+use crate::{
+    activity::Activity,
+    auto_commit::AutoCommit,
+    throttle::{bytes_per_sec, check_mb_per_sec, RateLimiter},
+};
+
+/// The memory budget of the writer merging the segments, the minimum
+/// accepted by tantivy: merges don't buffer documents.
+const MERGE_HEAP_SIZE: usize = 15_000_000;
+
+/// When and how the segments of an index are compacted.
+///
+/// Args:
+///     interval_secs (float, optional): The number of seconds between two
+///         checks of the segments. Defaults to 60.
+///     max_segments (int, optional): The number of segments above which the
+///         smallest segments are merged together. Defaults to 10.
+///     max_deleted_ratio (float, optional): The fraction of deleted
+///         documents from which a segment is merged to expunge them.
+///         Defaults to 0.2.
+///     max_mb_per_sec (float, optional): The maximum number of megabytes
+///         per second written by the merges. Unlimited by default.
+///
+/// Raises a ValueError if a limit is out of range.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+#[derive(Clone, Debug)]
+pub(crate) struct CompactionPolicy {
+    #[pyo3(get)]
+    interval_secs: f64,
+    #[pyo3(get)]
+    max_segments: usize,
+    #[pyo3(get)]
+    max_deleted_ratio: f64,
+    #[pyo3(get)]
+    max_mb_per_sec: Option<f64>,
+}
+
+#[pymethods]
+impl CompactionPolicy {
+    #[new]
+    #[pyo3(signature = (interval_secs = 60.0, max_segments = 10, max_deleted_ratio = 0.2, max_mb_per_sec = None))]
+    fn new(
+        interval_secs: f64,
+        max_segments: usize,
+        max_deleted_ratio: f64,
+        max_mb_per_sec: Option<f64>,
+    ) -> PyResult<Self> {
+        if !(interval_secs > 0.0 && interval_secs.is_finite()) {
+            return Err(PyValueError::new_err(
+                "interval_secs must be a positive number of seconds.",
+            ));
+        }
+        if max_segments == 0 {
+            return Err(PyValueError::new_err(
+                "max_segments must be at least 1.",
+            ));
+        }
+        if !(0.0..=1.0).contains(&max_deleted_ratio) {
+            return Err(PyValueError::new_err(
+                "max_deleted_ratio must be between 0 and 1.",
+            ));
+        }
+        check_mb_per_sec(max_mb_per_sec)?;
+        Ok(CompactionPolicy {
+            interval_secs,
+            max_segments,
+            max_deleted_ratio,
+            max_mb_per_sec,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "CompactionPolicy(interval_secs={}, max_segments={}, \
+             max_deleted_ratio={}, max_mb_per_sec={})",
+            self.interval_secs,
+            self.max_segments,
+            self.max_deleted_ratio,
+            self.max_mb_per_sec
+                .map_or("None".to_string(), |mb| mb.to_string()),
+        )
+    }
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        CompactionPolicy {
+            interval_secs: 60.0,
+            max_segments: 10,
+            max_deleted_ratio: 0.2,
+            max_mb_per_sec: None,
+        }
+    }
+}
+
+impl CompactionPolicy {
+    /// The segments to merge together, if any.
+    fn merge_candidates(&self, segments: &[SegmentMeta]) -> Vec<SegmentMeta> {
+        let mut segments = segments.to_vec();
+        segments.sort_by_key(|segment| segment.num_docs());
+        let num_smallest =
+            (segments.len() + 1).saturating_sub(self.max_segments);
+        segments
+            .iter()
+            .enumerate()
+            .filter(|(i, segment)| {
+                let deleted_ratio = segment.num_deleted_docs() as f64
+                    / segment.max_doc().max(1) as f64;
+                (num_smallest > 1 && *i < num_smallest)
+                    || (segment.has_deletes()
+                        && deleted_ratio >= self.max_deleted_ratio)
+            })
+            .map(|(_, segment)| segment.clone())
+            .collect()
+    }
+}
+
+/// The background compaction of an index. Dropping it stops the thread.
+pub(crate) struct Compaction {
+    _thread: AutoCommit,
+    /// The error that stopped the thread, if any.
+    error: Arc<Mutex<Option<tv::TantivyError>>>,
+}
+
+impl Compaction {
+    /// Start compacting `index`, whose files are written at the rate of
+    /// `limiter`.
+    pub(crate) fn spawn(
+        index: tv::Index,
+        limiter: Arc<RateLimiter>,
+        activity: Arc<Activity>,
+        policy: CompactionPolicy,
+    ) -> Self {
+        let error = Arc::new(Mutex::new(None));
+        let thread_error = error.clone();
+        let interval = Duration::from_secs_f64(policy.interval_secs);
+        let thread = AutoCommit::spawn(Some(interval), None, move |_| {
+            match compact(&index, &limiter, &activity, &policy) {
+                Ok(merged) => Some(merged),
+                Err(err) => {
+                    *thread_error.lock().unwrap() = Some(err);
+                    None
+                }
+            }
+        });
+        Compaction {
+            _thread: thread,
+            error,
+        }
+    }
+
+    /// The error that stopped the compaction, if any.
+    pub(crate) fn take_error(&self) -> Option<tv::TantivyError> {
+        self.error.lock().unwrap().take()
+    }
+}
+
+/// Merge the segments selected by the policy and garbage collect the files
+/// of the merged segments.
+///
+/// Returns whether segments were merged. Nothing is done while another
+/// writer holds the index lock: the segments are checked again after the
+/// next interval.
+fn compact(
+    index: &tv::Index,
+    limiter: &RateLimiter,
+    activity: &Activity,
+    policy: &CompactionPolicy,
+) -> tv::Result<bool> {
+    let candidates =
+        policy.merge_candidates(&index.searchable_segment_metas()?);
+    if candidates.is_empty() {
+        return Ok(false);
+    }
+    let mut writer: tv::IndexWriter =
+        match index.writer_with_num_threads(1, MERGE_HEAP_SIZE) {
+            Err(tv::TantivyError::LockFailure(LockError::LockBusy, _)) => {
+                return Ok(false)
+            }
+            writer => writer?,
+        };
+    writer.set_merge_policy(Box::new(NoMergePolicy));
+    // The limit of the index belongs to the writer holding the lock.
+    limiter.set_bytes_per_sec(policy.max_mb_per_sec.map_or(0, bytes_per_sec));
+    // The segments may have changed before the lock was acquired.
+    let segment_ids: Vec<_> = policy
+        .merge_candidates(&index.searchable_segment_metas()?)
+        .iter()
+        .map(|segment| segment.id())
+        .collect();
+    if !segment_ids.is_empty() {
+        activity.record_merge();
+        writer.merge(&segment_ids).wait()?;
+        futures::executor::block_on(writer.garbage_collect_files())?;
+    }
+    writer.wait_merging_threads()?;
+    Ok(!segment_ids.is_empty())
+}
@@ -0,0 +1,181 @@
+//! Top documents by score, with a limit on the number of documents sharing
+//! a value of a fast field.
+
+use std::collections::HashMap;
+
+use tantivy::{
+    self as tv,
+    collector::{Collector, SegmentCollector},
+    columnar::{BytesColumn, Column, ColumnType},
+    DocAddress, DocId, Score, SegmentOrdinal, SegmentReader,
+};
+
+/// The value of a document for the diversity field: the bytes of a text or
+/// bytes value, the big-endian bytes of the `u64` mapping of other values.
+/// None for the documents without a value.
+type GroupKey = Option<Vec<u8>>;
+
+/// Collects the top documents by score, keeping at most `max_per_value`
+/// documents with the same value of a fast field.
+///
+/// The result is the one of taking the hits in score order and skipping the
+/// ones whose value already has `max_per_value` hits. Only the first value
+/// of a multi-valued field counts. The documents without a value are not
+/// limited.
+pub(crate) struct DiversifiedTopDocs {
+    pub(crate) field_name: String,
+    pub(crate) max_per_value: usize,
+    pub(crate) limit: usize,
+    pub(crate) offset: usize,
+}
+
+pub(crate) struct DiversifiedSegmentCollector {
+    segment_ord: SegmentOrdinal,
+    /// The `u64` column of the field, None if the segment has no value.
+    column: Option<Column<u64>>,
+    /// The dictionary of the term ordinals of a text or bytes field.
+    dictionary: Option<BytesColumn>,
+    /// The best documents of each value, by descending score.
+    groups: HashMap<u64, Vec<(Score, DocId)>>,
+    /// The best documents without a value, by descending score.
+    missing: Vec<(Score, DocId)>,
+    max_per_value: usize,
+    limit: usize,
+}
+
+/// Insert a hit in a list sorted by descending score, keeping at most `cap`
+/// hits.
+fn insert_capped<T: Ord>(
+    hits: &mut Vec<(Score, T)>,
+    hit: (Score, T),
+    cap: usize,
+) {
+    if hits.len() == cap && hits.last().is_none_or(|last| hit.0 <= last.0) {
+        return;
+    }
+    let position = hits.partition_point(|other| by_score(other, &hit).is_lt());
+    hits.insert(position, hit);
+    hits.truncate(cap);
+}
+
+/// Descending score, then ascending document.
+fn by_score<T: Ord>(a: &(Score, T), b: &(Score, T)) -> std::cmp::Ordering {
+    b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1))
+}
+
+/// Keep the best `max_per_value` hits of each group, then the best `limit`
+/// of them.
+fn select<T: Ord>(
+    groups: impl IntoIterator<Item = (GroupKey, Vec<(Score, T)>)>,
+    max_per_value: usize,
+    limit: usize,
+) -> Vec<(Score, GroupKey, T)> {
+    let mut hits: Vec<(Score, GroupKey, T)> = groups
+        .into_iter()
+        .flat_map(|(key, mut group)| {
+            group.sort_by(by_score);
+            if key.is_some() {
+                group.truncate(max_per_value);
+            }
+            group
+                .into_iter()
+                .map(move |(score, doc)| (score, key.clone(), doc))
+        })
+        .collect();
+    hits.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.2.cmp(&b.2)));
+    hits.truncate(limit);
+    hits
+}
+
+impl Collector for DiversifiedTopDocs {
+    type Fruit = Vec<(Score, DocAddress)>;
+    type Child = DiversifiedSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_ord: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tv::Result<Self::Child> {
+        let fast_fields = segment.fast_fields();
+        let column = fast_fields.u64_lenient(&self.field_name)?;
+        let dictionary = match column.as_ref().map(|(_, kind)| *kind) {
+            Some(ColumnType::Str) => fast_fields
+                .str(&self.field_name)?
+                .map(|column| BytesColumn::clone(&column)),
+            Some(ColumnType::Bytes) => fast_fields.bytes(&self.field_name)?,
+            _ => None,
+        };
+        Ok(DiversifiedSegmentCollector {
+            segment_ord,
+            column: column.map(|(column, _)| column),
+            dictionary,
+            groups: HashMap::new(),
+            missing: Vec::new(),
+            max_per_value: self.max_per_value,
+            limit: self.limit + self.offset,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<Vec<(Score, GroupKey, DocAddress)>>,
+    ) -> tv::Result<Self::Fruit> {
+        let mut groups: HashMap<GroupKey, Vec<(Score, DocAddress)>> =
+            HashMap::new();
+        for (score, key, doc) in segment_fruits.into_iter().flatten() {
+            groups.entry(key).or_default().push((score, doc));
+        }
+        Ok(select(groups, self.max_per_value, self.limit + self.offset)
+            .into_iter()
+            .skip(self.offset)
+            .map(|(score, _, doc)| (score, doc))
+            .collect())
+    }
+}
+
+impl SegmentCollector for DiversifiedSegmentCollector {
+    type Fruit = Vec<(Score, GroupKey, DocAddress)>;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        match self.column.as_ref().and_then(|column| column.first(doc)) {
+            Some(value) => {
+                let group = self.groups.entry(value).or_default();
+                insert_capped(group, (score, doc), self.max_per_value);
+            }
+            None => insert_capped(&mut self.missing, (score, doc), self.limit),
+        }
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        let dictionary = self.dictionary;
+        let key = |value: u64| -> GroupKey {
+            let mut bytes = Vec::new();
+            match &dictionary {
+                Some(dictionary) => {
+                    // An ordinal of the column is always in its dictionary.
+                    let _ = dictionary.ord_to_bytes(value, &mut bytes);
+                }
+                None => bytes.extend(value.to_be_bytes()),
+            }
+            Some(bytes)
+        };
+        let groups = self
+            .groups
+            .into_iter()
+            .map(|(value, group)| (key(value), group))
+            .chain([(None, self.missing)]);
+        // The hits of a segment beyond its best `limit` ones can't be in
+        // the best `limit` hits of the index.
+        let segment_ord = self.segment_ord;
+        select(groups, self.max_per_value, self.limit)
+            .into_iter()
+            .map(|(score, key, doc)| {
+                (score, key, DocAddress::new(segment_ord, doc))
+            })
+            .collect()
+    }
+}
@@ -4,19 +4,27 @@
 use itertools::Itertools;
 use pyo3::{
     basic::CompareOp,
+    exceptions::PyTypeError,
     prelude::*,
     types::{
-        PyAny, PyBool, PyDateAccess, PyDateTime, PyDict, PyInt, PyList,
-        PyTimeAccess, PyTuple,
+        PyAny, PyBool, PyBytes, PyDateAccess, PyDateTime, PyDict, PyInt,
+        PyList, PyString, PyTimeAccess, PyTuple, PyType,
     },
-    Python,
+    IntoPyObjectExt, Python,
 };
 
 use chrono::{offset::TimeZone, NaiveDateTime, Utc};
 
 use tantivy::{self as tv, schema::document::OwnedValue as Value};
 
-use crate::{facet::Facet, schema::Schema, to_pyerr};
+use crate::{
+    bytes::{extract_bytes, Bytes},
+    facet::{extract_facet, Facet},
+    geo::GeoPoint,
+    pythonize,
+    schema::Schema,
+    to_pyerr,
+};
 use serde::{
     ser::SerializeMap, Deserialize, Deserializer, Serialize, Serializer,
 };
@@ -48,12 +56,11 @@ pub(crate) fn extract_value(any: &Bound<PyAny>) -> PyResult<Value> {
     if let Ok(facet) = any.extract::<Facet>() {
         return Ok(Value::Facet(facet.inner));
     }
-    if let Ok(b) = any.extract::<Vec<u8>>() {
+    if let Ok(b) = extract_bytes(any) {
         return Ok(Value::Bytes(b));
     }
     if let Ok(dict) = any.downcast::<PyDict>() {
-        if let Ok(json) = pythonize::depythonize_bound(dict.clone().into_any())
-        {
+        if let Ok(json) = pythonize::depythonize(dict.as_any()) {
             return Ok(Value::Object(json));
         }
     }
@@ -109,13 +116,9 @@ pub(crate) fn extract_value_for_type(
                 datetime.and_utc().timestamp(),
             ))
         }
-        tv::schema::Type::Facet => Value::Facet(
-            any.extract::<Facet>()
-                .map_err(to_pyerr_for_type("Facet", field_name, any))?
-                .inner,
-        ),
+        tv::schema::Type::Facet => Value::Facet(extract_facet(any)?),
         tv::schema::Type::Bytes => Value::Bytes(
-            any.extract::<Vec<u8>>()
+            extract_bytes(any)
                 .map_err(to_pyerr_for_type("Bytes", field_name, any))?,
         ),
         tv::schema::Type::Json => {
@@ -129,7 +132,7 @@ pub(crate) fn extract_value_for_type(
                 any.downcast::<PyDict>()
                     .map_err(to_pyerr_for_type("Json", field_name, any))
                     .and_then(|dict| {
-                        pythonize::depythonize_bound(dict.clone().into_any())
+                        pythonize::depythonize(dict.as_any())
                             .map_err(to_pyerr_for_type("Json", field_name, any))
                     })?,
             )
@@ -152,6 +155,57 @@ pub(crate) fn extract_value_for_type(
     Ok(value)
 }
 
+/// Convert the dataclasses, attrs classes and pydantic models of a value to
+/// dicts, and its tuples to lists, recursively.
+fn plain_value<'py>(value: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+    let py = value.py();
+    if value.is_instance_of::<PyType>() {
+        return Ok(value.clone());
+    }
+    if value.hasattr("model_dump")? {
+        return plain_value(&value.call_method0("model_dump")?);
+    }
+    if value.hasattr("__fields__")? && value.hasattr("dict")? {
+        // pydantic v1.
+        return plain_value(&value.call_method0("dict")?);
+    }
+    let dataclasses = py.import("dataclasses")?;
+    let attributes = if value.hasattr("__attrs_attrs__")? {
+        Some(value.getattr("__attrs_attrs__")?)
+    } else if dataclasses
+        .call_method1("is_dataclass", (value,))?
+        .is_truthy()?
+    {
+        Some(dataclasses.call_method1("fields", (value,))?)
+    } else {
+        None
+    };
+    if let Some(attributes) = attributes {
+        let dict = PyDict::new(py);
+        for attribute in attributes.try_iter()? {
+            let name = attribute?.getattr("name")?;
+            let item = value.getattr(name.downcast::<PyString>()?)?;
+            dict.set_item(name, plain_value(&item)?)?;
+        }
+        return Ok(dict.into_any());
+    }
+    if let Ok(dict) = value.downcast::<PyDict>() {
+        let plain = PyDict::new(py);
+        for (key, item) in dict.iter() {
+            plain.set_item(key, plain_value(&item)?)?;
+        }
+        return Ok(plain.into_any());
+    }
+    if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+        let items = value
+            .try_iter()?
+            .map(|item| plain_value(&item?))
+            .collect::<PyResult<Vec<_>>>()?;
+        return Ok(PyList::new(py, items)?.into_any());
+    }
+    Ok(value.clone())
+}
+
 fn extract_value_single_or_list(any: &Bound<PyAny>) -> PyResult<Vec<Value>> {
     if let Ok(values) = any.downcast::<PyList>() {
         values.iter().map(|v| extract_value(&v)).collect()
@@ -204,28 +258,28 @@ fn object_to_py(
     py: Python,
     obj: &BTreeMap<String, Value>,
 ) -> PyResult<PyObject> {
-    let dict = PyDict::new_bound(py);
+    let dict = PyDict::new(py);
     for (k, v) in obj.iter() {
         dict.set_item(k, value_to_py(py, v)?)?;
     }
     Ok(dict.into())
 }
 
-fn value_to_py(py: Python, value: &Value) -> PyResult<PyObject> {
+pub(crate) fn value_to_py(py: Python, value: &Value) -> PyResult<PyObject> {
     Ok(match value {
         Value::Null => py.None(),
-        Value::Str(text) => text.into_py(py),
-        Value::U64(num) => (*num).into_py(py),
-        Value::I64(num) => (*num).into_py(py),
-        Value::F64(num) => (*num).into_py(py),
-        Value::Bytes(b) => b.to_object(py),
+        Value::Str(text) => text.into_py_any(py)?,
+        Value::U64(num) => (*num).into_py_any(py)?,
+        Value::I64(num) => (*num).into_py_any(py)?,
+        Value::F64(num) => (*num).into_py_any(py)?,
+        Value::Bytes(b) => Bytes::new(b.clone()).into_py_any(py)?,
         Value::PreTokStr(_pretoken) => {
             // TODO implement me
             unimplemented!();
         }
         Value::Date(d) => {
             let utc = d.into_utc();
-            PyDateTime::new_bound(
+            PyDateTime::new(
                 py,
                 utc.year(),
                 utc.month() as u8,
@@ -236,11 +290,11 @@ fn value_to_py(py: Python, value: &Value) -> PyResult<PyObject> {
                 utc.microsecond(),
                 None,
             )?
-            .into_py(py)
+            .into_py_any(py)?
         }
-        Value::Facet(f) => Facet { inner: f.clone() }.into_py(py),
+        Value::Facet(f) => Facet { inner: f.clone() }.into_py_any(py)?,
         Value::Array(arr) => {
-            let mut list = PyList::empty_bound(py);
+            let list = PyList::empty(py);
             // Because `value_to_py` can return an error, we need to be able
             // to handle those errors on demand. Also, we want to avoid
             // collecting all the values into an intermediate `Vec` before
@@ -253,11 +307,44 @@ fn value_to_py(py: Python, value: &Value) -> PyResult<PyObject> {
             list.into()
         }
         Value::Object(obj) => object_to_py(py, obj)?,
-        Value::Bool(b) => b.into_py(py),
-        Value::IpAddr(i) => (*i).to_string().into_py(py),
+        Value::Bool(b) => b.into_py_any(py)?,
+        Value::IpAddr(i) => (*i).to_string().into_py_any(py)?,
     })
 }
 
+/// Convert a value to Python, JSON objects being serialized to UTF-8 JSON
+/// bytes instead of converted to dicts if `raw_json` is set.
+fn field_value_to_py(
+    py: Python,
+    value: &Value,
+    raw_json: bool,
+) -> PyResult<PyObject> {
+    match value {
+        Value::Object(json_object) if raw_json => {
+            let json = serde_json::to_vec(json_object).map_err(to_pyerr)?;
+            PyBytes::new(py, &json).into_py_any(py)
+        }
+        _ => value_to_py(py, value),
+    }
+}
+
+/// The name of the type of a value, for error messages.
+fn value_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Str(_) | Value::PreTokStr(_) => "text",
+        Value::U64(_) | Value::I64(_) => "integer",
+        Value::F64(_) => "float",
+        Value::Bool(_) => "boolean",
+        Value::Date(_) => "date",
+        Value::Facet(_) => "facet",
+        Value::Bytes(_) => "bytes",
+        Value::Array(_) => "list",
+        Value::Object(_) => "JSON",
+        Value::IpAddr(_) => "IP address",
+    }
+}
+
 fn value_to_string(value: &Value) -> String {
     match value {
         Value::Null => format!("{:?}", value),
@@ -564,6 +651,7 @@ impl Document {
         Ok(document)
     }
 
+    #[pyo3(signature = (py_dict, schema = None))]
     fn extend(
         &mut self,
         py_dict: &Bound<PyDict>,
@@ -576,6 +664,7 @@ impl Document {
         )
     }
 
+    #[pyo3(signature = (py_dict, schema = None))]
     #[staticmethod]
     fn from_dict(
         py_dict: &Bound<PyDict>,
@@ -590,6 +679,37 @@ impl Document {
         Ok(Document { field_values })
     }
 
+    /// Create a document from the attributes of an application object.
+    ///
+    /// The attributes named like the fields of the schema are read, which
+    /// works for dataclasses, attrs classes, pydantic models and plain
+    /// objects alike. Nested dataclasses, attrs classes and pydantic models
+    /// are converted to dicts, so that they can be indexed in JSON fields.
+    /// Missing attributes and None values are skipped.
+    ///
+    /// Args:
+    ///     obj (Any): The object to read the field values from.
+    ///     schema (Schema): The schema giving the names and types of the
+    ///         fields.
+    ///
+    /// Raises a ValueError if an attribute doesn't match the type of its
+    /// field.
+    #[staticmethod]
+    fn from_object(obj: &Bound<PyAny>, schema: &Schema) -> PyResult<Document> {
+        let py_dict = PyDict::new(obj.py());
+        for (_, field_entry) in schema.inner.fields() {
+            let name = field_entry.name();
+            if !obj.hasattr(name)? {
+                continue;
+            }
+            let value = obj.getattr(name)?;
+            if !value.is_none() {
+                py_dict.set_item(name, plain_value(&value)?)?;
+            }
+        }
+        Document::from_dict(&py_dict, Some(schema))
+    }
+
     /// Returns a dictionary with the different
     /// field values.
     ///
@@ -598,12 +718,20 @@ impl Document {
     ///
     /// For this reason, the dictionary, will associate
     /// a list of value for every field.
-    fn to_dict(&self, py: Python) -> PyResult<PyObject> {
-        let dict = PyDict::new_bound(py);
+    ///
+    /// The values of JSON fields are nested dicts and lists.
+    ///
+    /// Args:
+    ///     raw_json (bool, optional): Return the values of JSON fields as
+    ///         UTF-8 JSON bytes instead, e.g. to pass them on without
+    ///         converting them. Defaults to false.
+    #[pyo3(signature = (raw_json = false))]
+    fn to_dict(&self, py: Python, raw_json: bool) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
         for (key, values) in &self.field_values {
             let values_py: Vec<PyObject> = values
                 .iter()
-                .map(|v| value_to_py(py, v))
+                .map(|v| field_value_to_py(py, v, raw_json))
                 .collect::<PyResult<_>>()?;
             dict.set_item(key, values_py)?;
         }
@@ -681,18 +809,65 @@ impl Document {
     /// Add a facet value to the document.
     /// Args:
     ///     field_name (str): The field name for which we are adding the facet.
-    ///     value (Facet): The Facet that will be added to the document.
-    fn add_facet(&mut self, field_name: String, facet: &Facet) {
-        self.add_value(field_name, facet.inner.clone());
+    ///     value (Facet): The Facet that will be added to the document, or
+    ///         its string representation, e.g. `electronics/phones`, or the
+    ///         list of its segments, e.g. `["electronics", "phones"]`.
+    ///
+    /// Raises a ValueError naming the offending segment if the facet is
+    /// invalid.
+    fn add_facet(
+        &mut self,
+        field_name: String,
+        facet: &Bound<PyAny>,
+    ) -> PyResult<()> {
+        self.add_value(field_name, extract_facet(facet)?);
+        Ok(())
     }
 
     /// Add a bytes value to the document.
     ///
     /// Args:
     ///     field_name (str): The field for which we are adding the bytes.
-    ///     value (bytes): The bytes that will be added to the document.
-    fn add_bytes(&mut self, field_name: String, bytes: Vec<u8>) {
-        self.add_value(field_name, bytes);
+    ///     value (bytes): The bytes that will be added to the document, any
+    ///         bytes-like object is accepted.
+    fn add_bytes(
+        &mut self,
+        field_name: String,
+        bytes: &Bound<PyAny>,
+    ) -> PyResult<()> {
+        self.add_value(field_name, extract_bytes(bytes)?);
+        Ok(())
+    }
+
+    /// Add a dense vector to the document.
+    ///
+    /// Args:
+    ///     field_name (str): The vector field for which we are adding the
+    ///         vector.
+    ///     vector (List[float]): The vector that will be added to the
+    ///         document. Its length must match the dimension of the field.
+    fn add_vector(&mut self, field_name: String, vector: Vec<f32>) {
+        self.add_value(field_name, crate::vector::encode(&vector));
+    }
+
+    /// Add a geo point to the document.
+    ///
+    /// Args:
+    ///     field_name (str): The geo point field for which we are adding the
+    ///         point.
+    ///     lat (float): The latitude of the point, in degrees.
+    ///     lon (float): The longitude of the point, in degrees.
+    ///
+    /// Raises a ValueError if the coordinates are out of range.
+    fn add_geopoint(
+        &mut self,
+        field_name: String,
+        lat: f64,
+        lon: f64,
+    ) -> PyResult<()> {
+        let point = GeoPoint::new(lat, lon)?;
+        self.add_value(field_name, point.encode());
+        Ok(())
     }
 
     /// Add a JSON value to the document.
@@ -715,9 +890,7 @@ impl Document {
                 serde_json::from_str(json_str).map_err(to_pyerr)?;
             self.add_value(field_name, json_map);
             Ok(())
-        } else if let Ok(json_map) =
-            pythonize::depythonize_bound::<JsonMap>(value.clone())
-        {
+        } else if let Ok(json_map) = pythonize::depythonize::<JsonMap>(value) {
             self.add_value(field_name, json_map);
             Ok(())
         } else {
@@ -760,16 +933,20 @@ impl Document {
     ///
     /// Args:
     ///     field (Field): The field for which we would like to get the value.
+    ///     raw_json (bool, optional): Return the value of a JSON field as
+    ///         UTF-8 JSON bytes, see `to_dict`. Defaults to false.
     ///
     /// Returns the value if one is found, otherwise None.
     /// The type of the value depends on the field.
+    #[pyo3(signature = (fieldname, raw_json = false))]
     fn get_first(
         &self,
         py: Python,
         fieldname: &str,
+        raw_json: bool,
     ) -> PyResult<Option<PyObject>> {
         if let Some(value) = self.iter_values_for_field(fieldname).next() {
-            let py_value = value_to_py(py, value)?;
+            let py_value = field_value_to_py(py, value, raw_json)?;
             Ok(Some(py_value))
         } else {
             Ok(None)
@@ -780,18 +957,238 @@ impl Document {
     ///
     /// Args:
     ///     field (Field): The field for which we would like to get the values.
+    ///     raw_json (bool, optional): Return the values of a JSON field as
+    ///         UTF-8 JSON bytes, see `to_dict`. Defaults to false.
     ///
     /// Returns a list of values.
     /// The type of the value depends on the field.
-    fn get_all(&self, py: Python, field_name: &str) -> PyResult<Vec<PyObject>> {
+    #[pyo3(signature = (field_name, raw_json = false))]
+    fn get_all(
+        &self,
+        py: Python,
+        field_name: &str,
+        raw_json: bool,
+    ) -> PyResult<Vec<PyObject>> {
         self.iter_values_for_field(field_name)
-            .map(|value| value_to_py(py, value))
+            .map(|value| field_value_to_py(py, value, raw_json))
             .collect::<PyResult<Vec<_>>>()
     }
 
+    /// Get the first value of a text field.
+    ///
+    /// Args:
+    ///     field_name (str): The field.
+    ///     default (optional): Returned if the document has no value for the
+    ///         field. Defaults to None.
+    ///
+    /// Returns a str. Raises a TypeError if the value isn't a text.
+    #[pyo3(signature = (field_name, default = None))]
+    fn get_text(
+        &self,
+        py: Python,
+        field_name: &str,
+        default: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        self.get_typed(py, field_name, default, "text", |value| match value {
+            Value::Str(text) => Some(text.into_py_any(py)),
+            Value::PreTokStr(pretokenized) => {
+                Some(pretokenized.text.clone().into_py_any(py))
+            }
+            _ => None,
+        })
+    }
+
+    /// Get the first value of an integer field, signed or unsigned.
+    ///
+    /// Args:
+    ///     field_name (str): The field.
+    ///     default (optional): Returned if the document has no value for the
+    ///         field. Defaults to None.
+    ///
+    /// Returns an int. Raises a TypeError if the value isn't an integer.
+    #[pyo3(signature = (field_name, default = None))]
+    fn get_int(
+        &self,
+        py: Python,
+        field_name: &str,
+        default: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        self.get_typed(
+            py,
+            field_name,
+            default,
+            "integer",
+            |value| match value {
+                Value::I64(num) => Some(num.into_py_any(py)),
+                Value::U64(num) => Some(num.into_py_any(py)),
+                _ => None,
+            },
+        )
+    }
+
+    /// Get the first value of a float field. The values of integer fields
+    /// are converted.
+    ///
+    /// Args:
+    ///     field_name (str): The field.
+    ///     default (optional): Returned if the document has no value for the
+    ///         field. Defaults to None.
+    ///
+    /// Returns a float. Raises a TypeError if the value isn't a number.
+    #[pyo3(signature = (field_name, default = None))]
+    fn get_float(
+        &self,
+        py: Python,
+        field_name: &str,
+        default: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        self.get_typed(py, field_name, default, "float", |value| match value {
+            Value::F64(num) => Some(num.into_py_any(py)),
+            Value::I64(num) => Some((*num as f64).into_py_any(py)),
+            Value::U64(num) => Some((*num as f64).into_py_any(py)),
+            _ => None,
+        })
+    }
+
+    /// Get the first value of a date field.
+    ///
+    /// Args:
+    ///     field_name (str): The field.
+    ///     default (optional): Returned if the document has no value for the
+    ///         field. Defaults to None.
+    ///
+    /// Returns a naive datetime in UTC. Raises a TypeError if the value isn't
+    /// a date.
+    #[pyo3(signature = (field_name, default = None))]
+    fn get_datetime(
+        &self,
+        py: Python,
+        field_name: &str,
+        default: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        self.get_typed(py, field_name, default, "date", |value| match value {
+            Value::Date(_) => Some(value_to_py(py, value)),
+            _ => None,
+        })
+    }
+
+    /// Get the first value of a boolean field.
+    ///
+    /// Args:
+    ///     field_name (str): The field.
+    ///     default (optional): Returned if the document has no value for the
+    ///         field. Defaults to None.
+    ///
+    /// Returns a bool. Raises a TypeError if the value isn't a boolean.
+    #[pyo3(signature = (field_name, default = None))]
+    fn get_bool(
+        &self,
+        py: Python,
+        field_name: &str,
+        default: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        self.get_typed(
+            py,
+            field_name,
+            default,
+            "boolean",
+            |value| match value {
+                Value::Bool(b) => Some(b.into_py_any(py)),
+                _ => None,
+            },
+        )
+    }
+
+    /// Get the first value of a bytes field.
+    ///
+    /// Args:
+    ///     field_name (str): The field.
+    ///     default (optional): Returned if the document has no value for the
+    ///         field. Defaults to None.
+    ///
+    /// Returns the bytes, as `get_first` does. Raises a TypeError if the
+    /// value isn't bytes.
+    #[pyo3(signature = (field_name, default = None))]
+    fn get_bytes(
+        &self,
+        py: Python,
+        field_name: &str,
+        default: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        self.get_typed(py, field_name, default, "bytes", |value| match value {
+            Value::Bytes(_) => Some(value_to_py(py, value)),
+            _ => None,
+        })
+    }
+
+    /// Get the first value of a JSON field.
+    ///
+    /// Args:
+    ///     field_name (str): The field.
+    ///     default (optional): Returned if the document has no value for the
+    ///         field. Defaults to None.
+    ///
+    /// Returns a dict. Raises a TypeError if the value isn't a JSON object.
+    #[pyo3(signature = (field_name, default = None))]
+    fn get_json(
+        &self,
+        py: Python,
+        field_name: &str,
+        default: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        self.get_typed(py, field_name, default, "JSON", |value| match value {
+            Value::Object(_) => Some(value_to_py(py, value)),
+            _ => None,
+        })
+    }
+
+    /// Compare the fields of this document with the ones of another
+    /// document, e.g. the same document in an old and a new index.
+    ///
+    /// Args:
+    ///     other (Document): The document compared to this one, the newer.
+    ///
+    /// Returns a dict with three keys, each a dict keyed by field name:
+    ///
+    /// - `added`: the values of the fields only the other document has.
+    /// - `removed`: the values of the fields only this document has.
+    /// - `changed`: `(old_values, new_values)` tuples of the fields whose
+    ///   values differ, the order of the values included.
+    ///
+    /// All three are empty if the documents are equal.
+    fn diff(&self, py: Python, other: &Document) -> PyResult<Py<PyDict>> {
+        let values_to_py = |values: &Vec<Value>| -> PyResult<Vec<PyObject>> {
+            values.iter().map(|value| value_to_py(py, value)).collect()
+        };
+        let added = PyDict::new(py);
+        let removed = PyDict::new(py);
+        let changed = PyDict::new(py);
+        for (field_name, values) in &self.field_values {
+            match other.field_values.get(field_name) {
+                None => removed.set_item(field_name, values_to_py(values)?)?,
+                Some(other_values) if other_values != values => changed
+                    .set_item(
+                        field_name,
+                        (values_to_py(values)?, values_to_py(other_values)?),
+                    )?,
+                Some(_) => {}
+            }
+        }
+        for (field_name, values) in &other.field_values {
+            if !self.field_values.contains_key(field_name) {
+                added.set_item(field_name, values_to_py(values)?)?;
+            }
+        }
+        let diff = PyDict::new(py);
+        diff.set_item("added", added)?;
+        diff.set_item("removed", removed)?;
+        diff.set_item("changed", changed)?;
+        Ok(diff.unbind())
+    }
+
     fn __getitem__(&self, field_name: &str) -> PyResult<Vec<PyObject>> {
         Python::with_gil(|py| -> PyResult<Vec<PyObject>> {
-            self.get_all(py, field_name)
+            self.get_all(py, field_name, false)
         })
     }
 
@@ -812,17 +1209,17 @@ impl Document {
         other: &Self,
         op: CompareOp,
         py: Python<'_>,
-    ) -> PyObject {
+    ) -> PyResult<PyObject> {
         match op {
-            CompareOp::Eq => (self == other).into_py(py),
-            CompareOp::Ne => (self != other).into_py(py),
-            _ => py.NotImplemented(),
+            CompareOp::Eq => (self == other).into_py_any(py),
+            CompareOp::Ne => (self != other).into_py_any(py),
+            _ => Ok(py.NotImplemented()),
         }
     }
 
     #[staticmethod]
     fn _internal_from_pythonized(serialized: &Bound<PyAny>) -> PyResult<Self> {
-        pythonize::depythonize_bound(serialized.clone()).map_err(to_pyerr)
+        pythonize::depythonize(serialized).map_err(to_pyerr)
     }
 
     fn __reduce__<'a>(
@@ -831,13 +1228,13 @@ impl Document {
     ) -> PyResult<Bound<'a, PyTuple>> {
         let serialized = pythonize::pythonize(py, &*slf).map_err(to_pyerr)?;
 
-        Ok(PyTuple::new_bound(
+        let from_pythonized = slf
+            .into_pyobject(py)?
+            .getattr("_internal_from_pythonized")?;
+        PyTuple::new(
             py,
-            [
-                slf.into_py(py).getattr(py, "_internal_from_pythonized")?,
-                PyTuple::new_bound(py, [serialized]).to_object(py),
-            ],
-        ))
+            [from_pythonized, PyTuple::new(py, [serialized])?.into_any()],
+        )
     }
 }
 
@@ -905,6 +1302,32 @@ impl Document {
         Ok(())
     }
 
+    /// The first value of a field converted by `convert`, or `default` if
+    /// the document has none.
+    ///
+    /// Raises a TypeError if `convert` returns None, the value not being of
+    /// the `expected` type.
+    fn get_typed(
+        &self,
+        py: Python,
+        field_name: &str,
+        default: Option<PyObject>,
+        expected: &str,
+        convert: impl Fn(&Value) -> Option<PyResult<PyObject>>,
+    ) -> PyResult<PyObject> {
+        let Some(value) = self.iter_values_for_field(field_name).next() else {
+            return Ok(default.unwrap_or_else(|| py.None()));
+        };
+        match convert(value) {
+            Some(converted) => converted,
+            None => Err(PyTypeError::new_err(format!(
+                "The value of field `{field_name}` is of type {}, expected \
+                 {expected}.",
+                value_type_name(value)
+            ))),
+        }
+    }
+
     pub fn iter_values_for_field<'a>(
         &'a self,
         field: &str,
@@ -0,0 +1,273 @@
+use std::path::PathBuf;
+
+use pyo3::{
+    create_exception, exceptions::PyValueError, prelude::*, IntoPyObjectExt,
+    PyTypeInfo,
+};
+use tantivy::{
+    self as tv,
+    directory::error::{
+        LockError as TvLockError, OpenDirectoryError, OpenReadError,
+        OpenWriteError,
+    },
+};
+
+use crate::parser_error::QueryParserErrorIntoPy;
+
+create_exception!(
+    tantivy,
+    TantivyError,
+    PyValueError,
+    "Base class of all the errors raised by tantivy."
+);
+create_exception!(
+    tantivy,
+    QueryParserError,
+    TantivyError,
+    "Raised when a query string can't be parsed.\n\n\
+     The `error` attribute holds the matching object from the \
+     `query_parser_error` module and `field` the offending field name, if any."
+);
+create_exception!(
+    tantivy,
+    SchemaError,
+    TantivyError,
+    "Raised when an operation doesn't match the schema of the index.\n\n\
+     The `field` attribute holds the offending field name, if any."
+);
+create_exception!(
+    tantivy,
+    LockError,
+    TantivyError,
+    "Raised when a lock on the index directory can't be acquired.\n\n\
+     The `busy` attribute is true if the lock is held by another writer."
+);
+create_exception!(
+    tantivy,
+    LockAcquisitionError,
+    LockError,
+    "Raised when the index writer lock is held by another writer and couldn't \
+     be acquired in time."
+);
+create_exception!(
+    tantivy,
+    QueryTooComplexError,
+    TantivyError,
+    "Raised when a query goes over a complexity limit of the searcher, see \
+     `Index.set_search_limits`.\n\n\
+     The `limit` attribute holds the name of the limit, e.g. `max_clauses`, \
+     `value` the complexity of the query and `max` the limit."
+);
+create_exception!(
+    tantivy,
+    IndexCorruptedError,
+    TantivyError,
+    "Raised when the index data on disk is corrupted."
+);
+create_exception!(
+    tantivy,
+    IoError,
+    TantivyError,
+    "Raised when reading or writing the index directory fails.\n\n\
+     The `filepath` attribute holds the path of the file involved, if any."
+);
+create_exception!(
+    tantivy,
+    IndexVersionError,
+    TantivyError,
+    "Raised when an index on disk was written with an index format that this \
+     version of tantivy can't read."
+);
+
+/// Hint appended to `IndexVersionError` messages.
+const MIGRATION_HINT: &str = "To migrate, either open the index with a \
+    tantivy-py release that supports its index format and export the \
+    documents, or rebuild the index from the original data with this release.";
+
+pub(crate) fn register(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
+    m.add("TantivyError", py.get_type::<TantivyError>())?;
+    m.add("QueryParserError", py.get_type::<QueryParserError>())?;
+    m.add("SchemaError", py.get_type::<SchemaError>())?;
+    m.add("LockError", py.get_type::<LockError>())?;
+    m.add(
+        "LockAcquisitionError",
+        py.get_type::<LockAcquisitionError>(),
+    )?;
+    m.add(
+        "QueryTooComplexError",
+        py.get_type::<QueryTooComplexError>(),
+    )?;
+    m.add("IndexCorruptedError", py.get_type::<IndexCorruptedError>())?;
+    m.add("IoError", py.get_type::<IoError>())?;
+    m.add("IndexVersionError", py.get_type::<IndexVersionError>())?;
+    Ok(())
+}
+
+/// Create an exception of type `T` and set the given attributes on it.
+fn new_err_with_attrs<T: PyTypeInfo>(
+    py: Python,
+    message: String,
+    attrs: Vec<(&str, PyResult<PyObject>)>,
+) -> PyErr {
+    let err = PyErr::new::<T, _>(message);
+    let value = err.value(py);
+    for (name, attr) in attrs {
+        // Converting an attribute or setting it on a fresh exception instance
+        // only fails when out of memory, raise the exception without it.
+        if let Ok(attr) = attr {
+            let _ = value.setattr(name, attr);
+        }
+    }
+    err
+}
+
+/// Raise a `SchemaError` about the given field.
+pub(crate) fn schema_err(message: String, field: &str) -> PyErr {
+    Python::with_gil(|py| {
+        new_err_with_attrs::<SchemaError>(
+            py,
+            message,
+            vec![("field", field.into_py_any(py))],
+        )
+    })
+}
+
+/// Raise a `QueryTooComplexError` about the given limit.
+pub(crate) fn query_too_complex_err(
+    limit: &str,
+    value: usize,
+    max: usize,
+) -> PyErr {
+    Python::with_gil(|py| {
+        new_err_with_attrs::<QueryTooComplexError>(
+            py,
+            format!(
+                "The query exceeds the {limit} of {max} of the searcher \
+                 ({value})."
+            ),
+            vec![
+                ("limit", limit.into_py_any(py)),
+                ("value", value.into_py_any(py)),
+                ("max", max.into_py_any(py)),
+            ],
+        )
+    })
+}
+
+/// Returns true if the error signals an index format incompatibility.
+pub(crate) fn is_incompatible_index(err: &tv::TantivyError) -> bool {
+    matches!(
+        err,
+        tv::TantivyError::IncompatibleIndex(_)
+            | tv::TantivyError::OpenReadError(
+                OpenReadError::IncompatibleIndex(_)
+            )
+    )
+}
+
+/// Returns `Some` for IO related errors, holding the path of the file
+/// involved if it is known.
+fn io_err_filepath(err: &tv::TantivyError) -> Option<Option<&PathBuf>> {
+    let filepath = match err {
+        tv::TantivyError::OpenReadError(
+            OpenReadError::FileDoesNotExist(filepath)
+            | OpenReadError::IoError { filepath, .. },
+        )
+        | tv::TantivyError::OpenWriteError(
+            OpenWriteError::FileAlreadyExists(filepath)
+            | OpenWriteError::IoError { filepath, .. },
+        )
+        | tv::TantivyError::OpenDirectoryError(
+            OpenDirectoryError::DoesNotExist(filepath)
+            | OpenDirectoryError::NotADirectory(filepath)
+            | OpenDirectoryError::IoError {
+                directory_path: filepath,
+                ..
+            },
+        ) => Some(filepath),
+        tv::TantivyError::IoError(_)
+        | tv::TantivyError::OpenDirectoryError(
+            OpenDirectoryError::FailedToCreateTempDir(_),
+        ) => None,
+        _ => return None,
+    };
+    Some(filepath)
+}
+
+/// Convert a tantivy error into the matching Python exception.
+pub(crate) fn tantivy_err<E: Into<tv::TantivyError>>(err: E) -> PyErr {
+    let err: tv::TantivyError = err.into();
+    let message = err.to_string();
+
+    if let Some(filepath) = io_err_filepath(&err) {
+        return Python::with_gil(|py| {
+            let filepath = filepath
+                .map(|path| path.to_string_lossy().into_owned())
+                .into_py_any(py);
+            new_err_with_attrs::<IoError>(
+                py,
+                message,
+                vec![("filepath", filepath)],
+            )
+        });
+    }
+
+    match err {
+        tv::TantivyError::IncompatibleIndex(incompatibility)
+        | tv::TantivyError::OpenReadError(OpenReadError::IncompatibleIndex(
+            incompatibility,
+        )) => IndexVersionError::new_err(format!(
+            "{incompatibility:?}. {MIGRATION_HINT}"
+        )),
+        tv::TantivyError::LockFailure(TvLockError::LockBusy, _) => {
+            Python::with_gil(|py| {
+                new_err_with_attrs::<LockAcquisitionError>(
+                    py,
+                    message,
+                    vec![("busy", true.into_py_any(py))],
+                )
+            })
+        }
+        tv::TantivyError::LockFailure(TvLockError::IoError(_), _) => {
+            Python::with_gil(|py| {
+                new_err_with_attrs::<LockError>(
+                    py,
+                    message,
+                    vec![("busy", false.into_py_any(py))],
+                )
+            })
+        }
+        tv::TantivyError::DataCorruption(_) => {
+            IndexCorruptedError::new_err(message)
+        }
+        tv::TantivyError::FieldNotFound(field) => schema_err(message, &field),
+        tv::TantivyError::SchemaError(_) => Python::with_gil(|py| {
+            new_err_with_attrs::<SchemaError>(
+                py,
+                message,
+                vec![("field", Ok(py.None()))],
+            )
+        }),
+        _ => TantivyError::new_err(message),
+    }
+}
+
+/// Convert a query parser error into a `QueryParserError`.
+pub(crate) fn query_parser_err(err: tv::query::QueryParserError) -> PyErr {
+    let message = err.to_string();
+    let field = match &err {
+        tv::query::QueryParserError::FieldDoesNotExist(field)
+        | tv::query::QueryParserError::FieldNotIndexed(field)
+        | tv::query::QueryParserError::FieldDoesNotHavePositionsIndexed(
+            field,
+        ) => Some(field.clone()),
+        _ => None,
+    };
+    Python::with_gil(|py| {
+        new_err_with_attrs::<QueryParserError>(
+            py,
+            message,
+            vec![("error", err.into_py(py)), ("field", field.into_py_any(py))],
+        )
+    })
+}
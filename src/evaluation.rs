@@ -0,0 +1,286 @@
+//! Information retrieval metrics of the rankings of a searcher, computed
+//! over sets of relevance judgments.
+
+use std::{collections::HashMap, thread};
+
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyDict};
+use tantivy::{self as tv, collector::TopDocs, schema::OwnedValue};
+
+use crate::{
+    document::extract_value_for_type,
+    errors::{schema_err, tantivy_err},
+    get_field,
+    query::Query,
+    searcher::{fast_field_values, Searcher},
+};
+
+/// The number of hits ranked by the metrics without a cutoff.
+const DEFAULT_CUTOFF: usize = 10;
+
+#[derive(Clone, Copy)]
+enum Metric {
+    Ndcg(usize),
+    Mrr(usize),
+    Recall(usize),
+    Precision(usize),
+}
+
+impl std::str::FromStr for Metric {
+    type Err = PyErr;
+
+    fn from_str(metric: &str) -> PyResult<Self> {
+        let invalid = || {
+            PyValueError::new_err(format!(
+                "Invalid metric `{metric}`, valid choices are: 'ndcg@k', \
+                 'mrr@k', 'recall@k' and 'precision@k', with k a positive \
+                 number defaulting to {DEFAULT_CUTOFF}."
+            ))
+        };
+        let (name, k) = match metric.split_once('@') {
+            Some((name, k)) => {
+                (name, k.parse::<usize>().map_err(|_| invalid())?)
+            }
+            None => (metric, DEFAULT_CUTOFF),
+        };
+        if k == 0 {
+            return Err(invalid());
+        }
+        match name {
+            "ndcg" => Ok(Metric::Ndcg(k)),
+            "mrr" => Ok(Metric::Mrr(k)),
+            "recall" => Ok(Metric::Recall(k)),
+            "precision" => Ok(Metric::Precision(k)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Metric {
+    fn cutoff(self) -> usize {
+        match self {
+            Metric::Ndcg(k)
+            | Metric::Mrr(k)
+            | Metric::Recall(k)
+            | Metric::Precision(k) => k,
+        }
+    }
+
+    /// The metric of a ranking, given the relevance of each hit and the
+    /// relevance of all the judged documents.
+    fn score(self, hits: &[f64], judgments: &[f64]) -> f64 {
+        let is_relevant = |relevance: &&f64| **relevance > 0.0;
+        match self {
+            Metric::Ndcg(k) => {
+                let dcg = |relevances: &mut dyn Iterator<Item = &f64>| {
+                    relevances
+                        .take(k)
+                        .enumerate()
+                        .map(|(i, relevance)| {
+                            (relevance.exp2() - 1.0) / ((i + 2) as f64).log2()
+                        })
+                        .sum::<f64>()
+                };
+                let mut ideal: Vec<f64> = judgments.to_vec();
+                ideal.sort_by(|a, b| b.total_cmp(a));
+                let ideal_dcg = dcg(&mut ideal.iter().filter(is_relevant));
+                if ideal_dcg == 0.0 {
+                    return 0.0;
+                }
+                dcg(&mut hits.iter()) / ideal_dcg
+            }
+            Metric::Mrr(k) => hits
+                .iter()
+                .take(k)
+                .position(|relevance| *relevance > 0.0)
+                .map_or(0.0, |rank| 1.0 / (rank + 1) as f64),
+            Metric::Recall(k) => {
+                let num_relevant = judgments.iter().filter(is_relevant).count();
+                if num_relevant == 0 {
+                    return 0.0;
+                }
+                let found = hits.iter().take(k).filter(is_relevant).count();
+                found as f64 / num_relevant as f64
+            }
+            Metric::Precision(k) => {
+                let found = hits.iter().take(k).filter(is_relevant).count();
+                found as f64 / k as f64
+            }
+        }
+    }
+}
+
+/// The judgments of a query, by the JSON serialization of the document id.
+struct Judged {
+    query: Query,
+    judgments: HashMap<String, f64>,
+}
+
+/// Evaluates the relevance of the rankings of a searcher.
+///
+/// The documents are identified by the value of a fast field, e.g. a
+/// product id, so that the judgments survive reindexing.
+///
+/// Args:
+///     searcher (Searcher): The searcher to evaluate. Its filters apply.
+///     id_field (str): The fast field identifying the documents.
+///
+/// Raises a SchemaError if `id_field` isn't a fast field.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+pub(crate) struct Evaluator {
+    searcher: Searcher,
+    id_field: tv::schema::Field,
+}
+
+impl Evaluator {
+    /// The relevances of the hits of a query, best hit first.
+    fn rank(&self, judged: &Judged, cutoff: usize) -> tv::Result<Vec<f64>> {
+        let searcher = &self.searcher.inner;
+        let field_entry = searcher.schema().get_field_entry(self.id_field);
+        let hits = searcher
+            .search(judged.query.get(), &TopDocs::with_limit(cutoff))?;
+        hits.iter()
+            .map(|(_, address)| {
+                let segment = searcher.segment_reader(address.segment_ord);
+                let ids =
+                    fast_field_values(segment, field_entry, address.doc_id)?;
+                Ok(ids
+                    .first()
+                    .and_then(|id| judged.judgments.get(&id_key(id)))
+                    .copied()
+                    .unwrap_or(0.0))
+            })
+            .collect()
+    }
+}
+
+fn id_key(id: &OwnedValue) -> String {
+    serde_json::to_string(id).unwrap_or_default()
+}
+
+#[pymethods]
+impl Evaluator {
+    #[new]
+    fn new(searcher: Searcher, id_field: &str) -> PyResult<Self> {
+        let schema = searcher.inner.schema();
+        let field = get_field(schema, id_field)?;
+        let field_entry = schema.get_field_entry(field);
+        let value_type = field_entry.field_type().value_type();
+        if !field_entry.is_fast() || value_type == tv::schema::Type::Json {
+            return Err(schema_err(
+                format!(
+                    "Field `{id_field}` must be a fast field to identify the \
+                     documents."
+                ),
+                id_field,
+            ));
+        }
+        Ok(Evaluator {
+            searcher,
+            id_field: field,
+        })
+    }
+
+    /// Compute a metric over queries with relevance judgments.
+    ///
+    /// The queries are searched in parallel, without the GIL.
+    ///
+    /// Args:
+    ///     queries_with_judgments (List[Tuple[Query, Dict[Any, float]]]):
+    ///         The queries, each with the relevance of some documents, by
+    ///         the value of their id field. Documents with a relevance of 0
+    ///         or without judgment are not relevant.
+    ///     metric (str, optional): 'ndcg@k' for the normalized discounted
+    ///         cumulative gain of the first k hits, 'mrr@k' for the
+    ///         reciprocal rank of the first relevant hit within the first k,
+    ///         'recall@k' for the fraction of the relevant documents found
+    ///         in the first k hits, or 'precision@k' for the fraction of the
+    ///         first k hits which are relevant. k defaults to 10. Defaults
+    ///         to 'ndcg@10'.
+    ///
+    /// Returns a dict holding the mean of the metric over the queries in
+    /// `mean`, and the metric of each query in `per_query`.
+    ///
+    /// Raises a ValueError if the metric is invalid or if a document id
+    /// doesn't match the type of the id field.
+    #[pyo3(signature = (queries_with_judgments, metric = "ndcg@10"))]
+    fn evaluate(
+        &self,
+        py: Python,
+        queries_with_judgments: Vec<(Query, Bound<PyDict>)>,
+        metric: &str,
+    ) -> PyResult<Py<PyDict>> {
+        let parsed: Metric = metric.parse()?;
+        let field_entry =
+            self.searcher.inner.schema().get_field_entry(self.id_field);
+        let value_type = field_entry.field_type().value_type();
+        let judged = queries_with_judgments
+            .into_iter()
+            .map(|(query, judgments)| {
+                let judgments = judgments
+                    .iter()
+                    .map(|(id, relevance)| {
+                        let id = extract_value_for_type(
+                            &id,
+                            value_type,
+                            field_entry.name(),
+                        )?;
+                        Ok((id_key(&id), relevance.extract::<f64>()?))
+                    })
+                    .collect::<PyResult<_>>()?;
+                Ok(Judged {
+                    query: self.searcher.restrict(py, &query)?,
+                    judgments,
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let per_query = py.allow_threads(|| {
+            let num_threads = thread::available_parallelism()
+                .map_or(1, |n| n.get())
+                .min(judged.len().max(1));
+            let chunk_size = judged.len().div_ceil(num_threads).max(1);
+            thread::scope(|scope| {
+                let handles: Vec<_> = judged
+                    .chunks(chunk_size)
+                    .map(|chunk| {
+                        scope.spawn(move || {
+                            chunk
+                                .iter()
+                                .map(|judged| {
+                                    let hits =
+                                        self.rank(judged, parsed.cutoff())?;
+                                    let judgments: Vec<f64> = judged
+                                        .judgments
+                                        .values()
+                                        .copied()
+                                        .collect();
+                                    Ok(parsed.score(&hits, &judgments))
+                                })
+                                .collect::<tv::Result<Vec<f64>>>()
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap())
+                    .collect::<tv::Result<Vec<Vec<f64>>>>()
+            })
+        });
+        let per_query: Vec<f64> = per_query
+            .map_err(tantivy_err)?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let mean = if per_query.is_empty() {
+            0.0
+        } else {
+            per_query.iter().sum::<f64>() / per_query.len() as f64
+        };
+        let report = PyDict::new(py);
+        report.set_item("metric", metric)?;
+        report.set_item("mean", mean)?;
+        report.set_item("per_query", per_query)?;
+        Ok(report.unbind())
+    }
+}
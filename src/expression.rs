@@ -0,0 +1,479 @@
+//! Arithmetic expressions scoring the hits of a search from their score, the
+//! values of fast fields and signals, see the `score_expr` argument of
+//! `Searcher.search`.
+
+use std::sync::Arc;
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use tantivy::{
+    self as tv, columnar::Column, schema::Type, DocId, Score, SegmentReader,
+};
+
+use crate::{errors::schema_err, get_field, signals::Signals};
+
+/// The variable holding the score of the query.
+const SCORE_VARIABLE: &str = "_score";
+
+#[derive(Clone, Copy, Debug)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Function {
+    Abs,
+    Exp,
+    Log,
+    Log1p,
+    Log10,
+    Sqrt,
+    Pow,
+    Min,
+    Max,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "abs" => Function::Abs,
+            "exp" => Function::Exp,
+            "log" => Function::Log,
+            "log1p" => Function::Log1p,
+            "log10" => Function::Log10,
+            "sqrt" => Function::Sqrt,
+            "pow" => Function::Pow,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            _ => return None,
+        })
+    }
+
+    /// Whether the function takes `num_args` arguments.
+    fn accepts(self, num_args: usize) -> bool {
+        match self {
+            Function::Pow => num_args == 2,
+            Function::Min | Function::Max => num_args >= 2,
+            _ => num_args == 1,
+        }
+    }
+
+    fn apply(self, args: &[f64]) -> f64 {
+        match self {
+            Function::Abs => args[0].abs(),
+            Function::Exp => args[0].exp(),
+            Function::Log => args[0].ln(),
+            Function::Log1p => args[0].ln_1p(),
+            Function::Log10 => args[0].log10(),
+            Function::Sqrt => args[0].sqrt(),
+            Function::Pow => args[0].powf(args[1]),
+            Function::Min => args.iter().copied().fold(f64::INFINITY, f64::min),
+            Function::Max => {
+                args.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    Number(f64),
+    Score,
+    /// The value of the variable at this index of the variables of the
+    /// expression, its fields then its signals.
+    Field(usize),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, score: f64, values: &[f64]) -> f64 {
+        match self {
+            Expr::Number(number) => *number,
+            Expr::Score => score,
+            Expr::Field(index) => values[*index],
+            Expr::Neg(expr) => -expr.eval(score, values),
+            Expr::Binary(op, left, right) => {
+                let left = left.eval(score, values);
+                let right = right.eval(score, values);
+                match op {
+                    BinaryOp::Add => left + right,
+                    BinaryOp::Sub => left - right,
+                    BinaryOp::Mul => left * right,
+                    BinaryOp::Div => left / right,
+                    BinaryOp::Pow => left.powf(right),
+                }
+            }
+            Expr::Call(function, args) => {
+                let args: Vec<f64> =
+                    args.iter().map(|arg| arg.eval(score, values)).collect();
+                function.apply(&args)
+            }
+        }
+    }
+}
+
+/// A recursive descent parser of expressions, with the usual precedences:
+///
+///     expr  := term (('+' | '-') term)*
+///     term  := unary (('*' | '/') unary)*
+///     unary := ('-' | '+') unary | power
+///     power := atom ('^' unary)?
+///     atom  := number | variable | function '(' expr (',' expr)* ')'
+///            | '(' expr ')'
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+    variables: &'a [String],
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> PyErr {
+        PyValueError::new_err(format!(
+            "Invalid score_expr `{}` at position {}: {message}.",
+            self.source, self.pos
+        ))
+    }
+
+    /// The next character which isn't a whitespace.
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.source[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+        self.source[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            return true;
+        }
+        false
+    }
+
+    /// The longest prefix of the rest of the source matching `accept`.
+    fn take_while(&mut self, accept: impl Fn(char) -> bool) -> &str {
+        let start = self.pos;
+        let rest = &self.source[start..];
+        let len = rest.find(|c| !accept(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &self.source[start..start + len]
+    }
+
+    fn expr(&mut self) -> PyResult<Expr> {
+        let mut left = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some('+') => BinaryOp::Add,
+                Some('-') => BinaryOp::Sub,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> PyResult<Expr> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some('*') => BinaryOp::Mul,
+                Some('/') => BinaryOp::Div,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> PyResult<Expr> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> PyResult<Expr> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            let exponent = self.unary()?;
+            return Ok(Expr::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> PyResult<Expr> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected `)`"));
+                }
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => self.identifier(),
+            Some(_) => Err(self.error("expected a number, a variable or `(`")),
+            None => Err(self.error("unexpected end of expression")),
+        }
+    }
+
+    fn number(&mut self) -> PyResult<Expr> {
+        let source = self.source;
+        let start = self.pos;
+        self.take_while(|c| c.is_ascii_digit() || c == '.');
+        if source[self.pos..].starts_with(['e', 'E']) {
+            self.pos += 1;
+            if source[self.pos..].starts_with(['+', '-']) {
+                self.pos += 1;
+            }
+            self.take_while(|c| c.is_ascii_digit());
+        }
+        let text = &source[start..self.pos];
+        text.parse().map(Expr::Number).map_err(|_| {
+            self.pos = start;
+            self.error(&format!("invalid number `{text}`"))
+        })
+    }
+
+    fn identifier(&mut self) -> PyResult<Expr> {
+        let start = self.pos;
+        let name = self
+            .take_while(|c| c.is_ascii_alphanumeric() || c == '_')
+            .to_string();
+        if self.eat('(') {
+            let Some(function) = Function::from_name(&name) else {
+                self.pos = start;
+                return Err(self.error(&format!("unknown function `{name}`")));
+            };
+            let mut args = vec![self.expr()?];
+            while self.eat(',') {
+                args.push(self.expr()?);
+            }
+            if !self.eat(')') {
+                return Err(self.error("expected `,` or `)`"));
+            }
+            if !function.accepts(args.len()) {
+                self.pos = start;
+                return Err(self.error(&format!(
+                    "wrong number of arguments for `{name}`"
+                )));
+            }
+            return Ok(Expr::Call(function, args));
+        }
+        if name == SCORE_VARIABLE {
+            return Ok(Expr::Score);
+        }
+        match self.variables.iter().position(|variable| *variable == name) {
+            Some(index) => Ok(Expr::Field(index)),
+            None => {
+                self.pos = start;
+                Err(self.error(&format!(
+                    "unknown variable `{name}`, fields must be listed in \
+                     expr_fields and signals in expr_signals"
+                )))
+            }
+        }
+    }
+}
+
+/// The type of a numeric, boolean or date fast field.
+///
+/// Raises a SchemaError mentioning what the field is `used` for if the field
+/// isn't one.
+pub(crate) fn numeric_fast_field(
+    schema: &tv::schema::Schema,
+    field_name: &str,
+    used: &str,
+) -> PyResult<Type> {
+    let field_entry = schema.get_field_entry(get_field(schema, field_name)?);
+    let value_type = field_entry.field_type().value_type();
+    let is_numeric = matches!(
+        value_type,
+        Type::I64 | Type::U64 | Type::F64 | Type::Bool | Type::Date
+    );
+    if !field_entry.is_fast() || !is_numeric {
+        return Err(schema_err(
+            format!(
+                "Field `{field_name}` must be a numeric, boolean or date fast \
+                 field to be {used}."
+            ),
+            field_name,
+        ));
+    }
+    Ok(value_type)
+}
+
+/// A compiled score expression.
+pub(crate) struct ScoreExpr {
+    expr: Expr,
+    fields: Vec<(String, Type)>,
+    signal_names: Vec<String>,
+    signals: Arc<Signals>,
+}
+
+impl ScoreExpr {
+    /// Compile an expression over the score, some fast fields and some
+    /// signals of the signal store of the index.
+    ///
+    /// Raises a ValueError if the expression is invalid, a signal is named
+    /// like a field or the index has no signals, and a SchemaError if a
+    /// field isn't a numeric, boolean or date fast field.
+    pub(crate) fn compile(
+        schema: &tv::schema::Schema,
+        source: &str,
+        field_names: Vec<String>,
+        signal_names: Vec<String>,
+        signals: &Arc<Signals>,
+    ) -> PyResult<Self> {
+        if !signal_names.is_empty() && signals.key_field().is_none() {
+            return Err(PyValueError::new_err(
+                "The index has no signals, see Index.signals.",
+            ));
+        }
+        if let Some(name) =
+            signal_names.iter().find(|name| field_names.contains(name))
+        {
+            return Err(PyValueError::new_err(format!(
+                "`{name}` is listed both in expr_fields and expr_signals."
+            )));
+        }
+        let fields = field_names
+            .iter()
+            .map(|field_name| {
+                let value_type = numeric_fast_field(
+                    schema,
+                    field_name,
+                    "used in score_expr",
+                )?;
+                Ok((field_name.clone(), value_type))
+            })
+            .collect::<PyResult<_>>()?;
+
+        let variables = [field_names, signal_names.clone()].concat();
+        let mut parser = Parser {
+            source,
+            pos: 0,
+            variables: &variables,
+        };
+        let expr = parser.expr()?;
+        if parser.peek().is_some() {
+            return Err(parser.error("expected an operator"));
+        }
+        Ok(ScoreExpr {
+            expr,
+            fields,
+            signal_names,
+            signals: signals.clone(),
+        })
+    }
+
+    /// The scoring function of the documents of a segment. The missing
+    /// values of a field or signal are 0, and NaN scores rank last.
+    pub(crate) fn segment_scorer(
+        self: &Arc<Self>,
+        segment: &SegmentReader,
+    ) -> impl Fn(DocId, Score) -> Score {
+        let this = self.clone();
+        let columns: Vec<Option<NumericColumn>> = self
+            .fields
+            .iter()
+            .map(|(field_name, value_type)| {
+                NumericColumn::open(segment, field_name, *value_type)
+            })
+            .collect();
+        let signal_columns: Vec<Option<Arc<Vec<f64>>>> = self
+            .signal_names
+            .iter()
+            .map(|name| self.signals.column(segment, name))
+            .collect();
+        move |doc, score| {
+            let values: Vec<f64> = columns
+                .iter()
+                .map(|column| {
+                    column
+                        .as_ref()
+                        .and_then(|column| column.first(doc))
+                        .unwrap_or(0.0)
+                })
+                .chain(signal_columns.iter().map(|column| {
+                    column
+                        .as_ref()
+                        .map(|column| column[doc as usize])
+                        .filter(|value| !value.is_nan())
+                        .unwrap_or(0.0)
+                }))
+                .collect();
+            let score = this.expr.eval(score as f64, &values) as Score;
+            if score.is_nan() {
+                Score::NEG_INFINITY
+            } else {
+                score
+            }
+        }
+    }
+}
+
+/// The column of a fast field, with its values read as `f64`.
+pub(crate) enum NumericColumn {
+    I64(Column<i64>),
+    U64(Column<u64>),
+    F64(Column<f64>),
+    Bool(Column<bool>),
+    /// Dates are read as seconds since the Unix epoch.
+    Date(Column<tv::DateTime>),
+}
+
+impl NumericColumn {
+    /// The column of a field, None if the segment has no value.
+    pub(crate) fn open(
+        segment: &SegmentReader,
+        field_name: &str,
+        value_type: Type,
+    ) -> Option<Self> {
+        let fast_fields = segment.fast_fields();
+        match value_type {
+            Type::I64 => {
+                fast_fields.column_opt(field_name).ok()?.map(Self::I64)
+            }
+            Type::U64 => {
+                fast_fields.column_opt(field_name).ok()?.map(Self::U64)
+            }
+            Type::F64 => {
+                fast_fields.column_opt(field_name).ok()?.map(Self::F64)
+            }
+            Type::Bool => {
+                fast_fields.column_opt(field_name).ok()?.map(Self::Bool)
+            }
+            Type::Date => {
+                fast_fields.column_opt(field_name).ok()?.map(Self::Date)
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn first(&self, doc: DocId) -> Option<f64> {
+        match self {
+            NumericColumn::I64(column) => column.first(doc).map(|v| v as f64),
+            NumericColumn::U64(column) => column.first(doc).map(|v| v as f64),
+            NumericColumn::F64(column) => column.first(doc),
+            NumericColumn::Bool(column) => {
+                column.first(doc).map(|v| if v { 1.0 } else { 0.0 })
+            }
+            NumericColumn::Date(column) => column
+                .first(doc)
+                .map(|date| date.into_timestamp_secs() as f64),
+        }
+    }
+}
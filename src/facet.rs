@@ -1,11 +1,98 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops,
+    sync::OnceLock,
+    thread,
+};
+
 use crate::to_pyerr;
 use pyo3::{
     basic::CompareOp,
+    exceptions::{PyTypeError, PyValueError},
     prelude::*,
-    types::{PyTuple, PyType},
+    types::{PyBytes, PyDict, PyList, PyTuple, PyType},
+    IntoPyObjectExt,
 };
 use serde::{Deserialize, Serialize};
-use tantivy::schema;
+use tantivy::{
+    self as tv,
+    collector::{Collector, SegmentCollector},
+    fastfield::FacetReader,
+    query::EnableScoring,
+    schema, DocId, Executor, Score, SegmentOrdinal, SegmentReader,
+};
+
+/// Extract a facet from a `Facet`, from its string representation or from
+/// the list or tuple of its segments.
+pub(crate) fn extract_facet(any: &Bound<PyAny>) -> PyResult<schema::Facet> {
+    if let Ok(facet) = any.downcast::<Facet>() {
+        return Ok(facet.get().inner.clone());
+    }
+    if let Ok(facet_string) = any.extract::<&str>() {
+        return parse_facet(facet_string);
+    }
+    if any.is_instance_of::<PyList>() || any.is_instance_of::<PyTuple>() {
+        return facet_from_segments(any);
+    }
+    Err(PyTypeError::new_err(format!(
+        "Expected a Facet, a string or a list of segments, got {}.",
+        any.get_type().name()?
+    )))
+}
+
+/// Parse the string representation of a facet, the leading slash being
+/// optional and a trailing one ignored.
+fn parse_facet(facet_string: &str) -> PyResult<schema::Facet> {
+    if facet_string.contains('\0') {
+        return Err(PyValueError::new_err(format!(
+            "Invalid facet {facet_string:?}: facets can't contain a null \
+             character."
+        )));
+    }
+    let trimmed = facet_string.strip_suffix('/').unwrap_or(facet_string);
+    let facet = if trimmed.starts_with('/') {
+        schema::Facet::from_text(trimmed)
+    } else {
+        schema::Facet::from_text(&format!("/{trimmed}"))
+    }
+    .map_err(to_pyerr)?;
+    if facet.is_root() {
+        return Ok(facet);
+    }
+    if let Some(position) = facet.to_path().iter().position(|s| s.is_empty()) {
+        return Err(PyValueError::new_err(format!(
+            "Invalid facet {facet_string:?}: segment {position} is empty."
+        )));
+    }
+    Ok(facet)
+}
+
+/// Build a facet from the list or tuple of its segments.
+fn facet_from_segments(segments: &Bound<PyAny>) -> PyResult<schema::Facet> {
+    let mut path = Vec::new();
+    for (position, segment) in segments.try_iter()?.enumerate() {
+        let segment = segment?;
+        let Ok(segment) = segment.extract::<String>() else {
+            return Err(PyTypeError::new_err(format!(
+                "Invalid facet segment {position}: expected a string, got {}.",
+                segment.get_type().name()?
+            )));
+        };
+        if segment.is_empty() {
+            return Err(PyValueError::new_err(format!(
+                "Invalid facet segment {position}: segments can't be empty."
+            )));
+        }
+        if segment.contains('\0') {
+            return Err(PyValueError::new_err(format!(
+                "Invalid facet segment {position} ({segment:?}): segments \
+                 can't contain a null character."
+            )));
+        }
+        path.push(segment);
+    }
+    Ok(schema::Facet::from_path(path))
+}
 
 /// A Facet represent a point in a given hierarchy.
 ///
@@ -59,11 +146,38 @@ impl Facet {
     ///     facet_string (str): The string that contains a facet.
     ///
     /// Returns the created Facet.
+    ///
+    /// The leading slash may be omitted and a trailing slash is ignored, so
+    /// that `electronics/phones/` is the facet `/electronics/phones`.
+    ///
+    /// Raises a ValueError if a segment of the facet is empty.
     #[classmethod]
-    fn from_string(_cls: &Bound<PyType>, facet_string: &str) -> Facet {
-        Facet {
-            inner: schema::Facet::from(facet_string),
-        }
+    fn from_string(
+        _cls: &Bound<PyType>,
+        facet_string: &str,
+    ) -> PyResult<Facet> {
+        Ok(Facet {
+            inner: parse_facet(facet_string)?,
+        })
+    }
+
+    /// Create a Facet object from its segments.
+    /// Args:
+    ///     segments (List[str]): The segments of the facet, e.g.
+    ///         `["electronics", "phones"]` for `/electronics/phones`. They
+    ///         are taken as is, a slash is part of its segment.
+    ///
+    /// Returns the created Facet.
+    ///
+    /// Raises a ValueError if a segment is empty.
+    #[classmethod]
+    fn from_path(
+        _cls: &Bound<PyType>,
+        segments: &Bound<PyAny>,
+    ) -> PyResult<Facet> {
+        Ok(Facet {
+            inner: facet_from_segments(segments)?,
+        })
     }
 
     /// Returns the list of `segments` that forms a facet path.
@@ -87,11 +201,11 @@ impl Facet {
         other: &Self,
         op: CompareOp,
         py: Python<'_>,
-    ) -> PyObject {
+    ) -> PyResult<PyObject> {
         match op {
-            CompareOp::Eq => (self == other).into_py(py),
-            CompareOp::Ne => (self != other).into_py(py),
-            _ => py.NotImplemented(),
+            CompareOp::Eq => (self == other).into_py_any(py),
+            CompareOp::Ne => (self != other).into_py_any(py),
+            _ => Ok(py.NotImplemented()),
         }
     }
 
@@ -99,13 +213,351 @@ impl Facet {
         slf: PyRef<'a, Self>,
         py: Python<'a>,
     ) -> PyResult<Bound<'a, PyTuple>> {
-        let encoded_bytes = slf.inner.encoded_str().as_bytes().to_vec();
-        Ok(PyTuple::new_bound(
+        let encoded_bytes =
+            PyBytes::new(py, slf.inner.encoded_str().as_bytes());
+        let from_encoded = slf.into_pyobject(py)?.getattr("from_encoded")?;
+        PyTuple::new(
             py,
-            [
-                slf.into_py(py).getattr(py, "from_encoded")?,
-                PyTuple::new_bound(py, [encoded_bytes]).to_object(py),
-            ],
-        ))
+            [from_encoded, PyTuple::new(py, [encoded_bytes])?.into_any()],
+        )
+    }
+}
+
+/// The thread pool counting the facets of the segments in parallel, one
+/// thread per core, started by the first faceted search.
+fn facet_executor() -> &'static Executor {
+    static EXECUTOR: OnceLock<Executor> = OnceLock::new();
+    EXECUTOR.get_or_init(|| {
+        let num_threads =
+            thread::available_parallelism().map_or(1, |threads| threads.get());
+        Executor::multi_thread(num_threads, "tantivy-py-facets-")
+            .unwrap_or_else(|_| Executor::single_thread())
+    })
+}
+
+/// Search with a collector counting facets, the segments in parallel.
+///
+/// Walking the facets of every matching document costs much more than
+/// scoring it, so the faceted searches don't run on the calling thread
+/// alone like the other ones.
+pub(crate) fn search_in_parallel<C: Collector>(
+    searcher: &tv::Searcher,
+    query: &dyn tv::query::Query,
+    collector: &C,
+) -> tv::Result<C::Fruit> {
+    let enable_scoring = if collector.requires_scoring() {
+        EnableScoring::enabled_from_searcher(searcher)
+    } else {
+        EnableScoring::disabled_from_searcher(searcher)
+    };
+    searcher.search_with_executor(
+        query,
+        collector,
+        facet_executor(),
+        enable_scoring,
+    )
+}
+
+/// Counts the documents of all the facets below some facets, at any depth.
+///
+/// Tantivy's `FacetCollector` only counts the children of its facets. Here
+/// a document is counted once in each of its facets and their ancestors.
+pub(crate) struct FacetTreeCollector {
+    pub(crate) field_name: String,
+    pub(crate) facets: Vec<schema::Facet>,
+}
+
+pub(crate) struct FacetTreeSegmentCollector {
+    reader: FacetReader,
+    /// The counted facets, the ones below `facets`.
+    nodes: Vec<schema::Facet>,
+    /// The indexes in `nodes` of each facet of the segment and its
+    /// ancestors, by facet ordinal.
+    ord_nodes: Vec<Vec<usize>>,
+    counts: Vec<u64>,
+    /// The nodes of the collected document.
+    doc_nodes: Vec<usize>,
+}
+
+/// The separator of the segments of an encoded facet.
+const FACET_SEP_BYTE: u8 = 0;
+
+/// The lengths of the encodings of the ancestors of an encoded facet,
+/// itself included and the root excluded.
+fn ancestor_ends(encoded: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    let separators = encoded
+        .iter()
+        .enumerate()
+        .filter(|&(_, &byte)| byte == FACET_SEP_BYTE)
+        .map(|(end, _)| end);
+    let own_end = (!encoded.is_empty()).then_some(encoded.len());
+    separators.chain(own_end)
+}
+
+/// Whether an encoded facet is a strict prefix of another one, as
+/// `Facet.is_prefix_of`.
+fn is_prefix(facet: &[u8], other: &[u8]) -> bool {
+    other.len() > facet.len()
+        && (facet.is_empty()
+            || (other.starts_with(facet)
+                && other[facet.len()] == FACET_SEP_BYTE))
+}
+
+impl Collector for FacetTreeCollector {
+    type Fruit = BTreeMap<schema::Facet, u64>;
+    type Child = FacetTreeSegmentCollector;
+
+    fn for_segment(
+        &self,
+        _segment_ord: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tv::Result<Self::Child> {
+        let reader = segment.facet_reader(&self.field_name)?;
+        let counted: Vec<&[u8]> = self
+            .facets
+            .iter()
+            .map(|facet| facet.encoded_str().as_bytes())
+            .collect();
+        let mut nodes = Vec::new();
+        let mut node_ids: HashMap<Vec<u8>, usize> = HashMap::new();
+        let mut ord_nodes = Vec::with_capacity(reader.num_facets());
+        // The facets are streamed in ordinal order, looking them up one by
+        // one would decode a block of the dictionary for each of them.
+        let mut stream = reader.facet_dict().stream()?;
+        while stream.advance() {
+            let encoded = stream.key();
+            let mut ids = Vec::new();
+            for end in ancestor_ends(encoded) {
+                let ancestor = &encoded[..end];
+                if !counted.iter().any(|facet| is_prefix(facet, ancestor)) {
+                    continue;
+                }
+                let id = match node_ids.get(ancestor) {
+                    Some(&id) => id,
+                    None => {
+                        let facet =
+                            schema::Facet::from_encoded(ancestor.to_vec())
+                                .map_err(|error| {
+                                    tv::TantivyError::InvalidArgument(
+                                        error.to_string(),
+                                    )
+                                })?;
+                        nodes.push(facet);
+                        node_ids.insert(ancestor.to_vec(), nodes.len() - 1);
+                        nodes.len() - 1
+                    }
+                };
+                ids.push(id);
+            }
+            ord_nodes.push(ids);
+        }
+        Ok(FacetTreeSegmentCollector {
+            reader,
+            counts: vec![0; nodes.len()],
+            nodes,
+            ord_nodes,
+            doc_nodes: Vec::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<Self::Fruit>,
+    ) -> tv::Result<Self::Fruit> {
+        let mut counts = BTreeMap::new();
+        for segment_counts in segment_fruits {
+            for (facet, count) in segment_counts {
+                *counts.entry(facet).or_default() += count;
+            }
+        }
+        Ok(counts)
+    }
+}
+
+impl SegmentCollector for FacetTreeSegmentCollector {
+    type Fruit = BTreeMap<schema::Facet, u64>;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        self.doc_nodes.clear();
+        let mut ords = self.reader.facet_ords(doc);
+        let Some(first_ord) = ords.next() else {
+            return;
+        };
+        let Some(second_ord) = ords.next() else {
+            // The ancestors of a single facet are distinct.
+            for &node in &self.ord_nodes[first_ord as usize] {
+                self.counts[node] += 1;
+            }
+            return;
+        };
+        for ord in [first_ord, second_ord].into_iter().chain(ords) {
+            self.doc_nodes.extend(&self.ord_nodes[ord as usize]);
+        }
+        // A document with several facets counts once in their ancestors.
+        self.doc_nodes.sort_unstable();
+        self.doc_nodes.dedup();
+        for &node in &self.doc_nodes {
+            self.counts[node] += 1;
+        }
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        self.nodes
+            .into_iter()
+            .zip(self.counts)
+            .filter(|&(_, count)| count > 0)
+            .collect()
+    }
+}
+
+/// The number of documents of each facet, as counted by
+/// `Searcher.facet_counts`.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+pub(crate) struct FacetCounts {
+    pub(crate) counts: BTreeMap<schema::Facet, u64>,
+}
+
+impl FacetCounts {
+    /// The counted facets below a facet, in facet order.
+    fn descendants<'a>(
+        &'a self,
+        facet: &'a schema::Facet,
+    ) -> impl Iterator<Item = (&'a schema::Facet, u64)> + 'a {
+        // The encoding of the facets sorts the descendants of a facet right
+        // after it.
+        self.counts
+            .range::<schema::Facet, _>((
+                ops::Bound::Excluded(facet),
+                ops::Bound::Unbounded,
+            ))
+            .take_while(|(descendant, _)| facet.is_prefix_of(descendant))
+            .map(|(descendant, &count)| (descendant, count))
+    }
+}
+
+/// A node of the tree built by `FacetCounts.to_tree`.
+#[derive(Default)]
+struct FacetNode {
+    count: Option<u64>,
+    children: BTreeMap<String, FacetNode>,
+}
+
+/// Convert the children of a node to a dict mapping their names to their
+/// `count` and `children`.
+fn children_to_py(
+    py: Python,
+    children: BTreeMap<String, FacetNode>,
+) -> PyResult<Bound<PyDict>> {
+    let dict = PyDict::new(py);
+    for (name, child) in children {
+        let node = PyDict::new(py);
+        node.set_item("count", child.count)?;
+        node.set_item("children", children_to_py(py, child.children)?)?;
+        dict.set_item(name, node)?;
+    }
+    Ok(dict)
+}
+
+#[pymethods]
+impl FacetCounts {
+    /// Merge the facet counts of searches over disjoint sets of documents,
+    /// e.g. over the shards of an index.
+    ///
+    /// Args:
+    ///     counts (List[FacetCounts]): The counts to merge.
+    ///
+    /// Returns a FacetCounts object holding the sum of the counts of each
+    /// facet.
+    #[classmethod]
+    fn merge(_cls: &Bound<PyType>, counts: Vec<PyRef<FacetCounts>>) -> Self {
+        let mut merged = BTreeMap::new();
+        for facet_counts in &counts {
+            for (facet, count) in &facet_counts.counts {
+                *merged.entry(facet.clone()).or_insert(0) += count;
+            }
+        }
+        FacetCounts { counts: merged }
+    }
+
+    /// The counted facets below a facet, with their number of documents.
+    ///
+    /// Args:
+    ///     facet (Facet): The facet whose descendants are returned.
+    ///
+    /// Returns a list of `(Facet, count)` tuples, in facet order.
+    fn get(&self, facet: &Facet) -> Vec<(Facet, u64)> {
+        self.descendants(&facet.inner)
+            .map(|(facet, count)| {
+                (
+                    Facet {
+                        inner: facet.clone(),
+                    },
+                    count,
+                )
+            })
+            .collect()
+    }
+
+    /// The counted facets below a facet with the most documents.
+    ///
+    /// Args:
+    ///     facet (Facet): The facet whose descendants are returned.
+    ///     k (int): The maximum number of facets.
+    ///
+    /// Returns a list of `(Facet, count)` tuples, the most frequent first.
+    fn top_k(&self, facet: &Facet, k: usize) -> Vec<(Facet, u64)> {
+        let mut top = self.get(facet);
+        top.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        top.truncate(k);
+        top
+    }
+
+    /// The counted facets below a facet, as a tree of plain dicts.
+    ///
+    /// Args:
+    ///     root (Facet, optional): The facet at the root of the tree.
+    ///         Defaults to the root facet.
+    ///     depth (int, optional): The maximum depth of the facets below the
+    ///         root. Defaults to no limit.
+    ///
+    /// Returns a dict mapping the name of each child of the root to a dict
+    /// holding its `count`, None if the facet is above the counted ones, and
+    /// its own `children`, in the same format.
+    #[pyo3(signature = (root = None, depth = None))]
+    fn to_tree<'py>(
+        &self,
+        py: Python<'py>,
+        root: Option<&Facet>,
+        depth: Option<usize>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let root = root
+            .map(|facet| facet.inner.clone())
+            .unwrap_or_else(schema::Facet::root);
+        // The path of the root facet is `[""]`.
+        let root_depth = if root.is_root() {
+            0
+        } else {
+            root.to_path().len()
+        };
+        let mut tree = FacetNode::default();
+        for (facet, count) in self.descendants(&root) {
+            let path = &facet.to_path()[root_depth..];
+            if depth.is_some_and(|depth| path.len() > depth) {
+                continue;
+            }
+            let node = path.iter().fold(&mut tree, |node, name| {
+                node.children.entry(name.to_string()).or_default()
+            });
+            node.count = Some(count);
+        }
+        children_to_py(py, tree.children)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("FacetCounts(num_facets={})", self.counts.len())
     }
 }
@@ -0,0 +1,407 @@
+//! Learning to rank features of documents for a query, see
+//! `Searcher.extract_features`.
+
+use std::{
+    collections::HashMap,
+    ffi::{c_int, c_void},
+    ptr,
+    time::SystemTime,
+};
+
+use pyo3::{
+    exceptions::{PyBufferError, PyValueError},
+    ffi,
+    prelude::*,
+};
+use tantivy::{
+    self as tv,
+    query::{BooleanQuery, EnableScoring, Query as _, Scorer, TermQuery},
+    schema::{Field, IndexRecordOption, Type},
+    DocId, SegmentReader, Term,
+};
+
+use crate::{
+    errors::{schema_err, tantivy_err},
+    expression::{numeric_fast_field, NumericColumn},
+    get_field,
+    searcher::{segment_of, DocAddress},
+};
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// A feature of a document, parsed from a string such as "bm25:title".
+enum Feature {
+    /// The score of the query.
+    Score,
+    /// The BM25 score of the terms of the query in a field.
+    Bm25(Field),
+    /// The number of distinct terms of the query in a field of the document.
+    TermMatches(Field),
+    /// The number of tokens of a field.
+    FieldLength(Field),
+    /// The first value of a fast field.
+    Value(String, Type),
+    /// The days elapsed since the first value of a date fast field.
+    Recency(String),
+}
+
+impl Feature {
+    fn parse(schema: &tv::schema::Schema, feature: &str) -> PyResult<Self> {
+        if feature == "score" {
+            return Ok(Feature::Score);
+        }
+        let invalid = || {
+            PyValueError::new_err(format!(
+                "Invalid feature `{feature}`, valid features are 'score', \
+                 'bm25:<field>', 'term_matches:<field>', \
+                 'field_length:<field>', 'value:<field>' and \
+                 'recency:<field>'."
+            ))
+        };
+        let (kind, field_name) = feature.split_once(':').ok_or_else(invalid)?;
+        let text_field = |needs_fieldnorms: bool| -> PyResult<Field> {
+            let field = get_field(schema, field_name)?;
+            let field_entry = schema.get_field_entry(field);
+            let is_text = field_entry.field_type().value_type() == Type::Str;
+            let has_fieldnorms = field_entry.has_fieldnorms();
+            if !is_text
+                || !field_entry.is_indexed()
+                || (needs_fieldnorms && !has_fieldnorms)
+            {
+                let norms = if needs_fieldnorms {
+                    " with field norms"
+                } else {
+                    ""
+                };
+                return Err(schema_err(
+                    format!(
+                        "Field `{field_name}` must be an indexed text \
+                         field{norms} for the `{kind}` feature."
+                    ),
+                    field_name,
+                ));
+            }
+            Ok(field)
+        };
+        match kind {
+            "bm25" => Ok(Feature::Bm25(text_field(false)?)),
+            "term_matches" => Ok(Feature::TermMatches(text_field(false)?)),
+            "field_length" => Ok(Feature::FieldLength(text_field(true)?)),
+            "value" => {
+                let used = "extracted as a feature";
+                let value_type = numeric_fast_field(schema, field_name, used)?;
+                Ok(Feature::Value(field_name.to_string(), value_type))
+            }
+            "recency" => {
+                let used = "extracted as a recency feature";
+                if numeric_fast_field(schema, field_name, used)? != Type::Date {
+                    return Err(schema_err(
+                        format!(
+                            "Field `{field_name}` must be a date field for \
+                             the `recency` feature."
+                        ),
+                        field_name,
+                    ));
+                }
+                Ok(Feature::Recency(field_name.to_string()))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// The rows of the documents of a segment, by ascending document, as
+/// scorers can only move forward.
+type SegmentRows = Vec<(DocId, usize)>;
+
+/// Whether a scorer matches a document, moving it forward to the document.
+/// The documents can repeat.
+fn seek(scorer: &mut dyn Scorer, doc: DocId) -> bool {
+    if scorer.doc() < doc {
+        scorer.seek(doc);
+    }
+    scorer.doc() == doc
+}
+
+/// Extracts features of documents, computing each feature over the
+/// documents of a segment at once.
+struct Extractor<'a> {
+    searcher: &'a tv::Searcher,
+    query: &'a dyn tv::query::Query,
+    segments: HashMap<u32, SegmentRows>,
+    num_features: usize,
+    data: Vec<f64>,
+}
+
+impl Extractor<'_> {
+    fn set(&mut self, row: usize, column: usize, value: f64) {
+        self.data[row * self.num_features + column] = value;
+    }
+
+    /// Set a column from a function of the segment and the document.
+    fn fill(
+        &mut self,
+        column: usize,
+        value: impl Fn(&SegmentReader, DocId) -> tv::Result<f64>,
+    ) -> tv::Result<()> {
+        let segments = std::mem::take(&mut self.segments);
+        for (segment_ord, rows) in &segments {
+            let segment = self.searcher.segment_reader(*segment_ord);
+            for &(doc, row) in rows {
+                let value = value(segment, doc)?;
+                self.set(row, column, value);
+            }
+        }
+        self.segments = segments;
+        Ok(())
+    }
+
+    /// Set a column from the scores of a query, 0 for the documents not
+    /// matching it.
+    fn fill_scores(
+        &mut self,
+        column: usize,
+        query: &dyn tv::query::Query,
+    ) -> tv::Result<()> {
+        let weight = query
+            .weight(EnableScoring::enabled_from_searcher(self.searcher))?;
+        let segments = std::mem::take(&mut self.segments);
+        for (segment_ord, rows) in &segments {
+            let segment = self.searcher.segment_reader(*segment_ord);
+            let mut scorer = weight.scorer(segment, 1.0)?;
+            for &(doc, row) in rows {
+                if seek(scorer.as_mut(), doc) {
+                    let score = scorer.score() as f64;
+                    self.set(row, column, score);
+                }
+            }
+        }
+        self.segments = segments;
+        Ok(())
+    }
+
+    /// Add 1 to a column for the documents matching a term.
+    fn count_matches(&mut self, column: usize, term: Term) -> tv::Result<()> {
+        let query = TermQuery::new(term, IndexRecordOption::Basic);
+        let weight = query
+            .weight(EnableScoring::disabled_from_searcher(self.searcher))?;
+        let segments = std::mem::take(&mut self.segments);
+        for (segment_ord, rows) in &segments {
+            let segment = self.searcher.segment_reader(*segment_ord);
+            let mut scorer = weight.scorer(segment, 1.0)?;
+            for &(doc, row) in rows {
+                if seek(scorer.as_mut(), doc) {
+                    self.data[row * self.num_features + column] += 1.0;
+                }
+            }
+        }
+        self.segments = segments;
+        Ok(())
+    }
+
+    /// The distinct terms of the query in a field.
+    fn query_terms(&self, field: Field) -> Vec<Term> {
+        let mut terms = Vec::new();
+        self.query.query_terms(&mut |term, _| {
+            if term.field() == field && !terms.contains(term) {
+                terms.push(term.clone());
+            }
+        });
+        terms
+    }
+
+    fn extract(&mut self, column: usize, feature: &Feature) -> tv::Result<()> {
+        match feature {
+            Feature::Score => self.fill_scores(column, self.query),
+            Feature::Bm25(field) => {
+                let terms = self.query_terms(*field);
+                if terms.is_empty() {
+                    return Ok(());
+                }
+                let query = BooleanQuery::new_multiterms_query(terms);
+                self.fill_scores(column, &query)
+            }
+            Feature::TermMatches(field) => {
+                for term in self.query_terms(*field) {
+                    self.count_matches(column, term)?;
+                }
+                Ok(())
+            }
+            Feature::FieldLength(field) => self.fill(column, |segment, doc| {
+                let fieldnorms = segment.get_fieldnorms_reader(*field)?;
+                Ok(fieldnorms.fieldnorm(doc) as f64)
+            }),
+            Feature::Value(field_name, value_type) => {
+                self.fill(column, |segment, doc| {
+                    Ok(NumericColumn::open(segment, field_name, *value_type)
+                        .and_then(|column| column.first(doc))
+                        .unwrap_or(f64::NAN))
+                })
+            }
+            Feature::Recency(field_name) => {
+                let now = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+                self.fill(column, |segment, doc| {
+                    Ok(NumericColumn::open(segment, field_name, Type::Date)
+                        .and_then(|column| column.first(doc))
+                        .map_or(f64::NAN, |date| {
+                            (now - date) / SECONDS_PER_DAY
+                        }))
+                })
+            }
+        }
+    }
+}
+
+/// Compute features of documents for a query.
+///
+/// Raises a ValueError if a feature or a document address is invalid, and a
+/// SchemaError if the field of a feature doesn't support it.
+pub(crate) fn extract_features(
+    py: Python,
+    searcher: &tv::Searcher,
+    query: &dyn tv::query::Query,
+    doc_addresses: &[DocAddress],
+    features: Vec<String>,
+) -> PyResult<FeatureMatrix> {
+    let schema = searcher.schema();
+    let parsed = features
+        .iter()
+        .map(|feature| Feature::parse(schema, feature))
+        .collect::<PyResult<Vec<_>>>()?;
+
+    let mut segments: HashMap<u32, SegmentRows> = HashMap::new();
+    for (row, address) in doc_addresses.iter().enumerate() {
+        segment_of(searcher, address)?;
+        segments
+            .entry(address.segment_ord)
+            .or_default()
+            .push((address.doc, row));
+    }
+    for rows in segments.values_mut() {
+        rows.sort_unstable();
+    }
+
+    let num_features = parsed.len();
+    let mut extractor = Extractor {
+        searcher,
+        query,
+        segments,
+        num_features,
+        data: vec![0.0; doc_addresses.len() * num_features],
+    };
+    py.allow_threads(|| {
+        parsed.iter().enumerate().try_for_each(|(column, feature)| {
+            extractor.extract(column, feature)
+        })
+    })
+    .map_err(tantivy_err)?;
+    Ok(FeatureMatrix {
+        data: extractor.data,
+        shape: [doc_addresses.len() as isize, num_features as isize],
+        strides: [
+            (num_features * std::mem::size_of::<f64>()) as isize,
+            std::mem::size_of::<f64>() as isize,
+        ],
+        feature_names: features,
+    })
+}
+
+/// The features of documents, one row per document and one column per
+/// feature.
+///
+/// FeatureMatrix implements the buffer protocol as a read-only matrix of
+/// floats: `numpy.asarray(matrix)` reads it without copying, e.g. to train
+/// or serve a learning to rank model.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+pub(crate) struct FeatureMatrix {
+    data: Vec<f64>,
+    shape: [ffi::Py_ssize_t; 2],
+    strides: [ffi::Py_ssize_t; 2],
+    feature_names: Vec<String>,
+}
+
+#[pymethods]
+impl FeatureMatrix {
+    /// The number of documents and of features.
+    #[getter]
+    fn shape(&self) -> (usize, usize) {
+        (self.shape[0] as usize, self.shape[1] as usize)
+    }
+
+    /// The names of the features, in the order of the columns.
+    #[getter]
+    fn feature_names(&self) -> Vec<String> {
+        self.feature_names.clone()
+    }
+
+    /// The rows of the matrix as lists of floats.
+    fn tolist(&self) -> Vec<Vec<f64>> {
+        match self.shape[1] {
+            0 => vec![Vec::new(); self.shape[0] as usize],
+            num_features => self
+                .data
+                .chunks(num_features as usize)
+                .map(<[f64]>::to_vec)
+                .collect(),
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.shape[0] as usize
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FeatureMatrix(num_docs={}, features={:?})",
+            self.shape[0], self.feature_names
+        )
+    }
+
+    /// Expose the features as a read-only, C-contiguous, two-dimensional
+    /// buffer of doubles. The buffer holds a reference to the object, whose
+    /// data never moves since it is immutable.
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("The buffer view is null."));
+        }
+        if flags & ffi::PyBUF_WRITABLE == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err(
+                "Feature matrices are read-only.",
+            ));
+        }
+        let matrix = slf.get();
+        (*view).buf = matrix.data.as_ptr() as *mut c_void;
+        (*view).len =
+            (matrix.data.len() * std::mem::size_of::<f64>()) as ffi::Py_ssize_t;
+        (*view).readonly = 1;
+        (*view).itemsize = std::mem::size_of::<f64>() as ffi::Py_ssize_t;
+        (*view).format = if flags & ffi::PyBUF_FORMAT == ffi::PyBUF_FORMAT {
+            c"d".as_ptr() as *mut _
+        } else {
+            ptr::null_mut()
+        };
+        (*view).ndim = 2;
+        (*view).shape = if flags & ffi::PyBUF_ND == ffi::PyBUF_ND {
+            matrix.shape.as_ptr() as *mut _
+        } else {
+            ptr::null_mut()
+        };
+        (*view).strides = if flags & ffi::PyBUF_STRIDES == ffi::PyBUF_STRIDES {
+            matrix.strides.as_ptr() as *mut _
+        } else {
+            ptr::null_mut()
+        };
+        (*view).suboffsets = ptr::null_mut();
+        (*view).internal = ptr::null_mut();
+        (*view).obj = slf.into_any().into_ptr();
+        Ok(())
+    }
+
+    unsafe fn __releasebuffer__(&self, _view: *mut ffi::Py_buffer) {}
+}
@@ -0,0 +1,180 @@
+//! Fetching the stored documents of a searcher through shared block
+//! caches, see `Searcher.fetch_session`.
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use tantivy::{self as tv, store::StoreReader, Document as _, TantivyDocument};
+
+use crate::{
+    document::Document,
+    errors::tantivy_err,
+    searcher::{DocAddress, Searcher},
+};
+
+/// Fetches the stored documents of a searcher, keeping the last
+/// decompressed blocks of the doc store of every segment across calls,
+/// returned by `Searcher.fetch_session`.
+///
+/// `Searcher.doc` decompresses the block holding a document on every call,
+/// a session decompresses it once for all the documents of the block, e.g.
+/// the hits of a result page. The addresses are those of the searcher of
+/// the session, which keeps reading its segments after a reload of the
+/// index: the hits of a newer searcher need a session of their own.
+///
+/// The session can be used as a context manager, dropping its caches on
+/// exit:
+///
+///     with searcher.fetch_session() as session:
+///         docs = session.docs([address for _, address in result.hits])
+#[pyclass(module = "tantivy.tantivy")]
+pub(crate) struct FetchSession {
+    searcher: Py<Searcher>,
+    inner: tv::Searcher,
+    cache_blocks: usize,
+    /// The store readers of the segments, opened on their first fetch.
+    store_readers: Vec<Option<StoreReader>>,
+}
+
+impl FetchSession {
+    pub(crate) fn new(
+        searcher: Py<Searcher>,
+        inner: tv::Searcher,
+        cache_blocks: usize,
+    ) -> Self {
+        let store_readers =
+            inner.segment_readers().iter().map(|_| None).collect();
+        FetchSession {
+            searcher,
+            inner,
+            cache_blocks,
+            store_readers,
+        }
+    }
+
+    /// The store reader of the segment of a document, opened if needed.
+    fn store_reader(
+        &mut self,
+        doc_address: &DocAddress,
+    ) -> PyResult<&StoreReader> {
+        let segment_ord = doc_address.segment_ord as usize;
+        let Some(store_reader) = self.store_readers.get_mut(segment_ord) else {
+            return Err(PyValueError::new_err(format!(
+                "The segment {segment_ord} doesn't belong to the searcher of \
+                 the session."
+            )));
+        };
+        if store_reader.is_none() {
+            let segment = self.inner.segment_reader(segment_ord as u32);
+            *store_reader = Some(
+                segment
+                    .get_store_reader(self.cache_blocks)
+                    .map_err(tantivy_err)?,
+            );
+        }
+        Ok(store_reader.as_ref().unwrap())
+    }
+
+    fn fetch(&mut self, doc_address: &DocAddress) -> PyResult<TantivyDocument> {
+        let store_reader = self.store_reader(doc_address)?;
+        store_reader.get(doc_address.doc).map_err(tantivy_err)
+    }
+
+    fn to_document(&self, doc: TantivyDocument) -> Document {
+        Document {
+            field_values: doc.to_named_doc(self.inner.schema()).0,
+        }
+    }
+}
+
+#[pymethods]
+impl FetchSession {
+    /// The searcher the addresses of the documents belong to.
+    #[getter]
+    fn searcher(&self, py: Python) -> Py<Searcher> {
+        self.searcher.clone_ref(py)
+    }
+
+    /// Fetch a document, as `Searcher.doc`.
+    ///
+    /// Args:
+    ///     doc_address (DocAddress): The address of the document.
+    ///
+    /// Raises a ValueError if the document can't be found or doesn't match
+    /// the filters of the searcher.
+    fn doc(
+        &mut self,
+        py: Python,
+        doc_address: &DocAddress,
+    ) -> PyResult<Document> {
+        self.searcher
+            .borrow(py)
+            .check_visible(py, std::slice::from_ref(doc_address))?;
+        let doc = py.allow_threads(|| self.fetch(doc_address))?;
+        Ok(self.to_document(doc))
+    }
+
+    /// Fetch documents, reading them in the order of the doc store so that
+    /// every block is decompressed once.
+    ///
+    /// Args:
+    ///     doc_addresses (List[DocAddress]): The addresses of the documents.
+    ///
+    /// Returns the documents in the order of their addresses.
+    ///
+    /// Raises a ValueError if a document can't be found or doesn't match the
+    /// filters of the searcher.
+    fn docs(
+        &mut self,
+        py: Python,
+        doc_addresses: Vec<DocAddress>,
+    ) -> PyResult<Vec<Document>> {
+        self.searcher.borrow(py).check_visible(py, &doc_addresses)?;
+        let mut order: Vec<usize> = (0..doc_addresses.len()).collect();
+        order.sort_by_key(|&i| {
+            (doc_addresses[i].segment_ord, doc_addresses[i].doc)
+        });
+        let mut docs: Vec<Option<TantivyDocument>> =
+            doc_addresses.iter().map(|_| None).collect();
+        py.allow_threads(|| {
+            for i in order {
+                docs[i] = Some(self.fetch(&doc_addresses[i])?);
+            }
+            Ok::<_, PyErr>(())
+        })?;
+        Ok(docs
+            .into_iter()
+            .map(|doc| self.to_document(doc.unwrap()))
+            .collect())
+    }
+
+    /// Drop the caches of the session. Later fetches open them again.
+    fn close(&mut self) {
+        for store_reader in &mut self.store_readers {
+            *store_reader = None;
+        }
+    }
+
+    fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type = None, _exc_value = None, _traceback = None))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<&Bound<PyAny>>,
+        _exc_value: Option<&Bound<PyAny>>,
+        _traceback: Option<&Bound<PyAny>>,
+    ) -> bool {
+        self.close();
+        false
+    }
+
+    fn __repr__(&self) -> String {
+        let open = self.store_readers.iter().flatten().count();
+        format!(
+            "FetchSession(cache_blocks={}, open_segments={}/{})",
+            self.cache_blocks,
+            open,
+            self.store_readers.len()
+        )
+    }
+}
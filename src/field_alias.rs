@@ -0,0 +1,71 @@
+//! Alternative names of the fields in query strings, see
+//! `Index.set_field_aliases`.
+
+use std::{borrow::Cow, collections::HashMap};
+
+use tantivy::query_grammar::{UserInputAst, UserInputLeaf};
+
+/// The field named by an alias, or the name itself. The paths of JSON
+/// fields keep their path after the aliased field name.
+pub(crate) fn resolve<'a>(
+    aliases: &HashMap<String, String>,
+    name: &'a str,
+) -> Cow<'a, str> {
+    let (field_name, path) = match name.split_once('.') {
+        Some((field_name, path)) => (field_name, Some(path)),
+        None => (name, None),
+    };
+    match (aliases.get(field_name), path) {
+        (Some(target), Some(path)) => Cow::Owned(format!("{target}.{path}")),
+        (Some(target), None) => Cow::Owned(target.clone()),
+        (None, _) => Cow::Borrowed(name),
+    }
+}
+
+/// Replace the aliases of a parsed query string by their fields.
+pub(crate) fn rewrite(
+    ast: UserInputAst,
+    aliases: &HashMap<String, String>,
+) -> UserInputAst {
+    if aliases.is_empty() {
+        return ast;
+    }
+    let resolve_owned = |name: String| resolve(aliases, &name).into_owned();
+    match ast {
+        UserInputAst::Clause(clauses) => UserInputAst::Clause(
+            clauses
+                .into_iter()
+                .map(|(occur, clause)| (occur, rewrite(clause, aliases)))
+                .collect(),
+        ),
+        UserInputAst::Boost(ast, boost) => {
+            UserInputAst::Boost(Box::new(rewrite(*ast, aliases)), boost)
+        }
+        UserInputAst::Leaf(leaf) => {
+            let leaf = match *leaf {
+                UserInputLeaf::Literal(mut literal) => {
+                    literal.field_name = literal.field_name.map(resolve_owned);
+                    UserInputLeaf::Literal(literal)
+                }
+                UserInputLeaf::All => UserInputLeaf::All,
+                UserInputLeaf::Range {
+                    field,
+                    lower,
+                    upper,
+                } => UserInputLeaf::Range {
+                    field: field.map(resolve_owned),
+                    lower,
+                    upper,
+                },
+                UserInputLeaf::Set { field, elements } => UserInputLeaf::Set {
+                    field: field.map(resolve_owned),
+                    elements,
+                },
+                UserInputLeaf::Exists { field } => UserInputLeaf::Exists {
+                    field: resolve_owned(field),
+                },
+            };
+            UserInputAst::Leaf(Box::new(leaf))
+        }
+    }
+}
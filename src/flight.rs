@@ -0,0 +1,733 @@
+//! An Arrow Flight server streaming search results as Arrow record batches,
+//! see `Index.flight_server`. Built with the `flight` cargo feature.
+//!
+//! Only the calls needed to run queries are implemented: `GetSchema`,
+//! `GetFlightInfo` and `DoGet`. The others answer `UNIMPLEMENTED`.
+
+// The calls of tonic services fail with a `Status`, a large error.
+#![allow(clippy::result_large_err)]
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use arrow_array::{
+    builder::{
+        make_builder, ArrayBuilder, BinaryBuilder, BooleanBuilder,
+        Float64Builder, Int64Builder, ListBuilder, StringBuilder,
+        TimestampMicrosecondBuilder, UInt64Builder,
+    },
+    ArrayRef, Float32Array, RecordBatch, UInt32Array,
+};
+use arrow_ipc::writer::{
+    self as ipc, DictionaryTracker, IpcDataGenerator, IpcWriteOptions,
+};
+use arrow_schema::{
+    ArrowError, DataType, Field as ArrowField, Schema, TimeUnit,
+};
+use futures::channel::oneshot;
+use pyo3::{exceptions::PyValueError, prelude::*};
+use serde::Deserialize;
+use tantivy::{
+    self as tv,
+    collector::{Count, TopDocs},
+    query::{QueryParser, QueryParserError},
+    schema::{Field, FieldType, OwnedValue},
+    DocAddress, TantivyDocument,
+};
+use tonic::{
+    body::BoxBody,
+    codec::ProstCodec,
+    codegen::{empty_body, http, Body, BoxFuture, Service, StdError},
+    server::{Grpc, NamedService},
+    transport::Server,
+    Status,
+};
+
+use crate::{errors::tantivy_err, field_alias, index::Index};
+
+/// The maximum number of hits of a ticket.
+const MAX_LIMIT: usize = 100_000;
+/// The maximum number of rows of a record batch.
+const BATCH_SIZE: usize = 8192;
+/// How long stopping the server waits for the calls in progress.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+/// `FlightDescriptor.DescriptorType.CMD`.
+const DESCRIPTOR_CMD: i32 = 2;
+
+/// The messages of the Arrow Flight protocol used by the server, see
+/// `format/Flight.proto` in the Arrow repository. Unused fields are left
+/// out, they are skipped when decoding.
+mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Ticket {
+        #[prost(bytes = "vec", tag = "1")]
+        pub ticket: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FlightDescriptor {
+        /// The `DescriptorType`: 1 for a path, 2 for a command.
+        #[prost(int32, tag = "1")]
+        pub r#type: i32,
+        #[prost(bytes = "vec", tag = "2")]
+        pub cmd: Vec<u8>,
+        #[prost(string, repeated, tag = "3")]
+        pub path: Vec<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FlightEndpoint {
+        #[prost(message, optional, tag = "1")]
+        pub ticket: Option<Ticket>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FlightInfo {
+        /// The schema, as an encapsulated Arrow IPC message.
+        #[prost(bytes = "vec", tag = "1")]
+        pub schema: Vec<u8>,
+        #[prost(message, optional, tag = "2")]
+        pub flight_descriptor: Option<FlightDescriptor>,
+        #[prost(message, repeated, tag = "3")]
+        pub endpoint: Vec<FlightEndpoint>,
+        #[prost(int64, tag = "4")]
+        pub total_records: i64,
+        #[prost(int64, tag = "5")]
+        pub total_bytes: i64,
+        #[prost(bool, tag = "6")]
+        pub ordered: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SchemaResult {
+        /// The schema, as an encapsulated Arrow IPC message.
+        #[prost(bytes = "vec", tag = "1")]
+        pub schema: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FlightData {
+        #[prost(message, optional, tag = "1")]
+        pub flight_descriptor: Option<FlightDescriptor>,
+        /// The Arrow IPC message header, a flatbuffer.
+        #[prost(bytes = "vec", tag = "2")]
+        pub data_header: Vec<u8>,
+        #[prost(bytes = "vec", tag = "3")]
+        pub app_metadata: Vec<u8>,
+        /// The Arrow IPC message body.
+        #[prost(bytes = "vec", tag = "1000")]
+        pub data_body: Vec<u8>,
+    }
+}
+
+use proto::{FlightData, FlightDescriptor, FlightEndpoint, FlightInfo};
+
+/// The search requested by a ticket or a command descriptor, as JSON.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SearchRequest {
+    q: String,
+    /// The comma separated fields searched by default.
+    fields: Option<String>,
+    #[serde(default = "SearchRequest::default_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+}
+
+impl SearchRequest {
+    fn default_limit() -> usize {
+        10
+    }
+
+    fn parse(bytes: &[u8]) -> Result<Self, Status> {
+        let request: SearchRequest =
+            serde_json::from_slice(bytes).map_err(|err| {
+                Status::invalid_argument(format!("Invalid ticket: {err}"))
+            })?;
+        if !(1..=MAX_LIMIT).contains(&request.limit) {
+            return Err(Status::invalid_argument(format!(
+                "`limit` must be between 1 and {MAX_LIMIT}."
+            )));
+        }
+        Ok(request)
+    }
+}
+
+fn tantivy_status(err: tv::TantivyError) -> Status {
+    Status::internal(err.to_string())
+}
+
+fn arrow_status(err: ArrowError) -> Status {
+    Status::internal(err.to_string())
+}
+
+/// The Arrow type of the values of a stored field.
+fn value_type(field_type: &FieldType) -> DataType {
+    match field_type {
+        FieldType::U64(_) => DataType::UInt64,
+        FieldType::I64(_) => DataType::Int64,
+        FieldType::F64(_) => DataType::Float64,
+        FieldType::Bool(_) => DataType::Boolean,
+        FieldType::Date(_) => {
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+        }
+        FieldType::Bytes(_) => DataType::Binary,
+        // Facets and addresses as text, JSON objects serialized.
+        FieldType::Str(_)
+        | FieldType::Facet(_)
+        | FieldType::JsonObject(_)
+        | FieldType::IpAddr(_) => DataType::Utf8,
+    }
+}
+
+fn builder<T: 'static>(values: &mut dyn ArrayBuilder) -> &mut T {
+    values
+        .as_any_mut()
+        .downcast_mut()
+        .expect("the builder matches the type of the field")
+}
+
+/// Append a stored value to the builder of the values of its field.
+fn append_value(values: &mut dyn ArrayBuilder, value: &OwnedValue) {
+    match value {
+        OwnedValue::Null => {}
+        OwnedValue::Str(text) => {
+            builder::<StringBuilder>(values).append_value(text)
+        }
+        OwnedValue::PreTokStr(pretokenized) => {
+            builder::<StringBuilder>(values).append_value(&pretokenized.text)
+        }
+        OwnedValue::Facet(facet) => {
+            builder::<StringBuilder>(values).append_value(facet.to_string())
+        }
+        OwnedValue::IpAddr(ip) => {
+            let ip = match ip.to_ipv4_mapped() {
+                Some(ip) => ip.to_string(),
+                None => ip.to_string(),
+            };
+            builder::<StringBuilder>(values).append_value(ip)
+        }
+        OwnedValue::Array(_) | OwnedValue::Object(_) => {
+            let json = serde_json::to_string(value)
+                .expect("stored values serialize to JSON");
+            builder::<StringBuilder>(values).append_value(json)
+        }
+        OwnedValue::U64(n) => builder::<UInt64Builder>(values).append_value(*n),
+        OwnedValue::I64(n) => builder::<Int64Builder>(values).append_value(*n),
+        OwnedValue::F64(n) => {
+            builder::<Float64Builder>(values).append_value(*n)
+        }
+        OwnedValue::Bool(b) => {
+            builder::<BooleanBuilder>(values).append_value(*b)
+        }
+        OwnedValue::Date(date) => {
+            builder::<TimestampMicrosecondBuilder>(values)
+                .append_value(date.into_timestamp_micros())
+        }
+        OwnedValue::Bytes(bytes) => {
+            builder::<BinaryBuilder>(values).append_value(bytes)
+        }
+    }
+}
+
+/// The state shared by the calls of a server.
+struct Handler {
+    index: tv::Index,
+    reader: tv::IndexReader,
+    /// The field aliases of the index when the server started.
+    field_aliases: HashMap<String, String>,
+    /// The stored fields, the columns following the hit columns.
+    stored_fields: Vec<Field>,
+    schema: Arc<Schema>,
+}
+
+impl Handler {
+    fn new(
+        index: tv::Index,
+        reader: tv::IndexReader,
+        field_aliases: HashMap<String, String>,
+    ) -> Self {
+        let tantivy_schema = index.schema();
+        let mut stored_fields = Vec::new();
+        let mut columns = vec![
+            ArrowField::new("score", DataType::Float32, false),
+            ArrowField::new("segment_ord", DataType::UInt32, false),
+            ArrowField::new("doc", DataType::UInt32, false),
+        ];
+        for (field, entry) in tantivy_schema.fields() {
+            if !entry.is_stored() {
+                continue;
+            }
+            stored_fields.push(field);
+            // Fields have any number of values in a document.
+            columns.push(ArrowField::new_list(
+                entry.name(),
+                ArrowField::new_list_field(
+                    value_type(entry.field_type()),
+                    true,
+                ),
+                false,
+            ));
+        }
+        Handler {
+            index,
+            reader,
+            field_aliases,
+            stored_fields,
+            schema: Arc::new(Schema::new(columns)),
+        }
+    }
+
+    /// Parse a query in the tantivy query language, searching the given
+    /// comma separated fields by default, or all the indexed fields.
+    fn parse_query(
+        &self,
+        query: &str,
+        fields: Option<&str>,
+    ) -> Result<Box<dyn tv::query::Query>, Status> {
+        let schema = self.index.schema();
+        let default_fields: Vec<Field> = match fields {
+            Some(names) => names
+                .split(',')
+                .map(|name| {
+                    let name = field_alias::resolve(&self.field_aliases, name);
+                    schema.get_field(&name).map_err(|_| {
+                        Status::invalid_argument(format!(
+                            "Unknown field `{name}`."
+                        ))
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            None => schema
+                .fields()
+                .filter(|(_, entry)| entry.is_indexed())
+                .map(|(field, _)| field)
+                .collect(),
+        };
+        let ast = tv::query_grammar::parse_query(query).map_err(|_| {
+            Status::invalid_argument(
+                QueryParserError::SyntaxError(query.into()).to_string(),
+            )
+        })?;
+        QueryParser::for_index(&self.index, default_fields)
+            .build_query_from_user_input_ast(field_alias::rewrite(
+                ast,
+                &self.field_aliases,
+            ))
+            .map_err(|err| Status::invalid_argument(err.to_string()))
+    }
+
+    /// The schema as an encapsulated IPC message, as found in
+    /// `SchemaResult` and `FlightInfo`.
+    fn encapsulated_schema(&self) -> Result<Vec<u8>, Status> {
+        let options = IpcWriteOptions::default();
+        let encoded = IpcDataGenerator::default()
+            .schema_to_bytes_with_dictionary_tracker(
+                &self.schema,
+                &mut DictionaryTracker::new(false),
+                &options,
+            );
+        let mut message = Vec::new();
+        ipc::write_message(&mut message, encoded, &options)
+            .map_err(arrow_status)?;
+        Ok(message)
+    }
+
+    fn record_batch(
+        &self,
+        searcher: &tv::Searcher,
+        hits: &[(tv::Score, DocAddress)],
+    ) -> Result<RecordBatch, Status> {
+        let mut values: Vec<ListBuilder<Box<dyn ArrayBuilder>>> = self
+            .schema
+            .fields()
+            .iter()
+            .skip(3)
+            .map(|column| {
+                let DataType::List(values) = column.data_type() else {
+                    unreachable!("the stored fields are list columns");
+                };
+                ListBuilder::new(make_builder(values.data_type(), hits.len()))
+                    .with_field(values.clone())
+            })
+            .collect();
+        for (_, address) in hits {
+            let doc: TantivyDocument =
+                searcher.doc(*address).map_err(tantivy_status)?;
+            for (field, builder) in self.stored_fields.iter().zip(&mut values) {
+                for value in doc.get_all(*field) {
+                    append_value(builder.values().as_mut(), value);
+                }
+                builder.append(true);
+            }
+        }
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(Float32Array::from_iter_values(
+                hits.iter().map(|(score, _)| *score),
+            )),
+            Arc::new(UInt32Array::from_iter_values(
+                hits.iter().map(|(_, address)| address.segment_ord),
+            )),
+            Arc::new(UInt32Array::from_iter_values(
+                hits.iter().map(|(_, address)| address.doc_id),
+            )),
+        ];
+        columns.extend(
+            values
+                .iter_mut()
+                .map(|builder| Arc::new(builder.finish()) as ArrayRef),
+        );
+        RecordBatch::try_new(self.schema.clone(), columns).map_err(arrow_status)
+    }
+
+    fn get_schema(
+        &self,
+        _descriptor: FlightDescriptor,
+    ) -> Result<proto::SchemaResult, Status> {
+        Ok(proto::SchemaResult {
+            schema: self.encapsulated_schema()?,
+        })
+    }
+
+    /// Describe the results of a command descriptor, the JSON of a ticket.
+    /// Its only endpoint is this server, with that ticket.
+    fn get_flight_info(
+        &self,
+        descriptor: FlightDescriptor,
+    ) -> Result<FlightInfo, Status> {
+        if descriptor.r#type != DESCRIPTOR_CMD {
+            return Err(Status::invalid_argument(
+                "Only command descriptors are supported.",
+            ));
+        }
+        let request = SearchRequest::parse(&descriptor.cmd)?;
+        let query = self.parse_query(&request.q, request.fields.as_deref())?;
+        let count = self
+            .reader
+            .searcher()
+            .search(&query, &Count)
+            .map_err(tantivy_status)?;
+        let total_records =
+            count.saturating_sub(request.offset).min(request.limit);
+        Ok(FlightInfo {
+            schema: self.encapsulated_schema()?,
+            endpoint: vec![FlightEndpoint {
+                ticket: Some(proto::Ticket {
+                    ticket: descriptor.cmd.clone(),
+                }),
+            }],
+            flight_descriptor: Some(descriptor),
+            total_records: total_records as i64,
+            total_bytes: -1,
+            ordered: true,
+        })
+    }
+
+    /// The hits of a ticket, the schema first and then record batches of
+    /// at most `BATCH_SIZE` rows.
+    fn do_get(
+        &self,
+        ticket: proto::Ticket,
+    ) -> Result<FlightDataStream, Status> {
+        let request = SearchRequest::parse(&ticket.ticket)?;
+        let query = self.parse_query(&request.q, request.fields.as_deref())?;
+        let searcher = self.reader.searcher();
+        let hits = searcher
+            .search(
+                &query,
+                &TopDocs::with_limit(request.limit).and_offset(request.offset),
+            )
+            .map_err(tantivy_status)?;
+
+        let generator = IpcDataGenerator::default();
+        let options = IpcWriteOptions::default();
+        let mut tracker = DictionaryTracker::new(false);
+        let schema = generator.schema_to_bytes_with_dictionary_tracker(
+            &self.schema,
+            &mut tracker,
+            &options,
+        );
+        let mut messages = vec![Ok(FlightData {
+            data_header: schema.ipc_message,
+            ..Default::default()
+        })];
+        for hits in hits.chunks(BATCH_SIZE) {
+            let batch = self.record_batch(&searcher, hits)?;
+            // The schema has no dictionary columns.
+            let (_, encoded) = generator
+                .encoded_batch(&batch, &mut tracker, &options)
+                .map_err(arrow_status)?;
+            messages.push(Ok(FlightData {
+                data_header: encoded.ipc_message,
+                data_body: encoded.arrow_data,
+                ..Default::default()
+            }));
+        }
+        Ok(futures::stream::iter(messages))
+    }
+}
+
+type FlightDataStream =
+    futures::stream::Iter<std::vec::IntoIter<Result<FlightData, Status>>>;
+
+/// A call of a server, run on a blocking thread since searching is.
+struct Call<Req, Res> {
+    handler: Arc<Handler>,
+    method: fn(&Handler, Req) -> Result<Res, Status>,
+}
+
+impl<Req, Res> Service<tonic::Request<Req>> for Call<Req, Res>
+where
+    Req: Send + 'static,
+    Res: Send + 'static,
+{
+    type Response = tonic::Response<Res>;
+    type Error = Status;
+    type Future = BoxFuture<Self::Response, Status>;
+
+    fn poll_ready(
+        &mut self,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Status>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: tonic::Request<Req>) -> Self::Future {
+        let handler = self.handler.clone();
+        let method = self.method;
+        let request = request.into_inner();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || method(&handler, request))
+                .await
+                .map_err(|err| Status::internal(err.to_string()))?
+                .map(tonic::Response::new)
+        })
+    }
+}
+
+/// The `arrow.flight.protocol.FlightService` gRPC service, routing the
+/// calls to the handler.
+#[derive(Clone)]
+struct FlightService {
+    handler: Arc<Handler>,
+}
+
+impl FlightService {
+    fn method<Req, Res>(
+        &self,
+        method: fn(&Handler, Req) -> Result<Res, Status>,
+    ) -> Call<Req, Res> {
+        Call {
+            handler: self.handler.clone(),
+            method,
+        }
+    }
+}
+
+impl NamedService for FlightService {
+    const NAME: &'static str = "arrow.flight.protocol.FlightService";
+}
+
+impl<B> Service<http::Request<B>> for FlightService
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Infallible>;
+
+    fn poll_ready(
+        &mut self,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let method = request
+            .uri()
+            .path()
+            .strip_prefix("/arrow.flight.protocol.FlightService/");
+        match method {
+            Some("GetSchema") => {
+                let call = self.method(Handler::get_schema);
+                Box::pin(async move {
+                    let mut grpc = Grpc::new(ProstCodec::default());
+                    Ok(grpc.unary(call, request).await)
+                })
+            }
+            Some("GetFlightInfo") => {
+                let call = self.method(Handler::get_flight_info);
+                Box::pin(async move {
+                    let mut grpc = Grpc::new(ProstCodec::default());
+                    Ok(grpc.unary(call, request).await)
+                })
+            }
+            Some("DoGet") => {
+                let call = self.method(Handler::do_get);
+                Box::pin(async move {
+                    let mut grpc = Grpc::new(ProstCodec::default());
+                    Ok(grpc.server_streaming(call, request).await)
+                })
+            }
+            _ => Box::pin(async move {
+                let mut response = http::Response::new(empty_body());
+                let headers = response.headers_mut();
+                headers.insert(
+                    Status::GRPC_STATUS,
+                    (tonic::Code::Unimplemented as i32).into(),
+                );
+                headers.insert(
+                    http::header::CONTENT_TYPE,
+                    tonic::metadata::GRPC_CONTENT_TYPE,
+                );
+                Ok(response)
+            }),
+        }
+    }
+}
+
+/// The runtime of a running server.
+struct Running {
+    runtime: tokio::runtime::Runtime,
+    stop: oneshot::Sender<()>,
+    server: tokio::task::JoinHandle<Result<(), tonic::transport::Error>>,
+}
+
+/// An Arrow Flight server started by `Index.flight_server`. It stops when
+/// `stop` is called or when it is garbage collected.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+pub(crate) struct FlightServer {
+    address: SocketAddr,
+    running: Mutex<Option<Running>>,
+}
+
+impl FlightServer {
+    fn shutdown(&self) {
+        let Some(running) = self.running.lock().unwrap().take() else {
+            return;
+        };
+        let _ = running.stop.send(());
+        let result = running.runtime.block_on(async {
+            tokio::time::timeout(SHUTDOWN_TIMEOUT, running.server).await
+        });
+        if let Ok(Ok(Err(err))) = result {
+            log::warn!("flight server failed: {err}");
+        }
+        running.runtime.shutdown_background();
+    }
+}
+
+impl Drop for FlightServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[pymethods]
+impl FlightServer {
+    /// The address the server listens on.
+    #[getter]
+    fn host(&self) -> String {
+        self.address.ip().to_string()
+    }
+
+    /// The port the server listens on, the one picked by the system if the
+    /// server was started on port 0.
+    #[getter]
+    fn port(&self) -> u16 {
+        self.address.port()
+    }
+
+    /// The location of the server, as given to Flight clients.
+    #[getter]
+    fn location(&self) -> String {
+        format!("grpc+tcp://{}", self.address)
+    }
+
+    /// Whether the server is running.
+    #[getter]
+    fn running(&self) -> bool {
+        self.running.lock().unwrap().is_some()
+    }
+
+    /// Stop accepting calls, and wait for the calls in progress to
+    /// complete.
+    fn stop(&self, py: Python) {
+        py.allow_threads(|| self.shutdown());
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &self,
+        py: Python,
+        _exc_type: PyObject,
+        _exc_value: PyObject,
+        _traceback: PyObject,
+    ) {
+        self.stop(py);
+    }
+
+    fn __repr__(&self) -> String {
+        format!("FlightServer(location='{}')", self.location())
+    }
+}
+
+/// Serve an index over Arrow Flight, see `Index.flight_server`.
+pub(crate) fn serve(
+    index: &Index,
+    host: &str,
+    port: u16,
+    num_threads: usize,
+) -> PyResult<FlightServer> {
+    if num_threads == 0 {
+        return Err(PyValueError::new_err("num_threads must be at least 1."));
+    }
+    let listener = std::net::TcpListener::bind((host, port))?;
+    let address = listener.local_addr()?;
+    listener.set_nonblocking(true)?;
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(num_threads)
+        .thread_name("tantivy-flight")
+        .enable_all()
+        .build()?;
+    let listener = {
+        let _guard = runtime.enter();
+        tokio::net::TcpListener::from_std(listener)?
+    };
+    let handler = Arc::new(Handler::new(
+        index.index.clone(),
+        index.index.reader().map_err(tantivy_err)?,
+        index.field_aliases.clone(),
+    ));
+    let (stop, stopped) = oneshot::channel::<()>();
+    let server = runtime.spawn(
+        Server::builder()
+            .add_service(FlightService { handler })
+            .serve_with_incoming_shutdown(
+                tokio_stream::wrappers::TcpListenerStream::new(listener),
+                async {
+                    let _ = stopped.await;
+                },
+            ),
+    );
+    Ok(FlightServer {
+        address,
+        running: Mutex::new(Some(Running {
+            runtime,
+            stop,
+            server,
+        })),
+    })
+}
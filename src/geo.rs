@@ -0,0 +1,209 @@
+use pyo3::{exceptions::PyValueError, prelude::*};
+use tantivy::{
+    self as tv,
+    columnar::Column,
+    query::{EnableScoring, Explanation, Scorer, Weight},
+    DocId, Score, SegmentReader,
+};
+
+use crate::{
+    errors::schema_err,
+    schema::SchemaExtensions,
+    scorer::{explain_by_seek, VecScorer},
+};
+
+/// Mean radius of the earth, in meters.
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// A point on earth, in degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct GeoPoint {
+    pub(crate) lat: f64,
+    pub(crate) lon: f64,
+}
+
+impl GeoPoint {
+    pub(crate) fn new(lat: f64, lon: f64) -> PyResult<Self> {
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err(PyValueError::new_err(format!(
+                "Latitude must be between -90 and 90, got {lat}."
+            )));
+        }
+        if !(-180.0..=180.0).contains(&lon) {
+            return Err(PyValueError::new_err(format!(
+                "Longitude must be between -180 and 180, got {lon}."
+            )));
+        }
+        Ok(GeoPoint { lat, lon })
+    }
+
+    /// Encode the point as a morton code, interleaving the bits of the
+    /// quantized latitude and longitude.
+    pub(crate) fn encode(self) -> u64 {
+        let lat = quantize(self.lat, 90.0);
+        let lon = quantize(self.lon, 180.0);
+        (spread(lat) << 1) | spread(lon)
+    }
+
+    /// Decode a point encoded with `encode`.
+    pub(crate) fn decode(code: u64) -> Self {
+        GeoPoint {
+            lat: dequantize(compact(code >> 1), 90.0),
+            lon: dequantize(compact(code), 180.0),
+        }
+    }
+
+    /// The great circle distance to another point, in meters.
+    pub(crate) fn distance(self, other: GeoPoint) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let half_dlat = (lat2 - lat1) / 2.0;
+        let half_dlon = (other.lon - self.lon).to_radians() / 2.0;
+        let a = half_dlat.sin().powi(2)
+            + lat1.cos() * lat2.cos() * half_dlon.sin().powi(2);
+        2.0 * EARTH_RADIUS_M * a.sqrt().min(1.0).asin()
+    }
+}
+
+/// The factor applied to scores by a distance boost: 1 at the origin, halved
+/// every `scale_m` meters.
+pub(crate) fn distance_decay(distance_m: f64, scale_m: f64) -> f32 {
+    0.5f64.powf(distance_m / scale_m) as f32
+}
+
+fn quantize(degrees: f64, max: f64) -> u32 {
+    ((degrees + max) / (2.0 * max) * u32::MAX as f64).round() as u32
+}
+
+fn dequantize(value: u32, max: f64) -> f64 {
+    value as f64 / u32::MAX as f64 * (2.0 * max) - max
+}
+
+/// Spread the bits of `value` over the even bits of a u64.
+fn spread(value: u32) -> u64 {
+    let mut x = value as u64;
+    x = (x | (x << 16)) & 0x0000_FFFF_0000_FFFF;
+    x = (x | (x << 8)) & 0x00FF_00FF_00FF_00FF;
+    x = (x | (x << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+    x = (x | (x << 2)) & 0x3333_3333_3333_3333;
+    x = (x | (x << 1)) & 0x5555_5555_5555_5555;
+    x
+}
+
+/// Gather the even bits of `code`, the inverse of `spread`.
+fn compact(code: u64) -> u32 {
+    let mut x = code & 0x5555_5555_5555_5555;
+    x = (x | (x >> 1)) & 0x3333_3333_3333_3333;
+    x = (x | (x >> 2)) & 0x0F0F_0F0F_0F0F_0F0F;
+    x = (x | (x >> 4)) & 0x00FF_00FF_00FF_00FF;
+    x = (x | (x >> 8)) & 0x0000_FFFF_0000_FFFF;
+    x = (x | (x >> 16)) & 0x0000_0000_FFFF_FFFF;
+    x as u32
+}
+
+/// Check that `field_name` is a fast geo point field of the schema.
+pub(crate) fn check_geo_field(
+    schema: &tv::schema::Schema,
+    extensions: &SchemaExtensions,
+    field_name: &str,
+) -> PyResult<()> {
+    if !extensions.geo_fields.contains(field_name) {
+        return Err(schema_err(
+            format!("Field `{field_name}` is not a geo point field."),
+            field_name,
+        ));
+    }
+    let field = crate::get_field(schema, field_name)?;
+    if !schema.get_field_entry(field).is_fast() {
+        return Err(schema_err(
+            format!("Geo point field `{field_name}` is not a fast field."),
+            field_name,
+        ));
+    }
+    Ok(())
+}
+
+/// Open the column holding the points of a geo field in a segment.
+pub(crate) fn geo_column(
+    segment: &SegmentReader,
+    field_name: &str,
+) -> tv::Result<Option<Column<u64>>> {
+    segment.fast_fields().column_opt::<u64>(field_name)
+}
+
+/// The area matched by a `GeoQuery`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum GeoShape {
+    /// The box between two corners. The box crosses the antimeridian if the
+    /// minimum longitude is greater than the maximum one.
+    BoundingBox { min: GeoPoint, max: GeoPoint },
+    /// The points within `radius_m` meters of `center`.
+    Circle { center: GeoPoint, radius_m: f64 },
+}
+
+impl GeoShape {
+    fn contains(&self, point: GeoPoint) -> bool {
+        match *self {
+            GeoShape::BoundingBox { min, max } => {
+                let lon_matches = if min.lon <= max.lon {
+                    min.lon <= point.lon && point.lon <= max.lon
+                } else {
+                    min.lon <= point.lon || point.lon <= max.lon
+                };
+                lon_matches && min.lat <= point.lat && point.lat <= max.lat
+            }
+            GeoShape::Circle { center, radius_m } => {
+                center.distance(point) <= radius_m
+            }
+        }
+    }
+}
+
+/// Query matching the documents with a point of a geo field within a shape.
+///
+/// The points are read from the fast field, every matching document gets a
+/// constant score.
+#[derive(Clone, Debug)]
+pub(crate) struct GeoQuery {
+    pub(crate) field: String,
+    pub(crate) shape: GeoShape,
+}
+
+impl tv::query::Query for GeoQuery {
+    fn weight(
+        &self,
+        _enable_scoring: EnableScoring<'_>,
+    ) -> tv::Result<Box<dyn Weight>> {
+        Ok(Box::new(GeoWeight(self.clone())))
+    }
+}
+
+struct GeoWeight(GeoQuery);
+
+impl Weight for GeoWeight {
+    fn scorer(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> tv::Result<Box<dyn Scorer>> {
+        let mut docs = Vec::new();
+        if let Some(column) = geo_column(reader, &self.0.field)? {
+            for doc in 0..reader.max_doc() {
+                if column
+                    .values_for_doc(doc)
+                    .any(|code| self.0.shape.contains(GeoPoint::decode(code)))
+                {
+                    docs.push((doc, boost));
+                }
+            }
+        }
+        Ok(Box::new(VecScorer::new(docs)))
+    }
+
+    fn explain(
+        &self,
+        reader: &SegmentReader,
+        doc: DocId,
+    ) -> tv::Result<Explanation> {
+        explain_by_seek(self, reader, doc, "GeoQuery")
+    }
+}
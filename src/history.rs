@@ -0,0 +1,154 @@
+//! The history of the commits of an index, so that searchers can be opened
+//! on past commits until their files are garbage collected.
+//!
+//! Tantivy only keeps the list of segments of the last commit, in
+//! `meta.json`. The lists of the previous ones are kept in a sidecar file,
+//! along with the metadata of the commits, see `IndexWriter.set_commit_meta`.
+
+use std::path::Path;
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use serde::{Deserialize, Serialize};
+use tantivy::{self as tv, Directory, SegmentId};
+
+use crate::{
+    errors::tantivy_err, integrity, read_only::META_FILEPATH, sidecar, to_pyerr,
+};
+
+const HISTORY_PATH: &str = ".tantivy-py.commits.json";
+
+/// The maximum number of commits kept in the history.
+const MAX_COMMITS: usize = 100;
+
+/// The metadata of a commit, see `IndexWriter.set_commit_meta`.
+pub(crate) type CommitMeta = serde_json::Map<String, serde_json::Value>;
+
+/// A commit, with the same fields as in `meta.json`, and its metadata.
+#[derive(Deserialize, Serialize)]
+struct Commit {
+    opstamp: u64,
+    #[serde(default)]
+    payload: Option<String>,
+    segments: Vec<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    commit_meta: Option<CommitMeta>,
+}
+
+/// The fields of a segment in `meta.json`.
+#[derive(Deserialize)]
+struct SegmentEntry {
+    segment_id: SegmentId,
+    max_doc: u32,
+    deletes: Option<DeleteEntry>,
+}
+
+#[derive(Deserialize)]
+struct DeleteEntry {
+    num_deleted_docs: u32,
+    opstamp: u64,
+}
+
+impl Commit {
+    /// Whether all the files of the commit still exist.
+    fn is_available(&self, index: &tv::Index) -> bool {
+        let directory = index.directory();
+        self.segments.iter().all(|segment| {
+            let Ok(entry) =
+                serde_json::from_value::<SegmentEntry>(segment.clone())
+            else {
+                return false;
+            };
+            let mut meta =
+                index.new_segment_meta(entry.segment_id, entry.max_doc);
+            if let Some(deletes) = entry.deletes {
+                meta = meta.with_delete_meta(
+                    deletes.num_deleted_docs,
+                    deletes.opstamp,
+                );
+            }
+            let available = integrity::segment_files(&meta)
+                .all(|path| directory.exists(&path).unwrap_or(false));
+            available
+        })
+    }
+}
+
+fn read_meta(index: &tv::Index) -> PyResult<serde_json::Value> {
+    let bytes = index
+        .directory()
+        .atomic_read(Path::new(META_FILEPATH))
+        .map_err(tantivy_err)?;
+    serde_json::from_slice(&bytes).map_err(to_pyerr)
+}
+
+fn load(index: &tv::Index) -> PyResult<Vec<Commit>> {
+    match sidecar::read(index, HISTORY_PATH)? {
+        Some(bytes) => serde_json::from_slice(&bytes).map_err(to_pyerr),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Add the last commit of the index to its history, with its metadata, and
+/// drop the commits whose files were garbage collected.
+pub(crate) fn record(
+    index: &tv::Index,
+    commit_meta: Option<CommitMeta>,
+) -> PyResult<()> {
+    let mut last: Commit =
+        serde_json::from_value(read_meta(index)?).map_err(to_pyerr)?;
+    last.commit_meta = commit_meta;
+    let mut commits = load(index)?;
+    commits.retain(|commit| {
+        commit.opstamp != last.opstamp && commit.is_available(index)
+    });
+    commits.push(last);
+    let excess = commits.len().saturating_sub(MAX_COMMITS);
+    commits.drain(..excess);
+    let bytes = serde_json::to_vec(&commits).map_err(to_pyerr)?;
+    sidecar::write(index, HISTORY_PATH, &bytes)
+}
+
+/// The content `meta.json` had after the commit with the given opstamp,
+/// None if the commit isn't in the history.
+pub(crate) fn meta_at(
+    index: &tv::Index,
+    opstamp: u64,
+) -> PyResult<Option<Vec<u8>>> {
+    let mut meta = read_meta(index)?;
+    if meta["opstamp"] != opstamp {
+        let Some(commit) = load(index)?
+            .into_iter()
+            .find(|commit| commit.opstamp == opstamp)
+        else {
+            return Ok(None);
+        };
+        meta["opstamp"] = commit.opstamp.into();
+        meta["payload"] = commit.payload.into();
+        meta["segments"] = commit.segments.into();
+    }
+    serde_json::to_vec(&meta).map(Some).map_err(to_pyerr)
+}
+
+/// The metadata stored with a commit, the last one if `opstamp` is None.
+///
+/// The last commit has no metadata if it isn't in the history, e.g. if it
+/// was made by another program.
+///
+/// Raises a ValueError if a past commit isn't in the history.
+pub(crate) fn commit_meta(
+    index: &tv::Index,
+    opstamp: Option<u64>,
+) -> PyResult<Option<CommitMeta>> {
+    let last = index.load_metas().map_err(tantivy_err)?.opstamp;
+    let opstamp = opstamp.unwrap_or(last);
+    match load(index)?
+        .into_iter()
+        .find(|commit| commit.opstamp == opstamp)
+    {
+        Some(commit) => Ok(commit.commit_meta),
+        None if opstamp == last => Ok(None),
+        None => Err(PyValueError::new_err(format!(
+            "The commit {opstamp} isn't recorded in the index."
+        ))),
+    }
+}
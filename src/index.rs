@@ -1,99 +1,664 @@
 #![allow(clippy::new_ret_no_self)]
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
-use pyo3::{exceptions, prelude::*, types::PyAny};
+use pyo3::{
+    exceptions,
+    prelude::*,
+    types::{PyAny, PyBytes, PyDict},
+};
 
 use crate::{
-    document::{extract_value, Document},
-    get_field,
+    activity::{Activity, RecordingMergePolicy},
+    analysis,
+    auto_commit::AutoCommit,
+    common_terms::drop_common_terms,
+    compaction::{Compaction, CompactionPolicy},
+    document::{extract_value, extract_value_for_type, Document},
+    errors::{
+        is_incompatible_index, query_parser_err, schema_err, tantivy_err,
+    },
+    field_alias, get_field, history, integrity, metrics,
     parser_error::QueryParserErrorIntoPy,
+    paths, pythonize,
     query::Query,
-    schema::Schema,
-    searcher::Searcher,
+    query_cache::QueryCache,
+    query_escape, query_validation,
+    read_mode::open_directory,
+    read_only::{MetaOverride, ReadOnlyDirectory},
+    schema::{Schema, SchemaExtensions},
+    searcher::{fast_field_values, Order, SearchLimits, Searcher},
+    signals::{self, SignalStore, Signals},
+    signature, simple_query,
+    spell::SpellCorrector,
+    stats,
+    suggest::Suggester,
+    tenant::Tenant,
+    throttle::{check_mb_per_sec, RateLimiter, ThrottledDirectory},
     to_pyerr,
 };
 use tantivy as tv;
 use tantivy::{
-    directory::MmapDirectory,
+    directory::{error::LockError, MmapDirectory, RamDirectory},
+    indexer::UserOperation,
     schema::{
-        document::TantivyDocument, NamedFieldDocument, OwnedValue as Value,
-        Term,
+        document::TantivyDocument, Field, NamedFieldDocument,
+        OwnedValue as Value, Term,
     },
+    store::{Compressor, ZstdCompressor},
     tokenizer::{
         Language, LowerCaser, RemoveLongFilter, SimpleTokenizer, Stemmer,
         TextAnalyzer,
     },
+    Directory,
 };
+// Bring the trait into scope for the `to_named_doc` method, without shadowing
+// the `Document` class of tantivy-py.
+use tantivy::Document as _;
 
 const RELOAD_POLICY: &str = "commit";
 
+/// Default number of doc store blocks cached by a segment reader, as in
+/// tantivy.
+const DOC_STORE_CACHE_NUM_BLOCKS: usize = 100;
+
+/// Number of documents processed between two progress reports.
+const PROGRESS_STEP: usize = 1_000;
+
+/// How long to sleep between attempts to acquire a busy writer lock.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
 /// IndexWriter is the user entry-point to add documents to the index.
 ///
 /// To create an IndexWriter first create an Index and call the writer() method
 /// on the index object.
+///
+/// An IndexWriter can be shared between Python threads. Adding documents
+/// releases the GIL and may run concurrently, while `commit()` and
+/// `rollback()` wait for the pending additions to finish.
+///
+/// An IndexWriter is a context manager. On exit it commits, or rolls back if
+/// the block raised, and then releases the writer as `wait_merging_threads()`
+/// does:
+///
+///     with index.writer() as writer:
+///         writer.add_document(doc)
 #[pyclass(module = "tantivy.tantivy")]
 pub(crate) struct IndexWriter {
-    inner_index_writer: Option<tv::IndexWriter>,
+    shared: Arc<SharedIndexWriter>,
     schema: tv::schema::Schema,
+    extensions: Arc<SchemaExtensions>,
+    /// The tenant the writer is scoped to, see `TenantIndex`.
+    tenant: Option<Arc<Tenant>>,
+    /// The rate limit of the index files, set while the writer holds the
+    /// index lock.
+    limiter: Arc<RateLimiter>,
+}
+
+/// State shared by all the handles of an `IndexWriter`.
+struct SharedIndexWriter {
+    writer: RwLock<Option<tv::IndexWriter>>,
+    /// Number of documents added since the last commit.
+    pending_docs: AtomicU64,
+    last_commit_opstamp: AtomicU64,
+    /// The activity of the index, see `Index.stats`.
+    activity: Arc<Activity>,
+    auto_commit: Mutex<Option<AutoCommit>>,
+    /// The error that stopped the auto commit thread, if any.
+    auto_commit_error: Mutex<Option<tv::TantivyError>>,
+    /// The metadata stored with the next commits, see
+    /// `IndexWriter.set_commit_meta`.
+    commit_meta: Mutex<Option<history::CommitMeta>>,
+    /// Whether documents were added or deleted since the last commit, other
+    /// than by `IndexWriter.update_fast_field`.
+    other_changes: AtomicBool,
+    /// The fast field updates since the last commit.
+    updates: Mutex<FastFieldUpdates>,
+}
+
+/// The documents of the last commit updated by
+/// `IndexWriter.update_fast_field` since, which can't be read back from the
+/// index before the next commit.
+#[derive(Default)]
+struct FastFieldUpdates {
+    /// The last commit, the updated documents are read from.
+    searcher: Option<tv::Searcher>,
+    /// The new version of each updated document, and its updated fields.
+    docs: HashMap<tv::DocAddress, (TantivyDocument, HashSet<Field>)>,
+}
+
+impl SharedIndexWriter {
+    /// Forget the changes of the documents, once committed or rolled back.
+    fn clear_changes(&self) {
+        self.other_changes.store(false, Ordering::SeqCst);
+        *self.updates.lock().unwrap() = FastFieldUpdates::default();
+    }
+
+    /// Commit the writer, returns `None` if the writer was consumed.
+    fn commit(&self) -> Option<tv::Result<u64>> {
+        let start = Instant::now();
+        let (result, num_docs) = {
+            let mut writer = self.writer.write().unwrap();
+            let writer = writer.as_mut()?;
+            let result = writer.commit();
+            let mut num_docs = 0;
+            if let Ok(opstamp) = result {
+                num_docs = self.pending_docs.swap(0, Ordering::SeqCst);
+                self.clear_changes();
+                self.last_commit_opstamp.store(opstamp, Ordering::SeqCst);
+                self.activity.record_commit(num_docs);
+                // The commit went through, it merely can't be opened later.
+                let commit_meta = self.commit_meta.lock().unwrap().clone();
+                if let Err(err) = history::record(writer.index(), commit_meta) {
+                    log::warn!("Failed to record commit {opstamp}: {err}");
+                }
+            }
+            (result, num_docs)
+        };
+        if let Ok(opstamp) = result {
+            metrics::emit(
+                "commit",
+                start,
+                &[("opstamp", opstamp), ("num_docs", num_docs)],
+            );
+        }
+        Some(result)
+    }
+}
+
+fn consumed_writer_err() -> PyErr {
+    exceptions::PyRuntimeError::new_err(
+        "IndexWriter was consumed and no longer in a valid state",
+    )
 }
 
 impl IndexWriter {
-    fn inner(&self) -> PyResult<&tv::IndexWriter> {
-        self.inner_index_writer.as_ref().ok_or_else(|| {
-            exceptions::PyRuntimeError::new_err(
-                "IndexWriter was consumed and no longer in a valid state",
-            )
-        })
+    fn new(
+        writer: tv::IndexWriter,
+        schema: tv::schema::Schema,
+        extensions: Arc<SchemaExtensions>,
+        limiter: Arc<RateLimiter>,
+        activity: Arc<Activity>,
+    ) -> Self {
+        writer.set_merge_policy(Box::new(RecordingMergePolicy {
+            inner: writer.get_merge_policy(),
+            activity: activity.clone(),
+        }));
+        let last_commit_opstamp = AtomicU64::new(writer.commit_opstamp());
+        IndexWriter {
+            shared: Arc::new(SharedIndexWriter {
+                writer: RwLock::new(Some(writer)),
+                pending_docs: AtomicU64::new(0),
+                last_commit_opstamp,
+                activity,
+                auto_commit: Mutex::new(None),
+                auto_commit_error: Mutex::new(None),
+                commit_meta: Mutex::new(None),
+                other_changes: AtomicBool::new(false),
+                updates: Mutex::new(FastFieldUpdates::default()),
+            }),
+            schema,
+            extensions,
+            tenant: None,
+            limiter,
+        }
     }
 
-    fn inner_mut(&mut self) -> PyResult<&mut tv::IndexWriter> {
-        self.inner_index_writer.as_mut().ok_or_else(|| {
-            exceptions::PyRuntimeError::new_err(
-                "IndexWriter was consumed and no longer in a valid state",
-            )
-        })
+    /// Scope the writer to a tenant: its value is stamped on the added
+    /// documents, and deletes only apply to its documents.
+    pub(crate) fn with_tenant(self, tenant: Arc<Tenant>) -> Self {
+        IndexWriter {
+            tenant: Some(tenant),
+            ..self
+        }
+    }
+
+    /// Run `f` with shared access to the underlying writer.
+    fn with_inner<R>(
+        &self,
+        f: impl FnOnce(&tv::IndexWriter) -> R,
+    ) -> PyResult<R> {
+        let inner = self.shared.writer.read().unwrap();
+        inner.as_ref().map(f).ok_or_else(consumed_writer_err)
     }
 
-    fn take_inner(&mut self) -> PyResult<tv::IndexWriter> {
-        self.inner_index_writer.take().ok_or_else(|| {
-            exceptions::PyRuntimeError::new_err(
-                "IndexWriter was consumed and no longer in a valid state",
+    /// Run `f` with exclusive access to the underlying writer.
+    fn with_inner_mut<R>(
+        &self,
+        f: impl FnOnce(&mut tv::IndexWriter) -> R,
+    ) -> PyResult<R> {
+        let mut inner = self.shared.writer.write().unwrap();
+        inner.as_mut().map(f).ok_or_else(consumed_writer_err)
+    }
+
+    fn take_inner(&self) -> PyResult<tv::IndexWriter> {
+        self.shared.auto_commit.lock().unwrap().take();
+        let mut inner = self.shared.writer.write().unwrap();
+        inner.take().ok_or_else(consumed_writer_err)
+    }
+
+    /// Stamp the tenant of the writer on a document, and add its content
+    /// signatures if the schema has signature fields.
+    fn prepare(&self, doc: &mut TantivyDocument) -> PyResult<()> {
+        if self.extensions.signature_fields.is_empty() {
+            return self.prepare_with(None, doc);
+        }
+        self.with_inner(|inner| self.prepare_with(Some(inner), doc))?
+    }
+
+    /// `prepare`, with the underlying writer if the documents are signed.
+    fn prepare_with(
+        &self,
+        inner: Option<&tv::IndexWriter>,
+        doc: &mut TantivyDocument,
+    ) -> PyResult<()> {
+        if let Some(tenant) = &self.tenant {
+            tenant.stamp(doc)?;
+        }
+        match inner {
+            Some(inner) => signature::sign_document(
+                inner.index(),
+                &self.schema,
+                &self.extensions,
+                doc,
             )
+            .map_err(tantivy_err),
+            None => Ok(()),
+        }
+    }
+
+    fn add_tantivy_document(&self, mut doc: TantivyDocument) -> PyResult<u64> {
+        self.prepare(&mut doc)?;
+        let opstamp = self.with_inner(|inner| {
+            let opstamp = inner.add_document(doc)?;
+            self.shared.other_changes.store(true, Ordering::SeqCst);
+            self.shared.pending_docs.fetch_add(1, Ordering::SeqCst);
+            tv::Result::Ok(opstamp)
+        })?;
+        self.notify_auto_commit();
+        opstamp.map_err(tantivy_err)
+    }
+
+    /// Add documents that must stay contiguous in the same segment.
+    fn add_tantivy_block(
+        &self,
+        mut docs: Vec<TantivyDocument>,
+    ) -> PyResult<u64> {
+        for doc in &mut docs {
+            self.prepare(doc)?;
+        }
+        let num_docs = docs.len() as u64;
+        let opstamp = self.with_inner(|inner| {
+            let opstamp =
+                inner.run(docs.into_iter().map(UserOperation::Add))?;
+            self.shared.other_changes.store(true, Ordering::SeqCst);
+            self.shared
+                .pending_docs
+                .fetch_add(num_docs, Ordering::SeqCst);
+            tv::Result::Ok(opstamp)
+        })?;
+        self.notify_auto_commit();
+        opstamp.map_err(tantivy_err)
+    }
+
+    /// Wake the auto commit thread up if enough documents are pending.
+    fn notify_auto_commit(&self) {
+        if let Some(auto_commit) =
+            self.shared.auto_commit.lock().unwrap().as_ref()
+        {
+            let pending_docs = self.shared.pending_docs.load(Ordering::SeqCst);
+            if auto_commit
+                .max_docs()
+                .is_some_and(|max| pending_docs >= max)
+            {
+                auto_commit.notify();
+            }
+        }
+    }
+
+    /// Add a batch of documents without holding the GIL.
+    fn add_named_documents(
+        &self,
+        py: Python,
+        named_docs: Vec<NamedFieldDocument>,
+    ) -> PyResult<()> {
+        py.allow_threads(|| {
+            for named_doc in named_docs {
+                let doc =
+                    TantivyDocument::convert_named_doc(&self.schema, named_doc)
+                        .map_err(to_pyerr)?;
+                self.add_tantivy_document(doc)?;
+            }
+            Ok(())
         })
     }
+
+    /// Add the documents of a batch of newline-delimited JSON objects.
+    ///
+    /// Returns the number of documents added.
+    fn add_json_batch(&self, batch: &[u8]) -> PyResult<usize> {
+        let mut num_docs = 0;
+        for (i, line) in batch.split(|&byte| byte == b'\n').enumerate() {
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            // Formatted here, as formatting a PyErr takes the GIL.
+            let doc = std::str::from_utf8(line)
+                .map_err(|err| err.to_string())
+                .and_then(|json| {
+                    TantivyDocument::parse_json(&self.schema, json)
+                        .map_err(|err| err.to_string())
+                })
+                .map_err(|err| {
+                    exceptions::PyValueError::new_err(format!(
+                        "Line {}: {err}",
+                        i + 1
+                    ))
+                })?;
+            self.add_tantivy_document(doc)?;
+            num_docs += 1;
+        }
+        Ok(num_docs)
+    }
+
+    /// Raise the error that stopped the auto commit thread, if any.
+    fn check_auto_commit_error(&self) -> PyResult<()> {
+        match self.shared.auto_commit_error.lock().unwrap().take() {
+            Some(err) => Err(tantivy_err(err)),
+            None => Ok(()),
+        }
+    }
 }
 
 #[pymethods]
 impl IndexWriter {
     /// Add a document to the index.
     ///
-    /// If the indexing pipeline is full, this call may block.
+    /// If the indexing pipeline is full, this call may block. The GIL is
+    /// released while the document is added.
     ///
     /// Returns an `opstamp`, which is an increasing integer that can be used
     /// by the client to align commits with its own document queue.
     /// The `opstamp` represents the number of documents that have been added
     /// since the creation of the index.
-    pub fn add_document(&mut self, doc: &Document) -> PyResult<u64> {
+    pub fn add_document(&self, py: Python, doc: &Document) -> PyResult<u64> {
         let named_doc = NamedFieldDocument(doc.field_values.clone());
-        let doc = TantivyDocument::convert_named_doc(&self.schema, named_doc)
-            .map_err(to_pyerr)?;
-        self.inner()?.add_document(doc).map_err(to_pyerr)
+        py.allow_threads(|| {
+            let doc =
+                TantivyDocument::convert_named_doc(&self.schema, named_doc)
+                    .map_err(to_pyerr)?;
+            self.add_tantivy_document(doc)
+        })
+    }
+
+    /// Add a block of documents made of children followed by their parent.
+    ///
+    /// The documents of a block are given contiguous ids in the same
+    /// segment, which is what `Query.block_join_query` relies on to join
+    /// children to their parent. A block must therefore be deleted or
+    /// updated as a whole, and the parent must be the last document.
+    ///
+    /// The GIL is released while the block is added.
+    ///
+    /// Args:
+    ///     docs (List[Document]): The children, followed by their parent.
+    ///
+    /// Returns the `opstamp` of the last document of the block.
+    ///
+    /// Raises a ValueError if the block is empty.
+    fn add_document_block(
+        &self,
+        py: Python,
+        docs: Vec<PyRef<Document>>,
+    ) -> PyResult<u64> {
+        if docs.is_empty() {
+            return Err(exceptions::PyValueError::new_err(
+                "A document block cannot be empty.",
+            ));
+        }
+        let named_docs: Vec<_> = docs
+            .iter()
+            .map(|doc| NamedFieldDocument(doc.field_values.clone()))
+            .collect();
+        py.allow_threads(|| {
+            let docs = named_docs
+                .into_iter()
+                .map(|named_doc| {
+                    TantivyDocument::convert_named_doc(&self.schema, named_doc)
+                        .map_err(to_pyerr)
+                })
+                .collect::<PyResult<Vec<_>>>()?;
+            self.add_tantivy_block(docs)
+        })
+    }
+
+    /// Add all the documents of an iterable to the index.
+    ///
+    /// The GIL is released while batches of documents are added.
+    ///
+    /// Args:
+    ///     docs (Iterable[Document]): The documents to add.
+    ///     on_progress (Callable[[int, Optional[int]], None], optional): A
+    ///         callback called periodically with the number of documents
+    ///         added so far and the total number of documents, or None if
+    ///         `docs` has no length.
+    ///
+    /// Returns the number of documents that were added.
+    #[pyo3(signature = (docs, on_progress = None))]
+    fn add_documents(
+        &self,
+        py: Python,
+        docs: &Bound<PyAny>,
+        on_progress: Option<&Bound<PyAny>>,
+    ) -> PyResult<usize> {
+        let total = docs.len().ok();
+        let mut done = 0;
+        let mut batch = Vec::with_capacity(PROGRESS_STEP);
+        for doc in docs.try_iter()? {
+            let doc = doc?.extract::<PyRef<Document>>()?;
+            batch.push(NamedFieldDocument(doc.field_values.clone()));
+            if batch.len() == PROGRESS_STEP {
+                done += batch.len();
+                self.add_named_documents(py, std::mem::take(&mut batch))?;
+                report_progress(on_progress, done, total)?;
+            }
+        }
+        done += batch.len();
+        self.add_named_documents(py, batch)?;
+        report_progress(on_progress, done, total)?;
+        Ok(done)
+    }
+
+    /// Add batches of serialized documents, parsed and indexed by a
+    /// background thread.
+    ///
+    /// Python only has to produce the batches: a Rust thread takes them from
+    /// a bounded queue, parses them and adds their documents without the
+    /// GIL. When the queue is full, pulling the next batch from `batches`
+    /// waits for the thread to catch up.
+    ///
+    /// Args:
+    ///     batches (Iterable[Union[bytes, str]]): The batches, each holding
+    ///         documents as newline-delimited JSON objects, in the format of
+    ///         `add_json`.
+    ///     queue_size (int, optional): The number of batches waiting to be
+    ///         indexed before the producer is blocked. Defaults to 4.
+    ///
+    /// Returns the number of documents that were added.
+    ///
+    /// Raises a ValueError naming the index of the failing batch in
+    /// `batches` if one can't be added, with the original error as cause.
+    /// The batches before it are added, the ones after it are not pulled.
+    #[pyo3(signature = (batches, queue_size = 4))]
+    fn add_documents_parallel(
+        &self,
+        py: Python,
+        batches: &Bound<PyAny>,
+        queue_size: usize,
+    ) -> PyResult<usize> {
+        if queue_size == 0 {
+            return Err(exceptions::PyValueError::new_err(
+                "queue_size must be at least 1.",
+            ));
+        }
+        let (sender, receiver) =
+            mpsc::sync_channel::<(usize, Vec<u8>)>(queue_size);
+        thread::scope(|scope| {
+            let consumer = scope.spawn(move || {
+                let mut num_docs = 0;
+                for (index, batch) in receiver {
+                    match self.add_json_batch(&batch) {
+                        Ok(n) => num_docs += n,
+                        Err(err) => return Err((index, err)),
+                    }
+                }
+                Ok(num_docs)
+            });
+            let produced = (|| {
+                for (index, batch) in batches.try_iter()?.enumerate() {
+                    let batch = batch?;
+                    let batch = match batch.downcast::<PyBytes>() {
+                        Ok(bytes) => bytes.as_bytes().to_vec(),
+                        Err(_) => batch.extract::<String>()?.into_bytes(),
+                    };
+                    let sent = py.allow_threads(|| sender.send((index, batch)));
+                    if sent.is_err() {
+                        // The consumer stopped on an error.
+                        break;
+                    }
+                }
+                PyResult::Ok(())
+            })();
+            drop(sender);
+            let consumed = py.allow_threads(|| consumer.join()).unwrap();
+            match consumed {
+                Ok(num_docs) => produced.map(|()| num_docs),
+                Err((index, err)) => {
+                    let batch_err = exceptions::PyValueError::new_err(format!(
+                        "Failed to add batch {index}: {}",
+                        err.value(py)
+                    ));
+                    batch_err.set_cause(py, Some(err));
+                    Err(batch_err)
+                }
+            }
+        })
+    }
+
+    /// Add all the documents stored in another index to this index.
+    ///
+    /// This is typically used to rebuild an index with a new schema. The
+    /// stored fields of every live document of the searcher are added to
+    /// this writer, fields missing from this writer's schema are dropped.
+    /// Only stored fields can be carried over.
+    ///
+    /// Args:
+    ///     searcher (Searcher): A searcher over the source index.
+    ///     on_progress (Callable[[int, int], None], optional): A callback
+    ///         called periodically with the number of documents added so far
+    ///         and the total number of documents.
+    ///
+    /// Returns the number of documents that were added.
+    #[pyo3(signature = (searcher, on_progress = None))]
+    fn reindex(
+        &self,
+        py: Python,
+        searcher: &Searcher,
+        on_progress: Option<&Bound<PyAny>>,
+    ) -> PyResult<usize> {
+        let searcher = &searcher.inner;
+        let doc_addresses: Vec<tv::DocAddress> = searcher
+            .segment_readers()
+            .iter()
+            .enumerate()
+            .flat_map(|(segment_ord, segment_reader)| {
+                segment_reader.doc_ids_alive().map(move |doc_id| {
+                    tv::DocAddress::new(segment_ord as u32, doc_id)
+                })
+            })
+            .collect();
+        let total = doc_addresses.len();
+
+        let mut done = 0;
+        for chunk in doc_addresses.chunks(PROGRESS_STEP) {
+            py.allow_threads(|| {
+                for doc_address in chunk {
+                    let doc: TantivyDocument =
+                        searcher.doc(*doc_address).map_err(tantivy_err)?;
+                    let named_doc = doc.to_named_doc(searcher.schema());
+                    let doc = TantivyDocument::convert_named_doc(
+                        &self.schema,
+                        named_doc,
+                    )
+                    .map_err(to_pyerr)?;
+                    self.add_tantivy_document(doc)?;
+                }
+                PyResult::Ok(())
+            })?;
+            done += chunk.len();
+            report_progress(on_progress, done, Some(total))?;
+        }
+        if total == 0 {
+            report_progress(on_progress, 0, Some(0))?;
+        }
+        Ok(done)
+    }
+
+    /// Merge all the segments of the last commit into a single segment.
+    ///
+    /// This blocks until the merge is done. The GIL is released while
+    /// merging.
+    ///
+    /// Args:
+    ///     on_progress (Callable[[int, int], None], optional): A callback
+    ///         called with the number of segments merged so far and the
+    ///         number of segments to merge, before and after the merge.
+    #[pyo3(signature = (on_progress = None))]
+    fn merge(
+        &self,
+        py: Python,
+        on_progress: Option<&Bound<PyAny>>,
+    ) -> PyResult<()> {
+        let start = Instant::now();
+        let segment_ids = self
+            .with_inner(|inner| inner.index().searchable_segment_ids())?
+            .map_err(tantivy_err)?;
+        let total = segment_ids.len();
+        report_progress(on_progress, 0, Some(total))?;
+        if total > 1 {
+            let merge =
+                self.with_inner_mut(|inner| inner.merge(&segment_ids))?;
+            self.shared.activity.record_merge();
+            py.allow_threads(|| merge.wait()).map_err(tantivy_err)?;
+            metrics::emit("merge", start, &[("num_segments", total as u64)]);
+        }
+        report_progress(on_progress, total, Some(total))
     }
 
     /// Helper for the `add_document` method, but passing a json string.
     ///
-    /// If the indexing pipeline is full, this call may block.
+    /// If the indexing pipeline is full, this call may block. The GIL is
+    /// released while the document is added.
     ///
     /// Returns an `opstamp`, which is an increasing integer that can be used
     /// by the client to align commits with its own document queue.
     /// The `opstamp` represents the number of documents that have been added
     /// since the creation of the index.
-    pub fn add_json(&mut self, json: &str) -> PyResult<u64> {
-        let doc = TantivyDocument::parse_json(&self.schema, json)
-            .map_err(to_pyerr)?;
-        let opstamp = self.inner()?.add_document(doc);
-        opstamp.map_err(to_pyerr)
+    pub fn add_json(&self, py: Python, json: &str) -> PyResult<u64> {
+        py.allow_threads(|| {
+            let doc = TantivyDocument::parse_json(&self.schema, json)
+                .map_err(to_pyerr)?;
+            self.add_tantivy_document(doc)
+        })
     }
 
     /// Commits all of the pending changes
@@ -105,8 +670,16 @@ impl IndexWriter {
     /// spared), it will be possible to resume indexing from this point.
     ///
     /// Returns the `opstamp` of the last document that made it in the commit.
-    fn commit(&mut self) -> PyResult<u64> {
-        self.inner_mut()?.commit().map_err(to_pyerr)
+    ///
+    /// If a background auto commit failed, its error is raised instead.
+    fn commit(&self, py: Python) -> PyResult<u64> {
+        self.check_auto_commit_error()?;
+        py.allow_threads(|| {
+            self.shared
+                .commit()
+                .ok_or_else(consumed_writer_err)?
+                .map_err(tantivy_err)
+        })
     }
 
     /// Rollback to the last commit
@@ -114,20 +687,40 @@ impl IndexWriter {
     /// This cancels all of the update that happened before after the last
     /// commit. After calling rollback, the index is in the same state as it
     /// was after the last commit.
-    fn rollback(&mut self) -> PyResult<u64> {
-        self.inner_mut()?.rollback().map_err(to_pyerr)
+    fn rollback(&self, py: Python) -> PyResult<u64> {
+        py.allow_threads(|| {
+            self.with_inner_mut(|inner| {
+                self.shared.pending_docs.store(0, Ordering::SeqCst);
+                self.shared.clear_changes();
+                inner.rollback()
+            })?
+            .map_err(tantivy_err)
+        })
     }
 
     /// Detect and removes the files that are not used by the index anymore.
-    fn garbage_collect_files(&mut self) -> PyResult<()> {
+    fn garbage_collect_files(&self, py: Python) -> PyResult<()> {
         use futures::executor::block_on;
-        block_on(self.inner()?.garbage_collect_files()).map_err(to_pyerr)?;
-        Ok(())
+        py.allow_threads(|| {
+            self.with_inner(|inner| block_on(inner.garbage_collect_files()))?
+                .map_err(tantivy_err)?;
+            Ok(())
+        })
     }
 
     /// Deletes all documents from the index.
-    fn delete_all_documents(&mut self) -> PyResult<()> {
-        self.inner()?.delete_all_documents().map_err(to_pyerr)?;
+    ///
+    /// A writer scoped to a tenant only deletes the documents of the tenant.
+    fn delete_all_documents(&self) -> PyResult<()> {
+        self.shared.other_changes.store(true, Ordering::SeqCst);
+        match &self.tenant {
+            Some(tenant) => self
+                .with_inner(|inner| inner.delete_query(tenant.query()))?
+                .map_err(tantivy_err)?,
+            None => self
+                .with_inner(|inner| inner.delete_all_documents())?
+                .map_err(tantivy_err)?,
+        };
         Ok(())
     }
 
@@ -140,7 +733,146 @@ impl IndexWriter {
     /// for searchers.
     #[getter]
     fn commit_opstamp(&self) -> PyResult<u64> {
-        Ok(self.inner()?.commit_opstamp())
+        self.with_inner(|inner| inner.commit_opstamp())
+    }
+
+    /// Set the metadata stored with the commits made through this writer,
+    /// explicitly or by the auto commit thread, e.g. the version of the
+    /// indexing pipeline or the id of the indexed snapshot. It is read back
+    /// with `Index.commit_meta()`, without scanning the documents.
+    ///
+    /// The metadata is stored with the commits in the commit history of the
+    /// index, see `Index.searcher_at`, leaving their payload alone, and
+    /// applies to every following commit until it is replaced.
+    ///
+    /// Args:
+    ///     meta (dict, optional): The metadata, any dictionary serializable
+    ///         to JSON. None stops storing metadata.
+    ///
+    /// Raises a ValueError if the metadata can't be serialized to JSON.
+    #[pyo3(signature = (meta = None))]
+    fn set_commit_meta(&self, meta: Option<&Bound<PyDict>>) -> PyResult<()> {
+        let meta = meta
+            .map(|meta| pythonize::depythonize(meta.as_any()).map_err(to_pyerr))
+            .transpose()?;
+        *self.shared.commit_meta.lock().unwrap() = meta;
+        Ok(())
+    }
+
+    /// The opstamp returned by the last commit made through this writer,
+    /// either explicitly or by the auto commit thread.
+    #[getter]
+    fn last_commit_opstamp(&self) -> u64 {
+        self.shared.last_commit_opstamp.load(Ordering::SeqCst)
+    }
+
+    /// Commit automatically from a background thread.
+    ///
+    /// A commit is made when `interval_secs` elapsed since the last commit,
+    /// or as soon as `max_docs` documents were added since the last commit,
+    /// whichever comes first. No commit is made if no documents were added.
+    ///
+    /// Calling this method again replaces the previous thresholds.
+    ///
+    /// Args:
+    ///     interval_secs (float, optional): The maximum number of seconds
+    ///         between two commits.
+    ///     max_docs (int, optional): The maximum number of documents added
+    ///         between two commits.
+    ///
+    /// If a background commit fails, auto commit stops and the error is
+    /// raised by the next call to `commit()` or `disable_auto_commit()`.
+    ///
+    /// Raises ValueError if neither threshold is provided.
+    #[pyo3(signature = (interval_secs = None, max_docs = None))]
+    fn enable_auto_commit(
+        &self,
+        interval_secs: Option<f64>,
+        max_docs: Option<u64>,
+    ) -> PyResult<()> {
+        let interval = match interval_secs {
+            Some(secs) if !(secs > 0.0 && secs.is_finite()) => {
+                return Err(exceptions::PyValueError::new_err(
+                    "interval_secs must be a positive number of seconds.",
+                ))
+            }
+            Some(secs) => Some(Duration::from_secs_f64(secs)),
+            None => None,
+        };
+        if interval.is_none() && max_docs.is_none() {
+            return Err(exceptions::PyValueError::new_err(
+                "Either interval_secs or max_docs must be provided.",
+            ));
+        }
+        // Make sure the writer was not consumed.
+        self.with_inner(|_| ())?;
+
+        let shared = Arc::downgrade(&self.shared);
+        let auto_commit =
+            AutoCommit::spawn(interval, max_docs, move |interval_elapsed| {
+                let shared = shared.upgrade()?;
+                let pending_docs = shared.pending_docs.load(Ordering::SeqCst);
+                let due = pending_docs > 0
+                    && (interval_elapsed
+                        || max_docs.is_some_and(|max| pending_docs >= max));
+                if !due {
+                    return Some(false);
+                }
+                match shared.commit()? {
+                    Ok(_) => Some(true),
+                    Err(err) => {
+                        *shared.auto_commit_error.lock().unwrap() = Some(err);
+                        None
+                    }
+                }
+            });
+        *self.shared.auto_commit.lock().unwrap() = Some(auto_commit);
+        Ok(())
+    }
+
+    /// The maximum number of megabytes per second the writer writes to the
+    /// index directory, or None if unlimited.
+    ///
+    /// The limit applies to the segments written by commits and merges, and
+    /// can be changed while they are in progress, e.g. lowered during peak
+    /// search traffic.
+    ///
+    /// Setting it raises a ValueError if the limit isn't a positive number.
+    #[getter]
+    fn max_mb_per_sec(&self) -> Option<f64> {
+        match self.limiter.bytes_per_sec() {
+            0 => None,
+            bytes => Some(bytes as f64 / 1_000_000.0),
+        }
+    }
+
+    #[setter]
+    fn set_max_mb_per_sec(&self, max_mb_per_sec: Option<f64>) -> PyResult<()> {
+        let bytes_per_sec = check_mb_per_sec(max_mb_per_sec)?;
+        self.limiter.set_bytes_per_sec(bytes_per_sec);
+        Ok(())
+    }
+
+    /// Stop committing automatically.
+    ///
+    /// Raises the error that stopped the auto commit thread, if any.
+    fn disable_auto_commit(&self) -> PyResult<()> {
+        self.shared.auto_commit.lock().unwrap().take();
+        self.check_auto_commit_error()
+    }
+
+    /// Returns another handle to the same underlying writer.
+    ///
+    /// All the handles share the writer and its index lock, so documents
+    /// added through any of them are published by a single `commit()`.
+    fn clone(&self) -> IndexWriter {
+        IndexWriter {
+            shared: self.shared.clone(),
+            schema: self.schema.clone(),
+            extensions: self.extensions.clone(),
+            tenant: self.tenant.clone(),
+            limiter: self.limiter.clone(),
+        }
     }
 
     /// Delete all documents containing a given term.
@@ -149,51 +881,208 @@ impl IndexWriter {
     ///     field_name (str): The field name for which we want to filter deleted docs.
     ///     field_value (PyAny): Python object with the value we want to filter.
     ///
+    /// A writer scoped to a tenant only deletes the documents of the tenant.
+    ///
     /// If the field_name is not on the schema raises ValueError exception.
     /// If the field_value is not supported raises Exception.
     fn delete_documents(
-        &mut self,
+        &self,
         field_name: &str,
         field_value: &Bound<PyAny>,
     ) -> PyResult<u64> {
         let field = get_field(&self.schema, field_name)?;
         let value = extract_value(field_value)?;
-        let term = match value {
-            Value::Null => {
-                return Err(exceptions::PyValueError::new_err(format!(
-                    "Field `{field_name}` is null type not deletable."
-                )))
-            },
-            Value::Str(text) => Term::from_field_text(field, &text),
-            Value::U64(num) => Term::from_field_u64(field, num),
-            Value::I64(num) => Term::from_field_i64(field, num),
-            Value::F64(num) => Term::from_field_f64(field, num),
-            Value::Date(d) => Term::from_field_date(field, d),
-            Value::Facet(facet) => Term::from_facet(field, &facet),
-            Value::Bytes(_) => {
-                return Err(exceptions::PyValueError::new_err(format!(
-                    "Field `{field_name}` is bytes type not deletable."
-                )))
-            }
-            Value::PreTokStr(_pretok) => {
-                return Err(exceptions::PyValueError::new_err(format!(
-                    "Field `{field_name}` is pretokenized. This is not authorized for delete."
-                )))
-            }
-            Value::Array(_) => {
+        let term = value_term(field, field_name, value)?;
+        self.shared.other_changes.store(true, Ordering::SeqCst);
+        match &self.tenant {
+            Some(tenant) => self
+                .with_inner(|inner| inner.delete_query(tenant.scope(term)))?
+                .map_err(tantivy_err),
+            None => self.with_inner(|inner| inner.delete_term(term)),
+        }
+    }
+
+    /// Set the values of fast fields of the documents holding a term, e.g.
+    /// to update click counts without re-sending whole documents.
+    ///
+    /// The segments of an index are immutable, so the documents can't be
+    /// changed in place: the matching documents of the last commit are
+    /// rebuilt from their stored fields and from the columns of their
+    /// fast fields, their values of the given fields replaced, and the
+    /// documents deleted and added again. The documents already updated
+    /// since the last commit are updated from their pending version, so
+    /// consecutive updates accumulate. As any other change, the update is
+    /// published by the next commit.
+    ///
+    /// Args:
+    ///     term_field (str): The field of the term the documents hold.
+    ///     term_value (Any): The value of the term, as in `delete_documents`.
+    ///     values (Dict[str, Any]): The new value of every updated fast
+    ///         field, replacing all the values of the field.
+    ///
+    /// A writer scoped to a tenant only updates the documents of the tenant.
+    ///
+    /// Returns the number of documents updated.
+    ///
+    /// Raises a ValueError if a field of `values` isn't a fast field, if
+    /// the schema has fields that are neither stored nor fast, whose values
+    /// can't be rebuilt, or if documents were added or deleted since the
+    /// last commit, as they can't be read back before being committed.
+    fn update_fast_field(
+        &self,
+        py: Python,
+        term_field: &str,
+        term_value: &Bound<PyAny>,
+        values: HashMap<String, Bound<PyAny>>,
+    ) -> PyResult<usize> {
+        let unstored: Vec<&str> = self
+            .schema
+            .fields()
+            .filter(|(_, entry)| !entry.is_stored() && !is_rebuildable(entry))
+            .map(|(_, entry)| entry.name())
+            .collect();
+        if !unstored.is_empty() {
+            return Err(exceptions::PyValueError::new_err(format!(
+                "Fields {unstored:?} aren't stored nor fast fields, the \
+                 documents can't be rebuilt to update their fast fields."
+            )));
+        }
+        let mut updates = Vec::with_capacity(values.len());
+        for (field_name, value) in &values {
+            let field = get_field(&self.schema, field_name)?;
+            let entry = self.schema.get_field_entry(field);
+            if !entry.is_fast() {
                 return Err(exceptions::PyValueError::new_err(format!(
-                    "Field `{field_name}` is array type not deletable."
-                )))
+                    "Field `{field_name}` is not a fast field."
+                )));
             }
-            Value::Object(_) => {
-                return Err(exceptions::PyValueError::new_err(format!(
-                    "Field `{field_name}` is json object type not deletable."
-                )))
-            },
-            Value::Bool(b) => Term::from_field_bool(field, b),
-            Value::IpAddr(i) => Term::from_field_ip_addr(field, i)
+            let value_type = entry.field_type().value_type();
+            updates.push((
+                field,
+                extract_value_for_type(value, value_type, field_name)?,
+            ));
+        }
+        let updated_fields: HashSet<Field> =
+            updates.iter().map(|(field, _)| *field).collect();
+        let field = get_field(&self.schema, term_field)?;
+        let term = value_term(field, term_field, extract_value(term_value)?)?;
+        let holds_term = |doc: &TantivyDocument| {
+            doc.get_all(field).any(|value| {
+                value_term(field, term_field, value.clone())
+                    .is_ok_and(|value_term| value_term == term)
+            })
         };
-        Ok(self.inner()?.delete_term(term))
+        let query: Box<dyn tv::query::Query> = match &self.tenant {
+            Some(tenant) => tenant.scope(term.clone()),
+            None => Box::new(tv::query::TermQuery::new(
+                term.clone(),
+                tv::schema::IndexRecordOption::Basic,
+            )),
+        };
+        let signed = !self.extensions.signature_fields.is_empty();
+
+        py.allow_threads(|| {
+            // Everything goes under the same lock, so an auto commit doesn't
+            // publish the delete without the additions, nor forget the
+            // pending versions of the documents before they are committed.
+            self.with_inner(|inner| {
+                if self.shared.other_changes.load(Ordering::SeqCst) {
+                    return Err(exceptions::PyValueError::new_err(
+                        "Documents were added or deleted since the last \
+                         commit, commit them before updating fast fields.",
+                    ));
+                }
+                let mut pending = self.shared.updates.lock().unwrap();
+                let searcher = match &pending.searcher {
+                    Some(searcher) => searcher.clone(),
+                    None => {
+                        let reader: tv::IndexReader = inner
+                            .index()
+                            .reader_builder()
+                            .reload_policy(tv::ReloadPolicy::Manual)
+                            .try_into()
+                            .map_err(tantivy_err)?;
+                        pending.searcher.insert(reader.searcher()).clone()
+                    }
+                };
+                let doc_addresses = searcher
+                    .search(&query, &tv::collector::DocSetCollector)
+                    .map_err(tantivy_err)?;
+
+                // The documents holding the term: those of the last commit,
+                // unless their pending version no longer holds it, and the
+                // pending versions which now hold it.
+                let mut matches = Vec::new();
+                for doc_address in &doc_addresses {
+                    match pending.docs.get(doc_address) {
+                        Some((doc, changed))
+                            if changed.contains(&field) && !holds_term(doc) => {
+                        }
+                        Some((doc, changed)) => matches.push((
+                            *doc_address,
+                            doc.clone(),
+                            changed.clone(),
+                        )),
+                        None => matches.push((
+                            *doc_address,
+                            rebuild_document(&searcher, *doc_address)
+                                .map_err(tantivy_err)?,
+                            HashSet::new(),
+                        )),
+                    }
+                }
+                for (doc_address, (doc, changed)) in &pending.docs {
+                    let owned = self
+                        .tenant
+                        .as_ref()
+                        .is_none_or(|tenant| tenant.owns(doc));
+                    if changed.contains(&field)
+                        && owned
+                        && holds_term(doc)
+                        && !doc_addresses.contains(doc_address)
+                    {
+                        matches.push((
+                            *doc_address,
+                            doc.clone(),
+                            changed.clone(),
+                        ));
+                    }
+                }
+                if matches.is_empty() {
+                    return Ok(0);
+                }
+
+                let mut docs = Vec::with_capacity(matches.len());
+                for (doc_address, old_doc, mut changed) in matches {
+                    let mut doc = TantivyDocument::new();
+                    for field_value in old_doc.field_values() {
+                        if !updated_fields.contains(&field_value.field()) {
+                            doc.add_field_value(
+                                field_value.field(),
+                                field_value.value().clone(),
+                            );
+                        }
+                    }
+                    for (field, value) in &updates {
+                        doc.add_field_value(*field, value.clone());
+                    }
+                    self.prepare_with(signed.then_some(inner), &mut doc)?;
+                    changed.extend(&updated_fields);
+                    pending.docs.insert(doc_address, (doc.clone(), changed));
+                    docs.push(doc);
+                }
+                let num_docs = docs.len();
+                inner.delete_query(query).map_err(tantivy_err)?;
+                inner
+                    .run(docs.into_iter().map(UserOperation::Add))
+                    .map_err(tantivy_err)?;
+                self.shared
+                    .pending_docs
+                    .fetch_add(num_docs as u64, Ordering::SeqCst);
+                Ok(num_docs)
+            })?
+        })
+        .inspect(|_| self.notify_auto_commit())
     }
 
     /// If there are some merging threads, blocks until they all finish
@@ -201,8 +1090,281 @@ impl IndexWriter {
     ///
     /// This will consume the `IndexWriter`. Further accesses to the
     /// object will result in an error.
-    pub fn wait_merging_threads(&mut self) -> PyResult<()> {
-        self.take_inner()?.wait_merging_threads().map_err(to_pyerr)
+    pub fn wait_merging_threads(&self, py: Python) -> PyResult<()> {
+        let inner = self.take_inner()?;
+        py.allow_threads(|| inner.wait_merging_threads().map_err(tantivy_err))
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    /// Commit, or roll back if the block raised, and release the writer.
+    /// Nothing is done if the writer was already consumed in the block.
+    #[pyo3(signature = (exc_type = None, _exc_value = None, _traceback = None))]
+    fn __exit__(
+        &self,
+        py: Python,
+        exc_type: Option<&Bound<PyAny>>,
+        _exc_value: Option<&Bound<PyAny>>,
+        _traceback: Option<&Bound<PyAny>>,
+    ) -> PyResult<bool> {
+        if self.shared.writer.read().unwrap().is_none() {
+            return Ok(false);
+        }
+        let result = match exc_type {
+            None => self.commit(py),
+            Some(_) => self.rollback(py),
+        };
+        let released = self.wait_merging_threads(py);
+        result?;
+        released?;
+        Ok(false)
+    }
+}
+
+/// Whether the values of a field that isn't stored can be read back from
+/// its column, to rebuild the documents in `IndexWriter.update_fast_field`.
+fn is_rebuildable(entry: &tv::schema::FieldEntry) -> bool {
+    let value_type = entry.field_type().value_type();
+    (entry.is_fast() || value_type == tv::schema::Type::Facet)
+        && value_type != tv::schema::Type::Json
+}
+
+/// A document of a searcher, with its stored fields and the values of the
+/// fields that aren't stored read from their columns.
+fn rebuild_document(
+    searcher: &tv::Searcher,
+    doc_address: tv::DocAddress,
+) -> tv::Result<TantivyDocument> {
+    let mut doc: TantivyDocument = searcher.doc(doc_address)?;
+    let segment = searcher.segment_reader(doc_address.segment_ord);
+    for (field, entry) in searcher.schema().fields() {
+        if entry.is_stored() {
+            continue;
+        }
+        for value in fast_field_values(segment, entry, doc_address.doc_id)? {
+            doc.add_field_value(field, value);
+        }
+    }
+    Ok(doc)
+}
+
+/// The term of a field value, for the values that can be deleted by.
+fn value_term(field: Field, field_name: &str, value: Value) -> PyResult<Term> {
+    let term = match value {
+        Value::Null => {
+            return Err(exceptions::PyValueError::new_err(format!(
+                "Field `{field_name}` is null type not deletable."
+            )))
+        },
+        Value::Str(text) => Term::from_field_text(field, &text),
+        Value::U64(num) => Term::from_field_u64(field, num),
+        Value::I64(num) => Term::from_field_i64(field, num),
+        Value::F64(num) => Term::from_field_f64(field, num),
+        Value::Date(d) => Term::from_field_date(field, d),
+        Value::Facet(facet) => Term::from_facet(field, &facet),
+        Value::Bytes(_) => {
+            return Err(exceptions::PyValueError::new_err(format!(
+                "Field `{field_name}` is bytes type not deletable."
+            )))
+        }
+        Value::PreTokStr(_pretok) => {
+            return Err(exceptions::PyValueError::new_err(format!(
+                "Field `{field_name}` is pretokenized. This is not authorized for delete."
+            )))
+        }
+        Value::Array(_) => {
+            return Err(exceptions::PyValueError::new_err(format!(
+                "Field `{field_name}` is array type not deletable."
+            )))
+        }
+        Value::Object(_) => {
+            return Err(exceptions::PyValueError::new_err(format!(
+                "Field `{field_name}` is json object type not deletable."
+            )))
+        },
+        Value::Bool(b) => Term::from_field_bool(field, b),
+        Value::IpAddr(i) => Term::from_field_ip_addr(field, i)
+    };
+    Ok(term)
+}
+
+/// File paths as sorted strings.
+fn sorted_paths(paths: HashSet<PathBuf>) -> Vec<String> {
+    let mut paths: Vec<String> = paths
+        .iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Call an optional progress callback with `(done, total)`.
+fn report_progress(
+    on_progress: Option<&Bound<PyAny>>,
+    done: usize,
+    total: Option<usize>,
+) -> PyResult<()> {
+    if let Some(on_progress) = on_progress {
+        on_progress.call1((done, total))?;
+    }
+    Ok(())
+}
+
+/// Metadata describing the last commit of an index.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+#[derive(Clone)]
+pub(crate) struct IndexMeta {
+    /// The opstamp of the last commit.
+    #[pyo3(get)]
+    opstamp: u64,
+    /// The payload attached to the last commit, if any.
+    #[pyo3(get)]
+    payload: Option<String>,
+    /// The number of segments in the last commit.
+    #[pyo3(get)]
+    num_segments: usize,
+}
+
+#[pymethods]
+impl IndexMeta {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "IndexMeta(opstamp={}, payload={:?}, num_segments={})",
+            self.opstamp, self.payload, self.num_segments
+        ))
+    }
+}
+
+/// Settings of an index, fixed when the index is created.
+///
+/// Args:
+///     sort_by (Tuple[str, Order], optional): A `(field_name, order)` tuple
+///         sorting the documents of every segment by a fast field. Searches
+///         ordered by this field can then stop early. Defaults to the order
+///         the documents were added in.
+///     docstore_compression (str, optional): The codec compressing the
+///         stored fields, one of 'lz4', 'zstd' or 'none'. Defaults to 'lz4'.
+///     docstore_compression_level (int, optional): The zstd compression
+///         level, between 1 and 22. Higher levels compress better but
+///         slower. Defaults to zstd's default level, 3.
+///     docstore_blocksize (int, optional): The size in bytes of the blocks
+///         of stored fields compressed together. Smaller blocks make the
+///         retrieval of a single document faster, larger ones compress
+///         better. Defaults to 16384.
+///
+/// Raises a ValueError if the compression codec is unknown, or if a
+/// compression level is given for another codec than 'zstd'.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+#[derive(Clone)]
+pub(crate) struct IndexSettings {
+    pub(crate) inner: tv::IndexSettings,
+}
+
+#[pymethods]
+impl IndexSettings {
+    #[new]
+    #[pyo3(signature = (sort_by = None, docstore_compression = "lz4", docstore_blocksize = 16_384, docstore_compression_level = None))]
+    // Index sorting is deprecated upstream, it is kept until tantivy drops it.
+    #[allow(deprecated)]
+    fn new(
+        sort_by: Option<(String, Order)>,
+        docstore_compression: &str,
+        docstore_blocksize: usize,
+        docstore_compression_level: Option<i32>,
+    ) -> PyResult<Self> {
+        let docstore_compression =
+            match (docstore_compression, docstore_compression_level) {
+                ("zstd", Some(level)) if !(1..=22).contains(&level) => {
+                    return Err(exceptions::PyValueError::new_err(
+                        "docstore_compression_level must be between 1 and 22.",
+                    ))
+                }
+                ("zstd", compression_level) => {
+                    Compressor::Zstd(ZstdCompressor { compression_level })
+                }
+                (_, Some(_)) => {
+                    return Err(exceptions::PyValueError::new_err(
+                        "docstore_compression_level can only be used with \
+                         the 'zstd' compression.",
+                    ))
+                }
+                ("lz4", None) => Compressor::Lz4,
+                ("none", None) => Compressor::None,
+                _ => {
+                    return Err(exceptions::PyValueError::new_err(
+                        "Invalid docstore compression, valid choices are: \
+                         'lz4', 'zstd' and 'none'",
+                    ))
+                }
+            };
+        if docstore_blocksize == 0 {
+            return Err(exceptions::PyValueError::new_err(
+                "docstore_blocksize must be positive.",
+            ));
+        }
+        let sort_by_field =
+            sort_by.map(|(field, order)| tv::IndexSortByField {
+                field,
+                order: order.into(),
+            });
+        Ok(IndexSettings {
+            inner: tv::IndexSettings {
+                sort_by_field,
+                docstore_compression,
+                docstore_blocksize,
+                ..Default::default()
+            },
+        })
+    }
+
+    /// The `(field_name, order)` tuple the documents are sorted by, if any.
+    #[getter]
+    #[allow(deprecated)]
+    fn sort_by(&self) -> Option<(String, Order)> {
+        self.inner.sort_by_field.as_ref().map(|sort_by| {
+            let order = match sort_by.order {
+                tv::Order::Asc => Order::Asc,
+                tv::Order::Desc => Order::Desc,
+            };
+            (sort_by.field.clone(), order)
+        })
+    }
+
+    /// The codec compressing the stored fields.
+    #[getter]
+    fn docstore_compression(&self) -> &'static str {
+        match self.inner.docstore_compression {
+            Compressor::None => "none",
+            Compressor::Lz4 => "lz4",
+            Compressor::Zstd(_) => "zstd",
+        }
+    }
+
+    /// The zstd compression level, if one was set.
+    #[getter]
+    fn docstore_compression_level(&self) -> Option<i32> {
+        match self.inner.docstore_compression {
+            Compressor::Zstd(zstd) => zstd.compression_level,
+            _ => None,
+        }
+    }
+
+    /// The size in bytes of the compressed blocks of stored fields.
+    #[getter]
+    fn docstore_blocksize(&self) -> usize {
+        self.inner.docstore_blocksize
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "IndexSettings(docstore_compression='{}', docstore_compression_level={}, docstore_blocksize={})",
+            self.docstore_compression(),
+            self.docstore_compression_level()
+                .map_or("None".to_string(), |level| level.to_string()),
+            self.inner.docstore_blocksize
+        )
     }
 }
 
@@ -210,10 +1372,17 @@ impl IndexWriter {
 ///
 /// Args:
 ///     schema (Schema): The schema of the index.
-///     path (str, optional): The path where the index should be stored. If
-///         no path is provided, the index will be stored in memory.
+///     path (Union[str, os.PathLike], optional): The path where the index
+///         should be stored. If no path is provided, the index will be stored
+///         in memory.
 ///     reuse (bool, optional): Should we open an existing index if one exists
 ///         or always create a new one.
+///     settings (IndexSettings, optional): The settings of the index if it
+///         is created. An existing index keeps its own settings.
+///     read_mode (str, optional): How the files of the index are read, see
+///         `Index.open`. Only applies to indexes stored in a directory.
+///     madvise (str, optional): See `Index.open`.
+///     cache_mb (float, optional): See `Index.open`.
 ///
 /// If an index already exists it will be opened and reused. Raises OSError
 /// if there was a problem during the opening or creation of the index.
@@ -221,44 +1390,201 @@ impl IndexWriter {
 pub(crate) struct Index {
     pub(crate) index: tv::Index,
     reader: tv::IndexReader,
+    /// The directory of the index, below the files management of tantivy.
+    directory: Box<dyn Directory>,
+    extensions: Arc<SchemaExtensions>,
+    /// The ids of the damaged segments the index was opened without.
+    skipped_segments: Vec<String>,
+    search_limits: SearchLimits,
+    /// Cloned into every searcher, see `num_active_searchers`.
+    active_searchers: Arc<()>,
+    /// Cloned into every searcher, see `Searcher.enable_query_cache`.
+    query_cache: Arc<QueryCache>,
+    /// The background compaction, see `start_compaction`.
+    compaction: Mutex<Option<Compaction>>,
+    /// The limit of the rate at which the index files are written, set by
+    /// the writer holding the index lock, see `IndexWriter.max_mb_per_sec`.
+    limiter: Arc<RateLimiter>,
+    /// The fields named by the aliases of the query strings, see
+    /// `set_field_aliases`.
+    pub(crate) field_aliases: HashMap<String, String>,
+    /// Cloned into every searcher, see `signals`.
+    signals: Arc<Signals>,
+    /// Shared with the writers and the compaction, see `stats`.
+    activity: Arc<Activity>,
 }
 
 #[pymethods]
 impl Index {
+    /// Open an existing index.
+    ///
+    /// Args:
+    ///     path (Union[str, os.PathLike]): The directory of the index.
+    ///     on_corruption (str, optional): What to do with the segments that
+    ///         have a missing or damaged file. 'raise' fails to open the
+    ///         index, 'skip_segment' opens it without them, after verifying
+    ///         the checksum of all its files. An index opened without some of
+    ///         its segments is read-only, so that the skipped segments can
+    ///         still be restored. Defaults to 'raise'.
+    ///     read_mode (str, optional): How the files of the index are read.
+    ///         'mmap' memory maps them, leaving their caching to the page
+    ///         cache of the kernel. 'read' reads them with positioned reads
+    ///         through a block cache of the application, for file systems on
+    ///         which memory mapping performs badly, e.g. network storage.
+    ///         Defaults to 'mmap'.
+    ///     madvise (str, optional): The access pattern of the memory mapped
+    ///         files advised to the kernel: 'normal', 'random', 'sequential'
+    ///         or 'willneed'. Only applies to the 'mmap' read mode. Defaults
+    ///         to the kernel default.
+    ///     cache_mb (float, optional): The capacity of the block cache in
+    ///         megabytes. Only applies to the 'read' read mode. Defaults to
+    ///         64.
+    ///
+    /// Raises a ValueError if `on_corruption` or a read option is invalid.
     #[staticmethod]
-    fn open(path: &str) -> PyResult<Index> {
-        let index = tv::Index::open_in_dir(path).map_err(to_pyerr)?;
+    #[pyo3(signature = (path, on_corruption = "raise", read_mode = "mmap", madvise = None, cache_mb = None))]
+    pub(crate) fn open(
+        py: Python,
+        path: PathBuf,
+        on_corruption: &str,
+        read_mode: &str,
+        madvise: Option<&str>,
+        cache_mb: Option<f64>,
+    ) -> PyResult<Index> {
+        let skip_segments =
+            match on_corruption {
+                "raise" => false,
+                "skip_segment" => true,
+                _ => return Err(exceptions::PyValueError::new_err(
+                    "Invalid on_corruption, valid choices are: 'raise' and \
+                     'skip_segment'",
+                )),
+            };
+        let directory = open_directory(
+            &paths::normalize(path),
+            read_mode,
+            madvise,
+            cache_mb,
+        )?;
+        let limiter = Arc::new(RateLimiter::new(0));
+        let mut index = tv::Index::open(ThrottledDirectory::new(
+            directory.clone(),
+            limiter.clone(),
+        ))
+        .map_err(tantivy_err)?;
+        let mut skipped_segments = Vec::new();
+        if skip_segments {
+            let damaged = py
+                .allow_threads(|| integrity::damaged_segments(&index))
+                .map_err(tantivy_err)?;
+            if !damaged.is_empty() {
+                skipped_segments =
+                    damaged.iter().map(|id| id.uuid_string()).collect();
+                skipped_segments.sort();
+                index = tv::Index::open(ReadOnlyDirectory::new(
+                    directory.clone(),
+                    MetaOverride::SkipSegments(Arc::new(damaged)),
+                ))
+                .map_err(tantivy_err)?;
+            }
+        }
 
         Index::register_custom_text_analyzers(&index);
 
-        let reader = index.reader().map_err(to_pyerr)?;
-        Ok(Index { index, reader })
+        let reader =
+            py.allow_threads(|| index.reader()).map_err(tantivy_err)?;
+        let extensions = Arc::new(SchemaExtensions::load(&index)?);
+        let signals = Arc::new(Signals::load(&index)?);
+        Ok(Index {
+            index,
+            reader,
+            directory,
+            extensions,
+            skipped_segments,
+            search_limits: SearchLimits::default(),
+            active_searchers: Arc::default(),
+            query_cache: Arc::default(),
+            compaction: Mutex::default(),
+            limiter,
+            field_aliases: HashMap::new(),
+            signals,
+            activity: Arc::default(),
+        })
     }
 
     #[new]
-    #[pyo3(signature = (schema, path = None, reuse = true))]
-    fn new(schema: &Schema, path: Option<&str>, reuse: bool) -> PyResult<Self> {
-        let index = match path {
-            Some(p) => {
-                let directory = MmapDirectory::open(p).map_err(to_pyerr)?;
-                if reuse {
-                    tv::Index::open_or_create(directory, schema.inner.clone())
-                } else {
-                    tv::Index::create(
-                        directory,
-                        schema.inner.clone(),
-                        tv::IndexSettings::default(),
-                    )
-                }
-                .map_err(to_pyerr)?
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (schema, path = None, reuse = true, settings = None, read_mode = "mmap", madvise = None, cache_mb = None))]
+    fn new(
+        py: Python,
+        schema: &Schema,
+        path: Option<PathBuf>,
+        reuse: bool,
+        settings: Option<&IndexSettings>,
+        read_mode: &str,
+        madvise: Option<&str>,
+        cache_mb: Option<f64>,
+    ) -> PyResult<Self> {
+        let settings = settings
+            .map(|settings| settings.inner.clone())
+            .unwrap_or_default();
+        let directory: Box<dyn Directory> = match &path {
+            Some(p) => open_directory(
+                &paths::normalize(p.clone()),
+                read_mode,
+                madvise,
+                cache_mb,
+            )?,
+            None if read_mode != "mmap"
+                || madvise.is_some()
+                || cache_mb.is_some() =>
+            {
+                return Err(exceptions::PyValueError::new_err(
+                    "The read options only apply to indexes stored in a \
+                     directory.",
+                ))
             }
-            None => tv::Index::create_in_ram(schema.inner.clone()),
+            None => Box::new(RamDirectory::create()),
         };
+        let limiter = Arc::new(RateLimiter::new(0));
+        let throttled =
+            ThrottledDirectory::new(directory.clone(), limiter.clone());
+        let index = if reuse && path.is_some() {
+            tv::Index::builder()
+                .schema(schema.inner.clone())
+                .settings(settings)
+                .open_or_create(throttled)
+        } else {
+            tv::Index::create(throttled, schema.inner.clone(), settings)
+        }
+        .map_err(tantivy_err)?;
 
         Index::register_custom_text_analyzers(&index);
 
-        let reader = index.reader().map_err(to_pyerr)?;
-        Ok(Index { index, reader })
+        let reader =
+            py.allow_threads(|| index.reader()).map_err(tantivy_err)?;
+        let extensions = if schema.extensions.is_empty() {
+            SchemaExtensions::load(&index)?
+        } else {
+            schema.extensions.save(&index)?;
+            schema.extensions.clone()
+        };
+        let signals = Arc::new(Signals::load(&index)?);
+        Ok(Index {
+            index,
+            reader,
+            directory,
+            extensions: Arc::new(extensions),
+            skipped_segments: Vec::new(),
+            search_limits: SearchLimits::default(),
+            active_searchers: Arc::default(),
+            query_cache: Arc::default(),
+            compaction: Mutex::default(),
+            limiter,
+            field_aliases: HashMap::new(),
+            signals,
+            activity: Arc::default(),
+        })
     }
 
     /// Create a `IndexWriter` for the index.
@@ -277,24 +1603,70 @@ impl Index {
     ///     num_threads (int, optional): The number of threads that the writer
     ///         should use. If this value is 0, tantivy will choose
     ///         automatically the number of threads.
+    ///     block (bool, optional): If another writer holds the index lock,
+    ///         wait for it to be released instead of failing immediately.
+    ///         Defaults to False.
+    ///     lock_timeout (float, optional): The maximum number of seconds to
+    ///         wait for the lock. Providing it implies `block`, which
+    ///         otherwise waits indefinitely.
+    ///     max_mb_per_sec (float, optional): The maximum number of megabytes
+    ///         per second the writer writes to the index directory, when
+    ///         committing and merging, so that it doesn't saturate the disk
+    ///         the searchers read from. See `IndexWriter.max_mb_per_sec`.
+    ///         Unlimited by default.
     ///
+    /// Raises LockAcquisitionError if the index lock is held by another
+    /// writer and couldn't be acquired.
     /// Raises ValueError if there was an error while creating the writer.
-    #[pyo3(signature = (heap_size = 128_000_000, num_threads = 0))]
-    fn writer(
+    #[pyo3(signature = (heap_size = 128_000_000, num_threads = 0, block = false, lock_timeout = None, max_mb_per_sec = None))]
+    pub(crate) fn writer(
         &self,
+        py: Python,
         heap_size: usize,
         num_threads: usize,
+        block: bool,
+        lock_timeout: Option<f64>,
+        max_mb_per_sec: Option<f64>,
     ) -> PyResult<IndexWriter> {
-        let writer = match num_threads {
-            0 => self.index.writer(heap_size),
-            _ => self.index.writer_with_num_threads(num_threads, heap_size),
-        }
-        .map_err(to_pyerr)?;
-        let schema = self.index.schema();
-        Ok(IndexWriter {
-            inner_index_writer: Some(writer),
-            schema,
-        })
+        let deadline = match lock_timeout {
+            Some(secs) if !(secs >= 0.0 && secs.is_finite()) => {
+                return Err(exceptions::PyValueError::new_err(
+                    "lock_timeout must be a non-negative number of seconds.",
+                ))
+            }
+            Some(secs) => Some(Instant::now() + Duration::from_secs_f64(secs)),
+            None => None,
+        };
+        let bytes_per_sec = check_mb_per_sec(max_mb_per_sec)?;
+
+        let writer = loop {
+            let result = match num_threads {
+                0 => self.index.writer(heap_size),
+                _ => self.index.writer_with_num_threads(num_threads, heap_size),
+            };
+            let may_wait = match deadline {
+                Some(deadline) => Instant::now() < deadline,
+                None => block,
+            };
+            match result {
+                Err(tv::TantivyError::LockFailure(LockError::LockBusy, _))
+                    if may_wait =>
+                {
+                    py.allow_threads(|| thread::sleep(LOCK_RETRY_INTERVAL));
+                    py.check_signals()?;
+                }
+                result => break result.map_err(tantivy_err)?,
+            }
+        };
+        // The limit of the index belongs to the writer holding the lock.
+        self.limiter.set_bytes_per_sec(bytes_per_sec);
+        Ok(IndexWriter::new(
+            writer,
+            self.index.schema(),
+            self.extensions.clone(),
+            self.limiter.clone(),
+            self.activity.clone(),
+        ))
     }
 
     /// Configure the index reader.
@@ -304,11 +1676,18 @@ impl Index {
     ///         IndexReader should use. Can be `Manual` or `OnCommit`.
     ///     num_warmers (int, optional): The number of searchers that the
     ///         reader should create.
-    #[pyo3(signature = (reload_policy = RELOAD_POLICY, num_warmers = 0))]
+    ///     doc_store_cache_num_blocks (int, optional): The number of
+    ///         decompressed doc store blocks cached by each segment reader.
+    ///         Workloads fetching many documents per search benefit from a
+    ///         larger cache, see `Searcher.doc_store_cache_stats`. Defaults
+    ///         to 100.
+    #[pyo3(signature = (reload_policy = RELOAD_POLICY, num_warmers = 0, doc_store_cache_num_blocks = DOC_STORE_CACHE_NUM_BLOCKS))]
     fn config_reader(
         &mut self,
+        py: Python,
         reload_policy: &str,
         num_warmers: usize,
+        doc_store_cache_num_blocks: usize,
     ) -> Result<(), PyErr> {
         let reload_policy = reload_policy.to_lowercase();
         let reload_policy = match reload_policy.as_ref() {
@@ -321,45 +1700,467 @@ impl Index {
             ))
         };
         let builder = self.index.reader_builder();
-        let builder = builder.reload_policy(reload_policy);
+        let builder = builder
+            .reload_policy(reload_policy)
+            .doc_store_cache_num_blocks(doc_store_cache_num_blocks);
         let builder = if num_warmers > 0 {
             builder.num_warming_threads(num_warmers)
         } else {
             builder
         };
 
-        self.reader = builder.try_into().map_err(to_pyerr)?;
+        self.reader = py
+            .allow_threads(|| builder.try_into())
+            .map_err(tantivy_err)?;
+        Ok(())
+    }
+
+    /// Limit the result window and the complexity of the queries of the
+    /// searches of the searchers returned from now on.
+    ///
+    /// This protects servers from requests asking for millions of hits, or
+    /// whose queries are costly to run, see `Searcher.with_search_limits`.
+    ///
+    /// Args:
+    ///     max_limit (int, optional): The maximum number of hits of a search.
+    ///         Defaults to no maximum.
+    ///     max_offset (int, optional): The maximum offset of a search.
+    ///         Defaults to no maximum.
+    ///     max_clauses (int, optional): The maximum number of boolean query
+    ///         clauses of a query. Defaults to no maximum.
+    ///     max_expanded_terms (int, optional): The maximum number of terms
+    ///         matched by a fuzzy query. Defaults to no maximum.
+    ///     max_regex_states (int, optional): The maximum number of automaton
+    ///         states of a regex query. Defaults to no maximum.
+    #[pyo3(signature = (max_limit = None, max_offset = None, max_clauses = None, max_expanded_terms = None, max_regex_states = None))]
+    fn set_search_limits(
+        &mut self,
+        max_limit: Option<usize>,
+        max_offset: Option<usize>,
+        max_clauses: Option<usize>,
+        max_expanded_terms: Option<usize>,
+        max_regex_states: Option<usize>,
+    ) {
+        self.search_limits = SearchLimits {
+            max_limit,
+            max_offset,
+            max_clauses,
+            max_expanded_terms,
+            max_regex_states,
+        };
+    }
+
+    /// Serve the index over Arrow Flight, from Rust threads.
+    ///
+    /// Flight clients, in any language, get the hits of a query as Arrow
+    /// record batches. The ticket of a `DoGet` call is the JSON of the
+    /// search, `{"q": "...", "limit": 10, "offset": 0, "fields": "..."}`:
+    /// a query in the tantivy query language, the optional page of hits, 10
+    /// by default and at most 100,000, and the comma separated fields
+    /// searched by default, all the indexed fields by default.
+    /// `GetFlightInfo` describes the results of a command descriptor holding
+    /// that JSON, and `GetSchema` returns the schema of the results.
+    ///
+    /// The results have a `score`, `segment_ord` and `doc` column, and a
+    /// list column per stored field. The server searches the last commit of
+    /// the index, reloaded as commits happen. It has no authentication: bind
+    /// it to a private address.
+    ///
+    /// Only available when built with the `flight` cargo feature.
+    ///
+    /// Args:
+    ///     port (int, optional): The port to listen on, 0 for a port picked
+    ///         by the system. Defaults to 8815.
+    ///     host (str, optional): The address to listen on. Defaults to
+    ///         '127.0.0.1'.
+    ///     num_threads (int, optional): The number of threads answering the
+    ///         calls. Defaults to 4.
+    ///
+    /// Returns the running `FlightServer`.
+    ///
+    /// Raises an OSError if the address can't be listened on, and a ValueError
+    /// if `num_threads` is 0.
+    #[cfg(feature = "flight")]
+    #[pyo3(signature = (port = 8815, host = "127.0.0.1", num_threads = 4))]
+    fn flight_server(
+        &self,
+        port: u16,
+        host: &str,
+        num_threads: usize,
+    ) -> PyResult<crate::flight::FlightServer> {
+        crate::flight::serve(self, host, port, num_threads)
+    }
+
+    /// Name fields differently in the query strings, e.g. to keep the
+    /// queries of an external syntax working after fields are renamed.
+    ///
+    /// The aliases apply to the query strings, default fields, boosts and
+    /// fuzzy fields of `parse_query`, `parse_query_lenient` and
+    /// `validate_query`, and to the paths of JSON fields: `alias.key`
+    /// stands for `field.key`.
+    ///
+    /// Args:
+    ///     aliases (Dict[str, str]): The field names by alias, replacing the
+    ///         previous aliases.
+    ///
+    /// Raises a ValueError if an alias is the name of a field, and a
+    /// SchemaError if an aliased field doesn't exist.
+    fn set_field_aliases(
+        &mut self,
+        aliases: HashMap<String, String>,
+    ) -> PyResult<()> {
+        let schema = self.index.schema();
+        for (alias, field_name) in &aliases {
+            if schema.get_field(alias).is_ok() {
+                return Err(exceptions::PyValueError::new_err(format!(
+                    "The alias `{alias}` is the name of a field."
+                )));
+            }
+            get_field(&schema, field_name)?;
+        }
+        self.field_aliases = aliases;
         Ok(())
     }
 
+    /// The field names by alias, see `set_field_aliases`.
+    #[getter]
+    fn field_aliases(&self) -> HashMap<String, String> {
+        self.field_aliases.clone()
+    }
+
+    /// The signal store of the index, holding numeric signals of the
+    /// documents which change without reindexing them, see `SignalStore`.
+    ///
+    /// Args:
+    ///     key_field (str, optional): The field whose values key the
+    ///         signals, a text, integer or unsigned fast field holding a
+    ///         unique value per document. Required the first time, the
+    ///         field is stored with the signals.
+    ///
+    /// Raises a ValueError if the key field isn't given the first time or
+    /// the signals are keyed by another field, and a SchemaError if the
+    /// field can't key the signals.
+    #[pyo3(signature = (key_field = None))]
+    fn signals(&self, key_field: Option<&str>) -> PyResult<SignalStore> {
+        match key_field {
+            Some(key_field) => {
+                signals::check_key_field(&self.index.schema(), key_field)?;
+                self.signals.set_key_field(key_field)?;
+            }
+            None if self.signals.key_field().is_none() => {
+                return Err(exceptions::PyValueError::new_err(
+                    "The index has no signals yet, the key_field of the \
+                     signals must be given.",
+                ))
+            }
+            None => {}
+        }
+        Ok(SignalStore {
+            inner: self.signals.clone(),
+        })
+    }
+
     /// Returns a searcher
     ///
     /// This method should be called every single time a search query is performed.
     /// The same searcher must be used for a given query, as it ensures the use of a consistent segment set.
-    fn searcher(&self) -> Searcher {
+    pub(crate) fn searcher(&self) -> Searcher {
         Searcher {
             inner: self.reader.searcher(),
+            extensions: self.extensions.clone(),
+            filters: Vec::new(),
+            limits: self.search_limits,
+            active: self.active_searchers.clone(),
+            query_cache: self.query_cache.clone(),
+            signals: self.signals.clone(),
         }
     }
 
+    /// Returns a searcher over a past commit of the index.
+    ///
+    /// The last 100 commits made by an `IndexWriter` are recorded in the
+    /// index, and can be searched until the writer garbage collects the files
+    /// of their segments, typically after these are merged.
+    ///
+    /// Args:
+    ///     commit (Union[int, str, os.PathLike]): The opstamp returned by the
+    ///         commit, or the path to a copy of the `meta.json` file of the
+    ///         index made after the commit.
+    ///
+    /// Raises a ValueError if the commit isn't recorded, or if its files were
+    /// garbage collected.
+    fn searcher_at(
+        &self,
+        py: Python,
+        commit: &Bound<PyAny>,
+    ) -> PyResult<Searcher> {
+        let meta = if let Ok(opstamp) = commit.extract::<u64>() {
+            history::meta_at(&self.index, opstamp)?.ok_or_else(|| {
+                exceptions::PyValueError::new_err(format!(
+                    "The commit {opstamp} isn't recorded in the index."
+                ))
+            })?
+        } else {
+            let path: PathBuf = commit.extract()?;
+            std::fs::read(path)?
+        };
+        let directory = ReadOnlyDirectory::new(
+            self.directory.clone(),
+            MetaOverride::Replace(Arc::new(meta)),
+        );
+        let searcher = py.allow_threads(|| {
+            let index = tv::Index::open(directory).map_err(tantivy_err)?;
+            let directory = index.directory();
+            for meta in index.searchable_segment_metas().map_err(tantivy_err)? {
+                for path in integrity::segment_files(&meta) {
+                    if !directory.exists(&path).map_err(to_pyerr)? {
+                        return Err(exceptions::PyValueError::new_err(
+                            format!(
+                            "The file {} of the commit was garbage collected.",
+                            path.display()
+                        ),
+                        ));
+                    }
+                }
+            }
+            Index::register_custom_text_analyzers(&index);
+            let reader = index
+                .reader_builder()
+                .reload_policy(tv::ReloadPolicy::Manual)
+                .try_into()
+                .map_err(tantivy_err)?;
+            PyResult::Ok(reader.searcher())
+        })?;
+        Ok(Searcher {
+            inner: searcher,
+            extensions: self.extensions.clone(),
+            filters: Vec::new(),
+            limits: self.search_limits,
+            active: self.active_searchers.clone(),
+            query_cache: self.query_cache.clone(),
+            signals: self.signals.clone(),
+        })
+    }
+
+    /// Create a spell corrector using the terms of a text field as
+    /// dictionary.
+    ///
+    /// Args:
+    ///     field_name (str): The indexed text field providing the terms.
+    ///     min_doc_freq (int, optional): The minimum number of documents a
+    ///         term must appear in to be suggested. Defaults to 1.
+    ///
+    /// Returns a SpellCorrector reflecting the index at the time of the
+    /// call.
+    ///
+    /// Raises a SchemaError if the field isn't an indexed text field.
+    #[pyo3(signature = (field_name, min_doc_freq = 1))]
+    fn spell_corrector(
+        &self,
+        field_name: &str,
+        min_doc_freq: u64,
+    ) -> PyResult<SpellCorrector> {
+        SpellCorrector::new(
+            &self.index,
+            self.reader.searcher(),
+            field_name,
+            min_doc_freq,
+        )
+    }
+
+    /// Open the autocomplete suggester of a field.
+    ///
+    /// The suggester is persisted in the index directory, it is only rebuilt
+    /// when the index changed since it was last built.
+    ///
+    /// Args:
+    ///     field_name (str): The text field providing the suggestions. If the
+    ///         field is stored its values are suggested, otherwise its
+    ///         indexed terms.
+    ///     weight_field (str, optional): A stored numeric field holding the
+    ///         weight of each document. Only valid with a stored field.
+    ///         Suggestions are weighted by the number of documents holding
+    ///         them by default, or by their document frequency for terms.
+    ///
+    /// Returns a Suggester reflecting the last commit.
+    ///
+    /// Raises a SchemaError if the fields don't fit.
+    #[pyo3(signature = (field_name, weight_field = None))]
+    fn suggester(
+        &self,
+        py: Python,
+        field_name: &str,
+        weight_field: Option<&str>,
+    ) -> PyResult<Suggester> {
+        let searcher = self.reader.searcher();
+        py.allow_threads(|| {
+            Suggester::open(&self.index, &searcher, field_name, weight_field)
+        })
+    }
+
     /// Check if the given path contains an existing index.
     /// Args:
-    ///     path: The path where tantivy will search for an index.
+    ///     path (Union[str, os.PathLike]): The path where tantivy will search
+    ///         for an index.
     ///
     /// Returns True if an index exists at the given path, False otherwise.
     ///
     /// Raises OSError if the directory cannot be opened.
+    /// Raises IndexVersionError if an index exists but was written with an
+    /// index format that this version of tantivy can't read.
     #[staticmethod]
-    fn exists(path: &str) -> PyResult<bool> {
-        let directory = MmapDirectory::open(path).map_err(to_pyerr)?;
-        tv::Index::exists(&directory).map_err(to_pyerr)
+    fn exists(path: PathBuf) -> PyResult<bool> {
+        let directory =
+            MmapDirectory::open(paths::normalize(path)).map_err(tantivy_err)?;
+        if !tv::Index::exists(&directory).map_err(tantivy_err)? {
+            return Ok(false);
+        }
+
+        // Segment files carry the format version in their footer, which is
+        // only validated once the segments are opened.
+        let compatibility = tv::Index::open(directory)
+            .and_then(|index| index.reader().map(|_| ()));
+        match compatibility {
+            Err(err) if is_incompatible_index(&err) => Err(tantivy_err(err)),
+            _ => Ok(true),
+        }
+    }
+
+    /// Metadata of the last commit of the index.
+    ///
+    /// The metadata is read from the index directory, so it reflects the
+    /// last commit even if the reader was not reloaded yet.
+    ///
+    /// Returns an `IndexMeta` object.
+    fn meta(&self) -> PyResult<IndexMeta> {
+        let metas = self.index.load_metas().map_err(tantivy_err)?;
+        Ok(IndexMeta {
+            opstamp: metas.opstamp,
+            payload: metas.payload,
+            num_segments: metas.segments.len(),
+        })
+    }
+
+    /// The metadata stored with a commit by `IndexWriter.set_commit_meta`.
+    ///
+    /// Args:
+    ///     opstamp (int, optional): The opstamp of a past commit recorded in
+    ///         the index, as for `searcher_at`. Defaults to the last commit.
+    ///
+    /// Returns the metadata as a dictionary, or None if the commit has no
+    /// metadata, e.g. if it was made by another program.
+    ///
+    /// Raises a ValueError if the commit isn't recorded or if the commit
+    /// history can't be parsed.
+    #[pyo3(signature = (opstamp = None))]
+    fn commit_meta(
+        &self,
+        py: Python,
+        opstamp: Option<u64>,
+    ) -> PyResult<Option<PyObject>> {
+        history::commit_meta(&self.index, opstamp)?
+            .map(|meta| pythonize::pythonize(py, &meta).map_err(to_pyerr))
+            .transpose()
+    }
+
+    /// The settings the index was created with.
+    #[getter]
+    fn settings(&self) -> IndexSettings {
+        IndexSettings {
+            inner: self.index.settings().clone(),
+        }
     }
 
     /// The schema of the current index.
     #[getter]
     fn schema(&self) -> Schema {
         let schema = self.index.schema();
-        Schema { inner: schema }
+        Schema {
+            inner: schema,
+            extensions: (*self.extensions).clone(),
+        }
+    }
+
+    /// Compact the index from a background thread.
+    ///
+    /// Every `policy.interval_secs`, the thread merges the smallest segments
+    /// if there are more than `policy.max_segments`, and the segments with
+    /// many deleted documents, then garbage collects the files of the merged
+    /// segments. Merges are written at most at `policy.max_mb_per_sec`, so
+    /// that they don't starve the searchers of disk bandwidth.
+    ///
+    /// The thread takes the index lock while merging, and skips its turn if
+    /// another writer holds it. Writers created meanwhile should pass
+    /// `block=True` to `writer()` to wait for the merge to finish.
+    ///
+    /// Calling this method again replaces the previous policy. The thread
+    /// stops with the index.
+    ///
+    /// Args:
+    ///     policy (CompactionPolicy, optional): When and how the segments
+    ///         are compacted. Defaults to `CompactionPolicy()`.
+    ///
+    /// Raises a ValueError if the index was opened without some of its
+    /// segments, as it is read-only.
+    #[pyo3(signature = (policy = None))]
+    fn start_compaction(
+        &self,
+        policy: Option<CompactionPolicy>,
+    ) -> PyResult<()> {
+        if !self.skipped_segments.is_empty() {
+            return Err(exceptions::PyValueError::new_err(
+                "The index was opened without some of its segments, it is \
+                 read-only.",
+            ));
+        }
+        let policy = policy.unwrap_or_default();
+        let compaction = Compaction::spawn(
+            self.index.clone(),
+            self.limiter.clone(),
+            self.activity.clone(),
+            policy,
+        );
+        *self.compaction.lock().unwrap() = Some(compaction);
+        Ok(())
+    }
+
+    /// Stop compacting the index from a background thread.
+    ///
+    /// A merge in progress completes in the background.
+    ///
+    /// Raises the error that stopped the compaction thread, if any.
+    fn stop_compaction(&self) -> PyResult<()> {
+        match self.compaction.lock().unwrap().take() {
+            Some(compaction) => match compaction.take_error() {
+                Some(err) => Err(tantivy_err(err)),
+                None => Ok(()),
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// The ids of the damaged segments left out when the index was opened
+    /// with `on_corruption="skip_segment"`.
+    #[getter]
+    fn skipped_segments(&self) -> Vec<String> {
+        self.skipped_segments.clone()
+    }
+
+    /// The number of `Searcher` objects of the index still alive.
+    ///
+    /// Searchers keep the files of their segments, so a count that keeps
+    /// growing points to searchers held by stale references. Comparing their
+    /// `Searcher.generation` to `reader_generation` tells the outdated ones.
+    fn num_active_searchers(&self) -> usize {
+        Arc::strong_count(&self.active_searchers) - 1
+    }
+
+    /// The generation of the searchers currently returned by `searcher`,
+    /// incremented on every reload. It starts over when the reader is
+    /// configured again with `config_reader`.
+    #[getter]
+    fn reader_generation(&self) -> u64 {
+        self.reader.searcher().generation().generation_id()
     }
 
     /// Update searchers so that they reflect the state of the last .commit().
@@ -367,8 +2168,168 @@ impl Index {
     /// If you set up the the reload policy to be on 'commit' (which is the
     /// default) every commit should be rapidly reflected on your IndexReader
     /// and you should not need to call reload() at all.
-    fn reload(&self) -> PyResult<()> {
-        self.reader.reload().map_err(to_pyerr)
+    pub(crate) fn reload(&self, py: Python) -> PyResult<()> {
+        let start = Instant::now();
+        // The writer threads hold the lock of the index meta while they log,
+        // which needs the GIL once the logs are sent to Python.
+        py.allow_threads(|| self.reader.reload())
+            .map_err(tantivy_err)?;
+        let searcher = self.reader.searcher();
+        metrics::emit(
+            "reload",
+            start,
+            &[
+                ("num_segments", searcher.segment_readers().len() as u64),
+                ("num_docs", searcher.num_docs()),
+            ],
+        );
+        Ok(())
+    }
+
+    /// Verify the checksums of the files of the searchable segments.
+    ///
+    /// Returns the sorted paths of the damaged files, relative to the index
+    /// directory.
+    fn validate_checksum(&self, py: Python) -> PyResult<Vec<String>> {
+        py.allow_threads(|| {
+            let damaged =
+                self.index.validate_checksum().map_err(tantivy_err)?;
+            Ok(sorted_paths(damaged))
+        })
+    }
+
+    /// The disk space used by each field of the index, in bytes.
+    ///
+    /// The doc store compresses blocks of whole documents, so the `store`
+    /// size of a field is an estimate: the store is split among the stored
+    /// fields in proportion to the uncompressed size of their values. This
+    /// reads every stored document.
+    ///
+    /// Returns a dict mapping each field name to a dict of the bytes used by
+    /// its `termdict`, `postings`, `positions`, `fast_fields`, `fieldnorms`
+    /// and `store`, and their `total`.
+    fn field_space_usage(&self, py: Python) -> PyResult<Py<PyDict>> {
+        let searcher = self.reader.searcher();
+        let usages = py
+            .allow_threads(|| stats::field_space_usage(&searcher))
+            .map_err(tantivy_err)?;
+        let dict = PyDict::new(py);
+        for ((_, field_entry), usage) in searcher.schema().fields().zip(usages)
+        {
+            let field_dict = PyDict::new(py);
+            field_dict.set_item("termdict", usage.termdict)?;
+            field_dict.set_item("postings", usage.postings)?;
+            field_dict.set_item("positions", usage.positions)?;
+            field_dict.set_item("fast_fields", usage.fast_fields)?;
+            field_dict.set_item("fieldnorms", usage.fieldnorms)?;
+            field_dict.set_item("store", usage.store)?;
+            field_dict.set_item("total", usage.total())?;
+            dict.set_item(field_entry.name(), field_dict)?;
+        }
+        Ok(dict.unbind())
+    }
+
+    /// A snapshot of the index for monitoring and capacity planning.
+    ///
+    /// The documents, segments and disk usage are those of the last reload
+    /// of the index. The activity counts the commits and merges of the
+    /// writers of this `Index` object since it was opened, the merges when
+    /// they start, be they started by `IndexWriter.merge`, the merge policy
+    /// of a writer or `start_compaction`.
+    ///
+    /// Returns a dict with:
+    ///
+    /// - `num_docs`, `num_deleted_docs` and `num_segments`.
+    /// - `disk_usage`: a dict of the bytes used by the `termdict`,
+    ///   `postings`, `positions`, `fast_fields`, `fieldnorms`, `store` and
+    ///   `deletes` of the segments, and their `total`.
+    /// - `uptime_secs`: the seconds elapsed since the index was opened.
+    /// - `commits`, `committed_docs` and `merges` since then, and the
+    ///   indexing throughput `docs_per_sec`.
+    /// - `last_commit` and `last_merge`: the times of the last commit and
+    ///   merge in seconds since the epoch, None if there was none.
+    fn stats(&self, py: Python) -> PyResult<PyObject> {
+        let stats = self.stats_report(py)?;
+        pythonize::pythonize(py, &stats).map_err(to_pyerr)
+    }
+
+    /// Check the integrity of the index, e.g. after a crash or a copy.
+    ///
+    /// Args:
+    ///     full (bool, optional): Also read every segment entirely: its
+    ///         stored documents, the postings of all its terms and its fast
+    ///         fields. Defaults to false.
+    ///
+    /// Returns a dict holding:
+    ///     ok (bool): Whether no problem was found.
+    ///     damaged_files (List[str]): The files whose checksum doesn't match.
+    ///     missing_files (List[str]): The files of the segments missing from
+    ///         the directory.
+    ///     segments (List[dict]): With `full`, the `segment_id`, `num_docs`
+    ///         and the `error` met reading each segment, None if it was read
+    ///         successfully. Empty otherwise.
+    #[pyo3(signature = (full = false))]
+    fn check(&self, py: Python, full: bool) -> PyResult<Py<PyDict>> {
+        let (damaged_files, missing_files, segments) =
+            py.allow_threads(|| {
+                let damaged =
+                    self.index.validate_checksum().map_err(tantivy_err)?;
+                let directory = self.index.directory();
+                let mut missing = HashSet::new();
+                for meta in self
+                    .index
+                    .searchable_segment_metas()
+                    .map_err(tantivy_err)?
+                {
+                    for path in integrity::segment_files(&meta) {
+                        if !directory.exists(&path).map_err(to_pyerr)? {
+                            missing.insert(path);
+                        }
+                    }
+                }
+                let segments: Vec<_> = if full {
+                    self.index
+                        .searchable_segments()
+                        .map_err(tantivy_err)?
+                        .iter()
+                        .map(|segment| {
+                            let meta = segment.meta();
+                            (
+                                meta.id().uuid_string(),
+                                meta.num_docs(),
+                                integrity::read_segment(segment),
+                            )
+                        })
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                PyResult::Ok((
+                    sorted_paths(damaged),
+                    sorted_paths(missing),
+                    segments,
+                ))
+            })?;
+
+        let ok = damaged_files.is_empty()
+            && missing_files.is_empty()
+            && segments.iter().all(|(_, _, error)| error.is_none());
+        let report = PyDict::new(py);
+        report.set_item("ok", ok)?;
+        report.set_item("damaged_files", damaged_files)?;
+        report.set_item("missing_files", missing_files)?;
+        let segments = segments
+            .into_iter()
+            .map(|(segment_id, num_docs, error)| {
+                let segment = PyDict::new(py);
+                segment.set_item("segment_id", segment_id)?;
+                segment.set_item("num_docs", num_docs)?;
+                segment.set_item("error", error)?;
+                Ok(segment)
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        report.set_item("segments", segments)?;
+        Ok(report.unbind())
     }
 
     /// Parse a query
@@ -388,21 +2349,68 @@ impl Index {
     ///         `prefix` determines if terms which are prefixes of the given term match the query.
     ///         `distance` determines the maximum Levenshtein distance between terms matching the query and the given term.
     ///         `transpose_cost_one` determines if transpositions of neighbouring characters are counted only once against the Levenshtein distance.
-    #[pyo3(signature = (query, default_field_names = None, field_boosts = HashMap::new(), fuzzy_fields = HashMap::new()))]
+    ///
+    ///     max_doc_frequency (float, optional): Drop the terms found in more
+    ///         than this fraction of the documents, between 0 and 1, as
+    ///         query-time stop words. Phrases and excluded terms are kept,
+    ///         and so is the query if all of its terms are that common.
+    ///
+    ///     plain_text (bool, optional): Parse the query as plain text, e.g.
+    ///         the input of a search box: none of its characters have a
+    ///         special meaning, see `escape_query_string`. Defaults to false.
+    ///
+    ///     conjunction_by_default (bool, optional): Match the documents
+    ///         holding all the terms of the query, instead of any of them,
+    ///         unless the query says otherwise. Defaults to false.
+    ///
+    ///     field_analyzers (Dict[str, str], optional): The analyzers
+    ///         processing the query text of text and JSON fields, by field
+    ///         name, instead of the ones the fields are indexed with. E.g.
+    ///         `{"title": "raw"}` looks the whole text up as a single term.
+    ///
+    /// Raises a ValueError if `max_doc_frequency` isn't between 0 and 1 or
+    /// an analyzer isn't registered, and a SchemaError if a field of
+    /// `field_analyzers` isn't an indexed text or JSON field.
+    #[pyo3(signature = (query, default_field_names = None, field_boosts = HashMap::new(), fuzzy_fields = HashMap::new(), max_doc_frequency = None, plain_text = false, conjunction_by_default = false, field_analyzers = HashMap::new()))]
+    #[allow(clippy::too_many_arguments)]
     pub fn parse_query(
         &self,
         query: &str,
         default_field_names: Option<Vec<String>>,
         field_boosts: HashMap<String, tv::Score>,
         fuzzy_fields: HashMap<String, (bool, u8, bool)>,
+        max_doc_frequency: Option<f64>,
+        plain_text: bool,
+        conjunction_by_default: bool,
+        field_analyzers: HashMap<String, String>,
     ) -> PyResult<Query> {
-        let parser = self.prepare_query_parser(
+        let mut parser = self.prepare_query_parser(
             default_field_names,
             field_boosts,
             fuzzy_fields,
+            field_analyzers,
         )?;
+        if conjunction_by_default {
+            parser.set_conjunction_by_default();
+        }
+        let escaped;
+        let query = if plain_text {
+            escaped = query_escape::escape(query);
+            &escaped
+        } else {
+            query
+        };
 
-        let query = parser.parse_query(query).map_err(to_pyerr)?;
+        let ast = tv::query_grammar::parse_query(query).map_err(|_| {
+            query_parser_err(tv::query::QueryParserError::SyntaxError(
+                query.to_string(),
+            ))
+        })?;
+        let ast = field_alias::rewrite(ast, &self.field_aliases);
+        let query = parser
+            .build_query_from_user_input_ast(ast)
+            .map_err(query_parser_err)?;
+        let query = self.drop_common_terms(query, max_doc_frequency)?;
 
         Ok(Query { inner: query })
     }
@@ -430,54 +2438,250 @@ impl Index {
     ///         `distance` determines the maximum Levenshtein distance between terms matching the query and the given term.
     ///         `transpose_cost_one` determines if transpositions of neighbouring characters are counted only once against the Levenshtein distance.
     ///
+    ///     max_doc_frequency (float, optional): Drop the terms found in more
+    ///         than this fraction of the documents, see `parse_query`.
+    ///
+    ///     field_analyzers (Dict[str, str], optional): The analyzers
+    ///         processing the query text of fields, see `parse_query`.
+    ///
     /// Returns a tuple containing the parsed query and a list of errors.
     ///
     /// Raises ValueError if a field in `default_field_names` is not defined or marked as indexed.
-    #[pyo3(signature = (query, default_field_names = None, field_boosts = HashMap::new(), fuzzy_fields = HashMap::new()))]
+    #[pyo3(signature = (query, default_field_names = None, field_boosts = HashMap::new(), fuzzy_fields = HashMap::new(), max_doc_frequency = None, field_analyzers = HashMap::new()))]
+    #[allow(clippy::too_many_arguments)]
     pub fn parse_query_lenient(
         &self,
         query: &str,
         default_field_names: Option<Vec<String>>,
         field_boosts: HashMap<String, tv::Score>,
         fuzzy_fields: HashMap<String, (bool, u8, bool)>,
+        max_doc_frequency: Option<f64>,
+        field_analyzers: HashMap<String, String>,
         py: Python,
     ) -> PyResult<(Query, Vec<PyObject>)> {
         let parser = self.prepare_query_parser(
             default_field_names,
             field_boosts,
             fuzzy_fields,
+            field_analyzers,
         )?;
 
-        let (query, errors) = parser.parse_query_lenient(query);
-        let errors = errors.into_iter().map(|err| err.into_py(py)).collect();
+        let (ast, syntax_errors) =
+            tv::query_grammar::parse_query_lenient(query);
+        let ast = field_alias::rewrite(ast, &self.field_aliases);
+        let (query, parser_errors) =
+            parser.build_query_from_user_input_ast_lenient(ast);
+        let errors = syntax_errors
+            .into_iter()
+            .map(|error| {
+                tv::query::QueryParserError::SyntaxError(format!(
+                    "{} at position {}",
+                    error.message, error.pos
+                ))
+            })
+            .chain(parser_errors);
+        let query = self.drop_common_terms(query, max_doc_frequency)?;
+        let errors =
+            errors.map(|err| err.into_py(py)).collect::<PyResult<_>>()?;
 
         Ok((Query { inner: query }, errors))
     }
+
+    /// Parse a query string typed in a search box with a forgiving syntax,
+    /// like the `simple_query_string` of Elasticsearch. It never raises
+    /// because of the query string.
+    ///
+    /// The words and `"quoted phrases"` of the query can be prefixed by
+    /// `+`, the documents must match them, or `-`, the documents must not,
+    /// and `|` separates alternatives matching documents on their own, e.g.
+    /// `+whale -"moby dick" | ahab`. Any other character is part of a word.
+    ///
+    /// Args:
+    ///     query (str): The query string.
+    ///     default_field_names (List[str], optional): The fields searched,
+    ///         all the indexed fields by default.
+    ///     conjunction_by_default (bool, optional): Match the documents
+    ///         holding all the unprefixed words of an alternative, instead of
+    ///         any of them. Defaults to false.
+    ///
+    /// Raises a ValueError if a field doesn't exist, and a SchemaError if a
+    /// field isn't indexed.
+    #[pyo3(signature = (query, default_field_names = None, conjunction_by_default = false))]
+    fn parse_simple_query(
+        &self,
+        query: &str,
+        default_field_names: Option<Vec<String>>,
+        conjunction_by_default: bool,
+    ) -> PyResult<Query> {
+        let mut parser = self.prepare_query_parser(
+            default_field_names,
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+        )?;
+        if conjunction_by_default {
+            parser.set_conjunction_by_default();
+        }
+        let (query, _) = parser.build_query_from_user_input_ast_lenient(
+            simple_query::parse(query),
+        );
+        Ok(Query { inner: query })
+    }
+
+    /// Check a query string without running it, e.g. to give live feedback
+    /// on the queries typed by users.
+    ///
+    /// Args:
+    ///     query (str): The query, following the tantivy query language.
+    ///     default_field_names (List[str], optional): The fields searched by
+    ///         the terms without a field, see `parse_query`. Defaults to all
+    ///         the indexed fields.
+    ///
+    /// Returns a dict with:
+    ///     - `valid`: whether `parse_query` accepts the query.
+    ///     - `tree`: the parsed query, as a string.
+    ///     - `fields`: the sorted names of the fields searched by the query.
+    ///     - `errors`: the syntax errors, as dicts with a `message` and the
+    ///       byte `position` of the error in the query.
+    ///     - `warnings`: the messages about the parts of the query that
+    ///       can't match anything, e.g. the terms of unindexed fields.
+    ///
+    /// Raises a ValueError if a field in `default_field_names` is not defined
+    /// or marked as indexed.
+    #[pyo3(signature = (query, default_field_names = None))]
+    fn validate_query(
+        &self,
+        py: Python,
+        query: &str,
+        default_field_names: Option<Vec<String>>,
+    ) -> PyResult<PyObject> {
+        let schema = self.index.schema();
+        let default_field_names: Vec<String> = match default_field_names {
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    field_alias::resolve(&self.field_aliases, name).into_owned()
+                })
+                .collect(),
+            None => schema
+                .fields()
+                .filter(|(_, field_entry)| field_entry.is_indexed())
+                .map(|(_, field_entry)| field_entry.name().to_string())
+                .collect(),
+        };
+        let parser = self.prepare_query_parser(
+            Some(default_field_names.clone()),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+        )?;
+        let report = query_validation::validate(
+            &parser,
+            &schema,
+            &default_field_names,
+            &self.field_aliases,
+            query,
+        );
+        pythonize::pythonize(py, &report).map_err(to_pyerr)
+    }
+
+    /// Show how a text is analyzed as a query of a text field, and as the
+    /// text of a document, e.g. to find out why a query doesn't match a
+    /// document: the terms of a query are only found if the documents hold
+    /// them as tokens.
+    ///
+    /// Args:
+    ///     field_name (str): The text field searched.
+    ///     text (str): The text, following the tantivy query language when
+    ///         analyzed as a query.
+    ///
+    /// Returns a dict with:
+    ///     - `tokenizer`: the name of the tokenizer of the field.
+    ///     - `document_tokens`: the tokens of the text in a document, as
+    ///       dicts with their `text`, `position`, `offset_from` and
+    ///       `offset_to`.
+    ///     - `query_terms`: the terms searched by the query, as dicts with
+    ///       their `text`, `doc_freq` in the index and whether the text
+    ///       analyzed as a document holds them, `in_document`.
+    ///     - `consistent`: whether the document tokens hold all the query
+    ///       terms, e.g. false for a multi-word query of a `raw` field, which
+    ///       the query parser splits into words.
+    ///
+    /// Raises a SchemaError if the field isn't an indexed text field.
+    fn analyze_query_terms(
+        &self,
+        py: Python,
+        field_name: &str,
+        text: &str,
+    ) -> PyResult<PyObject> {
+        let field_name = field_alias::resolve(&self.field_aliases, field_name);
+        let analysis =
+            analysis::analyze(&self.searcher().inner, &field_name, text)?;
+        pythonize::pythonize(py, &analysis).map_err(to_pyerr)
+    }
 }
 
 impl Index {
+    /// The report of `stats`.
+    pub(crate) fn stats_report(
+        &self,
+        py: Python,
+    ) -> PyResult<stats::IndexStats> {
+        let searcher = self.reader.searcher();
+        py.allow_threads(|| stats::index_stats(&searcher, &self.activity))
+            .map_err(tantivy_err)
+    }
+
+    /// Drop the terms of a parsed query found in more than
+    /// `max_doc_frequency` of the documents.
+    fn drop_common_terms(
+        &self,
+        query: Box<dyn tv::query::Query>,
+        max_doc_frequency: Option<f64>,
+    ) -> PyResult<Box<dyn tv::query::Query>> {
+        let Some(max_doc_frequency) = max_doc_frequency else {
+            return Ok(query);
+        };
+        if !(max_doc_frequency > 0.0 && max_doc_frequency <= 1.0) {
+            return Err(exceptions::PyValueError::new_err(
+                "max_doc_frequency must be between 0 and 1.",
+            ));
+        }
+        let searcher = self.reader.searcher();
+        let max_doc_freq = max_doc_frequency * searcher.num_docs() as f64;
+        let is_common = |term: &Term| {
+            searcher
+                .doc_freq(term)
+                .is_ok_and(|doc_freq| doc_freq as f64 > max_doc_freq)
+        };
+        Ok(drop_common_terms(query.as_ref(), &is_common).unwrap_or(query))
+    }
+
     fn prepare_query_parser(
         &self,
         default_field_names: Option<Vec<String>>,
         field_boosts: HashMap<String, tv::Score>,
         fuzzy_fields: HashMap<String, (bool, u8, bool)>,
+        field_analyzers: HashMap<String, String>,
     ) -> PyResult<tv::query::QueryParser> {
         let schema = self.index.schema();
+        let query_schema =
+            self.with_query_analyzers(&schema, field_analyzers)?;
 
         let default_fields = if let Some(default_field_names) =
             default_field_names
         {
             default_field_names.iter().map(|field_name| {
-                let field = schema.get_field(field_name).map_err(|_err| {
-                    exceptions::PyValueError::new_err(format!(
-                        "Field `{field_name}` is not defined in the schema."
-                    ))
-                })?;
+                let field_name =
+                    field_alias::resolve(&self.field_aliases, field_name);
+                let field_name = field_name.as_ref();
+                let field = get_field(&schema, field_name)?;
 
                 let field_entry = schema.get_field_entry(field);
                 if !field_entry.is_indexed() {
-                    return Err(exceptions::PyValueError::new_err(
-                        format!("Field `{field_name}` is not set as indexed in the schema.")
+                    return Err(schema_err(
+                        format!("Field `{field_name}` is not set as indexed in the schema."),
+                        field_name,
                     ));
                 }
 
@@ -491,32 +2695,104 @@ impl Index {
                 .collect()
         };
 
-        let mut parser =
-            tv::query::QueryParser::for_index(&self.index, default_fields);
+        let mut parser = tv::query::QueryParser::new(
+            query_schema,
+            default_fields,
+            self.index.tokenizers().clone(),
+        );
 
         for (field_name, boost) in field_boosts {
-            let field = schema.get_field(&field_name).map_err(|_err| {
-                exceptions::PyValueError::new_err(format!(
-                    "Field `{field_name}` is not defined in the schema."
-                ))
-            })?;
+            let field_name =
+                field_alias::resolve(&self.field_aliases, &field_name);
+            let field = get_field(&schema, &field_name)?;
             parser.set_field_boost(field, boost);
         }
 
         for (field_name, (prefix, distance, transpose_cost_one)) in fuzzy_fields
         {
-            let field = schema.get_field(&field_name).map_err(|_err| {
-                exceptions::PyValueError::new_err(format!(
-                    "Field `{field_name}` is not defined in the schema."
-                ))
-            })?;
+            let field_name =
+                field_alias::resolve(&self.field_aliases, &field_name);
+            let field = get_field(&schema, &field_name)?;
             parser.set_field_fuzzy(field, prefix, distance, transpose_cost_one);
         }
 
         Ok(parser)
     }
 
-    fn register_custom_text_analyzers(index: &tv::Index) {
+    /// The schema the query parser reads the analyzers of the fields from,
+    /// with the text and JSON fields of `field_analyzers` analyzed by the
+    /// given analyzers instead.
+    ///
+    /// Raises a SchemaError if a field isn't an indexed text or JSON field,
+    /// and a ValueError if an analyzer isn't registered.
+    fn with_query_analyzers(
+        &self,
+        schema: &tv::schema::Schema,
+        field_analyzers: HashMap<String, String>,
+    ) -> PyResult<tv::schema::Schema> {
+        if field_analyzers.is_empty() {
+            return Ok(schema.clone());
+        }
+        let mut analyzers = HashMap::new();
+        for (field_name, analyzer_name) in field_analyzers {
+            let field_name =
+                field_alias::resolve(&self.field_aliases, &field_name);
+            let field = get_field(schema, &field_name)?;
+            if self.index.tokenizers().get(&analyzer_name).is_none() {
+                return Err(exceptions::PyValueError::new_err(format!(
+                    "No analyzer named `{analyzer_name}` is registered."
+                )));
+            }
+            analyzers.insert(field, analyzer_name);
+        }
+
+        let mut builder = tv::schema::Schema::builder();
+        for (field, field_entry) in schema.fields() {
+            let Some(analyzer_name) = analyzers.get(&field) else {
+                builder.add_field(field_entry.clone());
+                continue;
+            };
+            let field_name = field_entry.name().to_string();
+            let not_analyzed = || {
+                schema_err(
+                    format!(
+                        "Field `{field_name}` is not an indexed text or JSON \
+                         field, its query text isn't analyzed."
+                    ),
+                    &field_name,
+                )
+            };
+            let field_entry = match field_entry.field_type() {
+                tv::schema::FieldType::Str(options) => {
+                    let indexing = options
+                        .get_indexing_options()
+                        .ok_or_else(not_analyzed)?
+                        .clone()
+                        .set_tokenizer(analyzer_name);
+                    tv::schema::FieldEntry::new_text(
+                        field_name,
+                        options.clone().set_indexing_options(indexing),
+                    )
+                }
+                tv::schema::FieldType::JsonObject(options) => {
+                    let indexing = options
+                        .get_text_indexing_options()
+                        .ok_or_else(not_analyzed)?
+                        .clone()
+                        .set_tokenizer(analyzer_name);
+                    tv::schema::FieldEntry::new_json(
+                        field_name,
+                        options.clone().set_indexing_options(indexing),
+                    )
+                }
+                _ => return Err(not_analyzed()),
+            };
+            builder.add_field(field_entry);
+        }
+        Ok(builder.build())
+    }
+
+    pub(crate) fn register_custom_text_analyzers(index: &tv::Index) {
         let analyzers = [
             ("ar_stem", Language::Arabic),
             ("da_stem", Language::Danish),
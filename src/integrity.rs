@@ -0,0 +1,93 @@
+use std::{
+    collections::HashSet,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+};
+
+use tantivy::{
+    self as tv, schema::IndexRecordOption, DocSet, Segment, SegmentComponent,
+    SegmentId, SegmentMeta, SegmentReader, TantivyDocument, TERMINATED,
+};
+
+/// The files of a segment that must exist in the directory.
+pub(crate) fn segment_files(
+    meta: &SegmentMeta,
+) -> impl Iterator<Item = PathBuf> + '_ {
+    SegmentComponent::iterator()
+        .filter(|component| match component {
+            SegmentComponent::TempStore => false,
+            // The delete file only exists if the segment has deletes.
+            SegmentComponent::Delete => meta.has_deletes(),
+            _ => true,
+        })
+        .map(|component| meta.relative_path(*component))
+}
+
+/// The ids of the searchable segments that have a missing file, a file
+/// whose checksum doesn't match, or that can't be opened.
+pub(crate) fn damaged_segments(
+    index: &tv::Index,
+) -> tv::Result<HashSet<SegmentId>> {
+    let directory = index.directory();
+    let mut damaged = HashSet::new();
+    for segment in index.searchable_segments()? {
+        let meta = segment.meta();
+        let files_ok = segment_files(meta)
+            .all(|path| matches!(directory.validate_checksum(&path), Ok(true)));
+        if !files_ok || SegmentReader::open(&segment).is_err() {
+            damaged.insert(meta.id());
+        }
+    }
+    Ok(damaged)
+}
+
+/// Read every component of a segment: the stored documents, the postings of
+/// every term and the fast field columns.
+///
+/// Returns the error met, if any. Damaged data can make tantivy panic, the
+/// panic is reported as an error.
+pub(crate) fn read_segment(segment: &Segment) -> Option<String> {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| read_all(segment)));
+    match result {
+        Ok(Ok(())) => None,
+        Ok(Err(err)) => Some(err.to_string()),
+        Err(panic) => Some(
+            panic
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_else(|| "Reading the segment panicked".to_string()),
+        ),
+    }
+}
+
+fn read_all(segment: &Segment) -> tv::Result<()> {
+    let reader = SegmentReader::open(segment)?;
+    let store_reader = reader.get_store_reader(0)?;
+    for doc in store_reader.iter::<TantivyDocument>(reader.alive_bitset()) {
+        doc?;
+    }
+
+    for (field, field_entry) in reader.schema().fields() {
+        if field_entry.is_indexed() {
+            let inverted_index = reader.inverted_index(field)?;
+            let mut terms = inverted_index.terms().stream()?;
+            while terms.advance() {
+                let mut postings = inverted_index.read_postings_from_terminfo(
+                    terms.value(),
+                    IndexRecordOption::Basic,
+                )?;
+                while postings.advance() != TERMINATED {}
+            }
+        }
+        if field_entry.is_fast() {
+            let handles = reader
+                .fast_fields()
+                .dynamic_column_handles(field_entry.name())?;
+            for handle in handles {
+                handle.open()?;
+            }
+        }
+    }
+    Ok(())
+}
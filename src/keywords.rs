@@ -0,0 +1,113 @@
+//! The terms distinguishing a document or the results of a query from the
+//! rest of the index, see `Searcher.significant_terms`.
+
+use std::collections::HashMap;
+
+use tantivy::{
+    self as tv,
+    query::{EnableScoring, Query},
+    schema::{Field, IndexRecordOption},
+    DocSet, Term, TERMINATED,
+};
+
+use crate::{scorer::DocBitSet, term_vector::term_vector};
+
+/// The BM25 inverse document frequency of a term.
+fn idf(doc_freq: u64, num_docs: u64) -> f64 {
+    let doc_freq = doc_freq as f64;
+    (1.0 + (num_docs as f64 - doc_freq + 0.5) / (doc_freq + 0.5)).ln()
+}
+
+/// The best `k` terms by descending score, then ascending term.
+fn top_k(mut terms: Vec<(String, f64)>, k: usize) -> Vec<(String, f64)> {
+    terms.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    terms.truncate(k);
+    terms
+}
+
+/// The terms of a field of a document by TF-IDF: their frequency in the
+/// document times their inverse document frequency in the index.
+pub(crate) fn document_terms(
+    searcher: &tv::Searcher,
+    address: tv::DocAddress,
+    field: Field,
+    k: usize,
+) -> tv::Result<Vec<(String, f64)>> {
+    let segment = searcher.segment_reader(address.segment_ord);
+    let num_docs = searcher.num_docs();
+    let terms = term_vector(segment, field, address.doc_id)?
+        .into_iter()
+        .map(|(text, freq, _)| {
+            let doc_freq =
+                searcher.doc_freq(&Term::from_field_text(field, &text))?;
+            Ok((text, freq as f64 * idf(doc_freq, num_docs)))
+        })
+        .collect::<tv::Result<_>>()?;
+    Ok(top_k(terms, k))
+}
+
+/// The terms of a field over-represented in the documents matching a query,
+/// the foreground, compared to the whole index, the background.
+///
+/// Terms are scored like the JLH heuristic of Elasticsearch: the absolute
+/// change of their document frequency times its relative change. The terms
+/// which aren't more frequent in the foreground are left out.
+pub(crate) fn query_terms(
+    searcher: &tv::Searcher,
+    query: &dyn Query,
+    field: Field,
+    k: usize,
+) -> tv::Result<Vec<(String, f64)>> {
+    let weight =
+        query.weight(EnableScoring::disabled_from_searcher(searcher))?;
+    let mut foreground_size = 0u64;
+    let mut foreground_freqs: HashMap<Vec<u8>, u64> = HashMap::new();
+    for segment in searcher.segment_readers() {
+        let mut foreground = DocBitSet::collect(weight.as_ref(), segment)?;
+        if let Some(alive) = segment.alive_bitset() {
+            foreground.retain(|doc| alive.is_alive(doc));
+        }
+        if foreground.len() == 0 {
+            continue;
+        }
+        foreground_size += foreground.len() as u64;
+        let inverted_index = segment.inverted_index(field)?;
+        let mut terms = inverted_index.terms().stream()?;
+        while terms.advance() {
+            let mut postings = inverted_index.read_postings_from_terminfo(
+                terms.value(),
+                IndexRecordOption::Basic,
+            )?;
+            let mut count = 0;
+            let mut doc = postings.doc();
+            while doc != TERMINATED {
+                if foreground.contains(doc) {
+                    count += 1;
+                }
+                doc = postings.advance();
+            }
+            if count > 0 {
+                *foreground_freqs.entry(terms.key().to_vec()).or_default() +=
+                    count;
+            }
+        }
+    }
+
+    let background_size = searcher.num_docs();
+    let mut scored = Vec::new();
+    for (bytes, foreground_freq) in foreground_freqs {
+        let term = Term::from_field_bytes(field, &bytes);
+        let background_freq = searcher.doc_freq(&term)?;
+        let foreground_rate = foreground_freq as f64 / foreground_size as f64;
+        let background_rate =
+            background_freq as f64 / background_size.max(1) as f64;
+        if foreground_rate <= background_rate || background_rate == 0.0 {
+            continue;
+        }
+        let score = (foreground_rate - background_rate)
+            * (foreground_rate / background_rate);
+        let text = String::from_utf8_lossy(&bytes).into_owned();
+        scored.push((text, score));
+    }
+    Ok(top_k(scored, k))
+}
@@ -2,24 +2,115 @@ use ::tantivy as tv;
 use ::tantivy::schema::{OwnedValue as Value, Term};
 use pyo3::{exceptions, prelude::*, wrap_pymodule};
 
+mod activity;
+mod aggregation;
+mod analysis;
+mod approx_count;
+mod asyncio;
+mod auto_commit;
+mod bitset;
+mod block_join;
+mod bytes;
+mod catalog;
+mod collectors;
+mod common_terms;
+mod compaction;
+mod diversify;
 mod document;
+mod errors;
+mod evaluation;
+mod expression;
 mod facet;
+mod features;
+mod fetch_session;
+mod field_alias;
+#[cfg(feature = "flight")]
+mod flight;
+mod geo;
+mod history;
 mod index;
+mod integrity;
+mod keywords;
+mod logging;
+mod metrics;
+mod min_should_match;
+mod multi_index;
 mod parser_error;
+mod paths;
+mod percolator;
+mod profile;
+mod pythonize;
 mod query;
+mod query_builder;
+mod query_cache;
+mod query_escape;
+mod query_limits;
+mod query_validation;
+mod read_mode;
+mod read_only;
 mod schema;
 mod schemabuilder;
+mod scorer;
+mod scroll;
 mod searcher;
+#[cfg(feature = "server")]
+mod server;
+mod sidecar;
+mod signals;
+mod signature;
+mod simple_query;
 mod snippet;
+mod sort;
+mod spell;
+mod stats;
+mod suggest;
+mod table;
+mod tenant;
+mod term_vector;
+mod throttle;
+mod vector;
 
+use bitset::Bitset;
+use bytes::Bytes;
+use catalog::IndexCatalog;
+use collectors::MultiCollector;
+use compaction::CompactionPolicy;
 use document::{extract_value, extract_value_for_type, Document};
-use facet::Facet;
-use index::Index;
+use evaluation::Evaluator;
+use facet::{Facet, FacetCounts};
+use features::FeatureMatrix;
+use fetch_session::FetchSession;
+use index::{Index, IndexMeta, IndexSettings};
+use multi_index::{MultiIndexReader, MultiSearcher};
+use percolator::Percolator;
 use query::{Occur, Query};
+use query_builder::QueryBuilder;
 use schema::{FieldType, Schema};
 use schemabuilder::SchemaBuilder;
-use searcher::{DocAddress, Order, SearchResult, Searcher};
+use scroll::Scroll;
+use searcher::{DocAddress, Order, PinnedSearcher, SearchResult, Searcher};
+use signals::SignalStore;
 use snippet::{Snippet, SnippetGenerator};
+use spell::SpellCorrector;
+use suggest::Suggester;
+use tenant::TenantIndex;
+
+// Multi-threaded servers share these objects between Python threads, which
+// run truly in parallel on free-threaded Python. Break the build if they stop
+// being Sync.
+const _: () = {
+    const fn assert_sync<T: Send + Sync>() {}
+    assert_sync::<Index>();
+    assert_sync::<index::IndexWriter>();
+    assert_sync::<Searcher>();
+    assert_sync::<SearchResult>();
+    assert_sync::<DocAddress>();
+    assert_sync::<Query>();
+    assert_sync::<Schema>();
+    assert_sync::<Document>();
+    assert_sync::<SnippetGenerator>();
+    assert_sync::<Scroll>();
+};
 
 /// Python bindings for the search engine library Tantivy.
 ///
@@ -71,23 +162,59 @@ use snippet::{Snippet, SnippetGenerator};
 ///
 ///     >>> assert len(result) == 1
 ///
-#[pymodule]
-fn tantivy(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
+#[pymodule(gil_used = false)]
+fn tantivy(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_class::<Order>()?;
     m.add_class::<Schema>()?;
     m.add_class::<SchemaBuilder>()?;
     m.add_class::<Searcher>()?;
+    m.add_class::<SignalStore>()?;
     m.add_class::<SearchResult>()?;
+    m.add_class::<Bytes>()?;
+    m.add_class::<PinnedSearcher>()?;
+    m.add_class::<Scroll>()?;
+    m.add_class::<FetchSession>()?;
+    m.add_class::<Bitset>()?;
     m.add_class::<Document>()?;
     m.add_class::<Index>()?;
+    m.add_class::<IndexCatalog>()?;
+    m.add_class::<IndexMeta>()?;
+    m.add_class::<IndexSettings>()?;
+    m.add_class::<CompactionPolicy>()?;
     m.add_class::<DocAddress>()?;
     m.add_class::<Facet>()?;
+    m.add_class::<FacetCounts>()?;
+    m.add_class::<FeatureMatrix>()?;
     m.add_class::<Query>()?;
+    m.add_class::<QueryBuilder>()?;
     m.add_class::<Snippet>()?;
     m.add_class::<SnippetGenerator>()?;
+    m.add_class::<SpellCorrector>()?;
+    m.add_class::<Suggester>()?;
+    m.add_class::<Percolator>()?;
+    m.add_class::<Evaluator>()?;
+    m.add_class::<TenantIndex>()?;
+    m.add_class::<MultiIndexReader>()?;
+    m.add_class::<MultiSearcher>()?;
+    m.add_class::<MultiCollector>()?;
     m.add_class::<Occur>()?;
     m.add_class::<FieldType>()?;
 
+    errors::register(py, m)?;
+
+    m.add_function(wrap_pyfunction!(metrics::set_metrics_callback, m)?)?;
+    m.add_function(wrap_pyfunction!(metrics::metrics_prometheus, m)?)?;
+    m.add_function(wrap_pyfunction!(logging::configure_logging, m)?)?;
+    m.add_function(wrap_pyfunction!(logging::disable_logging, m)?)?;
+    m.add_function(wrap_pyfunction!(query_escape::escape_query_string, m)?)?;
+    #[cfg(feature = "server")]
+    {
+        m.add_class::<server::SearchServer>()?;
+        m.add_function(wrap_pyfunction!(server::serve, m)?)?;
+    }
+    #[cfg(feature = "flight")]
+    m.add_class::<flight::FlightServer>()?;
+
     m.add_wrapped(wrap_pymodule!(query_parser_error))?;
 
     m.add("__version__", tv::version_string())?;
@@ -119,7 +246,7 @@ fn tantivy(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
 ///     >>> assert len(errors) == 2
 ///     >>> assert isinstance(errors[0], query_parser_error.FieldDoesNotExistError)
 ///     >>> assert isinstance(errors[1], query_parser_error.ExpectedIntError)
-#[pymodule]
+#[pymodule(gil_used = false)]
 fn query_parser_error(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_class::<parser_error::SyntaxError>()?;
     m.add_class::<parser_error::UnsupportedQueryError>()?;
@@ -143,7 +270,7 @@ fn query_parser_error(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
 }
 
 pub(crate) fn to_pyerr<E: ToString>(err: E) -> PyErr {
-    exceptions::PyValueError::new_err(err.to_string())
+    errors::TantivyError::new_err(err.to_string())
 }
 
 pub(crate) fn get_field(
@@ -151,9 +278,10 @@ pub(crate) fn get_field(
     field_name: &str,
 ) -> PyResult<tv::schema::Field> {
     let field = schema.get_field(field_name).map_err(|_err| {
-        exceptions::PyValueError::new_err(format!(
-            "Field `{field_name}` is not defined in the schema."
-        ))
+        errors::schema_err(
+            format!("Field `{field_name}` is not defined in the schema."),
+            field_name,
+        )
     })?;
 
     Ok(field)
@@ -190,10 +318,18 @@ pub(crate) fn make_term_for_type(
     field_name: &str,
     field_type: FieldType,
     field_value: &Bound<PyAny>,
+) -> PyResult<tv::Term> {
+    make_term_for_value_type(schema, field_name, field_type.into(), field_value)
+}
+
+pub(crate) fn make_term_for_value_type(
+    schema: &tv::schema::Schema,
+    field_name: &str,
+    value_type: tv::schema::Type,
+    field_value: &Bound<PyAny>,
 ) -> PyResult<tv::Term> {
     let field = get_field(schema, field_name)?;
-    let value =
-        extract_value_for_type(field_value, field_type.into(), field_name)?;
+    let value = extract_value_for_type(field_value, value_type, field_name)?;
     let term = match value {
         Value::Str(text) => Term::from_field_text(field, &text),
         Value::U64(num) => Term::from_field_u64(field, num),
@@ -0,0 +1,195 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex, Once,
+    },
+    thread,
+    time::Duration,
+};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyDict};
+
+/// The name of the Python logger the records are sent to.
+static LOGGER_NAME: Mutex<String> = Mutex::new(String::new());
+static INSTALL: Once = Once::new();
+/// Whether records are forwarded, turned off by `disable_logging` and
+/// before the interpreter is finalized.
+static FORWARDING: AtomicBool = AtomicBool::new(false);
+/// The number of records being forwarded.
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+/// Forwards the records of tantivy's `log` facade to Python's `logging`.
+struct PythonLogger;
+
+/// Counts a record as being forwarded while it lives.
+struct InFlight;
+
+impl InFlight {
+    fn enter() -> Self {
+        IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+        InFlight
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Log for PythonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        // Merges, commits and reloads log from their threads, which may
+        // outlive the interpreter: taking the GIL once it is finalizing
+        // aborts the process. The forwarding is turned off before that, see
+        // `stop_forwarding`, which waits for the records in flight.
+        let _in_flight = InFlight::enter();
+        if !FORWARDING.load(Ordering::SeqCst) {
+            return;
+        }
+        let logger_name = LOGGER_NAME.lock().unwrap().clone();
+        Python::with_gil(|py| {
+            let result = (|| {
+                let logger = py
+                    .import("logging")?
+                    .call_method1("getLogger", (logger_name,))?;
+                let extra = PyDict::new(py);
+                extra.set_item("target", record.target())?;
+                let kwargs = PyDict::new(py);
+                kwargs.set_item("extra", extra)?;
+                logger.call_method(
+                    "log",
+                    (python_level(record.level()), record.args().to_string()),
+                    Some(&kwargs),
+                )?;
+                PyResult::Ok(())
+            })();
+            if let Err(err) = result {
+                err.write_unraisable(py, None);
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Stop forwarding records, and wait for the records in flight, which may
+/// be waiting for the GIL.
+fn stop_forwarding(py: Python) {
+    FORWARDING.store(false, Ordering::SeqCst);
+    py.allow_threads(|| {
+        while IN_FLIGHT.load(Ordering::SeqCst) > 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+    });
+}
+
+/// Registered with `atexit`, so that the forwarding stops before the
+/// interpreter is finalized.
+#[pyfunction]
+fn stop_forwarding_at_exit(py: Python) {
+    stop_forwarding(py);
+}
+
+fn python_level(level: Level) -> u32 {
+    match level {
+        Level::Error => 40,
+        Level::Warn => 30,
+        Level::Info => 20,
+        Level::Debug => 10,
+        Level::Trace => 5,
+    }
+}
+
+/// The most verbose records logged at a Python level.
+fn level_filter(level: &Bound<PyAny>) -> PyResult<LevelFilter> {
+    let level =
+        match level.extract::<u32>() {
+            Ok(level) => level,
+            Err(_) => match level.extract::<String>()?.to_uppercase().as_str() {
+                "CRITICAL" | "ERROR" => 40,
+                "WARNING" | "WARN" => 30,
+                "INFO" => 20,
+                "DEBUG" => 10,
+                "TRACE" | "NOTSET" => 0,
+                _ => return Err(PyValueError::new_err(
+                    "Invalid level, valid choices are: 'ERROR', 'WARNING', \
+                     'INFO', 'DEBUG', 'TRACE' or a number",
+                )),
+            },
+        };
+    Ok(match level {
+        41.. => LevelFilter::Off,
+        40 => LevelFilter::Error,
+        30..=39 => LevelFilter::Warn,
+        20..=29 => LevelFilter::Info,
+        10..=19 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    })
+}
+
+/// Send the log records of tantivy to Python's `logging` module.
+///
+/// Merges, commits and garbage collection log their progress and their
+/// failures. The records go to the `logger_name` logger, with the Rust
+/// module emitting them as the `target` attribute. Calling this function
+/// again changes the level and the logger, `disable_logging` stops sending
+/// the records.
+///
+/// The records stop being sent when the interpreter exits, so that the
+/// background threads of tantivy don't log into a finalized interpreter.
+///
+/// Args:
+///     level (Union[int, str]): The least severe records to forward, a
+///         `logging` level or its name. "TRACE" forwards every record, and
+///         a level above "ERROR" none. Defaults to "WARNING".
+///     logger_name (str): The name of the Python logger. Defaults to
+///         "tantivy".
+///
+/// Raises a ValueError if the level is invalid.
+#[pyfunction]
+#[pyo3(signature = (level = None, logger_name = "tantivy"))]
+pub(crate) fn configure_logging(
+    py: Python,
+    level: Option<&Bound<PyAny>>,
+    logger_name: &str,
+) -> PyResult<()> {
+    let filter = match level {
+        Some(level) => level_filter(level)?,
+        None => LevelFilter::Warn,
+    };
+    *LOGGER_NAME.lock().unwrap() = logger_name.to_string();
+    let mut installed = Ok(());
+    INSTALL.call_once(|| {
+        installed = log::set_logger(&PythonLogger)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+            .and_then(|_| {
+                let hook = wrap_pyfunction!(stop_forwarding_at_exit, py)?;
+                py.import("atexit")?.call_method1("register", (hook,))?;
+                Ok(())
+            });
+    });
+    installed?;
+    log::set_max_level(filter);
+    FORWARDING.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Stop sending the log records of tantivy to Python's `logging` module,
+/// until `configure_logging` is called again.
+///
+/// The records being sent when this function is called are sent before it
+/// returns.
+#[pyfunction]
+pub(crate) fn disable_logging(py: Python) {
+    log::set_max_level(LevelFilter::Off);
+    stop_forwarding(py);
+}
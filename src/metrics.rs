@@ -0,0 +1,326 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Instant,
+};
+
+use pyo3::{exceptions::PyTypeError, prelude::*, types::PyDict};
+
+use crate::{index::Index, stats::IndexStats};
+
+/// Whether a callback is set, to skip building events without taking the
+/// GIL.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static CALLBACK: Mutex<Option<PyObject>> = Mutex::new(None);
+
+/// The totals of the events of every operation since the module was
+/// loaded, see `metrics_prometheus`.
+static TOTALS: Mutex<BTreeMap<&str, Totals>> = Mutex::new(BTreeMap::new());
+
+/// The counters of the events holding the state after the operation
+/// rather than an amount, exported as their last value.
+const GAUGES: &[(&str, &str)] = &[
+    ("commit", "opstamp"),
+    ("reload", "num_segments"),
+    ("reload", "num_docs"),
+];
+
+#[derive(Default)]
+struct Totals {
+    count: u64,
+    duration_secs: f64,
+    /// The sum, or the last value of the gauges, of each counter.
+    counters: BTreeMap<&'static str, u64>,
+}
+
+/// Set a callback receiving an event for every search, commit, merge and
+/// reload.
+///
+/// The callback is called with a dict holding the `operation` name, its
+/// `duration_ms` and counters depending on the operation:
+///
+/// - search: `num_hits`, `num_segments`, and `count` if it was requested.
+/// - commit: `opstamp` and `num_docs`, the number of documents committed.
+///   Auto commits are reported too, from their background thread.
+/// - merge: `num_segments`, the number of segments merged by
+///   `IndexWriter.merge`. The background merges are not reported.
+/// - reload: `num_segments` and `num_docs` of the reloaded searcher.
+///
+/// Exceptions raised by the callback are reported as unraisable exceptions,
+/// they don't interrupt the operation.
+///
+/// Args:
+///     callback (Callable[[dict], None], optional): The callback, None to
+///         stop emitting events.
+///
+/// Raises a TypeError if the callback isn't callable.
+#[pyfunction]
+#[pyo3(signature = (callback = None))]
+pub(crate) fn set_metrics_callback(
+    callback: Option<&Bound<PyAny>>,
+) -> PyResult<()> {
+    if callback.is_some_and(|callback| !callback.is_callable()) {
+        return Err(PyTypeError::new_err(
+            "The metrics callback must be callable.",
+        ));
+    }
+    let mut current = CALLBACK.lock().unwrap();
+    *current = callback.map(|callback| callback.clone().unbind());
+    ENABLED.store(current.is_some(), Ordering::SeqCst);
+    Ok(())
+}
+
+/// Add the event of an operation to the totals.
+fn record(
+    operation: &'static str,
+    duration_secs: f64,
+    counters: &[(&'static str, u64)],
+) {
+    let mut totals = TOTALS.lock().unwrap();
+    let totals = totals.entry(operation).or_default();
+    totals.count += 1;
+    totals.duration_secs += duration_secs;
+    for &(name, value) in counters {
+        let total = totals.counters.entry(name).or_default();
+        if GAUGES.contains(&(operation, name)) {
+            *total = value;
+        } else {
+            *total += value;
+        }
+    }
+}
+
+/// Emit the event of an operation started at `start`, taking the GIL if
+/// needed. Must not be called while holding a lock the callback could need.
+pub(crate) fn emit(
+    operation: &'static str,
+    start: Instant,
+    counters: &[(&'static str, u64)],
+) {
+    let duration_secs = start.elapsed().as_secs_f64();
+    record(operation, duration_secs, counters);
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let duration_ms = duration_secs * 1000.0;
+    Python::with_gil(|py| {
+        let Some(callback) =
+            CALLBACK.lock().unwrap().as_ref().map(|c| c.clone_ref(py))
+        else {
+            return;
+        };
+        let result = (|| {
+            let event = PyDict::new(py);
+            event.set_item("operation", operation)?;
+            event.set_item("duration_ms", duration_ms)?;
+            for (name, value) in counters {
+                event.set_item(name, value)?;
+            }
+            callback.call1(py, (event,))
+        })();
+        if let Err(err) = result {
+            err.write_unraisable(py, Some(callback.bind(py)));
+        }
+    });
+}
+
+/// Render the totals of the events of the operations, and the stats of
+/// indexes, in the Prometheus text exposition format.
+///
+/// The events are the ones passed to the callback of
+/// `set_metrics_callback`, counted whether a callback is set or not, since
+/// the module was loaded:
+///
+/// - `tantivy_operations_total` and
+///   `tantivy_operation_duration_seconds_total`, labeled by `operation`.
+/// - `tantivy_<operation>_<counter>_total`, the sum of a counter of the
+///   events, e.g. `tantivy_search_num_hits_total`.
+/// - `tantivy_commit_opstamp`, `tantivy_reload_num_segments` and
+///   `tantivy_reload_num_docs`, the value of the last event.
+///
+/// The stats of the indexes, see `Index.stats`, are labeled by `index`:
+/// `tantivy_index_num_docs`, `tantivy_index_num_deleted_docs`,
+/// `tantivy_index_num_segments`, `tantivy_index_disk_usage_bytes` labeled
+/// by `component` too, `tantivy_index_commits_total`,
+/// `tantivy_index_committed_docs_total`, `tantivy_index_merges_total`,
+/// `tantivy_index_last_commit_timestamp_seconds` and
+/// `tantivy_index_last_merge_timestamp_seconds`.
+///
+/// Args:
+///     indexes (Dict[str, Index], optional): The indexes whose stats are
+///         exported, by name. Defaults to none.
+///
+/// Returns the metrics as a string, e.g. the body of the response to a
+/// Prometheus scrape.
+#[pyfunction]
+#[pyo3(signature = (indexes = None))]
+pub(crate) fn metrics_prometheus(
+    py: Python,
+    indexes: Option<BTreeMap<String, PyRef<Index>>>,
+) -> PyResult<String> {
+    let mut out = String::new();
+    write_totals(&mut out, &TOTALS.lock().unwrap());
+    let stats = indexes
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, index)| Ok((escape_label(&name), index.stats_report(py)?)))
+        .collect::<PyResult<Vec<_>>>()?;
+    write_index_stats(&mut out, &stats);
+    Ok(out)
+}
+
+/// Write the header of a metric family.
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn write_totals(out: &mut String, totals: &BTreeMap<&str, Totals>) {
+    family(
+        out,
+        "tantivy_operations_total",
+        "counter",
+        "The number of operations.",
+    );
+    for (operation, totals) in totals {
+        let _ = writeln!(
+            out,
+            "tantivy_operations_total{{operation=\"{operation}\"}} {}",
+            totals.count
+        );
+    }
+    family(
+        out,
+        "tantivy_operation_duration_seconds_total",
+        "counter",
+        "The time spent in the operations.",
+    );
+    for (operation, totals) in totals {
+        let _ = writeln!(
+            out,
+            "tantivy_operation_duration_seconds_total\
+             {{operation=\"{operation}\"}} {}",
+            totals.duration_secs
+        );
+    }
+    for (operation, totals) in totals {
+        for (counter, value) in &totals.counters {
+            let name = if GAUGES.contains(&(operation, counter)) {
+                let name = format!("tantivy_{operation}_{counter}");
+                let help = format!("The {counter} of the last {operation}.");
+                family(out, &name, "gauge", &help);
+                name
+            } else {
+                let name = format!("tantivy_{operation}_{counter}_total");
+                let help = format!(
+                    "The sum of the {counter} of the {operation} operations."
+                );
+                family(out, &name, "counter", &help);
+                name
+            };
+            let _ = writeln!(out, "{name} {value}");
+        }
+    }
+}
+
+/// The metrics of the stats of an index: their name, type, help and value,
+/// if any.
+type IndexMetric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&IndexStats) -> Option<f64>,
+);
+
+const INDEX_METRICS: &[IndexMetric] = &[
+    (
+        "tantivy_index_num_docs",
+        "gauge",
+        "The number of documents of the index.",
+        |stats| Some(stats.num_docs as f64),
+    ),
+    (
+        "tantivy_index_num_deleted_docs",
+        "gauge",
+        "The number of deleted documents not merged away yet.",
+        |stats| Some(stats.num_deleted_docs as f64),
+    ),
+    (
+        "tantivy_index_num_segments",
+        "gauge",
+        "The number of segments of the index.",
+        |stats| Some(stats.num_segments as f64),
+    ),
+    (
+        "tantivy_index_commits_total",
+        "counter",
+        "The number of commits since the index was opened.",
+        |stats| Some(stats.commits as f64),
+    ),
+    (
+        "tantivy_index_committed_docs_total",
+        "counter",
+        "The number of documents committed since the index was opened.",
+        |stats| Some(stats.committed_docs as f64),
+    ),
+    (
+        "tantivy_index_merges_total",
+        "counter",
+        "The number of merges started since the index was opened.",
+        |stats| Some(stats.merges as f64),
+    ),
+    (
+        "tantivy_index_last_commit_timestamp_seconds",
+        "gauge",
+        "The time of the last commit.",
+        |stats| stats.last_commit,
+    ),
+    (
+        "tantivy_index_last_merge_timestamp_seconds",
+        "gauge",
+        "The time of the start of the last merge.",
+        |stats| stats.last_merge,
+    ),
+];
+
+/// Write the stats of indexes, labeled by their escaped names.
+fn write_index_stats(out: &mut String, indexes: &[(String, IndexStats)]) {
+    if indexes.is_empty() {
+        return;
+    }
+    for (name, kind, help, value) in INDEX_METRICS {
+        family(out, name, kind, help);
+        for (index, stats) in indexes {
+            if let Some(value) = value(stats) {
+                let _ = writeln!(out, "{name}{{index=\"{index}\"}} {value}");
+            }
+        }
+    }
+    family(
+        out,
+        "tantivy_index_disk_usage_bytes",
+        "gauge",
+        "The bytes used by each component of the segments of the index.",
+    );
+    for (index, stats) in indexes {
+        for (component, bytes) in stats.disk_usage.components() {
+            let _ = writeln!(
+                out,
+                "tantivy_index_disk_usage_bytes\
+                 {{index=\"{index}\",component=\"{component}\"}} {bytes}"
+            );
+        }
+    }
+}
+
+/// Escape a label value of the exposition format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
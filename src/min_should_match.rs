@@ -0,0 +1,130 @@
+use pyo3::{exceptions::PyValueError, prelude::*};
+use tantivy::{
+    self as tv,
+    query::{EnableScoring, Explanation, Scorer, Weight},
+    DocId, DocSet, Score, SegmentReader, TERMINATED,
+};
+
+use crate::scorer::{explain_by_seek, VecScorer};
+
+/// How many of the optional clauses of a boolean query must match, either a
+/// number or a percentage like "75%" of the optional clauses, rounded down.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum MinimumShouldMatch {
+    Count(usize),
+    Percent(f64),
+}
+
+impl MinimumShouldMatch {
+    /// The number of clauses that must match among `num_clauses`.
+    pub(crate) fn resolve(self, num_clauses: usize) -> usize {
+        match self {
+            MinimumShouldMatch::Count(count) => count,
+            MinimumShouldMatch::Percent(percent) => {
+                (num_clauses as f64 * percent / 100.0).floor() as usize
+            }
+        }
+    }
+}
+
+impl<'py> FromPyObject<'py> for MinimumShouldMatch {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(count) = ob.extract::<usize>() {
+            return Ok(MinimumShouldMatch::Count(count));
+        }
+        let invalid = || {
+            PyValueError::new_err(
+                "minimum_should_match must be a non-negative number of \
+                 clauses, or a percentage like '75%'.",
+            )
+        };
+        let percent = ob
+            .extract::<&str>()
+            .map_err(|_| invalid())?
+            .strip_suffix('%')
+            .and_then(|percent| percent.trim().parse::<f64>().ok())
+            .filter(|percent| (0.0..=100.0).contains(percent))
+            .ok_or_else(invalid)?;
+        Ok(MinimumShouldMatch::Percent(percent))
+    }
+}
+
+/// Query matching the documents matched by at least `minimum` of its
+/// clauses, scored by the sum of the scores of the matching clauses.
+#[derive(Debug)]
+pub(crate) struct AtLeastQuery {
+    pub(crate) clauses: Vec<Box<dyn tv::query::Query>>,
+    pub(crate) minimum: usize,
+}
+
+impl Clone for AtLeastQuery {
+    fn clone(&self) -> Self {
+        AtLeastQuery {
+            clauses: self.clauses.iter().map(|q| q.box_clone()).collect(),
+            minimum: self.minimum,
+        }
+    }
+}
+
+impl tv::query::Query for AtLeastQuery {
+    fn weight(
+        &self,
+        enable_scoring: EnableScoring<'_>,
+    ) -> tv::Result<Box<dyn Weight>> {
+        let weights = self
+            .clauses
+            .iter()
+            .map(|clause| clause.weight(enable_scoring))
+            .collect::<tv::Result<_>>()?;
+        Ok(Box::new(AtLeastWeight {
+            weights,
+            minimum: self.minimum,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a tv::Term, bool)) {
+        for clause in &self.clauses {
+            clause.query_terms(visitor);
+        }
+    }
+}
+
+struct AtLeastWeight {
+    weights: Vec<Box<dyn Weight>>,
+    minimum: usize,
+}
+
+impl Weight for AtLeastWeight {
+    fn scorer(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> tv::Result<Box<dyn Scorer>> {
+        let mut matches: Vec<(DocId, Score)> = Vec::new();
+        for weight in &self.weights {
+            let mut scorer = weight.scorer(reader, boost)?;
+            let mut doc = scorer.doc();
+            while doc != TERMINATED {
+                matches.push((doc, scorer.score()));
+                doc = scorer.advance();
+            }
+        }
+        // Each clause matches a document at most once, so the clauses
+        // matching a document are the entries of its group.
+        matches.sort_by_key(|&(doc, _)| doc);
+        let docs = matches
+            .chunk_by(|a, b| a.0 == b.0)
+            .filter(|group| group.len() >= self.minimum)
+            .map(|group| (group[0].0, group.iter().map(|&(_, s)| s).sum()))
+            .collect();
+        Ok(Box::new(VecScorer::new(docs)))
+    }
+
+    fn explain(
+        &self,
+        reader: &SegmentReader,
+        doc: DocId,
+    ) -> tv::Result<Explanation> {
+        explain_by_seek(self, reader, doc, "AtLeastQuery")
+    }
+}
@@ -0,0 +1,151 @@
+//! Searchers over several indexes taken together, see `MultiIndexReader`.
+
+use std::sync::{Arc, Mutex};
+
+use pyo3::{exceptions::PyIndexError, prelude::*};
+
+use crate::{index::Index, searcher::Searcher};
+
+/// The searchers of the indexes of a reader, taken by the same reload.
+struct Snapshot {
+    generation: u64,
+    searchers: Vec<Searcher>,
+}
+
+/// Reads several indexes, e.g. the partitions of a federated setup, as of
+/// the same reload.
+///
+/// The reader holds one searcher per index, replaced all at once by
+/// `reload`. `acquire` returns them together with the generation of the
+/// reload, so the searches over all the partitions see the same commits
+/// even while writers commit, until the next reload.
+///
+/// Args:
+///     indexes (List[Index]): The indexes to read.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+pub(crate) struct MultiIndexReader {
+    indexes: Vec<Py<Index>>,
+    snapshot: Mutex<Arc<Snapshot>>,
+}
+
+/// The current searchers of the indexes.
+fn searchers(py: Python, indexes: &[Py<Index>]) -> Vec<Searcher> {
+    indexes
+        .iter()
+        .map(|index| index.borrow(py).searcher())
+        .collect()
+}
+
+#[pymethods]
+impl MultiIndexReader {
+    #[new]
+    fn new(py: Python, indexes: Vec<Py<Index>>) -> Self {
+        let snapshot = Snapshot {
+            generation: 0,
+            searchers: searchers(py, &indexes),
+        };
+        MultiIndexReader {
+            indexes,
+            snapshot: Mutex::new(Arc::new(snapshot)),
+        }
+    }
+
+    /// The indexes of the reader.
+    #[getter]
+    fn indexes(&self, py: Python) -> Vec<Py<Index>> {
+        self.indexes
+            .iter()
+            .map(|index| index.clone_ref(py))
+            .collect()
+    }
+
+    /// The generation of the searchers returned by `acquire`, incremented
+    /// on every reload.
+    #[getter]
+    fn generation(&self) -> u64 {
+        self.snapshot.lock().unwrap().generation
+    }
+
+    /// Reload the indexes and replace the searchers of all of them at once.
+    ///
+    /// The commits made while the indexes reload may only be seen by the
+    /// searchers of some of them.
+    fn reload(&self, py: Python) -> PyResult<()> {
+        for index in &self.indexes {
+            index.borrow(py).reload(py)?;
+        }
+        let searchers = searchers(py, &self.indexes);
+        let mut snapshot = self.snapshot.lock().unwrap();
+        *snapshot = Arc::new(Snapshot {
+            generation: snapshot.generation + 1,
+            searchers,
+        });
+        Ok(())
+    }
+
+    /// Acquire the searchers of the indexes, all as of the last reload.
+    ///
+    /// Returns a MultiSearcher holding one searcher per index, in the order
+    /// of the indexes.
+    fn acquire(&self) -> MultiSearcher {
+        MultiSearcher {
+            snapshot: self.snapshot.lock().unwrap().clone(),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MultiIndexReader(indexes: {}, generation: {})",
+            self.indexes.len(),
+            self.generation()
+        )
+    }
+}
+
+/// The searchers of several indexes as of the same reload of a
+/// `MultiIndexReader`, returned by `MultiIndexReader.acquire`.
+///
+/// The searchers keep the files of their segments, so the view doesn't
+/// change while the searcher is alive.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+pub(crate) struct MultiSearcher {
+    snapshot: Arc<Snapshot>,
+}
+
+#[pymethods]
+impl MultiSearcher {
+    /// The generation of the reload of the reader the searchers were taken
+    /// by. Searchers of the same generation see the same commits.
+    #[getter]
+    fn generation(&self) -> u64 {
+        self.snapshot.generation
+    }
+
+    /// The searchers of the indexes, in the order of the indexes of the
+    /// reader.
+    #[getter]
+    fn searchers(&self) -> Vec<Searcher> {
+        self.snapshot.searchers.clone()
+    }
+
+    fn __len__(&self) -> usize {
+        self.snapshot.searchers.len()
+    }
+
+    fn __getitem__(&self, index: isize) -> PyResult<Searcher> {
+        let len = self.snapshot.searchers.len() as isize;
+        let position = if index < 0 { index + len } else { index };
+        if !(0..len).contains(&position) {
+            return Err(PyIndexError::new_err("Searcher index out of range."));
+        }
+        Ok(self.snapshot.searchers[position as usize].clone())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MultiSearcher(searchers: {}, generation: {})",
+            self.snapshot.searchers.len(),
+            self.snapshot.generation
+        )
+    }
+}
@@ -0,0 +1,47 @@
+//! The paths of index directories given by Python, as `str` or
+//! `os.PathLike` objects.
+
+use std::path::PathBuf;
+
+/// The maximum length of a path of the Windows file APIs, unless the path
+/// is in the verbatim `\\?\` form.
+#[cfg(windows)]
+const MAX_PATH: usize = 260;
+
+/// Normalize the path of an index directory.
+///
+/// On Windows, long paths are made absolute and verbatim, so that the
+/// files of deeply nested indexes can be opened without the long paths
+/// setting of the system. Paths are passed to the wide character file
+/// APIs, so non-ASCII directories need no conversion.
+#[cfg(windows)]
+pub(crate) fn normalize(path: PathBuf) -> PathBuf {
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+    let Ok(absolute) = std::path::absolute(&path) else {
+        return path;
+    };
+    let wide: Vec<u16> = absolute.as_os_str().encode_wide().collect();
+    let prefixed = |prefix: &str| {
+        wide.starts_with(&prefix.encode_utf16().collect::<Vec<_>>())
+    };
+    if wide.len() < MAX_PATH || prefixed(r"\\?\") {
+        return absolute;
+    }
+    // `C:\dir` becomes `\\?\C:\dir` and `\\server\share` becomes
+    // `\\?\UNC\server\share`.
+    let (prefix, rest) = if prefixed(r"\\") {
+        (r"\\?\UNC\", &wide[2..])
+    } else {
+        (r"\\?\", &wide[..])
+    };
+    let mut verbatim: Vec<u16> = prefix.encode_utf16().collect();
+    verbatim.extend_from_slice(rest);
+    PathBuf::from(std::ffi::OsString::from_wide(&verbatim))
+}
+
+/// Normalize the path of an index directory.
+#[cfg(not(windows))]
+pub(crate) fn normalize(path: PathBuf) -> PathBuf {
+    path
+}
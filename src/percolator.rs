@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use pyo3::prelude::*;
+use tantivy::{
+    self as tv, collector::DocSetCollector, schema::NamedFieldDocument,
+    ReloadPolicy, SingleSegmentIndexWriter, TantivyDocument,
+};
+
+use crate::{
+    document::Document, errors::tantivy_err, index::Index, query::Query,
+    schema::Schema, to_pyerr,
+};
+
+/// Memory budget of the in memory index the documents are matched against.
+const MEMORY_BUDGET: usize = 15_000_000;
+
+/// Matches documents against a set of registered queries.
+///
+/// This is the reverse of a search: instead of finding the documents
+/// matching a query, it finds the queries matching a document, e.g. to alert
+/// on saved searches as new documents come in.
+///
+/// Args:
+///     schema (Schema): The schema of the documents that will be matched.
+///
+/// Example:
+///
+///     >>> percolator = tantivy.Percolator(schema)
+///     >>> percolator.add_query("sea", index.parse_query("sea", ["title"]))
+///     >>> percolator.match_document(tantivy.Document(title="The Sea"))
+///     ['sea']
+#[pyclass(module = "tantivy.tantivy")]
+pub(crate) struct Percolator {
+    schema: tv::schema::Schema,
+    queries: BTreeMap<String, Query>,
+}
+
+impl Percolator {
+    /// Returns the ids of the queries matching each document.
+    fn percolate(
+        &self,
+        docs: Vec<TantivyDocument>,
+    ) -> tv::Result<Vec<Vec<String>>> {
+        let mut matches = vec![Vec::new(); docs.len()];
+        if docs.is_empty() || self.queries.is_empty() {
+            return Ok(matches);
+        }
+
+        let index = tv::Index::create_in_ram(self.schema.clone());
+        Index::register_custom_text_analyzers(&index);
+        let mut writer = SingleSegmentIndexWriter::new(index, MEMORY_BUDGET)?;
+        for doc in docs {
+            writer.add_document(doc)?;
+        }
+        let index = writer.finalize()?;
+        let searcher = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?
+            .searcher();
+
+        // The index has a single segment holding the documents in order.
+        for (id, query) in &self.queries {
+            for address in searcher.search(query.get(), &DocSetCollector)? {
+                matches[address.doc_id as usize].push(id.clone());
+            }
+        }
+        Ok(matches)
+    }
+
+    fn convert(&self, doc: &Document) -> PyResult<TantivyDocument> {
+        let named_doc = NamedFieldDocument(doc.field_values.clone());
+        TantivyDocument::convert_named_doc(&self.schema, named_doc)
+            .map_err(to_pyerr)
+    }
+}
+
+#[pymethods]
+impl Percolator {
+    #[new]
+    fn new(schema: &Schema) -> Self {
+        Percolator {
+            schema: schema.inner.clone(),
+            queries: BTreeMap::new(),
+        }
+    }
+
+    /// Register a query, replacing the query registered with the same id.
+    ///
+    /// Args:
+    ///     id (str): The id reported when the query matches a document.
+    ///     query (Query): The query to register.
+    fn add_query(&mut self, id: String, query: Query) {
+        self.queries.insert(id, query);
+    }
+
+    /// Unregister a query.
+    ///
+    /// Args:
+    ///     id (str): The id the query was registered with.
+    ///
+    /// Returns True if a query was registered with this id.
+    fn remove_query(&mut self, id: &str) -> bool {
+        self.queries.remove(id).is_some()
+    }
+
+    /// The number of registered queries.
+    fn __len__(&self) -> usize {
+        self.queries.len()
+    }
+
+    /// Find the registered queries matching a document.
+    ///
+    /// Args:
+    ///     doc (Document): The document to match.
+    ///
+    /// Returns the sorted ids of the matching queries.
+    ///
+    /// Raises a ValueError if the document doesn't match the schema.
+    fn match_document(
+        &self,
+        py: Python,
+        doc: &Document,
+    ) -> PyResult<Vec<String>> {
+        let doc = self.convert(doc)?;
+        py.allow_threads(|| {
+            let mut matches = self.percolate(vec![doc]).map_err(tantivy_err)?;
+            Ok(matches.pop().unwrap_or_default())
+        })
+    }
+
+    /// Find the registered queries matching each document of a batch.
+    ///
+    /// Matching a batch is much cheaper than matching its documents one by
+    /// one.
+    ///
+    /// Args:
+    ///     docs (List[Document]): The documents to match.
+    ///
+    /// Returns a list holding the sorted ids of the matching queries for
+    /// each document.
+    ///
+    /// Raises a ValueError if a document doesn't match the schema.
+    fn match_documents(
+        &self,
+        py: Python,
+        docs: Vec<PyRef<Document>>,
+    ) -> PyResult<Vec<Vec<String>>> {
+        let docs = docs
+            .iter()
+            .map(|doc| self.convert(doc))
+            .collect::<PyResult<Vec<_>>>()?;
+        py.allow_threads(|| self.percolate(docs).map_err(tantivy_err))
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tantivy::{
+    self as tv,
+    collector::Collector,
+    query::{EnableScoring, Query},
+};
+
+/// Where the time of a search went, in milliseconds.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub(crate) struct SearchProfile {
+    /// Building the weight of the query, including the term statistics
+    /// used for scoring.
+    pub(crate) weight_ms: f64,
+    pub(crate) segments: Vec<SegmentProfile>,
+    /// Merging the results of the segments.
+    pub(crate) merge_ms: f64,
+    pub(crate) total_ms: f64,
+}
+
+/// Matching, scoring and collecting the documents of a segment.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct SegmentProfile {
+    pub(crate) segment_ord: u32,
+    pub(crate) segment_id: String,
+    pub(crate) num_docs: u32,
+    pub(crate) collect_ms: f64,
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Run a search like `tv::Searcher::search`, one segment after the other,
+/// timing each phase.
+pub(crate) fn search_profiled<C: Collector>(
+    searcher: &tv::Searcher,
+    query: &dyn Query,
+    collector: &C,
+) -> tv::Result<(C::Fruit, SearchProfile)> {
+    let start = Instant::now();
+    let enable_scoring = if collector.requires_scoring() {
+        EnableScoring::enabled_from_searcher(searcher)
+    } else {
+        EnableScoring::disabled_from_searcher(searcher)
+    };
+    let weight = query.weight(enable_scoring)?;
+    let weight_ms = elapsed_ms(start);
+
+    let mut segments = Vec::new();
+    let mut fruits = Vec::new();
+    for (segment_ord, reader) in searcher.segment_readers().iter().enumerate() {
+        let segment_start = Instant::now();
+        let segment_ord = segment_ord as u32;
+        fruits.push(collector.collect_segment(
+            weight.as_ref(),
+            segment_ord,
+            reader,
+        )?);
+        segments.push(SegmentProfile {
+            segment_ord,
+            segment_id: reader.segment_id().uuid_string(),
+            num_docs: reader.num_docs(),
+            collect_ms: elapsed_ms(segment_start),
+        });
+    }
+
+    let merge_start = Instant::now();
+    let fruit = collector.merge_fruits(fruits)?;
+    let profile = SearchProfile {
+        weight_ms,
+        segments,
+        merge_ms: elapsed_ms(merge_start),
+        total_ms: elapsed_ms(start),
+    };
+    Ok((fruit, profile))
+}
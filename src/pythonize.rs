@@ -0,0 +1,840 @@
+//! Convert serde types to Python objects and back.
+//!
+//! Structs and maps become dicts, sequences lists and tuples tuples. Unit
+//! enum variants are their name, the other variants a dict with the name of
+//! the variant as the only key. This is the layout of the `pythonize` crate,
+//! which the bindings used until it lagged behind pyo3.
+
+use std::fmt;
+
+use pyo3::{
+    prelude::*,
+    types::{
+        PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyFrozenSet, PyInt,
+        PyList, PyMapping, PySequence, PySet, PyString, PyTuple,
+    },
+    IntoPyObjectExt,
+};
+use serde::{
+    de::{self, IntoDeserializer},
+    ser, Deserialize, Serialize,
+};
+
+/// An error converting a value, a Python error or one of serde.
+#[derive(Debug)]
+pub(crate) struct Error(String);
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+impl From<PyErr> for Error {
+    fn from(err: PyErr) -> Self {
+        Error(err.to_string())
+    }
+}
+
+impl From<pyo3::DowncastError<'_, '_>> for Error {
+    fn from(err: pyo3::DowncastError) -> Self {
+        Error(err.to_string())
+    }
+}
+
+/// Convert a value to a Python object.
+pub(crate) fn pythonize<T>(py: Python, value: &T) -> Result<PyObject>
+where
+    T: ?Sized + Serialize,
+{
+    value.serialize(Pythonizer { py })
+}
+
+/// Convert a Python object to a value.
+pub(crate) fn depythonize<T>(obj: &Bound<PyAny>) -> Result<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    T::deserialize(&mut Depythonizer { input: obj.clone() })
+}
+
+#[derive(Clone, Copy)]
+struct Pythonizer<'py> {
+    py: Python<'py>,
+}
+
+impl Pythonizer<'_> {
+    fn object<T>(self, value: T) -> Result<PyObject>
+    where
+        for<'py> T: IntoPyObject<'py>,
+    {
+        Ok(value.into_py_any(self.py)?)
+    }
+}
+
+/// The items of a list, tuple or tuple variant.
+struct SeqSerializer<'py> {
+    py: Python<'py>,
+    items: Vec<PyObject>,
+    /// The variant of a tuple variant.
+    variant: Option<&'static str>,
+    tuple: bool,
+}
+
+/// The entries of a map, struct or struct variant.
+struct MapSerializer<'py> {
+    dict: Bound<'py, PyDict>,
+    key: Option<PyObject>,
+    /// The variant of a struct variant.
+    variant: Option<&'static str>,
+}
+
+/// Wrap the value of a variant in a dict keyed by its name.
+fn variant_dict<'py>(
+    py: Python<'py>,
+    variant: &'static str,
+    value: impl IntoPyObject<'py>,
+) -> Result<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item(variant, value)?;
+    Ok(dict.into_any().unbind())
+}
+
+impl<'py> ser::Serializer for Pythonizer<'py> {
+    type Ok = PyObject;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer<'py>;
+    type SerializeTuple = SeqSerializer<'py>;
+    type SerializeTupleStruct = SeqSerializer<'py>;
+    type SerializeTupleVariant = SeqSerializer<'py>;
+    type SerializeMap = MapSerializer<'py>;
+    type SerializeStruct = MapSerializer<'py>;
+    type SerializeStructVariant = MapSerializer<'py>;
+
+    fn serialize_bool(self, v: bool) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<PyObject> {
+        self.object(v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<PyObject> {
+        Ok(PyString::new(self.py, v).into_any().unbind())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<PyObject> {
+        Ok(PyBytes::new(self.py, v).into_any().unbind())
+    }
+
+    fn serialize_none(self) -> Result<PyObject> {
+        Ok(self.py.None())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<PyObject>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<PyObject> {
+        self.serialize_none()
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<PyObject> {
+        self.serialize_none()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<PyObject> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<PyObject>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<PyObject>
+    where
+        T: ?Sized + Serialize,
+    {
+        variant_dict(self.py, variant, value.serialize(self)?)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer<'py>> {
+        Ok(SeqSerializer {
+            py: self.py,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+            tuple: false,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer<'py>> {
+        let mut seq = self.serialize_seq(Some(len))?;
+        seq.tuple = true;
+        Ok(seq)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer<'py>> {
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer<'py>> {
+        let mut seq = self.serialize_tuple(len)?;
+        seq.variant = Some(variant);
+        Ok(seq)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer<'py>> {
+        Ok(MapSerializer {
+            dict: PyDict::new(self.py),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<MapSerializer<'py>> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<MapSerializer<'py>> {
+        let mut map = self.serialize_map(Some(len))?;
+        map.variant = Some(variant);
+        Ok(map)
+    }
+}
+
+impl SeqSerializer<'_> {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.items.push(pythonize(self.py, value)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<PyObject> {
+        let seq = if self.tuple {
+            PyTuple::new(self.py, self.items)?.into_any()
+        } else {
+            PyList::new(self.py, self.items)?.into_any()
+        };
+        match self.variant {
+            Some(variant) => variant_dict(self.py, variant, seq),
+            None => Ok(seq.unbind()),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl<'py> MapSerializer<'py> {
+    fn insert<T: ?Sized + Serialize>(
+        &mut self,
+        key: impl IntoPyObject<'py>,
+        value: &T,
+    ) -> Result<()> {
+        let py = self.dict.py();
+        self.dict.set_item(key, pythonize(py, value)?)?;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<PyObject> {
+        match self.variant {
+            Some(variant) => variant_dict(self.dict.py(), variant, self.dict),
+            None => Ok(self.dict.into_any().unbind()),
+        }
+    }
+}
+
+impl ser::SerializeMap for MapSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        self.key = Some(pythonize(self.dict.py(), key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value is called after serialize_key");
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer<'_> {
+    type Ok = PyObject;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<PyObject> {
+        self.finish()
+    }
+}
+
+struct Depythonizer<'py> {
+    input: Bound<'py, PyAny>,
+}
+
+impl<'py> Depythonizer<'py> {
+    fn sequence(&self, len: Option<usize>) -> Result<SeqAccess<'py>> {
+        let seq = self.input.downcast::<PySequence>()?.clone();
+        let actual = seq.len()?;
+        match len {
+            Some(len) if len != actual => Err(Error(format!(
+                "expected a sequence of {len} items, got {actual}"
+            ))),
+            _ => Ok(SeqAccess {
+                seq,
+                index: 0,
+                len: actual,
+            }),
+        }
+    }
+
+    fn mapping(&self) -> Result<MapAccess<'py>> {
+        let map = self.input.downcast::<PyMapping>()?;
+        Ok(MapAccess {
+            keys: map.keys()?,
+            values: map.values()?,
+            index: 0,
+        })
+    }
+}
+
+macro_rules! deserialize_extract {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+        $(
+            fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                visitor.$visit(self.input.extract()?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Depythonizer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value> {
+        let obj = &self.input;
+        if obj.is_none() {
+            self.deserialize_unit(visitor)
+        } else if obj.is_instance_of::<PyBool>() {
+            self.deserialize_bool(visitor)
+        } else if obj.is_instance_of::<PyInt>() {
+            // Integers beyond i64 are the unsigned ones.
+            match obj.extract::<i64>() {
+                Ok(n) => visitor.visit_i64(n),
+                Err(_) => self.deserialize_u64(visitor),
+            }
+        } else if obj.is_instance_of::<PyList>()
+            || obj.is_instance_of::<PyTuple>()
+        {
+            self.deserialize_seq(visitor)
+        } else if obj.is_instance_of::<PyDict>() {
+            self.deserialize_map(visitor)
+        } else if obj.is_instance_of::<PyString>() {
+            self.deserialize_str(visitor)
+        } else if obj.is_instance_of::<PyBytes>()
+            || obj.is_instance_of::<PyByteArray>()
+        {
+            self.deserialize_bytes(visitor)
+        } else if obj.is_instance_of::<PyFloat>() {
+            self.deserialize_f64(visitor)
+        } else if obj.is_instance_of::<PyFrozenSet>()
+            || obj.is_instance_of::<PySet>()
+        {
+            let items: Vec<_> = obj.try_iter()?.collect::<PyResult<_>>()?;
+            let items = PyList::new(obj.py(), items)?;
+            visitor.visit_seq(SeqAccess {
+                len: items.len(),
+                seq: items.into_sequence(),
+                index: 0,
+            })
+        } else if obj.downcast::<PySequence>().is_ok() {
+            self.deserialize_seq(visitor)
+        } else if obj.downcast::<PyMapping>().is_ok() {
+            self.deserialize_map(visitor)
+        } else {
+            Err(Error(format!(
+                "unsupported type {}",
+                obj.get_type().qualname()?
+            )))
+        }
+    }
+
+    fn deserialize_bool<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_bool(self.input.is_truthy()?)
+    }
+
+    deserialize_extract!(
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    );
+
+    fn deserialize_str<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_str(&self.input.downcast::<PyString>()?.to_cow()?)
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.input.downcast::<PyBytes>() {
+            Ok(bytes) => visitor.visit_bytes(bytes.as_bytes()),
+            Err(_) => visitor.visit_byte_buf(self.input.extract()?),
+        }
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value> {
+        if self.input.is_none() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value> {
+        if self.input.is_none() {
+            visitor.visit_unit()
+        } else {
+            Err(Error("expected None".to_string()))
+        }
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_seq(self.sequence(None)?)
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_seq(self.sequence(Some(len))?)
+    }
+
+    fn deserialize_tuple_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_map(self.mapping()?)
+    }
+
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        if let Ok(dict) = self.input.downcast::<PyDict>() {
+            let mut items = dict.iter();
+            let (Some((variant, value)), None) = (items.next(), items.next())
+            else {
+                return Err(Error(
+                    "expected a dict with a single key for an enum".into(),
+                ));
+            };
+            let variant =
+                variant.downcast_into::<PyString>().map_err(|_| {
+                    Error("the variant of an enum must be a str".into())
+                })?;
+            visitor.visit_enum(EnumAccess {
+                de: Depythonizer { input: value },
+                variant,
+            })
+        } else if let Ok(variant) = self.input.downcast::<PyString>() {
+            visitor.visit_enum(variant.to_cow()?.into_deserializer())
+        } else {
+            Err(Error("expected a str or a dict for an enum".into()))
+        }
+    }
+
+    fn deserialize_identifier<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+}
+
+struct SeqAccess<'py> {
+    seq: Bound<'py, PySequence>,
+    index: usize,
+    len: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>> {
+        if self.index == self.len {
+            return Ok(None);
+        }
+        let input = self.seq.get_item(self.index)?;
+        self.index += 1;
+        seed.deserialize(&mut Depythonizer { input }).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.index)
+    }
+}
+
+struct MapAccess<'py> {
+    keys: Bound<'py, PyList>,
+    values: Bound<'py, PyList>,
+    index: usize,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>> {
+        if self.index == self.keys.len() {
+            return Ok(None);
+        }
+        let input = self.keys.get_item(self.index)?;
+        seed.deserialize(&mut Depythonizer { input }).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value> {
+        let input = self.values.get_item(self.index)?;
+        self.index += 1;
+        seed.deserialize(&mut Depythonizer { input })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.keys.len() - self.index)
+    }
+}
+
+struct EnumAccess<'py> {
+    de: Depythonizer<'py>,
+    variant: Bound<'py, PyString>,
+}
+
+impl<'de, 'py> de::EnumAccess<'de> for EnumAccess<'py> {
+    type Error = Error;
+    type Variant = Depythonizer<'py>;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Depythonizer<'py>)> {
+        let variant = self.variant.to_cow()?;
+        let variant: de::value::StrDeserializer<Error> =
+            variant.as_ref().into_deserializer();
+        Ok((seed.deserialize(variant)?, self.de))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Depythonizer<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(
+        mut self,
+        seed: T,
+    ) -> Result<T::Value> {
+        seed.deserialize(&mut self)
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_seq(self.sequence(Some(len))?)
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_map(self.mapping()?)
+    }
+}
@@ -1,32 +1,38 @@
 use crate::{
-    get_field, make_term, make_term_for_type, schema::FieldType, to_pyerr,
-    DocAddress, Schema,
+    block_join::BlockJoinQuery,
+    document::value_to_py,
+    geo::{check_geo_field, GeoPoint, GeoQuery, GeoShape},
+    get_field, make_term, make_term_for_type,
+    min_should_match::{AtLeastQuery, MinimumShouldMatch},
+    schema::FieldType,
+    to_pyerr, DocAddress, Schema,
 };
 use core::ops::Bound as OpsBound;
 use pyo3::{
     exceptions,
     prelude::*,
-    types::{PyAny, PyFloat, PyString, PyTuple},
+    types::{PyAny, PyFloat, PyString},
 };
+use std::collections::BTreeSet;
 use tantivy as tv;
+use tantivy::schema::{
+    OwnedValue as Value, Type, ValueBytes, JSON_END_OF_PATH,
+};
 
 /// Custom Tuple struct to represent a pair of Occur and Query
 /// for the BooleanQuery
-struct OccurQueryPair(Occur, Query);
-
-impl<'source> FromPyObject<'source> for OccurQueryPair {
-    fn extract(ob: &'source PyAny) -> PyResult<Self> {
-        let tuple = ob.downcast::<PyTuple>()?;
-        let occur = tuple.get_item(0)?.extract()?;
-        let query = tuple.get_item(1)?.extract()?;
+pub(crate) struct OccurQueryPair(Occur, Query);
 
+impl<'py> FromPyObject<'py> for OccurQueryPair {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        let (occur, query) = ob.extract()?;
         Ok(OccurQueryPair(occur, query))
     }
 }
 
 /// Tantivy's Occur
-#[pyclass(frozen, module = "tantivy.tantivy")]
-#[derive(Clone)]
+#[pyclass(frozen, eq, eq_int, module = "tantivy.tantivy")]
+#[derive(Clone, PartialEq)]
 pub enum Occur {
     Must,
     Should,
@@ -63,12 +69,129 @@ impl Query {
     }
 }
 
+/// A query matching the documents of `scoring` which match `filter` too,
+/// scored by `scoring` alone.
+pub(crate) fn filtered(
+    scoring: Box<dyn tv::query::Query>,
+    filter: Box<dyn tv::query::Query>,
+) -> Box<dyn tv::query::Query> {
+    Box::new(tv::query::BooleanQuery::new(vec![
+        (tv::query::Occur::Must, scoring),
+        (
+            tv::query::Occur::Must,
+            Box::new(tv::query::ConstScoreQuery::new(filter, 0.0)),
+        ),
+    ]))
+}
+
+/// A boolean query of two queries.
+fn combine(occur: tv::query::Occur, left: &Query, right: &Query) -> Query {
+    Query {
+        inner: Box::new(tv::query::BooleanQuery::new(vec![
+            (occur, left.inner.box_clone()),
+            (occur, right.inner.box_clone()),
+        ])),
+    }
+}
+
+/// The separator of the segments of the JSON paths of terms, private to
+/// tantivy.
+const JSON_PATH_SEGMENT_SEP: char = '\u{1}';
+
+/// The value of a term, None for JSON values.
+fn term_value(value: ValueBytes<&[u8]>) -> Option<Value> {
+    Some(match value.typ() {
+        Type::Str => Value::Str(value.as_str()?.to_string()),
+        Type::U64 => Value::U64(value.as_u64()?),
+        Type::I64 => Value::I64(value.as_i64()?),
+        Type::F64 => Value::F64(value.as_f64()?),
+        Type::Bool => Value::Bool(value.as_bool()?),
+        Type::Date => Value::Date(value.as_date()?),
+        Type::Facet => Value::Facet(value.as_facet()?),
+        Type::Bytes => Value::Bytes(value.as_bytes()?.to_vec()),
+        Type::IpAddr => Value::IpAddr(value.as_ip_addr()?),
+        Type::Json => return None,
+    })
+}
+
+/// The field name and value of a term. The terms of JSON fields are named
+/// by their field and path, e.g. `attributes.color`.
+fn term_entry(
+    schema: &tv::schema::Schema,
+    term: &tv::Term,
+) -> Option<(String, Value)> {
+    let field_name = schema.get_field_name(term.field());
+    if term.typ() != Type::Json {
+        return Some((field_name.to_string(), term_value(term.value())?));
+    }
+    let bytes = term.serialized_value_bytes();
+    let end = bytes.iter().position(|&byte| byte == JSON_END_OF_PATH)?;
+    let path = String::from_utf8_lossy(&bytes[..end])
+        .replace(JSON_PATH_SEGMENT_SEP, ".");
+    let value = term_value(ValueBytes::wrap(&bytes[end + 1..]))?;
+    Some((format!("{field_name}.{path}"), value))
+}
+
 #[pymethods]
 impl Query {
     fn __repr__(&self) -> PyResult<String> {
         Ok(format!("Query({:?})", self.get()))
     }
 
+    /// `a & b` matches the documents matching both queries, and sums their
+    /// scores.
+    fn __and__(&self, other: &Query) -> Query {
+        combine(tv::query::Occur::Must, self, other)
+    }
+
+    /// `a | b` matches the documents matching either query, and sums their
+    /// scores.
+    fn __or__(&self, other: &Query) -> Query {
+        combine(tv::query::Occur::Should, self, other)
+    }
+
+    /// `~a` matches all the documents not matching the query.
+    fn __invert__(&self) -> Query {
+        Query {
+            inner: Box::new(tv::query::BooleanQuery::new(vec![
+                (tv::query::Occur::Must, Box::new(tv::query::AllQuery)),
+                (tv::query::Occur::MustNot, self.inner.box_clone()),
+            ])),
+        }
+    }
+
+    /// The terms the query matches on, e.g. to highlight them, to key a
+    /// cache of the query or to audit the queries of users.
+    ///
+    /// The terms are those of the term, term set, phrase and more like this
+    /// queries, including the subqueries of boolean, boost and filter
+    /// queries. The queries matching terms by pattern or range, e.g. fuzzy,
+    /// regex and range queries, have none.
+    ///
+    /// Args:
+    ///     schema (Schema): The schema of the index the query is for.
+    ///
+    /// Returns a list of unique `(field_name, value)` tuples, by field in the
+    /// order of the schema then by value. The terms of JSON fields are named
+    /// by their field and path, e.g. `attributes.color`.
+    fn extract_terms(
+        &self,
+        py: Python,
+        schema: &Schema,
+    ) -> PyResult<Vec<(String, PyObject)>> {
+        let mut terms = BTreeSet::new();
+        self.inner.query_terms(&mut |term, _| {
+            terms.insert(term.clone());
+        });
+        terms
+            .iter()
+            .filter_map(|term| term_entry(&schema.inner, term))
+            .map(|(field_name, value)| {
+                Ok((field_name, value_to_py(py, &value)?))
+            })
+            .collect()
+    }
+
     /// Construct a Tantivy's TermQuery
     #[staticmethod]
     #[pyo3(signature = (schema, field_name, field_value, index_option = "position"))]
@@ -204,16 +327,46 @@ impl Query {
     }
 
     /// Construct a Tantivy's BooleanQuery
+    ///
+    /// Args:
+    ///     subqueries (List[Tuple[Occur, Query]]): The clauses of the query.
+    ///     minimum_should_match (Union[int, str], optional): How many of the
+    ///         `Should` clauses must match, either a number or a percentage
+    ///         of them like "75%", rounded down. By default they are only
+    ///         required if there are no `Must` clauses.
+    ///
+    /// Raises a ValueError if `minimum_should_match` is invalid.
     #[staticmethod]
-    #[pyo3(signature = (subqueries))]
+    #[pyo3(signature = (subqueries, minimum_should_match = None))]
     pub(crate) fn boolean_query(
-        subqueries: Vec<(Occur, Query)>,
+        subqueries: Vec<OccurQueryPair>,
+        minimum_should_match: Option<MinimumShouldMatch>,
     ) -> PyResult<Query> {
-        let dyn_subqueries = subqueries
+        let mut dyn_subqueries = subqueries
             .into_iter()
-            .map(|(occur, query)| (occur.into(), query.inner.box_clone()))
+            .map(|OccurQueryPair(occur, query)| {
+                (occur.into(), query.inner.box_clone())
+            })
             .collect::<Vec<_>>();
 
+        let num_should = dyn_subqueries
+            .iter()
+            .filter(|(occur, _)| *occur == tv::query::Occur::Should)
+            .count();
+        let minimum = minimum_should_match
+            .map_or(0, |minimum| minimum.resolve(num_should));
+        if minimum > 0 {
+            let (should, mut others): (Vec<_>, Vec<_>) = dyn_subqueries
+                .into_iter()
+                .partition(|(occur, _)| *occur == tv::query::Occur::Should);
+            let at_least = AtLeastQuery {
+                clauses: should.into_iter().map(|(_, query)| query).collect(),
+                minimum,
+            };
+            others.push((tv::query::Occur::Must, Box::new(at_least)));
+            dyn_subqueries = others;
+        }
+
         let inner = tv::query::BooleanQuery::from(dyn_subqueries);
 
         Ok(Query {
@@ -222,6 +375,7 @@ impl Query {
     }
 
     /// Construct a Tantivy's DisjunctionMaxQuery
+    #[pyo3(signature = (subqueries, tie_breaker = None))]
     #[staticmethod]
     pub(crate) fn disjunction_max_query(
         subqueries: Vec<Query>,
@@ -320,6 +474,28 @@ impl Query {
         })
     }
 
+    /// Construct a query restricting a query to the documents of a filter.
+    ///
+    /// The filter is a required clause which doesn't contribute to the
+    /// score, so the hits are ranked by `scoring_query` alone.
+    ///
+    /// Args:
+    ///     scoring_query (Query): The query matching and scoring the hits.
+    ///     filter_query (Query): The query the hits must also match.
+    #[staticmethod]
+    #[pyo3(signature = (scoring_query, filter_query))]
+    pub(crate) fn filtered(
+        scoring_query: &Query,
+        filter_query: &Query,
+    ) -> Query {
+        Query {
+            inner: filtered(
+                scoring_query.inner.box_clone(),
+                filter_query.inner.box_clone(),
+            ),
+        }
+    }
+
     /// Construct a Tantivy's ConstScoreQuery
     #[staticmethod]
     #[pyo3(signature = (query, score))]
@@ -333,6 +509,112 @@ impl Query {
         })
     }
 
+    /// Construct a query matching the documents with a point of a geo field
+    /// within a bounding box.
+    ///
+    /// Args:
+    ///     schema (Schema): The schema of the index.
+    ///     field_name (str): The geo point field to search.
+    ///     min_lat (float): The latitude of the southern edge of the box.
+    ///     min_lon (float): The longitude of the western edge of the box.
+    ///     max_lat (float): The latitude of the northern edge of the box.
+    ///     max_lon (float): The longitude of the eastern edge of the box. If
+    ///         it is smaller than `min_lon` the box crosses the antimeridian.
+    ///
+    /// Raises a SchemaError if the field isn't a fast geo point field.
+    #[staticmethod]
+    #[pyo3(signature = (schema, field_name, min_lat, min_lon, max_lat, max_lon))]
+    pub(crate) fn geo_bounding_box_query(
+        schema: &Schema,
+        field_name: &str,
+        min_lat: f64,
+        min_lon: f64,
+        max_lat: f64,
+        max_lon: f64,
+    ) -> PyResult<Query> {
+        check_geo_field(&schema.inner, &schema.extensions, field_name)?;
+        let shape = GeoShape::BoundingBox {
+            min: GeoPoint::new(min_lat, min_lon)?,
+            max: GeoPoint::new(max_lat, max_lon)?,
+        };
+        Ok(Query {
+            inner: Box::new(GeoQuery {
+                field: field_name.to_string(),
+                shape,
+            }),
+        })
+    }
+
+    /// Construct a query matching the documents with a point of a geo field
+    /// within a distance of a location.
+    ///
+    /// Args:
+    ///     schema (Schema): The schema of the index.
+    ///     field_name (str): The geo point field to search.
+    ///     lat (float): The latitude of the location.
+    ///     lon (float): The longitude of the location.
+    ///     radius_m (float): The maximum distance to the location, in meters.
+    ///
+    /// Raises a SchemaError if the field isn't a fast geo point field.
+    #[staticmethod]
+    #[pyo3(signature = (schema, field_name, lat, lon, radius_m))]
+    pub(crate) fn geo_distance_query(
+        schema: &Schema,
+        field_name: &str,
+        lat: f64,
+        lon: f64,
+        radius_m: f64,
+    ) -> PyResult<Query> {
+        check_geo_field(&schema.inner, &schema.extensions, field_name)?;
+        if radius_m.is_nan() || radius_m < 0.0 {
+            return Err(exceptions::PyValueError::new_err(
+                "radius_m must be a positive number of meters.",
+            ));
+        }
+        let shape = GeoShape::Circle {
+            center: GeoPoint::new(lat, lon)?,
+            radius_m,
+        };
+        Ok(Query {
+            inner: Box::new(GeoQuery {
+                field: field_name.to_string(),
+                shape,
+            }),
+        })
+    }
+
+    /// Construct a query matching the parents of the documents matching a
+    /// child query.
+    ///
+    /// The parents and their children must have been added together with
+    /// `IndexWriter.add_document_block`, the parent last.
+    ///
+    /// Args:
+    ///     parent_filter (Query): A query matching all the parent documents,
+    ///         and only them.
+    ///     child_query (Query): The query the children must match.
+    ///     score_mode (str, optional): How the scores of the matching
+    ///         children of a parent are combined into its score, one of
+    ///         'avg', 'max', 'min', 'sum' or 'none' for a constant score.
+    ///         Defaults to 'avg'.
+    ///
+    /// Raises a ValueError if the score mode is invalid.
+    #[staticmethod]
+    #[pyo3(signature = (parent_filter, child_query, score_mode = "avg"))]
+    pub(crate) fn block_join_query(
+        parent_filter: Query,
+        child_query: Query,
+        score_mode: &str,
+    ) -> PyResult<Query> {
+        Ok(Query {
+            inner: Box::new(BlockJoinQuery {
+                parent_filter: parent_filter.inner,
+                child_query: child_query.inner,
+                score_mode: score_mode.parse()?,
+            }),
+        })
+    }
+
     #[staticmethod]
     #[pyo3(signature = (schema, field_name, field_type, lower_bound, upper_bound, include_lower = true, include_upper = true))]
     pub(crate) fn range_query(
@@ -344,69 +626,104 @@ impl Query {
         include_lower: bool,
         include_upper: bool,
     ) -> PyResult<Query> {
-        match field_type {
-            FieldType::Text => {
-                return Err(exceptions::PyValueError::new_err(
-                    "Text fields are not supported for range queries.",
-                ))
-            }
-            FieldType::Boolean => {
-                return Err(exceptions::PyValueError::new_err(
-                    "Boolean fields are not supported for range queries.",
-                ))
-            }
-            FieldType::Facet => {
-                return Err(exceptions::PyValueError::new_err(
-                    "Facet fields are not supported for range queries.",
-                ))
-            }
-            FieldType::Bytes => {
-                return Err(exceptions::PyValueError::new_err(
-                    "Bytes fields are not supported for range queries.",
-                ))
-            }
-            FieldType::Json => {
-                return Err(exceptions::PyValueError::new_err(
-                    "Json fields are not supported for range queries.",
-                ))
-            }
-            _ => {}
-        }
-
-        let lower_bound_term = make_term_for_type(
-            &schema.inner,
-            field_name,
-            field_type.clone(),
-            lower_bound,
-        )?;
-        let upper_bound_term = make_term_for_type(
-            &schema.inner,
+        range(
+            schema,
             field_name,
-            field_type.clone(),
-            upper_bound,
-        )?;
-
-        let lower_bound = if include_lower {
-            OpsBound::Included(lower_bound_term)
-        } else {
-            OpsBound::Excluded(lower_bound_term)
-        };
-
-        let upper_bound = if include_upper {
-            OpsBound::Included(upper_bound_term)
-        } else {
-            OpsBound::Excluded(upper_bound_term)
-        };
+            field_type,
+            Some(lower_bound),
+            Some(upper_bound),
+            include_lower,
+            include_upper,
+        )
+    }
+}
 
-        let inner = tv::query::RangeQuery::new_term_bounds(
-            field_name.to_string(),
-            field_type.into(),
-            &lower_bound,
-            &upper_bound,
-        );
+/// A bound of a range query, unbounded if `value` is None.
+fn make_bound(
+    schema: &Schema,
+    field_name: &str,
+    field_type: &FieldType,
+    value: Option<&Bound<PyAny>>,
+    inclusive: bool,
+) -> PyResult<OpsBound<tv::Term>> {
+    let Some(value) = value else {
+        return Ok(OpsBound::Unbounded);
+    };
+    let term = make_term_for_type(
+        &schema.inner,
+        field_name,
+        field_type.clone(),
+        value,
+    )?;
+    Ok(if inclusive {
+        OpsBound::Included(term)
+    } else {
+        OpsBound::Excluded(term)
+    })
+}
 
-        Ok(Query {
-            inner: Box::new(inner),
-        })
+/// A range query, unbounded on the sides whose bound is None.
+pub(crate) fn range(
+    schema: &Schema,
+    field_name: &str,
+    field_type: FieldType,
+    lower_bound: Option<&Bound<PyAny>>,
+    upper_bound: Option<&Bound<PyAny>>,
+    include_lower: bool,
+    include_upper: bool,
+) -> PyResult<Query> {
+    match field_type {
+        FieldType::Text => {
+            return Err(exceptions::PyValueError::new_err(
+                "Text fields are not supported for range queries.",
+            ))
+        }
+        FieldType::Boolean => {
+            return Err(exceptions::PyValueError::new_err(
+                "Boolean fields are not supported for range queries.",
+            ))
+        }
+        FieldType::Facet => {
+            return Err(exceptions::PyValueError::new_err(
+                "Facet fields are not supported for range queries.",
+            ))
+        }
+        FieldType::Bytes => {
+            return Err(exceptions::PyValueError::new_err(
+                "Bytes fields are not supported for range queries.",
+            ))
+        }
+        FieldType::Json => {
+            return Err(exceptions::PyValueError::new_err(
+                "Json fields are not supported for range queries.",
+            ))
+        }
+        _ => {}
     }
+
+    let lower_bound = make_bound(
+        schema,
+        field_name,
+        &field_type,
+        lower_bound,
+        include_lower,
+    )?;
+    let upper_bound = make_bound(
+        schema,
+        field_name,
+        &field_type,
+        upper_bound,
+        include_upper,
+    )?;
+
+    let inner = tv::query::RangeQuery::new_term_bounds(
+        field_name.to_string(),
+        field_type.into(),
+        &lower_bound,
+        &upper_bound,
+    );
+
+    Ok(Query {
+        inner: Box::new(inner),
+    })
 }
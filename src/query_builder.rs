@@ -0,0 +1,187 @@
+use pyo3::{exceptions, prelude::*, types::PyString};
+use tantivy as tv;
+
+use crate::{
+    get_field, make_term_for_value_type,
+    query::{self, Query},
+    Schema,
+};
+
+/// Builds queries on the fields of a schema, without repeating the schema
+/// in every call.
+///
+/// Queries combine with the `&`, `|` and `~` operators into boolean
+/// queries:
+///
+///     >>> Q = tantivy.QueryBuilder(schema)
+///     >>> query = (
+///     ...     Q.term("title", "rust") & Q.range("year", 2015, 2020)
+///     ...     | ~Q.term("lang", "go")
+///     ... )
+///
+/// The values are converted to the type of their field, e.g. an int for an
+/// unsigned field. Text values are not tokenized: `term("title", "rust")`
+/// matches the token "rust", use `Index.parse_query` for user input.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+pub(crate) struct QueryBuilder {
+    schema: Py<Schema>,
+}
+
+impl QueryBuilder {
+    fn term_for(
+        &self,
+        field_name: &str,
+        value: &Bound<PyAny>,
+    ) -> PyResult<tv::Term> {
+        let schema = &self.schema.get().inner;
+        let value_type = value_type(schema, field_name)?;
+        if value_type == tv::schema::Type::Json {
+            return Err(exceptions::PyValueError::new_err(format!(
+                "Field `{field_name}` is a JSON field, use Index.parse_query \
+                 to search its paths."
+            )));
+        }
+        make_term_for_value_type(schema, field_name, value_type, value)
+    }
+}
+
+fn value_type(
+    schema: &tv::schema::Schema,
+    field_name: &str,
+) -> PyResult<tv::schema::Type> {
+    let field = get_field(schema, field_name)?;
+    Ok(schema.get_field_entry(field).field_type().value_type())
+}
+
+#[pymethods]
+impl QueryBuilder {
+    #[new]
+    fn new(schema: Py<Schema>) -> Self {
+        QueryBuilder { schema }
+    }
+
+    /// A query matching the documents holding a value in a field.
+    ///
+    /// Args:
+    ///     field_name (str): The field to search.
+    ///     value (Any): The value, or for a text field the token, to match.
+    fn term(&self, field_name: &str, value: &Bound<PyAny>) -> PyResult<Query> {
+        let term = self.term_for(field_name, value)?;
+        Ok(Query {
+            inner: Box::new(tv::query::TermQuery::new(
+                term,
+                tv::schema::IndexRecordOption::WithFreqs,
+            )),
+        })
+    }
+
+    /// A query matching the documents holding any of some values in a
+    /// field.
+    ///
+    /// Args:
+    ///     field_name (str): The field to search.
+    ///     values (List[Any]): The values to match.
+    fn terms(
+        &self,
+        field_name: &str,
+        values: Vec<Bound<PyAny>>,
+    ) -> PyResult<Query> {
+        let terms = values
+            .iter()
+            .map(|value| self.term_for(field_name, value))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(Query {
+            inner: Box::new(tv::query::TermSetQuery::new(terms)),
+        })
+    }
+
+    /// A query matching the documents whose value of a field is in a range.
+    ///
+    /// Args:
+    ///     field_name (str): The field to search, which must be a numeric,
+    ///         date or IP address field.
+    ///     lower (Any, optional): The lower bound, None for no lower bound.
+    ///     upper (Any, optional): The upper bound, None for no upper bound.
+    ///     include_lower (bool, optional): Whether the lower bound matches.
+    ///         Defaults to True.
+    ///     include_upper (bool, optional): Whether the upper bound matches.
+    ///         Defaults to True.
+    ///
+    /// Raises a ValueError if the field type doesn't support ranges.
+    #[pyo3(signature = (field_name, lower = None, upper = None, include_lower = true, include_upper = true))]
+    fn range(
+        &self,
+        field_name: &str,
+        lower: Option<&Bound<PyAny>>,
+        upper: Option<&Bound<PyAny>>,
+        include_lower: bool,
+        include_upper: bool,
+    ) -> PyResult<Query> {
+        let schema = self.schema.get();
+        let field_type = value_type(&schema.inner, field_name)?.into();
+        query::range(
+            schema,
+            field_name,
+            field_type,
+            lower,
+            upper,
+            include_lower,
+            include_upper,
+        )
+    }
+
+    /// A query matching the documents containing a phrase in a text field.
+    ///
+    /// Args:
+    ///     field_name (str): The field to search.
+    ///     words (List[str]): The tokens of the phrase.
+    ///     slop (int, optional): The number of other tokens allowed between
+    ///         the words. Defaults to 0.
+    #[pyo3(signature = (field_name, words, slop = 0))]
+    fn phrase(
+        &self,
+        field_name: &str,
+        words: Vec<Bound<PyAny>>,
+        slop: u32,
+    ) -> PyResult<Query> {
+        Query::phrase_query(self.schema.get(), field_name, words, slop)
+    }
+
+    /// A query matching the documents of a text field containing a token
+    /// close to a word.
+    ///
+    /// Args:
+    ///     field_name (str): The field to search.
+    ///     text (str): The word.
+    ///     distance (int, optional): The maximum edit distance. Defaults to 1.
+    ///     prefix (bool, optional): Whether the word may be the prefix of the
+    ///         token. Defaults to False.
+    #[pyo3(signature = (field_name, text, distance = 1, prefix = false))]
+    fn fuzzy(
+        &self,
+        field_name: &str,
+        text: &Bound<PyString>,
+        distance: u8,
+        prefix: bool,
+    ) -> PyResult<Query> {
+        Query::fuzzy_term_query(
+            self.schema.get(),
+            field_name,
+            text,
+            distance,
+            true,
+            prefix,
+        )
+    }
+
+    /// A query matching the documents of a text field containing a token
+    /// matching a regular expression.
+    fn regex(&self, field_name: &str, pattern: &str) -> PyResult<Query> {
+        Query::regex_query(self.schema.get(), field_name, pattern)
+    }
+
+    /// A query matching all the documents.
+    fn all(&self) -> PyResult<Query> {
+        Query::all_query()
+    }
+}
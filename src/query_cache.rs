@@ -0,0 +1,206 @@
+//! Cache of the documents matching the filters of the searches, by segment,
+//! see `Searcher.enable_query_cache`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use tantivy::{
+    self as tv,
+    query::{EnableScoring, Explanation, Scorer, Weight},
+    DocId, Score, SegmentId, SegmentReader,
+};
+
+use crate::scorer::{explain_by_seek, BitSetScorer, DocBitSet};
+
+/// The debug representation of a query, and a segment. Segments never
+/// change once written, so neither do their matching documents.
+type CacheKey = (String, SegmentId);
+
+/// A least recently used cache of the documents matching queries, shared by
+/// the searchers of an index. It is disabled until `enable` is called.
+#[derive(Default)]
+pub(crate) struct QueryCache {
+    state: Mutex<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    /// The maximum number of entries and of bytes, None while disabled.
+    capacity: Option<(usize, usize)>,
+    entries: HashMap<CacheKey, (Arc<DocBitSet>, u64)>,
+    /// The keys of the entries by last use.
+    usage: BTreeMap<u64, CacheKey>,
+    clock: u64,
+    num_bytes: usize,
+    hits: usize,
+    misses: usize,
+}
+
+/// The statistics of a query cache.
+pub(crate) struct CacheStats {
+    pub(crate) num_entries: usize,
+    pub(crate) num_bytes: usize,
+    pub(crate) hits: usize,
+    pub(crate) misses: usize,
+}
+
+impl CacheState {
+    fn evict_to_fit(&mut self) {
+        let Some((max_entries, max_bytes)) = self.capacity else {
+            return;
+        };
+        while self.entries.len() > max_entries || self.num_bytes > max_bytes {
+            let Some((_, key)) = self.usage.pop_first() else {
+                break;
+            };
+            if let Some((bitset, _)) = self.entries.remove(&key) {
+                self.num_bytes -= bitset.num_bytes();
+            }
+        }
+    }
+}
+
+impl QueryCache {
+    /// Enable the cache with a capacity, or change its capacity.
+    pub(crate) fn enable(&self, max_entries: usize, max_bytes: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.capacity = Some((max_entries, max_bytes));
+        state.evict_to_fit();
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        let state = self.state.lock().unwrap();
+        CacheStats {
+            num_entries: state.entries.len(),
+            num_bytes: state.num_bytes,
+            hits: state.hits,
+            misses: state.misses,
+        }
+    }
+
+    /// A query matching the documents of `query`, cached if the cache is
+    /// enabled. Scores are constant.
+    pub(crate) fn wrap(
+        self: &Arc<Self>,
+        query: Box<dyn tv::query::Query>,
+    ) -> Box<dyn tv::query::Query> {
+        if self.state.lock().unwrap().capacity.is_none() {
+            return query;
+        }
+        Box::new(CachedQuery {
+            key: format!("{query:?}"),
+            query,
+            cache: self.clone(),
+        })
+    }
+
+    fn get_or_collect(
+        &self,
+        key: CacheKey,
+        collect: impl FnOnce() -> tv::Result<DocBitSet>,
+    ) -> tv::Result<Arc<DocBitSet>> {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.clock += 1;
+            let clock = state.clock;
+            if let Some((bitset, last_use)) = state.entries.get_mut(&key) {
+                let bitset = bitset.clone();
+                let previous = std::mem::replace(last_use, clock);
+                state.usage.remove(&previous);
+                state.usage.insert(clock, key);
+                state.hits += 1;
+                return Ok(bitset);
+            }
+            state.misses += 1;
+        }
+        // Concurrent misses of a key collect it twice, rather than holding
+        // the lock while collecting.
+        let bitset = Arc::new(collect()?);
+        let mut state = self.state.lock().unwrap();
+        let Some((_, max_bytes)) = state.capacity else {
+            return Ok(bitset);
+        };
+        if bitset.num_bytes() > max_bytes || state.entries.contains_key(&key) {
+            return Ok(bitset);
+        }
+        state.clock += 1;
+        let clock = state.clock;
+        state.num_bytes += bitset.num_bytes();
+        state.usage.insert(clock, key.clone());
+        state.entries.insert(key, (bitset.clone(), clock));
+        state.evict_to_fit();
+        Ok(bitset)
+    }
+}
+
+struct CachedQuery {
+    key: String,
+    query: Box<dyn tv::query::Query>,
+    cache: Arc<QueryCache>,
+}
+
+impl Clone for CachedQuery {
+    fn clone(&self) -> Self {
+        CachedQuery {
+            key: self.key.clone(),
+            query: self.query.box_clone(),
+            cache: self.cache.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for CachedQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CachedQuery").field(&self.query).finish()
+    }
+}
+
+impl tv::query::Query for CachedQuery {
+    fn weight(
+        &self,
+        enable_scoring: EnableScoring<'_>,
+    ) -> tv::Result<Box<dyn Weight>> {
+        let disabled_scoring = match enable_scoring.searcher() {
+            Some(searcher) => EnableScoring::disabled_from_searcher(searcher),
+            None => {
+                EnableScoring::disabled_from_schema(enable_scoring.schema())
+            }
+        };
+        Ok(Box::new(CachedWeight {
+            key: self.key.clone(),
+            weight: self.query.weight(disabled_scoring)?,
+            cache: self.cache.clone(),
+        }))
+    }
+}
+
+struct CachedWeight {
+    key: String,
+    weight: Box<dyn Weight>,
+    cache: Arc<QueryCache>,
+}
+
+impl Weight for CachedWeight {
+    fn scorer(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> tv::Result<Box<dyn Scorer>> {
+        let key = (self.key.clone(), reader.segment_id());
+        let docs = self.cache.get_or_collect(key, || {
+            DocBitSet::collect(self.weight.as_ref(), reader)
+        })?;
+        Ok(Box::new(BitSetScorer::new(docs, boost)))
+    }
+
+    fn explain(
+        &self,
+        reader: &SegmentReader,
+        doc: DocId,
+    ) -> tv::Result<Explanation> {
+        explain_by_seek(self, reader, doc, "CachedQuery")
+    }
+}
@@ -0,0 +1,42 @@
+//! Escaping of user input into query strings, see `escape_query_string`.
+
+use pyo3::prelude::*;
+
+/// The query string matching the words of a text, with no special syntax.
+///
+/// Every whitespace separated word is quoted, with its quotes and
+/// backslashes escaped.
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for word in text.split_whitespace() {
+        if !escaped.is_empty() {
+            escaped.push(' ');
+        }
+        escaped.push('"');
+        for c in word.chars() {
+            if matches!(c, '"' | '\\') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped.push('"');
+    }
+    escaped
+}
+
+/// Escape user input, e.g. of a search box, into a query string where none
+/// of the characters have a special meaning, so that a stray colon, quote
+/// or bracket can't make the query invalid.
+///
+/// Every word is quoted. The words the tokenizer of a field splits into
+/// several tokens, e.g. `foo:bar`, are searched as phrases.
+///
+/// Args:
+///     text (str): The user input.
+///
+/// Returns the query string, to pass to `Index.parse_query`, possibly
+/// combined with other clauses.
+#[pyfunction]
+pub(crate) fn escape_query_string(text: &str) -> String {
+    escape(text)
+}
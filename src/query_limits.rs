@@ -0,0 +1,177 @@
+//! Measuring the complexity of queries, see `Index.set_search_limits`.
+//!
+//! The fuzzy and regex queries of tantivy don't expose what they search,
+//! it is read back from their `Debug` representation.
+
+use std::collections::HashSet;
+
+use levenshtein_automata::LevenshteinAutomatonBuilder;
+use tantivy::{
+    self as tv,
+    query::{BooleanQuery, FuzzyTermQuery, Query, RegexQuery},
+    schema::Field,
+};
+
+use crate::spell::DfaAutomaton;
+
+/// The complexity of a query, each measure stopping once it is above its
+/// limit.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Complexity {
+    /// The clauses of the boolean queries, nested ones included.
+    pub(crate) clauses: usize,
+    /// The most terms of the index matched by a fuzzy query.
+    pub(crate) expanded_terms: usize,
+    /// The most states of the automaton of a regex query.
+    pub(crate) regex_states: usize,
+}
+
+/// The parameters of a fuzzy query.
+struct Fuzzy {
+    field: Field,
+    text: String,
+    distance: u8,
+    transposition_cost_one: bool,
+    prefix: bool,
+}
+
+/// Measure the complexity of a query, counting the terms matched by its
+/// fuzzy queries in the segments of the searcher only if
+/// `max_expanded_terms` is set, and up to one more than it.
+pub(crate) fn measure(
+    searcher: &tv::Searcher,
+    query: &dyn Query,
+    max_expanded_terms: Option<usize>,
+) -> tv::Result<Complexity> {
+    let mut complexity = Complexity::default();
+    visit(searcher, query, max_expanded_terms, &mut complexity)?;
+    Ok(complexity)
+}
+
+fn visit(
+    searcher: &tv::Searcher,
+    query: &dyn Query,
+    max_expanded_terms: Option<usize>,
+    complexity: &mut Complexity,
+) -> tv::Result<()> {
+    if let Some(boolean) = query.downcast_ref::<BooleanQuery>() {
+        complexity.clauses += boolean.clauses().len();
+        for (_, clause) in boolean.clauses() {
+            visit(searcher, clause.as_ref(), max_expanded_terms, complexity)?;
+        }
+    } else if let Some(fuzzy) = query.downcast_ref::<FuzzyTermQuery>() {
+        let (Some(max), Some(fuzzy)) = (max_expanded_terms, parse_fuzzy(fuzzy))
+        else {
+            return Ok(());
+        };
+        let expanded_terms = count_expansions(searcher, &fuzzy, max)?;
+        complexity.expanded_terms =
+            complexity.expanded_terms.max(expanded_terms);
+    } else if let Some(regex) = query.downcast_ref::<RegexQuery>() {
+        complexity.regex_states =
+            complexity.regex_states.max(count_regex_states(regex));
+    }
+    Ok(())
+}
+
+/// Count the distinct terms of the segments matched by a fuzzy query, up to
+/// `max + 1`.
+fn count_expansions(
+    searcher: &tv::Searcher,
+    fuzzy: &Fuzzy,
+    max: usize,
+) -> tv::Result<usize> {
+    // The automaton builder of tantivy supports distances up to 2, larger
+    // ones fail when the query is run.
+    if fuzzy.distance > 2 {
+        return Ok(0);
+    }
+    let builder = LevenshteinAutomatonBuilder::new(
+        fuzzy.distance,
+        fuzzy.transposition_cost_one,
+    );
+    let dfa = if fuzzy.prefix {
+        builder.build_prefix_dfa(&fuzzy.text)
+    } else {
+        builder.build_dfa(&fuzzy.text)
+    };
+    let mut terms = HashSet::new();
+    for segment_reader in searcher.segment_readers() {
+        let inverted_index = segment_reader.inverted_index(fuzzy.field)?;
+        let mut stream = inverted_index
+            .terms()
+            .search(DfaAutomaton(&dfa))
+            .into_stream()?;
+        while stream.advance() {
+            terms.insert(stream.key().to_vec());
+            if terms.len() > max {
+                return Ok(terms.len());
+            }
+        }
+    }
+    Ok(terms.len())
+}
+
+/// Read the parameters of a fuzzy query on a text field from its `Debug`
+/// representation, e.g. `FuzzyTermQuery { term: Term(field=0, type=Str,
+/// "abc"), distance: 1, transposition_cost_one: true, prefix: false }`.
+fn parse_fuzzy(query: &FuzzyTermQuery) -> Option<Fuzzy> {
+    let debug = format!("{query:?}");
+    let rest = debug.strip_prefix("FuzzyTermQuery { term: Term(field=")?;
+    let (field_id, rest) = rest.split_once(", type=Str, ")?;
+    let (text, rest) = unquote(rest)?;
+    let rest = rest.strip_prefix("), distance: ")?;
+    let (distance, rest) = rest.split_once(", transposition_cost_one: ")?;
+    let (transposition_cost_one, rest) = rest.split_once(", prefix: ")?;
+    let prefix = rest.strip_suffix(" }")?;
+    Some(Fuzzy {
+        field: Field::from_field_id(field_id.parse().ok()?),
+        text,
+        distance: distance.parse().ok()?,
+        transposition_cost_one: transposition_cost_one.parse().ok()?,
+        prefix: prefix.parse().ok()?,
+    })
+}
+
+/// Read a string literal as written by `Debug`, returning its value and
+/// what follows it.
+fn unquote(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    let mut value = String::new();
+    while let Some((_, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, chars.as_str())),
+            '\\' => value.push(match chars.next()?.1 {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                '0' => '\0',
+                'u' => {
+                    let rest = chars.as_str().strip_prefix('{')?;
+                    let (hex, _) = rest.split_once('}')?;
+                    let c = char::from_u32(u32::from_str_radix(hex, 16).ok()?)?;
+                    // Skip the braces and digits.
+                    chars.nth(hex.len() + 1)?;
+                    c
+                }
+                c => c,
+            }),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Count the states of the automaton of a regex query, listed after the
+/// instructions in its `Debug` representation, one `NNN  [...]` line each
+/// followed by its `NNN   XX => NNN` transitions.
+fn count_regex_states(query: &RegexQuery) -> usize {
+    let debug = format!("{query:?}");
+    let Some((_, states)) = debug.split_once("------------\n") else {
+        return 0;
+    };
+    states
+        .lines()
+        .filter(|line| line.as_bytes().get(5) == Some(&b'['))
+        .count()
+}
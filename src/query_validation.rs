@@ -0,0 +1,108 @@
+//! Reports on user-entered query strings, see `Index.validate_query`.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::Serialize;
+use tantivy::{
+    query::QueryParser,
+    query_grammar::{self, UserInputAst, UserInputLeaf},
+    schema::Schema,
+};
+
+use crate::field_alias;
+
+/// A syntax error of a query string.
+#[derive(Serialize)]
+struct SyntaxError {
+    message: String,
+    /// The byte offset of the error in the query string.
+    position: usize,
+}
+
+/// What the query parser makes of a query string, without running it.
+#[derive(Serialize)]
+pub(crate) struct QueryReport {
+    valid: bool,
+    tree: String,
+    fields: BTreeSet<String>,
+    errors: Vec<SyntaxError>,
+    warnings: Vec<String>,
+}
+
+/// Collect the fields named by the leaves of a query, and whether some
+/// leaves have no field.
+fn collect_fields<'a>(
+    ast: &'a UserInputAst,
+    fields: &mut BTreeSet<&'a str>,
+    unfielded: &mut bool,
+) {
+    match ast {
+        UserInputAst::Clause(clauses) => {
+            for (_, clause) in clauses {
+                collect_fields(clause, fields, unfielded);
+            }
+        }
+        UserInputAst::Boost(ast, _) => collect_fields(ast, fields, unfielded),
+        UserInputAst::Leaf(leaf) => {
+            let field = match leaf.as_ref() {
+                UserInputLeaf::Literal(literal) => {
+                    literal.field_name.as_deref()
+                }
+                UserInputLeaf::All => return,
+                UserInputLeaf::Range { field, .. }
+                | UserInputLeaf::Set { field, .. } => field.as_deref(),
+                UserInputLeaf::Exists { field } => Some(field.as_str()),
+            };
+            match field {
+                Some(field) => {
+                    fields.insert(field);
+                }
+                None => *unfielded = true,
+            }
+        }
+    }
+}
+
+/// Parse a query string leniently and report on it.
+///
+/// The syntax errors are the parts of the query the grammar skipped, the
+/// warnings the parts turned into subqueries matching nothing, e.g. terms
+/// of missing or unindexed fields.
+pub(crate) fn validate(
+    parser: &QueryParser,
+    schema: &Schema,
+    default_field_names: &[String],
+    field_aliases: &HashMap<String, String>,
+    query: &str,
+) -> QueryReport {
+    let (ast, syntax_errors) = query_grammar::parse_query_lenient(query);
+    let ast = field_alias::rewrite(ast, field_aliases);
+    let mut named = BTreeSet::new();
+    let mut unfielded = false;
+    collect_fields(&ast, &mut named, &mut unfielded);
+    let mut fields: BTreeSet<String> = named
+        .into_iter()
+        .filter(|name| schema.find_field(name).is_some())
+        .map(str::to_string)
+        .collect();
+    if unfielded {
+        fields.extend(default_field_names.iter().cloned());
+    }
+    let tree = format!("{ast:?}");
+    let (_, parser_errors) =
+        parser.build_query_from_user_input_ast_lenient(ast);
+
+    QueryReport {
+        valid: syntax_errors.is_empty() && parser_errors.is_empty(),
+        tree,
+        fields,
+        errors: syntax_errors
+            .into_iter()
+            .map(|error| SyntaxError {
+                message: error.message,
+                position: error.pos,
+            })
+            .collect(),
+        warnings: parser_errors.iter().map(ToString::to_string).collect(),
+    }
+}
@@ -0,0 +1,353 @@
+//! How the files of an index directory are read: memory mapped, or with
+//! positioned reads through a block cache managed by the application, see
+//! `Index.open`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    fs::File,
+    io,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use pyo3::{exceptions::PyValueError, PyResult};
+use tantivy::{
+    self as tv,
+    directory::{
+        error::{DeleteError, LockError, OpenReadError, OpenWriteError},
+        Advice, DirectoryLock, FileHandle, Lock, MmapDirectory, OwnedBytes,
+        WatchCallback, WatchHandle, WritePtr,
+    },
+    Directory, HasLen,
+};
+
+use crate::errors::tantivy_err;
+
+/// The size of the blocks read and cached by a `BufferedDirectory`.
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// The capacity of the block cache of the 'read' mode, by default.
+const DEFAULT_CACHE_MB: f64 = 64.0;
+
+/// Open the directory of an index with a read mode.
+///
+/// Args:
+///     read_mode: 'mmap' to memory map the files, 'read' to read them with
+///         positioned reads through a block cache.
+///     madvise: The access pattern advised to the kernel for the memory
+///         mapped files, 'normal', 'random', 'sequential' or 'willneed'.
+///     cache_mb: The capacity of the block cache of the 'read' mode.
+///
+/// Raises a ValueError if an option is invalid or doesn't apply to the read
+/// mode.
+pub(crate) fn open_directory(
+    path: &Path,
+    read_mode: &str,
+    madvise: Option<&str>,
+    cache_mb: Option<f64>,
+) -> PyResult<Box<dyn Directory>> {
+    match read_mode {
+        "mmap" => {
+            if cache_mb.is_some() {
+                return Err(PyValueError::new_err(
+                    "cache_mb only applies to the 'read' read mode.",
+                ));
+            }
+            let directory = match madvise.map(parse_advice).transpose()? {
+                Some(advice) => MmapDirectory::open_with_madvice(path, advice),
+                None => MmapDirectory::open(path),
+            }
+            .map_err(tantivy_err)?;
+            Ok(Box::new(directory))
+        }
+        "read" => {
+            if madvise.is_some() {
+                return Err(PyValueError::new_err(
+                    "madvise only applies to the 'mmap' read mode.",
+                ));
+            }
+            let cache_mb = cache_mb.unwrap_or(DEFAULT_CACHE_MB);
+            if !(cache_mb >= 0.0 && cache_mb.is_finite()) {
+                return Err(PyValueError::new_err(
+                    "cache_mb must not be negative.",
+                ));
+            }
+            let directory = BufferedDirectory::open(
+                path,
+                (cache_mb * 1_000_000.0) as usize,
+            )
+            .map_err(tantivy_err)?;
+            Ok(Box::new(directory))
+        }
+        _ => Err(PyValueError::new_err(
+            "Invalid read_mode, valid choices are: 'mmap' and 'read'",
+        )),
+    }
+}
+
+fn parse_advice(madvise: &str) -> PyResult<Advice> {
+    match madvise {
+        "normal" => Ok(Advice::Normal),
+        "random" => Ok(Advice::Random),
+        "sequential" => Ok(Advice::Sequential),
+        "willneed" => Ok(Advice::WillNeed),
+        _ => Err(PyValueError::new_err(
+            "Invalid madvise, valid choices are: 'normal', 'random', \
+             'sequential' and 'willneed'",
+        )),
+    }
+}
+
+/// A block of a file, by path and block number. Index files never change
+/// once written, so neither do their blocks.
+type BlockKey = (Arc<Path>, usize);
+
+/// A least recently used cache of file blocks, shared by the files of a
+/// `BufferedDirectory`.
+struct BlockCache {
+    max_bytes: usize,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    blocks: HashMap<BlockKey, (OwnedBytes, u64)>,
+    /// The keys of the blocks by last use.
+    usage: BTreeMap<u64, BlockKey>,
+    clock: u64,
+    num_bytes: usize,
+}
+
+impl BlockCache {
+    fn get_or_read(
+        &self,
+        key: BlockKey,
+        read: impl FnOnce() -> io::Result<OwnedBytes>,
+    ) -> io::Result<OwnedBytes> {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.clock += 1;
+            let clock = state.clock;
+            if let Some((block, last_use)) = state.blocks.get_mut(&key) {
+                let block = block.clone();
+                let previous = std::mem::replace(last_use, clock);
+                state.usage.remove(&previous);
+                state.usage.insert(clock, key);
+                return Ok(block);
+            }
+        }
+        // Concurrent misses of a block read it twice, rather than holding
+        // the lock while reading.
+        let block = read()?;
+        if block.len() > self.max_bytes {
+            return Ok(block);
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.blocks.contains_key(&key) {
+            return Ok(block);
+        }
+        state.clock += 1;
+        let clock = state.clock;
+        state.num_bytes += block.len();
+        state.usage.insert(clock, key.clone());
+        state.blocks.insert(key, (block.clone(), clock));
+        while state.num_bytes > self.max_bytes {
+            let Some((_, key)) = state.usage.pop_first() else {
+                break;
+            };
+            if let Some((evicted, _)) = state.blocks.remove(&key) {
+                state.num_bytes -= evicted.len();
+            }
+        }
+        Ok(block)
+    }
+
+    /// Drop the blocks of a deleted file.
+    fn evict(&self, path: &Path) {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let blocks = &mut state.blocks;
+        let num_bytes = &mut state.num_bytes;
+        state.usage.retain(|_, key| {
+            if *key.0 != *path {
+                return true;
+            }
+            if let Some((evicted, _)) = blocks.remove(key) {
+                *num_bytes -= evicted.len();
+            }
+            false
+        });
+    }
+}
+
+/// A file read with positioned reads, by blocks going through the cache of
+/// its directory. Reads longer than a block bypass the cache.
+struct BufferedFile {
+    path: Arc<Path>,
+    file: File,
+    len: usize,
+    cache: Arc<BlockCache>,
+}
+
+impl BufferedFile {
+    fn read_at(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        let mut buffer = vec![0; range.len()];
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileExt;
+            self.file.read_exact_at(&mut buffer, range.start as u64)?;
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::FileExt;
+            let mut read = 0;
+            while read < buffer.len() {
+                match self.file.seek_read(
+                    &mut buffer[read..],
+                    (range.start + read) as u64,
+                )? {
+                    0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                    n => read += n,
+                }
+            }
+        }
+        Ok(OwnedBytes::new(buffer))
+    }
+
+    fn block(&self, block: usize) -> io::Result<OwnedBytes> {
+        self.cache.get_or_read((self.path.clone(), block), || {
+            let start = block * BLOCK_SIZE;
+            self.read_at(start..(start + BLOCK_SIZE).min(self.len))
+        })
+    }
+}
+
+impl fmt::Debug for BufferedFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BufferedFile").field(&self.path).finish()
+    }
+}
+
+impl HasLen for BufferedFile {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl FileHandle for BufferedFile {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        if range.is_empty() {
+            return Ok(OwnedBytes::empty());
+        }
+        if range.end > self.len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Read past the end of {}.", self.path.display()),
+            ));
+        }
+        if range.len() > BLOCK_SIZE {
+            return self.read_at(range);
+        }
+        let first = range.start / BLOCK_SIZE;
+        let last = (range.end - 1) / BLOCK_SIZE;
+        let offset = range.start - first * BLOCK_SIZE;
+        if first == last {
+            return Ok(self.block(first)?.slice(offset..offset + range.len()));
+        }
+        let mut bytes = self.block(first)?.as_slice()[offset..].to_vec();
+        bytes.extend_from_slice(
+            &self.block(last)?.as_slice()[..range.end - last * BLOCK_SIZE],
+        );
+        Ok(OwnedBytes::new(bytes))
+    }
+}
+
+/// A directory reading its files with positioned reads through a block
+/// cache rather than memory mapping them, for file systems on which page
+/// faults are slow, e.g. network storage. Writes go to an `MmapDirectory`.
+#[derive(Clone)]
+pub(crate) struct BufferedDirectory {
+    root: PathBuf,
+    inner: MmapDirectory,
+    cache: Arc<BlockCache>,
+}
+
+impl BufferedDirectory {
+    fn open(root: &Path, max_bytes: usize) -> tv::Result<Self> {
+        Ok(BufferedDirectory {
+            root: root.to_path_buf(),
+            inner: MmapDirectory::open(root)?,
+            cache: Arc::new(BlockCache {
+                max_bytes,
+                state: Mutex::default(),
+            }),
+        })
+    }
+}
+
+impl fmt::Debug for BufferedDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BufferedDirectory")
+            .field(&self.root)
+            .finish()
+    }
+}
+
+impl Directory for BufferedDirectory {
+    fn get_file_handle(
+        &self,
+        path: &Path,
+    ) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        let full_path = self.root.join(path);
+        let io_err = |err: io::Error| match err.kind() {
+            io::ErrorKind::NotFound => {
+                OpenReadError::FileDoesNotExist(path.to_path_buf())
+            }
+            _ => OpenReadError::wrap_io_error(err, path.to_path_buf()),
+        };
+        let file = File::open(full_path).map_err(io_err)?;
+        let len = file.metadata().map_err(io_err)?.len() as usize;
+        Ok(Arc::new(BufferedFile {
+            path: Arc::from(path),
+            file,
+            len,
+            cache: self.cache.clone(),
+        }))
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        self.inner.delete(path)?;
+        self.cache.evict(path);
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.inner.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        self.inner.open_write(path)
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.inner.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.inner.atomic_write(path, data)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.inner.sync_directory()
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.inner.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> tv::Result<WatchHandle> {
+        self.inner.watch(watch_callback)
+    }
+}
@@ -0,0 +1,120 @@
+//! A read-only view of an index directory, with a modified list of
+//! segments.
+
+use std::{collections::HashSet, io, path::Path, sync::Arc};
+
+use tantivy::{
+    self as tv,
+    directory::{
+        error::{DeleteError, LockError, OpenReadError, OpenWriteError},
+        DirectoryLock, FileHandle, FileSlice, Lock, WatchCallback, WatchHandle,
+        WritePtr, INDEX_WRITER_LOCK,
+    },
+    Directory, SegmentId,
+};
+
+/// The file listing the segments of the index.
+pub(crate) const META_FILEPATH: &str = "meta.json";
+
+/// How a `ReadOnlyDirectory` changes the `meta.json` it reads.
+#[derive(Clone, Debug)]
+pub(crate) enum MetaOverride {
+    /// Drop these segments from the index.
+    SkipSegments(Arc<HashSet<SegmentId>>),
+    /// Read this content instead.
+    Replace(Arc<Vec<u8>>),
+}
+
+/// A directory whose `meta.json` is changed as it is read, so that the index
+/// opens with other segments than the ones of its last commit.
+///
+/// The index writer lock can't be acquired: a commit would make the changed
+/// list of segments the one of the index for good.
+#[derive(Clone, Debug)]
+pub(crate) struct ReadOnlyDirectory {
+    inner: Box<dyn Directory>,
+    meta: MetaOverride,
+}
+
+impl ReadOnlyDirectory {
+    pub(crate) fn new(inner: Box<dyn Directory>, meta: MetaOverride) -> Self {
+        ReadOnlyDirectory { inner, meta }
+    }
+
+    fn read_meta(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        let skipped = match &self.meta {
+            MetaOverride::Replace(data) => return Ok(data.to_vec()),
+            MetaOverride::SkipSegments(skipped) => skipped,
+        };
+        let data = self.inner.atomic_read(path)?;
+        let mut meta: serde_json::Value = serde_json::from_slice(&data)
+            .map_err(|err| {
+                OpenReadError::wrap_io_error(err.into(), path.into())
+            })?;
+        if let Some(segments) = meta["segments"].as_array_mut() {
+            segments.retain(|segment| {
+                serde_json::from_value::<SegmentId>(
+                    segment["segment_id"].clone(),
+                )
+                .map_or(true, |id| !skipped.contains(&id))
+            });
+        }
+        Ok(meta.to_string().into_bytes())
+    }
+}
+
+impl Directory for ReadOnlyDirectory {
+    fn get_file_handle(
+        &self,
+        path: &Path,
+    ) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        self.inner.get_file_handle(path)
+    }
+
+    fn open_read(&self, path: &Path) -> Result<FileSlice, OpenReadError> {
+        self.inner.open_read(path)
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        self.inner.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.inner.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        self.inner.open_write(path)
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        if path == Path::new(META_FILEPATH) {
+            self.read_meta(path)
+        } else {
+            self.inner.atomic_read(path)
+        }
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.inner.atomic_write(path, data)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.inner.sync_directory()
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        if lock.filepath == INDEX_WRITER_LOCK.filepath {
+            return Err(LockError::IoError(Arc::new(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "The index was opened with a modified list of segments, it is \
+                 read-only.",
+            ))));
+        }
+        self.inner.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> tv::Result<WatchHandle> {
+        self.inner.watch(watch_callback)
+    }
+}
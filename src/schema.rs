@@ -1,10 +1,12 @@
-use crate::to_pyerr;
-use pyo3::{basic::CompareOp, prelude::*, types::PyTuple};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{pythonize, sidecar, signature::SignatureOptions, to_pyerr};
+use pyo3::{basic::CompareOp, prelude::*, types::PyTuple, IntoPyObjectExt};
 use serde::{Deserialize, Serialize};
 use tantivy as tv;
 
 /// Tantivy's Type
-#[pyclass(frozen, module = "tantivy.tantivy")]
+#[pyclass(frozen, eq, eq_int, module = "tantivy.tantivy")]
 #[derive(Clone, PartialEq)]
 pub(crate) enum FieldType {
     Text,
@@ -36,6 +38,23 @@ impl From<FieldType> for tv::schema::Type {
     }
 }
 
+impl From<tv::schema::Type> for FieldType {
+    fn from(value_type: tv::schema::Type) -> FieldType {
+        match value_type {
+            tv::schema::Type::Str => FieldType::Text,
+            tv::schema::Type::U64 => FieldType::Unsigned,
+            tv::schema::Type::I64 => FieldType::Integer,
+            tv::schema::Type::F64 => FieldType::Float,
+            tv::schema::Type::Bool => FieldType::Boolean,
+            tv::schema::Type::Date => FieldType::Date,
+            tv::schema::Type::Facet => FieldType::Facet,
+            tv::schema::Type::Bytes => FieldType::Bytes,
+            tv::schema::Type::Json => FieldType::Json,
+            tv::schema::Type::IpAddr => FieldType::IpAddr,
+        }
+    }
+}
+
 /// Tantivy schema.
 ///
 /// The schema is very strict. To build the schema the `SchemaBuilder` class is
@@ -44,6 +63,78 @@ impl From<FieldType> for tv::schema::Type {
 #[derive(Deserialize, PartialEq, Serialize)]
 pub(crate) struct Schema {
     pub(crate) inner: tv::schema::Schema,
+    #[serde(default)]
+    pub(crate) extensions: SchemaExtensions,
+}
+
+/// Sidecar file holding the schema extensions.
+const EXTENSIONS_PATH: &str = ".tantivy-py.json";
+
+/// Field metadata that the tantivy schema has no room for.
+///
+/// It is persisted in its own file next to the index meta.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub(crate) struct SchemaExtensions {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) vector_fields: BTreeMap<String, VectorOptions>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub(crate) geo_fields: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) signature_fields: BTreeMap<String, SignatureOptions>,
+}
+
+impl SchemaExtensions {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.vector_fields.is_empty()
+            && self.geo_fields.is_empty()
+            && self.signature_fields.is_empty()
+    }
+
+    /// Read the extensions stored in the index, if any.
+    pub(crate) fn load(index: &tv::Index) -> PyResult<Self> {
+        match sidecar::read(index, EXTENSIONS_PATH)? {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(to_pyerr),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Store the extensions in the index.
+    pub(crate) fn save(&self, index: &tv::Index) -> PyResult<()> {
+        let bytes = serde_json::to_vec(self).map_err(to_pyerr)?;
+        sidecar::write(index, EXTENSIONS_PATH, &bytes)
+    }
+}
+
+/// The similarity measure used to compare vectors.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum VectorMetric {
+    Cosine,
+    Dot,
+    L2,
+}
+
+impl std::str::FromStr for VectorMetric {
+    type Err = PyErr;
+
+    fn from_str(metric: &str) -> PyResult<Self> {
+        match metric {
+            "cosine" => Ok(VectorMetric::Cosine),
+            "dot" => Ok(VectorMetric::Dot),
+            "l2" => Ok(VectorMetric::L2),
+            _ => Err(pyo3::exceptions::PyValueError::new_err(
+                "Invalid vector metric, valid choices are: 'cosine', 'dot' \
+                 and 'l2'",
+            )),
+        }
+    }
+}
+
+/// Options of a dense vector field.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct VectorOptions {
+    pub(crate) dim: usize,
+    pub(crate) metric: VectorMetric,
 }
 
 #[pymethods]
@@ -53,17 +144,17 @@ impl Schema {
         other: &Self,
         op: CompareOp,
         py: Python<'_>,
-    ) -> PyObject {
+    ) -> PyResult<PyObject> {
         match op {
-            CompareOp::Eq => (self == other).into_py(py),
-            CompareOp::Ne => (self != other).into_py(py),
-            _ => py.NotImplemented(),
+            CompareOp::Eq => (self == other).into_py_any(py),
+            CompareOp::Ne => (self != other).into_py_any(py),
+            _ => Ok(py.NotImplemented()),
         }
     }
 
     #[staticmethod]
     fn _internal_from_pythonized(serialized: &Bound<PyAny>) -> PyResult<Self> {
-        pythonize::depythonize_bound(serialized.clone()).map_err(to_pyerr)
+        pythonize::depythonize(serialized).map_err(to_pyerr)
     }
 
     fn __reduce__<'a>(
@@ -72,12 +163,12 @@ impl Schema {
     ) -> PyResult<Bound<'a, PyTuple>> {
         let serialized = pythonize::pythonize(py, &*slf).map_err(to_pyerr)?;
 
-        Ok(PyTuple::new_bound(
+        let from_pythonized = slf
+            .into_pyobject(py)?
+            .getattr("_internal_from_pythonized")?;
+        PyTuple::new(
             py,
-            [
-                slf.into_py(py).getattr(py, "_internal_from_pythonized")?,
-                PyTuple::new_bound(py, [serialized]).to_object(py),
-            ],
-        ))
+            [from_pythonized, PyTuple::new(py, [serialized])?.into_any()],
+        )
     }
 }
@@ -1,8 +1,18 @@
 #![allow(clippy::new_ret_no_self)]
 
-use pyo3::{exceptions, prelude::*};
+use pyo3::{
+    exceptions,
+    prelude::*,
+    types::{
+        PyBool, PyBytes, PyDateTime, PyDict, PyFloat, PyInt, PyString, PyType,
+    },
+};
 
-use crate::schema::Schema;
+use crate::{
+    facet::Facet,
+    schema::{Schema, SchemaExtensions, VectorMetric, VectorOptions},
+    signature::{SignatureKind, SignatureOptions},
+};
 use std::sync::{Arc, RwLock};
 use tantivy::schema::{
     self, BytesOptions, DateOptions, IpAddrOptions, INDEXED,
@@ -27,6 +37,7 @@ use tantivy::schema::{
 #[derive(Clone)]
 pub(crate) struct SchemaBuilder {
     pub(crate) builder: Arc<RwLock<Option<schema::SchemaBuilder>>>,
+    extensions: Arc<RwLock<SchemaExtensions>>,
 }
 
 const NO_TOKENIZER_NAME: &str = "raw";
@@ -39,6 +50,7 @@ impl SchemaBuilder {
     fn new() -> Self {
         SchemaBuilder {
             builder: Arc::new(From::from(Some(schema::Schema::builder()))),
+            extensions: Arc::default(),
         }
     }
 
@@ -47,6 +59,101 @@ impl SchemaBuilder {
         schema::is_valid_field_name(name)
     }
 
+    /// Create a schema builder with a field for each annotated attribute of
+    /// a class.
+    ///
+    /// The field types are inferred from the type hints: `str` gives a text
+    /// field, `int` an integer field, `float` a float field, `bool` a
+    /// boolean field, `datetime` a date field, `bytes` a bytes field, and
+    /// `dict` or a nested annotated class a JSON field. `Optional[X]` and
+    /// lists, tuples and sets of `X` give the field of `X`, which holds
+    /// several values. All the fields are stored, and the numeric and date
+    /// fields are indexed.
+    ///
+    /// More fields can be added to the returned builder before building the
+    /// schema.
+    ///
+    /// Args:
+    ///     cls (type): A TypedDict, a dataclass, or any class with type
+    ///         annotations.
+    ///     overrides (Dict[str, Optional[dict]], optional): The options of
+    ///         some fields, passed to the `add_<type>_field` method, replacing
+    ///         the inferred ones. The 'type' key changes the type of the
+    ///         field, e.g. `{"tags": {"type": "facet"}}`, `{"sku": {"fast":
+    ///         True, "tokenizer_name": "raw"}}`. A None value leaves the
+    ///         attribute out of the schema.
+    ///
+    /// Raises a TypeError if the type of a field can't be inferred and isn't
+    /// given in `overrides`, and a ValueError if a type is unknown.
+    #[staticmethod]
+    #[pyo3(signature = (cls, overrides = None))]
+    fn from_typed_dict(
+        py: Python,
+        cls: &Bound<PyAny>,
+        overrides: Option<&Bound<PyDict>>,
+    ) -> PyResult<Py<SchemaBuilder>> {
+        let hints = py
+            .import("typing")?
+            .call_method1("get_type_hints", (cls,))?;
+        let builder = Bound::new(py, SchemaBuilder::new())?;
+        for (name, hint) in hints.downcast::<PyDict>()?.iter() {
+            let name: String = name.extract()?;
+            let field_override = match overrides {
+                Some(overrides) => overrides.get_item(&name)?,
+                None => None,
+            };
+            let options = match &field_override {
+                Some(field_override) if field_override.is_none() => continue,
+                Some(field_override) => {
+                    Some(field_override.downcast::<PyDict>()?.copy()?)
+                }
+                None => None,
+            };
+            let field_type = match options
+                .as_ref()
+                .map(|options| options.get_item("type"))
+                .transpose()?
+                .flatten()
+            {
+                Some(field_type) => field_type.extract::<String>()?,
+                None => infer_field_type(&hint)?
+                    .ok_or_else(|| {
+                        exceptions::PyTypeError::new_err(format!(
+                            "Can't infer the type of field `{name}` from \
+                             {hint}, give it in overrides."
+                        ))
+                    })?
+                    .to_string(),
+            };
+            let kwargs = PyDict::new(py);
+            match field_type.as_str() {
+                "facet" => {}
+                "integer" | "unsigned" | "float" | "boolean" | "date" => {
+                    kwargs.set_item("stored", true)?;
+                    kwargs.set_item("indexed", true)?;
+                }
+                "text" | "json" | "bytes" | "ip_addr" => {
+                    kwargs.set_item("stored", true)?;
+                }
+                _ => {
+                    return Err(exceptions::PyValueError::new_err(format!(
+                        "Unknown type `{field_type}` for field `{name}`."
+                    )))
+                }
+            }
+            if let Some(options) = options {
+                options.del_item("type").ok();
+                kwargs.update(options.as_mapping())?;
+            }
+            builder.call_method(
+                format!("add_{field_type}_field").as_str(),
+                (name,),
+                Some(&kwargs),
+            )?;
+        }
+        Ok(builder.unbind())
+    }
+
     /// Add a new text field to the schema.
     ///
     /// Args:
@@ -473,6 +580,160 @@ impl SchemaBuilder {
         Ok(self.clone())
     }
 
+    /// Add a dense vector field to the schema.
+    ///
+    /// Vectors are stored as a fast field and can be searched with
+    /// `Searcher.knn_search`. Use `Document.add_vector` to set the vector of
+    /// a document.
+    ///
+    /// Args:
+    ///     name (str): The name of the field.
+    ///     dim (int): The number of dimensions of the vectors.
+    ///     metric (str, optional): The similarity measure used to compare
+    ///         vectors. Can be one of 'cosine', 'dot' or 'l2'. Defaults to
+    ///         'cosine'.
+    ///
+    /// Returns the associated field handle.
+    /// Raises a ValueError if there was an error with the field creation.
+    #[pyo3(signature = (name, dim, metric = "cosine"))]
+    fn add_vector_field(
+        &mut self,
+        name: &str,
+        dim: usize,
+        metric: &str,
+    ) -> PyResult<Self> {
+        let metric: VectorMetric = metric.parse()?;
+        if dim == 0 {
+            return Err(exceptions::PyValueError::new_err(
+                "Vector fields need at least one dimension.",
+            ));
+        }
+
+        if let Some(builder) = self.builder.write().unwrap().as_mut() {
+            builder.add_bytes_field(name, BytesOptions::default().set_fast());
+        } else {
+            return Err(exceptions::PyValueError::new_err(
+                "Schema builder object isn't valid anymore.",
+            ));
+        }
+        self.extensions
+            .write()
+            .unwrap()
+            .vector_fields
+            .insert(name.to_string(), VectorOptions { dim, metric });
+
+        Ok(self.clone())
+    }
+
+    /// Add a geo point field to the schema.
+    ///
+    /// Points are encoded as a morton code in an unsigned field. Use
+    /// `Document.add_geopoint` to set the location of a document and the
+    /// `Query.geo_bounding_box_query` and `Query.geo_distance_query` queries
+    /// to search them.
+    ///
+    /// Args:
+    ///     name (str): The name of the field.
+    ///     stored (bool, optional): If true sets the field as stored, the
+    ///         encoded point can be later restored from a Searcher.
+    ///         Defaults to False.
+    ///     fast (bool, optional): Set the field as a fast field. Geo queries
+    ///         and distance sorting read the points from the fast field.
+    ///         Defaults to True.
+    ///
+    /// Returns the associated field handle.
+    /// Raises a ValueError if there was an error with the field creation.
+    #[pyo3(signature = (name, stored = false, fast = true))]
+    fn add_geopoint_field(
+        &mut self,
+        name: &str,
+        stored: bool,
+        fast: bool,
+    ) -> PyResult<Self> {
+        let opts = SchemaBuilder::build_numeric_option(stored, false, fast)?;
+
+        if let Some(builder) = self.builder.write().unwrap().as_mut() {
+            builder.add_u64_field(name, opts);
+        } else {
+            return Err(exceptions::PyValueError::new_err(
+                "Schema builder object isn't valid anymore.",
+            ));
+        }
+        self.extensions
+            .write()
+            .unwrap()
+            .geo_fields
+            .insert(name.to_string());
+
+        Ok(self.clone())
+    }
+
+    /// Add a content signature field to the schema.
+    ///
+    /// The signature of a document is computed from the tokens of a text
+    /// field when the document is added, and stored in a fast field, for
+    /// `Searcher.find_near_duplicates` to detect near duplicate content.
+    /// Tokens are hashed by overlapping shingles of three.
+    ///
+    /// Args:
+    ///     name (str): The name of the field.
+    ///     source_field (str): The text field the signature is computed from.
+    ///     kind (str, optional): 'simhash' for a 64 bits SimHash, compared by
+    ///         the number of differing bits, or 'minhash' for a MinHash of
+    ///         `num_hashes` hashes, compared by the number of differing
+    ///         hashes. Defaults to 'simhash'.
+    ///     num_hashes (int, optional): The number of hashes of a MinHash
+    ///         signature. Defaults to 64.
+    ///
+    /// Returns the associated field handle.
+    /// Raises a ValueError if there was an error with the field creation.
+    #[pyo3(signature = (name, source_field, kind = "simhash", num_hashes = 64))]
+    fn add_signature_field(
+        &mut self,
+        name: &str,
+        source_field: &str,
+        kind: &str,
+        num_hashes: usize,
+    ) -> PyResult<Self> {
+        let kind: SignatureKind = kind.parse()?;
+        if num_hashes == 0 {
+            return Err(exceptions::PyValueError::new_err(
+                "MinHash signatures need at least one hash.",
+            ));
+        }
+
+        if let Some(builder) = self.builder.write().unwrap().as_mut() {
+            match kind {
+                SignatureKind::SimHash => {
+                    builder.add_u64_field(
+                        name,
+                        schema::NumericOptions::default().set_fast(),
+                    );
+                }
+                SignatureKind::MinHash => {
+                    builder.add_bytes_field(
+                        name,
+                        BytesOptions::default().set_fast(),
+                    );
+                }
+            }
+        } else {
+            return Err(exceptions::PyValueError::new_err(
+                "Schema builder object isn't valid anymore.",
+            ));
+        }
+        self.extensions.write().unwrap().signature_fields.insert(
+            name.to_string(),
+            SignatureOptions {
+                source: source_field.to_string(),
+                kind,
+                num_hashes,
+            },
+        );
+
+        Ok(self.clone())
+    }
+
     /// Finalize the creation of a Schema.
     ///
     /// Returns a Schema object. After this is called the SchemaBuilder cannot
@@ -481,7 +742,25 @@ impl SchemaBuilder {
         let builder = self.builder.write().unwrap().take();
         if let Some(builder) = builder {
             let schema = builder.build();
-            Ok(Schema { inner: schema })
+            let extensions = self.extensions.read().unwrap().clone();
+            for (name, options) in &extensions.signature_fields {
+                let is_text =
+                    schema.get_field(&options.source).is_ok_and(|field| {
+                        schema.get_field_entry(field).field_type().value_type()
+                            == schema::Type::Str
+                    });
+                if !is_text {
+                    return Err(exceptions::PyValueError::new_err(format!(
+                        "The source of signature field `{name}`, `{}`, is \
+                         not a text field.",
+                        options.source
+                    )));
+                }
+            }
+            Ok(Schema {
+                inner: schema,
+                extensions,
+            })
         } else {
             Err(exceptions::PyValueError::new_err(
                 "Schema builder object isn't valid anymore.",
@@ -544,3 +823,66 @@ impl SchemaBuilder {
         Ok(options)
     }
 }
+
+/// The type of the field holding the values of a type hint, None if it
+/// can't be inferred.
+fn infer_field_type(hint: &Bound<PyAny>) -> PyResult<Option<&'static str>> {
+    let py = hint.py();
+    let typing = py.import("typing")?;
+    let origin = typing.call_method1("get_origin", (hint,))?;
+    let args = typing.call_method1("get_args", (hint,))?;
+    if !origin.is_none() {
+        let args: Vec<Bound<PyAny>> = args
+            .try_iter()?
+            .filter(|arg| {
+                arg.as_ref().map_or(true, |arg| {
+                    !arg.is(&py.None().into_bound(py).get_type())
+                        && !arg.is(&py.Ellipsis().into_bound(py))
+                })
+            })
+            .collect::<PyResult<_>>()?;
+        let collections = py.import("collections.abc")?;
+        let is_subclass = |base: &Bound<PyAny>| -> PyResult<bool> {
+            Ok(origin.downcast::<PyType>().is_ok()
+                && origin.downcast::<PyType>()?.is_subclass(base)?)
+        };
+        if is_subclass(&collections.getattr("Mapping")?)? {
+            return Ok(Some("json"));
+        }
+        let is_union = origin.is(&typing.getattr("Union")?)
+            || py
+                .import("types")?
+                .getattr("UnionType")
+                .is_ok_and(|union_type| origin.is(&union_type));
+        let is_collection = is_subclass(&collections.getattr("Collection")?)?
+            && !origin.is(&py.get_type::<PyString>())
+            && !origin.is(&py.get_type::<PyBytes>());
+        return match args.as_slice() {
+            [arg] if is_union || is_collection => infer_field_type(arg),
+            _ => Ok(None),
+        };
+    }
+    let field_type = if hint.is(&py.get_type::<PyBool>()) {
+        "boolean"
+    } else if hint.is(&py.get_type::<PyInt>()) {
+        "integer"
+    } else if hint.is(&py.get_type::<PyFloat>()) {
+        "float"
+    } else if hint.is(&py.get_type::<PyString>()) {
+        "text"
+    } else if hint.is(&py.get_type::<PyBytes>()) {
+        "bytes"
+    } else if hint.is(&py.get_type::<PyDateTime>()) {
+        "date"
+    } else if hint.is(&py.get_type::<Facet>()) {
+        "facet"
+    } else if hint.is(&py.get_type::<PyDict>())
+        || hint.hasattr("__annotations__")?
+    {
+        // A TypedDict, a dataclass or a model class.
+        "json"
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(field_type))
+}
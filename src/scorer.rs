@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use tantivy::{
+    self as tv,
+    query::{Explanation, Scorer, Weight},
+    DocId, DocSet, Score, SegmentReader, TERMINATED,
+};
+
+/// Scorer iterating over matching documents collected upfront, sorted by id.
+pub(crate) struct VecScorer {
+    docs: Vec<(DocId, Score)>,
+    cursor: usize,
+}
+
+impl VecScorer {
+    pub(crate) fn new(docs: Vec<(DocId, Score)>) -> Self {
+        VecScorer { docs, cursor: 0 }
+    }
+}
+
+impl DocSet for VecScorer {
+    fn advance(&mut self) -> DocId {
+        self.cursor = (self.cursor + 1).min(self.docs.len());
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.docs
+            .get(self.cursor)
+            .map_or(TERMINATED, |&(doc, _)| doc)
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.docs.len() as u32
+    }
+}
+
+impl Scorer for VecScorer {
+    fn score(&mut self) -> Score {
+        self.docs.get(self.cursor).map_or(0.0, |&(_, score)| score)
+    }
+}
+
+/// Explain the score of a document by seeking it with the weight's scorer.
+pub(crate) fn explain_by_seek(
+    weight: &dyn Weight,
+    reader: &SegmentReader,
+    doc: DocId,
+    description: &'static str,
+) -> tv::Result<Explanation> {
+    let mut scorer = weight.scorer(reader, 1.0)?;
+    if scorer.seek(doc) != doc {
+        return Err(tv::TantivyError::InvalidArgument(format!(
+            "Document #({doc}) does not match"
+        )));
+    }
+    Ok(Explanation::new(description, scorer.score()))
+}
+
+/// The documents of a segment, one bit per document.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DocBitSet {
+    words: Vec<u64>,
+    len: u32,
+}
+
+impl DocBitSet {
+    /// The documents of a segment matching a weight, deleted ones included.
+    pub(crate) fn collect(
+        weight: &dyn Weight,
+        reader: &SegmentReader,
+    ) -> tv::Result<Self> {
+        let mut bitset = DocBitSet {
+            words: vec![0; reader.max_doc().div_ceil(64) as usize],
+            len: 0,
+        };
+        weight.for_each_no_score(reader, &mut |docs| {
+            for &doc in docs {
+                bitset.words[doc as usize / 64] |= 1 << (doc % 64);
+            }
+            bitset.len += docs.len() as u32;
+        })?;
+        Ok(bitset)
+    }
+
+    pub(crate) fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub(crate) fn contains(&self, doc: DocId) -> bool {
+        self.words
+            .get(doc as usize / 64)
+            .is_some_and(|word| word & (1 << (doc % 64)) != 0)
+    }
+
+    /// Remove the documents for which `keep` returns false.
+    pub(crate) fn retain(&mut self, keep: impl Fn(DocId) -> bool) {
+        let mut doc = self.next_from(0);
+        while doc != TERMINATED {
+            if !keep(doc) {
+                self.words[doc as usize / 64] &= !(1 << (doc % 64));
+                self.len -= 1;
+            }
+            doc = self.next_from(doc + 1);
+        }
+    }
+
+    /// The documents of both sets, of the same segment.
+    pub(crate) fn intersection(&self, other: &DocBitSet) -> DocBitSet {
+        self.combine(other, |a, b| a & b)
+    }
+
+    /// The documents of either set, of the same segment.
+    pub(crate) fn union(&self, other: &DocBitSet) -> DocBitSet {
+        self.combine(other, |a, b| a | b)
+    }
+
+    fn combine(&self, other: &DocBitSet, op: fn(u64, u64) -> u64) -> DocBitSet {
+        let num_words = self.words.len().max(other.words.len());
+        let word =
+            |words: &[u64], index| words.get(index).copied().unwrap_or(0);
+        let words: Vec<u64> = (0..num_words)
+            .map(|index| {
+                op(word(&self.words, index), word(&other.words, index))
+            })
+            .collect();
+        let len = words.iter().map(|word| word.count_ones()).sum();
+        DocBitSet { words, len }
+    }
+
+    /// The memory used by the bits.
+    pub(crate) fn num_bytes(&self) -> usize {
+        self.words.len() * std::mem::size_of::<u64>()
+    }
+
+    /// The first document of the set from `doc` on, or `TERMINATED`.
+    fn next_from(&self, doc: DocId) -> DocId {
+        let mut index = doc as usize / 64;
+        let Some(&word) = self.words.get(index) else {
+            return TERMINATED;
+        };
+        let mut word = word & (u64::MAX << (doc % 64));
+        loop {
+            if word != 0 {
+                return (index * 64) as DocId + word.trailing_zeros();
+            }
+            index += 1;
+            match self.words.get(index) {
+                Some(&next) => word = next,
+                None => return TERMINATED,
+            }
+        }
+    }
+}
+
+/// Scorer iterating over the documents of a bitset, with a constant score.
+pub(crate) struct BitSetScorer {
+    docs: Arc<DocBitSet>,
+    doc: DocId,
+    score: Score,
+}
+
+impl BitSetScorer {
+    pub(crate) fn new(docs: Arc<DocBitSet>, score: Score) -> Self {
+        let doc = docs.next_from(0);
+        BitSetScorer { docs, doc, score }
+    }
+}
+
+impl DocSet for BitSetScorer {
+    fn advance(&mut self) -> DocId {
+        if self.doc != TERMINATED {
+            self.doc = self.docs.next_from(self.doc + 1);
+        }
+        self.doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc < target {
+            self.doc = self.docs.next_from(target);
+        }
+        self.doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.docs.len()
+    }
+}
+
+impl Scorer for BitSetScorer {
+    fn score(&mut self) -> Score {
+        self.score
+    }
+}
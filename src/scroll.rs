@@ -0,0 +1,144 @@
+//! Iterating over all the documents matching a query in batches, see
+//! `Searcher.scroll`.
+
+use std::sync::Mutex;
+
+use pyo3::prelude::*;
+use tantivy::{
+    self as tv,
+    query::{EnableScoring, Scorer, Weight},
+    DocSet, TERMINATED,
+};
+
+use crate::{
+    errors::tantivy_err,
+    searcher::{DocAddress, Searcher},
+};
+
+/// The batches of the hits of a query covering all the matching documents,
+/// returned by `Searcher.scroll`.
+///
+/// The hits are `(score, DocAddress)` tuples in the order of the documents,
+/// not by score, and the addresses are those of the searcher of the scroll.
+/// All the batches see the documents of that searcher, even after later
+/// commits.
+///
+///     scroll = searcher.scroll(query, batch_size=1000)
+///     for batch in scroll:
+///         export([scroll.searcher.doc(address) for _, address in batch])
+#[pyclass(frozen, module = "tantivy.tantivy")]
+pub(crate) struct Scroll {
+    searcher: Py<Searcher>,
+    inner: tv::Searcher,
+    weight: Box<dyn Weight>,
+    batch_size: usize,
+    /// Locked while reading a batch, threads iterating over the same scroll
+    /// get the batches one after the other.
+    cursor: Mutex<Cursor>,
+}
+
+/// Where a scroll is in its documents.
+struct Cursor {
+    /// The segment being read, and its scorer positioned on its next hit.
+    segment_ord: usize,
+    scorer: Option<Box<dyn Scorer>>,
+    /// Tracked by the segment inventory of the index until the scroll is
+    /// exhausted, see `Searcher.pin`.
+    segments: Vec<tv::SegmentMeta>,
+}
+
+impl Scroll {
+    pub(crate) fn new(
+        searcher: Py<Searcher>,
+        inner: tv::Searcher,
+        query: &dyn tv::query::Query,
+        batch_size: usize,
+        segments: Vec<tv::SegmentMeta>,
+    ) -> PyResult<Self> {
+        let weight = query
+            .weight(EnableScoring::enabled_from_searcher(&inner))
+            .map_err(tantivy_err)?;
+        Ok(Scroll {
+            searcher,
+            inner,
+            weight,
+            batch_size,
+            cursor: Mutex::new(Cursor {
+                segment_ord: 0,
+                scorer: None,
+                segments,
+            }),
+        })
+    }
+
+    /// The next hits, up to the batch size, empty once all were read.
+    fn next_batch(&self) -> tv::Result<Vec<(f32, DocAddress)>> {
+        let mut batch = Vec::new();
+        let mut cursor = self.cursor.lock().unwrap();
+        let cursor = &mut *cursor;
+        let segment_readers = self.inner.segment_readers();
+        while batch.len() < self.batch_size
+            && cursor.segment_ord < segment_readers.len()
+        {
+            let segment = &segment_readers[cursor.segment_ord];
+            let scorer = match &mut cursor.scorer {
+                Some(scorer) => scorer,
+                current => current.insert(self.weight.scorer(segment, 1.0)?),
+            };
+            let alive_bitset = segment.alive_bitset();
+            while batch.len() < self.batch_size {
+                let doc = scorer.doc();
+                if doc == TERMINATED {
+                    break;
+                }
+                if alive_bitset.is_none_or(|alive| alive.is_alive(doc)) {
+                    batch.push((
+                        scorer.score(),
+                        DocAddress {
+                            segment_ord: cursor.segment_ord as u32,
+                            doc,
+                        },
+                    ));
+                }
+                scorer.advance();
+            }
+            if scorer.doc() == TERMINATED {
+                cursor.scorer = None;
+                cursor.segment_ord += 1;
+            }
+        }
+        if cursor.segment_ord == segment_readers.len() {
+            cursor.segments.clear();
+        }
+        Ok(batch)
+    }
+}
+
+#[pymethods]
+impl Scroll {
+    /// The searcher the addresses of the hits belong to.
+    #[getter]
+    fn searcher(&self, py: Python) -> Py<Searcher> {
+        self.searcher.clone_ref(py)
+    }
+
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python) -> PyResult<Option<Vec<(f32, DocAddress)>>> {
+        let batch = py
+            .allow_threads(|| self.next_batch())
+            .map_err(tantivy_err)?;
+        Ok((!batch.is_empty()).then_some(batch))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Scroll(batch_size={}, segment={}/{})",
+            self.batch_size,
+            self.cursor.lock().unwrap().segment_ord,
+            self.inner.segment_readers().len()
+        )
+    }
+}
@@ -1,13 +1,54 @@
 #![allow(clippy::new_ret_no_self)]
 
-use crate::{document::Document, query::Query, to_pyerr};
-use pyo3::types::PyDict;
-use pyo3::{basic::CompareOp, exceptions::PyValueError, prelude::*};
+use crate::{
+    aggregation::{self, FilterAggregation, MissingValue},
+    approx_count, asyncio,
+    bitset::Bitset,
+    collectors,
+    diversify::DiversifiedTopDocs,
+    document::{value_to_py, Document},
+    errors::{query_too_complex_err, schema_err, tantivy_err},
+    expression::ScoreExpr,
+    facet::{self, Facet, FacetCounts, FacetTreeCollector},
+    features::{self, FeatureMatrix},
+    fetch_session::FetchSession,
+    geo::{check_geo_field, distance_decay, geo_column, GeoPoint},
+    get_field, keywords, metrics,
+    profile::{search_profiled, SearchProfile},
+    pythonize,
+    query::{self, Query},
+    query_cache::QueryCache,
+    query_limits,
+    schema::SchemaExtensions,
+    scorer::DocBitSet,
+    scroll::Scroll,
+    signals::Signals,
+    signature::{NearDuplicateCollector, Signature},
+    sort::{FieldSort, OrderBy},
+    stats::docs_with_field,
+    table,
+    term_vector::term_vector,
+    to_pyerr,
+    vector::KnnCollector,
+};
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::{PyKeyError, PyTypeError, PyValueError};
+use pyo3::types::{PyDict, PyTuple, PyType};
+use pyo3::{basic::CompareOp, prelude::*, IntoPyObjectExt};
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+    time::Instant,
+};
 use tantivy as tv;
-use tantivy::aggregation::AggregationCollector;
-use tantivy::collector::{Count, MultiCollector, TopDocs};
-use tantivy::TantivyDocument;
+use tantivy::aggregation::agg_req::{get_fast_field_names, Aggregations};
+use tantivy::aggregation::{metric::Stats, AggregationCollector, Key};
+use tantivy::collector::{Count, DocSetCollector, MultiCollector, TopDocs};
+use tantivy::query::{BooleanQuery, EnableScoring};
+use tantivy::schema::{IndexRecordOption, OwnedValue};
+use tantivy::{DocSet, TantivyDocument, TERMINATED};
 // Bring the trait into scope. This is required for the `to_named_doc` method.
 // However, tantivy-py declares its own `Document` class, so we need to avoid
 // introduce the `Document` trait into the namespace.
@@ -17,8 +58,113 @@ use tantivy::Document as _;
 ///
 /// A Searcher is used to search the index given a prepared Query.
 #[pyclass(module = "tantivy.tantivy")]
+#[derive(Clone)]
 pub(crate) struct Searcher {
     pub(crate) inner: tv::Searcher,
+    pub(crate) extensions: Arc<SchemaExtensions>,
+    /// The filters every search is restricted to, see `with_filter`.
+    pub(crate) filters: Vec<SearchFilter>,
+    pub(crate) limits: SearchLimits,
+    /// Shared by the searchers of an index to count them, see
+    /// `Index.num_active_searchers`.
+    pub(crate) active: Arc<()>,
+    /// Shared by the searchers of an index, see `enable_query_cache`.
+    pub(crate) query_cache: Arc<QueryCache>,
+    /// Shared by the searchers of an index, see `Index.signals`.
+    pub(crate) signals: Arc<Signals>,
+}
+
+/// The maximum result window and query complexity of the searches, see
+/// `Index.set_search_limits`.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct SearchLimits {
+    pub(crate) max_limit: Option<usize>,
+    pub(crate) max_offset: Option<usize>,
+    pub(crate) max_clauses: Option<usize>,
+    pub(crate) max_expanded_terms: Option<usize>,
+    pub(crate) max_regex_states: Option<usize>,
+}
+
+impl SearchLimits {
+    /// Check the number of hits requested in the `name` argument.
+    pub(crate) fn check_limit(&self, name: &str, limit: usize) -> PyResult<()> {
+        check_max(name, limit, self.max_limit, "max_limit")
+    }
+
+    pub(crate) fn check_offset(&self, offset: usize) -> PyResult<()> {
+        check_max("offset", offset, self.max_offset, "max_offset")
+    }
+
+    /// Check the complexity of a query, raising a QueryTooComplexError.
+    pub(crate) fn check_query(
+        &self,
+        searcher: &tv::Searcher,
+        query: &dyn tv::query::Query,
+    ) -> PyResult<()> {
+        if self.max_clauses.is_none()
+            && self.max_expanded_terms.is_none()
+            && self.max_regex_states.is_none()
+        {
+            return Ok(());
+        }
+        let complexity =
+            query_limits::measure(searcher, query, self.max_expanded_terms)
+                .map_err(tantivy_err)?;
+        for (limit, value, max) in [
+            ("max_clauses", complexity.clauses, self.max_clauses),
+            (
+                "max_expanded_terms",
+                complexity.expanded_terms,
+                self.max_expanded_terms,
+            ),
+            (
+                "max_regex_states",
+                complexity.regex_states,
+                self.max_regex_states,
+            ),
+        ] {
+            match max {
+                Some(max) if value > max => {
+                    return Err(query_too_complex_err(limit, value, max))
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_max(
+    name: &str,
+    value: usize,
+    max: Option<usize>,
+    max_name: &str,
+) -> PyResult<()> {
+    match max {
+        Some(max) if value > max => Err(PyValueError::new_err(format!(
+            "The {name} {value} exceeds the {max_name} of {max} of the \
+             searcher."
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// A filter restricting the hits of a searcher.
+pub(crate) enum SearchFilter {
+    Query(Query),
+    /// A callable returning the filter query, called on every search.
+    Callable(PyObject),
+}
+
+impl Clone for SearchFilter {
+    fn clone(&self) -> Self {
+        match self {
+            SearchFilter::Query(query) => SearchFilter::Query(query.clone()),
+            SearchFilter::Callable(callable) => Python::with_gil(|py| {
+                SearchFilter::Callable(callable.clone_ref(py))
+            }),
+        }
+    }
 }
 
 #[derive(Clone, Deserialize, FromPyObject, PartialEq, Serialize)]
@@ -38,16 +184,36 @@ impl std::fmt::Debug for Fruit {
     }
 }
 
-impl ToPyObject for Fruit {
-    fn to_object(&self, py: Python) -> PyObject {
-        match self {
-            Fruit::Score(s) => s.to_object(py),
-            Fruit::Order(o) => o.to_object(py),
-        }
+impl<'py> IntoPyObject<'py> for &Fruit {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = std::convert::Infallible;
+
+    fn into_pyobject(
+        self,
+        py: Python<'py>,
+    ) -> Result<Self::Output, Self::Error> {
+        Ok(match self {
+            Fruit::Score(s) => s.into_pyobject(py)?.into_any(),
+            Fruit::Order(o) => o.into_pyobject(py)?.into_any(),
+        })
     }
 }
 
-#[pyclass(frozen, module = "tantivy.tantivy")]
+/// Extract scores from a buffer of floats, e.g. a numpy array, or a
+/// sequence of numbers.
+fn extract_scores(any: &Bound<PyAny>) -> PyResult<Vec<f64>> {
+    if let Ok(buffer) = PyBuffer::<f64>::get(any) {
+        return buffer.to_vec(any.py());
+    }
+    if let Ok(buffer) = PyBuffer::<f32>::get(any) {
+        let scores = buffer.to_vec(any.py())?;
+        return Ok(scores.into_iter().map(f64::from).collect());
+    }
+    any.extract()
+}
+
+#[pyclass(frozen, eq, eq_int, module = "tantivy.tantivy")]
 #[derive(Clone, Copy, Deserialize, PartialEq, Serialize)]
 /// Enum representing the direction in which something should be sorted.
 pub(crate) enum Order {
@@ -68,7 +234,7 @@ impl From<Order> for tv::Order {
 }
 
 #[pyclass(frozen, module = "tantivy.tantivy")]
-#[derive(Clone, Default, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Default, Deserialize, Serialize)]
 /// Object holding a results successful search.
 pub(crate) struct SearchResult {
     hits: Vec<(Fruit, DocAddress)>,
@@ -76,28 +242,542 @@ pub(crate) struct SearchResult {
     /// How many documents matched the query. Only available if `count` was set
     /// to true during the search.
     count: Option<usize>,
+    count_relation: CountRelation,
+    /// The statistics of the stats collectors of the search, by name.
+    #[serde(default)]
+    stats: HashMap<String, Stats>,
+    /// The timings of the search, if it was profiled.
+    #[serde(default)]
+    profile: Option<SearchProfile>,
+    /// The position of the result each hit comes from, if it is merged.
+    #[serde(default)]
+    origins: Option<Vec<usize>>,
+    /// The facet counts of the facets collectors of the search, by name.
+    #[serde(default)]
+    facet_counts: HashMap<String, BTreeMap<tv::schema::Facet, u64>>,
+}
+
+/// How the `count` of a search result relates to the number of matching
+/// documents.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CountRelation {
+    /// The count is exact.
+    #[default]
+    Eq,
+    /// The search stopped early, more documents may match.
+    Gte,
+}
+
+impl CountRelation {
+    fn as_str(self) -> &'static str {
+        match self {
+            CountRelation::Eq => "eq",
+            CountRelation::Gte => "gte",
+        }
+    }
+}
+
+impl std::str::FromStr for CountRelation {
+    type Err = PyErr;
+
+    fn from_str(relation: &str) -> PyResult<Self> {
+        match relation {
+            "eq" => Ok(CountRelation::Eq),
+            "gte" => Ok(CountRelation::Gte),
+            _ => Err(PyValueError::new_err(
+                "Invalid count relation, valid choices are: 'eq' and 'gte'",
+            )),
+        }
+    }
+}
+
+/// Timings differ between two runs of the same search, they don't make their
+/// results different.
+impl PartialEq for SearchResult {
+    fn eq(&self, other: &Self) -> bool {
+        self.hits == other.hits
+            && self.count == other.count
+            && self.count_relation == other.count_relation
+            && self.stats == other.stats
+            && self.origins == other.origins
+            && self.facet_counts == other.facet_counts
+    }
+}
+
+impl SearchResult {
+    /// A result holding an exact count, if any.
+    fn exact(hits: Vec<(Fruit, DocAddress)>, count: Option<usize>) -> Self {
+        SearchResult {
+            hits,
+            count,
+            count_relation: CountRelation::Eq,
+            stats: HashMap::new(),
+            profile: None,
+            origins: None,
+            facet_counts: HashMap::new(),
+        }
+    }
+}
+
+/// Compare the fruits of hits of the same kind.
+fn cmp_fruits(a: &Fruit, b: &Fruit) -> std::cmp::Ordering {
+    match (a, b) {
+        (Fruit::Score(a), Fruit::Score(b)) => a.total_cmp(b),
+        (Fruit::Order(a), Fruit::Order(b)) => a.cmp(b),
+        (Fruit::Score(_), Fruit::Order(_)) => std::cmp::Ordering::Less,
+        (Fruit::Order(_), Fruit::Score(_)) => std::cmp::Ordering::Greater,
+    }
+}
+
+/// Combine the statistics of a field over disjoint sets of documents.
+fn merge_stats(a: &Stats, b: &Stats) -> Stats {
+    let bound =
+        |a: Option<f64>, b: Option<f64>, f: fn(f64, f64) -> f64| match (a, b) {
+            (Some(a), Some(b)) => Some(f(a, b)),
+            (a, b) => a.or(b),
+        };
+    let count = a.count + b.count;
+    let sum = a.sum + b.sum;
+    Stats {
+        count,
+        sum,
+        min: bound(a.min, b.min, f64::min),
+        max: bound(a.max, b.max, f64::max),
+        avg: (count > 0).then(|| sum / count as f64),
+    }
 }
 
 #[pymethods]
 impl SearchResult {
     #[new]
+    #[pyo3(signature = (hits, count, count_relation = "eq", stats = None, origins = None, facet_counts = None))]
     fn new(
         py: Python,
         hits: Vec<(PyObject, DocAddress)>,
         count: Option<usize>,
+        count_relation: &str,
+        stats: Option<Bound<PyDict>>,
+        origins: Option<Vec<usize>>,
+        facet_counts: Option<Bound<PyDict>>,
     ) -> PyResult<Self> {
         let hits = hits
             .iter()
             .map(|(f, d)| Ok((f.extract(py)?, d.clone())))
             .collect::<PyResult<Vec<_>>>()?;
-        Ok(Self { hits, count })
+        let stats = match stats {
+            Some(stats) => {
+                pythonize::depythonize(&stats.into_any()).map_err(to_pyerr)?
+            }
+            None => HashMap::new(),
+        };
+        let facet_counts = match facet_counts {
+            Some(facet_counts) => {
+                pythonize::depythonize(&facet_counts.into_any())
+                    .map_err(to_pyerr)?
+            }
+            None => HashMap::new(),
+        };
+        Ok(Self {
+            hits,
+            count,
+            count_relation: count_relation.parse()?,
+            stats,
+            profile: None,
+            origins,
+            facet_counts,
+        })
+    }
+
+    /// Merge the results of several searches, e.g. over the shards of an
+    /// index or for a batch of queries, into one.
+    ///
+    /// Args:
+    ///     results (List[SearchResult]): The results to merge, whose hits
+    ///         are each in the given `order`.
+    ///     limit (int, optional): The maximum number of hits to keep.
+    ///         Defaults to 10.
+    ///     order (Order, optional): The order of the hits, descending for
+    ///         scores, ascending for `sort_by_distance` or when the
+    ///         results were ordered by a field in ascending order. Defaults
+    ///         to descending.
+    ///
+    /// Returns a `SearchResult` holding the first `limit` hits of all the
+    /// results. Hits that tie keep the order of `results`, and `origins`
+    /// tells which result each hit comes from, as the addresses of hits
+    /// from different indexes may be equal. The count is the sum of the
+    /// counts, None if a result has no count, and a lower bound if one of
+    /// them is. The statistics and the facet counts of the fields collected
+    /// for all the results are combined.
+    ///
+    /// Raises a ValueError if some hits are scored and others are ordered by
+    /// a field.
+    #[classmethod]
+    #[pyo3(signature = (results, limit = 10, order = Order::Desc))]
+    fn merge(
+        _cls: &Bound<PyType>,
+        results: Vec<PyRef<SearchResult>>,
+        limit: usize,
+        order: Order,
+    ) -> PyResult<SearchResult> {
+        let mut hits: Vec<(usize, &(Fruit, DocAddress))> = results
+            .iter()
+            .enumerate()
+            .flat_map(|(origin, result)| {
+                result.hits.iter().map(move |hit| (origin, hit))
+            })
+            .collect();
+        let scored = |(_, (fruit, _)): &(usize, &(Fruit, DocAddress))| {
+            matches!(fruit, Fruit::Score(_))
+        };
+        if hits.iter().any(scored) && !hits.iter().all(scored) {
+            return Err(PyValueError::new_err(
+                "Can't merge scored hits with hits ordered by a field.",
+            ));
+        }
+        // Stable, so that ties keep the order of the results.
+        hits.sort_by(|(_, (a, _)), (_, (b, _))| match order {
+            Order::Asc => cmp_fruits(a, b),
+            Order::Desc => cmp_fruits(b, a),
+        });
+        hits.truncate(limit);
+
+        let count = results
+            .iter()
+            .map(|result| result.count)
+            .sum::<Option<usize>>();
+        let count_relation = if results
+            .iter()
+            .any(|result| result.count_relation == CountRelation::Gte)
+        {
+            CountRelation::Gte
+        } else {
+            CountRelation::Eq
+        };
+        let mut stats = HashMap::new();
+        if let Some((first, rest)) = results.split_first() {
+            for (field_name, field_stats) in &first.stats {
+                let merged =
+                    rest.iter().try_fold(field_stats.clone(), |acc, result| {
+                        result
+                            .stats
+                            .get(field_name)
+                            .map(|other| merge_stats(&acc, other))
+                    });
+                if let Some(merged) = merged {
+                    stats.insert(field_name.clone(), merged);
+                }
+            }
+        }
+        let mut facet_counts = HashMap::new();
+        if let Some((first, rest)) = results.split_first() {
+            for (field_name, counts) in &first.facet_counts {
+                let merged =
+                    rest.iter().try_fold(counts.clone(), |mut acc, result| {
+                        for (facet, count) in
+                            result.facet_counts.get(field_name)?
+                        {
+                            *acc.entry(facet.clone()).or_insert(0) += count;
+                        }
+                        Some(acc)
+                    });
+                if let Some(merged) = merged {
+                    facet_counts.insert(field_name.clone(), merged);
+                }
+            }
+        }
+        Ok(SearchResult {
+            origins: Some(hits.iter().map(|&(origin, _)| origin).collect()),
+            hits: hits.into_iter().map(|(_, hit)| hit.clone()).collect(),
+            count,
+            count_relation,
+            stats,
+            profile: None,
+            facet_counts,
+        })
+    }
+
+    /// For a result returned by `merge`, the position in the merged results
+    /// of the result each hit comes from, else None.
+    #[getter]
+    fn origins(&self) -> Option<Vec<usize>> {
+        self.origins.clone()
+    }
+
+    /// The statistics of a field over all the documents matching the query.
+    ///
+    /// Args:
+    ///     field_name (str): The name of a stats collector of the
+    ///         `collector` of the search.
+    ///
+    /// Returns a dict with the `count` of values and their `sum`, `min`,
+    /// `max` and `avg`. The last three are None if no document had a value.
+    ///
+    /// Raises a KeyError if the statistics of the field weren't collected.
+    fn stats(&self, py: Python, field_name: &str) -> PyResult<PyObject> {
+        let stats = self.stats.get(field_name).ok_or_else(|| {
+            PyKeyError::new_err(format!(
+                "The statistics of `{field_name}` weren't collected, add a \
+                 stats collector of that name to the collector of the search."
+            ))
+        })?;
+        pythonize::pythonize(py, stats).map_err(to_pyerr)
+    }
+
+    /// The facet counts of a facet field over all the documents matching the
+    /// query.
+    ///
+    /// Args:
+    ///     field_name (str): The name of a facets collector of the
+    ///         `collector` of the search.
+    ///
+    /// Returns a FacetCounts object.
+    ///
+    /// Raises a KeyError if the facets of the field weren't counted.
+    fn facet_counts(&self, field_name: &str) -> PyResult<FacetCounts> {
+        let counts = self.facet_counts.get(field_name).ok_or_else(|| {
+            PyKeyError::new_err(format!(
+                "The facets of `{field_name}` weren't counted, add a facets \
+                 collector of that name to the collector of the search."
+            ))
+        })?;
+        Ok(FacetCounts {
+            counts: counts.clone(),
+        })
+    }
+
+    /// Where the time of the search went, if it was run with
+    /// `profile=True`, else None.
+    ///
+    /// A dict with the milliseconds spent building the weight of the query
+    /// (`weight_ms`), collecting each segment (`segments`, a list of dicts
+    /// with `segment_ord`, `segment_id`, `num_docs` and `collect_ms`),
+    /// merging their results (`merge_ms`) and in total (`total_ms`).
+    #[getter]
+    fn profile(&self, py: Python) -> PyResult<Option<PyObject>> {
+        self.profile
+            .as_ref()
+            .map(|profile| pythonize::pythonize(py, profile).map_err(to_pyerr))
+            .transpose()
+    }
+
+    /// Re-rank the hits with scores computed outside of tantivy, e.g. by a
+    /// cross-encoder model.
+    ///
+    /// Args:
+    ///     scores (Union[Dict[DocAddress, float], Sequence[float]]): The new
+    ///         scores by hit address, or in the order of the hits, e.g. as a
+    ///         numpy array. The hits of a result returned by `merge` are
+    ///         keyed by `(origin, DocAddress)` tuples instead, as hits from
+    ///         different results may share their address.
+    ///     top_k (int, optional): The number of hits to keep. Defaults to all
+    ///         of them.
+    ///
+    /// Returns a new `SearchResult` with the hits by descending new score.
+    /// Hits with equal scores keep their order, and the hits missing from a
+    /// dict of scores come last, in their order and with their score. The
+    /// count and the statistics of the result are kept.
+    ///
+    /// Raises a ValueError if the hits are ordered by a field, if a score is
+    /// NaN, if a sequence of scores doesn't have one score per hit, or if a
+    /// key of a dict isn't a hit.
+    #[pyo3(signature = (scores, top_k = None))]
+    fn rerank(
+        &self,
+        scores: &Bound<PyAny>,
+        top_k: Option<usize>,
+    ) -> PyResult<SearchResult> {
+        if self
+            .hits
+            .iter()
+            .any(|(fruit, _)| matches!(fruit, Fruit::Order(_)))
+        {
+            return Err(PyValueError::new_err(
+                "Can't rerank hits ordered by a field.",
+            ));
+        }
+        let new_scores: Vec<Option<f64>> = if let Ok(scores) =
+            scores.downcast::<PyDict>()
+        {
+            // The hits of a merged result are keyed by their origin too.
+            let key = |rank: usize| {
+                let origin = self.origins.as_ref().map(|origins| origins[rank]);
+                (origin, self.hits[rank].1.clone())
+            };
+            let mut by_key = HashMap::new();
+            for (key, score) in scores.iter() {
+                let key = match &self.origins {
+                    Some(_) => {
+                        let (origin, address) =
+                            key.extract().map_err(|_| {
+                                PyTypeError::new_err(
+                                    "The hits of a merged result are keyed by \
+                                     (origin, DocAddress) tuples.",
+                                )
+                            })?;
+                        (Some(origin), address)
+                    }
+                    None => (None, key.extract::<DocAddress>()?),
+                };
+                by_key.insert(key, score.extract::<f64>()?);
+            }
+            let keys: HashSet<_> = (0..self.hits.len()).map(key).collect();
+            if let Some((origin, address)) =
+                by_key.keys().find(|key| !keys.contains(key))
+            {
+                let origin = origin
+                    .map(|origin| format!(" of result {origin}"))
+                    .unwrap_or_default();
+                return Err(PyValueError::new_err(format!(
+                    "The document {} of segment {}{origin} isn't a hit.",
+                    address.doc, address.segment_ord
+                )));
+            }
+            (0..self.hits.len())
+                .map(|rank| by_key.get(&key(rank)).copied())
+                .collect()
+        } else {
+            let scores = extract_scores(scores)?;
+            if scores.len() != self.hits.len() {
+                return Err(PyValueError::new_err(format!(
+                    "Got {} scores for {} hits.",
+                    scores.len(),
+                    self.hits.len()
+                )));
+            }
+            scores.into_iter().map(Some).collect()
+        };
+        if new_scores.iter().flatten().any(|score| score.is_nan()) {
+            return Err(PyValueError::new_err("The scores must not be NaN."));
+        }
+
+        let mut ranks: Vec<usize> = (0..self.hits.len()).collect();
+        // Stable, so that ties keep the order of the hits.
+        ranks.sort_by(|&a, &b| match (new_scores[a], new_scores[b]) {
+            (Some(a), Some(b)) => b.total_cmp(&a),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+        ranks.truncate(top_k.unwrap_or(usize::MAX));
+        let origins = self
+            .origins
+            .as_ref()
+            .map(|origins| ranks.iter().map(|&rank| origins[rank]).collect());
+        let hits = ranks
+            .into_iter()
+            .map(|rank| {
+                let (fruit, address) = &self.hits[rank];
+                let fruit = match new_scores[rank] {
+                    Some(score) => Fruit::Score(score as f32),
+                    None => fruit.clone(),
+                };
+                (fruit, address.clone())
+            })
+            .collect();
+        Ok(SearchResult {
+            hits,
+            origins,
+            ..self.clone()
+        })
+    }
+
+    /// The values of fields of the hits as columns, e.g. to build a
+    /// DataFrame with `pandas.DataFrame(result.to_table(...))`.
+    ///
+    /// The fast fields are read from their columns, the other fields and the
+    /// stored text fields from the stored documents, which are only fetched
+    /// if needed.
+    ///
+    /// Args:
+    ///     fields (List[str]): The stored or fast fields to read.
+    ///     searcher (Searcher): The searcher which returned the result.
+    ///     all_values (bool, optional): Whether the cells are the lists of
+    ///         the values of the fields, instead of their first value or None.
+    ///         Defaults to false.
+    ///     format (str, optional): 'pydict' for a dict mapping `_score` and
+    ///         the field names to lists with one cell per hit, in the order
+    ///         of the hits, or 'arrow' for a `pyarrow.Table` of these
+    ///         columns. Defaults to 'pydict'.
+    ///
+    /// Raises a SchemaError if a field is neither stored nor fast, a
+    /// ValueError if a hit isn't a document of the searcher or the format is
+    /// invalid, and an ImportError if the 'arrow' format is requested and
+    /// pyarrow isn't installed.
+    #[pyo3(signature = (fields, searcher, all_values = false, format = "pydict"))]
+    fn to_table(
+        &self,
+        py: Python,
+        fields: Vec<String>,
+        searcher: &Searcher,
+        all_values: bool,
+        format: &str,
+    ) -> PyResult<PyObject> {
+        if !matches!(format, "pydict" | "arrow") {
+            return Err(PyValueError::new_err(
+                "Invalid table format, valid choices are: 'pydict' and 'arrow'",
+            ));
+        }
+        let doc_addresses: Vec<DocAddress> = self
+            .hits
+            .iter()
+            .map(|(_, address)| address.clone())
+            .collect();
+        let columns =
+            table::read_columns(py, &searcher.inner, &doc_addresses, &fields)?;
+
+        let table = PyDict::new(py);
+        let scores = self
+            .hits
+            .iter()
+            .map(|(fruit, _)| fruit.into_py_any(py))
+            .collect::<PyResult<Vec<_>>>()?;
+        table.set_item("_score", scores)?;
+        for (field_name, column) in fields.iter().zip(columns) {
+            let cells = column
+                .iter()
+                .map(|values| {
+                    if all_values {
+                        let values = values
+                            .iter()
+                            .map(|value| value_to_py(py, value))
+                            .collect::<PyResult<Vec<_>>>()?;
+                        return values.into_py_any(py);
+                    }
+                    match values.first() {
+                        Some(value) => value_to_py(py, value),
+                        None => Ok(py.None()),
+                    }
+                })
+                .collect::<PyResult<Vec<_>>>()?;
+            table.set_item(field_name, cells)?;
+        }
+        if format == "arrow" {
+            return Ok(py
+                .import("pyarrow")?
+                .call_method1("table", (table,))?
+                .unbind());
+        }
+        Ok(table.into_any().unbind())
+    }
+
+    /// How `count` relates to the number of matching documents: 'eq' if it
+    /// is exact, 'gte' if it is a lower bound because the search stopped
+    /// early. None if the count wasn't requested.
+    #[getter]
+    fn count_relation(&self) -> Option<&'static str> {
+        self.count.map(|_| self.count_relation.as_str())
     }
 
     fn __repr__(&self) -> PyResult<String> {
         if let Some(count) = self.count {
+            let relation = match self.count_relation {
+                CountRelation::Eq => "",
+                CountRelation::Gte => ">=",
+            };
             Ok(format!(
-                "SearchResult(hits: {:?}, count: {})",
-                self.hits, count
+                "SearchResult(hits: {:?}, count: {}{})",
+                self.hits, relation, count
             ))
         } else {
             Ok(format!("SearchResult(hits: {:?})", self.hits))
@@ -109,31 +789,46 @@ impl SearchResult {
         other: &Self,
         op: CompareOp,
         py: Python<'_>,
-    ) -> PyObject {
+    ) -> PyResult<PyObject> {
         match op {
-            CompareOp::Eq => (self == other).into_py(py),
-            CompareOp::Ne => (self != other).into_py(py),
-            _ => py.NotImplemented(),
+            CompareOp::Eq => (self == other).into_py_any(py),
+            CompareOp::Ne => (self != other).into_py_any(py),
+            _ => Ok(py.NotImplemented()),
         }
     }
 
+    #[allow(clippy::type_complexity)]
     fn __getnewargs__(
         &self,
         py: Python,
-    ) -> PyResult<(Vec<(PyObject, DocAddress)>, Option<usize>)> {
-        Ok((self.hits(py)?, self.count))
+    ) -> PyResult<(
+        Vec<(PyObject, DocAddress)>,
+        Option<usize>,
+        &'static str,
+        PyObject,
+        Option<Vec<usize>>,
+        PyObject,
+    )> {
+        Ok((
+            self.hits(py)?,
+            self.count,
+            self.count_relation.as_str(),
+            pythonize::pythonize(py, &self.stats).map_err(to_pyerr)?,
+            self.origins.clone(),
+            pythonize::pythonize(py, &self.facet_counts).map_err(to_pyerr)?,
+        ))
     }
 
     #[getter]
     /// The list of tuples that contains the scores and DocAddress of the
     /// search results.
     fn hits(&self, py: Python) -> PyResult<Vec<(PyObject, DocAddress)>> {
-        let ret: Vec<(PyObject, DocAddress)> = self
-            .hits
+        self.hits
             .iter()
-            .map(|(result, address)| (result.to_object(py), address.clone()))
-            .collect();
-        Ok(ret)
+            .map(|(result, address)| {
+                Ok((result.into_py_any(py)?, address.clone()))
+            })
+            .collect()
     }
 }
 
@@ -147,19 +842,79 @@ impl Searcher {
     ///         return. Defaults to 10.
     ///     count (bool, optional): Should the number of documents that match
     ///         the query be returned as well. Defaults to true.
-    ///     order_by_field (Field, optional): A schema field that the results
-    ///         should be ordered by. The field must be declared as a fast field
-    ///         when building the schema. Note, this only works for unsigned
-    ///         fields.
+    ///     order_by_field (Union[str, Tuple[str, Order, str]], optional): A
+    ///         schema field that the results should be ordered by. The field
+    ///         must be declared as a fast field when building the schema.
+    ///         Text and bytes fields are sorted by their terms, in byte
+    ///         order, and can't be averaged. The first value of a
+    ///         multivalued field counts, unless a `(field_name, order, mode)`
+    ///         tuple gives the order and the mode reducing the values:
+    ///         'first', 'min', 'max' or 'avg'.
     ///     offset (Field, optional): The offset from which the results have
     ///         to be returned.
     ///     order (Order, optional): The order in which the results
     ///         should be sorted. If not specified, defaults to descending.
+    ///     sort_by_distance (Tuple[str, float, float], optional): A
+    ///         `(field_name, lat, lon)` tuple sorting the results by distance
+    ///         to the given location, nearest first, instead of by score.
+    ///         The field must be a fast geo point field. The score of a hit
+    ///         is its distance in meters, infinite if the document has no
+    ///         point.
+    ///     boost_by_distance (Tuple[str, float, float, float], optional): A
+    ///         `(field_name, lat, lon, scale_m)` tuple decaying the score of
+    ///         each hit with its distance to the given location. The score is
+    ///         halved every `scale_m` meters, documents without a point score
+    ///         0. The field must be a fast geo point field.
+    ///     profile (bool, optional): Time the phases of the search, see
+    ///         `SearchResult.profile`. Segments are then searched one after
+    ///         the other. Defaults to false.
+    ///     diversify_by (Tuple[str, int], optional): A
+    ///         `(field_name, max_per_value)` tuple keeping at most
+    ///         `max_per_value` hits with the same value of the field, e.g. so
+    ///         that a single brand doesn't fill the first page. Lower scored
+    ///         hits take the place of the skipped ones. The field must be a
+    ///         fast field, only its first value counts, and the documents
+    ///         without a value are not limited.
+    ///     pre_filter (Bitset, optional): Only documents of this bitset,
+    ///         returned by `matching_bitset`, are searched. The scores don't
+    ///         change.
+    ///     score_expr (str, optional): An arithmetic expression replacing the
+    ///         score of each hit, e.g. `"_score * 0.7 + log1p(clicks) * 0.3"`.
+    ///         It is compiled once and evaluated in Rust, with `_score` the
+    ///         score of the query. It supports numbers, `+`, `-`, `*`, `/`,
+    ///         `^`, parentheses and the functions abs, exp, log, log1p,
+    ///         log10, sqrt, pow, min and max.
+    ///     expr_fields (List[str], optional): The fields used as variables in
+    ///         `score_expr`, which must be numeric, boolean or date fast
+    ///         fields. Only their first value counts, missing values are 0,
+    ///         booleans are 0 or 1 and dates are seconds since the epoch.
+    ///     missing (Union[str, int, float, bool, datetime], optional): Where
+    ///         the documents without a value of `order_by_field` rank:
+    ///         'first', 'last', or as if they had the given value of the
+    ///         field. Defaults to 'last', in both orders.
+    ///     expr_signals (List[str], optional): The signals of the signal
+    ///         store of the index used as variables in `score_expr`, see
+    ///         `Index.signals`. Documents without a signal have 0.
+    ///     collector (MultiCollector, optional): Stats and facets collectors
+    ///         run over all the matching documents in the same pass, their
+    ///         fruits read by name with `SearchResult.stats` and
+    ///         `SearchResult.facet_counts`. With facets collectors, the
+    ///         segments are searched in parallel, unless profiled.
+    ///
+    /// Only one of `order_by_field`, `sort_by_distance`,
+    /// `boost_by_distance` and `score_expr` can be given, and `diversify_by`
+    /// can't be combined with them.
     ///
     /// Returns `SearchResult` object.
     ///
-    /// Raises a ValueError if there was an error with the search.
-    #[pyo3(signature = (query, limit = 10, count = true, order_by_field = None, offset = 0, order = Order::Desc))]
+    /// Raises a ValueError if there was an error with the search, if
+    /// `pre_filter` doesn't cover the segments of the searcher, if
+    /// `score_expr` is invalid or if `collector` has other collectors than
+    /// stats and facets ones, and a SchemaError if a field of
+    /// `diversify_by`, `expr_fields` or of a stats collector isn't a
+    /// suitable fast field, or if a facets collector reads a field that
+    /// isn't a facet field.
+    #[pyo3(signature = (query, limit = 10, count = true, order_by_field = None, offset = 0, order = Order::Desc, sort_by_distance = None, boost_by_distance = None, profile = false, diversify_by = None, pre_filter = None, score_expr = None, expr_fields = Vec::new(), missing = None, expr_signals = Vec::new(), collector = None))]
     #[allow(clippy::too_many_arguments)]
     fn search(
         &self,
@@ -167,11 +922,101 @@ impl Searcher {
         query: &Query,
         limit: usize,
         count: bool,
-        order_by_field: Option<&str>,
+        order_by_field: Option<OrderBy>,
         offset: usize,
         order: Order,
+        sort_by_distance: Option<(String, f64, f64)>,
+        boost_by_distance: Option<(String, f64, f64, f64)>,
+        profile: bool,
+        diversify_by: Option<(String, usize)>,
+        pre_filter: Option<Bitset>,
+        score_expr: Option<&str>,
+        expr_fields: Vec<String>,
+        missing: Option<&Bound<PyAny>>,
+        expr_signals: Vec<String>,
+        collector: Option<PyRef<collectors::MultiCollector>>,
     ) -> PyResult<SearchResult> {
-        py.allow_threads(move || {
+        let sort_options = [
+            order_by_field.is_some(),
+            sort_by_distance.is_some(),
+            boost_by_distance.is_some(),
+            score_expr.is_some(),
+        ];
+        if sort_options.iter().filter(|&&option| option).count() > 1 {
+            return Err(PyValueError::new_err(
+                "Only one of order_by_field, sort_by_distance, \
+                 boost_by_distance and score_expr can be used.",
+            ));
+        }
+        self.limits.check_limit("limit", limit)?;
+        self.limits.check_offset(offset)?;
+        if diversify_by.is_some() && sort_options.contains(&true) {
+            return Err(PyValueError::new_err(
+                "diversify_by can't be combined with order_by_field, \
+                 sort_by_distance, boost_by_distance or score_expr.",
+            ));
+        }
+        if let Some((field_name, max_per_value)) = &diversify_by {
+            self.check_diversify_field(field_name)?;
+            if *max_per_value == 0 {
+                return Err(PyValueError::new_err(
+                    "The max_per_value of diversify_by must be at least 1.",
+                ));
+            }
+        }
+        let sort_by_distance = sort_by_distance
+            .map(|(field_name, lat, lon)| self.geo_origin(field_name, lat, lon))
+            .transpose()?;
+        let boost_by_distance = boost_by_distance
+            .map(|(field_name, lat, lon, scale_m)| {
+                if scale_m.is_nan() || scale_m <= 0.0 {
+                    return Err(PyValueError::new_err(
+                        "The scale of boost_by_distance must be a positive \
+                         number of meters.",
+                    ));
+                }
+                Ok((self.geo_origin(field_name, lat, lon)?, scale_m))
+            })
+            .transpose()?;
+        if missing.is_some() && order_by_field.is_none() {
+            return Err(PyValueError::new_err(
+                "missing only applies to order_by_field.",
+            ));
+        }
+        let field_sort = order_by_field
+            .map(|order_by| {
+                FieldSort::new(&self.inner, &order_by, order, missing)
+            })
+            .transpose()?;
+        let score_expr = score_expr
+            .map(|source| {
+                ScoreExpr::compile(
+                    self.inner.schema(),
+                    source,
+                    expr_fields,
+                    expr_signals,
+                    &self.signals,
+                )
+            })
+            .transpose()?
+            .map(Arc::new);
+        let collectors::SearchCollectors {
+            stats: stats_aggs,
+            facets: facet_collectors,
+        } = match collector {
+            Some(collector) => collector.search_collectors(self)?,
+            None => collectors::SearchCollectors {
+                stats: None,
+                facets: Vec::new(),
+            },
+        };
+        let mut query = self.restrict(py, query)?;
+        if let Some(bitset) = &pre_filter {
+            query = bitset.restrict(&self.inner, query)?;
+        }
+        let start = Instant::now();
+
+        let result = py.allow_threads(move || {
             let mut multicollector = MultiCollector::new();
 
             let count_handle = if count {
@@ -179,15 +1024,139 @@ impl Searcher {
             } else {
                 None
             };
+            let stats_handle = stats_aggs.map(|aggs| {
+                multicollector.add_collector(AggregationCollector::from_aggs(
+                    aggs,
+                    Default::default(),
+                ))
+            });
+            let facet_handles: Vec<_> = facet_collectors
+                .into_iter()
+                .map(|(name, collector)| {
+                    (name, multicollector.add_collector(collector))
+                })
+                .collect();
+
+            let faceted = !facet_handles.is_empty();
+            let mut search_profile = None;
+            let mut run = |collector: &MultiCollector| {
+                if faceted && !profile {
+                    return facet::search_in_parallel(
+                        &self.inner,
+                        query.get(),
+                        collector,
+                    );
+                }
+                if !profile {
+                    return self.inner.search(query.get(), collector);
+                }
+                let (fruit, timings) =
+                    search_profiled(&self.inner, query.get(), collector)?;
+                search_profile = Some(timings);
+                Ok(fruit)
+            };
 
             let (mut multifruit, hits) = {
-                if let Some(order_by) = order_by_field {
+                if let Some((field_name, max_per_value)) = diversify_by {
+                    let collector = DiversifiedTopDocs {
+                        field_name,
+                        max_per_value,
+                        limit,
+                        offset,
+                    };
+                    let top_docs_handle =
+                        multicollector.add_collector(collector);
+                    let mut r = run(&multicollector).map_err(tantivy_err)?;
+                    let result: Vec<(Fruit, DocAddress)> = top_docs_handle
+                        .extract(&mut r)
+                        .iter()
+                        .map(|(f, d)| (Fruit::Score(*f), DocAddress::from(d)))
+                        .collect();
+                    (r, result)
+                } else if let Some((field_name, center)) = sort_by_distance {
+                    let collector = TopDocs::with_limit(limit)
+                        .and_offset(offset)
+                        .custom_score(move |segment: &tv::SegmentReader| {
+                            let column =
+                                geo_column(segment, &field_name).ok().flatten();
+                            move |doc| {
+                                // Negated so that the nearest documents rank
+                                // first.
+                                column
+                                    .as_ref()
+                                    .and_then(|column| column.first(doc))
+                                    .map_or(f64::NEG_INFINITY, |code| {
+                                        -center.distance(GeoPoint::decode(code))
+                                    })
+                            }
+                        });
+                    let top_docs_handle =
+                        multicollector.add_collector(collector);
+                    let mut r = run(&multicollector).map_err(tantivy_err)?;
+                    let result: Vec<(Fruit, DocAddress)> = top_docs_handle
+                        .extract(&mut r)
+                        .iter()
+                        .map(|(distance, d)| {
+                            (
+                                Fruit::Score(-*distance as f32),
+                                DocAddress::from(d),
+                            )
+                        })
+                        .collect();
+                    (r, result)
+                } else if let Some(((field_name, center), scale_m)) =
+                    boost_by_distance
+                {
+                    let collector = TopDocs::with_limit(limit)
+                        .and_offset(offset)
+                        .tweak_score(move |segment: &tv::SegmentReader| {
+                            let column =
+                                geo_column(segment, &field_name).ok().flatten();
+                            move |doc, score: tv::Score| {
+                                let decay = column
+                                    .as_ref()
+                                    .and_then(|column| column.first(doc))
+                                    .map_or(0.0, |code| {
+                                        let point = GeoPoint::decode(code);
+                                        distance_decay(
+                                            center.distance(point),
+                                            scale_m,
+                                        )
+                                    });
+                                score * decay
+                            }
+                        });
+                    let top_docs_handle =
+                        multicollector.add_collector(collector);
+                    let mut r = run(&multicollector).map_err(tantivy_err)?;
+                    let result: Vec<(Fruit, DocAddress)> = top_docs_handle
+                        .extract(&mut r)
+                        .iter()
+                        .map(|(f, d)| (Fruit::Score(*f), DocAddress::from(d)))
+                        .collect();
+                    (r, result)
+                } else if let Some(score_expr) = score_expr {
+                    let collector = TopDocs::with_limit(limit)
+                        .and_offset(offset)
+                        .tweak_score(move |segment: &tv::SegmentReader| {
+                            score_expr.segment_scorer(segment)
+                        });
+                    let top_docs_handle =
+                        multicollector.add_collector(collector);
+                    let mut r = run(&multicollector).map_err(tantivy_err)?;
+                    let result: Vec<(Fruit, DocAddress)> = top_docs_handle
+                        .extract(&mut r)
+                        .iter()
+                        .map(|(f, d)| (Fruit::Score(*f), DocAddress::from(d)))
+                        .collect();
+                    (r, result)
+                } else if let Some(field_sort) = field_sort {
                     let collector = TopDocs::with_limit(limit)
                         .and_offset(offset)
-                        .order_by_u64_field(order_by, order.into());
+                        .custom_score(field_sort);
                     let top_docs_handle =
                         multicollector.add_collector(collector);
-                    let ret = self.inner.search(query.get(), &multicollector);
+                    let ret = run(&multicollector);
 
                     match ret {
                         Ok(mut r) => {
@@ -200,16 +1169,14 @@ impl Searcher {
                                 .collect();
                             (r, result)
                         }
-                        Err(e) => {
-                            return Err(PyValueError::new_err(e.to_string()))
-                        }
+                        Err(e) => return Err(tantivy_err(e)),
                     }
                 } else {
                     let collector =
                         TopDocs::with_limit(limit).and_offset(offset);
                     let top_docs_handle =
                         multicollector.add_collector(collector);
-                    let ret = self.inner.search(query.get(), &multicollector);
+                    let ret = run(&multicollector);
 
                     match ret {
                         Ok(mut r) => {
@@ -222,51 +1189,788 @@ impl Searcher {
                                 .collect();
                             (r, result)
                         }
-                        Err(e) => {
-                            return Err(PyValueError::new_err(e.to_string()))
-                        }
+                        Err(e) => return Err(tantivy_err(e)),
                     }
                 }
             };
 
             let count = count_handle.map(|h| h.extract(&mut multifruit));
+            let mut result = SearchResult::exact(hits, count);
+            result.profile = search_profile;
+            if let Some(handle) = stats_handle {
+                result.stats =
+                    aggregation::stats_results(handle.extract(&mut multifruit));
+            }
+            for (name, handle) in facet_handles {
+                result
+                    .facet_counts
+                    .insert(name, handle.extract(&mut multifruit));
+            }
+
+            Ok(result)
+        })?;
+
+        let mut counters = vec![
+            ("num_hits", result.hits.len() as u64),
+            ("num_segments", self.inner.segment_readers().len() as u64),
+        ];
+        if let Some(count) = result.count {
+            counters.push(("count", count as u64));
+        }
+        metrics::emit("search", start, &counters);
+        Ok(result)
+    }
+
+    /// Search the index without blocking the running asyncio event loop.
+    ///
+    /// Takes the same arguments as `search`, which runs in the default
+    /// executor of the loop.
+    ///
+    /// Returns an awaitable of a `SearchResult` object.
+    ///
+    /// Raises a RuntimeError if there is no running event loop.
+    #[pyo3(signature = (*args, **kwargs))]
+    fn search_async<'py>(
+        slf: &Bound<'py, Self>,
+        args: Py<PyTuple>,
+        kwargs: Option<Py<PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let searcher = slf.clone().unbind();
+        asyncio::spawn(slf.py(), move |py| {
+            searcher
+                .bind(py)
+                .call_method(
+                    "search",
+                    args.bind(py),
+                    kwargs.as_ref().map(|kwargs| kwargs.bind(py)),
+                )
+                .map(Bound::unbind)
+        })
+    }
+
+    /// Search the nearest neighbors of a vector.
+    ///
+    /// The search is an exact scan over the vectors of all the documents
+    /// matching the filter query.
+    ///
+    /// Args:
+    ///     field_name (str): The vector field to search.
+    ///     query_vector (List[float]): The vector to compare documents to. Its
+    ///         length must match the dimension of the field.
+    ///     k (int, optional): The number of neighbors to return. Defaults
+    ///         to 10.
+    ///     filter_query (Query, optional): Only documents matching this query
+    ///         are considered. Defaults to all documents.
+    ///
+    /// Returns `SearchResult` object, with the nearest documents first. The
+    /// score of a hit is the similarity for the 'cosine' and 'dot' metrics,
+    /// and the euclidean distance for the 'l2' metric.
+    ///
+    /// Raises a SchemaError if the field isn't a vector field or the vector
+    /// dimension doesn't match.
+    #[pyo3(signature = (field_name, query_vector, k = 10, filter_query = None))]
+    fn knn_search(
+        &self,
+        py: Python,
+        field_name: &str,
+        query_vector: Vec<f32>,
+        k: usize,
+        filter_query: Option<&Query>,
+    ) -> PyResult<SearchResult> {
+        self.limits.check_limit("k", k)?;
+        let collector = self.knn_collector(field_name, query_vector, k)?;
+        let metric = collector.metric;
+        let filter_query = match (filter_query, self.filter_query(py)?) {
+            (Some(query), Some(filter)) => Some(must_match_all(vec![
+                query.inner.box_clone(),
+                filter.inner,
+            ])),
+            (query, filter) => filter.or_else(|| query.cloned()),
+        };
+        py.allow_threads(move || {
+            let hits = self.knn_hits(&collector, filter_query.as_ref())?;
+            let hits = hits
+                .iter()
+                .map(|(rank, doc)| {
+                    (Fruit::Score(metric.score(*rank)), DocAddress::from(doc))
+                })
+                .collect();
+            Ok(SearchResult::exact(hits, None))
+        })
+    }
+
+    /// Find the near duplicates of a document by their content signature.
+    ///
+    /// The signatures of all the documents matching the filters of the
+    /// searcher are compared to the signature of the document, e.g. to
+    /// collapse the near duplicate articles of a news feed at query time.
+    ///
+    /// Args:
+    ///     doc_address (DocAddress): The document to find the duplicates of.
+    ///     hamming_distance (int, optional): The maximum distance between
+    ///         signatures: the number of differing bits for 'simhash'
+    ///         signatures, and of differing hashes for 'minhash' signatures.
+    ///         Defaults to 3.
+    ///     field_name (str, optional): The signature field, see
+    ///         `SchemaBuilder.add_signature_field`. Defaults to the only
+    ///         signature field of the schema.
+    ///
+    /// Returns `SearchResult` object, holding the documents other than
+    /// `doc_address` within the distance, nearest first. The order value of
+    /// a hit is its distance.
+    ///
+    /// Raises a ValueError if the document doesn't exist, doesn't match the
+    /// filters of the searcher or has no signature, and a SchemaError if the
+    /// field isn't a signature field.
+    #[pyo3(signature = (doc_address, hamming_distance = 3, field_name = None))]
+    fn find_near_duplicates(
+        &self,
+        py: Python,
+        doc_address: &DocAddress,
+        hamming_distance: u32,
+        field_name: Option<&str>,
+    ) -> PyResult<SearchResult> {
+        let signature_fields = &self.extensions.signature_fields;
+        let (field_name, options) = match field_name {
+            Some(name) => match signature_fields.get_key_value(name) {
+                Some(field) => field,
+                None => {
+                    return Err(schema_err(
+                        format!("Field `{name}` is not a signature field."),
+                        name,
+                    ))
+                }
+            },
+            None if signature_fields.len() == 1 => {
+                signature_fields.iter().next().unwrap()
+            }
+            None => {
+                return Err(PyValueError::new_err(format!(
+                    "The schema has {} signature fields, pass the field_name.",
+                    signature_fields.len()
+                )))
+            }
+        };
+        self.check_visible(py, std::slice::from_ref(doc_address))?;
+        let segment = segment_of(&self.inner, doc_address)?;
+        let Some(signature) =
+            Signature::read(segment, field_name, options.kind, doc_address.doc)
+                .map_err(tantivy_err)?
+        else {
+            return Err(PyValueError::new_err(format!(
+                "Document {} in segment {} has no `{field_name}` signature.",
+                doc_address.doc, doc_address.segment_ord
+            )));
+        };
+        let collector = NearDuplicateCollector {
+            field: field_name.clone(),
+            signature,
+            max_distance: hamming_distance,
+        };
+        let filter_query = self.filter_query(py)?;
+        let address = tv::DocAddress::from(doc_address);
+        py.allow_threads(move || {
+            let hits = match &filter_query {
+                Some(query) => self.inner.search(query.get(), &collector),
+                None => self.inner.search(&tv::query::AllQuery, &collector),
+            }
+            .map_err(tantivy_err)?;
+            let hits = hits
+                .iter()
+                .filter(|(_, doc)| *doc != address)
+                .map(|(distance, doc)| {
+                    (Fruit::Order(u64::from(*distance)), DocAddress::from(doc))
+                })
+                .collect();
+            Ok(SearchResult::exact(hits, None))
+        })
+    }
+
+    /// Search the index combining a text query with a second ranking.
+    ///
+    /// Both rankings are computed natively and fused into a single one, e.g.
+    /// to blend BM25 relevance with vector similarity.
+    ///
+    /// Args:
+    ///     query (Query): The query producing the first ranking, usually a
+    ///         BM25 scored text query.
+    ///     vector_or_scores: The second ranking. Either a
+    ///         `(field_name, query_vector)` tuple running a nearest neighbors
+    ///         search on a vector field, or a list of `(score, DocAddress)`
+    ///         pairs computed by the caller, higher scores ranking first.
+    ///     k (int, optional): The number of hits to return. Defaults to 10.
+    ///     fusion (str, optional): How the rankings are combined. 'rrf' for
+    ///         reciprocal rank fusion, summing `weight / (rrf_k + rank)` for
+    ///         each ranking, or 'linear' for a weighted sum of the min-max
+    ///         normalized scores. Defaults to 'rrf'.
+    ///     weights (Tuple[float, float], optional): The weights of the query
+    ///         ranking and of the second ranking. Defaults to (1.0, 1.0).
+    ///     num_candidates (int, optional): How many hits of each ranking are
+    ///         fused. Defaults to the greater of `k` and 100.
+    ///     rrf_k (int, optional): The rank constant of reciprocal rank
+    ///         fusion. Defaults to 60.
+    ///
+    /// Returns `SearchResult` object, holding the fused scores.
+    ///
+    /// Raises a ValueError if the fusion method is unknown, and a SchemaError
+    /// if the vector field is invalid.
+    #[pyo3(signature = (
+        query,
+        vector_or_scores,
+        k = 10,
+        fusion = "rrf",
+        weights = (1.0, 1.0),
+        num_candidates = None,
+        rrf_k = 60
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn hybrid_search(
+        &self,
+        py: Python,
+        query: &Query,
+        vector_or_scores: SecondRanking,
+        k: usize,
+        fusion: &str,
+        weights: (f32, f32),
+        num_candidates: Option<usize>,
+        rrf_k: u32,
+    ) -> PyResult<SearchResult> {
+        let fusion: Fusion = fusion.parse()?;
+        self.limits.check_limit("k", k)?;
+        if let Some(num_candidates) = num_candidates {
+            self.limits.check_limit("num_candidates", num_candidates)?;
+        }
+        let num_candidates = num_candidates.unwrap_or(k.max(100));
+        let (collector, scores) = match vector_or_scores {
+            SecondRanking::Vector(field_name, query_vector) => {
+                let collector = self.knn_collector(
+                    &field_name,
+                    query_vector,
+                    num_candidates,
+                )?;
+                (Some(collector), Vec::new())
+            }
+            SecondRanking::Scores(mut scores) => {
+                scores.sort_by(|a, b| b.0.total_cmp(&a.0));
+                scores.truncate(num_candidates);
+                let scores = scores
+                    .iter()
+                    .map(|(score, doc)| (*score, doc.into()))
+                    .collect();
+                (None, scores)
+            }
+        };
+
+        let query = self.restrict(py, query)?;
+        let filter = self.filter_query(py)?;
+
+        py.allow_threads(move || {
+            let first = self
+                .inner
+                .search(query.get(), &TopDocs::with_limit(num_candidates))
+                .map_err(tantivy_err)?;
+            let second = match (collector, filter) {
+                (Some(collector), filter) => {
+                    self.knn_hits(&collector, filter.as_ref())?
+                }
+                (None, Some(filter)) => {
+                    let allowed = self
+                        .inner
+                        .search(filter.get(), &DocSetCollector)
+                        .map_err(tantivy_err)?;
+                    scores
+                        .into_iter()
+                        .filter(|(_, doc)| allowed.contains(doc))
+                        .collect()
+                }
+                (None, None) => scores,
+            };
 
-            Ok(SearchResult { hits, count })
+            let rankings = [(weights.0, first), (weights.1, second)];
+            let hits = fusion
+                .fuse(&rankings, rrf_k)
+                .into_iter()
+                .take(k)
+                .map(|(score, doc)| {
+                    (Fruit::Score(score), DocAddress::from(&doc))
+                })
+                .collect();
+            Ok(SearchResult::exact(hits, None))
         })
     }
 
-    #[pyo3(signature = (query, agg))]
+    /// Run aggregations over the documents matching a query.
+    ///
+    /// The aggregations use the Elasticsearch syntax. The fields they read
+    /// must be fast fields. Subfields of a fast JSON field are referenced by
+    /// their path, e.g. "attributes.price".
+    ///
+    /// Top level aggregations can also be `filter` and `filters`
+    /// aggregations whose buckets are `Query` objects, for instance
+    /// `{"cheap": {"filter": query, "aggs": {...}}}` or
+    /// `{"by_color": {"filters": {"filters": {"red": red_query, ...}}}}`.
+    /// Each of their buckets is collected by its own search.
+    ///
+    /// The requests may hold `datetime` and `date` values, e.g. the bounds
+    /// of a `range` aggregation over a date field or the `extended_bounds`
+    /// of a `date_histogram`, naive ones being UTC, and `Decimal` values.
+    ///
+    /// Args:
+    ///     query (Query): The query selecting the aggregated documents.
+    ///     agg (dict): The aggregation requests, by name.
+    ///     missing (Dict[str, Union[float, str]], optional): The value the
+    ///         documents without a value of a field are aggregated with, by
+    ///         field name. It applies to the metrics and `terms`
+    ///         aggregations of the field which don't set their own
+    ///         `missing`, the documents are skipped otherwise.
+    ///
+    /// Returns a dict holding the result of each aggregation, by name.
+    ///
+    /// Raises a SchemaError if an aggregation reads a field that doesn't
+    /// exist or isn't a fast field, and a ValueError if the aggregation
+    /// requests are invalid or if the missing value of a field aggregated by
+    /// a metric isn't a number.
+    #[pyo3(signature = (query, agg, missing = None))]
     fn aggregate(
         &self,
         py: Python,
         query: &Query,
         agg: Py<PyDict>,
+        missing: Option<HashMap<String, MissingValue>>,
     ) -> PyResult<Py<PyDict>> {
-        let py_json = py.import_bound("json")?;
-        let agg_query_str = py_json.call_method1("dumps", (agg,))?.to_string();
+        let missing = missing.unwrap_or_default();
+        let plain_aggs = PyDict::new(py);
+        let mut filter_aggs = Vec::new();
+        for (name, request) in agg.bind(py).iter() {
+            let name: String = name.extract()?;
+            match FilterAggregation::parse(&name, &request)? {
+                Some(mut filter_agg) => {
+                    aggregation::set_missing(
+                        &mut filter_agg.sub_aggregations,
+                        &missing,
+                    )?;
+                    self.check_aggregation_fields(
+                        &filter_agg.sub_aggregations,
+                    )?;
+                    filter_aggs.push(filter_agg);
+                }
+                None => plain_aggs.set_item(name, request)?,
+            }
+        }
 
-        let agg_str = py.allow_threads(move || {
-            let agg_collector = AggregationCollector::from_aggs(
-                serde_json::from_str(&agg_query_str).map_err(to_pyerr)?,
-                Default::default(),
-            );
+        let mut aggs = aggregation::parse_requests(plain_aggs.as_any())?;
+        aggregation::set_missing(&mut aggs, &missing)?;
+        self.check_aggregation_fields(&aggs)?;
+        let query = self.restrict(py, query)?;
+
+        let results = py.allow_threads(move || {
+            let agg_collector =
+                AggregationCollector::from_aggs(aggs, Default::default());
             let agg_res = self
                 .inner
                 .search(query.get(), &agg_collector)
-                .map_err(to_pyerr)?;
+                .map_err(tantivy_err)?;
 
-            serde_json::to_string(&agg_res).map_err(to_pyerr)
+            let mut results =
+                serde_json::to_value(&agg_res).map_err(to_pyerr)?;
+            for filter_agg in filter_aggs {
+                results[&filter_agg.name] =
+                    filter_agg.run(&self.inner, query.get())?;
+            }
+            Ok::<_, PyErr>(results)
         })?;
 
-        let agg_dict = py_json.call_method1("loads", (agg_str,))?;
-        let agg_dict = agg_dict.downcast::<PyDict>()?;
+        let results = pythonize::pythonize(py, &results).map_err(to_pyerr)?;
+        Ok(results.downcast_bound::<PyDict>(py)?.clone().unbind())
+    }
 
-        Ok(agg_dict.clone().unbind())
+    /// Run aggregations without blocking the running asyncio event loop.
+    ///
+    /// Takes the same arguments as `aggregate`, the aggregations run in the
+    /// default executor of the loop.
+    ///
+    /// Returns an awaitable of the dict of aggregation results.
+    ///
+    /// Raises a RuntimeError if there is no running event loop.
+    #[pyo3(signature = (query, agg, missing = None))]
+    fn aggregate_async<'py>(
+        &self,
+        py: Python<'py>,
+        query: Query,
+        agg: Py<PyDict>,
+        missing: Option<HashMap<String, MissingValue>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let searcher = self.clone();
+        asyncio::spawn(py, move |py| {
+            searcher.aggregate(py, &query, agg, missing)
+        })
     }
 
-    /// Returns the overall number of documents in the index.
-    #[getter]
-    fn num_docs(&self) -> u64 {
+    /// Count the documents matching a query by interval of a date field.
+    ///
+    /// This wraps a date histogram aggregation. The buckets are aligned on
+    /// the given timezone, with the UTC offset it has at the start of
+    /// `extended_bounds`, or else at the oldest matching document, so daily
+    /// buckets are off by an hour across daylight saving time changes.
+    ///
+    /// Args:
+    ///     query (Query): The query selecting the counted documents.
+    ///     field_name (str): The date field to bucket by. It must be a fast
+    ///         field.
+    ///     interval (str, optional): The fixed size of the buckets, a number
+    ///         followed by a unit among 'ms', 's', 'm', 'h' and 'd'. Defaults
+    ///         to '1d'.
+    ///     timezone (str, optional): The IANA name of the timezone of the
+    ///         buckets. Defaults to 'UTC'.
+    ///     extended_bounds (Tuple[datetime, datetime], optional): A
+    ///         `(min, max)` range that the buckets are extended to, with
+    ///         empty buckets, even if no document falls in it. Naive
+    ///         datetimes are read as UTC, like the values of date fields.
+    ///
+    /// Returns a list of `(datetime, count)` pairs, one for every interval
+    /// between the first and the last bucket, oldest first. The datetimes
+    /// are the naive start times of the buckets in the timezone.
+    ///
+    /// Raises a SchemaError if the field isn't a fast field, and a
+    /// ValueError if the interval is invalid.
+    #[pyo3(signature = (query, field_name, interval = "1d", timezone = "UTC", extended_bounds = None))]
+    fn date_histogram(
+        &self,
+        py: Python,
+        query: &Query,
+        field_name: &str,
+        interval: &str,
+        timezone: &str,
+        extended_bounds: Option<(Bound<PyAny>, Bound<PyAny>)>,
+    ) -> PyResult<Vec<(PyObject, u64)>> {
+        let extended_bounds = match extended_bounds {
+            Some((min, max)) => {
+                Some((datetime_millis(&min)?, datetime_millis(&max)?))
+            }
+            None => None,
+        };
+
+        let datetime = py.import("datetime")?;
+        let tz = match timezone {
+            "UTC" => datetime.getattr("timezone")?.getattr("utc")?,
+            _ => py
+                .import("zoneinfo")?
+                .getattr("ZoneInfo")?
+                .call1((timezone,))?,
+        };
+        let from_timestamp =
+            datetime.getattr("datetime")?.getattr("fromtimestamp")?;
+        let reference_secs = match extended_bounds {
+            Some((min, _)) => Some(min as f64 / 1000.0),
+            None => self.first_date(py, query, field_name)?,
+        }
+        .unwrap_or_else(|| chrono::Utc::now().timestamp() as f64);
+        let utc_offset_secs: f64 = from_timestamp
+            .call1((reference_secs, &tz))?
+            .call_method0("utcoffset")?
+            .call_method0("total_seconds")?
+            .extract()?;
+        // Buckets start at local midnight, which is before midnight UTC east
+        // of Greenwich.
+        let offset_ms = -(utc_offset_secs * 1000.0) as i64;
+        let offset = if offset_ms < 0 {
+            format!("-{}ms", -offset_ms)
+        } else {
+            format!("{offset_ms}ms")
+        };
+
+        let mut request = serde_json::json!({
+            "date_histogram": {
+                "field": field_name,
+                "fixed_interval": interval,
+                "offset": offset,
+            }
+        });
+        if let Some((min, max)) = extended_bounds {
+            request["date_histogram"]["extended_bounds"] =
+                serde_json::json!({ "min": min, "max": max });
+        }
+        let aggs = aggregation::single(request)?;
+        self.check_aggregation_fields(&aggs)?;
+        let query = self.restrict(py, query)?;
+
+        let buckets = py.allow_threads(|| {
+            aggregation::run_single(&self.inner, query.get(), aggs)
+                .map(aggregation::histogram_buckets)
+                .map_err(tantivy_err)
+        })?;
+
+        let naive = PyDict::new(py);
+        naive.set_item("tzinfo", py.None())?;
+        buckets
+            .into_iter()
+            .map(|(key_ms, count)| {
+                let start = from_timestamp
+                    .call1((key_ms / 1000.0, &tz))?
+                    .call_method("replace", (), Some(&naive))?;
+                Ok((start.unbind(), count))
+            })
+            .collect()
+    }
+
+    /// Find the most frequent values of a field among the documents matching
+    /// a query, with metrics computed over the documents of each value.
+    ///
+    /// This wraps a terms aggregation and its metric sub-aggregations.
+    ///
+    /// Args:
+    ///     query (Query): The query selecting the documents.
+    ///     field_name (str): The field whose values are counted. It must be
+    ///         a fast field, text fields should use the 'raw' tokenizer.
+    ///     k (int, optional): The maximum number of values. Defaults to 10.
+    ///     metrics (Dict[str, Tuple[str, str]], optional): The metrics to
+    ///         compute for each value, by name. A metric is an
+    ///         `(op, field_name)` tuple, `op` being one of 'avg', 'min',
+    ///         'max', 'sum' and 'count'.
+    ///
+    /// Returns a list of `(value, count, metrics)` tuples, the most frequent
+    /// value first. `metrics` is a dict holding the value of each metric by
+    /// name, None if no document of the bucket has a value.
+    ///
+    /// Raises a SchemaError if a field isn't a fast field, and a ValueError
+    /// if a metric is invalid.
+    #[pyo3(signature = (query, field_name, k = 10, metrics = None))]
+    #[allow(clippy::type_complexity)]
+    fn top_terms(
+        &self,
+        py: Python,
+        query: &Query,
+        field_name: &str,
+        k: u32,
+        metrics: Option<HashMap<String, (String, String)>>,
+    ) -> PyResult<Vec<(PyObject, u64, HashMap<String, Option<f64>>)>> {
+        let metrics = metrics.unwrap_or_default();
+        let sub_aggregations = metrics
+            .iter()
+            .map(|(name, (op, field))| {
+                Ok((name.clone(), aggregation::metric(op, field)?))
+            })
+            .collect::<PyResult<serde_json::Map<_, _>>>()?;
+        let aggs = aggregation::single(serde_json::json!({
+            "terms": { "field": field_name, "size": k },
+            "aggs": sub_aggregations,
+        }))?;
+        self.check_aggregation_fields(&aggs)?;
+        let query = self.restrict(py, query)?;
+
+        let buckets = py.allow_threads(|| {
+            aggregation::run_single(&self.inner, query.get(), aggs)
+                .map(aggregation::term_buckets)
+                .map_err(tantivy_err)
+        })?;
+        buckets
+            .into_iter()
+            .map(|(key, count, mut sub_aggregations)| {
+                let key = match key {
+                    Key::Str(term) => term.into_py_any(py)?,
+                    Key::F64(value) => value.into_py_any(py)?,
+                };
+                let values = metrics
+                    .keys()
+                    .map(|name| {
+                        let result = sub_aggregations.0.remove(name);
+                        (name.clone(), aggregation::metric_value(result))
+                    })
+                    .collect();
+                Ok((key, count, values))
+            })
+            .collect()
+    }
+
+    /// Count the documents matching a query by facet.
+    ///
+    /// Args:
+    ///     query (Query): The query selecting the documents.
+    ///     field_name (str): The facet field to count.
+    ///     facets (List[Facet], optional): The facets whose descendants are
+    ///         counted, at any depth. Defaults to the root facet, as does an
+    ///         empty list.
+    ///
+    /// The segments are counted in parallel, on a thread pool with one
+    /// thread per core shared by the faceted searches.
+    ///
+    /// Returns a FacetCounts object.
+    ///
+    /// Raises a SchemaError if the field isn't a facet field.
+    #[pyo3(signature = (query, field_name, facets = None))]
+    fn facet_counts(
+        &self,
+        py: Python,
+        query: &Query,
+        field_name: &str,
+        facets: Option<Vec<Facet>>,
+    ) -> PyResult<FacetCounts> {
+        let collector =
+            self.facet_collector(field_name, facets.unwrap_or_default())?;
+        let query = self.restrict(py, query)?;
+
+        let counts = py
+            .allow_threads(|| {
+                facet::search_in_parallel(&self.inner, query.get(), &collector)
+            })
+            .map_err(tantivy_err)?;
+        Ok(FacetCounts { counts })
+    }
+
+    /// Estimate the number of documents matching a query from a sample of
+    /// them, e.g. to display "about 1,200,000 results" without counting
+    /// every match.
+    ///
+    /// Blocks of consecutive documents spread over the index are counted
+    /// until the estimated relative standard error of the count is small
+    /// enough. Rare matches need more blocks, up to all of them.
+    ///
+    /// Args:
+    ///     query (Query): The query selecting the documents.
+    ///     max_error (float, optional): The relative standard error the
+    ///         estimate stops at, 0 for an exact count. Defaults to 0.01.
+    ///
+    /// Returns a dict with the estimated `count`, whether it is `exact`, its
+    /// estimated `relative_error` and the number of `sampled_docs`.
+    ///
+    /// Raises a ValueError if `max_error` isn't between 0 and 1.
+    #[pyo3(signature = (query, max_error = 0.01))]
+    fn approx_count(
+        &self,
+        py: Python,
+        query: &Query,
+        max_error: f64,
+    ) -> PyResult<PyObject> {
+        if !(0.0..1.0).contains(&max_error) {
+            return Err(PyValueError::new_err(
+                "max_error must be between 0 and 1.",
+            ));
+        }
+        let query = self.restrict(py, query)?;
+        let estimate = py
+            .allow_threads(|| {
+                approx_count::estimate(&self.inner, query.get(), max_error)
+            })
+            .map_err(tantivy_err)?;
+        pythonize::pythonize(py, &estimate).map_err(to_pyerr)
+    }
+
+    /// Run the collectors of a MultiCollector in a single pass over the
+    /// documents matching a query.
+    ///
+    /// Args:
+    ///     query (Query): The query selecting the documents.
+    ///     collector (MultiCollector): The collectors, by name.
+    ///
+    /// Returns a dict mapping the name of every collector to its fruit, see
+    /// the `add_*` methods of MultiCollector.
+    ///
+    /// Raises a SchemaError if a facet collector reads a field that isn't a
+    /// facet field, or a histogram or stats collector a field that isn't a
+    /// fast field, and a ValueError if a limit or offset exceeds those of
+    /// the searcher.
+    fn collect(
+        &self,
+        py: Python,
+        query: &Query,
+        collector: &collectors::MultiCollector,
+    ) -> PyResult<Py<PyDict>> {
+        collector.collect(py, self, query)
+    }
+
+    /// Find the terms of a text field appearing in the most documents, e.g.
+    /// to spot junk tokens.
+    ///
+    /// The document frequencies are summed over the segments. Deleted
+    /// documents are counted until their segment is merged. On a searcher
+    /// restricted by `with_filter`, only the live documents matching the
+    /// filters are counted, which reads the postings of every term.
+    ///
+    /// Args:
+    ///     field_name (str): The indexed text field whose terms are counted.
+    ///     k (int, optional): The maximum number of terms. Defaults to 10.
+    ///
+    /// Returns a list of `(term, doc_freq)` tuples, the most frequent term
+    /// first.
+    ///
+    /// Raises a SchemaError if the field isn't an indexed text field.
+    #[pyo3(signature = (field_name, k = 10))]
+    fn top_terms_by_doc_freq(
+        &self,
+        py: Python,
+        field_name: &str,
+        k: usize,
+    ) -> PyResult<Vec<(String, u64)>> {
+        let schema = self.inner.schema();
+        let field = get_field(schema, field_name)?;
+        let field_entry = schema.get_field_entry(field);
+        if field_entry.field_type().value_type() != tv::schema::Type::Str
+            || !field_entry.is_indexed()
+        {
+            return Err(schema_err(
+                format!("Field `{field_name}` is not an indexed text field."),
+                field_name,
+            ));
+        }
+
+        let filter_docs = self.filter_docs(py, None)?;
+        let mut doc_freqs: HashMap<Vec<u8>, u64> = HashMap::new();
+        py.allow_threads(|| {
+            for (segment_ord, segment_reader) in
+                self.inner.segment_readers().iter().enumerate()
+            {
+                let docs = filter_docs
+                    .as_ref()
+                    .map(|segments| &segments[&(segment_ord as u32)]);
+                let inverted_index = segment_reader.inverted_index(field)?;
+                let mut stream = inverted_index.terms().stream()?;
+                while stream.advance() {
+                    let term_info = stream.value();
+                    let doc_freq = match docs {
+                        None => term_info.doc_freq as u64,
+                        Some(docs) => {
+                            let mut postings = inverted_index
+                                .read_postings_from_terminfo(
+                                    term_info,
+                                    IndexRecordOption::Basic,
+                                )?;
+                            let mut doc_freq = 0;
+                            while postings.doc() != TERMINATED {
+                                if docs.contains(postings.doc()) {
+                                    doc_freq += 1;
+                                }
+                                postings.advance();
+                            }
+                            doc_freq
+                        }
+                    };
+                    if doc_freq > 0 {
+                        *doc_freqs.entry(stream.key().to_vec()).or_default() +=
+                            doc_freq;
+                    }
+                }
+            }
+            tv::Result::Ok(())
+        })
+        .map_err(tantivy_err)?;
+
+        let mut terms: Vec<(Vec<u8>, u64)> = doc_freqs.into_iter().collect();
+        terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        terms.truncate(k);
+        Ok(terms
+            .into_iter()
+            .map(|(term, doc_freq)| {
+                (String::from_utf8_lossy(&term).into_owned(), doc_freq)
+            })
+            .collect())
+    }
+
+    /// Returns the overall number of documents in the index.
+    ///
+    /// The filters of `with_filter` don't apply: this is the number of
+    /// documents the scores of the hits are computed from.
+    #[getter]
+    fn num_docs(&self) -> u64 {
         self.inner.num_docs()
     }
 
@@ -276,8 +1980,19 @@ impl Searcher {
         self.inner.segment_readers().len()
     }
 
+    /// The generation of the searcher, incremented by the index reader on
+    /// every reload. A searcher older than `Index.reader_generation` holds
+    /// on to the files of replaced segments.
+    #[getter]
+    fn generation(&self) -> u64 {
+        self.inner.generation().generation_id()
+    }
+
     /// Return the overall number of documents containing
     /// the given term.
+    ///
+    /// The filters of `with_filter` don't apply: this is the document
+    /// frequency the scores of the hits are computed from.
     #[pyo3(signature = (field_name, field_value))]
     fn doc_freq(
         &self,
@@ -287,7 +2002,7 @@ impl Searcher {
         // Wrap the tantivy Searcher `doc_freq` method to return a PyResult.
         let schema = self.inner.schema();
         let term = crate::make_term(schema, field_name, field_value)?;
-        self.inner.doc_freq(&term).map_err(to_pyerr)
+        self.inner.doc_freq(&term).map_err(tantivy_err)
     }
 
     /// Fetches a document from Tantivy's store given a DocAddress.
@@ -296,16 +2011,486 @@ impl Searcher {
     ///     doc_address (DocAddress): The DocAddress that is associated with
     ///         the document that we wish to fetch.
     ///
-    /// Returns the Document, raises ValueError if the document can't be found.
-    fn doc(&self, doc_address: &DocAddress) -> PyResult<Document> {
+    /// Returns the Document, raises ValueError if the document can't be found
+    /// or doesn't match the filters of the searcher.
+    fn doc(&self, py: Python, doc_address: &DocAddress) -> PyResult<Document> {
+        self.check_visible(py, std::slice::from_ref(doc_address))?;
         let doc: TantivyDocument =
-            self.inner.doc(doc_address.into()).map_err(to_pyerr)?;
+            self.inner.doc(doc_address.into()).map_err(tantivy_err)?;
         let named_doc = doc.to_named_doc(self.inner.schema());
         Ok(crate::document::Document {
             field_values: named_doc.0,
         })
     }
 
+    /// Read the values of a fast field for a document, without fetching the
+    /// stored document.
+    ///
+    /// Args:
+    ///     doc_address (DocAddress): The address of the document.
+    ///     field_name (str): The fast field to read.
+    ///
+    /// Returns the list of the values of the field, empty if the document
+    /// has none.
+    ///
+    /// Raises a SchemaError if the field isn't a fast field, and a ValueError
+    /// if the document address is out of range or doesn't match the filters
+    /// of the searcher.
+    fn fast_field_values(
+        &self,
+        py: Python,
+        doc_address: &DocAddress,
+        field_name: &str,
+    ) -> PyResult<Vec<PyObject>> {
+        let schema = self.inner.schema();
+        let field = get_field(schema, field_name)?;
+        let field_entry = schema.get_field_entry(field);
+        let value_type = field_entry.field_type().value_type();
+        let is_fast =
+            field_entry.is_fast() || value_type == tv::schema::Type::Facet;
+        if !is_fast || value_type == tv::schema::Type::Json {
+            return Err(schema_err(
+                format!("Field `{field_name}` is not a fast field."),
+                field_name,
+            ));
+        }
+        self.check_visible(py, std::slice::from_ref(doc_address))?;
+        let segment = segment_of(&self.inner, doc_address)?;
+        let values = fast_field_values(segment, field_entry, doc_address.doc)
+            .map_err(tantivy_err)?;
+        values
+            .iter()
+            .map(|value| crate::document::value_to_py(py, value))
+            .collect()
+    }
+
+    /// The terms of a text field of a document, with their positions.
+    ///
+    /// The terms are reconstructed from the inverted index, so they are the
+    /// tokens produced by the tokenizer of the field, e.g. lowercased, and
+    /// the field doesn't need to be stored. Every term of the field in the
+    /// segment is looked up, this is meant for feature engineering or
+    /// keyword extraction over a few documents rather than for every hit.
+    ///
+    /// Args:
+    ///     doc_address (DocAddress): The address of the document.
+    ///     field_name (str): The indexed text field.
+    ///
+    /// Returns a dict mapping each term to a tuple of its frequency in the
+    /// field and of its positions, which are empty if the field doesn't
+    /// index positions.
+    ///
+    /// Raises a ValueError if the document doesn't exist or doesn't match the
+    /// filters of the searcher, and a SchemaError if the field isn't an
+    /// indexed text field.
+    fn term_vector(
+        &self,
+        py: Python,
+        doc_address: &DocAddress,
+        field_name: &str,
+    ) -> PyResult<HashMap<String, (u32, Vec<u32>)>> {
+        let field = self.text_field(field_name)?;
+        self.check_visible(py, std::slice::from_ref(doc_address))?;
+        let segment = segment_of(&self.inner, doc_address)?;
+        let terms = py
+            .allow_threads(|| term_vector(segment, field, doc_address.doc))
+            .map_err(tantivy_err)?;
+        Ok(terms
+            .into_iter()
+            .map(|(term, freq, positions)| (term, (freq, positions)))
+            .collect())
+    }
+
+    /// The terms best distinguishing a document, or the documents matching
+    /// a query, from the rest of the index.
+    ///
+    /// For a document, the terms are scored by TF-IDF: their frequency in
+    /// the document times their BM25 inverse document frequency. For a
+    /// query, the terms are scored by how much more often the matching
+    /// documents hold them than the whole index, with the JLH heuristic of
+    /// Elasticsearch, which is useful for auto-tagging or related searches.
+    /// The filters of the searcher apply to the document and to the matching
+    /// documents, not to the frequencies of the terms in the index, which
+    /// are those the scores of the hits are computed from.
+    ///
+    /// Args:
+    ///     doc_address_or_query (Union[DocAddress, Query]): The document, or
+    ///         the query matching the documents.
+    ///     field_name (str): The indexed text field the terms are read from.
+    ///     k (int, optional): The number of terms to return. Defaults to 10.
+    ///
+    /// Returns a list of `(term, score)` tuples, best term first.
+    ///
+    /// Raises a ValueError if the document doesn't exist or doesn't match the
+    /// filters of the searcher, a TypeError if the first argument is neither
+    /// a DocAddress nor a Query, and a SchemaError if the field isn't an
+    /// indexed text field.
+    #[pyo3(signature = (doc_address_or_query, field_name, k = 10))]
+    fn significant_terms(
+        &self,
+        py: Python,
+        doc_address_or_query: &Bound<PyAny>,
+        field_name: &str,
+        k: usize,
+    ) -> PyResult<Vec<(String, f64)>> {
+        let field = self.text_field(field_name)?;
+        if let Ok(address) = doc_address_or_query.extract::<DocAddress>() {
+            self.check_visible(py, std::slice::from_ref(&address))?;
+            let address = tv::DocAddress::from(&address);
+            return py
+                .allow_threads(|| {
+                    keywords::document_terms(&self.inner, address, field, k)
+                })
+                .map_err(tantivy_err);
+        }
+        let Ok(query) = doc_address_or_query.extract::<Query>() else {
+            return Err(PyTypeError::new_err(
+                "Expected a DocAddress or a Query.",
+            ));
+        };
+        let query = self.restrict(py, &query)?;
+        py.allow_threads(|| {
+            keywords::query_terms(&self.inner, query.get(), field, k)
+        })
+        .map_err(tantivy_err)
+    }
+
+    /// Count the documents holding a value for a field.
+    ///
+    /// This helps spotting mapping bugs, e.g. a field that is never filled,
+    /// and deciding which optional fields are worth a fast field. The count
+    /// scans the field's fast column, postings or stored values, it is
+    /// meant for diagnostics rather than for every search.
+    ///
+    /// Args:
+    ///     field_name (str): The field to count.
+    ///
+    /// Returns a dict holding the number of live documents with the field in
+    /// `num_docs`, the count of each segment in `segments`, and the fraction
+    /// of all live documents having the field in `density`.
+    ///
+    /// Raises a ValueError if the field doesn't exist.
+    fn field_density(
+        &self,
+        py: Python,
+        field_name: &str,
+    ) -> PyResult<Py<PyDict>> {
+        let schema = self.inner.schema();
+        let field = get_field(schema, field_name)?;
+        let field_entry = schema.get_field_entry(field);
+        let segments = py
+            .allow_threads(|| {
+                self.inner
+                    .segment_readers()
+                    .iter()
+                    .map(|segment| docs_with_field(segment, field, field_entry))
+                    .collect::<tv::Result<Vec<u32>>>()
+            })
+            .map_err(tantivy_err)?;
+
+        let num_docs: u64 = segments.iter().map(|&count| count as u64).sum();
+        let density = match self.inner.num_docs() {
+            0 => 0.0,
+            total => num_docs as f64 / total as f64,
+        };
+        let dict = PyDict::new(py);
+        dict.set_item("num_docs", num_docs)?;
+        dict.set_item("segments", segments)?;
+        dict.set_item("density", density)?;
+        Ok(dict.unbind())
+    }
+
+    /// Statistics of the doc store caches of the searcher's segments.
+    ///
+    /// Useful to tune the `doc_store_cache_num_blocks` option of
+    /// `Index.config_reader`: many misses mean the cache is too small for
+    /// the documents fetched by the searches.
+    ///
+    /// Returns a dict with the number of cached blocks in `num_entries`, and
+    /// the number of `cache_hits` and `cache_misses` since the reader was
+    /// last reloaded, as searchers of the same reload share their caches.
+    fn doc_store_cache_stats(&self) -> HashMap<&'static str, usize> {
+        let stats = self.inner.doc_store_cache_stats();
+        HashMap::from([
+            ("num_entries", stats.num_entries),
+            ("cache_hits", stats.cache_hits),
+            ("cache_misses", stats.cache_misses),
+        ])
+    }
+
+    /// Compute learning to rank features of documents for a query.
+    ///
+    /// All the features of all the documents are computed in a single pass
+    /// over the segments, rather than with a search per document.
+    ///
+    /// Args:
+    ///     query (Query): The query the features are computed for.
+    ///     doc_addresses (List[DocAddress]): The documents, e.g. the hits of
+    ///         a search of the query.
+    ///     features (List[str]): The features, among:
+    ///         - 'score': the score of the query, 0 if it doesn't match.
+    ///         - 'bm25:<field>': the BM25 score of the terms of the query in
+    ///           a text field.
+    ///         - 'term_matches:<field>': the number of distinct terms of the
+    ///           query in a text field of the document.
+    ///         - 'field_length:<field>': the number of tokens of a text field.
+    ///         - 'value:<field>': the first value of a numeric, boolean or
+    ///           date fast field, NaN if the document has none.
+    ///         - 'recency:<field>': the days elapsed since the first value of
+    ///           a date fast field, NaN if the document has none.
+    ///
+    /// Returns a `FeatureMatrix` with one row per document, which
+    /// `numpy.asarray` reads without copying.
+    ///
+    /// Raises a ValueError if a feature or a document address is invalid, or
+    /// if a document doesn't match the filters of the searcher, and a
+    /// SchemaError if the field of a feature doesn't support it.
+    fn extract_features(
+        &self,
+        py: Python,
+        query: &Query,
+        doc_addresses: Vec<DocAddress>,
+        features: Vec<String>,
+    ) -> PyResult<FeatureMatrix> {
+        self.check_visible(py, &doc_addresses)?;
+        features::extract_features(
+            py,
+            &self.inner,
+            query.get(),
+            &doc_addresses,
+            features,
+        )
+    }
+
+    /// The documents matching a query, as a compact bitset.
+    ///
+    /// The bitset can be passed as the `pre_filter` of later searches of
+    /// this searcher, e.g. to compute an audience once and apply it cheaply
+    /// to many queries. The filters of the searcher apply.
+    ///
+    /// Args:
+    ///     query (Query): The query the documents must match.
+    ///
+    /// Returns a `Bitset` with one bit per document of each segment.
+    fn matching_bitset(&self, py: Python, query: &Query) -> PyResult<Bitset> {
+        let query = self.restrict(py, query)?;
+        py.allow_threads(|| Bitset::collect(&self.inner, query.get()))
+            .map_err(tantivy_err)
+    }
+
+    /// Cache the documents matching the filters of the searches.
+    ///
+    /// The filters added by `with_filter` are then evaluated once per
+    /// segment, and the documents they match are kept in a least recently
+    /// used cache of bitsets, keyed by the filter and the segment. This
+    /// speeds up dashboards repeating the same filters on an index which
+    /// changes slowly, as segments never change once written.
+    ///
+    /// The cache is shared by all the searchers of the index, including the
+    /// ones of later reloads. Calling this method again changes its capacity.
+    ///
+    /// Args:
+    ///     max_entries (int): The maximum number of cached bitsets.
+    ///     max_bytes (int): The maximum memory used by the cached bitsets,
+    ///         one bit per document of their segment.
+    fn enable_query_cache(&self, max_entries: usize, max_bytes: usize) {
+        self.query_cache.enable(max_entries, max_bytes);
+    }
+
+    /// Statistics of the query cache of the index.
+    ///
+    /// Returns a dict with the number of cached bitsets in `num_entries`,
+    /// their memory in `num_bytes`, and the number of `cache_hits` and
+    /// `cache_misses` since the cache was enabled.
+    fn query_cache_stats(&self) -> HashMap<&'static str, usize> {
+        let stats = self.query_cache.stats();
+        HashMap::from([
+            ("num_entries", stats.num_entries),
+            ("num_bytes", stats.num_bytes),
+            ("cache_hits", stats.hits),
+            ("cache_misses", stats.misses),
+        ])
+    }
+
+    /// Returns a searcher restricted to the documents matching a filter.
+    ///
+    /// Every search of the returned searcher is AND-ed with the filter,
+    /// which doesn't change the scores of the hits. This is meant for
+    /// multi-tenant applications, to make sure that a search never returns
+    /// the documents of another tenant. Filters of successive calls add up.
+    ///
+    /// The methods reading documents by address, like `doc`, raise a
+    /// ValueError for the documents not matching the filters. The
+    /// statistics the scores are computed from, `num_docs` and `doc_freq`,
+    /// still count the whole index.
+    ///
+    /// Args:
+    ///     query_or_callable (Union[Query, Callable[[], Query]]): The filter
+    ///         query, or a callable without arguments returning it, which is
+    ///         called on every search, e.g. to read the current tenant from a
+    ///         context variable.
+    ///
+    /// Raises a TypeError if the filter is neither a query nor a callable.
+    fn with_filter(&self, query_or_callable: &Bound<PyAny>) -> PyResult<Self> {
+        let filter = if let Ok(query) = query_or_callable.extract::<Query>() {
+            SearchFilter::Query(query)
+        } else if query_or_callable.is_callable() {
+            SearchFilter::Callable(query_or_callable.clone().unbind())
+        } else {
+            return Err(PyTypeError::new_err(
+                "The filter must be a Query or a callable returning a Query.",
+            ));
+        };
+        Ok(self.with_search_filter(filter))
+    }
+
+    /// Returns a searcher limiting the result window and the complexity of
+    /// the queries of its searches.
+    ///
+    /// This protects servers from requests asking for millions of hits, or
+    /// whose queries are costly to run. The limits replace the ones of this
+    /// searcher, which come from `Index.set_search_limits`.
+    ///
+    /// Args:
+    ///     max_limit (int, optional): The maximum number of hits of a search,
+    ///         the `limit` of `search` and the `k` of `knn_search` and
+    ///         `hybrid_search`. Defaults to no maximum.
+    ///     max_offset (int, optional): The maximum `offset` of `search`.
+    ///         Defaults to no maximum.
+    ///     max_clauses (int, optional): The maximum number of clauses of the
+    ///         boolean queries of a query, nested ones included. Defaults to
+    ///         no maximum.
+    ///     max_expanded_terms (int, optional): The maximum number of terms of
+    ///         the index matched by a fuzzy query of a query. Defaults to no
+    ///         maximum.
+    ///     max_regex_states (int, optional): The maximum number of states of
+    ///         the automaton of a regex query of a query. Defaults to no
+    ///         maximum.
+    ///
+    /// The searches going over a result window limit raise a ValueError, the
+    /// ones going over a complexity limit a QueryTooComplexError.
+    #[pyo3(signature = (max_limit = None, max_offset = None, max_clauses = None, max_expanded_terms = None, max_regex_states = None))]
+    fn with_search_limits(
+        &self,
+        max_limit: Option<usize>,
+        max_offset: Option<usize>,
+        max_clauses: Option<usize>,
+        max_expanded_terms: Option<usize>,
+        max_regex_states: Option<usize>,
+    ) -> Self {
+        Searcher {
+            limits: SearchLimits {
+                max_limit,
+                max_offset,
+                max_clauses,
+                max_expanded_terms,
+                max_regex_states,
+            },
+            ..self.clone()
+        }
+    }
+
+    /// Pin the segments of this searcher for a long-running export.
+    ///
+    /// Commits and merges of the index writer garbage collect the files of
+    /// the segments that are no longer part of the index. A pinned searcher
+    /// keeps the files of its segments until it is released, so that it can
+    /// be iterated across commits. Only the writers of this `Index` object
+    /// honor the pin.
+    ///
+    /// The pin can be used as a context manager, releasing it on exit:
+    ///
+    ///     with searcher.pin() as pinned:
+    ///         for _score, address in pinned.search(query, 1000).hits:
+    ///             export(pinned.doc(address))
+    ///
+    /// Returns a `PinnedSearcher`.
+    fn pin(&self, py: Python) -> PyResult<PinnedSearcher> {
+        Ok(PinnedSearcher {
+            searcher: Some(Py::new(py, self.clone())?),
+            segments: self.segment_metas()?,
+        })
+    }
+
+    /// Iterate over all the documents matching a query in batches, e.g. to
+    /// export them.
+    ///
+    /// Unlike paging with `search` and an offset, every batch continues
+    /// where the previous one stopped, and all the batches see the documents
+    /// of this searcher, even after later commits.
+    ///
+    /// Args:
+    ///     query (Query): The query selecting the documents.
+    ///     batch_size (int, optional): The maximum number of hits of a
+    ///         batch. Defaults to 1000.
+    ///     keep_segments (bool, optional): Keep the files of the segments of
+    ///         this searcher from garbage collection until the scroll is
+    ///         exhausted, as `pin` does. Defaults to true.
+    ///
+    /// Returns a `Scroll` iterating over lists of `(score, DocAddress)`
+    /// tuples, in the order of the documents.
+    ///
+    /// Raises a ValueError if the batch size is 0.
+    #[pyo3(signature = (query, batch_size = 1000, keep_segments = true))]
+    fn scroll(
+        &self,
+        py: Python,
+        query: &Query,
+        batch_size: usize,
+        keep_segments: bool,
+    ) -> PyResult<Scroll> {
+        if batch_size == 0 {
+            return Err(PyValueError::new_err(
+                "The batch size must be at least 1.",
+            ));
+        }
+        let segments = if keep_segments {
+            self.segment_metas()?
+        } else {
+            Vec::new()
+        };
+        let query = self.restrict(py, query)?;
+        Scroll::new(
+            Py::new(py, self.clone())?,
+            self.inner.clone(),
+            query.get(),
+            batch_size,
+            segments,
+        )
+    }
+
+    /// Start a session fetching stored documents of this searcher, e.g. the
+    /// hits of a result page.
+    ///
+    /// The session keeps the last decompressed blocks of the doc store of
+    /// every segment across its fetches, instead of decompressing a block
+    /// for every document as `doc` does.
+    ///
+    /// Args:
+    ///     cache_blocks (int, optional): The number of decompressed blocks
+    ///         kept per segment. Defaults to 8.
+    ///
+    /// Returns a `FetchSession`.
+    ///
+    /// Raises a ValueError if `cache_blocks` is 0.
+    #[pyo3(signature = (cache_blocks = 8))]
+    fn fetch_session(
+        &self,
+        py: Python,
+        cache_blocks: usize,
+    ) -> PyResult<FetchSession> {
+        if cache_blocks == 0 {
+            return Err(PyValueError::new_err(
+                "A fetch session must cache at least 1 block.",
+            ));
+        }
+        Ok(FetchSession::new(
+            Py::new(py, self.clone())?,
+            self.inner.clone(),
+            cache_blocks,
+        ))
+    }
+
     fn __repr__(&self) -> PyResult<String> {
         Ok(format!(
             "Searcher(num_docs={}, num_segments={})",
@@ -315,14 +2500,577 @@ impl Searcher {
     }
 }
 
+impl Searcher {
+    /// A searcher also restricted to the documents of a filter.
+    pub(crate) fn with_search_filter(&self, filter: SearchFilter) -> Self {
+        let mut filters = self.filters.clone();
+        filters.push(filter);
+        Searcher {
+            inner: self.inner.clone(),
+            extensions: self.extensions.clone(),
+            filters,
+            limits: self.limits,
+            active: self.active.clone(),
+            query_cache: self.query_cache.clone(),
+            signals: self.signals.clone(),
+        }
+    }
+
+    /// The query matching the documents of all the filters, if any.
+    fn filter_query(&self, py: Python) -> PyResult<Option<Query>> {
+        let filters = self
+            .filters
+            .iter()
+            .map(|filter| match filter {
+                SearchFilter::Query(query) => {
+                    Ok(self.query_cache.wrap(query.inner.box_clone()))
+                }
+                SearchFilter::Callable(callable) => {
+                    let query: Query = callable.call0(py)?.extract(py)?;
+                    Ok(self.query_cache.wrap(query.inner))
+                }
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(match filters.len() {
+            0 => None,
+            1 => filters.into_iter().next().map(|inner| Query { inner }),
+            _ => Some(must_match_all(filters)),
+        })
+    }
+
+    /// The metas of the segments of the searcher.
+    ///
+    /// Loading the metas registers them in the segment inventory of the
+    /// index, which the garbage collection reads the living files from, so
+    /// the files are kept as long as the metas live.
+    fn segment_metas(&self) -> PyResult<Vec<tv::SegmentMeta>> {
+        let segment_ids: HashSet<tv::SegmentId> = self
+            .inner
+            .segment_readers()
+            .iter()
+            .map(|segment| segment.segment_id())
+            .collect();
+        Ok(self
+            .inner
+            .index()
+            .searchable_segment_metas()
+            .map_err(tantivy_err)?
+            .into_iter()
+            .filter(|meta| segment_ids.contains(&meta.id()))
+            .collect())
+    }
+
+    /// Restrict a query to the documents of the filters, keeping its scores.
+    pub(crate) fn restrict(
+        &self,
+        py: Python,
+        query: &Query,
+    ) -> PyResult<Query> {
+        self.limits.check_query(&self.inner, query.inner.as_ref())?;
+        Ok(match self.filter_query(py)? {
+            Some(filter) => Query {
+                inner: query::filtered(query.inner.box_clone(), filter.inner),
+            },
+            None => query.clone(),
+        })
+    }
+
+    /// The documents of each segment matching the filters, deleted ones
+    /// excluded, None if the searcher has no filters. `segment_ords` selects
+    /// the segments to read, all of them if None.
+    fn filter_docs(
+        &self,
+        py: Python,
+        segment_ords: Option<&HashSet<u32>>,
+    ) -> PyResult<Option<HashMap<u32, DocBitSet>>> {
+        let Some(filter) = self.filter_query(py)? else {
+            return Ok(None);
+        };
+        py.allow_threads(|| {
+            let weight = filter
+                .get()
+                .weight(EnableScoring::disabled_from_searcher(&self.inner))?;
+            let mut segments = HashMap::new();
+            for (segment_ord, reader) in
+                self.inner.segment_readers().iter().enumerate()
+            {
+                let segment_ord = segment_ord as u32;
+                if segment_ords.is_some_and(|ords| !ords.contains(&segment_ord))
+                {
+                    continue;
+                }
+                let mut docs = DocBitSet::collect(weight.as_ref(), reader)?;
+                if let Some(alive) = reader.alive_bitset() {
+                    docs.retain(|doc| alive.is_alive(doc));
+                }
+                segments.insert(segment_ord, docs);
+            }
+            tv::Result::Ok(Some(segments))
+        })
+        .map_err(tantivy_err)
+    }
+
+    /// Check that documents exist and match the filters of the searcher,
+    /// before reading them by address.
+    ///
+    /// Raises a ValueError otherwise.
+    pub(crate) fn check_visible(
+        &self,
+        py: Python,
+        doc_addresses: &[DocAddress],
+    ) -> PyResult<()> {
+        for doc_address in doc_addresses {
+            segment_of(&self.inner, doc_address)?;
+        }
+        let segment_ords = doc_addresses
+            .iter()
+            .map(|doc_address| doc_address.segment_ord)
+            .collect();
+        let Some(segments) = self.filter_docs(py, Some(&segment_ords))? else {
+            return Ok(());
+        };
+        match doc_addresses.iter().find(|doc_address| {
+            !segments[&doc_address.segment_ord].contains(doc_address.doc)
+        }) {
+            Some(doc_address) => Err(PyValueError::new_err(format!(
+                "No document {} in segment {} matching the filters of the \
+                 searcher.",
+                doc_address.doc, doc_address.segment_ord
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that the fields read by aggregations are fast fields, as
+    /// tantivy returns empty results for the other ones.
+    pub(crate) fn check_aggregation_fields(
+        &self,
+        aggs: &Aggregations,
+    ) -> PyResult<()> {
+        let schema = self.inner.schema();
+        for path in get_fast_field_names(aggs) {
+            let Some((field, json_path)) = schema.find_field(&path) else {
+                return Err(schema_err(
+                    format!("Aggregated field `{path}` does not exist."),
+                    &path,
+                ));
+            };
+            let field_entry = schema.get_field_entry(field);
+            let field_name = field_entry.name();
+            let is_json =
+                field_entry.field_type().value_type() == tv::schema::Type::Json;
+            if !json_path.is_empty() && !is_json {
+                return Err(schema_err(
+                    format!(
+                        "Aggregated field `{path}` does not exist, only JSON \
+                         fields have subfields."
+                    ),
+                    field_name,
+                ));
+            }
+            if !field_entry.is_fast() {
+                return Err(schema_err(
+                    format!(
+                        "Aggregated field `{path}` must be a fast field, \
+                         declare `{field_name}` with fast=True."
+                    ),
+                    field_name,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The oldest value of a date field among the documents matching a
+    /// query, in seconds since the epoch.
+    fn first_date(
+        &self,
+        py: Python,
+        query: &Query,
+        field_name: &str,
+    ) -> PyResult<Option<f64>> {
+        let aggs = aggregation::single(
+            serde_json::json!({ "min": { "field": field_name } }),
+        )?;
+        self.check_aggregation_fields(&aggs)?;
+        let query = self.restrict(py, query)?;
+        let min_nanos = py.allow_threads(|| {
+            aggregation::run_single(&self.inner, query.get(), aggs)
+                .map(aggregation::metric_value)
+                .map_err(tantivy_err)
+        })?;
+        Ok(min_nanos.map(|nanos| nanos / 1e9))
+    }
+
+    /// Validate the origin of a distance sort or boost.
+    fn geo_origin(
+        &self,
+        field_name: String,
+        lat: f64,
+        lon: f64,
+    ) -> PyResult<(String, GeoPoint)> {
+        check_geo_field(self.inner.schema(), &self.extensions, &field_name)?;
+        Ok((field_name, GeoPoint::new(lat, lon)?))
+    }
+
+    /// The field of an indexed text field.
+    ///
+    /// Raises a SchemaError if the field isn't one.
+    fn text_field(&self, field_name: &str) -> PyResult<tv::schema::Field> {
+        let schema = self.inner.schema();
+        let field = get_field(schema, field_name)?;
+        let field_entry = schema.get_field_entry(field);
+        let is_text =
+            field_entry.field_type().value_type() == tv::schema::Type::Str;
+        if !is_text || !field_entry.is_indexed() {
+            return Err(schema_err(
+                format!("Field `{field_name}` is not an indexed text field."),
+                field_name,
+            ));
+        }
+        Ok(field)
+    }
+
+    /// The collector counting the descendants of facets of a field, or of
+    /// the root facet if none is given.
+    pub(crate) fn facet_collector(
+        &self,
+        field_name: &str,
+        facets: Vec<Facet>,
+    ) -> PyResult<FacetTreeCollector> {
+        let schema = self.inner.schema();
+        let field = get_field(schema, field_name)?;
+        if schema.get_field_entry(field).field_type().value_type()
+            != tv::schema::Type::Facet
+        {
+            return Err(schema_err(
+                format!("Field `{field_name}` is not a facet field."),
+                field_name,
+            ));
+        }
+        let facets = if facets.is_empty() {
+            vec![tv::schema::Facet::root()]
+        } else {
+            facets.into_iter().map(|facet| facet.inner).collect()
+        };
+        Ok(FacetTreeCollector {
+            field_name: field_name.to_string(),
+            facets,
+        })
+    }
+
+    /// Check that the results can be diversified by the values of a field.
+    fn check_diversify_field(&self, field_name: &str) -> PyResult<()> {
+        let schema = self.inner.schema();
+        let field_entry =
+            schema.get_field_entry(get_field(schema, field_name)?);
+        let value_type = field_entry.field_type().value_type();
+        if !field_entry.is_fast() || value_type == tv::schema::Type::Json {
+            return Err(schema_err(
+                format!(
+                    "Field `{field_name}` must be a fast field to diversify \
+                     the results by its values."
+                ),
+                field_name,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Build the collector of a nearest neighbors search on a vector field.
+    fn knn_collector(
+        &self,
+        field_name: &str,
+        query_vector: Vec<f32>,
+        k: usize,
+    ) -> PyResult<KnnCollector> {
+        let Some(options) = self.extensions.vector_fields.get(field_name)
+        else {
+            return Err(schema_err(
+                format!("Field `{field_name}` is not a vector field."),
+                field_name,
+            ));
+        };
+        if query_vector.len() != options.dim {
+            return Err(schema_err(
+                format!(
+                    "Field `{field_name}` holds vectors of dimension {}, got \
+                     a query vector of dimension {}.",
+                    options.dim,
+                    query_vector.len()
+                ),
+                field_name,
+            ));
+        }
+
+        Ok(KnnCollector {
+            field: field_name.to_string(),
+            query: query_vector,
+            metric: options.metric,
+            k,
+        })
+    }
+
+    /// Run a nearest neighbors search, returning the ranks of the hits.
+    fn knn_hits(
+        &self,
+        collector: &KnnCollector,
+        filter_query: Option<&Query>,
+    ) -> PyResult<Vec<(f32, tv::DocAddress)>> {
+        match filter_query {
+            Some(query) => self.inner.search(query.get(), collector),
+            None => self.inner.search(&tv::query::AllQuery, collector),
+        }
+        .map_err(tantivy_err)
+    }
+}
+
+/// The milliseconds since the epoch of a datetime, naive ones being UTC.
+fn datetime_millis(value: &Bound<PyAny>) -> PyResult<i64> {
+    Ok(aggregation::utc_datetime(value)?.timestamp_millis())
+}
+
+/// The segment of a document.
+///
+/// Raises a ValueError if the searcher has no such document.
+pub(crate) fn segment_of<'a>(
+    searcher: &'a tv::Searcher,
+    doc_address: &DocAddress,
+) -> PyResult<&'a tv::SegmentReader> {
+    searcher
+        .segment_readers()
+        .get(doc_address.segment_ord as usize)
+        .filter(|segment| doc_address.doc < segment.max_doc())
+        .ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "No document {} in segment {}.",
+                doc_address.doc, doc_address.segment_ord
+            ))
+        })
+}
+
+/// The values of a fast field for a document, in column order.
+pub(crate) fn fast_field_values(
+    segment: &tv::SegmentReader,
+    field_entry: &tv::schema::FieldEntry,
+    doc: tv::DocId,
+) -> tv::Result<Vec<OwnedValue>> {
+    fn column_values<T>(
+        segment: &tv::SegmentReader,
+        field_name: &str,
+        doc: tv::DocId,
+        to_value: impl Fn(T) -> OwnedValue,
+    ) -> tv::Result<Vec<OwnedValue>>
+    where
+        T: tv::columnar::HasAssociatedColumnType,
+        tv::columnar::DynamicColumn: Into<Option<tv::columnar::Column<T>>>,
+    {
+        let column = segment.fast_fields().column_opt::<T>(field_name)?;
+        Ok(column
+            .map(|column| column.values_for_doc(doc).map(to_value).collect())
+            .unwrap_or_default())
+    }
+
+    use tv::schema::Type;
+
+    let name = field_entry.name();
+    match field_entry.field_type().value_type() {
+        Type::U64 => column_values(segment, name, doc, OwnedValue::U64),
+        Type::I64 => column_values(segment, name, doc, OwnedValue::I64),
+        Type::F64 => column_values(segment, name, doc, OwnedValue::F64),
+        Type::Bool => column_values(segment, name, doc, OwnedValue::Bool),
+        Type::Date => column_values(segment, name, doc, OwnedValue::Date),
+        Type::IpAddr => column_values(segment, name, doc, OwnedValue::IpAddr),
+        Type::Str => {
+            let Some(column) = segment.fast_fields().str(name)? else {
+                return Ok(Vec::new());
+            };
+            column
+                .term_ords(doc)
+                .map(|ord| {
+                    let mut text = String::new();
+                    column.ord_to_str(ord, &mut text)?;
+                    Ok(OwnedValue::Str(text))
+                })
+                .collect()
+        }
+        Type::Bytes => {
+            let Some(column) = segment.fast_fields().bytes(name)? else {
+                return Ok(Vec::new());
+            };
+            column
+                .term_ords(doc)
+                .map(|ord| {
+                    let mut bytes = Vec::new();
+                    column.ord_to_bytes(ord, &mut bytes)?;
+                    Ok(OwnedValue::Bytes(bytes))
+                })
+                .collect()
+        }
+        Type::Facet => {
+            let reader = segment.facet_reader(name)?;
+            reader
+                .facet_ords(doc)
+                .map(|ord| {
+                    let mut facet = tv::schema::Facet::root();
+                    reader.facet_from_ord(ord, &mut facet)?;
+                    Ok(OwnedValue::Facet(facet))
+                })
+                .collect()
+        }
+        Type::Json => Ok(Vec::new()),
+    }
+}
+
+/// A query matching the documents matching all the given queries.
+fn must_match_all(queries: Vec<Box<dyn tv::query::Query>>) -> Query {
+    let clauses = queries
+        .into_iter()
+        .map(|query| (tv::query::Occur::Must, query))
+        .collect();
+    Query {
+        inner: Box::new(BooleanQuery::new(clauses)),
+    }
+}
+
+/// The second ranking of a hybrid search.
+#[derive(FromPyObject)]
+enum SecondRanking {
+    Vector(String, Vec<f32>),
+    Scores(Vec<(f32, DocAddress)>),
+}
+
+/// How a hybrid search combines its rankings.
+#[derive(Clone, Copy)]
+enum Fusion {
+    Rrf,
+    Linear,
+}
+
+impl std::str::FromStr for Fusion {
+    type Err = PyErr;
+
+    fn from_str(fusion: &str) -> PyResult<Self> {
+        match fusion {
+            "rrf" => Ok(Fusion::Rrf),
+            "linear" => Ok(Fusion::Linear),
+            _ => Err(PyValueError::new_err(
+                "Invalid fusion method, valid choices are: 'rrf' and 'linear'",
+            )),
+        }
+    }
+}
+
+impl Fusion {
+    /// Combine weighted rankings, each sorted best first, into a single one.
+    fn fuse(
+        self,
+        rankings: &[(f32, Vec<(f32, tv::DocAddress)>)],
+        rrf_k: u32,
+    ) -> Vec<(f32, tv::DocAddress)> {
+        let mut fused: HashMap<tv::DocAddress, f32> = HashMap::new();
+        for (weight, hits) in rankings {
+            let (min, max) = hits.iter().fold(
+                (f32::INFINITY, f32::NEG_INFINITY),
+                |(min, max), (score, _)| (min.min(*score), max.max(*score)),
+            );
+            for (rank, (score, doc)) in hits.iter().enumerate() {
+                let contribution = match self {
+                    Fusion::Rrf => 1.0 / (rrf_k as f32 + rank as f32 + 1.0),
+                    Fusion::Linear if max > min => (score - min) / (max - min),
+                    Fusion::Linear => 1.0,
+                };
+                *fused.entry(*doc).or_default() += weight * contribution;
+            }
+        }
+
+        let mut fused: Vec<(f32, tv::DocAddress)> =
+            fused.into_iter().map(|(doc, score)| (score, doc)).collect();
+        fused.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+        fused
+    }
+}
+
+/// A searcher whose segment files are kept from garbage collection until
+/// it is released, see `Searcher.pin`.
+#[pyclass(module = "tantivy.tantivy")]
+pub(crate) struct PinnedSearcher {
+    searcher: Option<Py<Searcher>>,
+    /// Tracked by the segment inventory of the index as long as they live.
+    segments: Vec<tv::SegmentMeta>,
+}
+
+impl PinnedSearcher {
+    fn get(&self, py: Python) -> PyResult<Py<Searcher>> {
+        self.searcher
+            .as_ref()
+            .map(|searcher| searcher.clone_ref(py))
+            .ok_or_else(|| {
+                PyValueError::new_err("The pinned searcher was released.")
+            })
+    }
+}
+
+#[pymethods]
+impl PinnedSearcher {
+    /// The pinned searcher.
+    ///
+    /// Raises a ValueError if the pin was released.
+    #[getter]
+    fn searcher(&self, py: Python) -> PyResult<Py<Searcher>> {
+        self.get(py)
+    }
+
+    /// Whether the pin was released.
+    #[getter]
+    fn released(&self) -> bool {
+        self.searcher.is_none()
+    }
+
+    /// Release the segments of the searcher, their files can be garbage
+    /// collected by the next commit or merge. Releasing twice is a no-op.
+    fn release(&mut self) {
+        self.searcher = None;
+        self.segments.clear();
+    }
+
+    fn __enter__(&self, py: Python) -> PyResult<Py<Searcher>> {
+        self.get(py)
+    }
+
+    #[pyo3(signature = (_exc_type = None, _exc_value = None, _traceback = None))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<&Bound<PyAny>>,
+        _exc_value: Option<&Bound<PyAny>>,
+        _traceback: Option<&Bound<PyAny>>,
+    ) -> bool {
+        self.release();
+        false
+    }
+
+    fn __repr__(&self) -> String {
+        if self.searcher.is_some() {
+            format!("PinnedSearcher(num_segments={})", self.segments.len())
+        } else {
+            "PinnedSearcher(released)".to_string()
+        }
+    }
+}
+
 /// DocAddress contains all the necessary information to identify a document
 /// given a Searcher object.
 ///
 /// It consists in an id identifying its segment, and its segment-local DocId.
 /// The id used for the segment is actually an ordinal in the list of segment
 /// hold by a Searcher.
+///
+/// Addresses are hashable and ordered by segment, then by document, so they
+/// can be used as dict keys and sorted.
 #[pyclass(frozen, module = "tantivy.tantivy")]
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(
+    Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
 pub(crate) struct DocAddress {
     pub(crate) segment_ord: tv::SegmentOrdinal,
     pub(crate) doc: tv::DocId,
@@ -353,17 +3101,38 @@ impl DocAddress {
         other: &Self,
         op: CompareOp,
         py: Python<'_>,
-    ) -> PyObject {
-        match op {
-            CompareOp::Eq => (self == other).into_py(py),
-            CompareOp::Ne => (self != other).into_py(py),
-            _ => py.NotImplemented(),
-        }
+    ) -> PyResult<PyObject> {
+        op.matches(self.cmp(other)).into_py_any(py)
+    }
+
+    fn __hash__(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
     }
 
     fn __getnewargs__(&self) -> PyResult<(tv::SegmentOrdinal, tv::DocId)> {
         Ok((self.segment_ord, self.doc))
     }
+
+    /// The address packed into a single 64 bits integer, the segment
+    /// ordinal in the high 32 bits and the document in the low 32 bits.
+    ///
+    /// Packed addresses sort like the addresses, and fit in a numpy
+    /// `uint64` array. Like the addresses, they are only meaningful in the
+    /// context of a searcher.
+    fn pack(&self) -> u64 {
+        u64::from(self.segment_ord) << 32 | u64::from(self.doc)
+    }
+
+    /// The address of an integer returned by `pack`.
+    #[staticmethod]
+    fn unpack(packed: u64) -> DocAddress {
+        DocAddress {
+            segment_ord: (packed >> 32) as tv::SegmentOrdinal,
+            doc: packed as tv::DocId,
+        }
+    }
 }
 
 impl From<&tv::DocAddress> for DocAddress {
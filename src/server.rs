@@ -0,0 +1,535 @@
+//! A minimal HTTP/JSON server searching an index from Rust threads, see
+//! `serve`. Built with the `server` cargo feature.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use serde_json::{json, Value};
+use tantivy::{
+    self as tv,
+    aggregation::{agg_req::Aggregations, AggregationCollector},
+    collector::{Count, TopDocs},
+    query::{AllQuery, QueryParser, QueryParserError},
+    schema::Field,
+    DocAddress, Document, TantivyDocument,
+};
+
+use crate::{errors::tantivy_err, field_alias, index::Index};
+
+/// The maximum size of a request body.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+/// The maximum number of request headers.
+const MAX_HEADERS: usize = 100;
+/// How long a connection may stay idle while its request is read.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+/// The maximum number of hits of a search request.
+const MAX_LIMIT: usize = 10_000;
+
+/// A request of a client.
+struct Request {
+    method: String,
+    path: String,
+    params: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+/// A response: its status code and JSON body.
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl ToString) -> Self {
+        Response {
+            status,
+            body: json!({ "error": message.to_string() }),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+impl From<tv::TantivyError> for Response {
+    fn from(err: tv::TantivyError) -> Self {
+        Response::error(500, err)
+    }
+}
+
+/// Decode a percent-encoded query string component.
+fn percent_decode(text: &str) -> String {
+    let hex = |byte: u8| (byte as char).to_digit(16).map(|digit| digit as u8);
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i + 1..i + 3) {
+            Some(&[high, low]) if bytes[i] == b'%' => {
+                hex(high).zip(hex(low)).map(|(high, low)| high << 4 | low)
+            }
+            _ => None,
+        };
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => decoded.push(b' '),
+            (None, byte) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn parse_params(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(name), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Read a request, None if the client closed the connection first.
+fn read_request(
+    reader: &mut BufReader<TcpStream>,
+) -> Result<Option<Request>, Response> {
+    let bad_request = |err: io::Error| Response::error(400, err);
+    let mut line = String::new();
+    if reader.read_line(&mut line).map_err(bad_request)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(Response::error(400, "Invalid request line."));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut content_length = 0;
+    for num_headers in 0.. {
+        let mut header = String::new();
+        reader.read_line(&mut header).map_err(bad_request)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if num_headers == MAX_HEADERS {
+            return Err(Response::error(400, "Too many headers."));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(|_| {
+                    Response::error(400, "Invalid Content-Length.")
+                })?;
+            }
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        return Err(Response::error(413, "The request body is too large."));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(bad_request)?;
+    Ok(Some(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        params: parse_params(query),
+        body,
+    }))
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    response: &Response,
+) -> io::Result<()> {
+    let body = response.body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// The state shared by the threads of a server.
+struct Handler {
+    index: tv::Index,
+    reader: tv::IndexReader,
+    /// The field aliases of the index when the server started.
+    field_aliases: HashMap<String, String>,
+}
+
+impl Handler {
+    fn handle(&self, request: &Request) -> Response {
+        let result = match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/search") => self.search(&request.params),
+            ("GET", "/doc") => self.doc(&request.params),
+            ("POST", "/aggregate") => self.aggregate(&request.body),
+            (_, "/search" | "/doc" | "/aggregate") => {
+                return Response::error(405, "Method not allowed.")
+            }
+            _ => return Response::error(404, "Not found."),
+        };
+        result.unwrap_or_else(|response| response)
+    }
+
+    fn param<T: std::str::FromStr>(
+        params: &HashMap<String, String>,
+        name: &str,
+        default: Option<T>,
+    ) -> Result<T, Response> {
+        match (params.get(name), default) {
+            (Some(value), _) => value.parse().map_err(|_| {
+                Response::error(400, format!("Invalid `{name}` parameter."))
+            }),
+            (None, Some(default)) => Ok(default),
+            (None, None) => Err(Response::error(
+                400,
+                format!("Missing `{name}` parameter."),
+            )),
+        }
+    }
+
+    /// Parse a query in the tantivy query language, searching the given
+    /// comma separated fields by default, or all the indexed fields.
+    fn parse_query(
+        &self,
+        query: &str,
+        fields: Option<&str>,
+    ) -> Result<Box<dyn tv::query::Query>, Response> {
+        let schema = self.index.schema();
+        let default_fields: Vec<Field> = match fields {
+            Some(names) => names
+                .split(',')
+                .map(|name| {
+                    let name = field_alias::resolve(&self.field_aliases, name);
+                    schema.get_field(&name).map_err(|_| {
+                        Response::error(400, format!("Unknown field `{name}`."))
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            None => schema
+                .fields()
+                .filter(|(_, entry)| entry.is_indexed())
+                .map(|(field, _)| field)
+                .collect(),
+        };
+        let ast = tv::query_grammar::parse_query(query).map_err(|_| {
+            Response::error(400, QueryParserError::SyntaxError(query.into()))
+        })?;
+        QueryParser::for_index(&self.index, default_fields)
+            .build_query_from_user_input_ast(field_alias::rewrite(
+                ast,
+                &self.field_aliases,
+            ))
+            .map_err(|err| Response::error(400, err))
+    }
+
+    fn stored_doc(
+        &self,
+        searcher: &tv::Searcher,
+        address: DocAddress,
+    ) -> Result<Value, Response> {
+        let doc: TantivyDocument = searcher.doc(address)?;
+        serde_json::from_str(&doc.to_json(&self.index.schema()))
+            .map_err(|err| Response::error(500, err))
+    }
+
+    /// `GET /search?q=...&limit=10&offset=0&fields=title,body`
+    fn search(
+        &self,
+        params: &HashMap<String, String>,
+    ) -> Result<Response, Response> {
+        let query: String = Self::param(params, "q", None)?;
+        let limit = Self::param(params, "limit", Some(10))?;
+        let offset = Self::param(params, "offset", Some(0))?;
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(Response::error(
+                400,
+                format!("`limit` must be between 1 and {MAX_LIMIT}."),
+            ));
+        }
+        let query =
+            self.parse_query(&query, params.get("fields").map(String::as_str))?;
+        let searcher = self.reader.searcher();
+        let (count, top_docs) = searcher.search(
+            &query,
+            &(Count, TopDocs::with_limit(limit).and_offset(offset)),
+        )?;
+        let hits = top_docs
+            .into_iter()
+            .map(|(score, address)| {
+                Ok(json!({
+                    "score": score,
+                    "segment_ord": address.segment_ord,
+                    "doc": address.doc_id,
+                    "fields": self.stored_doc(&searcher, address)?,
+                }))
+            })
+            .collect::<Result<Vec<_>, Response>>()?;
+        Ok(Response::ok(json!({ "count": count, "hits": hits })))
+    }
+
+    /// `GET /doc?segment_ord=0&doc=1`
+    fn doc(
+        &self,
+        params: &HashMap<String, String>,
+    ) -> Result<Response, Response> {
+        let segment_ord = Self::param(params, "segment_ord", None)?;
+        let doc_id = Self::param(params, "doc", None)?;
+        let searcher = self.reader.searcher();
+        let in_range = searcher
+            .segment_readers()
+            .get(segment_ord as usize)
+            .is_some_and(|segment| doc_id < segment.max_doc());
+        if !in_range {
+            return Err(Response::error(404, "No such document."));
+        }
+        let address = DocAddress::new(segment_ord, doc_id);
+        Ok(Response::ok(self.stored_doc(&searcher, address)?))
+    }
+
+    /// `POST /aggregate` with a `{"aggs": {...}, "q": "..."}` body, the
+    /// aggregations in the Elasticsearch syntax and an optional query.
+    fn aggregate(&self, body: &[u8]) -> Result<Response, Response> {
+        let mut request: Value = serde_json::from_slice(body)
+            .map_err(|err| Response::error(400, err))?;
+        let aggs: Aggregations = serde_json::from_value(request["aggs"].take())
+            .map_err(|err| Response::error(400, err))?;
+        let query = match request["q"].as_str() {
+            Some(query) => {
+                self.parse_query(query, request["fields"].as_str())?
+            }
+            None => Box::new(AllQuery),
+        };
+        let collector =
+            AggregationCollector::from_aggs(aggs, Default::default());
+        let results = self.reader.searcher().search(&query, &collector)?;
+        let results = serde_json::to_value(results)
+            .map_err(|err| Response::error(500, err))?;
+        Ok(Response::ok(results))
+    }
+
+    fn serve_connection(&self, stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let response = match read_request(&mut reader) {
+            Ok(Some(request)) => self.handle(&request),
+            Ok(None) => return Ok(()),
+            Err(response) => response,
+        };
+        let mut stream = stream;
+        write_response(&mut stream, &response)
+    }
+}
+
+/// The threads of a running server.
+struct Running {
+    stopping: Arc<AtomicBool>,
+    acceptor: JoinHandle<()>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+/// A search server started by `serve`. It stops when `stop` is called or
+/// when it is garbage collected.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+pub(crate) struct SearchServer {
+    address: SocketAddr,
+    running: Mutex<Option<Running>>,
+}
+
+impl SearchServer {
+    fn shutdown(&self) {
+        let Some(running) = self.running.lock().unwrap().take() else {
+            return;
+        };
+        running.stopping.store(true, Ordering::SeqCst);
+        // Wake the acceptor up, blocked until the next connection.
+        let mut wake = self.address;
+        if wake.ip().is_unspecified() {
+            wake.set_ip(match wake.ip() {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            });
+        }
+        let _ = TcpStream::connect(wake);
+        let _ = running.acceptor.join();
+        for worker in running.workers {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for SearchServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[pymethods]
+impl SearchServer {
+    /// The address the server listens on.
+    #[getter]
+    fn host(&self) -> String {
+        self.address.ip().to_string()
+    }
+
+    /// The port the server listens on, the one picked by the system if the
+    /// server was started on port 0.
+    #[getter]
+    fn port(&self) -> u16 {
+        self.address.port()
+    }
+
+    /// Whether the server is running.
+    #[getter]
+    fn running(&self) -> bool {
+        self.running.lock().unwrap().is_some()
+    }
+
+    /// Stop accepting connections, and wait for the requests in progress
+    /// to complete.
+    fn stop(&self, py: Python) {
+        py.allow_threads(|| self.shutdown());
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &self,
+        py: Python,
+        _exc_type: PyObject,
+        _exc_value: PyObject,
+        _traceback: PyObject,
+    ) {
+        self.stop(py);
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SearchServer(address='{}')", self.address)
+    }
+}
+
+/// Serve an index over HTTP, from Rust threads.
+///
+/// The server answers these requests with JSON:
+///
+/// - `GET /search?q=...`: the hits of a query in the tantivy query language,
+///   with their score, address and stored fields. The optional `limit` and
+///   `offset` parameters page the hits, 10 by default, and `fields` lists
+///   the comma separated fields searched by default, all the indexed fields
+///   by default.
+/// - `GET /doc?segment_ord=...&doc=...`: the stored fields of a document.
+/// - `POST /aggregate`: the results of the aggregations of the `aggs` member
+///   of the JSON body, in the Elasticsearch syntax, over the documents
+///   matching its optional `q` query.
+///
+/// Errors are answered with a `{"error": ...}` body. The server searches
+/// the last commit of the index, reloaded as commits happen. It has no
+/// authentication: bind it to a private address.
+///
+/// Args:
+///     index (Index): The index to serve.
+///     host (str, optional): The address to listen on. Defaults to
+///         '127.0.0.1'.
+///     port (int, optional): The port to listen on, 0 for a port picked by
+///         the system. Defaults to 8080.
+///     num_threads (int, optional): The number of threads answering the
+///         requests. Defaults to 4.
+///
+/// Returns the running `SearchServer`.
+///
+/// Raises an OSError if the address can't be listened on, and a ValueError
+/// if `num_threads` is 0.
+#[pyfunction]
+#[pyo3(signature = (index, host = "127.0.0.1", port = 8080, num_threads = 4))]
+pub(crate) fn serve(
+    index: &Index,
+    host: &str,
+    port: u16,
+    num_threads: usize,
+) -> PyResult<SearchServer> {
+    if num_threads == 0 {
+        return Err(PyValueError::new_err("num_threads must be at least 1."));
+    }
+    let listener = TcpListener::bind((host, port))?;
+    let address = listener.local_addr()?;
+    let handler = Arc::new(Handler {
+        index: index.index.clone(),
+        reader: index.index.reader().map_err(tantivy_err)?,
+        field_aliases: index.field_aliases.clone(),
+    });
+    let (sender, receiver) = mpsc::channel::<TcpStream>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    let workers = (0..num_threads)
+        .map(|_| {
+            let handler = handler.clone();
+            let receiver = receiver.clone();
+            thread::spawn(move || loop {
+                let Ok(stream) = receiver.lock().unwrap().recv() else {
+                    break;
+                };
+                if let Err(err) = handler.serve_connection(stream) {
+                    log::debug!("search server connection failed: {err}");
+                }
+            })
+        })
+        .collect();
+    let stopping = Arc::new(AtomicBool::new(false));
+    let acceptor_stopping = stopping.clone();
+    // Dropping the sender when the acceptor stops stops the workers.
+    let acceptor = thread::spawn(move || {
+        for stream in listener.incoming() {
+            if acceptor_stopping.load(Ordering::SeqCst) {
+                break;
+            }
+            match stream {
+                Ok(stream) => {
+                    if sender.send(stream).is_err() {
+                        break;
+                    }
+                }
+                Err(err) => log::warn!("search server accept failed: {err}"),
+            }
+        }
+    });
+    Ok(SearchServer {
+        address,
+        running: Mutex::new(Some(Running {
+            stopping,
+            acceptor,
+            workers,
+        })),
+    })
+}
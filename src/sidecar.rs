@@ -0,0 +1,33 @@
+//! Files tantivy-py stores in the index directory, next to the index data.
+//!
+//! Their names start with a dot, which keeps tantivy from treating them as
+//! managed files, so that garbage collection leaves them alone.
+
+use std::path::Path;
+
+use pyo3::prelude::*;
+use tantivy::{self as tv, directory::error::OpenReadError, Directory};
+
+use crate::errors::tantivy_err;
+
+/// Read a sidecar file, returns `None` if it doesn't exist.
+pub(crate) fn read(index: &tv::Index, name: &str) -> PyResult<Option<Vec<u8>>> {
+    match index.directory().atomic_read(Path::new(name)) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(OpenReadError::FileDoesNotExist(_)) => Ok(None),
+        Err(err) => Err(tantivy_err(err)),
+    }
+}
+
+/// Atomically replace the content of a sidecar file.
+pub(crate) fn write(
+    index: &tv::Index,
+    name: &str,
+    bytes: &[u8],
+) -> PyResult<()> {
+    debug_assert!(name.starts_with('.'));
+    index
+        .directory()
+        .atomic_write(Path::new(name), bytes)
+        .map_err(tantivy_err)
+}
@@ -0,0 +1,329 @@
+//! Scoring signals kept next to the index and keyed by a primary key field,
+//! see `Index.signals`.
+//!
+//! The signals change without reindexing the documents: they are read into
+//! per-segment columns at search time, to be used in score expressions.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use pyo3::{
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+    types::PyString,
+};
+use serde::{Deserialize, Serialize};
+use tantivy::{
+    self as tv, columnar::StrColumn, schema::Type, DocId, SegmentId,
+    SegmentReader,
+};
+
+use crate::{errors::schema_err, get_field, sidecar, to_pyerr};
+
+/// Sidecar file holding the signals.
+const SIGNALS_PATH: &str = ".tantivy-py.signals.json";
+
+/// The signals of the documents, by key and by name.
+type Values = HashMap<String, HashMap<String, f64>>;
+
+/// The signal and segment of a column.
+type ColumnKey = (SegmentId, String);
+
+#[derive(Default, Deserialize, Serialize)]
+struct StoredSignals {
+    key_field: Option<String>,
+    values: Values,
+}
+
+/// The signal store of an index, shared by the index and its searchers.
+pub(crate) struct Signals {
+    index: tv::Index,
+    state: RwLock<StoredSignals>,
+    /// The columns of the signals by segment, cleared on every change.
+    columns: Mutex<HashMap<ColumnKey, Arc<Vec<f64>>>>,
+}
+
+impl Signals {
+    /// Read the signals stored in the index, if any.
+    pub(crate) fn load(index: &tv::Index) -> PyResult<Self> {
+        let state = match sidecar::read(index, SIGNALS_PATH)? {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(to_pyerr)?,
+            None => StoredSignals::default(),
+        };
+        Ok(Signals {
+            index: index.clone(),
+            state: RwLock::new(state),
+            columns: Mutex::default(),
+        })
+    }
+
+    /// The key field of the store, None until it is set.
+    pub(crate) fn key_field(&self) -> Option<String> {
+        self.state.read().unwrap().key_field.clone()
+    }
+
+    /// Set the key field of a store without one.
+    ///
+    /// Raises a ValueError if the store is keyed by another field.
+    pub(crate) fn set_key_field(&self, field_name: &str) -> PyResult<()> {
+        match self.key_field() {
+            Some(key_field) if key_field == field_name => Ok(()),
+            Some(key_field) => Err(PyValueError::new_err(format!(
+                "The signals are keyed by `{key_field}`, not `{field_name}`."
+            ))),
+            None => self.update(|state| {
+                state.key_field = Some(field_name.to_string());
+            }),
+        }
+    }
+
+    /// Apply a change to the signals, and store them in the index.
+    fn update(&self, change: impl FnOnce(&mut StoredSignals)) -> PyResult<()> {
+        let mut state = self.state.write().unwrap();
+        change(&mut state);
+        self.columns.lock().unwrap().clear();
+        let bytes = serde_json::to_vec(&*state).map_err(to_pyerr)?;
+        sidecar::write(&self.index, SIGNALS_PATH, &bytes)
+    }
+
+    /// The values of a signal for the documents of a segment, NaN for the
+    /// documents without one. None if the store has no key field or the
+    /// segment no key.
+    pub(crate) fn column(
+        &self,
+        segment: &SegmentReader,
+        name: &str,
+    ) -> Option<Arc<Vec<f64>>> {
+        let cache_key = (segment.segment_id(), name.to_string());
+        if let Some(column) = self.columns.lock().unwrap().get(&cache_key) {
+            return Some(column.clone());
+        }
+        let column = {
+            let state = self.state.read().unwrap();
+            let key_field = state.key_field.as_deref()?;
+            let values: HashMap<&str, f64> = state
+                .values
+                .iter()
+                .filter_map(|(key, signals)| {
+                    Some((key.as_str(), *signals.get(name)?))
+                })
+                .collect();
+            Arc::new(read_column(segment, key_field, &values)?)
+        };
+        self.columns
+            .lock()
+            .unwrap()
+            .insert(cache_key, column.clone());
+        Some(column)
+    }
+}
+
+/// The values of the documents of a segment, by the value of their key.
+fn read_column(
+    segment: &SegmentReader,
+    key_field: &str,
+    values: &HashMap<&str, f64>,
+) -> Option<Vec<f64>> {
+    let schema = segment.schema();
+    let field = schema.get_field(key_field).ok()?;
+    let fast_fields = segment.fast_fields();
+    let docs = 0..segment.max_doc();
+    let column = match schema.get_field_entry(field).field_type().value_type() {
+        Type::Str => {
+            let keys: StrColumn = fast_fields.str(key_field).ok()??;
+            let mut by_ord = HashMap::new();
+            for (key, value) in values {
+                if let Ok(Some(ord)) = keys.dictionary().term_ord(key) {
+                    by_ord.insert(ord, *value);
+                }
+            }
+            docs.map(|doc: DocId| {
+                keys.term_ords(doc)
+                    .next()
+                    .and_then(|ord| by_ord.get(&ord).copied())
+                    .unwrap_or(f64::NAN)
+            })
+            .collect()
+        }
+        Type::I64 => {
+            let keys = fast_fields.column_opt::<i64>(key_field).ok()??;
+            let values = parsed_keys::<i64>(values);
+            docs.map(|doc| {
+                keys.first(doc)
+                    .and_then(|key| values.get(&key).copied())
+                    .unwrap_or(f64::NAN)
+            })
+            .collect()
+        }
+        Type::U64 => {
+            let keys = fast_fields.column_opt::<u64>(key_field).ok()??;
+            let values = parsed_keys::<u64>(values);
+            docs.map(|doc| {
+                keys.first(doc)
+                    .and_then(|key| values.get(&key).copied())
+                    .unwrap_or(f64::NAN)
+            })
+            .collect()
+        }
+        _ => return None,
+    };
+    Some(column)
+}
+
+/// The values by key of an integer key field.
+fn parsed_keys<T: std::str::FromStr + std::hash::Hash + Eq>(
+    values: &HashMap<&str, f64>,
+) -> HashMap<T, f64> {
+    values
+        .iter()
+        .filter_map(|(key, value)| Some((key.parse().ok()?, *value)))
+        .collect()
+}
+
+/// The key of a document, a string or an integer.
+fn extract_key(key: &Bound<PyAny>) -> PyResult<String> {
+    if let Ok(key) = key.downcast::<PyString>() {
+        return Ok(key.to_str()?.to_string());
+    }
+    if let Ok(key) = key.extract::<i64>() {
+        return Ok(key.to_string());
+    }
+    if let Ok(key) = key.extract::<u64>() {
+        return Ok(key.to_string());
+    }
+    Err(PyTypeError::new_err(
+        "Signal keys must be strings or integers.",
+    ))
+}
+
+/// Check that a field can key the signals: a string, integer or unsigned
+/// fast field.
+pub(crate) fn check_key_field(
+    schema: &tv::schema::Schema,
+    field_name: &str,
+) -> PyResult<()> {
+    let field_entry = schema.get_field_entry(get_field(schema, field_name)?);
+    let value_type = field_entry.field_type().value_type();
+    if !field_entry.is_fast()
+        || !matches!(value_type, Type::Str | Type::I64 | Type::U64)
+    {
+        return Err(schema_err(
+            format!(
+                "Field `{field_name}` must be a text, integer or unsigned fast \
+                 field to key the signals."
+            ),
+            field_name,
+        ));
+    }
+    Ok(())
+}
+
+/// Numeric signals of the documents, e.g. click-through rates, stored next
+/// to the index and keyed by the value of a primary key field of the
+/// documents, returned by `Index.signals`.
+///
+/// The signals change without reindexing the documents, and can be used as
+/// variables of the `score_expr` of a search, see `expr_signals` of
+/// `Searcher.search`. The searches see the changes right away, and every
+/// change is stored in the index.
+///
+///     signals = index.signals("id")
+///     signals.set("doc-42", {"ctr": 0.12})
+#[pyclass(frozen, module = "tantivy.tantivy")]
+pub(crate) struct SignalStore {
+    pub(crate) inner: Arc<Signals>,
+}
+
+#[pymethods]
+impl SignalStore {
+    /// The field whose values key the signals.
+    #[getter]
+    fn key_field(&self) -> Option<String> {
+        self.inner.key_field()
+    }
+
+    /// Set signals of a document, keeping its other signals.
+    ///
+    /// Args:
+    ///     key (Union[str, int]): The value of the key field of the document.
+    ///     signals (Dict[str, float]): The values of the signals by name.
+    fn set(
+        &self,
+        key: &Bound<PyAny>,
+        signals: HashMap<String, f64>,
+    ) -> PyResult<()> {
+        let key = extract_key(key)?;
+        self.inner.update(|state| {
+            state.values.entry(key).or_default().extend(signals);
+        })
+    }
+
+    /// Set signals of several documents at once, storing the signals once.
+    ///
+    /// Args:
+    ///     signals (Dict[Union[str, int], Dict[str, float]]): The signals
+    ///         by name of the documents by key.
+    fn set_many(&self, signals: &Bound<PyAny>) -> PyResult<()> {
+        let signals = signals
+            .call_method0("items")?
+            .try_iter()?
+            .map(|item| {
+                let (key, values): (Bound<PyAny>, HashMap<String, f64>) =
+                    item?.extract()?;
+                Ok((extract_key(&key)?, values))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        self.inner.update(|state| {
+            for (key, values) in signals {
+                state.values.entry(key).or_default().extend(values);
+            }
+        })
+    }
+
+    /// Get the signals of a document.
+    ///
+    /// Returns the values of the signals by name, None if the document has
+    /// none.
+    fn get(
+        &self,
+        key: &Bound<PyAny>,
+    ) -> PyResult<Option<HashMap<String, f64>>> {
+        let key = extract_key(key)?;
+        Ok(self.inner.state.read().unwrap().values.get(&key).cloned())
+    }
+
+    /// Remove the signals of a document.
+    ///
+    /// Returns whether the document had signals.
+    fn delete(&self, key: &Bound<PyAny>) -> PyResult<bool> {
+        let key = extract_key(key)?;
+        let mut removed = false;
+        self.inner.update(|state| {
+            removed = state.values.remove(&key).is_some();
+        })?;
+        Ok(removed)
+    }
+
+    /// Read the signals stored in the index again, e.g. after another
+    /// process changed them.
+    fn reload(&self) -> PyResult<()> {
+        let Signals { state, .. } = Signals::load(&self.inner.index)?;
+        *self.inner.state.write().unwrap() = state.into_inner().unwrap();
+        self.inner.columns.lock().unwrap().clear();
+        Ok(())
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.state.read().unwrap().values.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SignalStore(key_field={:?}, num_keys={})",
+            self.key_field(),
+            self.__len__()
+        )
+    }
+}
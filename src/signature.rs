@@ -0,0 +1,336 @@
+//! Content signatures of text fields, computed when documents are added and
+//! compared to find near duplicates, see `Searcher.find_near_duplicates`.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tantivy::{
+    self as tv,
+    collector::{Collector, SegmentCollector},
+    columnar::{BytesColumn, Column},
+    schema::{OwnedValue, Schema},
+    DocAddress, DocId, Score, SegmentOrdinal, SegmentReader, TantivyDocument,
+};
+
+use crate::schema::SchemaExtensions;
+
+/// The number of consecutive tokens hashed together into a feature.
+const SHINGLE_SIZE: usize = 3;
+
+/// How the signature of a text is computed.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SignatureKind {
+    /// 64 bits, the majority vote of the bits of the feature hashes.
+    SimHash,
+    /// The minimum of the feature hashes under `num_hashes` hash functions.
+    MinHash,
+}
+
+impl std::str::FromStr for SignatureKind {
+    type Err = pyo3::PyErr;
+
+    fn from_str(kind: &str) -> pyo3::PyResult<Self> {
+        match kind {
+            "simhash" => Ok(SignatureKind::SimHash),
+            "minhash" => Ok(SignatureKind::MinHash),
+            _ => Err(pyo3::exceptions::PyValueError::new_err(
+                "Invalid signature kind, valid choices are: 'simhash' and \
+                 'minhash'",
+            )),
+        }
+    }
+}
+
+/// Options of a signature field.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct SignatureOptions {
+    /// The text field the signature is computed from.
+    pub(crate) source: String,
+    pub(crate) kind: SignatureKind,
+    /// The number of hash functions of a MinHash signature.
+    pub(crate) num_hashes: usize,
+}
+
+/// The signature of a document.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Signature {
+    SimHash(u64),
+    MinHash(Vec<u32>),
+}
+
+impl Signature {
+    /// The number of differing bits of SimHash signatures, or of differing
+    /// hashes of MinHash signatures. None if the signatures don't compare.
+    pub(crate) fn distance(&self, other: &Signature) -> Option<u32> {
+        match (self, other) {
+            (Signature::SimHash(a), Signature::SimHash(b)) => {
+                Some((a ^ b).count_ones())
+            }
+            (Signature::MinHash(a), Signature::MinHash(b))
+                if a.len() == b.len() =>
+            {
+                Some(a.iter().zip(b).filter(|(x, y)| x != y).count() as u32)
+            }
+            _ => None,
+        }
+    }
+
+    fn encode_minhash(hashes: &[u32]) -> Vec<u8> {
+        hashes.iter().flat_map(|hash| hash.to_le_bytes()).collect()
+    }
+
+    fn decode_minhash(bytes: &[u8]) -> Signature {
+        Signature::MinHash(
+            bytes
+                .chunks_exact(4)
+                .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+                .collect(),
+        )
+    }
+
+    /// The signature of a document, if it has one.
+    pub(crate) fn read(
+        segment: &SegmentReader,
+        field_name: &str,
+        kind: SignatureKind,
+        doc: DocId,
+    ) -> tv::Result<Option<Signature>> {
+        let fast_fields = segment.fast_fields();
+        match kind {
+            SignatureKind::SimHash => Ok(fast_fields
+                .u64(field_name)
+                .ok()
+                .and_then(|column| column.first(doc))
+                .map(Signature::SimHash)),
+            SignatureKind::MinHash => {
+                let Some(column) = fast_fields.bytes(field_name)? else {
+                    return Ok(None);
+                };
+                let Some(ord) = column.ords().first(doc) else {
+                    return Ok(None);
+                };
+                let mut bytes = Vec::new();
+                column.ord_to_bytes(ord, &mut bytes)?;
+                Ok(Some(Signature::decode_minhash(&bytes)))
+            }
+        }
+    }
+}
+
+/// A stable 64 bits hash, FNV-1a followed by a finalizer spreading its bits.
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    mix(hash)
+}
+
+/// The finalizer of SplitMix64.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// The hashes of the shingles of a token sequence, or of its tokens if it
+/// has fewer tokens than a shingle.
+fn feature_hashes(tokens: &[String]) -> Vec<u64> {
+    let hash_tokens =
+        |tokens: &[String]| hash_bytes(tokens.join("\0").as_bytes());
+    if tokens.len() < SHINGLE_SIZE {
+        return tokens.chunks(1).map(hash_tokens).collect();
+    }
+    tokens.windows(SHINGLE_SIZE).map(hash_tokens).collect()
+}
+
+fn simhash(features: &[u64]) -> u64 {
+    let mut votes = [0i64; 64];
+    for feature in features {
+        for (bit, vote) in votes.iter_mut().enumerate() {
+            *vote += if feature >> bit & 1 == 1 { 1 } else { -1 };
+        }
+    }
+    votes
+        .iter()
+        .enumerate()
+        .filter(|(_, vote)| **vote > 0)
+        .fold(0, |signature, (bit, _)| signature | 1 << bit)
+}
+
+fn minhash(features: &[u64], num_hashes: usize) -> Vec<u32> {
+    (0..num_hashes as u64)
+        .map(|seed| {
+            let seed = mix(seed.wrapping_add(0x9e37_79b9_7f4a_7c15));
+            features
+                .iter()
+                .map(|feature| (mix(feature ^ seed) >> 32) as u32)
+                .min()
+                .unwrap_or(u32::MAX)
+        })
+        .collect()
+}
+
+/// Add the signatures of the signature fields of a document, computed from
+/// the tokens of their source field with the tokenizer of that field.
+///
+/// Signatures already set on the document, e.g. when reindexing stored
+/// documents, and those of documents without source text are left alone.
+pub(crate) fn sign_document(
+    index: &tv::Index,
+    schema: &Schema,
+    extensions: &SchemaExtensions,
+    doc: &mut TantivyDocument,
+) -> tv::Result<()> {
+    for (name, options) in &extensions.signature_fields {
+        let (Ok(field), Ok(source)) =
+            (schema.get_field(name), schema.get_field(&options.source))
+        else {
+            continue;
+        };
+        if doc.get_first(field).is_some() {
+            continue;
+        }
+        let mut analyzer = index.tokenizer_for_field(source)?;
+        let mut tokens = Vec::new();
+        for value in doc.get_all(source) {
+            if let OwnedValue::Str(text) = value {
+                analyzer
+                    .token_stream(text)
+                    .process(&mut |token| tokens.push(token.text.clone()));
+            }
+        }
+        if tokens.is_empty() {
+            continue;
+        }
+        let features = feature_hashes(&tokens);
+        match options.kind {
+            SignatureKind::SimHash => doc.add_u64(field, simhash(&features)),
+            SignatureKind::MinHash => doc.add_bytes(
+                field,
+                Signature::encode_minhash(&minhash(
+                    &features,
+                    options.num_hashes,
+                )),
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Collector of the documents whose signature is within a distance of a
+/// signature, nearest first.
+pub(crate) struct NearDuplicateCollector {
+    pub(crate) field: String,
+    pub(crate) signature: Signature,
+    pub(crate) max_distance: u32,
+}
+
+impl Collector for NearDuplicateCollector {
+    type Fruit = Vec<(u32, DocAddress)>;
+    type Child = NearDuplicateSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_ord: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tv::Result<NearDuplicateSegmentCollector> {
+        let fast_fields = segment.fast_fields();
+        let signatures = match &self.signature {
+            Signature::SimHash(_) => fast_fields
+                .u64(&self.field)
+                .ok()
+                .map(SegmentSignatures::SimHash),
+            Signature::MinHash(_) => match fast_fields.bytes(&self.field)? {
+                Some(column) => {
+                    let distances =
+                        minhash_distances(&column, &self.signature)?;
+                    Some(SegmentSignatures::MinHash(column, distances))
+                }
+                None => None,
+            },
+        };
+        Ok(NearDuplicateSegmentCollector {
+            segment_ord,
+            signatures,
+            signature: self.signature.clone(),
+            max_distance: self.max_distance,
+            hits: Vec::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<Vec<(u32, DocAddress)>>,
+    ) -> tv::Result<Vec<(u32, DocAddress)>> {
+        let mut hits: Vec<_> = segment_fruits.into_iter().flatten().collect();
+        hits.sort_unstable();
+        Ok(hits)
+    }
+}
+
+/// The distance of each distinct MinHash signature of a column to
+/// `signature`, by ordinal. Signatures are deduplicated by the column
+/// dictionary, so each is compared once.
+fn minhash_distances(
+    column: &BytesColumn,
+    signature: &Signature,
+) -> tv::Result<HashMap<u64, u32>> {
+    let mut distances = HashMap::new();
+    let mut stream = column.dictionary().stream()?;
+    while stream.advance() {
+        let other = Signature::decode_minhash(stream.key());
+        if let Some(distance) = signature.distance(&other) {
+            distances.insert(stream.term_ord(), distance);
+        }
+    }
+    Ok(distances)
+}
+
+enum SegmentSignatures {
+    SimHash(Column<u64>),
+    MinHash(BytesColumn, HashMap<u64, u32>),
+}
+
+pub(crate) struct NearDuplicateSegmentCollector {
+    segment_ord: SegmentOrdinal,
+    signatures: Option<SegmentSignatures>,
+    signature: Signature,
+    max_distance: u32,
+    hits: Vec<(u32, DocAddress)>,
+}
+
+impl SegmentCollector for NearDuplicateSegmentCollector {
+    type Fruit = Vec<(u32, DocAddress)>;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        let distance = match (&self.signatures, &self.signature) {
+            (Some(SegmentSignatures::SimHash(column)), signature) => {
+                column.first(doc).and_then(|other| {
+                    signature.distance(&Signature::SimHash(other))
+                })
+            }
+            (Some(SegmentSignatures::MinHash(column, distances)), _) => column
+                .ords()
+                .first(doc)
+                .and_then(|ord| distances.get(&ord).copied()),
+            (None, _) => None,
+        };
+        if let Some(distance) =
+            distance.filter(|distance| *distance <= self.max_distance)
+        {
+            self.hits
+                .push((distance, DocAddress::new(self.segment_ord, doc)));
+        }
+    }
+
+    fn harvest(self) -> Vec<(u32, DocAddress)> {
+        self.hits
+    }
+}
@@ -0,0 +1,96 @@
+//! A forgiving syntax of query strings for search boxes, like the
+//! `simple_query_string` of Elasticsearch, see `Index.parse_simple_query`.
+
+use tantivy::query_grammar::{
+    Delimiter, Occur, UserInputAst, UserInputLeaf, UserInputLiteral,
+};
+
+/// A clause of a group of the query.
+type Clause = (Option<Occur>, UserInputAst);
+
+fn literal(phrase: String, delimiter: Delimiter) -> UserInputAst {
+    UserInputAst::Leaf(Box::new(UserInputLeaf::Literal(UserInputLiteral {
+        field_name: None,
+        phrase,
+        delimiter,
+        slop: 0,
+        prefix: false,
+    })))
+}
+
+/// The query of the clauses of a group, which matches all the documents
+/// but the excluded ones if all its clauses are exclusions.
+fn group(mut clauses: Vec<Clause>) -> UserInputAst {
+    if clauses
+        .iter()
+        .all(|(occur, _)| *occur == Some(Occur::MustNot))
+    {
+        clauses.push((
+            Some(Occur::Must),
+            UserInputAst::Leaf(Box::new(UserInputLeaf::All)),
+        ));
+    }
+    UserInputAst::Clause(clauses)
+}
+
+/// Parse a simple query string, which never fails.
+///
+/// Its words and `"quoted phrases"` can be prefixed by `+`, the documents
+/// must match them, or `-`, the documents must not. `|` separates
+/// alternatives, each matching documents on its own. Any other character is
+/// part of a word, an unterminated phrase runs to the end of the text, and
+/// the operators without a word are ignored.
+pub(crate) fn parse(text: &str) -> UserInputAst {
+    let mut groups: Vec<Vec<Clause>> = vec![Vec::new()];
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '|' {
+            chars.next();
+            groups.push(Vec::new());
+            continue;
+        }
+        let mut occur = None;
+        while let Some(&c) = chars.peek() {
+            match c {
+                '+' => occur = Some(Occur::Must),
+                '-' => occur = Some(Occur::MustNot),
+                _ => break,
+            }
+            chars.next();
+        }
+        let (phrase, delimiter) = if chars.next_if_eq(&'"').is_some() {
+            let phrase = chars.by_ref().take_while(|&c| c != '"').collect();
+            (phrase, Delimiter::DoubleQuotes)
+        } else {
+            let mut word = String::new();
+            while let Some(c) =
+                chars.next_if(|&c| !c.is_whitespace() && c != '|' && c != '"')
+            {
+                word.push(c);
+            }
+            (word, Delimiter::None)
+        };
+        if !phrase.trim().is_empty() {
+            let clauses = groups.last_mut().expect("There is a group");
+            clauses.push((occur, literal(phrase, delimiter)));
+        }
+    }
+    let mut groups: Vec<UserInputAst> = groups
+        .into_iter()
+        .filter(|clauses| !clauses.is_empty())
+        .map(group)
+        .collect();
+    if groups.len() == 1 {
+        return groups.remove(0);
+    }
+    UserInputAst::Clause(
+        groups
+            .into_iter()
+            .map(|group| (Some(Occur::Should), group))
+            .collect(),
+    )
+}
@@ -1,4 +1,4 @@
-use crate::to_pyerr;
+use crate::errors::tantivy_err;
 use pyo3::prelude::*;
 use tantivy as tv;
 // Bring the trait into scope to use methods like `as_str()` on `OwnedValue`.
@@ -55,14 +55,10 @@ impl SnippetGenerator {
         schema: &crate::Schema,
         field_name: &str,
     ) -> PyResult<SnippetGenerator> {
-        let field = schema
-            .inner
-            .get_field(field_name)
-            .or(Err("field not found"))
-            .map_err(to_pyerr)?;
+        let field = crate::get_field(&schema.inner, field_name)?;
         let generator =
             tv::SnippetGenerator::create(&searcher.inner, query.get(), field)
-                .map_err(to_pyerr)?;
+                .map_err(tantivy_err)?;
 
         Ok(SnippetGenerator {
             field_name: field_name.to_string(),
@@ -0,0 +1,350 @@
+//! Sorting the hits of a search by a fast field, see the `order_by_field`
+//! argument of `Searcher.search`.
+
+use std::collections::HashMap;
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use tantivy::{
+    self as tv,
+    collector::{CustomScorer, CustomSegmentScorer},
+    columnar::{BytesColumn, Column, ColumnType, MonotonicallyMappableToU64},
+    fastfield::FastFieldNotAvailableError,
+    schema::{OwnedValue, Type},
+    DateTime, DocId, SegmentId, SegmentReader,
+};
+
+use crate::{
+    document::extract_value_for_type, errors::tantivy_err, get_field,
+    searcher::Order,
+};
+
+/// The `order_by_field` argument of a search: a field name, or a
+/// `(field_name, order, mode)` tuple.
+#[derive(Clone, FromPyObject)]
+pub(crate) enum OrderBy {
+    #[pyo3(transparent)]
+    Field(String),
+    WithMode(String, Order, String),
+}
+
+/// How the values of a multivalued field reduce to the value a document is
+/// sorted by.
+#[derive(Clone, Copy, PartialEq)]
+enum SortMode {
+    First,
+    Min,
+    Max,
+    Avg,
+}
+
+impl std::str::FromStr for SortMode {
+    type Err = PyErr;
+
+    fn from_str(mode: &str) -> PyResult<Self> {
+        match mode {
+            "first" => Ok(SortMode::First),
+            "min" => Ok(SortMode::Min),
+            "max" => Ok(SortMode::Max),
+            "avg" => Ok(SortMode::Avg),
+            _ => Err(PyValueError::new_err(
+                "Invalid sort mode, valid choices are: 'first', 'min', 'max' \
+                 and 'avg'",
+            )),
+        }
+    }
+}
+
+/// Where the documents without a value of the sort field rank.
+#[derive(Clone)]
+enum Missing {
+    First,
+    Last,
+    /// The value the documents rank with.
+    Value(OwnedValue),
+}
+
+/// The `u64` mapping of a value of a fast field, which has the order of the
+/// values.
+fn mapped(value: &OwnedValue) -> Option<u64> {
+    match value {
+        OwnedValue::U64(value) => Some(*value),
+        OwnedValue::I64(value) => Some(value.to_u64()),
+        OwnedValue::F64(value) => Some(value.to_u64()),
+        OwnedValue::Bool(value) => Some(value.to_u64()),
+        OwnedValue::Date(value) => Some(value.to_u64()),
+        _ => None,
+    }
+}
+
+/// A value of a fast field as a number, dates as nanoseconds since the
+/// epoch.
+fn number(value: &OwnedValue) -> Option<f64> {
+    match value {
+        OwnedValue::U64(value) => Some(*value as f64),
+        OwnedValue::I64(value) => Some(*value as f64),
+        OwnedValue::F64(value) => Some(*value),
+        OwnedValue::Bool(value) => Some(f64::from(u8::from(*value))),
+        OwnedValue::Date(value) => Some(value.into_timestamp_nanos() as f64),
+        _ => None,
+    }
+}
+
+/// The number of the `u64` mapping of a value of a column.
+fn column_number(column_type: ColumnType, value: u64) -> f64 {
+    match column_type {
+        ColumnType::I64 => i64::from_u64(value) as f64,
+        ColumnType::F64 => f64::from_u64(value),
+        ColumnType::DateTime => {
+            DateTime::from_u64(value).into_timestamp_nanos() as f64
+        }
+        _ => value as f64,
+    }
+}
+
+/// The rank of a mapped value, larger first.
+fn rank(descending: bool, value: u64) -> u64 {
+    if descending {
+        value
+    } else {
+        u64::MAX - value
+    }
+}
+
+/// The column of the terms of a text or bytes fast field in a segment.
+fn term_column(
+    segment: &SegmentReader,
+    field_name: &str,
+    value_type: Type,
+) -> Option<BytesColumn> {
+    let fast_fields = segment.fast_fields();
+    match value_type {
+        Type::Str => fast_fields.str(field_name).ok()?.map(Into::into),
+        Type::Bytes => fast_fields.bytes(field_name).ok()?,
+        _ => None,
+    }
+}
+
+/// The ordinals of the terms of a text or bytes fast field among the terms
+/// of all the segments of a searcher, by segment and segment ordinal.
+///
+/// The ordinals of the terms in a segment follow the order of the terms,
+/// but only within the segment: the shared ones compare across segments.
+fn global_term_ords(
+    searcher: &tv::Searcher,
+    field_name: &str,
+    value_type: Type,
+) -> tv::Result<HashMap<SegmentId, Vec<u64>>> {
+    let mut segment_terms = Vec::new();
+    for segment in searcher.segment_readers() {
+        let Some(column) = term_column(segment, field_name, value_type) else {
+            continue;
+        };
+        let dictionary = column.dictionary();
+        let mut terms = Vec::with_capacity(dictionary.num_terms());
+        let mut stream = dictionary.stream()?;
+        while stream.advance() {
+            terms.push(stream.key().to_vec());
+        }
+        segment_terms.push((segment.segment_id(), terms));
+    }
+    let mut all_terms: Vec<&[u8]> = segment_terms
+        .iter()
+        .flat_map(|(_, terms)| terms.iter().map(Vec::as_slice))
+        .collect();
+    all_terms.sort_unstable();
+    all_terms.dedup();
+    Ok(segment_terms
+        .iter()
+        .map(|(segment_id, terms)| {
+            let ords = terms
+                .iter()
+                .map(|term| {
+                    all_terms.binary_search(&term.as_slice()).unwrap_or(0)
+                        as u64
+                })
+                .collect();
+            (*segment_id, ords)
+        })
+        .collect())
+}
+
+/// Orders the hits by the value of a fast field, as `u64` mappings of the
+/// values: the larger first for a descending order, else their
+/// complement. Averages are mapped as floats, and the terms of text and
+/// bytes fields as their ordinals among the terms of all the segments.
+pub(crate) struct FieldSort {
+    field_name: String,
+    order: tv::Order,
+    mode: SortMode,
+    missing: Missing,
+    /// The searcher-wide ordinals of the terms of a text or bytes field.
+    term_ords: Option<HashMap<SegmentId, Vec<u64>>>,
+}
+
+impl FieldSort {
+    /// Sort by a field in the given order, unless `order_by` gives its own.
+    /// The documents without a value are placed as given by `missing`:
+    /// 'first', 'last' or a value of the field. Defaults to 'last'.
+    ///
+    /// Raises a ValueError if the sort mode or `missing` is invalid, e.g.
+    /// 'avg' for a text field.
+    pub(crate) fn new(
+        searcher: &tv::Searcher,
+        order_by: &OrderBy,
+        order: Order,
+        missing: Option<&Bound<PyAny>>,
+    ) -> PyResult<Self> {
+        let (field_name, order, mode) = match order_by {
+            OrderBy::Field(field_name) => (field_name, order, SortMode::First),
+            OrderBy::WithMode(field_name, order, mode) => {
+                (field_name, *order, mode.parse()?)
+            }
+        };
+        let schema = searcher.schema();
+        let missing = match missing {
+            None => Missing::Last,
+            Some(missing) => match missing.extract::<&str>() {
+                Ok("first") => Missing::First,
+                Ok("last") => Missing::Last,
+                _ => {
+                    let field = get_field(schema, field_name)?;
+                    let value_type =
+                        schema.get_field_entry(field).field_type().value_type();
+                    if !matches!(
+                        value_type,
+                        Type::U64
+                            | Type::I64
+                            | Type::F64
+                            | Type::Bool
+                            | Type::Date
+                    ) {
+                        return Err(PyValueError::new_err(format!(
+                            "The missing value of `{field_name}` must be \
+                             'first' or 'last', only numeric, boolean and \
+                             date fields take a value."
+                        )));
+                    }
+                    Missing::Value(extract_value_for_type(
+                        missing, value_type, field_name,
+                    )?)
+                }
+            },
+        };
+        let value_type = schema.find_field(field_name).map(|(field, _)| {
+            schema.get_field_entry(field).field_type().value_type()
+        });
+        let term_ords = match value_type {
+            Some(value_type @ (Type::Str | Type::Bytes)) => {
+                if mode == SortMode::Avg {
+                    return Err(PyValueError::new_err(format!(
+                        "The sort mode of `{field_name}` can't be 'avg', only \
+                         numeric, boolean and date fields are averaged."
+                    )));
+                }
+                Some(
+                    global_term_ords(searcher, field_name, value_type)
+                        .map_err(tantivy_err)?,
+                )
+            }
+            _ => None,
+        };
+        Ok(FieldSort {
+            field_name: field_name.to_string(),
+            order: order.into(),
+            mode,
+            missing,
+            term_ords,
+        })
+    }
+}
+
+impl CustomScorer<u64> for FieldSort {
+    type Child = FieldSortScorer;
+
+    fn segment_scorer(
+        &self,
+        segment_reader: &SegmentReader,
+    ) -> tv::Result<FieldSortScorer> {
+        let schema = segment_reader.schema();
+        let (column, column_type) = segment_reader
+            .fast_fields()
+            .u64_lenient(&self.field_name)?
+            .ok_or_else(|| match schema.find_field(&self.field_name) {
+                Some((field, _)) => FastFieldNotAvailableError::new(
+                    schema.get_field_entry(field),
+                )
+                .into(),
+                None => {
+                    tv::TantivyError::FieldNotFound(self.field_name.clone())
+                }
+            })?;
+        let descending = self.order.is_desc();
+        let missing_rank = match &self.missing {
+            Missing::First => u64::MAX,
+            Missing::Last => u64::MIN,
+            Missing::Value(value) => {
+                let mapped = if self.mode == SortMode::Avg {
+                    number(value).map(f64::to_u64)
+                } else {
+                    mapped(value)
+                };
+                mapped.map_or(u64::MIN, |value| rank(descending, value))
+            }
+        };
+        let term_ords = match column_type {
+            ColumnType::Str | ColumnType::Bytes => self
+                .term_ords
+                .as_ref()
+                .and_then(|term_ords| {
+                    term_ords.get(&segment_reader.segment_id())
+                })
+                .cloned(),
+            _ => None,
+        };
+        Ok(FieldSortScorer {
+            column,
+            column_type,
+            mode: self.mode,
+            descending,
+            missing_rank,
+            term_ords,
+        })
+    }
+}
+
+pub(crate) struct FieldSortScorer {
+    column: Column<u64>,
+    column_type: ColumnType,
+    mode: SortMode,
+    descending: bool,
+    missing_rank: u64,
+    /// The searcher-wide ordinals of the term ordinals of the segment.
+    term_ords: Option<Vec<u64>>,
+}
+
+impl CustomSegmentScorer<u64> for FieldSortScorer {
+    fn score(&mut self, doc: DocId) -> u64 {
+        let value = match self.mode {
+            SortMode::First => self.column.first(doc),
+            SortMode::Min => self.column.values_for_doc(doc).min(),
+            SortMode::Max => self.column.values_for_doc(doc).max(),
+            SortMode::Avg => {
+                let (sum, count) = self.column.values_for_doc(doc).fold(
+                    (0.0, 0),
+                    |(sum, count), value| {
+                        (
+                            sum + column_number(self.column_type, value),
+                            count + 1,
+                        )
+                    },
+                );
+                (count > 0).then(|| (sum / f64::from(count)).to_u64())
+            }
+        };
+        let value = match &self.term_ords {
+            Some(term_ords) => value.map(|ord| term_ords[ord as usize]),
+            None => value,
+        };
+        value.map_or(self.missing_rank, |value| rank(self.descending, value))
+    }
+}
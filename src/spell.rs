@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use levenshtein_automata::{Distance, LevenshteinAutomatonBuilder, DFA};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use tantivy::{self as tv, schema::Type, tokenizer::TextAnalyzer};
+use tantivy_fst::Automaton;
+
+use crate::{
+    errors::{schema_err, tantivy_err},
+    get_field,
+};
+
+/// The largest edit distance supported by `SpellCorrector.suggest`.
+const MAX_EDITS: u8 = 2;
+
+/// Adapts a Levenshtein DFA to search the term dictionary.
+pub(crate) struct DfaAutomaton<'a>(pub(crate) &'a DFA);
+
+impl Automaton for DfaAutomaton<'_> {
+    type State = u32;
+
+    fn start(&self) -> u32 {
+        self.0.initial_state()
+    }
+
+    fn is_match(&self, state: &u32) -> bool {
+        matches!(self.0.distance(*state), Distance::Exact(_))
+    }
+
+    fn can_match(&self, state: &u32) -> bool {
+        *state != levenshtein_automata::SINK_STATE
+    }
+
+    fn accept(&self, state: &u32, byte: u8) -> u32 {
+        self.0.transition(*state, byte)
+    }
+}
+
+/// Suggests corrections for misspelled words, using the terms of a text
+/// field as dictionary.
+///
+/// The dictionary is the term dictionary of the index at the time the
+/// corrector was created, terms appearing in fewer than `min_doc_freq`
+/// documents are ignored.
+#[pyclass(module = "tantivy.tantivy")]
+pub(crate) struct SpellCorrector {
+    searcher: tv::Searcher,
+    field: tv::schema::Field,
+    analyzer: TextAnalyzer,
+    min_doc_freq: u64,
+}
+
+impl SpellCorrector {
+    pub(crate) fn new(
+        index: &tv::Index,
+        searcher: tv::Searcher,
+        field_name: &str,
+        min_doc_freq: u64,
+    ) -> PyResult<Self> {
+        let schema = index.schema();
+        let field = get_field(&schema, field_name)?;
+        let field_entry = schema.get_field_entry(field);
+        if field_entry.field_type().value_type() != Type::Str
+            || !field_entry.is_indexed()
+        {
+            return Err(schema_err(
+                format!("Field `{field_name}` is not an indexed text field."),
+                field_name,
+            ));
+        }
+        let analyzer = index.tokenizer_for_field(field).map_err(tantivy_err)?;
+        Ok(SpellCorrector {
+            searcher,
+            field,
+            analyzer,
+            min_doc_freq,
+        })
+    }
+
+    /// The terms within the DFA distance of the word, with their distance
+    /// and document frequency.
+    fn candidates(&self, dfa: &DFA) -> tv::Result<HashMap<Vec<u8>, (u8, u64)>> {
+        let mut candidates: HashMap<Vec<u8>, (u8, u64)> = HashMap::new();
+        for segment_reader in self.searcher.segment_readers() {
+            let inverted_index = segment_reader.inverted_index(self.field)?;
+            let mut stream = inverted_index
+                .terms()
+                .search(DfaAutomaton(dfa))
+                .into_stream()?;
+            while stream.advance() {
+                let Distance::Exact(distance) = dfa.eval(stream.key()) else {
+                    continue;
+                };
+                let doc_freq = stream.value().doc_freq as u64;
+                candidates
+                    .entry(stream.key().to_vec())
+                    .or_insert((distance, 0))
+                    .1 += doc_freq;
+            }
+        }
+        Ok(candidates)
+    }
+}
+
+#[pymethods]
+impl SpellCorrector {
+    /// Suggest corrections for a word.
+    ///
+    /// The word is processed by the tokenizer of the field before being
+    /// looked up, so the suggestions are terms as they are indexed.
+    ///
+    /// Args:
+    ///     word (str): The possibly misspelled word.
+    ///     max_edits (int, optional): The maximum Levenshtein distance between
+    ///         the word and a suggestion, a transposition counting as a single
+    ///         edit. Can be 1 or 2. Defaults to 2.
+    ///     limit (int, optional): The maximum number of suggestions. Defaults
+    ///         to 5.
+    ///
+    /// Returns a list of terms, the closest first and then the most frequent.
+    /// The word itself is never suggested.
+    ///
+    /// Raises a ValueError if `max_edits` is out of range.
+    #[pyo3(signature = (word, max_edits = 2, limit = 5))]
+    fn suggest(
+        &self,
+        py: Python,
+        word: &str,
+        max_edits: u8,
+        limit: usize,
+    ) -> PyResult<Vec<String>> {
+        if !(1..=MAX_EDITS).contains(&max_edits) {
+            return Err(PyValueError::new_err(format!(
+                "max_edits must be between 1 and {MAX_EDITS}."
+            )));
+        }
+        let mut analyzer = self.analyzer.clone();
+        let mut token_stream = analyzer.token_stream(word);
+        let Some(token) = token_stream.next() else {
+            return Ok(Vec::new());
+        };
+        let term = token.text.clone();
+
+        py.allow_threads(|| {
+            let dfa = LevenshteinAutomatonBuilder::new(max_edits, true)
+                .build_dfa(&term);
+            let candidates = self.candidates(&dfa).map_err(tantivy_err)?;
+
+            let mut suggestions: Vec<_> = candidates
+                .into_iter()
+                .filter(|(candidate, (_, doc_freq))| {
+                    *doc_freq >= self.min_doc_freq
+                        && candidate.as_slice() != term.as_bytes()
+                })
+                .collect();
+            suggestions.sort_by(
+                |(a, (a_dist, a_freq)), (b, (b_dist, b_freq))| {
+                    a_dist.cmp(b_dist).then(b_freq.cmp(a_freq)).then(a.cmp(b))
+                },
+            );
+            Ok(suggestions
+                .into_iter()
+                .take(limit)
+                .map(|(candidate, _)| {
+                    String::from_utf8_lossy(&candidate).into_owned()
+                })
+                .collect())
+        })
+    }
+}
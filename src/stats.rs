@@ -0,0 +1,260 @@
+use serde::Serialize;
+use tantivy::{
+    self as tv,
+    schema::{Field, FieldEntry, IndexRecordOption, OwnedValue, Type},
+    space_usage::PerFieldSpaceUsage,
+    DocSet, SegmentReader, TantivyDocument, TERMINATED,
+};
+
+use crate::activity::Activity;
+
+/// The number of live documents of a segment holding a value for a field.
+///
+/// The values are read from the cheapest source available: the fast field
+/// column, else the postings of every term of the field, else the doc store.
+pub(crate) fn docs_with_field(
+    segment: &SegmentReader,
+    field: Field,
+    field_entry: &FieldEntry,
+) -> tv::Result<u32> {
+    let max_doc = segment.max_doc();
+    let mut has_field = vec![false; max_doc as usize];
+
+    // The columns of a JSON field are keyed by path, they can't be looked up
+    // by the field name.
+    let is_json = field_entry.field_type().value_type() == Type::Json;
+    if field_entry.is_fast() && !is_json {
+        let handles = segment
+            .fast_fields()
+            .dynamic_column_handles(field_entry.name())?;
+        for handle in handles {
+            let column = handle.open()?;
+            for doc in 0..max_doc {
+                if column.column_index().has_value(doc) {
+                    has_field[doc as usize] = true;
+                }
+            }
+        }
+    } else if field_entry.is_indexed() {
+        let inverted_index = segment.inverted_index(field)?;
+        let mut terms = inverted_index.terms().stream()?;
+        while terms.advance() {
+            let mut postings = inverted_index.read_postings_from_terminfo(
+                terms.value(),
+                IndexRecordOption::Basic,
+            )?;
+            let mut doc = postings.doc();
+            while doc != TERMINATED {
+                has_field[doc as usize] = true;
+                doc = postings.advance();
+            }
+        }
+    } else {
+        let store_reader = segment.get_store_reader(1)?;
+        for doc in 0..max_doc {
+            let stored: TantivyDocument = store_reader.get(doc)?;
+            has_field[doc as usize] = stored.get_first(field).is_some();
+        }
+    }
+
+    let alive_bitset = segment.alive_bitset();
+    Ok((0..max_doc)
+        .filter(|&doc| {
+            has_field[doc as usize]
+                && alive_bitset.is_none_or(|alive| alive.is_alive(doc))
+        })
+        .count() as u32)
+}
+
+/// The bytes used by a field in each part of the index.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FieldSpaceUsage {
+    pub(crate) termdict: u64,
+    pub(crate) postings: u64,
+    pub(crate) positions: u64,
+    pub(crate) fast_fields: u64,
+    pub(crate) fieldnorms: u64,
+    /// The share of the doc store attributed to the field.
+    pub(crate) store: u64,
+}
+
+impl FieldSpaceUsage {
+    pub(crate) fn total(&self) -> u64 {
+        self.termdict
+            + self.postings
+            + self.positions
+            + self.fast_fields
+            + self.fieldnorms
+            + self.store
+    }
+}
+
+/// The space used by every field of the searcher's segments, by field id.
+///
+/// The doc store compresses blocks of whole documents, so it is split among
+/// the fields of each segment in proportion to the uncompressed size of
+/// their stored values, read from every stored document.
+pub(crate) fn field_space_usage(
+    searcher: &tv::Searcher,
+) -> tv::Result<Vec<FieldSpaceUsage>> {
+    let num_fields = searcher.schema().fields().count();
+    let mut usages = vec![FieldSpaceUsage::default(); num_fields];
+    for segment in searcher.segment_readers() {
+        let space_usage = segment.space_usage()?;
+        add_per_field(&mut usages, space_usage.termdict(), |usage| {
+            &mut usage.termdict
+        });
+        add_per_field(&mut usages, space_usage.postings(), |usage| {
+            &mut usage.postings
+        });
+        add_per_field(&mut usages, space_usage.positions(), |usage| {
+            &mut usage.positions
+        });
+        add_per_field(&mut usages, space_usage.fast_fields(), |usage| {
+            &mut usage.fast_fields
+        });
+        add_per_field(&mut usages, space_usage.fieldnorms(), |usage| {
+            &mut usage.fieldnorms
+        });
+
+        let mut stored_sizes = vec![0u64; num_fields];
+        let store_reader = segment.get_store_reader(1)?;
+        for doc in store_reader.iter::<TantivyDocument>(None) {
+            for field_value in doc?.field_values() {
+                stored_sizes[field_value.field().field_id() as usize] +=
+                    stored_size(field_value.value());
+            }
+        }
+        let stored_total: u64 = stored_sizes.iter().sum();
+        let store_bytes = space_usage.store().total().get_bytes();
+        if stored_total > 0 {
+            for (usage, size) in usages.iter_mut().zip(stored_sizes) {
+                usage.store += (store_bytes as u128 * size as u128
+                    / stored_total as u128)
+                    as u64;
+            }
+        }
+    }
+    Ok(usages)
+}
+
+/// The bytes used by each part of the index, see `Index.stats`.
+#[derive(Default, Serialize)]
+pub(crate) struct DiskUsage {
+    termdict: u64,
+    postings: u64,
+    positions: u64,
+    fast_fields: u64,
+    fieldnorms: u64,
+    store: u64,
+    deletes: u64,
+    total: u64,
+}
+
+impl DiskUsage {
+    /// The bytes of each component, by name, without the total.
+    pub(crate) fn components(&self) -> [(&'static str, u64); 7] {
+        [
+            ("termdict", self.termdict),
+            ("postings", self.postings),
+            ("positions", self.positions),
+            ("fast_fields", self.fast_fields),
+            ("fieldnorms", self.fieldnorms),
+            ("store", self.store),
+            ("deletes", self.deletes),
+        ]
+    }
+}
+
+/// A snapshot of the documents, segments, disk usage and activity of an
+/// index, see `Index.stats`.
+#[derive(Serialize)]
+pub(crate) struct IndexStats {
+    pub(crate) num_docs: u64,
+    pub(crate) num_deleted_docs: u64,
+    pub(crate) num_segments: usize,
+    pub(crate) disk_usage: DiskUsage,
+    /// The activity of the writers since the index was opened.
+    pub(crate) uptime_secs: f64,
+    pub(crate) commits: u64,
+    pub(crate) committed_docs: u64,
+    pub(crate) docs_per_sec: f64,
+    pub(crate) merges: u64,
+    pub(crate) last_commit: Option<f64>,
+    pub(crate) last_merge: Option<f64>,
+}
+
+pub(crate) fn index_stats(
+    searcher: &tv::Searcher,
+    activity: &Activity,
+) -> tv::Result<IndexStats> {
+    let mut disk_usage = DiskUsage::default();
+    for segment in searcher.segment_readers() {
+        let space_usage = segment.space_usage()?;
+        disk_usage.termdict += space_usage.termdict().total().get_bytes();
+        disk_usage.postings += space_usage.postings().total().get_bytes();
+        disk_usage.positions += space_usage.positions().total().get_bytes();
+        disk_usage.fast_fields += space_usage.fast_fields().total().get_bytes();
+        disk_usage.fieldnorms += space_usage.fieldnorms().total().get_bytes();
+        disk_usage.store += space_usage.store().total().get_bytes();
+        disk_usage.deletes += space_usage.deletes().get_bytes();
+        disk_usage.total += space_usage.total().get_bytes();
+    }
+    let uptime_secs = activity.uptime_secs();
+    let committed_docs = activity.committed_docs();
+    Ok(IndexStats {
+        num_docs: searcher.num_docs(),
+        num_deleted_docs: searcher
+            .segment_readers()
+            .iter()
+            .map(|segment| u64::from(segment.num_deleted_docs()))
+            .sum(),
+        num_segments: searcher.segment_readers().len(),
+        disk_usage,
+        uptime_secs,
+        commits: activity.commits(),
+        committed_docs,
+        docs_per_sec: if uptime_secs > 0.0 {
+            committed_docs as f64 / uptime_secs
+        } else {
+            0.0
+        },
+        merges: activity.merges(),
+        last_commit: activity.last_commit(),
+        last_merge: activity.last_merge(),
+    })
+}
+
+/// Add the bytes of each field to a part of their usage.
+fn add_per_field(
+    usages: &mut [FieldSpaceUsage],
+    per_field: &PerFieldSpaceUsage,
+    part: impl Fn(&mut FieldSpaceUsage) -> &mut u64,
+) {
+    for (field, field_usage) in per_field.fields() {
+        *part(&mut usages[field.field_id() as usize]) +=
+            field_usage.total().get_bytes();
+    }
+}
+
+/// The uncompressed size of a stored value, roughly as serialized.
+fn stored_size(value: &OwnedValue) -> u64 {
+    match value {
+        OwnedValue::Null => 0,
+        OwnedValue::Str(text) => text.len() as u64,
+        OwnedValue::PreTokStr(pretokenized) => pretokenized.text.len() as u64,
+        OwnedValue::Facet(facet) => facet.encoded_str().len() as u64,
+        OwnedValue::Bytes(bytes) => bytes.len() as u64,
+        OwnedValue::Bool(_) => 1,
+        OwnedValue::U64(_)
+        | OwnedValue::I64(_)
+        | OwnedValue::F64(_)
+        | OwnedValue::Date(_) => 8,
+        OwnedValue::IpAddr(_) => 16,
+        OwnedValue::Array(values) => values.iter().map(stored_size).sum(),
+        OwnedValue::Object(entries) => entries
+            .iter()
+            .map(|(key, value)| key.len() as u64 + stored_size(value))
+            .sum(),
+    }
+}
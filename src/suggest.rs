@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+
+use levenshtein_automata::LevenshteinAutomatonBuilder;
+use pyo3::{exceptions::PyValueError, prelude::*};
+use serde::{Deserialize, Serialize};
+use tantivy::{
+    self as tv,
+    schema::{Field, OwnedValue, Type},
+    TantivyDocument,
+};
+use tantivy_fst::{IntoStreamer, Map, Streamer};
+
+use crate::{
+    errors::{schema_err, tantivy_err},
+    get_field, sidecar,
+    spell::DfaAutomaton,
+    to_pyerr,
+};
+
+/// The largest edit distance supported by `Suggester.suggest`.
+const MAX_EDITS: u8 = 2;
+
+/// A suggestion and its weight.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Entry {
+    text: String,
+    weight: f64,
+}
+
+/// The entries of a suggester as persisted in the index directory, the FST
+/// being stored in a separate file.
+#[derive(Deserialize, Serialize)]
+struct Persisted {
+    /// The opstamp of the commit the suggester was built from.
+    opstamp: u64,
+    weight_field: Option<String>,
+    /// The entries, in the order of their keys in the FST.
+    entries: Vec<Entry>,
+}
+
+/// Autocomplete suggestions built over a field of the index.
+///
+/// The suggestions are the stored values of the field if it is stored, for
+/// example whole titles, or else its indexed terms. They are kept in an FST
+/// keyed by their lowercased text and persisted in the index directory, so
+/// that they are only rebuilt after the index changed.
+#[pyclass(module = "tantivy.tantivy")]
+pub(crate) struct Suggester {
+    map: Map<Vec<u8>>,
+    entries: Vec<Entry>,
+}
+
+impl Suggester {
+    /// Load the persisted suggester of a field, or build it if it is missing
+    /// or out of date.
+    pub(crate) fn open(
+        index: &tv::Index,
+        searcher: &tv::Searcher,
+        field_name: &str,
+        weight_field: Option<&str>,
+    ) -> PyResult<Self> {
+        let schema = index.schema();
+        let field = get_field(&schema, field_name)?;
+        let field_entry = schema.get_field_entry(field);
+        if field_entry.field_type().value_type() != Type::Str {
+            return Err(schema_err(
+                format!("Field `{field_name}` is not a text field."),
+                field_name,
+            ));
+        }
+        let weight_field = match weight_field {
+            Some(_) if !field_entry.is_stored() => {
+                return Err(PyValueError::new_err(
+                    "weight_field can only be used with a stored field.",
+                ))
+            }
+            Some(name) => {
+                let weight_field = get_field(&schema, name)?;
+                if !schema.get_field_entry(weight_field).is_stored() {
+                    return Err(schema_err(
+                        format!("Field `{name}` is not a stored field."),
+                        name,
+                    ));
+                }
+                Some((name, weight_field))
+            }
+            None => None,
+        };
+
+        let opstamp = index.load_metas().map_err(tantivy_err)?.opstamp;
+        let entries_path = format!(".tantivy-py.suggest.{field_name}.json");
+        let fst_path = format!(".tantivy-py.suggest.{field_name}.fst");
+        if let (Some(entries), Some(fst)) = (
+            sidecar::read(index, &entries_path)?,
+            sidecar::read(index, &fst_path)?,
+        ) {
+            let persisted: Persisted =
+                serde_json::from_slice(&entries).map_err(to_pyerr)?;
+            if persisted.opstamp == opstamp
+                && persisted.weight_field.as_deref()
+                    == weight_field.map(|(name, _)| name)
+            {
+                let map = Map::from_bytes(fst).map_err(to_pyerr)?;
+                return Ok(Suggester {
+                    map,
+                    entries: persisted.entries,
+                });
+            }
+        }
+
+        let by_key = if field_entry.is_stored() {
+            stored_entries(searcher, field, weight_field.map(|(_, f)| f))
+        } else {
+            term_entries(searcher, field)
+        }
+        .map_err(tantivy_err)?;
+        let map = Map::from_iter(
+            by_key
+                .keys()
+                .enumerate()
+                .map(|(ord, key)| (key, ord as u64)),
+        )
+        .map_err(to_pyerr)?;
+        let entries: Vec<Entry> = by_key.into_values().collect();
+
+        let persisted = Persisted {
+            opstamp,
+            weight_field: weight_field.map(|(name, _)| name.to_string()),
+            entries,
+        };
+        sidecar::write(index, &fst_path, &map.as_fst().to_vec())?;
+        sidecar::write(
+            index,
+            &entries_path,
+            &serde_json::to_vec(&persisted).map_err(to_pyerr)?,
+        )?;
+        Ok(Suggester {
+            map,
+            entries: persisted.entries,
+        })
+    }
+}
+
+/// Add a suggestion, merging it with the suggestions of the same key.
+fn add_entry(by_key: &mut BTreeMap<String, Entry>, text: &str, weight: f64) {
+    by_key
+        .entry(text.to_lowercase())
+        .or_insert_with(|| Entry {
+            text: text.to_string(),
+            weight: 0.0,
+        })
+        .weight += weight;
+}
+
+/// The stored values of a field, weighted by the stored value of the weight
+/// field or else by the number of documents holding them.
+fn stored_entries(
+    searcher: &tv::Searcher,
+    field: Field,
+    weight_field: Option<Field>,
+) -> tv::Result<BTreeMap<String, Entry>> {
+    let mut by_key = BTreeMap::new();
+    for segment_reader in searcher.segment_readers() {
+        let store_reader = segment_reader.get_store_reader(1)?;
+        for doc in
+            store_reader.iter::<TantivyDocument>(segment_reader.alive_bitset())
+        {
+            let doc = doc?;
+            let weight = match weight_field {
+                Some(weight_field) => match doc.get_first(weight_field) {
+                    Some(OwnedValue::U64(weight)) => *weight as f64,
+                    Some(OwnedValue::I64(weight)) => *weight as f64,
+                    Some(OwnedValue::F64(weight)) => *weight,
+                    _ => 0.0,
+                },
+                None => 1.0,
+            };
+            for value in doc.get_all(field) {
+                if let OwnedValue::Str(text) = value {
+                    add_entry(&mut by_key, text, weight);
+                }
+            }
+        }
+    }
+    Ok(by_key)
+}
+
+/// The indexed terms of a field, weighted by their document frequency.
+fn term_entries(
+    searcher: &tv::Searcher,
+    field: Field,
+) -> tv::Result<BTreeMap<String, Entry>> {
+    let mut by_key = BTreeMap::new();
+    for segment_reader in searcher.segment_readers() {
+        let inverted_index = segment_reader.inverted_index(field)?;
+        let mut stream = inverted_index.terms().stream()?;
+        while stream.advance() {
+            if let Ok(term) = std::str::from_utf8(stream.key()) {
+                let doc_freq = stream.value().doc_freq as f64;
+                add_entry(&mut by_key, term, doc_freq);
+            }
+        }
+    }
+    Ok(by_key)
+}
+
+#[pymethods]
+impl Suggester {
+    /// Suggest completions for a prefix.
+    ///
+    /// The lookup is case insensitive.
+    ///
+    /// Args:
+    ///     prefix (str): The text typed so far.
+    ///     limit (int, optional): The maximum number of suggestions. Defaults
+    ///         to 10.
+    ///     max_edits (int, optional): The maximum Levenshtein distance
+    ///         between the prefix and the beginning of a suggestion. 0 only
+    ///         matches exact prefixes, 1 and 2 allow typos. Defaults to 0.
+    ///
+    /// Returns a list of suggestions, the heaviest first.
+    ///
+    /// Raises a ValueError if `max_edits` is out of range.
+    #[pyo3(signature = (prefix, limit = 10, max_edits = 0))]
+    fn suggest(
+        &self,
+        py: Python,
+        prefix: &str,
+        limit: usize,
+        max_edits: u8,
+    ) -> PyResult<Vec<String>> {
+        if max_edits > MAX_EDITS {
+            return Err(PyValueError::new_err(format!(
+                "max_edits must be between 0 and {MAX_EDITS}."
+            )));
+        }
+        let prefix = prefix.to_lowercase();
+
+        py.allow_threads(|| {
+            let dfa = LevenshteinAutomatonBuilder::new(max_edits, true)
+                .build_prefix_dfa(&prefix);
+            let mut stream = self.map.search(DfaAutomaton(&dfa)).into_stream();
+            let mut matches = Vec::new();
+            while let Some((_, ord)) = stream.next() {
+                matches.push(&self.entries[ord as usize]);
+            }
+
+            matches.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+            Ok(matches
+                .into_iter()
+                .take(limit)
+                .map(|entry| entry.text.clone())
+                .collect())
+        })
+    }
+
+    /// The number of suggestions.
+    fn __len__(&self) -> usize {
+        self.entries.len()
+    }
+}
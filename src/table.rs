@@ -0,0 +1,113 @@
+//! The fields of the hits of a search as columns, see
+//! `SearchResult.to_table`.
+
+use pyo3::prelude::*;
+use tantivy::{
+    self as tv,
+    schema::{Field, OwnedValue, Type},
+    Document as _, TantivyDocument,
+};
+
+use crate::{
+    errors::{schema_err, tantivy_err},
+    get_field,
+    searcher::{fast_field_values, segment_of, DocAddress},
+};
+
+/// Where the values of a column are read from.
+enum Source {
+    /// The fast field column, without fetching the documents.
+    Fast(Field),
+    /// The stored documents.
+    Stored,
+}
+
+/// Find where the values of each field are read from, the fast field
+/// column when there is one, except for stored text fields.
+///
+/// Raises a SchemaError if a field is neither stored nor fast.
+fn sources(
+    schema: &tv::schema::Schema,
+    field_names: &[String],
+) -> PyResult<Vec<Source>> {
+    field_names
+        .iter()
+        .map(|field_name| {
+            let field = get_field(schema, field_name)?;
+            let field_entry = schema.get_field_entry(field);
+            let value_type = field_entry.field_type().value_type();
+            let is_fast = field_entry.is_fast() || value_type == Type::Facet;
+            // The fast column of a text field holds its tokens, the stored
+            // text is preferred.
+            let prefers_stored =
+                value_type == Type::Str && field_entry.is_stored();
+            if is_fast && value_type != Type::Json && !prefers_stored {
+                Ok(Source::Fast(field))
+            } else if field_entry.is_stored() {
+                Ok(Source::Stored)
+            } else {
+                Err(schema_err(
+                    format!(
+                        "Field `{field_name}` is neither stored nor fast, its \
+                         values can't be read."
+                    ),
+                    field_name,
+                ))
+            }
+        })
+        .collect()
+}
+
+/// Read the values of fields for documents, one column per field and one
+/// list of values per document in each column.
+///
+/// The stored documents are only fetched if a field isn't a fast field.
+pub(crate) fn read_columns(
+    py: Python,
+    searcher: &tv::Searcher,
+    doc_addresses: &[DocAddress],
+    field_names: &[String],
+) -> PyResult<Vec<Vec<Vec<OwnedValue>>>> {
+    let schema = searcher.schema();
+    let sources = sources(schema, field_names)?;
+    let segments = doc_addresses
+        .iter()
+        .map(|doc_address| segment_of(searcher, doc_address))
+        .collect::<PyResult<Vec<_>>>()?;
+    let needs_docs = sources
+        .iter()
+        .any(|source| matches!(source, Source::Stored));
+
+    py.allow_threads(|| {
+        let mut columns: Vec<Vec<Vec<OwnedValue>>> = sources
+            .iter()
+            .map(|_| Vec::with_capacity(doc_addresses.len()))
+            .collect();
+        for (doc_address, segment) in doc_addresses.iter().zip(segments) {
+            let named_doc = if needs_docs {
+                let doc: TantivyDocument =
+                    searcher.doc(doc_address.into()).map_err(tantivy_err)?;
+                doc.to_named_doc(schema).0
+            } else {
+                Default::default()
+            };
+            for ((source, field_name), column) in
+                sources.iter().zip(field_names).zip(&mut columns)
+            {
+                let values = match source {
+                    Source::Fast(field) => fast_field_values(
+                        segment,
+                        schema.get_field_entry(*field),
+                        doc_address.doc,
+                    )
+                    .map_err(tantivy_err)?,
+                    Source::Stored => {
+                        named_doc.get(field_name).cloned().unwrap_or_default()
+                    }
+                };
+                column.push(values);
+            }
+        }
+        Ok(columns)
+    })
+}
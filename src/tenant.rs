@@ -0,0 +1,217 @@
+//! Indexes shared by tenants, each only ever seeing its own documents, see
+//! `TenantIndex`.
+
+use std::sync::Arc;
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use tantivy::{
+    self as tv,
+    query::{BooleanQuery, TermQuery},
+    schema::{Field, IndexRecordOption, OwnedValue},
+    TantivyDocument, Term,
+};
+
+use crate::{
+    document::extract_value_for_type,
+    errors::schema_err,
+    get_field,
+    index::{Index, IndexWriter},
+    make_term_for_value_type,
+    query::Query,
+    searcher::{SearchFilter, Searcher},
+};
+
+/// The value of the routing field of a tenant.
+pub(crate) struct Tenant {
+    field: Field,
+    field_name: String,
+    value: OwnedValue,
+    term: Term,
+}
+
+impl Tenant {
+    /// Set the tenant value on a document.
+    ///
+    /// Raises a ValueError if the document holds the value of another
+    /// tenant.
+    pub(crate) fn stamp(&self, doc: &mut TantivyDocument) -> PyResult<()> {
+        let mut stamped = false;
+        for value in doc.get_all(self.field) {
+            if *value != self.value {
+                return Err(PyValueError::new_err(format!(
+                    "The document belongs to another tenant, its `{}` field \
+                     holds {value:?} instead of {:?}.",
+                    self.field_name, self.value
+                )));
+            }
+            stamped = true;
+        }
+        if !stamped {
+            doc.add_field_value(self.field, self.value.clone());
+        }
+        Ok(())
+    }
+
+    /// Whether a document holds the value of the tenant.
+    pub(crate) fn owns(&self, doc: &TantivyDocument) -> bool {
+        doc.get_all(self.field).any(|value| *value == self.value)
+    }
+
+    /// The query matching the documents of the tenant.
+    pub(crate) fn query(&self) -> Box<dyn tv::query::Query> {
+        Box::new(TermQuery::new(self.term.clone(), IndexRecordOption::Basic))
+    }
+
+    /// The query matching the documents of the tenant holding a term.
+    pub(crate) fn scope(&self, term: Term) -> Box<dyn tv::query::Query> {
+        Box::new(BooleanQuery::intersection(vec![
+            Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
+            self.query(),
+        ]))
+    }
+}
+
+/// An index shared by tenants, accessed on behalf of one of them.
+///
+/// The tenant of a document is the value of a routing field. The writers of
+/// a tenant index stamp the tenant value on the documents they add and
+/// only delete the documents of the tenant, and its searchers only search
+/// the documents of the tenant, so that application code can't leak
+/// documents across tenants by forgetting a filter.
+///
+/// Args:
+///     index (Index): The shared index.
+///     tenant_field (str): The routing field holding the tenant of the
+///         documents. It must be indexed, and text fields must use the 'raw'
+///         tokenizer.
+///     tenant (Any): The value of the tenant the index is accessed for.
+///
+/// Raises a SchemaError if the tenant field isn't indexed or is tokenized,
+/// and a ValueError if the tenant value doesn't match the field type.
+#[pyclass(frozen, module = "tantivy.tantivy")]
+pub(crate) struct TenantIndex {
+    index: Py<Index>,
+    tenant: Arc<Tenant>,
+    tenant_value: PyObject,
+}
+
+#[pymethods]
+impl TenantIndex {
+    #[new]
+    fn new(
+        py: Python,
+        index: Py<Index>,
+        tenant_field: &str,
+        tenant: &Bound<PyAny>,
+    ) -> PyResult<Self> {
+        let schema = index.borrow(py).index.schema();
+        let field = get_field(&schema, tenant_field)?;
+        let field_entry = schema.get_field_entry(field);
+        let is_tokenized = matches!(
+            field_entry.field_type(),
+            tv::schema::FieldType::Str(options)
+                if options
+                    .get_indexing_options()
+                    .is_some_and(|indexing| indexing.tokenizer() != "raw")
+        );
+        if !field_entry.is_indexed() || is_tokenized {
+            return Err(schema_err(
+                format!(
+                    "Field `{tenant_field}` must be indexed without being \
+                     tokenized to route the documents of the tenants."
+                ),
+                tenant_field,
+            ));
+        }
+        let value_type = field_entry.field_type().value_type();
+        let value = extract_value_for_type(tenant, value_type, tenant_field)?;
+        let term = make_term_for_value_type(
+            &schema,
+            tenant_field,
+            value_type,
+            tenant,
+        )?;
+        Ok(TenantIndex {
+            index,
+            tenant: Arc::new(Tenant {
+                field,
+                field_name: tenant_field.to_string(),
+                value,
+                term,
+            }),
+            tenant_value: tenant.clone().unbind(),
+        })
+    }
+
+    /// The shared index.
+    #[getter]
+    fn index(&self, py: Python) -> Py<Index> {
+        self.index.clone_ref(py)
+    }
+
+    /// The routing field holding the tenant of the documents.
+    #[getter]
+    fn tenant_field(&self) -> &str {
+        &self.tenant.field_name
+    }
+
+    /// The value of the tenant the index is accessed for.
+    #[getter]
+    fn tenant(&self, py: Python) -> PyObject {
+        self.tenant_value.clone_ref(py)
+    }
+
+    /// Create an `IndexWriter` scoped to the tenant.
+    ///
+    /// The tenant value is set on the documents added by the writer, and
+    /// its deletes only apply to the documents of the tenant. The writer
+    /// holds the lock of the shared index, like the writers of
+    /// `Index.writer`.
+    ///
+    /// Args: the same as `Index.writer`.
+    ///
+    /// Adding a document holding the value of another tenant raises a
+    /// ValueError.
+    #[pyo3(signature = (heap_size = 128_000_000, num_threads = 0, block = false, lock_timeout = None, max_mb_per_sec = None))]
+    fn writer(
+        &self,
+        py: Python,
+        heap_size: usize,
+        num_threads: usize,
+        block: bool,
+        lock_timeout: Option<f64>,
+        max_mb_per_sec: Option<f64>,
+    ) -> PyResult<IndexWriter> {
+        let writer = self.index.borrow(py).writer(
+            py,
+            heap_size,
+            num_threads,
+            block,
+            lock_timeout,
+            max_mb_per_sec,
+        )?;
+        Ok(writer.with_tenant(self.tenant.clone()))
+    }
+
+    /// Returns a searcher restricted to the documents of the tenant.
+    ///
+    /// The tenant filter applies like those of `Searcher.with_filter`,
+    /// including to the searchers derived from it.
+    fn searcher(&self, py: Python) -> Searcher {
+        let query = Query {
+            inner: self.tenant.query(),
+        };
+        self.index
+            .borrow(py)
+            .searcher()
+            .with_search_filter(SearchFilter::Query(query))
+    }
+
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "TenantIndex(tenant_field='{}', tenant={})",
+            self.tenant.field_name,
+            self.tenant_value.bind(py).repr()?
+        ))
+    }
+}
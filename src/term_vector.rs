@@ -0,0 +1,44 @@
+//! The terms of a document, reconstructed from the inverted index.
+
+use tantivy::{
+    self as tv, postings::Postings, schema::Field, DocId, DocSet, SegmentReader,
+};
+
+/// A term of a document, with its frequency and its positions, which are
+/// empty if the field doesn't index positions.
+pub(crate) type TermEntry = (String, u32, Vec<u32>);
+
+/// The terms of a text field of a document, in term order.
+///
+/// The postings of every term of the segment are searched for the document,
+/// so the cost grows with the number of terms of the field in the segment.
+pub(crate) fn term_vector(
+    segment: &SegmentReader,
+    field: Field,
+    doc: DocId,
+) -> tv::Result<Vec<TermEntry>> {
+    let record_option = segment
+        .schema()
+        .get_field_entry(field)
+        .field_type()
+        .get_index_record_option()
+        .unwrap_or(tv::schema::IndexRecordOption::Basic);
+    let inverted_index = segment.inverted_index(field)?;
+    let mut stream = inverted_index.terms().stream()?;
+    let mut terms = Vec::new();
+    while stream.advance() {
+        let mut postings = inverted_index
+            .read_postings_from_terminfo(stream.value(), record_option)?;
+        if postings.doc() < doc {
+            postings.seek(doc);
+        }
+        if postings.doc() != doc {
+            continue;
+        }
+        let mut positions = Vec::new();
+        postings.positions(&mut positions);
+        let text = String::from_utf8_lossy(stream.key()).into_owned();
+        terms.push((text, postings.term_freq(), positions));
+    }
+    Ok(terms)
+}
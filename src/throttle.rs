@@ -0,0 +1,185 @@
+//! A directory limiting the rate at which files are written, so that merges
+//! and commits don't saturate the disk the searchers read from.
+
+use std::{
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use pyo3::{exceptions::PyValueError, PyResult};
+use tantivy::{
+    self as tv,
+    directory::{
+        error::{DeleteError, LockError, OpenReadError, OpenWriteError},
+        AntiCallToken, DirectoryLock, FileHandle, FileSlice, Lock,
+        TerminatingWrite, WatchCallback, WatchHandle, WritePtr,
+    },
+    Directory,
+};
+
+/// Waits shorter than this are carried over to the next write, rather than
+/// sleeping for a few microseconds on every buffer flush.
+const MIN_SLEEP: Duration = Duration::from_millis(1);
+
+/// The bytes per second of a limit in megabytes per second.
+pub(crate) fn bytes_per_sec(mb_per_sec: f64) -> u64 {
+    (mb_per_sec * 1_000_000.0) as u64
+}
+
+/// The bytes per second of an optional limit in megabytes per second, 0 for
+/// no limit.
+///
+/// Raises a ValueError if the limit isn't a positive number.
+pub(crate) fn check_mb_per_sec(mb_per_sec: Option<f64>) -> PyResult<u64> {
+    match mb_per_sec {
+        Some(mb) if !(mb > 0.0 && mb.is_finite()) => Err(
+            PyValueError::new_err("max_mb_per_sec must be a positive number."),
+        ),
+        Some(mb) => Ok(bytes_per_sec(mb)),
+        None => Ok(0),
+    }
+}
+
+/// A limit on the number of bytes written per second, shared by the files
+/// of a directory. It can be changed while the files are written.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    /// The limit, 0 for no limit.
+    bytes_per_sec: AtomicU64,
+    /// When the bytes written so far are paid for.
+    paid_until: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(bytes_per_sec: u64) -> Self {
+        RateLimiter {
+            bytes_per_sec: AtomicU64::new(bytes_per_sec),
+            paid_until: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn set_bytes_per_sec(&self, bytes_per_sec: u64) {
+        self.bytes_per_sec.store(bytes_per_sec, Ordering::SeqCst);
+    }
+
+    pub(crate) fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec.load(Ordering::SeqCst)
+    }
+
+    /// Block until writing `num_bytes` more fits in the limit.
+    fn acquire(&self, num_bytes: usize) {
+        let bytes_per_sec = self.bytes_per_sec();
+        if bytes_per_sec == 0 {
+            return;
+        }
+        let wait = {
+            let mut paid_until = self.paid_until.lock().unwrap();
+            let now = Instant::now();
+            let start = paid_until.filter(|until| *until > now).unwrap_or(now);
+            let until = start
+                + Duration::from_secs_f64(
+                    num_bytes as f64 / bytes_per_sec as f64,
+                );
+            *paid_until = Some(until);
+            until.saturating_duration_since(now)
+        };
+        if wait >= MIN_SLEEP {
+            thread::sleep(wait);
+        }
+    }
+}
+
+/// A file written at the rate of a `RateLimiter`.
+struct ThrottledWrite {
+    inner: WritePtr,
+    limiter: Arc<RateLimiter>,
+}
+
+impl Write for ThrottledWrite {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.limiter.acquire(written);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl TerminatingWrite for ThrottledWrite {
+    fn terminate_ref(&mut self, token: AntiCallToken) -> io::Result<()> {
+        self.inner.terminate_ref(token)
+    }
+}
+
+/// A directory whose files are written at the rate of a `RateLimiter`.
+#[derive(Clone, Debug)]
+pub(crate) struct ThrottledDirectory {
+    inner: Box<dyn Directory>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl ThrottledDirectory {
+    pub(crate) fn new(
+        inner: Box<dyn Directory>,
+        limiter: Arc<RateLimiter>,
+    ) -> Self {
+        ThrottledDirectory { inner, limiter }
+    }
+}
+
+impl Directory for ThrottledDirectory {
+    fn get_file_handle(
+        &self,
+        path: &Path,
+    ) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        self.inner.get_file_handle(path)
+    }
+
+    fn open_read(&self, path: &Path) -> Result<FileSlice, OpenReadError> {
+        self.inner.open_read(path)
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        self.inner.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.inner.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        let inner = self.inner.open_write(path)?;
+        Ok(BufWriter::new(Box::new(ThrottledWrite {
+            inner,
+            limiter: self.limiter.clone(),
+        })))
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.inner.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.inner.atomic_write(path, data)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.inner.sync_directory()
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.inner.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> tv::Result<WatchHandle> {
+        self.inner.watch(watch_callback)
+    }
+}
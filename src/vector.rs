@@ -0,0 +1,208 @@
+use tantivy::{
+    self as tv,
+    collector::{Collector, SegmentCollector},
+    columnar::Column,
+    DocAddress, DocId, Score, SegmentOrdinal, SegmentReader,
+};
+
+use crate::schema::VectorMetric;
+
+/// Number of lanes the distance kernels are unrolled to, so that the compiler
+/// can map them to SIMD registers.
+const LANES: usize = 8;
+
+/// Encode a vector as the little endian bytes stored in the fast field.
+pub(crate) fn encode(vector: &[f32]) -> Vec<u8> {
+    vector
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// Decode the bytes of a fast field value into `output`.
+fn decode(bytes: &[u8], output: &mut Vec<f32>) {
+    output.clear();
+    output.extend(
+        bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap())),
+    );
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    let mut sums = [0f32; LANES];
+    let chunks_a = a.chunks_exact(LANES);
+    let chunks_b = b.chunks_exact(LANES);
+    let tail: f32 = chunks_a
+        .remainder()
+        .iter()
+        .zip(chunks_b.remainder())
+        .map(|(x, y)| x * y)
+        .sum();
+    for (chunk_a, chunk_b) in chunks_a.zip(chunks_b) {
+        for lane in 0..LANES {
+            sums[lane] += chunk_a[lane] * chunk_b[lane];
+        }
+    }
+    sums.iter().sum::<f32>() + tail
+}
+
+fn squared_l2(a: &[f32], b: &[f32]) -> f32 {
+    let mut sums = [0f32; LANES];
+    let chunks_a = a.chunks_exact(LANES);
+    let chunks_b = b.chunks_exact(LANES);
+    let tail: f32 = chunks_a
+        .remainder()
+        .iter()
+        .zip(chunks_b.remainder())
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum();
+    for (chunk_a, chunk_b) in chunks_a.zip(chunks_b) {
+        for lane in 0..LANES {
+            let diff = chunk_a[lane] - chunk_b[lane];
+            sums[lane] += diff * diff;
+        }
+    }
+    sums.iter().sum::<f32>() + tail
+}
+
+impl VectorMetric {
+    /// Compare two vectors. Higher values mean closer vectors.
+    fn rank(self, query: &[f32], query_norm: f32, vector: &[f32]) -> f32 {
+        match self {
+            VectorMetric::Cosine => {
+                let norm = dot(vector, vector).sqrt() * query_norm;
+                if norm == 0.0 {
+                    0.0
+                } else {
+                    dot(query, vector) / norm
+                }
+            }
+            VectorMetric::Dot => dot(query, vector),
+            VectorMetric::L2 => -squared_l2(query, vector),
+        }
+    }
+
+    /// Convert a rank computed by `rank` into the score reported to users:
+    /// the similarity for cosine and dot, the distance for l2.
+    pub(crate) fn score(self, rank: f32) -> f32 {
+        match self {
+            VectorMetric::Cosine | VectorMetric::Dot => rank,
+            VectorMetric::L2 => (-rank).sqrt(),
+        }
+    }
+}
+
+/// Keep the `k` best ranked hits, best first.
+fn top_k(hits: &mut Vec<(f32, DocAddress)>, k: usize) {
+    hits.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+    hits.truncate(k);
+}
+
+/// Collector performing an exact nearest neighbors scan on a vector field.
+///
+/// Its fruit holds the ranks of the `k` nearest documents matching the
+/// query, see `VectorMetric::rank`.
+pub(crate) struct KnnCollector {
+    pub(crate) field: String,
+    pub(crate) query: Vec<f32>,
+    pub(crate) metric: VectorMetric,
+    pub(crate) k: usize,
+}
+
+impl Collector for KnnCollector {
+    type Fruit = Vec<(f32, DocAddress)>;
+    type Child = KnnSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_ord: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tv::Result<KnnSegmentCollector> {
+        let Some(column) = segment.fast_fields().bytes(&self.field)? else {
+            return Ok(KnnSegmentCollector::empty(segment_ord, self.k));
+        };
+
+        // Vectors are deduplicated by the column dictionary, rank each of
+        // them once and look the ranks up by ordinal while collecting.
+        let query_norm = dot(&self.query, &self.query).sqrt();
+        let mut ranks = vec![f32::NAN; column.num_terms()];
+        let mut vector = Vec::with_capacity(self.query.len());
+        let mut stream = column.dictionary().stream()?;
+        while stream.advance() {
+            if stream.key().len() != self.query.len() * 4 {
+                continue;
+            }
+            decode(stream.key(), &mut vector);
+            ranks[stream.term_ord() as usize] =
+                self.metric.rank(&self.query, query_norm, &vector);
+        }
+
+        Ok(KnnSegmentCollector {
+            segment_ord,
+            ords: Some(column.ords().clone()),
+            ranks,
+            k: self.k,
+            hits: Vec::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<Vec<(f32, DocAddress)>>,
+    ) -> tv::Result<Vec<(f32, DocAddress)>> {
+        let mut hits: Vec<_> = segment_fruits.into_iter().flatten().collect();
+        top_k(&mut hits, self.k);
+        Ok(hits)
+    }
+}
+
+pub(crate) struct KnnSegmentCollector {
+    segment_ord: SegmentOrdinal,
+    ords: Option<Column<u64>>,
+    ranks: Vec<f32>,
+    k: usize,
+    hits: Vec<(f32, DocAddress)>,
+}
+
+impl KnnSegmentCollector {
+    fn empty(segment_ord: SegmentOrdinal, k: usize) -> Self {
+        KnnSegmentCollector {
+            segment_ord,
+            ords: None,
+            ranks: Vec::new(),
+            k,
+            hits: Vec::new(),
+        }
+    }
+}
+
+impl SegmentCollector for KnnSegmentCollector {
+    type Fruit = Vec<(f32, DocAddress)>;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        let Some(ord) = self.ords.as_ref().and_then(|ords| ords.first(doc))
+        else {
+            return;
+        };
+        let rank = self.ranks[ord as usize];
+        if rank.is_nan() {
+            return;
+        }
+        self.hits
+            .push((rank, DocAddress::new(self.segment_ord, doc)));
+        // Prune from time to time to keep the memory bounded by `k`.
+        if self.hits.len() >= 2 * self.k.max(512) {
+            top_k(&mut self.hits, self.k);
+        }
+    }
+
+    fn harvest(mut self) -> Vec<(f32, DocAddress)> {
+        top_k(&mut self.hits, self.k);
+        self.hits
+    }
+}
@@ -1,6 +1,7 @@
 import datetime
+import os
 from enum import Enum
-from typing import Any, Optional, Sequence, TypeVar, Union
+from typing import Any, Awaitable, Callable, Iterable, Optional, Sequence, TypeVar, Union
 
 class Schema:
     pass
@@ -10,10 +11,17 @@ class SchemaBuilder:
     def is_valid_field_name(name: str) -> bool:
         pass
 
+    @staticmethod
+    def from_typed_dict(
+        cls: type, overrides: Optional[dict[str, Optional[dict[str, Any]]]] = None
+    ) -> SchemaBuilder:
+        pass
+
     def add_text_field(
         self,
         name: str,
         stored: bool = False,
+        fast: bool = False,
         tokenizer_name: str = "default",
         index_option: str = "position",
     ) -> SchemaBuilder:
@@ -68,6 +76,7 @@ class SchemaBuilder:
         self,
         name: str,
         stored: bool = False,
+        fast: bool = False,
         tokenizer_name: str = "default",
         index_option: str = "position",
     ) -> SchemaBuilder:
@@ -98,6 +107,25 @@ class SchemaBuilder:
     ) -> SchemaBuilder:
         pass
 
+    def add_vector_field(
+        self, name: str, dim: int, metric: str = "cosine"
+    ) -> SchemaBuilder:
+        pass
+
+    def add_geopoint_field(
+        self, name: str, stored: bool = False, fast: bool = True
+    ) -> SchemaBuilder:
+        pass
+
+    def add_signature_field(
+        self,
+        name: str,
+        source_field: str,
+        kind: str = "simhash",
+        num_hashes: int = 64,
+    ) -> SchemaBuilder:
+        pass
+
     def build(self) -> Schema:
         pass
 
@@ -114,6 +142,10 @@ class Facet:
     def from_string(cls, facet_string: str) -> Facet:
         pass
 
+    @classmethod
+    def from_path(cls, segments: Sequence[str]) -> Facet:
+        pass
+
     @property
     def is_root(self) -> bool:
         pass
@@ -127,6 +159,22 @@ class Facet:
     def to_path_str(self) -> str:
         pass
 
+class FacetCounts:
+    @classmethod
+    def merge(cls, counts: list[FacetCounts]) -> FacetCounts:
+        pass
+
+    def get(self, facet: Facet) -> list[tuple[Facet, int]]:
+        pass
+
+    def top_k(self, facet: Facet, k: int) -> list[tuple[Facet, int]]:
+        pass
+
+    def to_tree(
+        self, root: Optional[Facet] = None, depth: Optional[int] = None
+    ) -> dict[str, Any]:
+        pass
+
 class Document:
     def __new__(cls, **kwargs) -> Document:
         pass
@@ -138,7 +186,11 @@ class Document:
     def from_dict(py_dict: dict, schema: Optional[Schema] = None) -> Document:
         pass
 
-    def to_dict(self) -> Any:
+    @staticmethod
+    def from_object(obj: Any, schema: Schema) -> Document:
+        pass
+
+    def to_dict(self, raw_json: bool = False) -> Any:
         pass
 
     def add_text(self, field_name: str, text: str) -> None:
@@ -159,10 +211,12 @@ class Document:
     def add_date(self, field_name: str, value: datetime.datetime) -> None:
         pass
 
-    def add_facet(self, field_name: str, facet: Facet) -> None:
+    def add_facet(
+        self, field_name: str, facet: Union[Facet, str, Sequence[str]]
+    ) -> None:
         pass
 
-    def add_bytes(self, field_name: str, bytes: bytes) -> None:
+    def add_bytes(self, field_name: str, bytes: Union[bytes, bytearray, memoryview, Bytes]) -> None:
         pass
 
     def add_json(self, field_name: str, value: Any) -> None:
@@ -171,6 +225,12 @@ class Document:
     def add_ip_addr(self, field_name: str, ip_addr: str) -> None:
         pass
 
+    def add_vector(self, field_name: str, vector: Sequence[float]) -> None:
+        pass
+
+    def add_geopoint(self, field_name: str, lat: float, lon: float) -> None:
+        pass
+
     @property
     def num_fields(self) -> int:
         pass
@@ -179,10 +239,36 @@ class Document:
     def is_empty(self) -> bool:
         pass
 
-    def get_first(self, field_name: str) -> Optional[Any]:
+    def get_first(self, field_name: str, raw_json: bool = False) -> Optional[Any]:
+        pass
+
+    def get_all(self, field_name: str, raw_json: bool = False) -> list[Any]:
+        pass
+
+    def get_text(self, field_name: str, default: Any = None) -> Optional[str]:
+        pass
+
+    def get_int(self, field_name: str, default: Any = None) -> Optional[int]:
+        pass
+
+    def get_float(self, field_name: str, default: Any = None) -> Optional[float]:
+        pass
+
+    def get_datetime(
+        self, field_name: str, default: Any = None
+    ) -> Optional[datetime.datetime]:
+        pass
+
+    def get_bool(self, field_name: str, default: Any = None) -> Optional[bool]:
+        pass
+
+    def get_bytes(self, field_name: str, default: Any = None) -> Optional[bytes]:
+        pass
+
+    def get_json(self, field_name: str, default: Any = None) -> Optional[dict]:
         pass
 
-    def get_all(self, field_name: str) -> list[Any]:
+    def diff(self, other: Document) -> dict[str, dict[str, Any]]:
         pass
 
 class Occur(Enum):
@@ -239,7 +325,10 @@ class Query:
 
 
     @staticmethod
-    def boolean_query(subqueries: Sequence[tuple[Occur, Query]]) -> Query:
+    def boolean_query(
+        subqueries: Sequence[tuple[Occur, Query]],
+        minimum_should_match: Optional[Union[int, str]] = None,
+    ) -> Query:
         pass
 
     @staticmethod
@@ -269,6 +358,10 @@ class Query:
     ) -> Query:
         pass
 
+    @staticmethod
+    def filtered(scoring_query: Query, filter_query: Query) -> Query:
+        pass
+
     @staticmethod
     def const_score_query(query: Query, score: float) -> Query:
         pass
@@ -284,6 +377,77 @@ class Query:
         include_upper: bool = True,
     ) -> Query:
         pass
+
+    @staticmethod
+    def geo_bounding_box_query(
+        schema: Schema,
+        field_name: str,
+        min_lat: float,
+        min_lon: float,
+        max_lat: float,
+        max_lon: float,
+    ) -> Query:
+        pass
+
+    @staticmethod
+    def geo_distance_query(
+        schema: Schema, field_name: str, lat: float, lon: float, radius_m: float
+    ) -> Query:
+        pass
+
+    @staticmethod
+    def block_join_query(
+        parent_filter: Query, child_query: Query, score_mode: str = "avg"
+    ) -> Query:
+        pass
+
+    def __and__(self, other: Query) -> Query:
+        pass
+
+    def __or__(self, other: Query) -> Query:
+        pass
+
+    def __invert__(self) -> Query:
+        pass
+
+    def extract_terms(self, schema: Schema) -> list[tuple[str, Any]]:
+        pass
+
+class QueryBuilder:
+    def __init__(self, schema: Schema) -> None:
+        pass
+
+    def term(self, field_name: str, value: Any) -> Query:
+        pass
+
+    def terms(self, field_name: str, values: Sequence[Any]) -> Query:
+        pass
+
+    def range(
+        self,
+        field_name: str,
+        lower: Optional[Any] = None,
+        upper: Optional[Any] = None,
+        include_lower: bool = True,
+        include_upper: bool = True,
+    ) -> Query:
+        pass
+
+    def phrase(
+        self, field_name: str, words: list[Union[str, tuple[int, str]]], slop: int = 0
+    ) -> Query:
+        pass
+
+    def fuzzy(
+        self, field_name: str, text: str, distance: int = 1, prefix: bool = False
+    ) -> Query:
+        pass
+
+    def regex(self, field_name: str, pattern: str) -> Query:
+        pass
+
+    def all(self) -> Query:
+        pass
  
 
 class Order(Enum):
@@ -302,142 +466,1045 @@ class DocAddress:
     def doc(self) -> int:
         pass
 
+    def __hash__(self) -> int:
+        pass
+
+    def __lt__(self, other: DocAddress) -> bool:
+        pass
+
+    def __le__(self, other: DocAddress) -> bool:
+        pass
+
+    def __gt__(self, other: DocAddress) -> bool:
+        pass
+
+    def __ge__(self, other: DocAddress) -> bool:
+        pass
+
+    def pack(self) -> int:
+        pass
+
+    @staticmethod
+    def unpack(packed: int) -> DocAddress:
+        pass
+
+class Bytes:
+    def __init__(self, data: Union[bytes, bytearray, memoryview, Sequence[int]]) -> None:
+        pass
+
+    def __len__(self) -> int:
+        pass
+
+    def __bytes__(self) -> bytes:
+        pass
+
+    def __buffer__(self, flags: int) -> memoryview:
+        pass
+
 class SearchResult:
     @property
     def hits(self) -> list[tuple[Any, DocAddress]]:
         pass
 
-class Searcher:
-    def search(
-        self,
-        query: Query,
+    @property
+    def count(self) -> Optional[int]:
+        pass
+
+    @property
+    def count_relation(self) -> Optional[str]:
+        pass
+
+    def stats(self, field_name: str) -> dict[str, Optional[float]]:
+        pass
+
+    def facet_counts(self, field_name: str) -> FacetCounts:
+        pass
+
+    @classmethod
+    def merge(
+        cls,
+        results: list[SearchResult],
         limit: int = 10,
-        count: bool = True,
-        order_by_field: Optional[str] = None,
-        offset: int = 0,
         order: Order = Order.Desc,
     ) -> SearchResult:
         pass
 
-    def aggregate(
+    @property
+    def origins(self) -> Optional[list[int]]:
+        pass
+
+    @property
+    def profile(self) -> Optional[dict[str, Any]]:
+        pass
+
+    def rerank(
         self,
-        search_query: Query,
-        agg_query: dict,
-    ) -> dict:
+        scores: Union[
+            dict[DocAddress, float],
+            dict[tuple[int, DocAddress], float],
+            Sequence[float],
+        ],
+        top_k: Optional[int] = None,
+    ) -> SearchResult:
+        pass
+
+    def to_table(
+        self,
+        fields: list[str],
+        searcher: Searcher,
+        all_values: bool = False,
+        format: str = "pydict",
+    ) -> Any:
+        pass
+
+class Bitset:
+    def __len__(self) -> int:
+        pass
+
+    def __and__(self, other: Bitset) -> Bitset:
         pass
 
+    def __or__(self, other: Bitset) -> Bitset:
+        pass
+
+class FeatureMatrix:
     @property
-    def num_docs(self) -> int:
+    def shape(self) -> tuple[int, int]:
         pass
 
     @property
-    def num_segments(self) -> int:
+    def feature_names(self) -> list[str]:
         pass
 
-    def doc(self, doc_address: DocAddress) -> Document:
+    def tolist(self) -> list[list[float]]:
         pass
 
-    def doc_freq(self, field_name: str, field_value: Any) -> int:
+    def __len__(self) -> int:
         pass
 
-class IndexWriter:
-    def add_document(self, doc: Document) -> int:
+class PinnedSearcher:
+    @property
+    def searcher(self) -> Searcher:
         pass
 
-    def add_json(self, json: str) -> int:
+    def searcher_at(self, commit: Union[int, str, os.PathLike]) -> Searcher:
         pass
 
-    def commit(self) -> int:
+    @property
+    def released(self) -> bool:
         pass
 
-    def rollback(self) -> int:
+    def release(self) -> None:
         pass
 
-    def garbage_collect_files(self) -> None:
+    def __enter__(self) -> Searcher:
         pass
 
-    def delete_all_documents(self) -> None:
+    def __exit__(self, exc_type, exc_value, traceback) -> bool:
         pass
 
+class Scroll:
     @property
-    def commit_opstamp(self) -> int:
+    def searcher(self) -> Searcher:
         pass
 
-    def delete_documents(self, field_name: str, field_value: Any) -> int:
+    def __iter__(self) -> Scroll:
         pass
 
-    def wait_merging_threads(self) -> None:
+    def __next__(self) -> list[tuple[float, DocAddress]]:
         pass
 
-class Index:
-    def __new__(
-        cls, schema: Schema, path: Optional[str] = None, reuse: bool = True
-    ) -> Index:
+class FetchSession:
+    @property
+    def searcher(self) -> Searcher:
         pass
 
-    @staticmethod
-    def open(path: str) -> Index:
+    def doc(self, doc_address: DocAddress) -> Document:
         pass
 
-    def writer(self, heap_size: int = 128_000_000, num_threads: int = 0) -> IndexWriter:
+    def docs(self, doc_addresses: list[DocAddress]) -> list[Document]:
         pass
 
-    def config_reader(
-        self, reload_policy: str = "commit", num_warmers: int = 0
-    ) -> None:
+    def close(self) -> None:
         pass
 
-    def searcher(self) -> Searcher:
+    def __enter__(self) -> FetchSession:
         pass
 
-    @staticmethod
-    def exists(path: str) -> bool:
+    def __exit__(self, exc_type, exc_value, traceback) -> bool:
         pass
 
-    @property
-    def schema(self) -> Schema:
+class MultiCollector:
+    def __init__(self) -> None:
         pass
 
-    def reload(self) -> None:
+    def add_top_docs(
+        self, name: str, limit: int = 10, offset: int = 0
+    ) -> MultiCollector:
         pass
 
-    def parse_query(
-        self, query: str, default_field_names: Optional[list[str]] = None
-    ) -> Query:
+    def add_count(self, name: str) -> MultiCollector:
         pass
 
-    def parse_query_lenient(
-        self, query: str, default_field_names: Optional[list[str]] = None
-    ) -> Query:
+    def add_facets(
+        self,
+        name: str,
+        field_name: str,
+        facets: Optional[list[Facet]] = None,
+    ) -> MultiCollector:
         pass
 
-class Range:
-    @property
-    def start(self) -> int:
+    def add_histogram(
+        self, name: str, field_name: str, interval: float
+    ) -> MultiCollector:
+        pass
+
+    def add_stats(self, name: str, field_name: str) -> MultiCollector:
         pass
 
     @property
-    def end(self) -> int:
+    def names(self) -> list[str]:
         pass
 
-class Snippet:
-    def to_html(self) -> str:
+    def __len__(self) -> int:
         pass
 
-    def highlighted(self) -> list[Range]:
+class Searcher:
+    def search(
+        self,
+        query: Query,
+        limit: int = 10,
+        count: bool = True,
+        order_by_field: Optional[Union[str, tuple[str, Order, str]]] = None,
+        offset: int = 0,
+        order: Order = Order.Desc,
+        sort_by_distance: Optional[tuple[str, float, float]] = None,
+        boost_by_distance: Optional[tuple[str, float, float, float]] = None,
+        profile: bool = False,
+        diversify_by: Optional[tuple[str, int]] = None,
+        pre_filter: Optional[Bitset] = None,
+        score_expr: Optional[str] = None,
+        expr_fields: Sequence[str] = (),
+        missing: Optional[Union[str, int, float, bool, datetime.datetime]] = None,
+        expr_signals: Sequence[str] = (),
+        collector: Optional[MultiCollector] = None,
+    ) -> SearchResult:
         pass
 
-class SnippetGenerator:
-    @staticmethod
-    def create(
-        searcher: Searcher, query: Query, schema: Schema, field_name: str
-    ) -> SnippetGenerator:
+    def search_async(
+        self,
+        query: Query,
+        limit: int = 10,
+        count: bool = True,
+        order_by_field: Optional[Union[str, tuple[str, Order, str]]] = None,
+        offset: int = 0,
+        order: Order = Order.Desc,
+        sort_by_distance: Optional[tuple[str, float, float]] = None,
+        boost_by_distance: Optional[tuple[str, float, float, float]] = None,
+        profile: bool = False,
+        diversify_by: Optional[tuple[str, int]] = None,
+        pre_filter: Optional[Bitset] = None,
+        score_expr: Optional[str] = None,
+        expr_fields: Sequence[str] = (),
+        missing: Optional[Union[str, int, float, bool, datetime.datetime]] = None,
+        expr_signals: Sequence[str] = (),
+        collector: Optional[MultiCollector] = None,
+    ) -> Awaitable[SearchResult]:
+        pass
+
+    def knn_search(
+        self,
+        field_name: str,
+        query_vector: Sequence[float],
+        k: int = 10,
+        filter_query: Optional[Query] = None,
+    ) -> SearchResult:
         pass
 
-    def snippet_from_doc(self, doc: Document) -> Snippet:
+    def find_near_duplicates(
+        self,
+        doc_address: DocAddress,
+        hamming_distance: int = 3,
+        field_name: Optional[str] = None,
+    ) -> SearchResult:
         pass
 
-    def set_max_num_chars(self, max_num_chars: int) -> None:
+    def hybrid_search(
+        self,
+        query: Query,
+        vector_or_scores: Union[
+            tuple[str, Sequence[float]], Sequence[tuple[float, DocAddress]]
+        ],
+        k: int = 10,
+        fusion: str = "rrf",
+        weights: tuple[float, float] = (1.0, 1.0),
+        num_candidates: Optional[int] = None,
+        rrf_k: int = 60,
+    ) -> SearchResult:
+        pass
+
+    def aggregate(
+        self,
+        search_query: Query,
+        agg_query: dict,
+        missing: Optional[dict[str, Union[float, str]]] = None,
+    ) -> dict:
+        pass
+
+    @property
+    def num_docs(self) -> int:
+        pass
+
+    @property
+    def num_segments(self) -> int:
+        pass
+
+    @property
+    def generation(self) -> int:
+        pass
+
+    def doc(self, doc_address: DocAddress) -> Document:
+        pass
+
+    def doc_freq(self, field_name: str, field_value: Any) -> int:
+        pass
+
+    def aggregate_async(
+        self,
+        search_query: Query,
+        agg_query: dict,
+        missing: Optional[dict[str, Union[float, str]]] = None,
+    ) -> Awaitable[dict]:
+        pass
+
+    def date_histogram(
+        self,
+        query: Query,
+        field_name: str,
+        interval: str = "1d",
+        timezone: str = "UTC",
+        extended_bounds: Optional[tuple[datetime.datetime, datetime.datetime]] = None,
+    ) -> list[tuple[datetime.datetime, int]]:
+        pass
+
+    def top_terms(
+        self,
+        query: Query,
+        field_name: str,
+        k: int = 10,
+        metrics: Optional[dict[str, tuple[str, str]]] = None,
+    ) -> list[tuple[Any, int, dict[str, Optional[float]]]]:
+        pass
+
+    def facet_counts(
+        self,
+        query: Query,
+        field_name: str,
+        facets: Optional[list[Facet]] = None,
+    ) -> FacetCounts:
+        pass
+
+    def approx_count(
+        self, query: Query, max_error: float = 0.01
+    ) -> dict[str, Any]:
+        pass
+
+    def collect(
+        self, query: Query, collector: MultiCollector
+    ) -> dict[str, Any]:
+        pass
+
+    def top_terms_by_doc_freq(
+        self, field_name: str, k: int = 10
+    ) -> list[tuple[str, int]]:
+        pass
+
+    def fast_field_values(
+        self, doc_address: DocAddress, field_name: str
+    ) -> list[Any]:
+        pass
+
+    def field_density(self, field_name: str) -> dict[str, Any]:
+        pass
+
+    def doc_store_cache_stats(self) -> dict[str, int]:
+        pass
+
+    def extract_features(
+        self,
+        query: Query,
+        doc_addresses: Sequence[DocAddress],
+        features: Sequence[str],
+    ) -> FeatureMatrix:
+        pass
+
+    def term_vector(
+        self, doc_address: DocAddress, field_name: str
+    ) -> dict[str, tuple[int, list[int]]]:
+        pass
+
+    def significant_terms(
+        self,
+        doc_address_or_query: Union[DocAddress, Query],
+        field_name: str,
+        k: int = 10,
+    ) -> list[tuple[str, float]]:
+        pass
+
+    def matching_bitset(self, query: Query) -> Bitset:
+        pass
+
+    def enable_query_cache(self, max_entries: int, max_bytes: int) -> None:
+        pass
+
+    def query_cache_stats(self) -> dict[str, int]:
+        pass
+
+    def with_filter(
+        self, query_or_callable: Union[Query, Callable[[], Query]]
+    ) -> Searcher:
+        pass
+
+    def with_search_limits(
+        self,
+        max_limit: Optional[int] = None,
+        max_offset: Optional[int] = None,
+        max_clauses: Optional[int] = None,
+        max_expanded_terms: Optional[int] = None,
+        max_regex_states: Optional[int] = None,
+    ) -> Searcher:
+        pass
+
+    def pin(self) -> PinnedSearcher:
+        pass
+
+    def scroll(
+        self, query: Query, batch_size: int = 1000, keep_segments: bool = True
+    ) -> Scroll:
+        pass
+
+    def fetch_session(self, cache_blocks: int = 8) -> FetchSession:
+        pass
+
+class IndexWriter:
+    def add_document(self, doc: Document) -> int:
+        pass
+
+    def add_json(self, json: str) -> int:
+        pass
+
+    def add_document_block(self, docs: list[Document]) -> int:
+        pass
+
+    def add_documents(
+        self,
+        docs: Iterable[Document],
+        on_progress: Optional[Callable[[int, Optional[int]], None]] = None,
+    ) -> int:
+        pass
+
+    def add_documents_parallel(
+        self, batches: Iterable[Union[bytes, str]], queue_size: int = 4
+    ) -> int:
+        pass
+
+    def reindex(
+        self,
+        searcher: Searcher,
+        on_progress: Optional[Callable[[int, int], None]] = None,
+    ) -> int:
+        pass
+
+    def merge(
+        self, on_progress: Optional[Callable[[int, int], None]] = None
+    ) -> None:
+        pass
+
+    def commit(self) -> int:
+        pass
+
+    def rollback(self) -> int:
+        pass
+
+    def garbage_collect_files(self) -> None:
+        pass
+
+    def delete_all_documents(self) -> None:
+        pass
+
+    @property
+    def commit_opstamp(self) -> int:
+        pass
+
+    def delete_documents(self, field_name: str, field_value: Any) -> int:
+        pass
+
+    def update_fast_field(
+        self, term_field: str, term_value: Any, values: dict[str, Any]
+    ) -> int:
+        pass
+
+    def set_commit_meta(self, meta: Optional[dict[str, Any]]) -> None:
+        pass
+
+    @property
+    def last_commit_opstamp(self) -> int:
+        pass
+
+    def enable_auto_commit(
+        self, interval_secs: Optional[float] = None, max_docs: Optional[int] = None
+    ) -> None:
+        pass
+
+    @property
+    def max_mb_per_sec(self) -> Optional[float]:
+        pass
+
+    @max_mb_per_sec.setter
+    def max_mb_per_sec(self, max_mb_per_sec: Optional[float]) -> None:
+        pass
+
+    def disable_auto_commit(self) -> None:
+        pass
+
+    def clone(self) -> IndexWriter:
+        pass
+
+    def wait_merging_threads(self) -> None:
+        pass
+
+    def __enter__(self) -> IndexWriter:
+        pass
+
+    def __exit__(self, exc_type, exc_value, traceback) -> bool:
+        pass
+
+class TantivyError(ValueError):
+    pass
+
+class QueryParserError(TantivyError):
+    error: Any
+    field: Optional[str]
+
+class SchemaError(TantivyError):
+    field: Optional[str]
+
+class LockError(TantivyError):
+    busy: bool
+
+class LockAcquisitionError(LockError):
+    pass
+
+class QueryTooComplexError(TantivyError):
+    limit: str
+    value: int
+    max: int
+
+class IndexCorruptedError(TantivyError):
+    pass
+
+class IoError(TantivyError):
+    filepath: Optional[str]
+
+class IndexVersionError(TantivyError):
+    pass
+
+class IndexMeta:
+    @property
+    def opstamp(self) -> int:
+        pass
+
+    @property
+    def payload(self) -> Optional[str]:
+        pass
+
+    @property
+    def num_segments(self) -> int:
+        pass
+
+class IndexSettings:
+    def __new__(
+        cls,
+        sort_by: Optional[tuple[str, Order]] = None,
+        docstore_compression: str = "lz4",
+        docstore_blocksize: int = 16384,
+        docstore_compression_level: Optional[int] = None,
+    ) -> IndexSettings:
+        pass
+
+    @property
+    def sort_by(self) -> Optional[tuple[str, Order]]:
+        pass
+
+    @property
+    def docstore_compression(self) -> str:
+        pass
+
+    @property
+    def docstore_compression_level(self) -> Optional[int]:
+        pass
+
+    @property
+    def docstore_blocksize(self) -> int:
+        pass
+
+class CompactionPolicy:
+    def __new__(
+        cls,
+        interval_secs: float = 60.0,
+        max_segments: int = 10,
+        max_deleted_ratio: float = 0.2,
+        max_mb_per_sec: Optional[float] = None,
+    ) -> CompactionPolicy:
+        pass
+
+    @property
+    def interval_secs(self) -> float:
+        pass
+
+    @property
+    def max_segments(self) -> int:
+        pass
+
+    @property
+    def max_deleted_ratio(self) -> float:
+        pass
+
+    @property
+    def max_mb_per_sec(self) -> Optional[float]:
+        pass
+
+class SpellCorrector:
+    def suggest(
+        self, word: str, max_edits: int = 2, limit: int = 5
+    ) -> list[str]:
+        pass
+
+class Suggester:
+    def suggest(
+        self, prefix: str, limit: int = 10, max_edits: int = 0
+    ) -> list[str]:
+        pass
+
+    def __len__(self) -> int:
+        pass
+
+class Percolator:
+    def __new__(cls, schema: Schema) -> Percolator:
+        pass
+
+    def add_query(self, id: str, query: Query) -> None:
+        pass
+
+    def remove_query(self, id: str) -> bool:
+        pass
+
+    def match_document(self, doc: Document) -> list[str]:
+        pass
+
+    def match_documents(self, docs: Sequence[Document]) -> list[list[str]]:
+        pass
+
+    def __len__(self) -> int:
+        pass
+
+class Evaluator:
+    def __new__(cls, searcher: Searcher, id_field: str) -> Evaluator:
+        pass
+
+    def evaluate(
+        self,
+        queries_with_judgments: Sequence[tuple[Query, dict[Any, float]]],
+        metric: str = "ndcg@10",
+    ) -> dict[str, Any]:
+        pass
+
+class Index:
+    def __new__(
+        cls,
+        schema: Schema,
+        path: Optional[Union[str, os.PathLike]] = None,
+        reuse: bool = True,
+        settings: Optional[IndexSettings] = None,
+        read_mode: str = "mmap",
+        madvise: Optional[str] = None,
+        cache_mb: Optional[float] = None,
+    ) -> Index:
+        pass
+
+    @staticmethod
+    def open(
+        path: Union[str, os.PathLike],
+        on_corruption: str = "raise",
+        read_mode: str = "mmap",
+        madvise: Optional[str] = None,
+        cache_mb: Optional[float] = None,
+    ) -> Index:
+        pass
+
+    @property
+    def settings(self) -> IndexSettings:
+        pass
+
+    def writer(
+        self,
+        heap_size: int = 128_000_000,
+        num_threads: int = 0,
+        block: bool = False,
+        lock_timeout: Optional[float] = None,
+        max_mb_per_sec: Optional[float] = None,
+    ) -> IndexWriter:
+        pass
+
+    def config_reader(
+        self,
+        reload_policy: str = "commit",
+        num_warmers: int = 0,
+        doc_store_cache_num_blocks: int = 100,
+    ) -> None:
+        pass
+
+    def set_search_limits(
+        self,
+        max_limit: Optional[int] = None,
+        max_offset: Optional[int] = None,
+        max_clauses: Optional[int] = None,
+        max_expanded_terms: Optional[int] = None,
+        max_regex_states: Optional[int] = None,
+    ) -> None:
+        pass
+
+    # Only available when built with the `flight` cargo feature.
+    def flight_server(
+        self, port: int = 8815, host: str = "127.0.0.1", num_threads: int = 4
+    ) -> FlightServer:
+        pass
+
+    def set_field_aliases(self, aliases: dict[str, str]) -> None:
+        pass
+
+    @property
+    def field_aliases(self) -> dict[str, str]:
+        pass
+
+    def signals(self, key_field: Optional[str] = None) -> SignalStore:
+        pass
+
+    def searcher(self) -> Searcher:
+        pass
+
+    @staticmethod
+    def exists(path: Union[str, os.PathLike]) -> bool:
+        pass
+
+    def meta(self) -> IndexMeta:
+        pass
+
+    def commit_meta(self, opstamp: Optional[int] = None) -> Optional[dict[str, Any]]:
+        pass
+
+    def spell_corrector(
+        self, field_name: str, min_doc_freq: int = 1
+    ) -> SpellCorrector:
+        pass
+
+    def suggester(
+        self, field_name: str, weight_field: Optional[str] = None
+    ) -> Suggester:
+        pass
+
+    @property
+    def schema(self) -> Schema:
+        pass
+
+    @property
+    def skipped_segments(self) -> list[str]:
+        pass
+
+    def start_compaction(self, policy: Optional[CompactionPolicy] = None) -> None:
+        pass
+
+    def stop_compaction(self) -> None:
+        pass
+
+    def num_active_searchers(self) -> int:
+        pass
+
+    @property
+    def reader_generation(self) -> int:
+        pass
+
+    def reload(self) -> None:
+        pass
+
+    def field_space_usage(self) -> dict[str, dict[str, int]]:
+        pass
+
+    def stats(self) -> dict[str, Any]:
+        pass
+
+    def validate_checksum(self) -> list[str]:
+        pass
+
+    def check(self, full: bool = False) -> dict[str, Any]:
+        pass
+
+    def parse_query(
+        self,
+        query: str,
+        default_field_names: Optional[list[str]] = None,
+        field_boosts: dict[str, float] = {},
+        fuzzy_fields: dict[str, tuple[bool, int, bool]] = {},
+        max_doc_frequency: Optional[float] = None,
+        plain_text: bool = False,
+        conjunction_by_default: bool = False,
+        field_analyzers: dict[str, str] = {},
+    ) -> Query:
+        pass
+
+    def parse_query_lenient(
+        self,
+        query: str,
+        default_field_names: Optional[list[str]] = None,
+        field_boosts: dict[str, float] = {},
+        fuzzy_fields: dict[str, tuple[bool, int, bool]] = {},
+        max_doc_frequency: Optional[float] = None,
+        field_analyzers: dict[str, str] = {},
+    ) -> tuple[Query, list[Any]]:
+        pass
+
+    def parse_simple_query(
+        self,
+        query: str,
+        default_field_names: Optional[list[str]] = None,
+        conjunction_by_default: bool = False,
+    ) -> Query:
+        pass
+
+    def validate_query(
+        self, query: str, default_field_names: Optional[list[str]] = None
+    ) -> dict[str, Any]:
+        pass
+
+    def analyze_query_terms(self, field_name: str, text: str) -> dict[str, Any]:
+        pass
+
+class IndexCatalog:
+    def __new__(cls, root_path: Union[str, os.PathLike]) -> IndexCatalog:
+        pass
+
+    def swap(
+        self, alias: str, index_path: Union[str, os.PathLike]
+    ) -> Optional[str]:
+        pass
+
+    def remove(self, alias: str) -> str:
+        pass
+
+    def resolve(self, alias: str) -> str:
+        pass
+
+    def open(
+        self,
+        alias: str,
+        on_corruption: str = "raise",
+        read_mode: str = "mmap",
+        madvise: Optional[str] = None,
+        cache_mb: Optional[float] = None,
+    ) -> Index:
+        pass
+
+    def aliases(self) -> dict[str, str]:
+        pass
+
+class TenantIndex:
+    def __new__(cls, index: Index, tenant_field: str, tenant: Any) -> TenantIndex:
+        pass
+
+    @property
+    def index(self) -> Index:
+        pass
+
+    @property
+    def tenant_field(self) -> str:
+        pass
+
+    @property
+    def tenant(self) -> Any:
+        pass
+
+    def writer(
+        self,
+        heap_size: int = 128_000_000,
+        num_threads: int = 0,
+        block: bool = False,
+        lock_timeout: Optional[float] = None,
+        max_mb_per_sec: Optional[float] = None,
+    ) -> IndexWriter:
+        pass
+
+    def searcher(self) -> Searcher:
+        pass
+
+class SignalStore:
+    @property
+    def key_field(self) -> Optional[str]:
+        pass
+
+    def set(self, key: Union[str, int], signals: dict[str, float]) -> None:
+        pass
+
+    def set_many(
+        self, signals: dict[Union[str, int], dict[str, float]]
+    ) -> None:
+        pass
+
+    def get(self, key: Union[str, int]) -> Optional[dict[str, float]]:
+        pass
+
+    def delete(self, key: Union[str, int]) -> bool:
+        pass
+
+    def reload(self) -> None:
+        pass
+
+    def __len__(self) -> int:
+        pass
+
+class MultiIndexReader:
+    def __new__(cls, indexes: list[Index]) -> MultiIndexReader:
+        pass
+
+    @property
+    def indexes(self) -> list[Index]:
+        pass
+
+    @property
+    def generation(self) -> int:
+        pass
+
+    def reload(self) -> None:
+        pass
+
+    def acquire(self) -> MultiSearcher:
+        pass
+
+class MultiSearcher:
+    @property
+    def generation(self) -> int:
+        pass
+
+    @property
+    def searchers(self) -> list[Searcher]:
+        pass
+
+    def __len__(self) -> int:
+        pass
+
+    def __getitem__(self, index: int) -> Searcher:
+        pass
+
+class Range:
+    @property
+    def start(self) -> int:
+        pass
+
+    @property
+    def end(self) -> int:
+        pass
+
+class Snippet:
+    def to_html(self) -> str:
+        pass
+
+    def highlighted(self) -> list[Range]:
+        pass
+
+class SnippetGenerator:
+    @staticmethod
+    def create(
+        searcher: Searcher, query: Query, schema: Schema, field_name: str
+    ) -> SnippetGenerator:
+        pass
+
+    def snippet_from_doc(self, doc: Document) -> Snippet:
+        pass
+
+    def set_max_num_chars(self, max_num_chars: int) -> None:
+        pass
+
+def set_metrics_callback(
+    callback: Optional[Callable[[dict[str, Any]], None]],
+) -> None:
+    pass
+
+def metrics_prometheus(indexes: Optional[dict[str, Index]] = None) -> str:
+    pass
+
+def escape_query_string(text: str) -> str:
+    pass
+
+def configure_logging(
+    level: Union[int, str] = "WARNING", logger_name: str = "tantivy"
+) -> None:
+    pass
+
+def disable_logging() -> None:
+    pass
+
+# Only available when built with the `server` cargo feature.
+class SearchServer:
+    @property
+    def host(self) -> str:
+        pass
+
+    @property
+    def port(self) -> int:
+        pass
+
+    @property
+    def running(self) -> bool:
+        pass
+
+    def stop(self) -> None:
+        pass
+
+    def __enter__(self) -> SearchServer:
+        pass
+
+    def __exit__(self, exc_type, exc_value, traceback) -> None:
+        pass
+
+def serve(
+    index: Index, host: str = "127.0.0.1", port: int = 8080, num_threads: int = 4
+) -> SearchServer:
+    pass
+
+# Only available when built with the `flight` cargo feature.
+class FlightServer:
+    @property
+    def host(self) -> str:
+        pass
+
+    @property
+    def port(self) -> int:
+        pass
+
+    @property
+    def location(self) -> str:
+        pass
+
+    @property
+    def running(self) -> bool:
+        pass
+
+    def stop(self) -> None:
+        pass
+
+    def __enter__(self) -> FlightServer:
+        pass
+
+    def __exit__(self, exc_type, exc_value, traceback) -> None:
         pass
 
 __version__: str
@@ -41,6 +41,14 @@ def schema_with_ip_addr_field():
         .build()
     )
 
+def schema_with_geo_field():
+    return (
+        SchemaBuilder()
+        .add_text_field("city", stored=True)
+        .add_geopoint_field("location")
+        .build()
+    )
+
 def create_index(dir=None):
     # assume all tests will use the same documents for now
     # other methods may set up function-local indexes
@@ -197,6 +205,27 @@ def create_index_with_ip_addr_field(dir=None):
     index.reload()
     return index
 
+def create_index_with_geo_field(dir=None):
+    schema = schema_with_geo_field()
+    index = Index(schema, dir)
+    writer = index.writer(15_000_000, 1)
+
+    for city, lat, lon in [
+        ("Paris", 48.8566, 2.3522),
+        ("London", 51.5074, -0.1278),
+        ("Berlin", 52.52, 13.405),
+        ("Suva", -18.1416, 178.4419),
+        ("Apia", -13.8333, -171.7667),
+    ]:
+        doc = Document(city=city)
+        doc.add_geopoint("location", lat, lon)
+        writer.add_document(doc)
+    writer.add_document(Document(city="Atlantis"))
+    writer.commit()
+    writer.wait_merging_threads()
+    index.reload()
+    return index
+
 def spanish_schema():
     return (
         SchemaBuilder()
@@ -270,6 +299,10 @@ def ram_index_with_date_field():
 def ram_index_with_ip_addr_field():
     return create_index_with_ip_addr_field()
 
+@pytest.fixture(scope="class")
+def ram_index_with_geo_field():
+    return create_index_with_geo_field()
+
 @pytest.fixture(scope="class")
 def spanish_index():
     return create_spanish_index()
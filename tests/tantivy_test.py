@@ -1,13 +1,18 @@
 from io import BytesIO
+from typing import Optional, TypedDict
 
 import copy
+import dataclasses
 import datetime
 import json
+import math
+import os
+import pathlib
 import pickle
 import pytest
 
 import tantivy
-from conftest import schema, schema_numeric_fields
+from conftest import create_index, schema, schema_numeric_fields
 from tantivy import Document, Index, SchemaBuilder, SnippetGenerator, Query, Occur, FieldType
 
 
@@ -124,6 +129,462 @@ class TestClass(object):
 }
 """)
 
+    def test_aggregate_json_subfields(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title", stored=True)
+            .add_json_field("attributes", fast=True, tokenizer_name="raw")
+            .add_json_field("extra")
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        for color, price in [("red", 10), ("red", 20), ("blue", 60)]:
+            doc = Document(title="shirt")
+            doc.add_json("attributes", {"color": color, "price": price})
+            writer.add_document(doc)
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+
+        result = searcher.aggregate(
+            Query.all_query(),
+            {
+                "avg_price": {"avg": {"field": "attributes.price"}},
+                "colors": {"terms": {"field": "attributes.color"}},
+            },
+        )
+        assert result["avg_price"]["value"] == 30.0
+        buckets = {b["key"]: b["doc_count"] for b in result["colors"]["buckets"]}
+        assert buckets == {"red": 2, "blue": 1}
+
+        for field in ["extra.price", "title.price", "missing", "title"]:
+            with pytest.raises(tantivy.SchemaError):
+                searcher.aggregate(
+                    Query.all_query(), {"avg_price": {"avg": {"field": field}}}
+                )
+
+    def test_aggregate_native_requests(self, ram_index_numeric_fields):
+        searcher = ram_index_numeric_fields.searcher()
+        result = searcher.aggregate(
+            Query.all_query(),
+            {
+                "ratings": {
+                    "range": {
+                        "field": "rating",
+                        "ranges": ({"to": 4}, {"from": 4}),
+                        "keyed": True,
+                    }
+                }
+            },
+        )
+        buckets = result["ratings"]["buckets"]
+        assert buckets["*-4"]["doc_count"] == 1
+        assert buckets["4-*"]["doc_count"] == 1
+        assert isinstance(buckets["*-4"]["doc_count"], int)
+
+    def test_aggregate_datetime_and_decimal_values(self):
+        from decimal import Decimal
+
+        schema = (
+            SchemaBuilder()
+            .add_date_field("date", fast=True)
+            .add_float_field("price", fast=True)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        for day, price in [(1, 10.0), (2, 20.5), (5, 30.0)]:
+            writer.add_document(
+                Document(date=datetime.datetime(2024, 1, day, 12), price=price)
+            )
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+
+        utc = datetime.timezone.utc
+        result = searcher.aggregate(
+            Query.all_query(),
+            {
+                "dates": {
+                    "range": {
+                        "field": "date",
+                        "ranges": [
+                            {"key": "early", "to": datetime.date(2024, 1, 2)},
+                            {
+                                "key": "late",
+                                "from": datetime.datetime(2024, 1, 2, tzinfo=utc),
+                            },
+                        ],
+                    }
+                },
+                "prices": {
+                    "range": {
+                        "field": "price",
+                        "ranges": [{"key": "cheap", "to": Decimal("20.5")}],
+                    }
+                },
+                "days": {
+                    "date_histogram": {
+                        "field": "date",
+                        "fixed_interval": "1d",
+                        "extended_bounds": {
+                            "min": datetime.date(2023, 12, 31),
+                            "max": datetime.date(2024, 1, 5),
+                        },
+                    }
+                },
+            },
+        )
+        dates = {b["key"]: b["doc_count"] for b in result["dates"]["buckets"]}
+        assert dates == {"early": 1, "late": 2}
+        prices = {b["key"]: b["doc_count"] for b in result["prices"]["buckets"]}
+        assert prices["cheap"] == 1
+        assert len(result["days"]["buckets"]) == 6
+
+    def test_aggregate_query_filters(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("color", stored=True, tokenizer_name="raw")
+            .add_integer_field("price", fast=True)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        for color, price in [("red", 10), ("red", 20), ("blue", 60)]:
+            writer.add_document(Document(color=color, price=price))
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+
+        red = Query.term_query(schema, "color", "red")
+        blue = Query.term_query(schema, "color", "blue")
+        result = searcher.aggregate(
+            Query.all_query(),
+            {
+                "red": {
+                    "filter": red,
+                    "aggs": {"avg_price": {"avg": {"field": "price"}}},
+                },
+                "by_color": {"filters": {"filters": {"red": red, "blue": blue}}},
+                "max_price": {"max": {"field": "price"}},
+            },
+        )
+        assert result["red"] == {"doc_count": 2, "avg_price": {"value": 15.0}}
+        assert result["by_color"] == {
+            "buckets": {"red": {"doc_count": 2}, "blue": {"doc_count": 1}}
+        }
+        assert result["max_price"]["value"] == 60.0
+
+        with pytest.raises(tantivy.SchemaError):
+            searcher.aggregate(
+                Query.all_query(),
+                {"red": {"filter": red, "aggs": {"a": {"avg": {"field": "color"}}}}},
+            )
+
+    def test_date_histogram(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title", stored=True)
+            .add_date_field("published", fast=True)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        for published in [
+            datetime.datetime(2024, 1, 1, 10),
+            datetime.datetime(2024, 1, 1, 23, 30),
+            datetime.datetime(2024, 1, 3, 5),
+        ]:
+            writer.add_document(Document(title="news", published=published))
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+        query = Query.all_query()
+
+        assert searcher.date_histogram(query, "published") == [
+            (datetime.datetime(2024, 1, 1), 2),
+            (datetime.datetime(2024, 1, 2), 0),
+            (datetime.datetime(2024, 1, 3), 1),
+        ]
+        assert searcher.date_histogram(query, "published", timezone="Europe/Paris") == [
+            (datetime.datetime(2024, 1, 1), 1),
+            (datetime.datetime(2024, 1, 2), 1),
+            (datetime.datetime(2024, 1, 3), 1),
+        ]
+        histogram = searcher.date_histogram(
+            query,
+            "published",
+            interval="12h",
+            extended_bounds=(
+                datetime.datetime(2023, 12, 31),
+                datetime.datetime(2024, 1, 3, 12),
+            ),
+        )
+        assert histogram[0] == (datetime.datetime(2023, 12, 31), 0)
+        assert histogram[-1] == (datetime.datetime(2024, 1, 3, 12), 0)
+        assert sum(count for _, count in histogram) == 3
+
+        with pytest.raises(ValueError):
+            searcher.date_histogram(query, "published", interval="1 week")
+        with pytest.raises(tantivy.SchemaError):
+            searcher.date_histogram(query, "title")
+
+    def test_top_terms(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("brand", fast=True, tokenizer_name="raw")
+            .add_float_field("price", fast=True)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        for brand, price in [("acme", 10.0), ("acme", 20.0), ("zeta", 5.0), ("acme", None)]:
+            doc = Document(brand=brand)
+            if price is not None:
+                doc.add_float("price", price)
+            writer.add_document(doc)
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+
+        result = searcher.top_terms(
+            Query.all_query(),
+            "brand",
+            k=5,
+            metrics={"avg_price": ("avg", "price"), "priced": ("count", "price")},
+        )
+        assert result == [
+            ("acme", 3, {"avg_price": 15.0, "priced": 2.0}),
+            ("zeta", 1, {"avg_price": 5.0, "priced": 1.0}),
+        ]
+        assert searcher.top_terms(Query.all_query(), "brand", k=1) == [("acme", 3, {})]
+
+        with pytest.raises(ValueError):
+            searcher.top_terms(
+                Query.all_query(), "brand", metrics={"m": ("median", "price")}
+            )
+
+    def test_top_terms_by_doc_freq(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title")
+            .add_integer_field("id", indexed=True)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        writer.add_document(Document(title="the old man and the sea"))
+        writer.commit()
+        writer.add_document(Document(title="the sea"))
+        writer.add_document(Document(title="of mice and men"))
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+        assert searcher.num_segments == 2
+
+        assert searcher.top_terms_by_doc_freq("title", k=3) == [
+            ("and", 2),
+            ("sea", 2),
+            ("the", 2),
+        ]
+        assert len(searcher.top_terms_by_doc_freq("title", k=100)) == 8
+        with pytest.raises(tantivy.SchemaError):
+            searcher.top_terms_by_doc_freq("id")
+
+    def test_facet_counts_segments(self):
+        schema = SchemaBuilder().add_facet_field("category").build()
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        # Each segment has its own facet ordinals, and is counted on its own
+        # thread.
+        for paths in [["/a/x", "/b"], ["/a/y"], ["/a/x", "/c/z"]]:
+            for path in paths:
+                doc = Document()
+                doc.add_facet("category", tantivy.Facet.from_string(path))
+                writer.add_document(doc)
+            writer.commit()
+        index.reload()
+        searcher = index.searcher()
+        assert searcher.num_segments == 3
+
+        counts = searcher.facet_counts(
+            Query.all_query(), "category", [tantivy.Facet.from_string("/a")]
+        )
+        assert [(facet.to_path_str(), count) for facet, count in counts.get(
+            tantivy.Facet.from_string("/a")
+        )] == [("/a/x", 2), ("/a/y", 1)]
+        result = searcher.search(
+            Query.all_query(),
+            collector=tantivy.MultiCollector().add_facets("category", "category"),
+        )
+        assert result.facet_counts("category").to_tree(depth=1) == {
+            "a": {"count": 3, "children": {}},
+            "b": {"count": 1, "children": {}},
+            "c": {"count": 1, "children": {}},
+        }
+
+    def test_facet_counts(self):
+        schema = SchemaBuilder().add_facet_field("category").build()
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        for paths in [["/a/x/1", "/a/x/2"], ["/a/y"], ["/b/z"], ["/b"]]:
+            doc = Document()
+            for path in paths:
+                doc.add_facet("category", tantivy.Facet.from_string(path))
+            writer.add_document(doc)
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+
+        counts = searcher.facet_counts(Query.all_query(), "category")
+        a = tantivy.Facet.from_string("/a")
+        assert [
+            (facet.to_path_str(), count) for facet, count in counts.get(a)
+        ] == [("/a/x", 1), ("/a/x/1", 1), ("/a/x/2", 1), ("/a/y", 1)]
+        assert counts.to_tree(depth=1) == {
+            "a": {"count": 2, "children": {}},
+            "b": {"count": 2, "children": {}},
+        }
+        assert counts.to_tree(a) == {
+            "x": {
+                "count": 1,
+                "children": {
+                    "1": {"count": 1, "children": {}},
+                    "2": {"count": 1, "children": {}},
+                },
+            },
+            "y": {"count": 1, "children": {}},
+        }
+
+        assert searcher.facet_counts(
+            Query.all_query(), "category", []
+        ).to_tree(depth=1) == counts.to_tree(depth=1)
+
+        result = searcher.search(
+            Query.all_query(),
+            collector=tantivy.MultiCollector().add_facets("category", "category"),
+        )
+        assert result.facet_counts("category").to_tree(depth=1) == {
+            "a": {"count": 2, "children": {}},
+            "b": {"count": 2, "children": {}},
+        }
+        assert pickle.loads(pickle.dumps(result)) == result
+        merged = tantivy.SearchResult.merge([result, result])
+        assert merged.facet_counts("category").get(a)[0][1] == 2
+        with pytest.raises(KeyError):
+            result.facet_counts("title")
+
+        counts = searcher.facet_counts(Query.all_query(), "category", [a])
+        assert counts.to_tree(depth=2) == {
+            "a": {
+                "count": None,
+                "children": {
+                    "x": {"count": 1, "children": {}},
+                    "y": {"count": 1, "children": {}},
+                },
+            }
+        }
+
+    def test_fast_field_values(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title")
+            .add_integer_field("tags", fast=True)
+            .add_text_field("keywords", fast=True, tokenizer_name="raw")
+            .add_facet_field("category")
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        doc = Document(title="full", tags=[3, 1], keywords=["sea", "boat"])
+        doc.add_facet("category", tantivy.Facet.from_string("/a/b"))
+        writer.add_document(doc)
+        writer.add_document(Document(title="empty"))
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+
+        def address(title):
+            query = Query.term_query(schema, "title", title)
+            return searcher.search(query).hits[0][1]
+
+        full = address("full")
+        assert searcher.fast_field_values(full, "tags") == [3, 1]
+        assert sorted(searcher.fast_field_values(full, "keywords")) == [
+            "boat",
+            "sea",
+        ]
+        (facet,) = searcher.fast_field_values(full, "category")
+        assert facet.to_path_str() == "/a/b"
+        assert searcher.fast_field_values(address("empty"), "tags") == []
+
+        with pytest.raises(tantivy.SchemaError):
+            searcher.fast_field_values(full, "title")
+        with pytest.raises(ValueError):
+            searcher.fast_field_values(tantivy.DocAddress(1, 0), "tags")
+
+    def test_field_space_usage(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title", stored=True)
+            .add_text_field("body")
+            .add_integer_field("rank", fast=True)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        for i in range(100):
+            writer.add_document(
+                Document(title=f"title {i}", body="lorem ipsum " * i, rank=i)
+            )
+        writer.commit()
+        index.reload()
+
+        usage = index.field_space_usage()
+        assert list(usage) == ["title", "body", "rank"]
+        assert usage["title"]["store"] > 0
+        assert usage["body"]["store"] == 0
+        assert usage["body"]["positions"] > 0
+        assert usage["rank"]["fast_fields"] > 0
+        assert usage["rank"]["postings"] == 0
+        for field_usage in usage.values():
+            assert field_usage["total"] == sum(
+                size for part, size in field_usage.items() if part != "total"
+            )
+
+    def test_index_stats(self):
+        index = Index(schema())
+        stats = index.stats()
+        assert stats["num_docs"] == 0
+        assert stats["commits"] == 0
+        assert stats["last_commit"] is None
+
+        writer = index.writer(15_000_000, 1)
+        for i in range(10):
+            writer.add_document(Document(title=f"title {i}", body="lorem ipsum"))
+            writer.commit()
+        writer.delete_documents("title", "1")
+        writer.commit()
+        writer.merge()
+        writer.wait_merging_threads()
+        index.reload()
+
+        stats = index.stats()
+        assert stats["num_docs"] == 9
+        assert stats["num_segments"] == 1
+        assert stats["commits"] == 11
+        assert stats["committed_docs"] == 10
+        assert stats["docs_per_sec"] > 0
+        assert stats["merges"] >= 1
+        assert stats["last_merge"] >= stats["last_commit"] > 0
+        disk_usage = stats["disk_usage"]
+        assert disk_usage["store"] > 0
+        assert disk_usage["positions"] > 0
+        assert disk_usage["total"] >= sum(
+            size for part, size in disk_usage.items() if part != "total"
+        )
+
     def test_and_query_numeric_fields(self, ram_index_numeric_fields):
         index = ram_index_numeric_fields
         searcher = index.searcher()
@@ -168,12 +629,178 @@ class TestClass(object):
             == """Query(BooleanQuery { subqueries: [(Should, FuzzyTermQuery { term: Term(field=0, type=Str, "winter"), distance: 1, transposition_cost_one: false, prefix: true }), (Should, TermQuery(Term(field=1, type=Str, "winter")))] })"""
         )
 
+    def test_parse_query_field_analyzers(self, ram_index):
+        def count(query, **kwargs):
+            query = ram_index.parse_query(query, ["title", "body"], **kwargs)
+            return ram_index.searcher().search(query).count
+
+        raw = {"title": "raw"}
+        query = ram_index.parse_query('"Old Man"', ["title"], field_analyzers=raw)
+        assert (
+            repr(query)
+            == """Query(TermQuery(Term(field=0, type=Str, "Old Man")))"""
+        )
+        assert count("Sea") == 1
+        assert count("Sea", field_analyzers=raw) == 0
+        assert count("sea", field_analyzers=raw) == 1
+        assert count("title:mice", field_analyzers={"body": "raw"}) == 1
+
+        query, errors = ram_index.parse_query_lenient(
+            "Sea", ["title"], field_analyzers=raw
+        )
+        assert errors == []
+        assert ram_index.searcher().search(query).count == 0
+
+        with pytest.raises(ValueError, match="analyzer"):
+            count("sea", field_analyzers={"title": "unknown"})
+
+        numeric = Index(schema_numeric_fields())
+        with pytest.raises(tantivy.SchemaError):
+            numeric.parse_query("1", ["id"], field_analyzers={"id": "raw"})
+
+    def test_parse_query_plain_text(self, ram_index):
+        user_input = 'title:sea (old "man  AND'
+        escaped = tantivy.escape_query_string(user_input)
+        assert escaped == r'"title:sea" "(old" "\"man" "AND"'
+        with pytest.raises(ValueError):
+            ram_index.parse_query(user_input, ["title"])
+
+        def count(query, **kwargs):
+            query = ram_index.parse_query(query, ["title"], **kwargs)
+            return ram_index.searcher().search(query).count
+
+        assert count(user_input, plain_text=True) == 2
+        assert count(escaped) == 2
+        assert count(user_input, plain_text=True, conjunction_by_default=True) == 0
+        assert count("old (man)", plain_text=True, conjunction_by_default=True) == 1
+        assert count("old sea", conjunction_by_default=True) == 1
+
+    def test_parse_simple_query(self, ram_index):
+        def titles(query, **kwargs):
+            query = ram_index.parse_simple_query(query, ["title"], **kwargs)
+            searcher = ram_index.searcher()
+            return sorted(
+                searcher.doc(address)["title"][0]
+                for _, address in searcher.search(query).hits
+            )
+
+        old_man = "The Old Man and the Sea"
+        mice = "Of Mice and Men"
+        assert titles("old mice") == [mice, old_man]
+        assert titles("old mice", conjunction_by_default=True) == []
+        assert titles("+and -sea") == [mice]
+        assert titles("-sea") == ["Frankenstein", mice]
+        assert titles('"man and the" | mice') == [mice, old_man]
+        assert titles('"the sea old"') == []
+        # Malformed input doesn't raise.
+        assert titles('title:sea (old "man') == [old_man]
+        assert titles("+ - | ") == []
+
+    def test_parse_query_max_doc_frequency(self):
+        index = Index(schema())
+        writer = index.writer(15_000_000, 1)
+        for title in ["the sea", "the whale", "the ship", "the old man"]:
+            writer.add_document(Document(title=title))
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+
+        def count(query, **kwargs):
+            parsed = index.parse_query(query, ["title"], max_doc_frequency=0.5, **kwargs)
+            return searcher.search(parsed).count
+
+        assert searcher.search(index.parse_query("the whale", ["title"])).count == 4
+        assert count("the whale") == 1
+        assert count("the whale", field_boosts={"title": 2.0}) == 1
+        assert count("the") == 4
+        assert count('"the whale"') == 1
+        assert count("whale -the") == 0
+
+        query, errors = index.parse_query_lenient(
+            "the ship", ["title"], max_doc_frequency=0.5
+        )
+        assert searcher.search(query).count == 1
+        with pytest.raises(ValueError):
+            index.parse_query("the", max_doc_frequency=0.0)
+
     def test_query_errors(self, ram_index):
         index = ram_index
         # no "bod" field
         with pytest.raises(ValueError):
             index.parse_query("bod:men", ["title", "body"])
 
+    def test_query_errors_are_structured(self, ram_index):
+        index = ram_index
+        from tantivy import query_parser_error
+
+        with pytest.raises(tantivy.QueryParserError) as excinfo:
+            index.parse_query("bod:men", ["title", "body"])
+        assert excinfo.value.field == "bod"
+        assert isinstance(
+            excinfo.value.error, query_parser_error.FieldDoesNotExistError
+        )
+
+        with pytest.raises(tantivy.SchemaError) as excinfo:
+            index.parse_query("men", ["bod"])
+        assert excinfo.value.field == "bod"
+        assert isinstance(excinfo.value, tantivy.TantivyError)
+
+    def test_validate_query(self, ram_index):
+        index = ram_index
+        report = index.validate_query("title:sea AND whale", ["body"])
+        assert report["valid"]
+        assert report["fields"] == ["body", "title"]
+        assert report["errors"] == [] and report["warnings"] == []
+        assert "sea" in report["tree"]
+
+        report = index.validate_query("bod:men AND (sea")
+        assert not report["valid"]
+        assert report["fields"] == ["body", "title"]
+        assert len(report["warnings"]) == 1 and "bod" in report["warnings"][0]
+        assert report["errors"][0]["position"] >= len("bod:men AND ")
+
+    def test_field_aliases(self, ram_index):
+        index = ram_index
+        searcher = index.searcher()
+        expected = searcher.search(index.parse_query("title:sea AND whale")).hits
+
+        index.set_field_aliases({"headline": "title", "body_text": "body"})
+        assert index.field_aliases == {"headline": "title", "body_text": "body"}
+        query = index.parse_query("headline:sea AND whale", ["body_text"])
+        assert searcher.search(query).hits == expected
+        _, errors = index.parse_query_lenient("headline:sea")
+        assert errors == []
+        assert index.validate_query("headline:sea")["fields"] == ["title"]
+
+        with pytest.raises(ValueError):
+            index.set_field_aliases({"title": "body"})
+        with pytest.raises(tantivy.SchemaError):
+            index.set_field_aliases({"summary": "abstract"})
+
+    def test_analyze_query_terms(self, ram_index):
+        analysis = ram_index.analyze_query_terms("title", "The Sea")
+        assert analysis["tokenizer"] == "default"
+        assert analysis["document_tokens"] == [
+            {"text": "the", "position": 0, "offset_from": 0, "offset_to": 3},
+            {"text": "sea", "position": 1, "offset_from": 4, "offset_to": 7},
+        ]
+        assert analysis["query_terms"] == [
+            {"text": "the", "doc_freq": 2, "in_document": True},
+            {"text": "sea", "doc_freq": 1, "in_document": True},
+        ]
+        assert analysis["consistent"]
+
+        schema = SchemaBuilder().add_text_field("city", tokenizer_name="raw").build()
+        index = Index(schema)
+        analysis = index.analyze_query_terms("city", "New York")
+        assert [token["text"] for token in analysis["document_tokens"]] == ["New York"]
+        assert [term["text"] for term in analysis["query_terms"]] == ["New", "York"]
+        assert not analysis["consistent"]
+
+        schema = SchemaBuilder().add_integer_field("id", indexed=True).build()
+        with pytest.raises(tantivy.SchemaError):
+            Index(schema).analyze_query_terms("id", "1")
+
     def test_query_lenient(self, ram_index_numeric_fields):
         from tantivy import query_parser_error
 
@@ -292,6 +919,83 @@ class TestClass(object):
         result = searcher.search(query, 10, order_by_field="order")
         assert len(result.hits) == 0
 
+    def test_order_by_search_missing(self):
+        schema = (
+            SchemaBuilder()
+            .add_unsigned_field("order", fast=True)
+            .add_text_field("title", stored=True, fast=True)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer()
+        for title, order in [("one", 1), ("none", None), ("three", 3)]:
+            doc = Document(title=title)
+            if order is not None:
+                doc.add_unsigned("order", order)
+            writer.add_document(doc)
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+
+        def titles(**kwargs):
+            result = searcher.search(
+                Query.all_query(), 10, order_by_field="order", **kwargs
+            )
+            return [searcher.doc(a)["title"][0] for _, a in result.hits]
+
+        assert titles() == ["three", "one", "none"]
+        assert titles(order=tantivy.Order.Asc) == ["one", "three", "none"]
+        assert titles(missing="first") == ["none", "three", "one"]
+        assert titles(missing=2) == ["three", "none", "one"]
+        with pytest.raises(ValueError):
+            titles(missing="middle")
+        with pytest.raises(ValueError):
+            searcher.search(Query.all_query(), missing="first")
+
+        aggs = searcher.aggregate(
+            Query.all_query(),
+            {
+                "avg": {"avg": {"field": "order"}},
+                "min": {"min": {"field": "order", "missing": 0}},
+                "orders": {"terms": {"field": "order"}},
+            },
+            missing={"order": 5},
+        )
+        assert aggs["avg"]["value"] == 3
+        assert aggs["min"]["value"] == 0
+        assert {b["key"] for b in aggs["orders"]["buckets"]} == {1, 3, 5}
+
+    def test_order_by_search_mode(self):
+        schema = (
+            SchemaBuilder()
+            .add_integer_field("prices", fast=True)
+            .add_text_field("title", stored=True)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer()
+        for title, prices in [("a", [5, 1]), ("b", [2, 3]), ("c", [4])]:
+            doc = Document(title=title)
+            for price in prices:
+                doc.add_integer("prices", price)
+            writer.add_document(doc)
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+
+        def titles(order, mode):
+            result = searcher.search(
+                Query.all_query(), 10, order_by_field=("prices", order, mode)
+            )
+            return [searcher.doc(a)["title"][0] for _, a in result.hits]
+
+        assert titles(tantivy.Order.Asc, "min") == ["a", "b", "c"]
+        assert titles(tantivy.Order.Desc, "max") == ["a", "c", "b"]
+        assert titles(tantivy.Order.Asc, "avg") == ["b", "a", "c"]
+        assert titles(tantivy.Order.Asc, "first") == ["b", "c", "a"]
+        with pytest.raises(ValueError):
+            titles(tantivy.Order.Asc, "median")
+
     def test_order_by_search_date(self):
         schema = (
             SchemaBuilder()
@@ -528,95 +1232,1928 @@ class TestClass(object):
         index = ram_index
         query = index.parse_query("sea whale", ["title", "body"])
 
-        orig = index.searcher().search(query, 10)
-        pickled = pickle.loads(pickle.dumps(orig))
+        orig = index.searcher().search(query, 10)
+        pickled = pickle.loads(pickle.dumps(orig))
+
+        assert orig == pickled
+
+    def test_search_result_count_relation(self, ram_index):
+        index = ram_index
+        query = index.parse_query("sea whale", ["title", "body"])
+        result = index.searcher().search(query, 10)
+        assert result.count_relation == "eq"
+        assert index.searcher().search(query, count=False).count_relation is None
+
+        lower_bound = tantivy.SearchResult(result.hits, 5, "gte")
+        assert lower_bound.count_relation == "gte"
+        assert repr(lower_bound).endswith("count: >=5)")
+        assert pickle.loads(pickle.dumps(lower_bound)) == lower_bound
+        assert lower_bound != tantivy.SearchResult(result.hits, 5)
+        with pytest.raises(ValueError):
+            tantivy.SearchResult(result.hits, 5, "lt")
+
+    def test_search_result_rerank(self, ram_index, ram_index_numeric_fields):
+        index = ram_index
+        result = index.searcher().search(Query.all_query(), 10)
+        addresses = [address for _, address in result.hits]
+        assert len(addresses) == 3
+
+        reranked = result.rerank([0.5, 0.5, 2.0])
+        assert reranked.hits == [
+            (2.0, addresses[2]),
+            (0.5, addresses[0]),
+            (0.5, addresses[1]),
+        ]
+        assert reranked.count == result.count
+        assert len(result.rerank([0.5, 0.5, 2.0], top_k=1).hits) == 1
+
+        # Hits without a new score come last, with their score.
+        reranked = result.rerank({addresses[2]: 1.0})
+        assert reranked.hits == [(1.0, addresses[2])] + result.hits[:2]
+
+        with pytest.raises(ValueError):
+            result.rerank([1.0])
+        with pytest.raises(ValueError):
+            result.rerank({tantivy.DocAddress(9, 9): 1.0})
+
+        # The hits of a merged result may share their address.
+        merged = tantivy.SearchResult.merge([result, result], limit=6)
+        assert merged.origins == [0, 0, 0, 1, 1, 1]
+        reranked = merged.rerank({(1, addresses[0]): 3.0, (0, addresses[0]): 2.0})
+        assert reranked.hits[:2] == [(3.0, addresses[0]), (2.0, addresses[0])]
+        assert reranked.origins[:2] == [1, 0]
+        reranked = merged.rerank([1.0, 2.0, 3.0, 4.0, 5.0, 6.0], top_k=2)
+        assert reranked.hits == [(6.0, addresses[2]), (5.0, addresses[1])]
+        assert reranked.origins == [1, 1]
+        with pytest.raises(TypeError):
+            merged.rerank({addresses[0]: 1.0})
+        with pytest.raises(ValueError):
+            merged.rerank({(2, addresses[0]): 1.0})
+
+        ordered = ram_index_numeric_fields.searcher().search(
+            Query.all_query(), order_by_field="rating"
+        )
+        with pytest.raises(ValueError, match="ordered by a field"):
+            ordered.rerank([1.0] * len(ordered.hits))
+
+    def test_search_result_merge(self):
+        a, b = tantivy.DocAddress(0, 1), tantivy.DocAddress(0, 2)
+        first = tantivy.SearchResult([(3.0, a), (1.0, b)], 2)
+        second = tantivy.SearchResult([(2.0, a), (1.0, b)], 5, "gte")
+
+        merged = tantivy.SearchResult.merge([first, second], limit=3)
+        assert merged.hits == [(3.0, a), (2.0, a), (1.0, b)]
+        assert merged.origins == [0, 1, 0]
+        assert merged.count == 7
+        assert merged.count_relation == "gte"
+        assert pickle.loads(pickle.dumps(merged)) == merged
+
+        ordered = tantivy.SearchResult.merge(
+            [first, second], order=tantivy.Order.Asc
+        )
+        assert [score for score, _ in ordered.hits] == [1.0, 1.0, 2.0, 3.0]
+        assert ordered.origins == [0, 1, 1, 0]
+        assert (
+            tantivy.SearchResult.merge([first, tantivy.SearchResult([], None)])
+        ).count is None
+
+        counts = [
+            index.searcher().facet_counts(Query.all_query(), "category")
+            for index in (
+                self._facet_index(["/a/x", "/b"]),
+                self._facet_index(["/a/y"]),
+            )
+        ]
+        merged = tantivy.FacetCounts.merge(counts)
+        assert merged.to_tree(depth=1) == {
+            "a": {"count": 2, "children": {}},
+            "b": {"count": 1, "children": {}},
+        }
+
+    @staticmethod
+    def _facet_index(paths):
+        schema = SchemaBuilder().add_facet_field("category").build()
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        for path in paths:
+            doc = Document()
+            doc.add_facet("category", tantivy.Facet.from_string(path))
+            writer.add_document(doc)
+        writer.commit()
+        index.reload()
+        return index
+
+    def test_search_async(self, ram_index):
+        import asyncio
+
+        index = ram_index
+        searcher = index.searcher()
+        query = index.parse_query("sea whale", ["title", "body"])
+
+        async def run():
+            return await asyncio.gather(
+                searcher.search_async(query, 10),
+                searcher.aggregate_async(Query.all_query(), {"n": {"filter": query}}),
+                searcher.search_async(query, order_by_field="missing"),
+                return_exceptions=True,
+            )
+
+        result, aggs, error = asyncio.run(run())
+        assert result == searcher.search(query, 10)
+        assert aggs == {"n": {"doc_count": result.count}}
+        assert isinstance(error, ValueError)
+        with pytest.raises(RuntimeError):
+            searcher.search_async(query)
+
+    def test_search_profile(self, ram_index):
+        index = ram_index
+        searcher = index.searcher()
+        query = index.parse_query("sea whale", ["title", "body"])
+
+        result = searcher.search(query, 10)
+        assert result.profile is None
+
+        profiled = searcher.search(query, 10, profile=True)
+        assert profiled == result
+        profile = profiled.profile
+        assert len(profile["segments"]) == searcher.num_segments
+        assert sum(s["num_docs"] for s in profile["segments"]) == searcher.num_docs
+        for key in ["weight_ms", "merge_ms", "total_ms"]:
+            assert 0 <= profile[key] <= profile["total_ms"]
+
+    def test_search_stats_collector(self, ram_index_numeric_fields):
+        index = ram_index_numeric_fields
+        searcher = index.searcher()
+        collector = (
+            tantivy.MultiCollector()
+            .add_stats("rating", "rating")
+            .add_stats("id", "id")
+        )
+        result = searcher.search(Query.all_query(), 1, collector=collector)
+        assert len(result.hits) == 1
+        assert result.stats("rating") == {
+            "count": 2,
+            "sum": 8.0,
+            "min": 3.5,
+            "max": 4.5,
+            "avg": 4.0,
+        }
+        assert result.stats("id")["max"] == 2.0
+        assert pickle.loads(pickle.dumps(result)) == result
+        with pytest.raises(KeyError):
+            result.stats("is_good")
+        with pytest.raises(tantivy.SchemaError):
+            searcher.search(
+                Query.all_query(),
+                collector=tantivy.MultiCollector().add_stats("good", "is_good"),
+            )
+        with pytest.raises(ValueError, match="Searcher.collect"):
+            searcher.search(
+                Query.all_query(),
+                collector=tantivy.MultiCollector().add_count("total"),
+            )
+
+    def test_collect(self, ram_index_numeric_fields):
+        searcher = ram_index_numeric_fields.searcher()
+        collector = (
+            tantivy.MultiCollector()
+            .add_top_docs("hits", limit=1)
+            .add_count("total")
+            .add_histogram("ratings", "rating", 1.0)
+            .add_stats("rating", "rating")
+        )
+        assert collector.names == ["hits", "total", "ratings", "rating"]
+        fruits = searcher.collect(Query.all_query(), collector)
+        assert len(fruits["hits"]) == 1
+        assert fruits["total"] == 2
+        assert fruits["ratings"] == [(3.0, 1), (4.0, 1)]
+        assert fruits["rating"]["avg"] == 4.0
+        with pytest.raises(ValueError):
+            collector.add_count("total")
+        with pytest.raises(tantivy.SchemaError):
+            searcher.collect(
+                Query.all_query(),
+                tantivy.MultiCollector().add_stats("good", "is_good"),
+            )
+
+    def test_search_result_to_table(self, ram_index_numeric_fields):
+        index = ram_index_numeric_fields
+        searcher = index.searcher()
+        result = searcher.search(
+            Query.all_query(), order_by_field="id", order=tantivy.Order.Asc
+        )
+        table = result.to_table(["id", "rating", "body"], searcher)
+        assert list(table) == ["_score", "id", "rating", "body"]
+        assert table["id"] == [1, 2]
+        assert table["rating"] == [3.5, 4.5]
+        assert table["body"][1].startswith("A few miles south of Soledad")
+        table = result.to_table(["id"], searcher, all_values=True)
+        assert table["id"] == [[1], [2]]
+        with pytest.raises(ValueError):
+            result.to_table(["id"], searcher, format="csv")
+        with pytest.raises(ValueError):
+            result.to_table(["missing"], searcher)
+
+    def test_search_diversify_by(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title", stored=True)
+            .add_text_field("brand", fast=True, tokenizer_name="raw")
+            .add_integer_field("id", stored=True)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        # The more "shoe" in the title, the higher the score.
+        brands = ["acme", "acme", "acme", "bolt", None, "acme", "bolt"]
+        for i, brand in enumerate(brands):
+            doc = Document(title=" ".join(["shoe"] * (len(brands) - i)), id=i)
+            if brand is not None:
+                doc.add_text("brand", brand)
+            writer.add_document(doc)
+            if i == 3:
+                writer.commit()
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+        query = index.parse_query("shoe", ["title"])
+
+        def ids(**kwargs):
+            hits = searcher.search(query, **kwargs).hits
+            return [searcher.doc(address)["id"][0] for _, address in hits]
+
+        assert ids() == [0, 1, 2, 3, 4, 5, 6]
+        assert ids(diversify_by=("brand", 1)) == [0, 3, 4]
+        assert ids(diversify_by=("brand", 2)) == [0, 1, 3, 4, 6]
+        assert ids(diversify_by=("brand", 2), limit=2, offset=2) == [3, 4]
+
+        with pytest.raises(tantivy.SchemaError):
+            searcher.search(query, diversify_by=("title", 1))
+        with pytest.raises(ValueError):
+            searcher.search(query, diversify_by=("brand", 0))
+        with pytest.raises(ValueError):
+            searcher.search(
+                query, order_by_field="id", diversify_by=("brand", 1)
+            )
+
+    def test_order_by_text_field(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("brand", stored=True, fast=True, tokenizer_name="raw")
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        # Each commit makes a segment with its own term ordinals.
+        for brands in [["delta", "alpha"], ["charlie"], ["bravo", "echo"]]:
+            for brand in brands:
+                writer.add_document(Document(brand=brand))
+            writer.commit()
+        writer.wait_merging_threads()
+        index.reload()
+        searcher = index.searcher()
+        assert searcher.num_segments == 3
+
+        def brands(**kwargs):
+            result = searcher.search(
+                Query.all_query(), order_by_field="brand", **kwargs
+            )
+            return [searcher.doc(address)["brand"][0] for _, address in result.hits]
+
+        assert brands(order=tantivy.Order.Asc) == [
+            "alpha", "bravo", "charlie", "delta", "echo"
+        ]
+        assert brands() == ["echo", "delta", "charlie", "bravo", "alpha"]
+        with pytest.raises(ValueError):
+            searcher.search(
+                Query.all_query(),
+                order_by_field=("brand", tantivy.Order.Asc, "avg"),
+            )
+
+    def test_search_score_expr(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title", stored=True)
+            .add_integer_field("clicks", fast=True)
+            .add_integer_field("id", stored=True)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        writer.add_document(Document(title="shoe shoe shoe", clicks=1, id=0))
+        writer.add_document(Document(title="shoe", clicks=1000, id=1))
+        writer.add_document(Document(title="shoe shoe", id=2))
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+        query = index.parse_query("shoe", ["title"])
+        scores = {
+            searcher.doc(address)["id"][0]: score
+            for score, address in searcher.search(query).hits
+        }
+
+        expr = "_score * 0.7 + log1p(clicks) * 0.3"
+        hits = searcher.search(query, score_expr=expr, expr_fields=["clicks"])
+        clicks = [1, 1000, 0]
+        ids = []
+        for score, address in hits.hits:
+            (id,) = searcher.doc(address)["id"]
+            expected = scores[id] * 0.7 + math.log1p(clicks[id]) * 0.3
+            assert score == pytest.approx(expected, rel=1e-6)
+            ids.append(id)
+        assert ids == [1, 0, 2]
+
+        with pytest.raises(ValueError):
+            searcher.search(query, score_expr="_score *")
+        with pytest.raises(ValueError):
+            searcher.search(query, score_expr="log1p(clicks)")
+        with pytest.raises(tantivy.SchemaError):
+            searcher.search(query, score_expr="id", expr_fields=["id"])
+        with pytest.raises(ValueError):
+            searcher.search(query, score_expr="_score", order_by_field="id")
+
+    def test_search_score_expr_signals(self, tmpdir):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title", stored=True)
+            .add_text_field("key", stored=True, fast=True, tokenizer_name="raw")
+            .add_integer_field("clicks", fast=True)
+            .build()
+        )
+        index = Index(schema, str(tmpdir))
+        writer = index.writer(15_000_000, 1)
+        writer.add_document(Document(title="shoe", key="doc-1", clicks=10))
+        writer.add_document(Document(title="shoe", key="doc-2", clicks=20))
+        writer.add_document(Document(title="shoe", key="doc-3", clicks=30))
+        writer.commit()
+        index.reload()
+
+        with pytest.raises(ValueError):
+            index.signals()
+        with pytest.raises(tantivy.SchemaError):
+            index.signals("title")
+        signals = index.signals("key")
+        assert signals.key_field == "key"
+        with pytest.raises(ValueError):
+            index.signals("clicks")
+        signals.set("doc-1", {"ctr": 0.5})
+        signals.set_many({"doc-2": {"ctr": 0.1}, "doc-3": {"ctr": 0.2}})
+        signals.set("doc-1", {"boost": 2.0})
+        assert signals.get("doc-1") == {"ctr": 0.5, "boost": 2.0}
+        assert len(signals) == 3
+
+        def keys(**kwargs):
+            searcher = index.searcher()
+            query = index.parse_query("shoe", ["title"])
+            hits = searcher.search(query, **kwargs).hits
+            return [searcher.doc(address)["key"][0] for _, address in hits]
+
+        expr = "ctr * 100 + clicks"
+        order = keys(score_expr=expr, expr_fields=["clicks"], expr_signals=["ctr"])
+        assert order == ["doc-1", "doc-3", "doc-2"]
+
+        # The searches see the changes without reindexing.
+        signals.set("doc-2", {"ctr": 0.9})
+        assert signals.delete("doc-1")
+        order = keys(score_expr=expr, expr_fields=["clicks"], expr_signals=["ctr"])
+        assert order == ["doc-2", "doc-3", "doc-1"]
+
+        with pytest.raises(ValueError):
+            keys(score_expr="clicks", expr_fields=["clicks"], expr_signals=["clicks"])
+        with pytest.raises(ValueError):
+            keys(score_expr="ctr")
+
+        reopened = Index.open(str(tmpdir))
+        assert reopened.signals().get("doc-2") == {"ctr": 0.9}
+        assert reopened.signals().get("doc-1") is None
+
+    def test_search_limits(self, ram_index):
+        index = ram_index
+        query = Query.all_query()
+        index.set_search_limits(max_limit=100, max_offset=1000)
+        searcher = index.searcher()
+        assert len(searcher.search(query, 100, offset=1000).hits) == 0
+        with pytest.raises(ValueError, match="max_limit"):
+            searcher.search(query, 10_000_000)
+        with pytest.raises(ValueError, match="max_offset"):
+            searcher.search(query, offset=1001)
+
+        strict = searcher.with_search_limits(max_limit=1)
+        assert len(strict.search(query, 1).hits) == 1
+        with pytest.raises(ValueError):
+            strict.search(query, 2)
+        assert len(strict.with_search_limits().search(query, 2).hits) == 2
+
+        index.set_search_limits()
+        assert len(index.searcher().search(query, 1000).hits) == 3
+
+    def test_query_complexity_limits(self):
+        index = create_index()
+        schema = index.schema
+        index.set_search_limits(
+            max_clauses=2, max_expanded_terms=1, max_regex_states=10
+        )
+        searcher = index.searcher()
+        assert searcher.search(index.parse_query("old man", ["title"])).count == 1
+        with pytest.raises(tantivy.QueryTooComplexError) as excinfo:
+            searcher.search(index.parse_query("old man sea", ["title"]))
+        assert excinfo.value.limit == "max_clauses"
+        assert excinfo.value.value == 3
+        assert excinfo.value.max == 2
+
+        # "men" and "man" are both within one edit of "mon".
+        fuzzy = Query.fuzzy_term_query(schema, "title", "mon")
+        with pytest.raises(tantivy.QueryTooComplexError) as excinfo:
+            searcher.search(fuzzy).count
+        assert excinfo.value.limit == "max_expanded_terms"
+        assert excinfo.value.value == 2
+        assert searcher.search(Query.fuzzy_term_query(schema, "title", "mice")).count == 1
+
+        assert searcher.search(Query.regex_query(schema, "title", "sea")).count == 1
+        with pytest.raises(tantivy.QueryTooComplexError, match="max_regex_states"):
+            searcher.search(Query.regex_query(schema, "title", "[a-z]{3,20}")).count
+
+        relaxed = searcher.with_search_limits(max_clauses=3)
+        assert relaxed.search(index.parse_query("old man sea", ["title"])).count == 1
+
+    def test_active_searchers(self):
+        import gc
+
+        index = create_index()
+        gc.collect()
+        before = index.num_active_searchers()
+        old = index.searcher()
+        filtered = old.with_filter(Query.all_query())
+        assert index.num_active_searchers() == before + 2
+        assert old.generation == index.reader_generation
+
+        index.reload()
+        assert index.reader_generation > old.generation
+        assert index.searcher().generation == index.reader_generation
+        del filtered
+        gc.collect()
+        assert index.num_active_searchers() == before + 1
+        del old
+        gc.collect()
+        assert index.num_active_searchers() == before
+
+    def test_delete_all_documents(self, ram_index):
+        index = ram_index
+        writer = index.writer()
+        writer.delete_all_documents()
+        writer.commit()
+
+        index.reload()
+        query = index.parse_query("sea whale", ["title", "body"])
+        result = index.searcher().search(query, 10)
+
+        assert len(result.hits) == 0
+
+
+class TestUpdateClass(object):
+    def test_delete_update(self, ram_index):
+        query = ram_index.parse_query("Frankenstein", ["title"])
+        result = ram_index.searcher().search(query, 10)
+        assert len(result.hits) == 1
+
+        writer = ram_index.writer()
+
+        with pytest.raises(ValueError):
+            writer.delete_documents("fake_field", "frankenstein")
+
+        with pytest.raises(ValueError):
+            writer.delete_documents("title", b"frankenstein")
+
+        writer.delete_documents("title", "frankenstein")
+        writer.commit()
+        ram_index.reload()
+
+        result = ram_index.searcher().search(query)
+        assert len(result.hits) == 0
+
+    def test_update_fast_field(self):
+        index = Index(schema_numeric_fields())
+        writer = index.writer()
+        writer.add_document(Document(id=1, rating=3.5, is_good=True, body="one"))
+        writer.add_document(Document(id=2, rating=4.5, is_good=False, body="two"))
+        writer.commit()
+
+        assert writer.update_fast_field("id", 1, {"rating": 1.0}) == 1
+        assert writer.update_fast_field("id", 3, {"rating": 1.0}) == 0
+        with pytest.raises(ValueError, match="not a fast field"):
+            writer.update_fast_field("id", 1, {"is_good": False})
+        writer.commit()
+        index.reload()
+
+        searcher = index.searcher()
+        assert searcher.num_docs == 2
+        docs = [
+            searcher.doc(address).to_dict()
+            for _, address in searcher.search(Query.all_query(), order_by_field="id").hits
+        ]
+        assert docs == [
+            {"id": [2], "rating": [4.5], "is_good": [False], "body": ["two"]},
+            {"id": [1], "rating": [1.0], "is_good": [True], "body": ["one"]},
+        ]
+        result = searcher.search(Query.all_query(), order_by_field="rating")
+        assert [searcher.doc(hit[1])["id"] for hit in result.hits] == [[2], [1]]
+
+        unstored = Index(schema())
+        with pytest.raises(ValueError, match="aren't stored"):
+            unstored.writer().update_fast_field("title", "x", {"title": "y"})
+
+    def test_update_fast_field_pending_changes(self):
+        schema = (
+            SchemaBuilder()
+            .add_integer_field("id", stored=True, indexed=True, fast=True)
+            .add_integer_field("clicks", stored=False, indexed=True, fast=True)
+            .add_integer_field("views", stored=True, fast=True)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer()
+        writer.add_document(Document(id=1, clicks=10, views=100))
+        with pytest.raises(ValueError, match="commit them before"):
+            writer.update_fast_field("id", 1, {"views": 101})
+        writer.commit()
+
+        # Consecutive updates accumulate, including when matched by a field
+        # updated since the last commit, and keep the unstored fast field.
+        assert writer.update_fast_field("id", 1, {"views": 101}) == 1
+        assert writer.update_fast_field("id", 1, {"clicks": 11}) == 1
+        assert writer.update_fast_field("clicks", 10, {"views": 0}) == 0
+        assert writer.update_fast_field("clicks", 11, {"views": 102}) == 1
+        writer.commit()
+        index.reload()
+
+        searcher = index.searcher()
+        assert searcher.num_docs == 1
+        (_, address), = searcher.search(Query.all_query()).hits
+        assert searcher.doc(address).to_dict() == {"id": [1], "views": [102]}
+        assert searcher.fast_field_values(address, "clicks") == [11]
+
+        writer.delete_documents("id", 1)
+        with pytest.raises(ValueError, match="commit them before"):
+            writer.update_fast_field("id", 1, {"views": 103})
+        writer.rollback()
+        assert writer.update_fast_field("id", 1, {"views": 103}) == 1
+
+    def test_concurrent_add_document(self):
+        import threading
+
+        index = Index(schema())
+        writer = index.writer(30_000_000, 2)
+
+        def add_documents(writer, thread_id):
+            for i in range(100):
+                writer.add_document(
+                    Document(title=f"thread {thread_id}", body=f"doc {i}")
+                )
+
+        threads = [
+            threading.Thread(target=add_documents, args=(writer.clone(), i))
+            for i in range(4)
+        ]
+        for thread in threads:
+            thread.start()
+        for thread in threads:
+            thread.join()
+
+        writer.commit()
+        index.reload()
+        assert index.searcher().num_docs == 400
+
+
+    def test_auto_commit(self):
+        import time
+
+        index = Index(schema())
+        writer = index.writer(15_000_000, 1)
+        with pytest.raises(ValueError):
+            writer.enable_auto_commit()
+
+        writer.enable_auto_commit(max_docs=5)
+        for i in range(5):
+            writer.add_document(Document(title=f"doc {i}"))
+
+        for _ in range(50):
+            time.sleep(0.1)
+            index.reload()
+            if index.searcher().num_docs == 5:
+                break
+        assert index.searcher().num_docs == 5
+        assert writer.last_commit_opstamp > 0
+
+        writer.disable_auto_commit()
+        writer.wait_merging_threads()
+
+
+    def test_add_documents_progress(self):
+        index = Index(schema())
+        writer = index.writer(15_000_000, 1)
+        progress = []
+        added = writer.add_documents(
+            [Document(title=f"doc {i}") for i in range(1500)],
+            on_progress=lambda done, total: progress.append((done, total)),
+        )
+        assert added == 1500
+        assert progress == [(1000, 1500), (1500, 1500)]
+        writer.commit()
+
+        writer.add_documents(Document(title="more") for _ in range(3))
+        writer.commit()
+
+        progress = []
+        writer.merge(on_progress=lambda done, total: progress.append((done, total)))
+        assert progress == [(0, 2), (2, 2)]
+        writer.wait_merging_threads()
+        index.reload()
+        assert index.searcher().num_segments == 1
+
+    def test_add_documents_parallel(self):
+        index = Index(schema())
+        writer = index.writer(15_000_000, 1)
+        batches = (
+            "\n".join(
+                json.dumps({"title": f"doc {i}"})
+                for i in range(b * 100, (b + 1) * 100)
+            ).encode()
+            for b in range(20)
+        )
+        assert writer.add_documents_parallel(batches, queue_size=2) == 2000
+        writer.commit()
+        index.reload()
+        assert index.searcher().num_docs == 2000
+
+        batches = ['{"title": "ok"}', '{"title": "ok"}\n{"title": 1}', "{}"]
+        with pytest.raises(ValueError, match="batch 1") as excinfo:
+            writer.add_documents_parallel(batches)
+        assert "Line 2" in str(excinfo.value.__cause__)
+
+    def test_reindex(self):
+        source = create_index()
+        index = Index(schema())
+        writer = index.writer(15_000_000, 1)
+        progress = []
+        added = writer.reindex(
+            source.searcher(),
+            on_progress=lambda done, total: progress.append((done, total)),
+        )
+        assert added == 3
+        assert progress == [(3, 3)]
+        writer.commit()
+        index.reload()
+        result = index.searcher().search(index.parse_query("Frankenstein", ["title"]))
+        assert len(result.hits) == 1
+
+    def test_compaction(self, tmpdir):
+        import time
+
+        index = Index(schema(), str(tmpdir))
+        writer = index.writer(15_000_000, 1)
+        for i in range(4):
+            writer.add_document(Document(title=f"doc {2 * i}"))
+            writer.add_document(Document(title=f"doc {2 * i + 1}"))
+            writer.commit()
+        writer.delete_documents("title", "0")
+        writer.commit()
+        writer.wait_merging_threads()
+        index.reload()
+        assert index.searcher().num_segments == 4
+
+        with pytest.raises(ValueError):
+            tantivy.CompactionPolicy(max_deleted_ratio=2.0)
+        policy = tantivy.CompactionPolicy(
+            interval_secs=0.05, max_segments=2, max_mb_per_sec=100.0
+        )
+        index.start_compaction(policy)
+        for _ in range(50):
+            time.sleep(0.1)
+            index.reload()
+            if index.searcher().num_segments == 2:
+                break
+        index.stop_compaction()
+        # The 3 smallest segments were merged, expunging the deleted document.
+        searcher = index.searcher()
+        assert searcher.num_segments == 2
+        assert searcher.num_docs == 7
+        assert searcher.search(Query.all_query()).count == 7
+
+    def test_writer_io_limit(self, tmpdir):
+        index = Index(schema(), str(tmpdir))
+        with pytest.raises(ValueError):
+            index.writer(15_000_000, 1, max_mb_per_sec=0)
+        writer = index.writer(15_000_000, 1, max_mb_per_sec=50.0)
+        assert writer.max_mb_per_sec == 50.0
+        writer.add_document(Document(title="first"))
+        writer.commit()
+
+        writer.max_mb_per_sec = None
+        assert writer.max_mb_per_sec is None
+        with pytest.raises(ValueError):
+            writer.max_mb_per_sec = -1.0
+        writer.max_mb_per_sec = 0.5
+        writer.add_document(Document(title="second"))
+        writer.commit()
+        writer.wait_merging_threads()
+
+        index.reload()
+        assert index.searcher().num_docs == 2
+        assert index.writer(15_000_000, 1).max_mb_per_sec is None
+
+
+class TestFromDiskClass(object):
+    def test_opens_from_dir_invalid_schema(self, dir_index):
+        invalid_schema = SchemaBuilder().add_text_field("🐱").build()
+        index_dir, _ = dir_index
+        with pytest.raises(ValueError):
+            Index(invalid_schema, str(index_dir), reuse=True)
+
+    def test_opens_from_dir(self, dir_index):
+        index_dir, _ = dir_index
+
+        index = Index(schema(), str(index_dir), reuse=True)
+        assert index.searcher().num_docs == 3
+
+    def test_pathlib_paths(self, tmpdir):
+        path = pathlib.Path(str(tmpdir)) / "índice 日本"
+        path.mkdir()
+        index = Index(schema(), path)
+        writer = index.writer(15_000_000, 1)
+        writer.add_document(Document(title="The sea"))
+        writer.commit()
+        writer.wait_merging_threads()
+
+        assert Index.exists(path)
+        assert Index.open(path).searcher().num_docs == 1
+        catalog = tantivy.IndexCatalog(pathlib.Path(str(tmpdir)))
+        catalog.swap("products", path)
+        assert pathlib.Path(catalog.resolve("products")) == path
+        assert catalog.open("products").searcher().num_docs == 1
+
+    def test_read_modes(self, dir_index):
+        index_dir, _ = dir_index
+
+        index = Index.open(str(index_dir), madvise="random")
+        query = index.parse_query("sea whale", ["title", "body"])
+        assert len(index.searcher().search(query, 10).hits) == 1
+
+        index = Index.open(str(index_dir), read_mode="read", cache_mb=1.0)
+        query = index.parse_query("sea whale", ["title", "body"])
+        assert len(index.searcher().search(query, 10).hits) == 1
+        writer = index.writer(15_000_000, 1)
+        writer.add_document(Document(title="The sea whale"))
+        writer.commit()
+        writer.wait_merging_threads()
+        index.reload()
+        assert len(index.searcher().search(query, 10).hits) == 2
+
+        with pytest.raises(ValueError):
+            Index.open(str(index_dir), read_mode="pread")
+        with pytest.raises(ValueError):
+            Index.open(str(index_dir), read_mode="read", madvise="random")
+        with pytest.raises(ValueError):
+            Index.open(str(index_dir), cache_mb=1.0)
+        with pytest.raises(ValueError):
+            Index(schema(), read_mode="read")
+
+    def test_open_missing_dir(self, tmpdir):
+        missing = str(tmpdir.join("missing"))
+        with pytest.raises(tantivy.IoError) as excinfo:
+            Index.open(missing)
+        assert excinfo.value.filepath == missing
+
+    def test_second_writer_lock_error(self, dir_index):
+        _, index = dir_index
+        writer = index.writer()
+        with pytest.raises(tantivy.LockAcquisitionError) as excinfo:
+            index.writer()
+        assert excinfo.value.busy
+        writer.wait_merging_threads()
+
+    def test_writer_lock_timeout(self, dir_index):
+        import threading
+        import time
+
+        _, index = dir_index
+        writer = index.writer()
+        start = time.monotonic()
+        with pytest.raises(tantivy.LockAcquisitionError):
+            index.writer(block=True, lock_timeout=0.2)
+        assert time.monotonic() - start >= 0.2
+
+        threading.Timer(0.2, writer.wait_merging_threads).start()
+        writer = index.writer(block=True, lock_timeout=10)
+
+        # A timeout waits for the lock without `block`.
+        threading.Timer(0.2, writer.wait_merging_threads).start()
+        writer = index.writer(lock_timeout=10)
+        writer.wait_merging_threads()
+
+    def test_index_meta(self, dir_index):
+        index_dir, index = dir_index
+        meta = index.meta()
+        assert meta.opstamp == 4
+        assert meta.payload is None
+        assert meta.num_segments == 1
+        assert Index.exists(str(index_dir))
+
+    def test_index_settings(self, tmpdir):
+        schema = (
+            SchemaBuilder()
+            .add_unsigned_field("timestamp", stored=True, fast=True)
+            .add_text_field("body", stored=True)
+            .build()
+        )
+        settings = tantivy.IndexSettings(
+            sort_by=("timestamp", tantivy.Order.Desc),
+            docstore_compression="zstd",
+            docstore_blocksize=4096,
+        )
+        index = Index(schema, str(tmpdir), settings=settings)
+        writer = index.writer(15_000_000, 1)
+        for timestamp in [1, 3, 2]:
+            doc = Document(body="text " * 100)
+            doc.add_unsigned("timestamp", timestamp)
+            writer.add_document(doc)
+        writer.commit()
+        writer.wait_merging_threads()
+
+        index = Index.open(str(tmpdir))
+        searcher = index.searcher()
+        hits = searcher.search(Query.all_query()).hits
+        # The documents are stored sorted by timestamp.
+        assert [searcher.doc(address)["timestamp"][0] for _, address in hits] == [3, 2, 1]
+        assert searcher.doc(hits[0][1])["body"][0] == "text " * 100
+
+        settings = index.settings
+        assert settings.sort_by == ("timestamp", tantivy.Order.Desc)
+        assert settings.docstore_compression == "zstd"
+        assert settings.docstore_compression_level is None
+        assert settings.docstore_blocksize == 4096
+
+        with pytest.raises(ValueError):
+            tantivy.IndexSettings(docstore_compression="brotli")
+
+    def test_docstore_compression_level(self):
+        settings = tantivy.IndexSettings(
+            docstore_compression="zstd", docstore_compression_level=19
+        )
+        index = Index(schema(), settings=settings)
+        assert index.settings.docstore_compression_level == 19
+        assert repr(index.settings) == (
+            "IndexSettings(docstore_compression='zstd', "
+            "docstore_compression_level=19, docstore_blocksize=16384)"
+        )
+        writer = index.writer(15_000_000, 1)
+        writer.add_document(Document(title="water " * 1000))
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+        (hit,) = searcher.search(Query.all_query()).hits
+        assert searcher.doc(hit[1])["title"] == ["water " * 1000]
+        assert Index(schema()).settings.docstore_compression == "lz4"
+
+        with pytest.raises(ValueError):
+            tantivy.IndexSettings(docstore_compression="zstd", docstore_compression_level=23)
+        with pytest.raises(ValueError):
+            tantivy.IndexSettings(docstore_compression="lz4", docstore_compression_level=3)
+
+    def test_doc_store_cache(self, dir_index):
+        _, index = dir_index
+        index.config_reader(doc_store_cache_num_blocks=10)
+        searcher = index.searcher()
+        assert searcher.doc_store_cache_stats() == {
+            "num_entries": 0,
+            "cache_hits": 0,
+            "cache_misses": 0,
+        }
+        hits = searcher.search(Query.all_query()).hits
+        for _ in range(2):
+            for _, address in hits:
+                searcher.doc(address)
+        stats = searcher.doc_store_cache_stats()
+        assert stats["num_entries"] == 1
+        assert stats["cache_misses"] == 1
+        assert stats["cache_hits"] == 5
+
+    def test_pinned_searcher(self, tmpdir):
+        index = Index(schema(), str(tmpdir))
+        writer = index.writer(15_000_000, 1)
+        writer.add_document(Document(title="old"))
+        writer.commit()
+        index.reload()
+        pinned = index.searcher().pin()
+        pinned_files = set(os.listdir(str(tmpdir)))
+
+        writer.delete_all_documents()
+        writer.add_document(Document(title="new"))
+        writer.commit()
+        writer.garbage_collect_files()
+        assert pinned_files <= set(os.listdir(str(tmpdir)))
+
+        with pinned as searcher:
+            hits = searcher.search(Query.all_query()).hits
+            assert [searcher.doc(address)["title"] for _, address in hits] == [
+                ["old"]
+            ]
+        assert pinned.released
+        with pytest.raises(ValueError):
+            pinned.searcher
+        pinned.release()
+
+        writer.garbage_collect_files()
+        assert not pinned_files <= set(os.listdir(str(tmpdir)))
+        writer.wait_merging_threads()
+
+    def test_scroll(self, tmpdir):
+        index = Index(schema(), str(tmpdir))
+        writer = index.writer(15_000_000, 1)
+        for i in range(5):
+            writer.add_document(Document(title=f"old {i}"))
+            writer.commit()
+        index.reload()
+        scroll = index.searcher().scroll(Query.all_query(), batch_size=2)
+        first = next(scroll)
+        assert len(first) == 2
+
+        writer.delete_all_documents()
+        writer.add_document(Document(title="new"))
+        writer.commit()
+        writer.garbage_collect_files()
+
+        batches = [first] + list(scroll)
+        assert [len(batch) for batch in batches] == [2, 2, 1]
+        titles = sorted(
+            scroll.searcher.doc(address)["title"][0]
+            for batch in batches
+            for _, address in batch
+        )
+        assert titles == [f"old {i}" for i in range(5)]
+        with pytest.raises(ValueError):
+            index.searcher().scroll(Query.all_query(), batch_size=0)
+        writer.wait_merging_threads()
+
+    def test_fetch_session(self, tmpdir):
+        index = Index(schema(), str(tmpdir))
+        writer = index.writer(15_000_000, 1)
+        for segment in range(2):
+            for i in range(20):
+                writer.add_document(Document(title=f"doc {segment} {i}"))
+            writer.commit()
+        writer.wait_merging_threads()
+        index.reload()
+        searcher = index.searcher()
+        addresses = [address for _, address in searcher.search(Query.all_query(), 40).hits]
+        expected = [searcher.doc(address)["title"] for address in addresses]
+
+        with searcher.fetch_session(cache_blocks=2) as session:
+            assert session.searcher.num_docs == 40
+            assert [doc["title"] for doc in session.docs(addresses)] == expected
+            assert session.doc(addresses[3])["title"] == expected[3]
+            assert "open_segments=2/2" in repr(session)
+        assert "open_segments=0/2" in repr(session)
+        # Closed sessions reopen their caches.
+        assert session.docs(addresses[:1])[0]["title"] == expected[0]
+
+        with pytest.raises(ValueError):
+            session.doc(tantivy.DocAddress(5, 0))
+        with pytest.raises(ValueError):
+            searcher.fetch_session(cache_blocks=0)
+
+    def test_writer_context_manager(self, tmpdir):
+        index = Index(schema(), str(tmpdir))
+        with index.writer(15_000_000, 1) as writer:
+            writer.add_document(Document(title="committed"))
+        with pytest.raises(RuntimeError):
+            writer.commit()
+
+        with pytest.raises(ZeroDivisionError):
+            with index.writer(15_000_000, 1) as writer:
+                writer.add_document(Document(title="rolled back"))
+                1 / 0
+
+        with index.writer(15_000_000, 1) as writer:
+            writer.wait_merging_threads()
+
+        index.reload()
+        assert index.searcher().num_docs == 1
+
+    def test_check(self, tmpdir):
+        index = Index(schema(), str(tmpdir))
+        writer = index.writer(15_000_000, 1)
+        for i in range(200):
+            writer.add_document(Document(title=f"title number {i} " * 20))
+        writer.commit()
+        writer.wait_merging_threads()
+        report = index.check(full=True)
+        assert report["ok"]
+        assert report["damaged_files"] == report["missing_files"] == []
+        assert [segment["error"] for segment in report["segments"]] == [None]
+        assert index.validate_checksum() == []
+
+        (store,) = [
+            name for name in os.listdir(str(tmpdir)) if name.endswith(".store")
+        ]
+        path = os.path.join(str(tmpdir), store)
+        with open(path, "rb") as f:
+            data = bytearray(f.read())
+        for i in range(20, len(data) - 200):
+            data[i] ^= 0xFF
+        with open(path, "wb") as f:
+            f.write(data)
+
+        index = Index.open(str(tmpdir))
+        assert index.validate_checksum() == [store]
+        report = index.check()
+        assert not report["ok"]
+        assert report["damaged_files"] == [store]
+        assert report["segments"] == []
+        (segment,) = index.check(full=True)["segments"]
+        assert segment["num_docs"] == 200
+        assert segment["error"] is not None
+
+    def test_open_skip_damaged_segment(self, tmpdir):
+        index = Index(schema(), str(tmpdir))
+        writer = index.writer(15_000_000, 1)
+        writer.add_document(Document(title="intact"))
+        writer.commit()
+        intact = set(os.listdir(str(tmpdir)))
+        writer.add_document(Document(title="damaged"))
+        writer.commit()
+        writer.wait_merging_threads()
+        (store,) = [
+            name
+            for name in set(os.listdir(str(tmpdir))) - intact
+            if name.endswith(".store")
+        ]
+        os.remove(os.path.join(str(tmpdir), store))
+
+        with pytest.raises(tantivy.IoError):
+            Index.open(str(tmpdir))
+        with pytest.raises(ValueError):
+            Index.open(str(tmpdir), on_corruption="ignore")
+
+        index = Index.open(str(tmpdir), on_corruption="skip_segment")
+        assert index.skipped_segments == [store.split(".")[0]]
+        searcher = index.searcher()
+        hits = searcher.search(Query.all_query()).hits
+        assert [searcher.doc(address)["title"] for _, address in hits] == [
+            ["intact"]
+        ]
+        with pytest.raises(tantivy.LockError):
+            index.writer()
+
+    def test_searcher_at(self, tmpdir):
+        index = Index(schema(), str(tmpdir))
+        writer = index.writer(15_000_000, 1)
+        writer.add_document(Document(title="first"))
+        first = writer.commit()
+        meta_copy = str(tmpdir) + "-meta.json"
+        with open(os.path.join(str(tmpdir), "meta.json")) as src:
+            with open(meta_copy, "w") as dst:
+                dst.write(src.read())
+        writer.add_document(Document(title="second"))
+        second = writer.commit()
+
+        def titles(searcher):
+            hits = searcher.search(Query.all_query()).hits
+            return sorted(searcher.doc(address)["title"][0] for _, address in hits)
+
+        assert titles(index.searcher_at(first)) == ["first"]
+        assert titles(index.searcher_at(meta_copy)) == ["first"]
+        assert titles(index.searcher_at(second)) == ["first", "second"]
+        with pytest.raises(ValueError):
+            index.searcher_at(second + 1)
+
+        writer.delete_all_documents()
+        writer.commit()
+        writer.wait_merging_threads()
+        with pytest.raises(ValueError):
+            index.searcher_at(first)
+        with pytest.raises(ValueError):
+            index.searcher_at(meta_copy)
+
+    def test_commit_meta(self, tmpdir):
+        index = Index(schema(), str(tmpdir))
+        writer = index.writer(15_000_000, 1)
+        writer.add_document(Document(title="first"))
+        first = writer.commit()
+        assert index.commit_meta() is None
+
+        writer.set_commit_meta({"pipeline": "2.1", "snapshot": 42})
+        writer.add_document(Document(title="second"))
+        second = writer.commit()
+        assert index.commit_meta() == {"pipeline": "2.1", "snapshot": 42}
+
+        writer.set_commit_meta({"pipeline": "2.2"})
+        writer.add_document(Document(title="third"))
+        writer.commit()
+        assert index.commit_meta() == {"pipeline": "2.2"}
+        assert index.commit_meta(second) == {"pipeline": "2.1", "snapshot": 42}
+        assert index.commit_meta(first) is None
+        with pytest.raises(ValueError):
+            index.commit_meta(first + 100)
+
+        writer.set_commit_meta(None)
+        writer.commit()
+        assert index.commit_meta() is None
+        # The metadata is kept out of the payload of the commits.
+        assert index.meta().payload is None
+
+        history = pathlib.Path(str(tmpdir), ".tantivy-py.commits.json")
+        commits = json.loads(history.read_text("utf-8"))
+        commits[-1]["commit_meta"] = "not a dict"
+        history.write_text(json.dumps(commits), "utf-8")
+        with pytest.raises(ValueError):
+            index.commit_meta()
+
+    def test_create_readers(self):
+        # not sure what is the point of this test.
+        idx = Index(schema())
+        idx.config_reader("Manual", 4)
+        assert idx.searcher().num_docs == 0
+        # by default this is manual mode
+        writer = idx.writer(30000000, 1)
+        writer.add_document(Document(title="mytitle", body="mybody"))
+        writer.commit()
+        assert idx.searcher().num_docs == 0
+        # Manual is the default setting.
+        # In this case, change are reflected only when
+        # the index is manually reloaded.
+        idx.reload()
+        assert idx.searcher().num_docs == 1
+        idx.config_reader("OnCommit", 4)
+        writer.add_document(Document(title="mytitle2", body="mybody2"))
+        writer.commit()
+        import time
+
+        for i in range(50):
+            # The index should be automatically reloaded.
+            # Wait for at most 5s for it to happen.
+            time.sleep(0.1)
+            if idx.searcher().num_docs == 2:
+                return
+        assert False
+
+
+class TestSpellCorrector(object):
+    def test_suggest(self, ram_index):
+        corrector = ram_index.spell_corrector("body")
+        assert corrector.suggest("Rivr") == ["river"]
+        assert corrector.suggest("watr", max_edits=1) == ["water"]
+        # The closest terms come first, then the most frequent ones.
+        assert corrector.suggest("watr") == ["water", "with", "warm", "was"]
+        assert corrector.suggest("watr", limit=2) == ["water", "with"]
+        assert "river" not in corrector.suggest("river")
+
+        # Terms below the frequency threshold are not suggested.
+        corrector = ram_index.spell_corrector("body", min_doc_freq=2)
+        assert corrector.suggest("watr") == ["with"]
+
+        with pytest.raises(ValueError):
+            corrector.suggest("watr", max_edits=3)
+        with pytest.raises(tantivy.SchemaError):
+            ram_index.spell_corrector("id")
+
+
+class TestSuggester(object):
+    def test_suggest(self, tmpdir):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title", stored=True)
+            .add_text_field("body")
+            .add_unsigned_field("popularity", stored=True)
+            .build()
+        )
+        index = Index(schema, str(tmpdir))
+        writer = index.writer()
+        for title, popularity in [
+            ("iPhone 15", 100),
+            ("iPhone 14", 50),
+            ("iPad Pro", 80),
+            ("Pixel 8", 70),
+            ("iphone 15", 10),
+        ]:
+            writer.add_document(
+                Document(title=title, body=f"{title} phone", popularity=popularity)
+            )
+        writer.commit()
+        index.reload()
+
+        # Stored values are suggested, weighted by their number of documents.
+        suggester = index.suggester("title")
+        assert len(suggester) == 4
+        assert suggester.suggest("IPH") == ["iPhone 15", "iPhone 14"]
+        assert suggester.suggest("ip", limit=1) == ["iPhone 15"]
+        assert suggester.suggest("ipjo") == []
+        assert suggester.suggest("ipjo", max_edits=1) == ["iPhone 15", "iPhone 14"]
+
+        suggester = index.suggester("title", weight_field="popularity")
+        assert suggester.suggest("ip") == ["iPhone 15", "iPad Pro", "iPhone 14"]
+
+        # Terms are suggested for fields that aren't stored.
+        assert index.suggester("body").suggest("p") == ["phone", "pixel", "pro"]
+
+        # The suggester is rebuilt once the index changed.
+        writer.add_document(Document(title="iPod", popularity=1000))
+        writer.commit()
+        index = Index.open(str(tmpdir))
+        suggester = index.suggester("title", weight_field="popularity")
+        assert suggester.suggest("ip")[0] == "iPod"
+
+        with pytest.raises(ValueError):
+            index.suggester("body", weight_field="popularity")
+        with pytest.raises(tantivy.SchemaError):
+            index.suggester("popularity")
+
+
+class TestPercolator(object):
+    def test_match_document(self, ram_index):
+        index = ram_index
+        percolator = tantivy.Percolator(index.schema)
+        percolator.add_query("sea", index.parse_query("sea", ["title"]))
+        percolator.add_query("old", index.parse_query("old", ["title", "body"]))
+        percolator.add_query("fish", index.parse_query("body:fish"))
+        assert len(percolator) == 3
+
+        doc = Document(title="The Old Man and the Sea", body="a fish")
+        assert percolator.match_document(doc) == ["fish", "old", "sea"]
+        assert percolator.match_document(Document(title="Moby Dick")) == []
+
+        assert percolator.remove_query("fish")
+        assert not percolator.remove_query("fish")
+        assert percolator.match_documents(
+            [doc, Document(title="old"), Document(title="new")]
+        ) == [["old", "sea"], ["old"], []]
+
+
+class TestEvaluator(object):
+    def test_evaluate(self, ram_index_numeric_fields):
+        index = ram_index_numeric_fields
+        evaluator = tantivy.Evaluator(index.searcher(), "id")
+        judgments = [
+            (index.parse_query("fish", ["body"]), {1: 1.0}),
+            (index.parse_query("green", ["body"]), {1: 2.0}),
+        ]
+        report = evaluator.evaluate(judgments)
+        assert report["metric"] == "ndcg@10"
+        assert report["per_query"] == [1.0, 0.0]
+        assert report["mean"] == 0.5
+        assert evaluator.evaluate(judgments, "mrr")["per_query"] == [1.0, 0.0]
+        assert evaluator.evaluate(judgments, "recall@1")["mean"] == 0.5
+        assert evaluator.evaluate(judgments, "precision@2")["mean"] == 0.25
+
+        with pytest.raises(ValueError):
+            evaluator.evaluate(judgments, "map@10")
+        with pytest.raises(ValueError):
+            evaluator.evaluate(judgments, "ndcg@0")
+        with pytest.raises(tantivy.SchemaError):
+            tantivy.Evaluator(index.searcher(), "is_good")
+
+
+class TestIndexCatalog(object):
+    def test_swap(self, tmpdir):
+        for name, title in [("blue", "old title"), ("green", "new title")]:
+            path = os.path.join(str(tmpdir), name)
+            os.mkdir(path)
+            index = Index(schema(), path)
+            writer = index.writer()
+            writer.add_document(Document(title=title))
+            writer.commit()
+            writer.wait_merging_threads()
+
+        catalog = tantivy.IndexCatalog(str(tmpdir))
+        assert catalog.swap("products", "blue") is None
+        index = catalog.open("products")
+        assert catalog.swap("products", "green") == "blue"
+        assert catalog.aliases() == {"products": "green"}
+        green = os.path.join(str(tmpdir), "green")
+        assert str(catalog.resolve("products")) == green
+
+        query = Query.term_query(schema(), "title", "old")
+        assert index.searcher().search(query).count == 1
+        index = tantivy.IndexCatalog(str(tmpdir)).open("products")
+        assert index.searcher().search(query).count == 0
+
+        os.mkdir(os.path.join(str(tmpdir), "empty"))
+        with pytest.raises(ValueError):
+            catalog.swap("products", "empty")
+        assert catalog.remove("products") == "green"
+        with pytest.raises(KeyError):
+            catalog.open("products")
+
+
+@pytest.mark.skipif(
+    not hasattr(tantivy, "serve"), reason="built without the server feature"
+)
+class TestSearchServer(object):
+    def test_serve(self, ram_index_numeric_fields):
+        import urllib.error
+        import urllib.request
+
+        with tantivy.serve(ram_index_numeric_fields, port=0) as server:
+            url = f"http://{server.host}:{server.port}"
+
+            def get(path, data=None):
+                request = urllib.request.Request(url + path, data=data)
+                with urllib.request.urlopen(request) as response:
+                    return json.loads(response.read())
+
+            result = get("/search?q=body%3Aold+AND+rating%3A%3E4&limit=5")
+            assert result["count"] == 0
+            result = get("/search?q=old+man&fields=body")
+            assert result["count"] == 1
+            hit = result["hits"][0]
+            assert hit["fields"]["id"] == [1]
+            address = f"segment_ord={hit['segment_ord']}&doc={hit['doc']}"
+            assert get("/doc?" + address)["rating"] == [3.5]
+
+            aggs = {"aggs": {"top": {"max": {"field": "rating"}}}}
+            result = get("/aggregate", json.dumps(aggs).encode())
+            assert result["top"]["value"] == 4.5
+
+            with pytest.raises(urllib.error.HTTPError) as excinfo:
+                get("/search?q=body:(")
+            assert excinfo.value.code == 400
+            with pytest.raises(urllib.error.HTTPError) as excinfo:
+                get("/missing")
+            assert excinfo.value.code == 404
+        assert not server.running
+
+
+@pytest.mark.skipif(
+    not hasattr(tantivy.Index, "flight_server"),
+    reason="built without the flight feature",
+)
+class TestFlightServer(object):
+    def test_flight_server(self, ram_index_numeric_fields):
+        with pytest.raises(ValueError):
+            ram_index_numeric_fields.flight_server(0, num_threads=0)
+
+        with ram_index_numeric_fields.flight_server(0) as server:
+            assert server.running
+            assert server.location == f"grpc+tcp://127.0.0.1:{server.port}"
+
+            flight = pytest.importorskip("pyarrow.flight")
+            client = flight.connect(server.location)
+            search = {"q": "old man", "fields": "body"}
+            ticket = flight.Ticket(json.dumps(search).encode())
+            table = client.do_get(ticket).read_all()
+            assert table.num_rows == 1
+            assert table.column_names[:3] == ["score", "segment_ord", "doc"]
+            assert table.column("id").to_pylist() == [[1]]
+            assert table.column("rating").to_pylist() == [[3.5]]
+
+            command = json.dumps({"q": "*", "limit": 1}).encode()
+            info = client.get_flight_info(
+                flight.FlightDescriptor.for_command(command)
+            )
+            assert info.total_records == 1
+            assert info.schema == table.schema
+
+            import pyarrow
+
+            bad = flight.Ticket(json.dumps({"q": "body:("}).encode())
+            with pytest.raises(pyarrow.ArrowInvalid):
+                client.do_get(bad).read_all()
+        assert not server.running
+
+
+class TestTenantIndex(object):
+    def test_tenant_index(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("tenant", stored=True, tokenizer_name="raw")
+            .add_integer_field("id", stored=True, indexed=True)
+            .add_text_field("body")
+            .build()
+        )
+        index = Index(schema)
+        acme = tantivy.TenantIndex(index, "tenant", "acme")
+        assert acme.tenant == "acme"
+        assert repr(acme) == "TenantIndex(tenant_field='tenant', tenant='acme')"
+
+        writer = acme.writer()
+        writer.add_document(Document(id=1, body="shared words"))
+        writer.add_document(Document(id=2, body="shared words", tenant="acme"))
+        with pytest.raises(ValueError):
+            writer.add_document(Document(id=3, body="leak", tenant="globex"))
+        writer.commit()
+        writer.wait_merging_threads()
+
+        globex = tantivy.TenantIndex(index, "tenant", "globex")
+        writer = globex.writer()
+        writer.add_document(Document(id=1, body="shared words"))
+        writer.commit()
+        writer.wait_merging_threads()
+        index.reload()
+
+        query = index.parse_query("shared", ["body"])
+        assert index.searcher().search(query).count == 3
+        searcher = acme.searcher()
+        assert searcher.search(query).count == 2
+        for _, address in searcher.search(query).hits:
+            assert searcher.doc(address)["tenant"] == ["acme"]
+
+        # Deletes are scoped to the tenant.
+        writer = globex.writer()
+        writer.delete_documents("id", 1)
+        writer.commit()
+        writer.delete_all_documents()
+        writer.commit()
+        writer.wait_merging_threads()
+        index.reload()
+        assert acme.searcher().search(query).count == 2
+        assert globex.searcher().search(query).count == 0
+
+        with pytest.raises(tantivy.SchemaError):
+            tantivy.TenantIndex(index, "body", "acme")
+
+
+class TestMultiIndexReader(object):
+    def test_acquire(self):
+        indexes = [create_index(), create_index()]
+        reader = tantivy.MultiIndexReader(indexes)
+        before = reader.acquire()
+        assert len(before) == 2
+        assert before.generation == 0
+
+        for index in indexes:
+            writer = index.writer(15_000_000, 1)
+            writer.add_document(Document(title="The Old Man and the Sea"))
+            writer.commit()
+            writer.wait_merging_threads()
+            index.reload()
+        # The searchers only change when the reader reloads.
+        assert [s.num_docs for s in reader.acquire().searchers] == [3, 3]
+
+        reader.reload()
+        after = reader.acquire()
+        assert after.generation == 1
+        assert [s.num_docs for s in after.searchers] == [4, 4]
+        assert [s.num_docs for s in before.searchers] == [3, 3]
+        assert after[-1].num_docs == 4
+        with pytest.raises(IndexError):
+            after[2]
+
+
+class TestSearcher(object):
+    def test_searcher_repr(self, ram_index, ram_index_numeric_fields):
+        assert repr(ram_index.searcher()) == "Searcher(num_docs=3, num_segments=1)"
+        assert (
+            repr(ram_index_numeric_fields.searcher())
+            == "Searcher(num_docs=2, num_segments=1)"
+        )
+
+    def test_approx_count(self, ram_index):
+        schema = SchemaBuilder().add_integer_field("n", indexed=True).build()
+        index = Index(schema)
+        writer = index.writer(50_000_000, 1)
+        for n in range(100_000):
+            writer.add_document(Document(n=n % 10))
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+        query = Query.term_query(schema, "n", 3)
+
+        estimate = searcher.approx_count(query, max_error=0.05)
+        assert not estimate["exact"]
+        assert estimate["sampled_docs"] < 100_000
+        assert estimate["relative_error"] <= 0.05
+        assert abs(estimate["count"] - 10_000) <= 1_000
+        assert searcher.approx_count(query, max_error=0) == {
+            "count": 10_000,
+            "exact": True,
+            "relative_error": 0.0,
+            "sampled_docs": 100_000,
+        }
+        # Small indexes are counted exactly.
+        assert ram_index.searcher().approx_count(Query.all_query())["exact"]
+        with pytest.raises(ValueError):
+            searcher.approx_count(query, max_error=1.5)
+
+    def test_share_between_threads(self, ram_index):
+        import sys
+        import sysconfig
+        from concurrent.futures import ThreadPoolExecutor
+
+        if sysconfig.get_config_var("Py_GIL_DISABLED"):
+            # Importing the module didn't enable the GIL again.
+            assert not sys._is_gil_enabled()
+
+        searcher = ram_index.searcher()
+        query = ram_index.parse_query("sea", ["title", "body"])
+        expected = searcher.search(query, 10).hits
+        with ThreadPoolExecutor(8) as pool:
+            results = list(
+                pool.map(lambda _: searcher.search(query, 10).hits, range(200))
+            )
+        assert results == [expected] * 200
+
+        index = Index(schema())
+        writer = index.writer(15_000_000, 1)
+        for i in range(1000):
+            writer.add_document(Document(title=f"doc {i}"))
+        writer.commit()
+        index.reload()
+        scroll = index.searcher().scroll(Query.all_query(), batch_size=7)
+
+        def drain(_):
+            return [address for batch in scroll for _, address in batch]
+
+        with ThreadPoolExecutor(4) as pool:
+            addresses = [a for part in pool.map(drain, range(4)) for a in part]
+        assert len(addresses) == 1000
+        assert len(set(addresses)) == 1000
+
+    def test_with_filter(self, ram_index):
+        index = ram_index
+        searcher = index.searcher()
+        sea = Query.term_query(index.schema, "title", "sea")
+        filtered = searcher.with_filter(sea)
+
+        assert filtered.search(Query.all_query()).count == 1
+        query = index.parse_query("old OR mice", ["title", "body"])
+        (hit,) = filtered.search(query).hits
+        assert filtered.doc(hit[1])["title"] == ["The Old Man and the Sea"]
+        # The filter doesn't change the scores.
+        assert hit == searcher.search(query).hits[0]
+
+        tenant = ["mice"]
+        dynamic = searcher.with_filter(
+            lambda: Query.term_query(index.schema, "title", tenant[0])
+        )
+        assert dynamic.search(Query.all_query()).count == 1
+        tenant[0] = "frankenstein"
+        (hit,) = dynamic.search(Query.all_query()).hits
+        assert dynamic.doc(hit[1])["title"][0] == "Frankenstein"
+
+        # Filters add up.
+        assert dynamic.with_filter(sea).search(Query.all_query()).count == 0
+        # The original searcher is left unfiltered.
+        assert searcher.search(Query.all_query()).count == 3
+
+        with pytest.raises(TypeError):
+            searcher.with_filter("title:sea")
+
+    def test_with_filter_hides_other_documents(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("tenant", stored=True, tokenizer_name="raw")
+            .add_text_field("body", stored=True)
+            .add_integer_field("clicks", stored=True, fast=True)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        writer.add_document(Document(tenant="a", body="red shoe", clicks=1))
+        writer.add_document(Document(tenant="b", body="secret boot", clicks=2))
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+        tenant_a = searcher.with_filter(Query.term_query(schema, "tenant", "a"))
+        ((_, own),) = tenant_a.search(Query.all_query()).hits
+        ((_, other),) = searcher.search(
+            Query.term_query(schema, "tenant", "b")
+        ).hits
+
+        assert tenant_a.doc(own)["body"] == ["red shoe"]
+        assert tenant_a.fast_field_values(own, "clicks") == [1]
+        assert "shoe" in tenant_a.term_vector(own, "body")
+        excluded = [
+            lambda: tenant_a.doc(other),
+            lambda: tenant_a.fast_field_values(other, "clicks"),
+            lambda: tenant_a.term_vector(other, "body"),
+            lambda: tenant_a.significant_terms(other, "body"),
+            lambda: tenant_a.extract_features(
+                Query.all_query(), [own, other], ["score"]
+            ),
+            lambda: tenant_a.fetch_session().doc(other),
+            lambda: tenant_a.fetch_session().docs([own, other]),
+        ]
+        for read in excluded:
+            with pytest.raises(ValueError, match="matching the filters"):
+                read()
+
+        terms = dict(tenant_a.top_terms_by_doc_freq("body"))
+        assert terms == {"red": 1, "shoe": 1}
+        assert dict(searcher.top_terms_by_doc_freq("body"))["secret"] == 1
+        # The statistics of the scores still count the whole index.
+        assert tenant_a.num_docs == 2
+        assert tenant_a.doc_freq("body", "secret") == 1
+
+    def test_query_cache(self, ram_index):
+        index = ram_index
+        query = index.parse_query("old OR mice", ["title", "body"])
+        sea = Query.term_query(index.schema, "title", "sea")
+        expected = index.searcher().with_filter(sea).search(query).hits
+
+        index.searcher().enable_query_cache(max_entries=10, max_bytes=1000)
+        filtered = index.searcher().with_filter(sea)
+        assert filtered.search(query).hits == expected
+        assert filtered.search(query).hits == expected
+        # The cache is shared by the searchers of the index.
+        stats = index.searcher().query_cache_stats()
+        assert stats["num_entries"] == 1
+        assert stats["cache_misses"] == 1
+        assert stats["cache_hits"] == 1
+
+        index.searcher().enable_query_cache(max_entries=0, max_bytes=1000)
+        assert index.searcher().query_cache_stats()["num_entries"] == 0
+        assert filtered.search(query).hits == expected
+
+    def test_matching_bitset(self, ram_index):
+        index = ram_index
+        searcher = index.searcher()
+        query = index.parse_query("old OR mice", ["title", "body"])
+        sea = searcher.matching_bitset(index.parse_query("sea", ["title"]))
+        titled = searcher.matching_bitset(Query.all_query())
+        assert len(sea) == 1
+        assert len(sea & titled) == 1
+        assert len(sea | titled) == 3
+
+        sea_query = Query.term_query(index.schema, "title", "sea")
+        expected = searcher.with_filter(sea_query).search(query).hits
+        assert searcher.search(query, pre_filter=sea).hits == expected
+
+        # A bitset doesn't apply to other segments.
+        other = Index(schema())
+        writer = other.writer()
+        writer.add_document(Document(title="The Sea"))
+        writer.commit()
+        other.reload()
+        with pytest.raises(ValueError):
+            other.searcher().search(query, pre_filter=sea)
+
+    def test_extract_features(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title", stored=True)
+            .add_text_field("body")
+            .add_integer_field("clicks", fast=True)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        writer.add_document(Document(title="red shoe", body="shoe", clicks=7))
+        writer.add_document(Document(title="blue shoe shoe", body="boot"))
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+        query = index.parse_query("red shoe", ["title", "body"])
+        hits = searcher.search(query).hits
+        features = [
+            "score",
+            "bm25:title",
+            "bm25:body",
+            "term_matches:title",
+            "field_length:title",
+            "value:clicks",
+        ]
+
+        matrix = searcher.extract_features(
+            query, [address for _, address in hits], features
+        )
+        assert matrix.shape == (2, 6)
+        assert matrix.feature_names == features
+        rows = matrix.tolist()
+        for (score, _), row in zip(hits, rows):
+            assert row[0] == pytest.approx(score)
+            assert row[0] == pytest.approx(row[1] + row[2])
+        assert [row[3:5] for row in rows] == [[2.0, 2.0], [1.0, 3.0]]
+        assert rows[0][5] == 7.0
+        assert math.isnan(rows[1][5])
+
+        view = memoryview(matrix)
+        assert view.shape == (2, 6)
+        assert view.format == "d"
+        assert view.tolist()[0] == rows[0]
+
+        with pytest.raises(ValueError):
+            searcher.extract_features(query, [], ["bm25"])
+        with pytest.raises(tantivy.SchemaError):
+            searcher.extract_features(query, [], ["value:title"])
+        with pytest.raises(ValueError):
+            searcher.extract_features(
+                query, [tantivy.DocAddress(0, 9)], ["score"]
+            )
+
+    def test_term_vector(self, ram_index):
+        index = ram_index
+        searcher = index.searcher()
+        query = Query.term_query(index.schema, "title", "mice")
+        ((_, address),) = searcher.search(query).hits
+        assert searcher.term_vector(address, "title") == {
+            "of": (1, [0]),
+            "mice": (1, [1]),
+            "and": (1, [2]),
+            "men": (1, [3]),
+        }
+
+        with pytest.raises(ValueError):
+            searcher.term_vector(tantivy.DocAddress(0, 99), "title")
+
+    def test_significant_terms(self, ram_index):
+        index = ram_index
+        searcher = index.searcher()
+        query = Query.term_query(index.schema, "title", "mice")
+        ((_, address),) = searcher.search(query).hits
+        terms = searcher.significant_terms(address, "title", k=2)
+        assert len(terms) == 2
+        assert {term for term, _ in terms} <= {"of", "mice", "and", "men"}
+        assert terms[0][1] >= terms[1][1]
+
+        terms = dict(searcher.significant_terms(query, "body"))
+        assert terms
+        assert all(score > 0 for score in terms.values())
+
+        with pytest.raises(TypeError):
+            searcher.significant_terms("mice", "title")
+        with pytest.raises(ValueError):
+            searcher.significant_terms(tantivy.DocAddress(0, 99), "title")
+
+    def test_field_density(self):
+        schema = (
+            SchemaBuilder()
+            .add_integer_field("id", indexed=True, stored=True)
+            .add_text_field("title", stored=True)
+            .add_text_field("body")
+            .add_float_field("rating", fast=True)
+            .add_json_field("attributes")
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer(15_000_000, 1)
+        for i in range(4):
+            doc = Document()
+            doc.add_integer("id", i)
+            if i < 3:
+                doc.add_text("title", f"title {i}")
+            if i % 2 == 0:
+                doc.add_float("rating", 4.5)
+                doc.add_json("attributes", {"color": "red"})
+            if i == 1:
+                doc.add_text("body", "body")
+            writer.add_document(doc)
+        writer.commit()
+        writer.delete_documents("id", 0)
+        writer.commit()
+        writer.wait_merging_threads()
+        index.reload()
+        searcher = index.searcher()
 
-        assert orig == pickled
+        density = searcher.field_density("title")
+        assert density["num_docs"] == 2
+        assert sum(density["segments"]) == 2
+        assert density["density"] == pytest.approx(2 / 3)
+        assert searcher.field_density("body")["num_docs"] == 1
+        assert searcher.field_density("rating")["num_docs"] == 1
+        assert searcher.field_density("attributes")["num_docs"] == 1
+        assert searcher.field_density("id")["density"] == 1.0
 
-    def test_delete_all_documents(self, ram_index):
-        index = ram_index
+        with pytest.raises(ValueError):
+            searcher.field_density("missing")
+
+    def test_knn_search(self, tmpdir):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title", stored=True)
+            .add_vector_field("embedding", 3)
+            .add_vector_field("position", 2, metric="l2")
+            .build()
+        )
+        index = Index(schema, str(tmpdir))
         writer = index.writer()
-        writer.delete_all_documents()
+        for title, embedding, position in [
+            ("a", [1.0, 0.0, 0.0], [0.0, 0.0]),
+            ("b", [0.0, 1.0, 0.0], [3.0, 4.0]),
+            ("c", [1.0, 1.0, 0.0], [1.0, 0.0]),
+        ]:
+            doc = Document(title=title)
+            doc.add_vector("embedding", embedding)
+            doc.add_vector("position", position)
+            writer.add_document(doc)
+        writer.add_document(Document(title="no vector"))
         writer.commit()
 
-        index.reload()
-        query = index.parse_query("sea whale", ["title", "body"])
-        result = index.searcher().search(query, 10)
-
-        assert len(result.hits) == 0
+        # The vector fields survive reopening the index.
+        index = Index.open(str(tmpdir))
+        searcher = index.searcher()
 
+        def titles(result):
+            return [searcher.doc(address)["title"][0] for _, address in result.hits]
 
-class TestUpdateClass(object):
-    def test_delete_update(self, ram_index):
-        query = ram_index.parse_query("Frankenstein", ["title"])
-        result = ram_index.searcher().search(query, 10)
-        assert len(result.hits) == 1
+        result = searcher.knn_search("embedding", [1.0, 0.0, 0.0], k=2)
+        assert titles(result) == ["a", "c"]
+        assert result.hits[0][0] == pytest.approx(1.0)
+        assert result.count is None
 
-        writer = ram_index.writer()
+        result = searcher.knn_search("position", [0.0, 0.0])
+        assert titles(result) == ["a", "c", "b"]
+        assert [score for score, _ in result.hits] == pytest.approx([0.0, 1.0, 5.0])
 
-        with pytest.raises(ValueError):
-            writer.delete_documents("fake_field", "frankenstein")
+        query = index.parse_query("b", ["title"])
+        result = searcher.knn_search("embedding", [1.0, 0.0, 0.0], filter_query=query)
+        assert titles(result) == ["b"]
 
-        with pytest.raises(ValueError):
-            writer.delete_documents("title", b"frankenstein")
+        with pytest.raises(tantivy.SchemaError):
+            searcher.knn_search("title", [1.0, 0.0, 0.0])
+        with pytest.raises(tantivy.SchemaError):
+            searcher.knn_search("embedding", [1.0, 0.0])
 
-        writer.delete_documents("title", "frankenstein")
+    def test_find_near_duplicates(self, tmpdir):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title", stored=True)
+            .add_text_field("body")
+            .add_signature_field("simhash", "body")
+            .add_signature_field("minhash", "body", kind="minhash", num_hashes=32)
+            .build()
+        )
+        index = Index(schema, str(tmpdir))
+        writer = index.writer()
+        story = (
+            "the central bank raised interest rates by a quarter point on "
+            "tuesday citing persistent inflation in services and housing"
+        )
+        for title, body in [
+            ("original", story),
+            ("copy", story.upper()),
+            ("edited", story.replace("tuesday", "wednesday")),
+            ("other", "the local team won the championship after extra time"),
+        ]:
+            writer.add_document(Document(title=title, body=body))
+        writer.add_document(Document(title="no body"))
         writer.commit()
-        ram_index.reload()
 
-        result = ram_index.searcher().search(query)
-        assert len(result.hits) == 0
+        index = Index.open(str(tmpdir))
+        searcher = index.searcher()
+        query = Query.term_query(index.schema, "title", "original")
+        ((_, address),) = searcher.search(query).hits
 
+        def titles(result):
+            return [searcher.doc(address)["title"][0] for _, address in result.hits]
 
-class TestFromDiskClass(object):
-    def test_opens_from_dir_invalid_schema(self, dir_index):
-        invalid_schema = SchemaBuilder().add_text_field("🐱").build()
-        index_dir, _ = dir_index
-        with pytest.raises(ValueError):
-            Index(invalid_schema, str(index_dir), reuse=True)
+        result = searcher.find_near_duplicates(address, 0, field_name="simhash")
+        assert titles(result) == ["copy"]
+        assert result.hits[0][0] == 0
 
-    def test_opens_from_dir(self, dir_index):
-        index_dir, _ = dir_index
+        result = searcher.find_near_duplicates(address, 16, field_name="minhash")
+        assert titles(result)[:1] == ["copy"]
+        assert "edited" in titles(result)
+        assert "other" not in titles(result)
 
-        index = Index(schema(), str(index_dir), reuse=True)
-        assert index.searcher().num_docs == 3
+        with pytest.raises(ValueError):
+            searcher.find_near_duplicates(address)
+        with pytest.raises(tantivy.SchemaError):
+            searcher.find_near_duplicates(address, field_name="body")
+        with pytest.raises(ValueError):
+            SchemaBuilder().add_signature_field("signature", "body").build()
 
-    def test_create_readers(self):
-        # not sure what is the point of this test.
-        idx = Index(schema())
-        idx.config_reader("Manual", 4)
-        assert idx.searcher().num_docs == 0
-        # by default this is manual mode
-        writer = idx.writer(30000000, 1)
-        writer.add_document(Document(title="mytitle", body="mybody"))
-        writer.commit()
-        assert idx.searcher().num_docs == 0
-        # Manual is the default setting.
-        # In this case, change are reflected only when
-        # the index is manually reloaded.
-        idx.reload()
-        assert idx.searcher().num_docs == 1
-        idx.config_reader("OnCommit", 4)
-        writer.add_document(Document(title="mytitle2", body="mybody2"))
+    def test_hybrid_search(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title", stored=True)
+            .add_vector_field("embedding", 2)
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer()
+        for title, embedding in [
+            ("old man sea", [0.0, 1.0]),
+            ("old sea", [1.0, 0.0]),
+            ("young man", [1.0, 0.1]),
+        ]:
+            doc = Document(title=title)
+            doc.add_vector("embedding", embedding)
+            writer.add_document(doc)
         writer.commit()
-        import time
+        index.reload()
+        searcher = index.searcher()
+        query = index.parse_query("old", ["title"])
 
-        for i in range(50):
-            # The index should be automatically reloaded.
-            # Wait for at most 5s for it to happen.
-            time.sleep(0.1)
-            if idx.searcher().num_docs == 2:
-                return
-        assert False
+        def titles(result):
+            return [searcher.doc(address)["title"][0] for _, address in result.hits]
 
+        # Documents found by both rankings come first.
+        result = searcher.hybrid_search(query, ("embedding", [1.0, 0.0]))
+        assert titles(result)[0] == "old sea"
+        assert len(result.hits) == 3
+        assert result.hits[0][0] == pytest.approx(2 / 61)
 
-class TestSearcher(object):
-    def test_searcher_repr(self, ram_index, ram_index_numeric_fields):
-        assert repr(ram_index.searcher()) == "Searcher(num_docs=3, num_segments=1)"
-        assert (
-            repr(ram_index_numeric_fields.searcher())
-            == "Searcher(num_docs=2, num_segments=1)"
+        result = searcher.hybrid_search(
+            query, ("embedding", [1.0, 0.0]), k=1, fusion="linear", weights=(0.0, 1.0)
         )
+        assert titles(result) == ["old sea"]
+        assert result.hits[0][0] == pytest.approx(1.0)
+
+        scores = [(1.0, address) for _, address in searcher.search(query).hits]
+        result = searcher.hybrid_search(query, scores, fusion="linear")
+        assert len(result.hits) == 2
+
+        with pytest.raises(ValueError):
+            searcher.hybrid_search(query, scores, fusion="max")
 
 
 class TestDocument(object):
@@ -628,6 +3165,52 @@ class TestDocument(object):
         assert doc.get_first("reference") == 1
         assert doc.to_dict() == {"name": ["Bill"], "reference": [1, 2]}
 
+    def test_document_typed_getters(self):
+        date = datetime.datetime(2019, 8, 12, 13, 0, 0)
+        doc = tantivy.Document(
+            name="Bill",
+            reference=[1, 2],
+            score=4.5,
+            date=date,
+            active=True,
+            payload=b"abc",
+            attrs={"city": "Paris"},
+        )
+        assert doc.get_text("name") == "Bill"
+        assert doc.get_int("reference") == 1
+        assert doc.get_float("score") == 4.5
+        assert doc.get_float("reference") == 1.0
+        assert doc.get_datetime("date") == date
+        assert doc.get_bool("active") is True
+        assert bytes(doc.get_bytes("payload")) == b"abc"
+        assert doc.get_json("attrs") == {"city": "Paris"}
+
+        assert doc.get_text("missing") is None
+        assert doc.get_int("missing", default=0) == 0
+        with pytest.raises(TypeError, match="`name` is of type text, expected integer"):
+            doc.get_int("name")
+        with pytest.raises(TypeError):
+            doc.get_text("reference")
+        with pytest.raises(TypeError):
+            doc.get_bool("reference")
+
+    def test_document_diff(self):
+        old = tantivy.Document(title="Old", tags=["a", "b"], rank=1, legacy="x")
+        new = tantivy.Document(title="Old", tags=["b", "a"], rank=2, extra=True)
+        assert old.diff(new) == {
+            "added": {"extra": [True]},
+            "removed": {"legacy": ["x"]},
+            "changed": {
+                "rank": ([1], [2]),
+                "tags": (["a", "b"], ["b", "a"]),
+            },
+        }
+        assert old.diff(old.__copy__()) == {
+            "added": {},
+            "removed": {},
+            "changed": {},
+        }
+
     def test_document_with_date(self):
         date = datetime.datetime(2019, 8, 12, 13, 0, 0)
         doc = tantivy.Document(name="Bill", date=date)
@@ -655,6 +3238,38 @@ class TestDocument(object):
         doc = tantivy.Document(facet=facet)
         assert doc["facet"][0].to_path() == ["asia/oceania", "fiji"]
 
+    def test_document_facet_from_path(self):
+        doc = tantivy.Document()
+        doc.add_facet("facet", ["electronics", "phones", "android"])
+        doc.add_facet("facet", "electronics/tv/")
+        doc.add_facet("facet", ("a/b", "c"))
+        assert [f.to_path() for f in doc["facet"]] == [
+            ["electronics", "phones", "android"],
+            ["electronics", "tv"],
+            ["a/b", "c"],
+        ]
+        assert tantivy.Facet.from_path(["europe", "france"]) == (
+            tantivy.Facet.from_string("europe/france/")
+        )
+
+        with pytest.raises(ValueError, match="segment 1: segments can't be empty"):
+            doc.add_facet("facet", ["electronics", "", "android"])
+        with pytest.raises(TypeError, match="segment 2: expected a string"):
+            doc.add_facet("facet", ["electronics", "phones", 3])
+        with pytest.raises(ValueError, match="segment 1 is empty"):
+            doc.add_facet("facet", "/electronics//android")
+
+        schema = (
+            tantivy.SchemaBuilder().add_facet_field("facet").build()
+        )
+        doc = tantivy.Document.from_dict(
+            {"facet": [["electronics", "phones"], "/books"]}, schema
+        )
+        assert [f.to_path() for f in doc["facet"]] == [
+            ["electronics", "phones"],
+            ["books"],
+        ]
+
     def test_document_eq(self):
         doc1 = tantivy.Document(name="Bill", reference=[1, 2])
         doc2 = tantivy.Document.from_dict({"name": "Bill", "reference": [1, 2]})
@@ -664,6 +3279,43 @@ class TestDocument(object):
         assert doc1 != doc3
         assert doc2 != doc3
 
+    def test_document_from_object(self):
+        @dataclasses.dataclass
+        class Author:
+            name: str
+            languages: tuple
+
+        @dataclasses.dataclass
+        class Book:
+            title: str
+            year: int
+            tags: tuple
+            author: Author
+            summary: Optional[str] = None
+            isbn: str = "unused"
+
+        schema = (
+            SchemaBuilder()
+            .add_text_field("title", stored=True)
+            .add_integer_field("year", stored=True)
+            .add_text_field("tags", stored=True)
+            .add_json_field("author", stored=True)
+            .add_text_field("summary", stored=True)
+            .build()
+        )
+        book = Book("Dune", 1965, ("sf", "classic"), Author("Herbert", ("en",)))
+        doc = Document.from_object(book, schema)
+        assert doc.to_dict() == {
+            "title": ["Dune"],
+            "year": [1965],
+            "tags": ["sf", "classic"],
+            "author": [{"name": "Herbert", "languages": ["en"]}],
+        }
+
+        book.year = "unknown"
+        with pytest.raises(ValueError, match="year"):
+            Document.from_object(book, schema)
+
     def test_document_copy(self):
         doc1 = tantivy.Document(name="Bill", reference=[1, 2])
         doc2 = copy.copy(doc1)
@@ -759,6 +3411,26 @@ class TestJsonField:
         # result = index.searcher().search(query, 2)
         # assert len(result.hits) == 1
 
+    def test_stored_json_field(self):
+        schema = SchemaBuilder().add_json_field("attributes", stored=True).build()
+        index = Index(schema)
+        writer = index.writer()
+        attributes = {"target": "submit-button", "cart": {"product_id": 103}}
+        writer.add_document(Document(attributes=attributes))
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+        doc = searcher.doc(searcher.search(Query.all_query()).hits[0][1])
+
+        assert doc.get_first("attributes") == attributes
+        assert doc.to_dict() == {"attributes": [attributes]}
+
+        raw = doc.get_first("attributes", raw_json=True)
+        assert isinstance(raw, bytes)
+        assert json.loads(raw) == attributes
+        assert [json.loads(value) for value in doc.get_all("attributes", raw_json=True)] == [attributes]
+        assert json.loads(doc.to_dict(raw_json=True)["attributes"][0]) == attributes
+
 
 @pytest.mark.parametrize("bytes_kwarg", [True, False])
 @pytest.mark.parametrize(
@@ -787,6 +3459,139 @@ def test_bytes(bytes_kwarg, bytes_payload):
     index.reload()
 
 
+def test_bytes_buffer():
+    schema = SchemaBuilder().add_bytes_field("embedding", stored=True).build()
+    index = Index(schema)
+    writer = index.writer()
+    writer.add_document(Document(embedding=b"abc"))
+    writer.commit()
+    index.reload()
+    searcher = index.searcher()
+    doc = searcher.doc(searcher.search(Query.all_query()).hits[0][1])
+
+    value = doc.get_first("embedding")
+    assert isinstance(value, tantivy.Bytes)
+    view = memoryview(value)
+    assert view.readonly
+    assert view.tolist() == [97, 98, 99]
+    assert bytes(value) == b"abc"
+    assert value == b"abc" and value == bytearray(b"abc")
+    assert hash(value) == hash(b"abc")
+    assert pickle.loads(pickle.dumps(value)) == value
+    assert Document.from_dict(doc.to_dict(), schema) == doc
+
+
+def test_metrics_callback():
+    events = []
+    tantivy.set_metrics_callback(events.append)
+    try:
+        index = Index(schema())
+        writer = index.writer(15_000_000, 1)
+        for title in ["first", "second"]:
+            writer.add_document(Document(title=title))
+            writer.commit()
+        writer.merge()
+        index.reload()
+        index.searcher().search(Query.all_query(), 1)
+    finally:
+        tantivy.set_metrics_callback(None)
+    index.searcher().search(Query.all_query(), 1)
+
+    assert [event["operation"] for event in events] == [
+        "commit",
+        "commit",
+        "merge",
+        "reload",
+        "search",
+    ]
+    assert all(event["duration_ms"] >= 0 for event in events)
+    assert events[0]["num_docs"] == 1
+    assert events[2]["num_segments"] == 2
+    assert events[3]["num_docs"] == 2
+    assert events[4]["num_hits"] == 1 and events[4]["count"] == 2
+
+    with pytest.raises(TypeError):
+        tantivy.set_metrics_callback(42)
+
+
+def test_metrics_prometheus():
+    def samples(text):
+        return {
+            line.rsplit(" ", 1)[0]: float(line.rsplit(" ", 1)[1])
+            for line in text.splitlines()
+            if not line.startswith("#")
+        }
+
+    before = samples(tantivy.metrics_prometheus())
+    index = Index(schema())
+    writer = index.writer(15_000_000, 1)
+    writer.add_document(Document(title="first"))
+    writer.add_document(Document(title="second"))
+    writer.commit()
+    index.reload()
+    index.searcher().search(Query.all_query(), 1)
+
+    text = tantivy.metrics_prometheus({'main "index"': index})
+    assert "# TYPE tantivy_operations_total counter" in text
+    assert "# TYPE tantivy_reload_num_docs gauge" in text
+    after = samples(text)
+
+    def delta(name):
+        return after[name] - before.get(name, 0)
+
+    assert delta('tantivy_operations_total{operation="search"}') == 1
+    assert delta('tantivy_operations_total{operation="commit"}') == 1
+    assert delta("tantivy_commit_num_docs_total") == 2
+    assert delta("tantivy_search_num_hits_total") == 1
+    assert after["tantivy_reload_num_docs"] == 2
+    assert after['tantivy_index_num_docs{index="main \\"index\\""}'] == 2
+    assert after['tantivy_index_commits_total{index="main \\"index\\""}'] == 1
+    assert (
+        after[
+            'tantivy_index_disk_usage_bytes{index="main \\"index\\"",component="store"}'
+        ]
+        > 0
+    )
+
+
+def test_configure_logging():
+    import logging
+
+    records = []
+
+    class Handler(logging.Handler):
+        def emit(self, record):
+            records.append(record)
+
+    logger = logging.getLogger("tantivy.test")
+    logger.addHandler(Handler())
+    logger.setLevel(logging.DEBUG)
+    tantivy.configure_logging("INFO", logger_name="tantivy.test")
+    try:
+        index = Index(schema())
+        writer = index.writer(15_000_000, 1)
+        writer.add_document(Document(title="logged"))
+        writer.commit()
+        writer.wait_merging_threads()
+    finally:
+        tantivy.disable_logging()
+
+    assert records
+    assert all(record.levelno >= logging.INFO for record in records)
+    assert all(record.target.startswith("tantivy") for record in records)
+
+    # Nothing is forwarded once disabled.
+    count = len(records)
+    writer = index.writer(15_000_000, 1)
+    writer.add_document(Document(title="not logged"))
+    writer.commit()
+    writer.wait_merging_threads()
+    assert len(records) == count
+
+    with pytest.raises(ValueError):
+        tantivy.configure_logging("LOUD")
+
+
 def test_schema_eq():
     schema1 = schema()
     schema2 = schema()
@@ -797,6 +3602,50 @@ def test_schema_eq():
     assert schema2 != schema3
 
 
+def test_schema_from_typed_dict():
+    class Author(TypedDict):
+        name: str
+
+    class Book(TypedDict):
+        title: str
+        year: int
+        rating: Optional[float]
+        published: datetime.datetime
+        tags: list[str]
+        author: Author
+        sku: str
+        internal: str
+
+    built = SchemaBuilder.from_typed_dict(
+        Book,
+        overrides={
+            "rating": {"fast": True},
+            "sku": {"tokenizer_name": "raw"},
+            "internal": None,
+        },
+    ).build()
+    expected = (
+        SchemaBuilder()
+        .add_text_field("title", stored=True)
+        .add_integer_field("year", stored=True, indexed=True)
+        .add_float_field("rating", stored=True, indexed=True, fast=True)
+        .add_date_field("published", stored=True, indexed=True)
+        .add_text_field("tags", stored=True)
+        .add_json_field("author", stored=True)
+        .add_text_field("sku", stored=True, tokenizer_name="raw")
+        .build()
+    )
+    assert built == expected
+
+    class Point(TypedDict):
+        coordinates: complex
+
+    with pytest.raises(TypeError, match="coordinates"):
+        SchemaBuilder.from_typed_dict(Point)
+    with pytest.raises(ValueError):
+        SchemaBuilder.from_typed_dict(Book, {"title": {"type": "text_field"}})
+
+
 def test_facet_eq():
     facet1 = tantivy.Facet.from_string("/europe/france")
     facet2 = tantivy.Facet.from_string("/europe/france")
@@ -839,6 +3688,22 @@ def test_doc_address_pickle():
     assert orig == pickled
 
 
+def test_doc_address_ordering_and_packing():
+    addresses = [
+        tantivy.DocAddress(1, 0),
+        tantivy.DocAddress(0, 7),
+        tantivy.DocAddress(0, 2),
+    ]
+    ordered = sorted(addresses)
+    assert [(a.segment_ord, a.doc) for a in ordered] == [(0, 2), (0, 7), (1, 0)]
+    assert tantivy.DocAddress(0, 2) < tantivy.DocAddress(0, 7)
+    assert {tantivy.DocAddress(0, 2): "a"}[tantivy.DocAddress(0, 2)] == "a"
+
+    packed = [a.pack() for a in ordered]
+    assert packed == sorted(packed)
+    assert [tantivy.DocAddress.unpack(p) for p in packed] == ordered
+
+
 class TestSnippets(object):
     def test_document_snippet(self, dir_index):
         index_dir, _ = dir_index
@@ -876,6 +3741,51 @@ class TestQuery(object):
         searched_doc = index.searcher().doc(doc_address)
         assert searched_doc["title"] == ["The Old Man and the Sea"]
 
+    def test_extract_terms(self, ram_index):
+        index = ram_index
+        query = index.parse_query('sea AND "old man" -title:whale', ["title", "body"])
+        assert query.extract_terms(index.schema) == [
+            ("title", "man"),
+            ("title", "old"),
+            ("title", "sea"),
+            ("title", "whale"),
+            ("body", "man"),
+            ("body", "old"),
+            ("body", "sea"),
+        ]
+        assert Query.all_query().extract_terms(index.schema) == []
+        regex = Query.regex_query(index.schema, "title", "s.*")
+        assert regex.extract_terms(index.schema) == []
+
+        schema = SchemaBuilder().add_json_field("attributes").add_integer_field("id", indexed=True).build()
+        query = Query.term_query(schema, "id", 7) | Index(schema).parse_query("attributes.color:red")
+        assert query.extract_terms(schema) == [("attributes.color", "red"), ("id", 7)]
+
+    def test_query_builder(self, ram_index_numeric_fields):
+        index = ram_index_numeric_fields
+        searcher = index.searcher()
+        Q = tantivy.QueryBuilder(index.schema)
+
+        def ids(query):
+            hits = searcher.search(query).hits
+            return sorted(searcher.doc(address)["id"][0] for _, address in hits)
+
+        assert ids(Q.term("is_good", True)) == [1]
+        assert ids(Q.range("rating", 4.0)) == [2]
+        assert ids(Q.range("id", upper=1)) == [1]
+        assert ids(Q.term("body", "river") & Q.range("id", 1, 2)) == [2]
+        assert ids(Q.term("body", "river") | Q.term("id", 1)) == [1, 2]
+        assert ids(~Q.term("is_good", True)) == [2]
+        assert ids(Q.term("body", "fish") & ~Q.term("id", 1)) == []
+        assert ids(Q.terms("id", [1, 2]) & Q.all()) == [1, 2]
+
+        with pytest.raises(TypeError):
+            Q.term("id", 1) & "id:1"
+        with pytest.raises(ValueError):
+            Q.range("body", "a", "b")
+        with pytest.raises(ValueError):
+            Q.term("unknown", 1)
+
     def test_term_set_query(self, ram_index):
         index = ram_index
 
@@ -1051,6 +3961,62 @@ class TestQuery(object):
                 (query1, Occur.Must),
             ])
 
+    def test_boolean_query_minimum_should_match(self, ram_index):
+        index = ram_index
+        searcher = index.searcher()
+
+        def terms(*words):
+            return [
+                (Occur.Should, Query.term_query(index.schema, "title", word))
+                for word in words
+            ]
+
+        should = terms("the", "and", "mice", "sea")
+        plain = searcher.search(Query.boolean_query(should), 10)
+        assert len(plain.hits) == 3
+
+        at_least_two = searcher.search(
+            Query.boolean_query(should, minimum_should_match=2), 10
+        )
+        assert len(at_least_two.hits) == 2
+        # The scores are the same as without the constraint.
+        assert all(hit in plain.hits for hit in at_least_two.hits)
+
+        for minimum in [3, "75%"]:
+            result = searcher.search(
+                Query.boolean_query(should, minimum_should_match=minimum), 10
+            )
+            assert [searcher.doc(address)["title"] for _, address in result.hits] == [
+                ["The Old Man and the Sea"]
+            ]
+
+        must_and = [(Occur.Must, Query.term_query(index.schema, "title", "and"))]
+        query = Query.boolean_query(
+            must_and + terms("sea", "mice"), minimum_should_match=1
+        )
+        assert len(searcher.search(query, 10).hits) == 2
+        query = Query.boolean_query(
+            must_and + terms("frankenstein", "prometheus"), minimum_should_match=1
+        )
+        assert len(searcher.search(query, 10).hits) == 0
+
+        for invalid in [-1, "150%", "most"]:
+            with pytest.raises(ValueError):
+                Query.boolean_query(should, minimum_should_match=invalid)
+
+    def test_filtered_query(self, ram_index):
+        index = ram_index
+        searcher = index.searcher()
+        scoring = Query.term_query(index.schema, "title", "and")
+        mice = Query.term_query(index.schema, "title", "mice")
+
+        unfiltered = searcher.search(scoring, 10).hits
+        result = searcher.search(Query.filtered(scoring, mice), 10).hits
+        assert len(unfiltered) == 2
+        assert len(result) == 1
+        # The filter doesn't change the score of the hit.
+        assert result[0] in unfiltered
+
     def test_disjunction_max_query(self, ram_index):
         index = ram_index
 
@@ -1443,3 +4409,126 @@ class TestQuery(object):
         
         with pytest.raises(ValueError, match="Facet fields are not supported for range queries."):
             Query.range_query(index.schema, "title", FieldType.Facet, 1, 2)
+
+    def test_geo_queries(self, ram_index_with_geo_field):
+        index = ram_index_with_geo_field
+        searcher = index.searcher()
+
+        def cities(result):
+            return sorted(searcher.doc(address)["city"][0] for _, address in result.hits)
+
+        query = Query.geo_distance_query(index.schema, "location", 48.85, 2.35, 400_000)
+        assert cities(searcher.search(query)) == ["London", "Paris"]
+
+        query = Query.geo_bounding_box_query(index.schema, "location", 45, -5, 55, 15)
+        assert cities(searcher.search(query)) == ["Berlin", "London", "Paris"]
+
+        # The box crosses the antimeridian.
+        query = Query.geo_bounding_box_query(index.schema, "location", -20, 170, -10, -170)
+        assert cities(searcher.search(query)) == ["Apia", "Suva"]
+
+        with pytest.raises(tantivy.SchemaError):
+            Query.geo_distance_query(index.schema, "city", 0, 0, 1)
+        with pytest.raises(ValueError):
+            Query.geo_distance_query(index.schema, "location", 91, 0, 1)
+
+    def test_sort_by_distance(self, ram_index_with_geo_field):
+        index = ram_index_with_geo_field
+        searcher = index.searcher()
+        result = searcher.search(
+            Query.all_query(), sort_by_distance=("location", 48.85, 2.35)
+        )
+        assert result.count == 6
+        assert [searcher.doc(address)["city"][0] for _, address in result.hits] == [
+            "Paris", "London", "Berlin", "Apia", "Suva", "Atlantis"
+        ]
+        assert result.hits[1][0] == pytest.approx(343_000, rel=0.01)
+        assert result.hits[-1][0] == float("inf")
+
+        with pytest.raises(ValueError):
+            searcher.search(
+                Query.all_query(),
+                order_by_field="location",
+                sort_by_distance=("location", 0, 0),
+            )
+
+    def test_boost_by_distance(self, ram_index_with_geo_field):
+        index = ram_index_with_geo_field
+        searcher = index.searcher()
+        query = index.parse_query("paris OR london OR berlin OR atlantis", ["city"])
+
+        def cities(result):
+            return [searcher.doc(address)["city"][0] for _, address in result.hits]
+
+        result = searcher.search(
+            query, boost_by_distance=("location", 52.52, 13.405, 100_000)
+        )
+        assert cities(result) == ["Berlin", "Paris", "London", "Atlantis"]
+        unboosted = dict(
+            (searcher.doc(address)["city"][0], score)
+            for score, address in searcher.search(query).hits
+        )
+        assert result.hits[0][0] == pytest.approx(unboosted["Berlin"])
+        # Documents without a location score 0.
+        assert result.hits[-1][0] == 0.0
+
+        with pytest.raises(ValueError):
+            searcher.search(query, boost_by_distance=("location", 0, 0, 0))
+        with pytest.raises(ValueError):
+            searcher.search(
+                query,
+                sort_by_distance=("location", 0, 0),
+                boost_by_distance=("location", 0, 0, 1000),
+            )
+
+    def test_block_join_query(self):
+        schema = (
+            SchemaBuilder()
+            .add_text_field("type", tokenizer_name="raw")
+            .add_text_field("name", stored=True)
+            .add_text_field("review")
+            .build()
+        )
+        index = Index(schema)
+        writer = index.writer()
+        writer.add_document_block(
+            [
+                Document(type="review", review="great sound"),
+                Document(type="review", review="poor battery"),
+                Document(type="product", name="headphones"),
+            ]
+        )
+        writer.add_document_block(
+            [
+                Document(type="review", review="great battery"),
+                Document(type="product", name="speaker"),
+            ]
+        )
+        writer.add_document_block([Document(type="product", name="cable")])
+        writer.commit()
+        index.reload()
+        searcher = index.searcher()
+
+        def products(query):
+            result = searcher.search(query)
+            return sorted(searcher.doc(address)["name"][0] for _, address in result.hits)
+
+        parent_filter = Query.term_query(schema, "type", "product")
+        query = Query.block_join_query(
+            parent_filter, index.parse_query("great", ["review"])
+        )
+        assert products(query) == ["headphones", "speaker"]
+        query = Query.block_join_query(
+            parent_filter, index.parse_query("battery", ["review"]), score_mode="none"
+        )
+        assert products(query) == ["headphones", "speaker"]
+        assert all(score == 1.0 for score, _ in searcher.search(query).hits)
+        query = Query.block_join_query(
+            parent_filter, index.parse_query("sound", ["review"]), score_mode="max"
+        )
+        assert products(query) == ["headphones"]
+
+        with pytest.raises(ValueError):
+            Query.block_join_query(parent_filter, Query.all_query(), score_mode="median")
+        with pytest.raises(ValueError):
+            writer.add_document_block([])